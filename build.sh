@@ -43,6 +43,7 @@ TARGET=""
 RPI_32=false
 RPI_64=false
 USE_ZIGBUILD=false
+MINIMAL=false
 
 for arg in "$@"; do
     case $arg in
@@ -68,11 +69,26 @@ for arg in "$@"; do
             USE_ZIGBUILD=true
             TARGET="aarch64-unknown-linux-gnu"
             ;;
+        --openwrt|--musl64)
+            RELEASE=true
+            USE_ZIGBUILD=true
+            MINIMAL=true
+            TARGET="aarch64-unknown-linux-musl"
+            ;;
+        --openwrt32|--musl32)
+            RELEASE=true
+            USE_ZIGBUILD=true
+            MINIMAL=true
+            TARGET="armv7-unknown-linux-musleabihf"
+            ;;
         --caps)
             CAPS=true
             INSTALL=true
             RELEASE=true
             ;;
+        --minimal|-m)
+            MINIMAL=true
+            ;;
         --target)
             # Next arg will be the target
             ;;
@@ -85,7 +101,10 @@ for arg in "$@"; do
             echo "  -c, --clean        Clean build artifacts before building"
             echo "  --rpi, --rpi32     Cross-compile for Raspberry Pi (32-bit ARM)"
             echo "  --rpi64            Cross-compile for Raspberry Pi (64-bit ARM)"
+            echo "  --openwrt          Static musl build for 64-bit ARM routers (AF_PACKET, no libpcap)"
+            echo "  --openwrt32        Static musl build for 32-bit ARM routers (AF_PACKET, no libpcap)"
             echo "  --caps             Install with capabilities (run without sudo)"
+            echo "  -m, --minimal      Capture + SQLite only (no TUI, GPS, health endpoint or pcap export)"
             echo "  -h, --help         Show this help message"
             echo ""
             echo "Cross-compilation requirements:"
@@ -118,18 +137,31 @@ if [ "$CLEAN" = true ]; then
     cargo clean
 fi
 
+# Feature selection
+FEATURE_ARGS=""
+if [ "$MINIMAL" = true ]; then
+    echo -e "${CYAN}Minimal build: default features disabled${NC}"
+    FEATURE_ARGS="--no-default-features"
+fi
+
+# musl targets link statically so the binary runs on any OpenWrt image
+if [[ "$TARGET" == *musl* ]]; then
+    export RUSTFLAGS="${RUSTFLAGS} -C target-feature=+crt-static"
+    echo -e "${CYAN}Static musl build: set \"backend\": \"afpacket\" in config.json${NC}"
+fi
+
 # Build
 if [ -n "$TARGET" ]; then
     echo -e "${YELLOW}Building release binary for ${TARGET} (using zigbuild)...${NC}"
-    cargo zigbuild --release --target "$TARGET"
+    cargo zigbuild --release --target "$TARGET" $FEATURE_ARGS
     BINARY="target/${TARGET}/release/prowl"
 elif [ "$RELEASE" = true ]; then
     echo -e "${YELLOW}Building release binary...${NC}"
-    cargo build --release
+    cargo build --release $FEATURE_ARGS
     BINARY="target/release/prowl"
 else
     echo -e "${YELLOW}Building debug binary...${NC}"
-    cargo build
+    cargo build $FEATURE_ARGS
     BINARY="target/debug/prowl"
 fi
 
@@ -0,0 +1,384 @@
+//! Linking the randomized MACs one device rotates through.
+//!
+//! Devices with a stored probe carrying the same capability fingerprint
+//! (see `fingerprint`) are treated as one physical device under several
+//! MACs. A shared fingerprint on its own is weak evidence, since every
+//! phone of one model and OS release shares it, so each link also records
+//! whether 802.11 sequence numbers carry on from where the other MAC
+//! stopped and how far the networks the two probed for overlap, and turns
+//! that into a confidence.
+//!
+//! `prowl link confirm` and `prowl link reject` record a verdict on a pair,
+//! stored with a snapshot of the evidence and confidence as they stood when
+//! it was given, which `link show` lists beside the current ones. A verdict
+//! decides whether the pair is linked from then on, whatever the evidence
+//! says, both in `link show` and in what `watch --group` follows.
+
+use crate::database::{Database, Device, DeviceLinkRecord};
+use crate::fingerprint;
+use crate::parser::sequence_gap;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Confidence from a shared fingerprint
+const FINGERPRINT_WEIGHT: f64 = 0.4;
+/// Confidence from sequence numbers carrying on across the MAC change
+const SEQUENCE_WEIGHT: f64 = 0.35;
+/// Confidence from probing for all the same networks
+const SSID_WEIGHT: f64 = 0.25;
+/// Most frames a device sends between its last probe under one MAC and
+/// its first under the next for the sequence to count as continuous
+pub const MAX_SEQUENCE_GAP: u16 = 64;
+/// Longest silence between the two MACs for a sequence handoff
+pub const MAX_HANDOFF_SECS: i64 = 300;
+
+/// A user's decision on a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkVerdict {
+    Confirmed,
+    Rejected,
+}
+
+impl LinkVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkVerdict::Confirmed => "confirmed",
+            LinkVerdict::Rejected => "rejected",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "confirmed" => Some(LinkVerdict::Confirmed),
+            "rejected" => Some(LinkVerdict::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// Why two MACs look like one device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinkEvidence {
+    pub shared_fingerprint: bool,
+    /// Frames from the earlier MAC's last probe to the later one's first,
+    /// when the later MAC appeared within `MAX_HANDOFF_SECS` of the earlier
+    /// going quiet
+    pub sequence_gap: Option<u16>,
+    pub shared_ssids: Vec<String>,
+    /// Shared SSIDs as a fraction of every SSID either probed for
+    pub ssid_overlap: f64,
+}
+
+impl LinkEvidence {
+    pub fn sequence_continuous(&self) -> bool {
+        self.sequence_gap.is_some_and(|gap| gap <= MAX_SEQUENCE_GAP)
+    }
+
+    /// 0.0 to 1.0
+    pub fn confidence(&self) -> f64 {
+        let mut confidence = self.ssid_overlap * SSID_WEIGHT;
+        if self.shared_fingerprint {
+            confidence += FINGERPRINT_WEIGHT;
+        }
+        if self.sequence_continuous() {
+            confidence += SEQUENCE_WEIGHT;
+        }
+        confidence
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.shared_fingerprint {
+            parts.push("shared fingerprint".to_string());
+        }
+        match self.sequence_gap {
+            Some(gap) if self.sequence_continuous() => parts.push(format!("sequence continues (+{})", gap)),
+            Some(gap) => parts.push(format!("sequence jumps {}", gap)),
+            None => {}
+        }
+        if !self.shared_ssids.is_empty() {
+            parts.push(format!(
+                "{} shared SSID(s), {:.0}% overlap",
+                self.shared_ssids.len(),
+                self.ssid_overlap * 100.0
+            ));
+        }
+        if parts.is_empty() {
+            "no evidence".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// A verdict with the evidence as it stood when it was given
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkOverride {
+    pub verdict: LinkVerdict,
+    pub confidence: f64,
+    pub evidence: LinkEvidence,
+    pub note: Option<String>,
+    pub updated_at: i64,
+}
+
+/// Another MAC that may be the same device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceLink {
+    pub mac: String,
+    pub evidence: LinkEvidence,
+    pub confidence: f64,
+    #[serde(rename = "override")]
+    pub user_override: Option<LinkOverride>,
+}
+
+impl DeviceLink {
+    /// Whether the two are treated as one device: the user's verdict, or
+    /// a shared fingerprint when there is none
+    pub fn linked(&self) -> bool {
+        match &self.user_override {
+            Some(o) => o.verdict == LinkVerdict::Confirmed,
+            None => self.evidence.shared_fingerprint,
+        }
+    }
+}
+
+fn find_device(db: &Database, mac: &str) -> Result<Device> {
+    db.get_device_by_mac(mac)?.ok_or_else(|| anyhow!("{} is not in the database", mac))
+}
+
+/// MACs sharing the device's latest fingerprint from the current algorithm
+fn fingerprint_peers(db: &Database, device: &Device) -> Result<Vec<String>> {
+    let algorithm = fingerprint::current();
+    match db.get_device_fingerprint(device.id)? {
+        Some((fp, version)) if version == algorithm.version() => {
+            db.get_macs_with_fingerprint(&fp, version, &device.mac)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn evidence(db: &Database, device: &Device, peers: &[String], other: &Device) -> Result<LinkEvidence> {
+    let sequence_gap = match (
+        db.get_device_sequence_span(device.id)?,
+        db.get_device_sequence_span(other.id)?,
+    ) {
+        (Some((a_first, a_last)), Some((b_first, b_last))) => {
+            // The earlier MAC has to fall silent before the later one starts
+            let (last, first) = if a_first.0 <= b_first.0 { (a_last, b_first) } else { (b_last, a_first) };
+            (0..=MAX_HANDOFF_SECS)
+                .contains(&(first.0 - last.0))
+                .then(|| sequence_gap(last.1, first.1))
+        }
+        _ => None,
+    };
+
+    let ssids: BTreeSet<String> = db.get_unique_ssids_for_device(device.id)?.into_iter().collect();
+    let other_ssids: BTreeSet<String> = db.get_unique_ssids_for_device(other.id)?.into_iter().collect();
+    let shared_ssids: Vec<String> = ssids.intersection(&other_ssids).cloned().collect();
+    let all = ssids.union(&other_ssids).count();
+    let ssid_overlap = if all == 0 { 0.0 } else { shared_ssids.len() as f64 / all as f64 };
+
+    Ok(LinkEvidence {
+        shared_fingerprint: peers.contains(&other.mac),
+        sequence_gap,
+        shared_ssids,
+        ssid_overlap,
+    })
+}
+
+/// Evidence that `mac` and `other` are one device
+pub fn link_evidence(db: &Database, mac: &str, other: &str) -> Result<LinkEvidence> {
+    let device = find_device(db, mac)?;
+    let peers = fingerprint_peers(db, &device)?;
+    evidence(db, &device, &peers, &find_device(db, other)?)
+}
+
+fn pair<'a>(mac: &'a str, other: &'a str) -> (&'a str, &'a str) {
+    if mac <= other {
+        (mac, other)
+    } else {
+        (other, mac)
+    }
+}
+
+/// Every MAC sharing `mac`'s fingerprint or with a verdict against it,
+/// linked ones first, then by confidence
+pub fn device_links(db: &Database, mac: &str) -> Result<Vec<DeviceLink>> {
+    let device = find_device(db, mac)?;
+    let peers = fingerprint_peers(db, &device)?;
+    let overrides = db.get_device_links(mac)?;
+
+    let mut macs: BTreeSet<&str> = peers.iter().map(String::as_str).collect();
+    for record in &overrides {
+        macs.insert(if record.mac_a == mac { record.mac_b.as_str() } else { record.mac_a.as_str() });
+    }
+
+    let mut links = Vec::new();
+    for other in macs {
+        // A pruned device leaves nothing to weigh
+        let other_device = match db.get_device_by_mac(other)? {
+            Some(d) => d,
+            None => continue,
+        };
+        let evidence = evidence(db, &device, &peers, &other_device)?;
+        let (a, b) = pair(mac, other);
+        let user_override = overrides
+            .iter()
+            .find(|r| r.mac_a == a && r.mac_b == b)
+            .and_then(|r| {
+                Some(LinkOverride {
+                    verdict: LinkVerdict::parse(&r.verdict)?,
+                    confidence: r.confidence,
+                    evidence: serde_json::from_str(&r.evidence_json).ok()?,
+                    note: r.note.clone(),
+                    updated_at: r.updated_at,
+                })
+            });
+        links.push(DeviceLink {
+            mac: other.to_string(),
+            confidence: evidence.confidence(),
+            evidence,
+            user_override,
+        });
+    }
+
+    links.sort_by(|a, b| b.linked().cmp(&a.linked()).then(b.confidence.total_cmp(&a.confidence)));
+    Ok(links)
+}
+
+/// Every MAC with a verdict against `mac`, and the verdict
+pub fn link_verdicts(db: &Database, mac: &str) -> Result<Vec<(String, LinkVerdict)>> {
+    let verdicts = db
+        .get_device_links(mac)?
+        .into_iter()
+        .filter_map(|record| {
+            let verdict = LinkVerdict::parse(&record.verdict)?;
+            let other = if record.mac_a == mac { record.mac_b } else { record.mac_a };
+            Some((other, verdict))
+        })
+        .collect();
+    Ok(verdicts)
+}
+
+/// Record the user's verdict on a pair with the evidence as it stands now
+pub fn set_verdict(
+    db: &Database,
+    mac: &str,
+    other: &str,
+    verdict: LinkVerdict,
+    note: Option<&str>,
+    now: i64,
+) -> Result<LinkEvidence> {
+    if mac == other {
+        anyhow::bail!("A MAC is always linked to itself");
+    }
+    let evidence = link_evidence(db, mac, other)?;
+    let (a, b) = pair(mac, other);
+    db.set_device_link(&DeviceLinkRecord {
+        mac_a: a.to_string(),
+        mac_b: b.to_string(),
+        verdict: verdict.as_str().to_string(),
+        confidence: evidence.confidence(),
+        evidence_json: serde_json::to_string(&evidence)?,
+        note: note.map(str::to_string),
+        updated_at: now,
+    })?;
+    Ok(evidence)
+}
+
+/// Drop the verdict on a pair. Returns false if there was none.
+pub fn clear_verdict(db: &Database, mac: &str, other: &str) -> Result<bool> {
+    let (a, b) = pair(mac, other);
+    db.clear_device_link(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{GpsStatus, ProbeCapture};
+    use crate::parser::{InformationElement, ProbeCapabilities};
+
+    fn caps(ht_cap_info: u8) -> ProbeCapabilities {
+        let element = |id: u8, data: &[u8]| InformationElement {
+            id,
+            ext_id: None,
+            data: data.to_vec(),
+        };
+        ProbeCapabilities {
+            information_elements: vec![
+                element(1, &[0x02, 0x04, 0x0b, 0x16]),
+                element(45, &[ht_cap_info, 0x01, 0x17, 0xff, 0xff, 0, 0]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn probe(db: &Database, mac: &str, ssid: &str, timestamp: i64, seq: u16, ht_cap_info: u8) {
+        db.insert_probe(&ProbeCapture {
+            mac: mac.to_string(),
+            ssid: ssid.to_string(),
+            timestamp,
+            timestamp_micros: 0,
+            lat: None,
+            lon: None,
+            signal_dbm: Some(-60),
+            channel: Some(6),
+            distance_m: None,
+            gps_status: GpsStatus::Disabled,
+            bssid: None,
+            sequence_number: Some(seq),
+            capabilities: Some(caps(ht_cap_info)),
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_links_with_evidence_and_overrides() {
+        let db = Database::open_in_memory().unwrap();
+        let (a, b) = ("02:00:00:00:00:01", "02:00:00:00:00:02");
+        let (c, d) = ("02:00:00:00:00:03", "02:00:00:00:00:04");
+        // `a` rotates to `b`, sequence carrying on; `c` is another phone of
+        // the same model at the same time; `d` is a different chipset
+        probe(&db, a, "HomeNet", 1_000, 4090, 0x6f);
+        probe(&db, a, "Office", 1_030, 4094, 0x6f);
+        probe(&db, b, "HomeNet", 1_090, 3, 0x6f);
+        probe(&db, b, "Office", 1_120, 9, 0x6f);
+        probe(&db, c, "CoffeeShop", 1_010, 2000, 0x6f);
+        probe(&db, c, "HomeNet", 1_100, 2100, 0x6f);
+        probe(&db, d, "HomeNet", 1_050, 100, 0x2d);
+
+        let links = device_links(&db, a).unwrap();
+        assert_eq!(links.iter().map(|l| l.mac.as_str()).collect::<Vec<_>>(), vec![b, c]);
+        let rotated = &links[0];
+        assert!(rotated.linked());
+        assert_eq!(rotated.evidence.sequence_gap, Some(5));
+        assert_eq!(rotated.evidence.shared_ssids, vec!["HomeNet", "Office"]);
+        assert!((rotated.confidence - 1.0).abs() < 1e-9);
+        assert_eq!(
+            rotated.evidence.describe(),
+            "shared fingerprint, sequence continues (+5), 2 shared SSID(s), 100% overlap"
+        );
+        // Heard together, so no handoff to check
+        assert_eq!(links[1].evidence.sequence_gap, None);
+        assert!(links[1].confidence < 0.5);
+
+        // The user overrides both ways; the verdict keeps its evidence
+        set_verdict(&db, b, a, LinkVerdict::Rejected, Some("two phones"), 2_000).unwrap();
+        set_verdict(&db, a, d, LinkVerdict::Confirmed, None, 2_010).unwrap();
+        let links = device_links(&db, a).unwrap();
+        assert_eq!(links.iter().map(|l| l.mac.as_str()).collect::<Vec<_>>(), vec![c, d, b]);
+        assert!(links[1].linked());
+        assert!(!links[1].evidence.shared_fingerprint);
+        let rejected = links[2].user_override.as_ref().unwrap();
+        assert_eq!(rejected.verdict, LinkVerdict::Rejected);
+        assert_eq!(rejected.note.as_deref(), Some("two phones"));
+        assert_eq!(rejected.evidence, links[2].evidence);
+
+        assert!(clear_verdict(&db, a, b).unwrap());
+        assert!(!clear_verdict(&db, a, b).unwrap());
+        assert!(device_links(&db, a).unwrap().iter().all(|l| l.mac == d || l.user_override.is_none()));
+        assert!(set_verdict(&db, a, a, LinkVerdict::Confirmed, None, 0).is_err());
+    }
+}
@@ -1,22 +1,162 @@
-use crate::database::{Database, Device, Probe};
+use crate::burst::device_bursts;
+use crate::config::BroadcastOnlyPolicy;
+use crate::database::{Database, Device, Probe, ProbeResponder};
+use crate::location_history::{location_overlap, visits, LocationOverlap, Visit};
+use crate::oui::VendorAttribution;
+use crate::parser::WpsSummary;
+use crate::residency::{update_residency, Residency};
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use log::info;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// SSIDs probed for by at most this many devices in the database are rare
+pub const RARE_SSID_MAX_DEVICES: usize = 2;
+
+/// How widely an SSID a device probed for is known locally
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SsidPopularity {
+    pub ssid: String,
+    /// Distinct devices in the database probing for it, this one included
+    pub devices: usize,
+    /// One of the user's own networks (`analysis.my_ssids`)
+    pub mine: bool,
+}
+
+impl SsidPopularity {
+    pub fn is_rare(&self) -> bool {
+        self.devices <= RARE_SSID_MAX_DEVICES
+    }
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SurveillanceAlert {
     pub device: Device,
     pub score: f64,
     pub reasons: Vec<String>,
     pub probed_ssids: Vec<String>,
+    /// APs this device sent directed probes to
+    pub directed_bssids: Vec<String>,
+    /// Probed SSIDs that an AP in range broadcast or answered to
+    pub nearby_ssids: Vec<String>,
+    /// APs that answered this device's probes
+    pub responding_aps: Vec<ProbeResponder>,
     pub location_count: usize,
     pub appearance_count: usize,
+    pub vendor: Option<VendorAttribution>,
+    /// What the device said about itself in its WPS element, if it sent one
+    pub wps: Option<WpsSummary>,
+    /// None when there weren't enough signal readings to judge
+    pub stability: Option<SignalStability>,
+    /// Long-term class as of the last residency update
+    pub residency: Option<Residency>,
+    /// Overlap with the user's imported location history; None without any
+    pub location_overlap: Option<LocationOverlap>,
+    /// How many local devices probe for each SSID this one does
+    pub ssid_popularity: Vec<SsidPopularity>,
+}
+
+/// Added to the score of a device that only recently became resident
+const NEW_RESIDENT_BOOST: f64 = 0.2;
+
+/// Added to the score of a device that burst into probing in the window
+const BURST_BOOST: f64 = 0.15;
+
+/// Places the user's history must cover before overlap is worth reporting
+const MIN_OVERLAP_PLACES: usize = 3;
+/// Share of the user's places (percent) a device must have been heard at
+/// to be flagged as travelling along
+const CO_TRAVEL_MIN_PERCENT: f64 = 50.0;
+
+/// Signal readings needed before stability means anything
+const MIN_STABILITY_SAMPLES: usize = 10;
+/// Time the readings must span; a few steady minutes is just someone
+/// standing still
+const MIN_STABILITY_SPAN_SECS: i64 = 30 * 60;
+/// RSSI standard deviation at or below which a device reads as stationary
+const STATIONARY_MAX_STDDEV_DB: f64 = 3.0;
+/// RSSI standard deviation at or above which a device reads as moving
+const MOVING_MIN_STDDEV_DB: f64 = 6.0;
+
+/// Whether a device looks fixed in place or carried around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mobility {
+    /// Steady signal for a long time: likely a planted camera or tracker
+    Stationary,
+    /// Fluctuating signal: likely a person walking around
+    Moving,
+    Undetermined,
+}
+
+impl Mobility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mobility::Stationary => "stationary",
+            Mobility::Moving => "moving",
+            Mobility::Undetermined => "undetermined",
+        }
+    }
+}
+
+/// Spread of a device's signal strength over the analysis period
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SignalStability {
+    pub stddev_db: f64,
+    pub samples: usize,
+    pub mobility: Mobility,
+}
+
+impl SignalStability {
+    /// 1.0 for signal as steady as a fixed device, falling to 0.0 for signal
+    /// that swings like a moving one
+    pub fn score(&self) -> f64 {
+        let range = MOVING_MIN_STDDEV_DB - STATIONARY_MAX_STDDEV_DB;
+        ((MOVING_MIN_STDDEV_DB - self.stddev_db) / range).clamp(0.0, 1.0)
+    }
+}
+
+/// RSSI spread of `probes`, or None with too few readings or too short a
+/// span to tell a planted device from someone pausing nearby
+pub fn signal_stability(probes: &[Probe]) -> Option<SignalStability> {
+    let readings: Vec<(i64, f64)> = probes
+        .iter()
+        .filter_map(|p| p.signal_dbm.map(|s| (p.timestamp, s as f64)))
+        .collect();
+    if readings.len() < MIN_STABILITY_SAMPLES {
+        return None;
+    }
+    let first = readings.iter().map(|(t, _)| *t).min()?;
+    let last = readings.iter().map(|(t, _)| *t).max()?;
+    if last - first < MIN_STABILITY_SPAN_SECS {
+        return None;
+    }
+
+    let n = readings.len() as f64;
+    let mean = readings.iter().map(|(_, s)| s).sum::<f64>() / n;
+    let variance = readings.iter().map(|(_, s)| (s - mean).powi(2)).sum::<f64>() / n;
+    let stddev_db = variance.sqrt();
+    let mobility = if stddev_db <= STATIONARY_MAX_STDDEV_DB {
+        Mobility::Stationary
+    } else if stddev_db >= MOVING_MIN_STDDEV_DB {
+        Mobility::Moving
+    } else {
+        Mobility::Undetermined
+    };
+    Some(SignalStability {
+        stddev_db,
+        samples: readings.len(),
+        mobility,
+    })
 }
 
 pub struct SurveillanceAnalyzer {
     time_windows_minutes: Vec<u32>,
     persistence_threshold: f64,
+    broadcast_only: BroadcastOnlyPolicy,
+    broadcast_only_weight: f64,
+    my_ssids: Vec<String>,
 }
 
 impl SurveillanceAnalyzer {
@@ -24,9 +164,25 @@ impl SurveillanceAnalyzer {
         SurveillanceAnalyzer {
             time_windows_minutes,
             persistence_threshold,
+            broadcast_only: BroadcastOnlyPolicy::Include,
+            broadcast_only_weight: 1.0,
+            my_ssids: Vec::new(),
         }
     }
 
+    /// The user's own SSIDs, so devices probing for them stand out
+    pub fn with_my_ssids(mut self, ssids: Vec<String>) -> Self {
+        self.my_ssids = ssids;
+        self
+    }
+
+    /// Set how devices that only send broadcast probes are scored
+    pub fn with_broadcast_only(mut self, policy: BroadcastOnlyPolicy, weight: f64) -> Self {
+        self.broadcast_only = policy;
+        self.broadcast_only_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
     pub fn analyze(&self, db: &Database, hours: u32) -> Result<Vec<SurveillanceAlert>> {
         let now = chrono::Utc::now().timestamp();
         let start = now - (hours as i64 * 3600);
@@ -37,32 +193,22 @@ impl SurveillanceAnalyzer {
             format_timestamp(now)
         );
 
+        let changes = update_residency(db, now)?;
+        if !changes.is_empty() {
+            info!("{} device(s) changed residency class", changes.len());
+        }
+
         let devices = db.get_devices_in_time_range(start, now)?;
         info!("Found {} devices in time range", devices.len());
 
+        let user_visits = visits(&db.get_location_history(start, now)?);
         let mut alerts = Vec::new();
 
         for device in devices {
-            let probes = db.get_probes_for_device(device.id)?;
-            if probes.is_empty() {
-                continue;
-            }
-
-            let score = self.calculate_persistence_score(&device, &probes, start, now);
-            let reasons = self.get_alert_reasons(&device, &probes, score);
-
-            if score >= self.persistence_threshold {
-                let ssids = db.get_unique_ssids_for_device(device.id)?;
-                let location_count = db.get_device_location_count(device.id)?;
-
-                alerts.push(SurveillanceAlert {
-                    device: device.clone(),
-                    score,
-                    reasons,
-                    probed_ssids: ssids,
-                    location_count,
-                    appearance_count: probes.len(),
-                });
+            if let Some(alert) = self.evaluate_device_with_visits(db, &device, start, now, &user_visits)? {
+                if alert.score >= self.persistence_threshold {
+                    alerts.push(alert);
+                }
             }
         }
 
@@ -77,23 +223,179 @@ impl SurveillanceAnalyzer {
         Ok(alerts)
     }
 
+    /// Score every device seen in `start..=end`, regardless of threshold,
+    /// without touching residency
+    pub fn score_devices(&self, db: &Database, start: i64, end: i64) -> Result<Vec<(String, f64)>> {
+        let user_visits = visits(&db.get_location_history(start, end)?);
+        let mut scores = Vec::new();
+        for device in db.get_devices_in_time_range(start, end)? {
+            if let Some(alert) = self.evaluate_device_with_visits(db, &device, start, end, &user_visits)? {
+                scores.push((device.mac, alert.score));
+            }
+        }
+        Ok(scores)
+    }
+
+    /// Score a single device over `start..=end`, regardless of threshold.
+    /// Returns None if the device has no probes.
+    pub fn evaluate_device(
+        &self,
+        db: &Database,
+        device: &Device,
+        start: i64,
+        end: i64,
+    ) -> Result<Option<SurveillanceAlert>> {
+        let user_visits = visits(&db.get_location_history(start, end)?);
+        self.evaluate_device_with_visits(db, device, start, end, &user_visits)
+    }
+
+    fn evaluate_device_with_visits(
+        &self,
+        db: &Database,
+        device: &Device,
+        start: i64,
+        end: i64,
+        user_visits: &[Visit],
+    ) -> Result<Option<SurveillanceAlert>> {
+        let probes = db.get_probes_for_device(device.id)?;
+        if probes.is_empty() {
+            return Ok(None);
+        }
+
+        let stability = signal_stability(&probes);
+        let mut score = self.calculate_persistence_score(device, &probes, start, end, stability.as_ref());
+        let mut reasons = self.get_alert_reasons(device, &probes, score);
+        if let Some(stability) = &stability {
+            match stability.mobility {
+                Mobility::Stationary => reasons.push(format!(
+                    "Stable signal (±{:.1} dB over {} readings): likely a stationary device near the sensor",
+                    stability.stddev_db, stability.samples
+                )),
+                Mobility::Moving => reasons.push(format!(
+                    "Fluctuating signal (±{:.1} dB): likely carried by someone moving",
+                    stability.stddev_db
+                )),
+                Mobility::Undetermined => {}
+            }
+        }
+
+        let bursts = device_bursts(db, &device.mac, start, end)?;
+        if let Some(strongest) = bursts.iter().max_by_key(|b| b.probes) {
+            score = (score + BURST_BOOST).min(1.0);
+            reasons.push(format!(
+                "Burst of probing: {} probes in {}s at {} against a baseline of {:.1}{}",
+                strongest.probes,
+                strongest.window_secs,
+                format_timestamp(strongest.window_start),
+                strongest.baseline,
+                if bursts.len() > 1 { format!(" ({} bursts)", bursts.len()) } else { String::new() }
+            ));
+        }
+
+        let residency = db.get_device_residency(&device.mac)?;
+        if let Some(record) = &residency {
+            if record.is_established(end) {
+                score = 0.0;
+                reasons.push(format!(
+                    "Long-established resident (seen on {} days since {}): alert suppressed",
+                    record.days_seen,
+                    format_timestamp(record.first_seen)
+                ));
+            } else if record.is_new_resident(end) {
+                score = (score + NEW_RESIDENT_BOOST).min(1.0);
+                reasons.push(format!(
+                    "New resident: first seen {}, now present on {} days",
+                    format_timestamp(record.first_seen),
+                    record.days_seen
+                ));
+            }
+        }
+
+        let overlap = if user_visits.is_empty() {
+            None
+        } else {
+            let sightings: Vec<i64> = probes
+                .iter()
+                .map(|p| p.timestamp)
+                .filter(|&t| t >= start && t <= end)
+                .collect();
+            Some(location_overlap(&device.mac, user_visits, &sightings))
+        };
+        if let Some(overlap) = &overlap {
+            if overlap.places >= MIN_OVERLAP_PLACES && overlap.percent() >= CO_TRAVEL_MIN_PERCENT {
+                reasons.push(overlap.describe());
+            }
+        }
+
+        let ssid_popularity: Vec<SsidPopularity> = db
+            .get_ssid_popularity_for_device(device.id)?
+            .into_iter()
+            .map(|(ssid, devices)| SsidPopularity {
+                mine: self.my_ssids.contains(&ssid),
+                ssid,
+                devices,
+            })
+            .collect();
+        reasons.extend(ssid_reasons(&ssid_popularity));
+
+        if is_broadcast_only(&probes) {
+            match self.broadcast_only {
+                BroadcastOnlyPolicy::Include => {}
+                BroadcastOnlyPolicy::Downweight => {
+                    score *= self.broadcast_only_weight;
+                    reasons.push(format!(
+                        "Broadcast-only prober: score weighted x{:.2}",
+                        self.broadcast_only_weight
+                    ));
+                }
+                BroadcastOnlyPolicy::Exclude => {
+                    score = 0.0;
+                    reasons.push("Broadcast-only prober: excluded from alerts".to_string());
+                }
+            }
+        }
+
+        Ok(Some(SurveillanceAlert {
+            device: device.clone(),
+            score,
+            reasons,
+            probed_ssids: device_ssids(db, device.id)?,
+            directed_bssids: directed_bssids(&probes),
+            nearby_ssids: db.get_nearby_ssids_for_device(device.id)?,
+            responding_aps: db.get_probe_responders_for_device(&device.mac)?,
+            location_count: db.get_device_location_count(device.id)?,
+            appearance_count: probes.len(),
+            vendor: db.get_device_vendor_attribution(device.id)?,
+            wps: db.get_device_capabilities(device.id)?.and_then(|caps| caps.wps_info),
+            stability,
+            residency: residency.map(|record| record.class),
+            location_overlap: overlap,
+            ssid_popularity,
+        }))
+    }
+
+    pub fn persistence_threshold(&self) -> f64 {
+        self.persistence_threshold
+    }
+
     fn calculate_persistence_score(
         &self,
         device: &Device,
         probes: &[Probe],
         start: i64,
         end: i64,
+        stability: Option<&SignalStability>,
     ) -> f64 {
         let mut score = 0.0;
         let total_duration = (end - start) as f64;
 
-        // 1. Time window coverage score (40% weight)
+        // 1. Time window coverage score (35% weight)
         let window_score = self.calculate_window_coverage(probes, start, end);
-        score += window_score * 0.4;
+        score += window_score * 0.35;
 
-        // 2. Appearance frequency score (30% weight)
+        // 2. Appearance frequency score (25% weight)
         let frequency_score = self.calculate_frequency_score(probes, start, end);
-        score += frequency_score * 0.3;
+        score += frequency_score * 0.25;
 
         // 3. Session duration score (20% weight)
         let duration = (device.last_seen - device.first_seen) as f64;
@@ -104,6 +406,12 @@ impl SurveillanceAnalyzer {
         let location_score = self.calculate_location_score(probes);
         score += location_score * 0.1;
 
+        // 5. Signal stability score (10% weight): a fixed device near the
+        // sensor holds a steady RSSI, a passer-by does not
+        if let Some(stability) = stability {
+            score += stability.score() * 0.1;
+        }
+
         score.min(1.0)
     }
 
@@ -192,6 +500,16 @@ impl SurveillanceAnalyzer {
             reasons.push(format!("Seen at {} different locations", locations.len()));
         }
 
+        // Directed probes name the exact AP the device is looking for
+        let bssids = directed_bssids(probes);
+        if !bssids.is_empty() {
+            reasons.push(format!(
+                "Directed probes to {} AP(s): {}",
+                bssids.len(),
+                bssids.join(", ")
+            ));
+        }
+
         // Check for long duration
         let duration_minutes = (device.last_seen - device.first_seen) / 60;
         if duration_minutes > 30 {
@@ -216,6 +534,110 @@ impl SurveillanceAnalyzer {
     }
 }
 
+/// Changes between two consecutive analysis runs
+#[derive(Debug, Clone, Default)]
+pub struct AlertDelta {
+    /// Devices that crossed the threshold since the last run
+    pub new_alerts: Vec<SurveillanceAlert>,
+    /// Devices still alerting whose score moved by at least `min_change`,
+    /// paired with their previous score
+    pub score_changes: Vec<(SurveillanceAlert, f64)>,
+    /// MACs that no longer alert
+    pub cleared: Vec<String>,
+}
+
+impl AlertDelta {
+    pub fn is_empty(&self) -> bool {
+        self.new_alerts.is_empty() && self.score_changes.is_empty() && self.cleared.is_empty()
+    }
+}
+
+/// Score movement that `analyze --follow` reports for a device still
+/// alerting; smaller changes are run-to-run noise
+pub const FOLLOW_MIN_SCORE_CHANGE: f64 = 0.05;
+
+/// Compare the current alerts against the previous run's MAC -> score map
+pub fn diff_alerts(
+    previous: &HashMap<String, f64>,
+    current: &[SurveillanceAlert],
+    min_change: f64,
+) -> AlertDelta {
+    let mut delta = AlertDelta::default();
+
+    for alert in current {
+        match previous.get(&alert.device.mac) {
+            None => delta.new_alerts.push(alert.clone()),
+            Some(&old) if (alert.score - old).abs() >= min_change => {
+                delta.score_changes.push((alert.clone(), old));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let current_macs: HashSet<&str> = current.iter().map(|a| a.device.mac.as_str()).collect();
+    delta.cleared = previous
+        .keys()
+        .filter(|mac| !current_macs.contains(mac.as_str()))
+        .cloned()
+        .collect();
+    delta.cleared.sort();
+
+    delta
+}
+
+/// Distinct SSIDs an AP must answer probes for before it looks like a
+/// karma-style rogue
+pub const KARMA_MIN_SSIDS: usize = 3;
+
+/// Alert reasons for probes naming the user's own networks. A network few
+/// devices here know points at someone who has been near the user before;
+/// a widely probed name is likely a coincidence.
+pub fn ssid_reasons(popularity: &[SsidPopularity]) -> Vec<String> {
+    popularity
+        .iter()
+        .filter(|p| p.mine)
+        .map(|p| {
+            if p.is_rare() {
+                format!(
+                    "Probes for your network {:?}, known to only {} device(s) here: possibly targeted",
+                    p.ssid, p.devices
+                )
+            } else {
+                format!("Probes for your network {:?}, a common name probed by {} devices here", p.ssid, p.devices)
+            }
+        })
+        .collect()
+}
+
+/// SSIDs a device probed for, plus the recovered names of hidden APs it
+/// sent directed probes to (marked as hidden)
+pub fn device_ssids(db: &Database, device_id: i64) -> Result<Vec<String>> {
+    let mut ssids = db.get_unique_ssids_for_device(device_id)?;
+    for (bssid, ssid) in db.get_hidden_ssids_for_device(device_id)? {
+        if !ssids.contains(&ssid) {
+            ssids.push(format!("{} (hidden AP {})", ssid, bssid));
+        }
+    }
+    Ok(ssids)
+}
+
+/// True if the device never named a network or AP in its probes
+pub fn is_broadcast_only(probes: &[Probe]) -> bool {
+    probes.iter().all(|p| p.ssid.is_empty() && p.bssid.is_none())
+}
+
+/// Distinct BSSIDs targeted by directed probes, sorted
+pub fn directed_bssids(probes: &[Probe]) -> Vec<String> {
+    let mut bssids: Vec<String> = probes
+        .iter()
+        .filter_map(|p| p.bssid.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    bssids.sort();
+    bssids
+}
+
 fn format_timestamp(ts: i64) -> String {
     Utc.timestamp_opt(ts, 0)
         .single()
@@ -247,3 +669,159 @@ pub fn analyze_time_windows(db: &Database, windows: &[u32]) -> Result<Vec<TimeWi
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(mac: &str, score: f64) -> SurveillanceAlert {
+        SurveillanceAlert {
+            device: Device {
+                id: 1,
+                mac: mac.to_string(),
+                first_seen: 0,
+                last_seen: 0,
+            },
+            score,
+            reasons: Vec::new(),
+            probed_ssids: Vec::new(),
+            directed_bssids: Vec::new(),
+            nearby_ssids: Vec::new(),
+            responding_aps: Vec::new(),
+            location_count: 0,
+            appearance_count: 0,
+            vendor: None,
+            wps: None,
+            stability: None,
+            residency: None,
+            location_overlap: None,
+            ssid_popularity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_alerts() {
+        let mut previous = HashMap::new();
+        previous.insert("AA:AA:AA:AA:AA:01".to_string(), 0.75);
+        previous.insert("AA:AA:AA:AA:AA:02".to_string(), 0.80);
+        previous.insert("AA:AA:AA:AA:AA:03".to_string(), 0.90);
+
+        let current = vec![
+            alert("AA:AA:AA:AA:AA:01", 0.76),
+            alert("AA:AA:AA:AA:AA:02", 0.95),
+            alert("AA:AA:AA:AA:AA:04", 0.71),
+        ];
+
+        let delta = diff_alerts(&previous, &current, 0.05);
+        assert_eq!(delta.new_alerts.len(), 1);
+        assert_eq!(delta.new_alerts[0].device.mac, "AA:AA:AA:AA:AA:04");
+        assert_eq!(delta.score_changes.len(), 1);
+        assert_eq!(delta.score_changes[0].0.device.mac, "AA:AA:AA:AA:AA:02");
+        assert_eq!(delta.cleared, vec!["AA:AA:AA:AA:AA:03".to_string()]);
+    }
+
+    #[test]
+    fn test_broadcast_only_policy() {
+        use crate::database::{GpsStatus, ProbeCapture};
+
+        let db = Database::open_in_memory().unwrap();
+        for (mac, ssid) in [("AA:AA:AA:AA:AA:01", ""), ("AA:AA:AA:AA:AA:02", "HomeNet")] {
+            for t in 0..20 {
+                db.insert_probe(&ProbeCapture {
+                    mac: mac.to_string(),
+                    ssid: ssid.to_string(),
+                    timestamp: 1000 + t * 60,
+                    timestamp_micros: 0,
+                    lat: None,
+                    lon: None,
+                    signal_dbm: Some(-50),
+                    channel: Some(6),
+                    distance_m: None,
+                    gps_status: GpsStatus::Disabled,
+                    bssid: None,
+                    sequence_number: None,
+                    capabilities: None,
+                })
+                .unwrap();
+            }
+        }
+        let broadcast = db.get_device_by_mac("AA:AA:AA:AA:AA:01").unwrap().unwrap();
+        let named = db.get_device_by_mac("AA:AA:AA:AA:AA:02").unwrap().unwrap();
+
+        let include = SurveillanceAnalyzer::new(vec![5, 10], 0.5);
+        let base = include.evaluate_device(&db, &broadcast, 1000, 2200).unwrap().unwrap();
+
+        let downweight = SurveillanceAnalyzer::new(vec![5, 10], 0.5)
+            .with_broadcast_only(BroadcastOnlyPolicy::Downweight, 0.5);
+        let weighted = downweight.evaluate_device(&db, &broadcast, 1000, 2200).unwrap().unwrap();
+        assert!((weighted.score - base.score * 0.5).abs() < 1e-9);
+        assert!(weighted.reasons.iter().any(|r| r.starts_with("Broadcast-only")));
+
+        let unaffected = downweight.evaluate_device(&db, &named, 1000, 2200).unwrap().unwrap();
+        assert!(!unaffected.reasons.iter().any(|r| r.starts_with("Broadcast-only")));
+
+        let exclude = SurveillanceAnalyzer::new(vec![5, 10], 0.5)
+            .with_broadcast_only(BroadcastOnlyPolicy::Exclude, 0.5);
+        assert_eq!(exclude.evaluate_device(&db, &broadcast, 1000, 2200).unwrap().unwrap().score, 0.0);
+
+        // Only one device here knows HomeNet
+        let mine = SurveillanceAnalyzer::new(vec![5, 10], 0.5).with_my_ssids(vec!["HomeNet".to_string()]);
+        let alert = mine.evaluate_device(&db, &named, 1000, 2200).unwrap().unwrap();
+        assert_eq!(
+            alert.ssid_popularity,
+            vec![SsidPopularity { ssid: "HomeNet".to_string(), devices: 1, mine: true }]
+        );
+        assert!(alert.reasons.iter().any(|r| r.contains("possibly targeted")));
+        let unflagged = include.evaluate_device(&db, &named, 1000, 2200).unwrap().unwrap();
+        assert!(unflagged.reasons.iter().all(|r| !r.contains("your network")));
+    }
+
+    #[test]
+    fn test_ssid_reasons() {
+        let popularity = |ssid: &str, devices, mine| SsidPopularity { ssid: ssid.to_string(), devices, mine };
+        let reasons = ssid_reasons(&[
+            popularity("HomeNet", 2, true),
+            popularity("xfinitywifi", 40, true),
+            popularity("Cafe", 1, false),
+        ]);
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("known to only 2 device(s)"));
+        assert!(reasons[1].contains("common name probed by 40 devices"));
+    }
+
+    #[test]
+    fn test_signal_stability() {
+        let probe = |timestamp: i64, signal: i32| Probe {
+            id: 0,
+            device_id: 1,
+            ssid: String::new(),
+            timestamp,
+            timestamp_micros: None,
+            lat: None,
+            lon: None,
+            signal_dbm: Some(signal),
+            channel: None,
+            distance_m: None,
+            gps_status: None,
+            bssid: None,
+            sequence_number: None,
+        };
+
+        let steady: Vec<Probe> = (0..20).map(|i| probe(i * 300, -55 + (i as i32 % 3) - 1)).collect();
+        let stability = signal_stability(&steady).unwrap();
+        assert_eq!(stability.mobility, Mobility::Stationary);
+        assert_eq!(stability.score(), 1.0);
+
+        let swinging: Vec<Probe> = (0..20)
+            .map(|i| probe(i * 300, if i % 2 == 0 { -40 } else { -75 }))
+            .collect();
+        let stability = signal_stability(&swinging).unwrap();
+        assert_eq!(stability.mobility, Mobility::Moving);
+        assert_eq!(stability.score(), 0.0);
+
+        // Steady, but only for ten minutes
+        let brief: Vec<Probe> = (0..20).map(|i| probe(i * 30, -55)).collect();
+        assert!(signal_stability(&brief).is_none());
+        assert!(signal_stability(&steady[..5]).is_none());
+    }
+}
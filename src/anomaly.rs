@@ -0,0 +1,273 @@
+//! Rate-of-new-devices anomaly detection.
+//!
+//! Counts never-before-seen devices per time bucket and keeps an
+//! exponentially weighted baseline of that rate. A bucket far above the
+//! baseline (a group arriving, or a tool cycling through random MACs) is
+//! reported as a spike.
+//!
+//! The baseline can also be learned from stored history (see
+//! `learn_baseline`) so a restarted capture doesn't start from scratch.
+
+use crate::config::AnomalyConfig;
+
+/// Event type recorded in the events table for new-device spikes
+pub const EVENT_NEW_DEVICE_SPIKE: &str = "new_device_spike";
+
+/// Smoothing factor for the baseline mean and variance
+const EWMA_ALPHA: f64 = 0.2;
+
+/// Idle buckets folded into the baseline after a capture pause
+const MAX_IDLE_BUCKETS: i64 = 60;
+
+/// A bucket whose new-device count exceeded the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewDeviceSpike {
+    pub bucket_start: i64,
+    pub bucket_secs: i64,
+    pub new_devices: usize,
+    pub baseline: f64,
+    pub threshold: f64,
+}
+
+impl NewDeviceSpike {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} new devices in {}s (baseline {:.1}, threshold {:.1})",
+            self.new_devices, self.bucket_secs, self.baseline, self.threshold
+        )
+    }
+}
+
+/// New-device rate learned from stored history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyBaseline {
+    /// Mean new devices per bucket
+    pub mean: f64,
+    pub variance: f64,
+    /// Buckets the baseline was learned from
+    pub buckets: usize,
+    pub bucket_secs: i64,
+    pub updated_at: i64,
+}
+
+/// Learn the new-device baseline from the first-seen times of devices over
+/// `[start, end)`. Buckets that would have been spikes under the learned
+/// rate are left out, as the live monitor leaves them out. `None` when the
+/// range doesn't hold a single bucket.
+pub fn learn_baseline(
+    first_seen: &[i64],
+    start: i64,
+    end: i64,
+    config: &AnomalyConfig,
+) -> Option<AnomalyBaseline> {
+    let bucket_secs = config.bucket_secs.max(1) as i64;
+    let buckets = ((end - start) / bucket_secs) as usize;
+    if buckets == 0 {
+        return None;
+    }
+    let mut counts = vec![0usize; buckets];
+    for &ts in first_seen {
+        if ts >= start && ts < end {
+            let idx = ((ts - start) / bucket_secs) as usize;
+            if let Some(count) = counts.get_mut(idx) {
+                *count += 1;
+            }
+        }
+    }
+
+    let (mean, variance) = mean_variance(&counts);
+    let threshold = (mean + config.spike_sigma * variance.sqrt()).max(config.min_new_devices as f64);
+    let quiet: Vec<usize> = counts.into_iter().filter(|&c| c as f64 <= threshold).collect();
+    let (mean, variance) = mean_variance(&quiet);
+
+    Some(AnomalyBaseline {
+        mean,
+        variance,
+        buckets: quiet.len(),
+        bucket_secs,
+        updated_at: end,
+    })
+}
+
+fn mean_variance(counts: &[usize]) -> (f64, f64) {
+    if counts.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = counts.len() as f64;
+    let mean = counts.iter().sum::<usize>() as f64 / n;
+    let variance = counts.iter().map(|&c| (c as f64 - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance)
+}
+
+/// Tracks the baseline rate of new devices and flags sudden spikes
+#[derive(Debug, Clone)]
+pub struct NewDeviceRateMonitor {
+    bucket_secs: i64,
+    sigma: f64,
+    min_new_devices: usize,
+    warmup_buckets: usize,
+    current_bucket: Option<i64>,
+    current_count: usize,
+    mean: f64,
+    variance: f64,
+    buckets_seen: usize,
+}
+
+impl NewDeviceRateMonitor {
+    pub fn new(config: &AnomalyConfig) -> Self {
+        NewDeviceRateMonitor {
+            bucket_secs: config.bucket_secs.max(1) as i64,
+            sigma: config.spike_sigma,
+            min_new_devices: config.min_new_devices,
+            warmup_buckets: config.warmup_buckets,
+            current_bucket: None,
+            current_count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            buckets_seen: 0,
+        }
+    }
+
+    /// Record one probe at `timestamp`; `new_device` is true if it created
+    /// a device. Returns a spike when a completed bucket was anomalous.
+    pub fn observe(&mut self, timestamp: i64, new_device: bool) -> Option<NewDeviceSpike> {
+        let bucket = timestamp - timestamp.rem_euclid(self.bucket_secs);
+        let mut spike = None;
+
+        match self.current_bucket {
+            Some(current) if bucket > current => {
+                spike = self.close_bucket(current);
+                // Empty buckets in between still count toward the baseline
+                let skipped = ((bucket - current) / self.bucket_secs - 1).min(MAX_IDLE_BUCKETS);
+                for _ in 0..skipped {
+                    self.update_baseline(0);
+                }
+                self.current_bucket = Some(bucket);
+                self.current_count = 0;
+            }
+            Some(_) => {}
+            None => self.current_bucket = Some(bucket),
+        }
+
+        if new_device {
+            self.current_count += 1;
+        }
+        spike
+    }
+
+    /// Baseline new devices per bucket
+    pub fn baseline(&self) -> f64 {
+        self.mean
+    }
+
+    /// Replace the running baseline with one learned from history. A
+    /// baseline learned with a different bucket size is ignored.
+    pub fn apply_baseline(&mut self, baseline: &AnomalyBaseline) -> bool {
+        if baseline.bucket_secs != self.bucket_secs || baseline.buckets == 0 {
+            return false;
+        }
+        self.mean = baseline.mean;
+        self.variance = baseline.variance;
+        // A learned baseline is already warmed up
+        self.buckets_seen = self.buckets_seen.max(baseline.buckets).max(self.warmup_buckets);
+        true
+    }
+
+    fn threshold(&self) -> f64 {
+        (self.mean + self.sigma * self.variance.sqrt()).max(self.min_new_devices as f64)
+    }
+
+    fn close_bucket(&mut self, bucket_start: i64) -> Option<NewDeviceSpike> {
+        let count = self.current_count;
+        let threshold = self.threshold();
+
+        if self.buckets_seen >= self.warmup_buckets && count as f64 > threshold {
+            // Spikes are kept out of the baseline so a sustained burst keeps alerting
+            return Some(NewDeviceSpike {
+                bucket_start,
+                bucket_secs: self.bucket_secs,
+                new_devices: count,
+                baseline: self.mean,
+                threshold,
+            });
+        }
+
+        self.update_baseline(count);
+        None
+    }
+
+    fn update_baseline(&mut self, count: usize) {
+        let x = count as f64;
+        if self.buckets_seen == 0 {
+            self.mean = x;
+            self.variance = 0.0;
+        } else {
+            let diff = x - self.mean;
+            self.mean += EWMA_ALPHA * diff;
+            self.variance = (1.0 - EWMA_ALPHA) * (self.variance + EWMA_ALPHA * diff * diff);
+        }
+        self.buckets_seen += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AnomalyConfig {
+        AnomalyConfig {
+            enabled: true,
+            bucket_secs: 60,
+            spike_sigma: 3.0,
+            min_new_devices: 5,
+            warmup_buckets: 3,
+        }
+    }
+
+    #[test]
+    fn test_spike_after_steady_baseline() {
+        let mut monitor = NewDeviceRateMonitor::new(&config());
+
+        // Five quiet minutes with two new devices each
+        for minute in 0..5 {
+            for i in 0..2 {
+                assert!(monitor.observe(minute * 60 + i, true).is_none());
+            }
+        }
+
+        // Burst of 20 new devices, reported when the next bucket starts
+        for i in 0..20 {
+            assert!(monitor.observe(300 + i, true).is_none());
+        }
+        let spike = monitor.observe(360, false).expect("spike");
+        assert_eq!(spike.new_devices, 20);
+        assert_eq!(spike.bucket_start, 300);
+        assert!(spike.baseline < 3.0);
+    }
+
+    #[test]
+    fn test_learned_baseline_skips_warmup_and_past_spikes() {
+        // Two new devices a minute for an hour, with one burst of 40
+        let mut first_seen: Vec<i64> = (0..60).flat_map(|m| [m * 60, m * 60 + 30]).collect();
+        first_seen.extend((0..40).map(|i| 1800 + i));
+        let baseline = learn_baseline(&first_seen, 0, 3600, &config()).unwrap();
+        assert_eq!(baseline.buckets, 59);
+        assert!((baseline.mean - 2.0).abs() < 1e-9);
+
+        let mut monitor = NewDeviceRateMonitor::new(&config());
+        assert!(monitor.apply_baseline(&baseline));
+        for i in 0..20 {
+            monitor.observe(i, true);
+        }
+        assert!(monitor.observe(60, false).is_some());
+    }
+
+    #[test]
+    fn test_no_spike_during_warmup() {
+        let mut monitor = NewDeviceRateMonitor::new(&config());
+        for i in 0..50 {
+            monitor.observe(i, true);
+        }
+        assert!(monitor.observe(60, false).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! Per-device probe burst detection.
+//!
+//! Works like a CFAR detector from radar: probes are counted per window for
+//! each device, and the window under test is compared against the average
+//! of that device's own earlier windows, skipping the few right before it.
+//! A device that has been quiet and suddenly scans hard (one that wakes up
+//! when you arrive somewhere) stands out even though it would look
+//! ordinary next to a phone that always probes that much.
+//!
+//! A device the detector hasn't seen for a whole training span counts as
+//! dormant, with an empty baseline, as long as it isn't new to the database.
+//! Bursts are stored as `probe_burst` events and raise the device's score
+//! in the analyzer.
+
+use crate::config::BurstConfig;
+use crate::database::Database;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Event type recorded in the events table for probe bursts
+pub const EVENT_PROBE_BURST: &str = "probe_burst";
+
+/// A window in which a device probed far above its own baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeBurst {
+    pub mac: String,
+    pub window_start: i64,
+    pub window_secs: i64,
+    /// Probes in the window when the burst was flagged
+    pub probes: u32,
+    /// Mean probes per window over the training windows
+    pub baseline: f64,
+    pub threshold: f64,
+}
+
+impl ProbeBurst {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} sent {} probes in {}s (baseline {:.1}, threshold {:.1})",
+            self.mac, self.probes, self.window_secs, self.baseline, self.threshold
+        )
+    }
+}
+
+/// Probe counts for one device
+#[derive(Debug, Clone)]
+struct DeviceWindows {
+    window_start: i64,
+    count: u32,
+    /// Completed windows, oldest first
+    history: VecDeque<u32>,
+    /// Already reported a burst in this window
+    flagged: bool,
+}
+
+/// Flags devices whose probe rate jumps far above their own baseline
+#[derive(Debug, Clone)]
+pub struct BurstDetector {
+    window_secs: i64,
+    training_windows: usize,
+    guard_windows: usize,
+    scale: f64,
+    min_probes: u32,
+    /// First window the detector saw
+    started: Option<i64>,
+    last_prune: i64,
+    devices: HashMap<String, DeviceWindows>,
+}
+
+impl BurstDetector {
+    pub fn new(config: &BurstConfig) -> Self {
+        BurstDetector {
+            window_secs: config.window_secs.max(1) as i64,
+            training_windows: config.training_windows.max(1),
+            guard_windows: config.guard_windows,
+            scale: config.scale,
+            min_probes: config.min_probes,
+            started: None,
+            last_prune: 0,
+            devices: HashMap::new(),
+        }
+    }
+
+    /// Record one probe from `mac` at `timestamp`; `new_device` is true if
+    /// it created the device. Returns a burst the first time a window goes
+    /// over the threshold.
+    pub fn observe(&mut self, mac: &str, timestamp: i64, new_device: bool) -> Option<ProbeBurst> {
+        let window = timestamp - timestamp.rem_euclid(self.window_secs);
+        let started = *self.started.get_or_insert(window);
+        let capacity = self.training_windows + self.guard_windows;
+        let span = capacity as i64 * self.window_secs;
+
+        // A device quiet for a whole span has only zeros left to remember
+        if window - self.last_prune >= span {
+            self.devices.retain(|_, d| d.window_start >= window - span);
+            self.last_prune = window;
+        }
+
+        let state = self.devices.entry(mac.to_string()).or_insert_with(|| {
+            // Dormant only if the detector was watching while it was quiet
+            let dormant = !new_device && window - started >= span;
+            DeviceWindows {
+                window_start: window,
+                count: 0,
+                history: if dormant { VecDeque::from(vec![0; capacity]) } else { VecDeque::new() },
+                flagged: false,
+            }
+        });
+
+        if window > state.window_start {
+            state.history.push_back(state.count);
+            let skipped = ((window - state.window_start) / self.window_secs - 1).min(capacity as i64);
+            state.history.extend(std::iter::repeat(0).take(skipped as usize));
+            while state.history.len() > capacity {
+                state.history.pop_front();
+            }
+            state.window_start = window;
+            state.count = 0;
+            state.flagged = false;
+        }
+        state.count += 1;
+
+        if state.flagged || state.history.len() < capacity {
+            return None;
+        }
+        let training = state.history.iter().take(self.training_windows);
+        let baseline = training.map(|&c| c as f64).sum::<f64>() / self.training_windows as f64;
+        let threshold = (baseline * self.scale).max(self.min_probes as f64);
+        if (state.count as f64) <= threshold {
+            return None;
+        }
+
+        state.flagged = true;
+        Some(ProbeBurst {
+            mac: mac.to_string(),
+            window_start: window,
+            window_secs: self.window_secs,
+            probes: state.count,
+            baseline,
+            threshold,
+        })
+    }
+}
+
+/// Bursts recorded for `mac` with windows starting in `start..=end`
+pub fn device_bursts(db: &Database, mac: &str, start: i64, end: i64) -> Result<Vec<ProbeBurst>> {
+    let bursts = db
+        .get_events_since(start, Some(EVENT_PROBE_BURST))?
+        .into_iter()
+        .filter(|event| event.timestamp <= end)
+        .filter_map(|event| serde_json::from_str::<ProbeBurst>(event.data_json.as_deref()?).ok())
+        .filter(|burst| burst.mac == mac)
+        .collect();
+    Ok(bursts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: &str = "00:11:22:33:44:55";
+
+    fn config() -> BurstConfig {
+        BurstConfig {
+            enabled: true,
+            window_secs: 60,
+            training_windows: 5,
+            guard_windows: 1,
+            scale: 4.0,
+            min_probes: 8,
+        }
+    }
+
+    #[test]
+    fn test_burst_against_own_baseline() {
+        let mut detector = BurstDetector::new(&config());
+
+        // Two probes a minute is this device's normal
+        for minute in 0..6 {
+            for i in 0..2 {
+                assert!(detector.observe(MAC, minute * 60 + i, minute == 0 && i == 0).is_none());
+            }
+        }
+
+        // Eight probes stays under the floor; the ninth goes over it
+        for i in 0..8 {
+            assert!(detector.observe(MAC, 360 + i, false).is_none());
+        }
+        let burst = detector.observe(MAC, 368, false).expect("burst");
+        assert_eq!(burst.probes, 9);
+        assert_eq!(burst.window_start, 360);
+        assert!((burst.baseline - 2.0).abs() < 1e-9);
+        assert_eq!(burst.threshold, 8.0);
+        // Reported once per window
+        assert!(detector.observe(MAC, 369, false).is_none());
+    }
+
+    #[test]
+    fn test_dormant_device_waking_up() {
+        let mut detector = BurstDetector::new(&config());
+        detector.observe("00:00:00:00:00:01", 0, true);
+
+        // Known from earlier captures, first heard well into this one
+        let start = 10 * 60;
+        for i in 0..8 {
+            assert!(detector.observe(MAC, start + i, false).is_none());
+        }
+        let burst = detector.observe(MAC, start + 8, false).expect("burst");
+        assert_eq!(burst.baseline, 0.0);
+
+        // A brand new device has no baseline to burst against
+        for i in 0..20 {
+            assert!(detector.observe("00:11:22:33:44:66", start + i, true).is_none());
+        }
+    }
+}
@@ -1,23 +1,50 @@
-use crate::channels::ChannelHopper;
-use crate::config::Config;
-use crate::database::{Database, ProbeCapture};
+use crate::anomaly::{NewDeviceRateMonitor, NewDeviceSpike, EVENT_NEW_DEVICE_SPIKE};
+use crate::burst::{BurstDetector, ProbeBurst, EVENT_PROBE_BURST};
+use crate::channels::{adapter_info, ChannelHopper};
+use crate::config::{AnalysisConfig, AnomalyConfig, BurstConfig, ChannelEntry, Config, QueueConfig};
+use crate::database::{BeaconCapture, CaptureRecord, Database, DeauthEvent, GpsStatus, ProbeCapture};
+use crate::deauth::{DeauthAttack, DeauthMonitor, EVENT_DEAUTH_ATTACK};
+use crate::dedup::RetryFilter;
+use crate::homenet::{MySsidProbe, MySsidWatch, EVENT_MY_SSID_PROBE};
+use crate::disk::{is_low_value, DiskState};
 use crate::distance::{estimate_distance, format_distance, distance_category};
+#[cfg(feature = "gps")]
 use crate::gps::GpsClient;
 use crate::ignore::IgnoreLists;
-use crate::parser::parse_probe_request;
+use crate::parser::{parse_beacon, parse_deauth, parse_probe_request, ParsedBeacon, ParsedDeauth};
+#[cfg(feature = "pcap-export")]
+use crate::pcap_dump::{PcapOutput, Rotation};
+use crate::privileges::{PrivilegeDrop, CAP_NET_ADMIN};
+use crate::queue::{BoundedQueue, Overflow, OverflowTracker};
+use crate::radiotap::parse_radiotap;
+use crate::source::{
+    capture_filter, open_file_source, open_source, DropStats, FrameTime, PacketSource, SourceExhausted,
+};
+use crate::status::CaptureStats;
+use crate::utilization::{frame_airtime_us, DwellClock, UtilizationTracker};
 use anyhow::{Context, Result};
-use log::{debug, error, info, warn};
-use pcap::Capture;
+use log::{debug, error, info, log, warn, Level};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
 
 pub struct CaptureEngine {
     config: Config,
     db: Database,
     ignore_lists: IgnoreLists,
     running: Arc<AtomicBool>,
+    stats: Option<Arc<CaptureStats>>,
+    channel_updates: Option<watch::Receiver<Vec<ChannelEntry>>>,
+    /// Listening time per channel, kept by the hopper when measuring utilization
+    dwell: Option<Arc<DwellClock>>,
+    /// While set, frames are read and discarded and the hopper stays put
+    paused: Arc<AtomicBool>,
+    /// Says when to stop storing low-value probes to save disk space
+    disk: Arc<DiskState>,
 }
 
 impl CaptureEngine {
@@ -27,9 +54,39 @@ impl CaptureEngine {
             db,
             ignore_lists,
             running,
+            stats: None,
+            channel_updates: None,
+            dwell: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            disk: Arc::new(DiskState::new()),
         }
     }
 
+    /// Let the hopper's channel set be swapped while capture runs
+    pub fn with_channel_updates(mut self, updates: watch::Receiver<Vec<ChannelEntry>>) -> Self {
+        self.channel_updates = Some(updates);
+        self
+    }
+
+    /// Feed live counters to a status line. Per-probe log lines drop to
+    /// debug level while stats are attached.
+    pub fn with_stats(mut self, stats: Arc<CaptureStats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Share the pause flag, e.g. with a signal handler or the status line
+    pub fn with_pause_flag(mut self, paused: Arc<AtomicBool>) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Follow the disk monitor, skipping low-value probes while it sheds
+    pub fn with_disk_state(mut self, disk: Arc<DiskState>) -> Self {
+        self.disk = disk;
+        self
+    }
+
     pub fn stop(&self) {
         self.running.store(false, Ordering::SeqCst);
     }
@@ -38,49 +95,87 @@ impl CaptureEngine {
         self.running.clone()
     }
 
-    pub async fn run(&self) -> Result<()> {
+    pub async fn run(mut self) -> Result<()> {
         self.running.store(true, Ordering::SeqCst);
 
         let interface = &self.config.capture.interface;
-        info!("Starting capture on interface: {}", interface);
+        match &self.config.capture.second_interface {
+            Some(second) => info!("Starting capture on {} (2.4 GHz) and {} (5 GHz)", interface, second),
+            None => info!("Starting capture on interface: {}", interface),
+        }
 
         // Open capture handle
-        debug!("Opening pcap capture on {}...", interface);
-        let cap_builder = Capture::from_device(interface.as_str())
-            .context("Failed to open capture device")?;
-        debug!("Setting promiscuous mode...");
-        let cap_builder = cap_builder.promisc(true).snaplen(65535).timeout(1000);
-        debug!("Activating capture...");
-        let mut cap = match cap_builder.open() {
-            Ok(c) => c,
+        debug!("Opening {:?} capture on {}...", self.config.capture.backend, interface);
+        let source = match open_source(
+            &self.config.capture,
+            Some(capture_filter(&self.config.capture).as_str()),
+            1000,
+        ) {
+            Ok(s) => s,
             Err(e) => {
                 error!("Failed to activate capture: {}", e);
                 error!("Make sure you're running as root (sudo) and the interface exists");
-                return Err(anyhow::anyhow!("Failed to activate capture: {}", e));
+                return Err(e);
             }
         };
-        debug!("Capture handle opened successfully");
+        debug!("Capture handle opened successfully ({})", source.name());
+        start_capture_session(&mut self.db, &self.config);
+
+        // Root was only needed to open the handle. The hopper's thread drops
+        // it for the whole process and keeps CAP_NET_ADMIN for itself.
+        let privilege_drop = PrivilegeDrop::plan(&self.config.capture)?;
+
+        // Start channel hopper in background (simulated and remote capture have no radio)
+        let _hopper = if self.config.capture.backend.has_radio() {
+            let mut hopper = ChannelHopper::new(
+                interface.clone(),
+                self.config.capture.channels.clone(),
+                self.config.capture.hop_interval_ms,
+            )
+            .with_band_split(self.config.capture.second_interface.clone());
+            if let Some(stats) = &self.stats {
+                hopper = hopper.with_channel_tracker(stats.channel.clone());
+            }
+            if let Some(updates) = &self.channel_updates {
+                hopper = hopper.with_channel_updates(updates.clone());
+            }
+            hopper = hopper.with_pause_flag(self.paused.clone());
+            if self.config.capture.measure_utilization {
+                let dwell = Arc::new(DwellClock::new());
+                hopper = hopper.with_dwell_clock(dwell.clone());
+                self.dwell = Some(dwell);
+            }
+            Some(hopper.spawn_thread(self.running.clone(), move || match privilege_drop {
+                Some(plan) => plan.apply(&[CAP_NET_ADMIN]),
+                None => Ok(()),
+            })?)
+        } else {
+            None
+        };
 
-        // Set to monitor mode filter for probe requests
-        // BPF filter for management frames type 0 subtype 4 (probe request)
-        if let Err(e) = cap.filter("type mgt subtype probe-req", true) {
-            warn!("Failed to set BPF filter, will filter in software: {}", e);
-        }
+        let running = self.running.clone();
+        let result = self.run_with_source(source).await;
+        // The hopper thread stops with the running flag
+        running.store(false, Ordering::SeqCst);
+        result
+    }
 
-        // Start channel hopper in background
-        let hopper = ChannelHopper::new(
-            interface.clone(),
-            self.config.capture.channels.clone(),
-            self.config.capture.hop_interval_ms,
-        );
-        let running_clone = self.running.clone();
-        let hopper_handle = tokio::spawn(async move {
-            if let Err(e) = hopper.run(running_clone).await {
-                error!("Channel hopper error: {}", e);
-            }
-        });
+    /// Replay a saved pcap/pcapng file, storing probes with their original
+    /// capture timestamps
+    pub async fn run_from_file<P: AsRef<Path>>(self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        info!("Reading probe requests from {:?}", path);
+        let source = open_file_source(path, Some(capture_filter(&self.config.capture).as_str()))?;
+        self.run_with_source(source).await
+    }
+
+    /// Run the capture pipeline on an already-open source. Used directly for
+    /// scripted sources, which need no interface or channel hopping.
+    pub async fn run_with_source(self, mut source: Box<dyn PacketSource>) -> Result<()> {
+        self.running.store(true, Ordering::SeqCst);
 
         // Start GPS client if enabled
+        #[cfg(feature = "gps")]
         let gps_rx = if self.config.gps.enabled {
             let (tx, rx) = mpsc::channel(1);
             let gps_client = GpsClient::new(
@@ -97,16 +192,74 @@ impl CaptureEngine {
         } else {
             None
         };
+        #[cfg(not(feature = "gps"))]
+        let gps_rx: Option<mpsc::Receiver<(f64, f64)>> = None;
+
+        // Database writes go through a bounded queue so a slow flush never
+        // stalls packet processing
+        let db_queue = BoundedQueue::new(
+            self.config.queues.db_capacity,
+            self.config.queues.db_policy,
+        );
+        let db_writer = spawn_db_writer(
+            self.db,
+            db_queue.clone(),
+            &self.config.anomaly,
+            &self.config.burst,
+            &self.config.analysis,
+            &self.config.queues,
+        );
 
         let mut gps_position: Option<(f64, f64)> = None;
         let mut gps_rx = gps_rx;
-        let current_channel: Option<u8> = None;
         let mut packet_count = 0u64;
         let mut probe_count = 0u64;
+        let mut retries = RetryFilter::new(self.config.capture.retry_window_ms);
+        let mut retry_count = 0u64;
+        let mut rate_limit = self.config.capture.max_probes_per_mac_per_minute.map(ProbeRateLimiter::new);
+        let mut suppressed_count = 0u64;
+        let mut shed_count = 0u64;
+        let mut corrupt_count = 0u64;
+        let weak_signal_dbm = self.config.disk.weak_signal_dbm;
+        let mut beacons = self.config.capture.capture_beacons.then(BeaconThrottle::default);
+        let detect_deauth = self.config.capture.detect_deauth;
+        let mut utilization = self.config.capture.measure_utilization.then(UtilizationTracker::new);
+        let mut session_macs: HashSet<String> = HashSet::new();
+        let probe_log_level = if self.stats.is_some() { Level::Debug } else { Level::Info };
+
+        #[cfg(feature = "pcap-export")]
+        let mut pcap_out = match &self.config.capture.pcap_output {
+            Some(path) => {
+                let rotation = Rotation {
+                    max_bytes: self.config.capture.pcap_rotate_mb.map(|mb| mb * 1024 * 1024),
+                    max_secs: self.config.capture.pcap_rotate_minutes.map(|m| m as i64 * 60),
+                };
+                if rotation.is_enabled() {
+                    info!("Writing probe request frames to rotating files based on {}", path);
+                } else {
+                    info!("Writing probe request frames to {}", path);
+                }
+                Some(PcapOutput::open(path, rotation)?)
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "pcap-export"))]
+        if self.config.capture.pcap_output.is_some() {
+            warn!("capture.pcap_output is set but this build lacks the pcap-export feature");
+        }
 
+        let source_name = source.name();
+        let mut drop_watch = DropWatch::new();
+        let mut was_paused = false;
         info!("Capture started. Press Ctrl+C to stop.");
 
         while self.running.load(Ordering::SeqCst) {
+            let paused = self.paused.load(Ordering::SeqCst);
+            if paused != was_paused {
+                info!("{}", if paused { "Capture paused" } else { "Capture resumed" });
+                was_paused = paused;
+            }
+
             // Update GPS position if available
             if let Some(ref mut rx) = gps_rx {
                 if let Ok(pos) = rx.try_recv() {
@@ -115,16 +268,58 @@ impl CaptureEngine {
                 }
             }
 
+            if let Some(usage) = utilization.as_mut().and_then(|u| u.take_due(self.dwell.as_deref())) {
+                db_queue.push(CaptureRecord::Utilization(usage));
+            }
+            if let Some(counts) = rate_limit.as_mut().and_then(|l| l.take_due()) {
+                db_queue.push(CaptureRecord::Suppressed(counts));
+            }
+
+            if let Some(drops) = drop_watch.poll(source.as_mut()) {
+                if let Some(stats) = &self.stats {
+                    stats.set_kernel_dropped(drops.total_dropped());
+                }
+            }
+
             // Capture packet
-            match cap.next_packet() {
-                Ok(packet) => {
+            match source.next_timestamped() {
+                // Keep draining the source so resuming doesn't replay a backlog
+                Ok(Some(_)) if paused => continue,
+                Ok(Some((data, captured_at))) => {
                     packet_count += 1;
+                    // Stamp on arrival, before parsing, when the source has no time
+                    let captured_at = captured_at.unwrap_or_else(FrameTime::now);
+
+                    // Signal strength and receive channel from the radiotap header
+                    let radiotap = parse_radiotap(data);
+                    let signal_dbm = radiotap.signal_dbm;
+
+                    if let (Some(tracker), Some(channel)) = (utilization.as_mut(), radiotap.channel) {
+                        let frame_len = data.len().saturating_sub(radiotap.header_len);
+                        tracker.record_frame(channel, frame_airtime_us(frame_len, radiotap.rate_500kbps));
+                    }
+
+                    // A corrupted frame still took airtime, but its addresses can't be trusted
+                    if radiotap.is_corrupt(data) {
+                        corrupt_count += 1;
+                        if let Some(stats) = &self.stats {
+                            stats.record_corrupt();
+                        }
+                        continue;
+                    }
+                    let frame = radiotap.strip_fcs(data);
 
-                    // Extract signal strength from radiotap header if present
-                    let signal_dbm = extract_signal_dbm(packet.data);
+                    if detect_deauth {
+                        if let Some(deauth) = parse_deauth(frame) {
+                            let event = deauth_event(deauth, captured_at.secs, signal_dbm, radiotap.channel);
+                            debug!("{} from {} to {}", event.kind.as_str(), event.source, event.destination);
+                            db_queue.push(CaptureRecord::Deauth(event));
+                            continue;
+                        }
+                    }
 
                     // Parse probe request
-                    if let Some(probe) = parse_probe_request(packet.data, signal_dbm) {
+                    if let Some(probe) = parse_probe_request(frame, signal_dbm) {
                         // Check ignore lists
                         if self.ignore_lists.should_ignore_mac(&probe.source_mac) {
                             debug!("Ignoring MAC: {}", probe.source_mac);
@@ -134,12 +329,37 @@ impl CaptureEngine {
                             debug!("Ignoring SSID: {}", probe.ssid);
                             continue;
                         }
+                        if retries.is_retransmission(
+                            &probe.source_mac,
+                            probe.sequence_number,
+                            probe.retry,
+                            captured_at.as_millis(),
+                        ) {
+                            retry_count += 1;
+                            continue;
+                        }
+                        if rate_limit.as_mut().is_some_and(|l| !l.admit(&probe.source_mac, captured_at.secs)) {
+                            suppressed_count += 1;
+                            continue;
+                        }
+                        if self.disk.is_shedding()
+                            && is_low_value(&probe.ssid, probe.signal_dbm, weak_signal_dbm)
+                        {
+                            self.disk.record_shed();
+                            shed_count += 1;
+                            continue;
+                        }
 
                         probe_count += 1;
-                        let now = SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_secs() as i64;
+                        let now = captured_at.secs;
+
+                        #[cfg(feature = "pcap-export")]
+                        if let Some(writer) = pcap_out.as_mut() {
+                            if let Err(e) = writer.write_frame(now, captured_at.micros, data) {
+                                error!("Failed to write pcap output, disabling it: {}", e);
+                                pcap_out = None;
+                            }
+                        }
 
                         // Calculate estimated distance from signal strength
                         let distance_m = if self.config.distance.enabled {
@@ -158,36 +378,66 @@ impl CaptureEngine {
                             mac: probe.source_mac.clone(),
                             ssid: probe.ssid.clone(),
                             timestamp: now,
+                            timestamp_micros: captured_at.micros,
                             lat: gps_position.map(|(lat, _)| lat),
                             lon: gps_position.map(|(_, lon)| lon),
                             signal_dbm: probe.signal_dbm,
-                            channel: current_channel,
+                            channel: probe.channel(radiotap.channel),
                             distance_m,
+                            gps_status: GpsStatus::from_position(self.config.gps.enabled, gps_position),
+                            bssid: probe.bssid.clone(),
+                            sequence_number: Some(probe.sequence_number),
                             capabilities: Some(probe.capabilities.clone()),
                         };
 
-                        if let Err(e) = self.db.insert_probe(&capture) {
-                            error!("Failed to insert probe: {}", e);
-                        } else {
-                            // Format output with distance if available
-                            let distance_str = distance_m
-                                .map(|d| format!(" ~{} ({})", format_distance(d), distance_category(d)))
-                                .unwrap_or_default();
-
-                            info!(
-                                "Probe: MAC={} SSID={:?} Signal={:?}dBm{}",
-                                probe.source_mac,
-                                if probe.ssid.is_empty() { "<broadcast>" } else { &probe.ssid },
-                                probe.signal_dbm,
-                                distance_str
-                            );
+                        if let Some(stats) = &self.stats {
+                            stats.record_probe(session_macs.insert(probe.source_mac.clone()));
+                            stats.set_gps(capture.gps_status);
+                        }
+
+                        if !db_queue.push(CaptureRecord::Probe(capture)) {
+                            debug!("Database queue full, dropped a record");
+                            if let Some(stats) = &self.stats {
+                                stats.set_queue_dropped(db_queue.dropped());
+                            }
+                        }
+
+                        // Format output with distance if available
+                        let distance_str = distance_m
+                            .map(|d| format!(" ~{} ({})", format_distance(d), distance_category(d)))
+                            .unwrap_or_default();
+
+                        let target_str = probe
+                            .bssid
+                            .as_deref()
+                            .map(|b| format!(" BSSID={}", b))
+                            .unwrap_or_default();
+
+                        log!(
+                            probe_log_level,
+                            "Probe: MAC={} SSID={:?}{} Signal={:?}dBm{}",
+                            probe.source_mac,
+                            if probe.ssid.is_empty() { "<broadcast>" } else { &probe.ssid },
+                            target_str,
+                            probe.signal_dbm,
+                            distance_str
+                        );
+                    } else if let Some(throttle) = beacons.as_mut() {
+                        if let Some(beacon) = parse_beacon(frame) {
+                            if let Some(capture) = throttle.admit(beacon, captured_at.secs) {
+                                db_queue.push(CaptureRecord::Beacon(capture));
+                            }
                         }
                     }
                 }
-                Err(pcap::Error::TimeoutExpired) => {
+                Ok(None) => {
                     // Normal timeout, continue
                     continue;
                 }
+                Err(e) if e.is::<SourceExhausted>() => {
+                    info!("Capture source {} finished", source_name);
+                    break;
+                }
                 Err(e) => {
                     if self.running.load(Ordering::SeqCst) {
                         error!("Capture error: {}", e);
@@ -197,78 +447,568 @@ impl CaptureEngine {
             }
         }
 
-        info!("Capture stopped. Packets: {}, Probes: {}", packet_count, probe_count);
-        hopper_handle.abort();
+        info!(
+            "Capture stopped. Packets: {}, Probes: {}, Corrupted frames: {}, Retransmissions skipped: {}, \
+            Rate limited: {}, Shed for disk space: {}",
+            packet_count, probe_count, corrupt_count, retry_count, suppressed_count, shed_count
+        );
+        if let Some(drops) = source.drop_stats() {
+            info!(
+                "Capture source {}: received {}, dropped {} (buffer), {} (interface)",
+                source_name, drops.received, drops.dropped, drops.if_dropped
+            );
+        }
+
+        if let Some(mut tracker) = utilization {
+            db_queue.push(CaptureRecord::Utilization(tracker.take(self.dwell.as_deref())));
+        }
+        if let Some(mut limiter) = rate_limit {
+            db_queue.push(CaptureRecord::Suppressed(limiter.take_suppressed()));
+        }
+
+        // Let the writer flush whatever is still queued
+        db_queue.close();
+        let written = db_writer.join().unwrap_or(0);
+        info!(
+            "Database writer stopped. Written: {}, Dropped (queue full): {}, Peak queue: {}/{}",
+            written,
+            db_queue.dropped(),
+            db_queue.high_water(),
+            db_queue.capacity()
+        );
         Ok(())
     }
 }
 
-fn extract_signal_dbm(data: &[u8]) -> Option<i32> {
-    if data.len() < 8 || data[0] != 0 {
-        return None;
+/// Toggle `paused` on every SIGUSR1, so capture can be held without
+/// stopping it: `kill -USR1 $(pidof prowl)`
+#[cfg(unix)]
+pub fn spawn_pause_signal(paused: Arc<AtomicBool>) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut usr1 = signal(SignalKind::user_defined1())?;
+    Ok(tokio::spawn(async move {
+        while usr1.recv().await.is_some() {
+            paused.fetch_xor(true, Ordering::SeqCst);
+        }
+    }))
+}
+
+/// How often the capture loops read the source's drop counters
+const DROP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Polls a source's kernel drop counters every `DROP_POLL_INTERVAL` and
+/// warns whenever they grow, since a dropped frame is a probe never seen
+pub struct DropWatch {
+    last_poll: Instant,
+    last: DropStats,
+}
+
+impl DropWatch {
+    pub fn new() -> Self {
+        DropWatch {
+            last_poll: Instant::now(),
+            last: DropStats::default(),
+        }
+    }
+
+    /// The source's counters when a poll is due and it has any
+    pub fn poll(&mut self, source: &mut dyn PacketSource) -> Option<DropStats> {
+        if self.last_poll.elapsed() < DROP_POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+        let drops = source.drop_stats()?;
+        let new_drops = drops.total_dropped().saturating_sub(self.last.total_dropped());
+        if new_drops > 0 {
+            warn!(
+                "Capture source dropped {} frames in the last {}s ({} total); the host may be too slow",
+                new_drops,
+                DROP_POLL_INTERVAL.as_secs(),
+                drops.total_dropped()
+            );
+        }
+        self.last = drops;
+        Some(drops)
     }
+}
 
-    let radiotap_len = u16::from_le_bytes([data[2], data[3]]) as usize;
-    if radiotap_len > data.len() || radiotap_len < 8 {
-        return None;
+impl Default for DropWatch {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+/// Minimum interval between stored beacons from the same AP
+const BEACON_STORE_INTERVAL_SECS: i64 = 60;
 
-    // Collect all present bitmasks (handle extended flags - bit 31)
-    let mut present_words: Vec<u32> = Vec::new();
-    let mut pos = 4;
-    loop {
-        if pos + 4 > data.len() {
+/// APs beacon about ten times a second; only the first beacon per AP per
+/// interval (or one whose SSID or hidden flag changed) is worth storing.
+/// Probe responses are throttled per AP and station they answered.
+#[derive(Debug, Default)]
+pub struct BeaconThrottle {
+    last_stored: HashMap<(String, Option<String>), (i64, String, bool)>,
+}
+
+impl BeaconThrottle {
+    pub fn admit(&mut self, beacon: ParsedBeacon, now: i64) -> Option<BeaconCapture> {
+        let key = (beacon.bssid.clone(), beacon.destination.clone());
+        if let Some((at, ssid, hidden)) = self.last_stored.get(&key) {
+            if now - at < BEACON_STORE_INTERVAL_SECS && *ssid == beacon.ssid && *hidden == beacon.hidden {
+                return None;
+            }
+        }
+        self.last_stored
+            .insert(key, (now, beacon.ssid.clone(), beacon.hidden));
+        Some(BeaconCapture {
+            bssid: beacon.bssid,
+            ssid: beacon.ssid,
+            hidden: beacon.hidden,
+            channel: beacon.channel,
+            timestamp: now,
+            probe_response: beacon.probe_response,
+            destination: beacon.destination,
+        })
+    }
+}
+
+/// Window the per-device ingest limit counts over
+const RATE_LIMIT_WINDOW_SECS: i64 = 60;
+/// How often suppressed counts are handed to the database writer
+const SUPPRESSED_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// Devices tracked before idle ones are pruned
+const RATE_LIMIT_PRUNE_AT: usize = 4096;
+
+/// Caps how many probes per device reach the database each minute, so a
+/// chatty IoT device doesn't dominate it. Held-back probes are counted per
+/// device and flushed as totals.
+#[derive(Debug)]
+pub struct ProbeRateLimiter {
+    max_per_window: u32,
+    /// Window start and probes stored in it, per MAC
+    windows: HashMap<String, (i64, u32)>,
+    suppressed: HashMap<String, u64>,
+    last_flush: Instant,
+}
+
+impl ProbeRateLimiter {
+    pub fn new(max_per_minute: u32) -> Self {
+        ProbeRateLimiter {
+            max_per_window: max_per_minute,
+            windows: HashMap::new(),
+            suppressed: HashMap::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Whether a probe from `mac` at `now` may be stored
+    pub fn admit(&mut self, mac: &str, now: i64) -> bool {
+        if let Some((start, stored)) = self.windows.get_mut(mac) {
+            if now - *start < RATE_LIMIT_WINDOW_SECS {
+                if *stored >= self.max_per_window {
+                    *self.suppressed.entry(mac.to_string()).or_insert(0) += 1;
+                    return false;
+                }
+                *stored += 1;
+                return true;
+            }
+        }
+
+        if self.windows.len() >= RATE_LIMIT_PRUNE_AT {
+            self.windows.retain(|_, (start, _)| now - *start < RATE_LIMIT_WINDOW_SECS);
+        }
+        self.windows.insert(mac.to_string(), (now, 1));
+        true
+    }
+
+    /// Suppressed counts since the last call, per MAC
+    pub fn take_suppressed(&mut self) -> Vec<(String, u64)> {
+        self.last_flush = Instant::now();
+        self.suppressed.drain().collect()
+    }
+
+    /// Like `take_suppressed`, once a flush is due and there is something
+    /// to report
+    pub fn take_due(&mut self) -> Option<Vec<(String, u64)>> {
+        if self.suppressed.is_empty() || self.last_flush.elapsed() < SUPPRESSED_FLUSH_INTERVAL {
             return None;
         }
-        let present = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
-        present_words.push(present);
-        pos += 4;
-        if present & (1 << 31) == 0 {
-            break;
+        Some(self.take_suppressed())
+    }
+}
+
+pub fn deauth_event(deauth: ParsedDeauth, timestamp: i64, signal_dbm: Option<i32>, channel: Option<u8>) -> DeauthEvent {
+    DeauthEvent {
+        timestamp,
+        kind: deauth.kind,
+        source: deauth.source,
+        destination: deauth.destination,
+        bssid: deauth.bssid,
+        reason_code: deauth.reason_code,
+        signal_dbm,
+        channel,
+    }
+}
+
+/// Drain `queue` into the database on a dedicated thread until the queue is
+/// closed and empty, one transaction per batch of `queues.db_batch_size`
+/// records or `queues.db_flush_ms`, whichever comes first. Returns the
+/// number of probes written.
+pub fn spawn_db_writer(
+    mut db: Database,
+    queue: BoundedQueue<CaptureRecord>,
+    anomaly: &AnomalyConfig,
+    burst: &BurstConfig,
+    analysis: &AnalysisConfig,
+    queues: &QueueConfig,
+) -> thread::JoinHandle<u64> {
+    let mut monitor = anomaly.enabled.then(|| NewDeviceRateMonitor::new(anomaly));
+    let mut bursts = burst.enabled.then(|| BurstDetector::new(burst));
+    let my_ssids = MySsidWatch::new(analysis);
+    let mut deauth_monitor = DeauthMonitor::new();
+    let batch_size = queues.db_batch_size;
+    let flush_interval = Duration::from_millis(queues.db_flush_ms.max(1));
+
+    thread::spawn(move || {
+        let mut written = 0u64;
+        // A batch whose commit failed, and how many times it has been tried
+        let mut failed: Option<(Vec<CaptureRecord>, u32)> = None;
+        let mut overflow = OverflowTracker::new(OVERFLOW_REPORT_INTERVAL);
+        // Weekly maintenance may store a fresher baseline while this runs
+        let mut baseline_at = refresh_anomaly_baseline(&db, &mut monitor, 0);
+        let mut baseline_checked = Instant::now();
+        loop {
+            if baseline_checked.elapsed() >= BASELINE_CHECK_INTERVAL {
+                baseline_at = refresh_anomaly_baseline(&db, &mut monitor, baseline_at);
+                baseline_checked = Instant::now();
+            }
+
+            if let Some(o) = overflow.poll(&queue) {
+                record_overflow(&db, &o);
+            }
+
+            let (batch, attempts) = match failed.take() {
+                Some(retry) => {
+                    thread::sleep(BATCH_RETRY_DELAY);
+                    retry
+                }
+                None => {
+                    let batch = queue.pop_batch(batch_size, flush_interval);
+                    if batch.is_empty() {
+                        if queue.is_closed() {
+                            break;
+                        }
+                        continue;
+                    }
+                    (batch, 0)
+                }
+            };
+
+            // The monitors only see a batch once it is committed, so a retry
+            // doesn't count anything twice
+            let result = db.in_transaction(|db| {
+                Ok(batch.iter().map(|record| write_record(db, record, my_ssids.as_ref())).collect::<Vec<_>>())
+            });
+            match result {
+                Ok(stored) => {
+                    for record in stored {
+                        written += observe_stored(
+                            &db,
+                            record,
+                            &mut monitor,
+                            &mut bursts,
+                            &mut deauth_monitor,
+                        );
+                    }
+                }
+                // A persistent error (disk full, schema trouble) would
+                // otherwise stall the writer while the queue drops the rest
+                Err(e) if attempts + 1 >= MAX_BATCH_ATTEMPTS => {
+                    record_dropped_batch(&db, batch.len(), &e);
+                }
+                Err(e) => {
+                    warn!("Failed to commit database batch of {} record(s), retrying: {}", batch.len(), e);
+                    failed = Some((batch, attempts + 1));
+                }
+            }
+        }
+        if let Some(o) = overflow.flush(&queue) {
+            record_overflow(&db, &o);
+        }
+        if let Err(e) = db.end_session(chrono::Utc::now().timestamp()) {
+            error!("Failed to close capture session: {}", e);
+        }
+        written
+    })
+}
+
+/// Event type recorded in the events table when the database queue overflows
+pub const EVENT_QUEUE_OVERFLOW: &str = "queue_overflow";
+
+/// Pause before retrying a batch whose commit failed
+const BATCH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Event type recorded when a batch is dropped after failing every retry
+pub const EVENT_BATCH_DROPPED: &str = "db_batch_dropped";
+
+/// Tries a failed batch gets before it is dropped
+const MAX_BATCH_ATTEMPTS: u32 = 5;
+
+/// Drops during a sustained burst are summed into one event per interval
+const OVERFLOW_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the database writer looks for a newly learned anomaly baseline
+const BASELINE_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Apply the stored anomaly baseline if it is newer than `applied_at`.
+/// Returns the timestamp of the baseline now in use.
+fn refresh_anomaly_baseline(db: &Database, monitor: &mut Option<NewDeviceRateMonitor>, applied_at: i64) -> i64 {
+    let monitor = match monitor.as_mut() {
+        Some(m) => m,
+        None => return applied_at,
+    };
+    match db.get_anomaly_baseline() {
+        Ok(Some(baseline)) if baseline.updated_at > applied_at => {
+            if monitor.apply_baseline(&baseline) {
+                info!(
+                    "Using learned new-device baseline: {:.2} per {}s over {} buckets",
+                    baseline.mean, baseline.bucket_secs, baseline.buckets
+                );
+            }
+            baseline.updated_at
+        }
+        Ok(_) => applied_at,
+        Err(e) => {
+            warn!("Failed to read anomaly baseline: {}", e);
+            applied_at
         }
     }
+}
 
-    let first_present = present_words[0];
+/// Record a session for a live capture on the configured interface, so every
+/// probe stored through `db` can be traced to the adapter that heard it and
+/// the settings it ran with
+pub fn start_capture_session(db: &mut Database, config: &Config) {
+    let capture = &config.capture;
+    let adapter = adapter_info(&capture.interface);
+    let channels = capture
+        .channels
+        .iter()
+        .map(|c| c.channel().to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let config_json = serde_json::to_string(config).ok();
+    let started_at = chrono::Utc::now().timestamp();
+    match db.start_session(&adapter, capture.backend.as_str(), &channels, config_json.as_deref(), started_at) {
+        Ok(id) => info!(
+            "Capture session {}: {} (driver: {}, MAC: {})",
+            id,
+            adapter.interface,
+            adapter.driver.as_deref().unwrap_or("unknown"),
+            adapter.mac.as_deref().unwrap_or("unknown")
+        ),
+        Err(e) => warn!("Failed to record capture session: {}", e),
+    }
+}
 
-    // Check if DBM Antenna Signal (bit 5) is present
-    if first_present & (1 << 5) == 0 {
-        return None;
+/// A stored record, for the monitors to see once its batch is committed
+enum Stored {
+    Probe {
+        mac: String,
+        timestamp: i64,
+        new_device: bool,
+        my_ssid: Option<MySsidProbe>,
+    },
+    Deauth(DeauthEvent),
+    Other,
+}
+
+/// Write one record inside the batch transaction. A record that fails
+/// partway is rolled back on its own and skipped, so the rest of the batch
+/// still commits without half of it.
+fn write_record(db: &Database, record: &CaptureRecord, my_ssids: Option<&MySsidWatch>) -> Stored {
+    match db.in_savepoint(|db| store_record(db, record, my_ssids)) {
+        Ok(stored) => stored,
+        Err(e) => {
+            error!("{:#}", e);
+            Stored::Other
+        }
     }
+}
 
-    let mut offset = pos; // Start after all present words
+fn store_record(db: &Database, record: &CaptureRecord, my_ssids: Option<&MySsidWatch>) -> Result<Stored> {
+    match record {
+        CaptureRecord::Probe(capture) => {
+            let new_device = db.insert_probe(capture).context("Failed to insert probe")?;
+            // Counted against the batch so far, so only the first probe for
+            // one of your networks raises it
+            let my_ssid = match my_ssids.map(|w| w.observe(db, capture, new_device)) {
+                Some(Ok(probe)) => probe,
+                Some(Err(e)) => {
+                    error!("Failed to check probe for your networks: {}", e);
+                    None
+                }
+                None => None,
+            };
+            return Ok(Stored::Probe {
+                mac: capture.mac.clone(),
+                timestamp: capture.timestamp,
+                new_device,
+                my_ssid,
+            });
+        }
+        CaptureRecord::Beacon(beacon) => {
+            db.upsert_access_point(beacon).context("Failed to record beacon")?;
+        }
+        CaptureRecord::Deauth(event) => {
+            db.insert_deauth_event(event).context("Failed to record deauth frame")?;
+            return Ok(Stored::Deauth(event.clone()));
+        }
+        CaptureRecord::Utilization(usage) => {
+            db.add_channel_usage(usage).context("Failed to record channel utilization")?;
+        }
+        CaptureRecord::Suppressed(counts) => {
+            for (mac, count) in counts {
+                db.add_suppressed_probes(mac, *count).context("Failed to record suppressed probes")?;
+            }
+        }
+    }
+    Ok(Stored::Other)
+}
+
+/// Feed a committed record to the monitors; returns 1 if it was a probe
+fn observe_stored(
+    db: &Database,
+    stored: Stored,
+    monitor: &mut Option<NewDeviceRateMonitor>,
+    bursts: &mut Option<BurstDetector>,
+    deauth_monitor: &mut DeauthMonitor,
+) -> u64 {
+    match stored {
+        Stored::Probe { mac, timestamp, new_device, my_ssid } => {
+            if let Some(spike) = monitor.as_mut().and_then(|m| m.observe(timestamp, new_device)) {
+                record_spike(db, &spike);
+            }
+            if let Some(burst) = bursts.as_mut().and_then(|b| b.observe(&mac, timestamp, new_device)) {
+                record_burst(db, &burst);
+            }
+            if let Some(probe) = my_ssid {
+                record_my_ssid_probe(db, &probe);
+            }
+            1
+        }
+        Stored::Deauth(event) => {
+            if let Some(attack) = deauth_monitor.observe(&event) {
+                record_deauth_attack(db, &attack);
+            }
+            0
+        }
+        Stored::Other => 0,
+    }
+}
 
-    // Bit 0: TSFT - 8 bytes, requires 8-byte alignment
-    if first_present & (1 << 0) != 0 {
-        offset = (offset + 7) & !7;
-        offset += 8;
+fn record_spike(db: &Database, spike: &NewDeviceSpike) {
+    let message = spike.describe();
+    warn!("New-device spike: {}", message);
+    let data = serde_json::json!({
+        "bucket_start": spike.bucket_start,
+        "bucket_secs": spike.bucket_secs,
+        "new_devices": spike.new_devices,
+        "baseline": spike.baseline,
+        "threshold": spike.threshold,
+    });
+    if let Err(e) = db.insert_event(spike.bucket_start, EVENT_NEW_DEVICE_SPIKE, &message, Some(&data.to_string())) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    // Bit 1: Flags - 1 byte
-    if first_present & (1 << 1) != 0 {
-        offset += 1;
+fn record_burst(db: &Database, burst: &ProbeBurst) {
+    let message = burst.describe();
+    warn!("Probe burst: {}", message);
+    let data = serde_json::to_string(burst).ok();
+    if let Err(e) = db.insert_event(burst.window_start, EVENT_PROBE_BURST, &message, data.as_deref()) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    // Bit 2: Rate - 1 byte
-    if first_present & (1 << 2) != 0 {
-        offset += 1;
+fn record_my_ssid_probe(db: &Database, probe: &MySsidProbe) {
+    let message = probe.describe();
+    warn!("Probe for your network: {}", message);
+    let data = serde_json::to_string(probe).ok();
+    if let Err(e) = db.insert_event(probe.timestamp, EVENT_MY_SSID_PROBE, &message, data.as_deref()) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    // Bit 3: Channel - 4 bytes, requires 2-byte alignment
-    if first_present & (1 << 3) != 0 {
-        offset = (offset + 1) & !1;
-        offset += 4;
+fn record_overflow(db: &Database, overflow: &Overflow) {
+    let message = format!(
+        "Database queue full: dropped {} record(s), {} in total (peak {}/{})",
+        overflow.dropped, overflow.total_dropped, overflow.high_water, overflow.capacity
+    );
+    warn!("{}", message);
+    let data = serde_json::json!({
+        "dropped": overflow.dropped,
+        "total_dropped": overflow.total_dropped,
+        "high_water": overflow.high_water,
+        "capacity": overflow.capacity,
+    });
+    let now = chrono::Utc::now().timestamp();
+    if let Err(e) = db.insert_event(now, EVENT_QUEUE_OVERFLOW, &message, Some(&data.to_string())) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    // Bit 4: FHSS - 2 bytes
-    if first_present & (1 << 4) != 0 {
-        offset += 2;
+fn record_dropped_batch(db: &Database, records: usize, error: &anyhow::Error) {
+    let message = format!(
+        "Gave up on a database batch of {} record(s) after {} attempts: {:#}",
+        records, MAX_BATCH_ATTEMPTS, error
+    );
+    error!("{}", message);
+    let data = serde_json::json!({
+        "records": records,
+        "attempts": MAX_BATCH_ATTEMPTS,
+        "error": format!("{:#}", error),
+    });
+    let now = chrono::Utc::now().timestamp();
+    if let Err(e) = db.insert_event(now, EVENT_BATCH_DROPPED, &message, Some(&data.to_string())) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    // Bit 5: DBM Antenna Signal - 1 byte signed
-    if offset < radiotap_len {
-        let signal = data[offset] as i8;
-        return Some(signal as i32);
+fn record_deauth_attack(db: &Database, attack: &DeauthAttack) {
+    let message = attack.describe();
+    warn!("Possible deauth attack: {}", message);
+    let data = serde_json::json!({
+        "bssid": attack.bssid,
+        "start": attack.start,
+        "end": attack.end,
+        "frames": attack.frames,
+        "broadcast_frames": attack.broadcast_frames,
+    });
+    if let Err(e) = db.insert_event(attack.end, EVENT_DEAUTH_ATTACK, &message, Some(&data.to_string())) {
+        error!("Failed to record event: {}", e);
     }
+}
 
-    None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_counts_suppressed() {
+        let mut limiter = ProbeRateLimiter::new(2);
+        let chatty = "AA:BB:CC:DD:EE:01";
+        assert!(limiter.admit(chatty, 100));
+        assert!(limiter.admit(chatty, 110));
+        assert!(!limiter.admit(chatty, 120));
+        assert!(!limiter.admit(chatty, 159));
+        // Other devices have their own budget
+        assert!(limiter.admit("AA:BB:CC:DD:EE:02", 120));
+        // A new minute starts a new window
+        assert!(limiter.admit(chatty, 160));
+
+        assert!(limiter.take_due().is_none());
+        assert_eq!(limiter.take_suppressed(), vec![(chatty.to_string(), 2)]);
+        assert!(limiter.take_suppressed().is_empty());
+    }
 }
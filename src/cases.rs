@@ -0,0 +1,173 @@
+//! Investigations ("cases") kept inside the capture database.
+//!
+//! A case groups devices, alert snapshots, free-text notes and time ranges
+//! under one name. Exporting a case bundles the items with a dossier for
+//! every attached device and a summary of each time range, so one JSON
+//! file carries everything related to an incident.
+
+use crate::analysis::{SurveillanceAlert, SurveillanceAnalyzer};
+use crate::database::{Case, CaseItem, CaseItemKind, Database, Event};
+use crate::export::{build_device_dossier, DeviceDossier};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Activity within a time range attached to a case
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeRangeSummary {
+    pub start: i64,
+    pub end: i64,
+    pub note: Option<String>,
+    pub probe_count: usize,
+    pub devices: Vec<String>,
+    pub events: Vec<Event>,
+}
+
+/// Everything attached to a case, ready to hand over as one document
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseBundle {
+    pub generated_at: String,
+    pub case: Case,
+    pub items: Vec<CaseItem>,
+    pub devices: Vec<DeviceDossier>,
+    pub time_ranges: Vec<TimeRangeSummary>,
+}
+
+/// Analyze `mac` over the last `hours` for attaching to a case as an alert
+pub fn alert_snapshot(
+    db: &Database,
+    analyzer: &SurveillanceAnalyzer,
+    mac: &str,
+    hours: u32,
+) -> Result<Option<SurveillanceAlert>> {
+    let device = match db.get_device_by_mac(mac)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+    let end = chrono::Utc::now().timestamp();
+    analyzer.evaluate_device(db, &device, end - hours as i64 * 3600, end)
+}
+
+/// Build the export bundle for `name`, or None if there is no such case
+pub fn build_case_bundle(
+    db: &Database,
+    name: &str,
+    analyzer: &SurveillanceAnalyzer,
+) -> Result<Option<CaseBundle>> {
+    let case = match db.get_case(name)? {
+        Some(c) => c,
+        None => return Ok(None),
+    };
+    let items = db.get_case_items(case.id)?;
+
+    let macs: BTreeSet<&str> = items
+        .iter()
+        .filter(|i| matches!(i.kind, CaseItemKind::Device | CaseItemKind::Alert))
+        .filter_map(|i| i.mac.as_deref())
+        .collect();
+    let mut devices = Vec::new();
+    for mac in macs {
+        if let Some(dossier) = build_device_dossier(db, mac, analyzer)? {
+            devices.push(dossier);
+        }
+    }
+
+    let mut time_ranges = Vec::new();
+    for item in items.iter().filter(|i| i.kind == CaseItemKind::TimeRange) {
+        let (start, end) = match (item.start, item.end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        let probe_count = db.count_probes_in_range(start, end)?;
+        let devices = db.get_macs_in_time_range(start, end)?;
+        let events = db
+            .get_events_since(start, None)?
+            .into_iter()
+            .filter(|e| e.timestamp <= end)
+            .collect();
+        time_ranges.push(TimeRangeSummary {
+            start,
+            end,
+            note: item.note.clone(),
+            probe_count,
+            devices,
+            events,
+        });
+    }
+
+    Ok(Some(CaseBundle {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        case,
+        items,
+        devices,
+        time_ranges,
+    }))
+}
+
+/// Parse a case time: RFC 3339, `YYYY-MM-DD HH:MM[:SS]` (UTC) or unix seconds
+pub fn parse_case_time(text: &str) -> Result<i64> {
+    let text = text.trim();
+    if let Ok(ts) = text.parse::<i64>() {
+        return Ok(ts);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Ok(dt.timestamp());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(text, format) {
+            return Ok(dt.and_utc().timestamp());
+        }
+    }
+    bail!("Invalid time {:?} (expected RFC 3339, \"YYYY-MM-DD HH:MM\" or unix seconds)", text)
+}
+
+/// Parse both ends of a time range and check their order
+pub fn parse_time_range(start: &str, end: &str) -> Result<(i64, i64)> {
+    let start = parse_case_time(start).context("Invalid range start")?;
+    let end = parse_case_time(end).context("Invalid range end")?;
+    if end < start {
+        bail!("Range end is before its start");
+    }
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ProbeCapture;
+
+    #[test]
+    fn test_parse_case_time() {
+        assert_eq!(parse_case_time("1700000000").unwrap(), 1_700_000_000);
+        assert_eq!(parse_case_time("2023-11-14T22:13:20Z").unwrap(), 1_700_000_000);
+        assert_eq!(parse_case_time("2023-11-14 22:13:20").unwrap(), 1_700_000_000);
+        assert_eq!(parse_case_time("2023-11-14 22:13").unwrap(), 1_699_999_980);
+        assert!(parse_case_time("yesterday").is_err());
+        assert!(parse_time_range("200", "100").is_err());
+    }
+
+    #[test]
+    fn test_case_bundle() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 1000)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", 5000)).unwrap();
+
+        let case_id = db.create_case("stalker", Some("Car in the street"), 900).unwrap();
+        assert!(db.create_case("stalker", None, 901).is_err());
+        db.add_case_item(case_id, &CaseItem::device("AA:BB:CC:DD:EE:01", 910)).unwrap();
+        db.add_case_item(case_id, &CaseItem::note("Seen at the gym too", 920)).unwrap();
+        db.add_case_item(case_id, &CaseItem::time_range(0, 2000, None, 930)).unwrap();
+
+        let analyzer = SurveillanceAnalyzer::new(vec![5, 10], 0.7);
+        let bundle = build_case_bundle(&db, "stalker", &analyzer).unwrap().unwrap();
+        assert_eq!(bundle.items.len(), 3);
+        assert_eq!(bundle.items[1].kind, CaseItemKind::Note);
+        assert_eq!(bundle.devices.len(), 1);
+        assert_eq!(bundle.time_ranges[0].probe_count, 1);
+        assert_eq!(bundle.time_ranges[0].devices, vec!["AA:BB:CC:DD:EE:01".to_string()]);
+
+        assert!(build_case_bundle(&db, "missing", &analyzer).unwrap().is_none());
+        assert_eq!(db.list_cases().unwrap()[0].1, 3);
+    }
+}
@@ -1,27 +1,160 @@
-use anyhow::{Context, Result};
+use crate::config::ChannelEntry;
+use crate::utilization::DwellClock;
+#[cfg(target_os = "linux")]
+use crate::nl80211::{self, Nl80211, NL80211_IFTYPE_MONITOR};
+#[cfg(windows)]
+use crate::npcap::{self as nl80211, Nl80211, NL80211_IFTYPE_MONITOR};
+#[cfg(not(any(target_os = "linux", windows)))]
+use unsupported::{self as nl80211, Nl80211, NL80211_IFTYPE_MONITOR};
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, error, info, warn};
+use std::path::Path;
 use std::process::Command;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 use tokio::time::sleep;
 
+/// Non-overlapping 2.4 GHz channels
+pub const CHANNELS_2GHZ: &[u8] = &[1, 6, 11];
+/// Non-DFS 5 GHz channels, usable without radar detection
+pub const CHANNELS_5GHZ: &[u8] = &[36, 40, 44, 48, 149, 153, 157, 161, 165];
+
+/// How often a paused hopper checks whether to resume
+const PAUSE_POLL: Duration = Duration::from_millis(250);
+
+/// Channel sets that can be switched between mid-capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelProfile {
+    Band2Ghz,
+    Band5Ghz,
+    All,
+    /// Stay on one channel
+    Locked(u8),
+}
+
+impl ChannelProfile {
+    /// Channels to hop. Configured channels in the chosen band are kept;
+    /// the band's defaults are used when none are configured.
+    pub fn channels(&self, configured: &[u8]) -> Vec<u8> {
+        let band = |defaults: &[u8], in_band: fn(u8) -> bool| {
+            let chosen: Vec<u8> = configured.iter().copied().filter(|c| in_band(*c)).collect();
+            if chosen.is_empty() {
+                defaults.to_vec()
+            } else {
+                chosen
+            }
+        };
+        match self {
+            ChannelProfile::Band2Ghz => band(CHANNELS_2GHZ, is_2ghz),
+            ChannelProfile::Band5Ghz => band(CHANNELS_5GHZ, is_5ghz),
+            ChannelProfile::All => {
+                let mut all = ChannelProfile::Band2Ghz.channels(configured);
+                all.extend(ChannelProfile::Band5Ghz.channels(configured));
+                all
+            }
+            ChannelProfile::Locked(channel) => vec![*channel],
+        }
+    }
+
+    /// Channels to hop as config entries, keeping the dwell time of any
+    /// channel that is configured
+    pub fn entries(&self, configured: &[ChannelEntry]) -> Vec<ChannelEntry> {
+        let numbers: Vec<u8> = configured.iter().map(|c| c.channel()).collect();
+        self.channels(&numbers)
+            .into_iter()
+            .map(|channel| {
+                configured
+                    .iter()
+                    .find(|c| c.channel() == channel)
+                    .copied()
+                    .unwrap_or(ChannelEntry::Channel(channel))
+            })
+            .collect()
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            ChannelProfile::Band2Ghz => "2.4 GHz only".to_string(),
+            ChannelProfile::Band5Ghz => "5 GHz only".to_string(),
+            ChannelProfile::All => "all bands".to_string(),
+            ChannelProfile::Locked(channel) => format!("locked on {}", channel),
+        }
+    }
+}
+
+fn is_2ghz(channel: u8) -> bool {
+    (1..=14).contains(&channel)
+}
+
+fn is_5ghz(channel: u8) -> bool {
+    !is_2ghz(channel)
+}
+
 pub struct ChannelHopper {
     interface: String,
-    channels: Vec<u8>,
+    /// Hops the 5 GHz channels while `interface` keeps to 2.4 GHz
+    second_interface: Option<String>,
+    channels: Vec<ChannelEntry>,
+    /// Dwell time for entries without their own
     hop_interval_ms: u64,
+    current: Option<Arc<AtomicU8>>,
+    updates: Option<watch::Receiver<Vec<ChannelEntry>>>,
+    dwell: Option<Arc<DwellClock>>,
+    paused: Option<Arc<AtomicBool>>,
 }
 
 impl ChannelHopper {
-    pub fn new(interface: String, channels: Vec<u8>, hop_interval_ms: u64) -> Self {
+    pub fn new(interface: String, channels: Vec<ChannelEntry>, hop_interval_ms: u64) -> Self {
         ChannelHopper {
             interface,
+            second_interface: None,
             channels,
             hop_interval_ms,
+            current: None,
+            updates: None,
+            dwell: None,
+            paused: None,
         }
     }
 
-    pub fn channels(&self) -> &[u8] {
+    /// Split the bands across two adapters: `interface` hops the 2.4 GHz
+    /// channels and `second` the 5 GHz ones. A band with no channels
+    /// configured hops its defaults, and so does a band a channel update
+    /// leaves empty.
+    pub fn with_band_split(mut self, second: Option<String>) -> Self {
+        self.second_interface = second;
+        self
+    }
+
+    /// Publish each successfully set channel into `current`. With the bands
+    /// split, only the first adapter's channel is published.
+    pub fn with_channel_tracker(mut self, current: Arc<AtomicU8>) -> Self {
+        self.current = Some(current);
+        self
+    }
+
+    /// Replace the hopped channels whenever `updates` changes
+    pub fn with_channel_updates(mut self, updates: watch::Receiver<Vec<ChannelEntry>>) -> Self {
+        self.updates = Some(updates);
+        self
+    }
+
+    /// Add the time spent listening on each channel to `dwell`
+    pub fn with_dwell_clock(mut self, dwell: Arc<DwellClock>) -> Self {
+        self.dwell = Some(dwell);
+        self
+    }
+
+    /// Stop hopping while `paused` is set, leaving the radio where it is
+    pub fn with_pause_flag(mut self, paused: Arc<AtomicBool>) -> Self {
+        self.paused = Some(paused);
+        self
+    }
+
+    pub fn channels(&self) -> &[ChannelEntry] {
         &self.channels
     }
 
@@ -35,131 +168,244 @@ impl ChannelHopper {
             return Ok(());
         }
 
+        match &self.second_interface {
+            Some(second) => {
+                // Both radios hop on this thread, which holds the capability
+                // channel changes need
+                let (first, second) = tokio::join!(
+                    self.hop(&self.interface, Some(ChannelProfile::Band2Ghz), running.clone()),
+                    self.hop(second, Some(ChannelProfile::Band5Ghz), running),
+                );
+                first.and(second)
+            }
+            None => self.hop(&self.interface, None, running).await,
+        }
+    }
+
+    /// Hop one adapter through the channels, or just those `band` picks
+    async fn hop(
+        &self,
+        interface: &str,
+        band: Option<ChannelProfile>,
+        running: Arc<AtomicBool>,
+    ) -> Result<()> {
+        let in_band = |channels: &[ChannelEntry]| match band {
+            Some(band) => band.entries(channels),
+            None => channels.to_vec(),
+        };
+        let mut channels = in_band(&self.channels);
+        // With the bands split, only the 2.4 GHz adapter drives the tracker
+        let current = self.current.as_ref().filter(|_| interface == self.interface);
+
         info!(
             "Starting channel hopper on {} with channels: {:?}, interval: {}ms",
-            self.interface, self.channels, self.hop_interval_ms
+            interface, channels, self.hop_interval_ms
         );
 
+        // One netlink socket for the whole run; a hop is then a single
+        // request instead of an `iw` process
+        let mut nl = Nl80211::connect()?;
+        let ifindex = nl80211::ifindex(interface)?;
+
+        let mut updates = self.updates.clone();
         let mut channel_idx = 0;
+        let mut last_set = None;
 
         while running.load(Ordering::SeqCst) {
-            let channel = self.channels[channel_idx];
+            if self.paused.as_ref().is_some_and(|p| p.load(Ordering::Relaxed)) {
+                sleep(PAUSE_POLL).await;
+                continue;
+            }
 
-            if let Err(e) = self.set_channel(channel) {
-                error!("Failed to set channel {}: {}", channel, e);
-            } else {
-                debug!("Switched to channel {}", channel);
+            if let Some(updates) = updates.as_mut() {
+                if updates.has_changed().unwrap_or(false) {
+                    let next = updates.borrow_and_update().clone();
+                    if !next.is_empty() {
+                        let next = in_band(&next);
+                        info!("Channel set on {} changed to {:?}", interface, next);
+                        channels = next;
+                        channel_idx = 0;
+                    }
+                }
             }
 
-            channel_idx = (channel_idx + 1) % self.channels.len();
-            sleep(Duration::from_millis(self.hop_interval_ms)).await;
+            let entry = channels[channel_idx % channels.len()];
+            let channel = entry.channel();
+
+            // A locked channel only needs setting once
+            if last_set != Some(channel) {
+                if let Err(e) = nl.set_channel(ifindex, channel) {
+                    error!("Failed to set channel {} on {}: {:#}", channel, interface, e);
+                } else {
+                    debug!("Switched {} to channel {}", interface, channel);
+                    last_set = Some(channel);
+                    if let Some(current) = current {
+                        current.store(channel, Ordering::Relaxed);
+                    }
+                }
+            }
+
+            channel_idx = (channel_idx + 1) % channels.len();
+            let listening = Instant::now();
+            sleep(Duration::from_millis(entry.dwell_ms(self.hop_interval_ms))).await;
+            // Credit the channel the radio is actually on, which stays the
+            // previous one when a switch fails
+            if let (Some(dwell), Some(tuned)) = (&self.dwell, last_set) {
+                dwell.add(tuned, listening.elapsed());
+            }
         }
 
-        info!("Channel hopper stopped");
+        info!("Channel hopper on {} stopped", interface);
         Ok(())
     }
 
-    fn set_channel(&self, channel: u8) -> Result<()> {
-        // Use iw to set channel
-        let output = Command::new("iw")
-            .args([
-                "dev",
-                &self.interface,
-                "set",
-                "channel",
-                &channel.to_string(),
-            ])
-            .output()
-            .context("Failed to execute iw command")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("iw command failed: {}", stderr);
-        }
-
-        Ok(())
+    /// Run the hopper on a thread of its own until `running` clears. `setup`
+    /// runs on that thread first and its error is returned here. Linux keeps
+    /// capabilities per thread, so this is where privileges are dropped while
+    /// keeping the one channel changes need.
+    pub fn spawn_thread<F>(self, running: Arc<AtomicBool>, setup: F) -> Result<thread::JoinHandle<()>>
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("channel-hopper".to_string())
+            .spawn(move || {
+                let ready = setup();
+                let ok = ready.is_ok();
+                let _ = ready_tx.send(ready);
+                if !ok {
+                    return;
+                }
+                let result = tokio::runtime::Builder::new_current_thread()
+                    .enable_time()
+                    .build()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|runtime| runtime.block_on(self.run(running)));
+                if let Err(e) = result {
+                    error!("Channel hopper error: {}", e);
+                }
+            })
+            .context("Failed to start the channel hopper")?;
+        ready_rx.recv().context("Channel hopper exited during setup")??;
+        Ok(handle)
     }
 }
 
-/// Set interface to monitor mode
-pub fn set_monitor_mode(interface: &str) -> Result<()> {
+/// Set interface to monitor mode, returning the interface to capture on.
+/// When the driver refuses the type change, `airmon-ng start` is tried
+/// instead; it usually leaves a renamed interface (wlan0 -> wlan0mon).
+pub fn set_monitor_mode(interface: &str) -> Result<String> {
     info!("Setting {} to monitor mode", interface);
 
-    // Bring interface down
-    let output = Command::new("ip")
-        .args(["link", "set", interface, "down"])
-        .output()
-        .context("Failed to bring interface down")?;
-
-    if !output.status.success() {
-        warn!("Failed to bring interface down: {:?}", output.stderr);
+    let err = match set_monitor_type(interface) {
+        Ok(()) => {
+            info!("Interface {} is now in monitor mode", interface);
+            return Ok(interface.to_string());
+        }
+        Err(e) => e,
+    };
+
+    warn!("{:#}; trying airmon-ng", err);
+    match airmon_start(interface) {
+        Ok(monitor) => {
+            info!("airmon-ng put {} in monitor mode as {}", interface, monitor);
+            Ok(monitor)
+        }
+        Err(airmon) => Err(anyhow!("{:#}; airmon-ng fallback failed: {:#}", err, airmon)),
     }
+}
 
-    // Set monitor mode
-    let output = Command::new("iw")
-        .args(["dev", interface, "set", "type", "monitor"])
-        .output()
-        .context("Failed to set monitor mode")?;
+fn set_monitor_type(interface: &str) -> Result<()> {
+    let ifindex = nl80211::ifindex(interface)?;
+    let mut nl = Nl80211::connect()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to set monitor mode: {}", stderr);
+    // Most drivers refuse a type change while the link is up
+    if let Err(e) = nl80211::set_link_up(interface, false) {
+        warn!("Failed to bring interface down: {:#}", e);
     }
 
-    // Bring interface up
-    let output = Command::new("ip")
-        .args(["link", "set", interface, "up"])
-        .output()
-        .context("Failed to bring interface up")?;
+    nl.set_iftype(ifindex, NL80211_IFTYPE_MONITOR)
+        .with_context(|| format!("Failed to set monitor mode on {}", interface))?;
+
+    nl80211::set_link_up(interface, true)
+}
 
+/// Run `airmon-ng start` on `interface` and find the monitor interface it
+/// left behind
+fn airmon_start(interface: &str) -> Result<String> {
+    let output = match Command::new("airmon-ng").args(["start", interface]).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => bail!("airmon-ng is not installed"),
+        Err(e) => return Err(e).context("Failed to run airmon-ng"),
+    };
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to bring interface up: {}", stderr);
+        bail!("airmon-ng exited with {}: {}", output.status, stderr.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Trust what airmon-ng reports, then its usual naming, then whatever
+    // is in monitor mode now
+    let candidates = [
+        airmon_monitor_interface(&stdout),
+        Some(format!("{}mon", interface)),
+        Some(interface.to_string()),
+    ];
+    for candidate in candidates.into_iter().flatten() {
+        if is_monitor_mode(&candidate).unwrap_or(false) {
+            return Ok(candidate);
+        }
+    }
+    match find_monitor_interface()? {
+        Some(found) => Ok(found),
+        None => bail!("airmon-ng ran but no interface is in monitor mode"),
     }
+}
 
-    info!("Interface {} is now in monitor mode", interface);
-    Ok(())
+/// Monitor interface named in `airmon-ng start` output, e.g.
+/// `(mac80211 monitor mode vif enabled for [phy0]wlan0 on [phy0]wlan0mon)`
+/// or, from older versions, `(monitor mode enabled on mon0)`
+pub fn airmon_monitor_interface(output: &str) -> Option<String> {
+    output
+        .lines()
+        .filter(|line| line.contains("monitor mode") && line.contains("enabled"))
+        .find_map(|line| {
+            let (_, name) = line.rsplit_once(" on ")?;
+            let name = name.trim().trim_end_matches(')');
+            // Drop the [phyN] prefix newer versions add
+            let name = name.rsplit(']').next().unwrap_or(name).trim();
+            (!name.is_empty() && !name.contains(char::is_whitespace)).then(|| name.to_string())
+        })
 }
 
 /// Check if interface is in monitor mode
 pub fn is_monitor_mode(interface: &str) -> Result<bool> {
-    let output = Command::new("iw")
-        .args(["dev", interface, "info"])
-        .output()
-        .context("Failed to get interface info")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.contains("type monitor"))
+    let ifindex = nl80211::ifindex(interface)?;
+    let iftype = Nl80211::connect()?.iftype(ifindex)?;
+    Ok(iftype == NL80211_IFTYPE_MONITOR)
 }
 
 /// Find the first wireless interface in monitor mode
 pub fn find_monitor_interface() -> Result<Option<String>> {
-    let output = Command::new("iw")
-        .args(["dev"])
-        .output()
-        .context("Failed to list wireless devices")?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    // Parse iw dev output to find interfaces
-    let mut current_interface: Option<String> = None;
-
-    for line in stdout.lines() {
-        let line = line.trim();
-        if line.starts_with("Interface ") {
-            current_interface = Some(line.strip_prefix("Interface ").unwrap_or("").to_string());
-        } else if line.starts_with("type ") && line.contains("monitor") {
-            if let Some(iface) = current_interface.take() {
-                info!("Found monitor mode interface: {}", iface);
-                return Ok(Some(iface));
-            }
-        }
+    let found = list_wireless_interfaces()?
+        .into_iter()
+        .find(|(_, mode)| mode == "monitor")
+        .map(|(iface, _)| iface);
+    if let Some(iface) = &found {
+        info!("Found monitor mode interface: {}", iface);
     }
+    Ok(found)
+}
 
-    Ok(None)
+/// List all wireless interfaces with their modes
+#[cfg(windows)]
+pub fn list_wireless_interfaces() -> Result<Vec<(String, String)>> {
+    nl80211::list_wireless_interfaces()
 }
 
 /// List all wireless interfaces with their modes
+#[cfg(not(windows))]
 pub fn list_wireless_interfaces() -> Result<Vec<(String, String)>> {
     let output = Command::new("iw")
         .args(["dev"])
@@ -167,12 +413,17 @@ pub fn list_wireless_interfaces() -> Result<Vec<(String, String)>> {
         .context("Failed to list wireless devices")?;
 
     let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(parse_iw_dev(&stdout))
+}
 
+/// Interfaces and their types from `iw dev` output
+#[cfg(not(windows))]
+fn parse_iw_dev(output: &str) -> Vec<(String, String)> {
     let mut interfaces = Vec::new();
     let mut current_interface: Option<String> = None;
     let mut current_type = String::from("unknown");
 
-    for line in stdout.lines() {
+    for line in output.lines() {
         let line = line.trim();
         if line.starts_with("Interface ") {
             // Save previous interface if exists
@@ -191,7 +442,48 @@ pub fn list_wireless_interfaces() -> Result<Vec<(String, String)>> {
         interfaces.push((iface, current_type));
     }
 
-    Ok(interfaces)
+    interfaces
+}
+
+/// The hardware behind a capture interface
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdapterInfo {
+    pub interface: String,
+    /// Kernel driver bound to the adapter, e.g. `ath9k_htc`
+    pub driver: Option<String>,
+    pub mac: Option<String>,
+}
+
+/// Read the driver and MAC of `interface` from sysfs. Fields that can't be
+/// read (no such interface, virtual device) are left empty.
+pub fn adapter_info(interface: &str) -> AdapterInfo {
+    adapter_info_in(Path::new("/sys/class/net"), interface)
+}
+
+fn adapter_info_in(sys_net: &Path, interface: &str) -> AdapterInfo {
+    let dir = sys_net.join(interface);
+    let mac = std::fs::read_to_string(dir.join("address"))
+        .ok()
+        .map(|address| address.trim().to_uppercase())
+        .filter(|address| !address.is_empty());
+    let driver = std::fs::read_link(dir.join("device").join("driver"))
+        .ok()
+        .and_then(|link| link.file_name().map(|name| name.to_string_lossy().into_owned()));
+    AdapterInfo {
+        interface: interface.to_string(),
+        driver,
+        mac,
+    }
+}
+
+/// Channel number for a center frequency in MHz (2.4 and 5 GHz bands)
+pub fn frequency_channel(mhz: u16) -> Option<u8> {
+    match mhz {
+        2484 => Some(14),
+        2412..=2472 => Some(((mhz - 2407) / 5) as u8),
+        5160..=5885 => Some(((mhz - 5000) / 5) as u8),
+        _ => None,
+    }
 }
 
 /// Get list of available 2.4GHz channels
@@ -229,3 +521,131 @@ pub fn parse_channels(config: &str) -> Vec<u8> {
         }
     }
 }
+
+/// Stand-ins for the nl80211 client where there is no radio control
+#[cfg(not(any(target_os = "linux", windows)))]
+mod unsupported {
+    use anyhow::{bail, Result};
+
+    pub const NL80211_IFTYPE_MONITOR: u32 = 6;
+
+    pub struct Nl80211;
+
+    impl Nl80211 {
+        pub fn connect() -> Result<Self> {
+            bail!("Radio control is only available on Linux (nl80211) and Windows (Npcap)")
+        }
+
+        pub fn set_channel(&mut self, _ifindex: u32, _channel: u8) -> Result<()> {
+            unreachable!()
+        }
+
+        pub fn set_iftype(&mut self, _ifindex: u32, _iftype: u32) -> Result<()> {
+            unreachable!()
+        }
+
+        pub fn iftype(&mut self, _ifindex: u32) -> Result<u32> {
+            unreachable!()
+        }
+    }
+
+    pub fn ifindex(_interface: &str) -> Result<u32> {
+        bail!("Radio control is only available on Linux (nl80211) and Windows (Npcap)")
+    }
+
+    pub fn set_link_up(_interface: &str, _up: bool) -> Result<()> {
+        bail!("Radio control is only available on Linux (nl80211) and Windows (Npcap)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frequency_channel() {
+        assert_eq!(frequency_channel(2412), Some(1));
+        assert_eq!(frequency_channel(2484), Some(14));
+        assert_eq!(frequency_channel(5180), Some(36));
+        assert_eq!(frequency_channel(5825), Some(165));
+        assert_eq!(frequency_channel(900), None);
+    }
+
+    #[test]
+    fn test_channel_profiles() {
+        let configured = [1, 6, 11, 36, 149];
+        assert_eq!(ChannelProfile::Band2Ghz.channels(&configured), vec![1, 6, 11]);
+        assert_eq!(ChannelProfile::Band5Ghz.channels(&configured), vec![36, 149]);
+        assert_eq!(ChannelProfile::Band5Ghz.channels(&[1, 6, 11]), CHANNELS_5GHZ.to_vec());
+        assert_eq!(ChannelProfile::All.channels(&[1, 6, 11]).len(), 3 + CHANNELS_5GHZ.len());
+        assert_eq!(ChannelProfile::Locked(6).channels(&configured), vec![6]);
+    }
+
+    #[test]
+    fn test_channel_entries_with_dwell() {
+        let configured: Vec<ChannelEntry> =
+            serde_json::from_str(r#"[1, {"channel": 6, "ms": 500}, 36]"#).unwrap();
+        assert_eq!(configured[0].dwell_ms(250), 250);
+        assert_eq!(configured[1], ChannelEntry::Dwell { channel: 6, ms: 500 });
+        assert_eq!(configured[1].dwell_ms(250), 500);
+
+        let entries = ChannelProfile::Band2Ghz.entries(&configured);
+        assert_eq!(entries, vec![ChannelEntry::Channel(1), ChannelEntry::Dwell { channel: 6, ms: 500 }]);
+        assert_eq!(ChannelProfile::Locked(11).entries(&configured), vec![ChannelEntry::Channel(11)]);
+    }
+
+    #[test]
+    fn test_airmon_monitor_interface() {
+        let modern = "\
+PHY\tInterface\tDriver\t\tChipset
+
+phy0\twlan0\t\tath9k_htc\tQualcomm Atheros Communications AR9271
+
+\t\t(mac80211 monitor mode vif enabled for [phy0]wlan0 on [phy0]wlan0mon)
+\t\t(mac80211 station mode vif disabled for [phy0]wlan0)
+";
+        assert_eq!(airmon_monitor_interface(modern).as_deref(), Some("wlan0mon"));
+        let legacy = "wlan1\t\tRalink RT2870/3070\trt2800usb - [phy1]\n\t\t(monitor mode enabled on mon0)\n";
+        assert_eq!(airmon_monitor_interface(legacy).as_deref(), Some("mon0"));
+        assert_eq!(airmon_monitor_interface("Found 2 processes that could cause trouble.\n"), None);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_parse_iw_dev() {
+        let output = "\
+phy#1
+\tInterface wlan1
+\t\tifindex 5
+\t\ttype monitor
+phy#0
+\tInterface wlan0
+\t\tifindex 3
+\t\ttype managed
+";
+        assert_eq!(
+            parse_iw_dev(output),
+            vec![("wlan1".to_string(), "monitor".to_string()), ("wlan0".to_string(), "managed".to_string())]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_adapter_info_from_sysfs() {
+        let root = std::env::temp_dir().join(format!("prowl-sysfs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let driver = root.join("drivers").join("ath9k_htc");
+        std::fs::create_dir_all(&driver).unwrap();
+        std::fs::create_dir_all(root.join("wlan1").join("device")).unwrap();
+        std::fs::write(root.join("wlan1").join("address"), "00:c0:ca:11:22:33\n").unwrap();
+        std::os::unix::fs::symlink(&driver, root.join("wlan1").join("device").join("driver")).unwrap();
+
+        let info = adapter_info_in(&root, "wlan1");
+        assert_eq!(info.driver.as_deref(), Some("ath9k_htc"));
+        assert_eq!(info.mac.as_deref(), Some("00:C0:CA:11:22:33"));
+        let missing = adapter_info_in(&root, "wlan9");
+        assert_eq!(missing.interface, "wlan9");
+        assert!(missing.driver.is_none() && missing.mac.is_none());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::queue::OverflowPolicy;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -11,6 +13,478 @@ pub struct Config {
     pub ignore_lists: IgnoreListsConfig,
     #[serde(default)]
     pub distance: DistanceConfig,
+    #[serde(default)]
+    pub occupancy: OccupancyConfig,
+    #[serde(default)]
+    pub queues: QueueConfig,
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+    #[serde(default)]
+    pub burst: BurstConfig,
+    #[serde(default)]
+    pub email: EmailConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub power: PowerConfig,
+    #[serde(default)]
+    pub disk: DiskConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub updates: UpdateConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub tui: TuiConfig,
+}
+
+/// Interactive display settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuiConfig {
+    /// Announce events as plain text lines instead of drawing the dashboard,
+    /// for screen readers and braille displays
+    #[serde(default)]
+    pub accessible: bool,
+    /// Base key bindings that `keys` overrides are applied on top of
+    #[serde(default)]
+    pub key_preset: KeyPreset,
+    /// Per-action key overrides, e.g. `"quit": ["ctrl+q"]`. Replaces every
+    /// key the preset binds to that action; checked at startup.
+    #[serde(default)]
+    pub keys: BTreeMap<String, Vec<String>>,
+    /// Hours the quick analyze key looks back over
+    #[serde(default = "default_analyze_hours")]
+    pub analyze_hours: u32,
+    /// Keep the last raw frame from each device in memory for the hex viewer
+    #[serde(default = "default_true")]
+    pub keep_frames: bool,
+}
+
+fn default_analyze_hours() -> u32 { 24 }
+
+impl Default for TuiConfig {
+    fn default() -> Self {
+        TuiConfig {
+            accessible: false,
+            key_preset: KeyPreset::default(),
+            keys: BTreeMap::new(),
+            analyze_hours: default_analyze_hours(),
+            keep_frames: true,
+        }
+    }
+}
+
+/// Built-in TUI key binding sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPreset {
+    /// Arrow keys plus single-letter commands
+    #[default]
+    Default,
+    /// hjkl movement alongside the arrow keys
+    Vim,
+    /// Ctrl-chord movement (C-n, C-p, C-f, C-b), for layouts where hjkl are scattered
+    Emacs,
+}
+
+/// Limits applied to statistics meant for publication
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Groups with fewer (noisy) devices than this are suppressed
+    #[serde(default = "default_min_group_size")]
+    pub min_group_size: usize,
+    /// Differential privacy budget per released count; smaller is noisier
+    #[serde(default = "default_epsilon")]
+    pub epsilon: f64,
+}
+
+fn default_min_group_size() -> usize { 5 }
+fn default_epsilon() -> f64 { 1.0 }
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        PrivacyConfig {
+            min_group_size: default_min_group_size(),
+            epsilon: default_epsilon(),
+        }
+    }
+}
+
+/// Rolling purge of raw probe data, for deployments that may only keep
+/// aggregates long-term
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// Purge raw data older than `raw_hours` while capture runs
+    #[serde(default)]
+    pub auto_purge: bool,
+    /// Hours of probes, devices and events kept before they are rolled up
+    /// into hourly counts and deleted
+    #[serde(default = "default_raw_hours")]
+    pub raw_hours: u32,
+    /// Minutes between purge runs
+    #[serde(default = "default_purge_interval")]
+    pub interval_minutes: u32,
+}
+
+fn default_raw_hours() -> u32 { 48 }
+fn default_purge_interval() -> u32 { 60 }
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            auto_purge: false,
+            raw_hours: default_raw_hours(),
+            interval_minutes: default_purge_interval(),
+        }
+    }
+}
+
+/// Scheduled summary reports sent through the local sendmail
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub to: Vec<String>,
+    #[serde(default = "default_email_from")]
+    pub from: String,
+    #[serde(default)]
+    pub schedule: ReportSchedule,
+    /// Hour of day (UTC) at which the report goes out
+    #[serde(default = "default_email_hour")]
+    pub send_hour_utc: u32,
+    /// Command that accepts a full message on stdin
+    #[serde(default = "default_sendmail_command")]
+    pub sendmail_command: String,
+    /// Number of suspicious devices listed in the report
+    #[serde(default = "default_email_top_devices")]
+    pub top_devices: usize,
+}
+
+fn default_email_from() -> String { "prowl@localhost".to_string() }
+fn default_email_hour() -> u32 { 7 }
+fn default_sendmail_command() -> String { "/usr/sbin/sendmail -t -i".to_string() }
+fn default_email_top_devices() -> usize { 10 }
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        EmailConfig {
+            enabled: false,
+            to: Vec::new(),
+            from: default_email_from(),
+            schedule: ReportSchedule::default(),
+            send_hour_utc: default_email_hour(),
+            sendmail_command: default_sendmail_command(),
+            top_devices: default_email_top_devices(),
+        }
+    }
+}
+
+/// Weekly refresh of learned baselines while `prowl capture` runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Hour of day (UTC) on Mondays at which maintenance runs
+    #[serde(default = "default_maintenance_hour")]
+    pub hour_utc: u32,
+    /// Days of history the new-device baseline is learned from
+    #[serde(default = "default_baseline_days")]
+    pub baseline_days: u32,
+}
+
+fn default_maintenance_hour() -> u32 { 4 }
+fn default_baseline_days() -> u32 { 7 }
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        MaintenanceConfig {
+            enabled: true,
+            hour_utc: default_maintenance_hour(),
+            baseline_days: default_baseline_days(),
+        }
+    }
+}
+
+/// Live risk scores refreshed while capture runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Minutes between re-scoring runs
+    #[serde(default = "default_risk_interval")]
+    pub interval_minutes: u32,
+    /// Hours of history each device is scored over
+    #[serde(default = "default_risk_window")]
+    pub window_hours: u32,
+}
+
+fn default_risk_interval() -> u32 { 5 }
+fn default_risk_window() -> u32 { 24 }
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        RiskConfig {
+            enabled: true,
+            interval_minutes: default_risk_interval(),
+            window_hours: default_risk_window(),
+        }
+    }
+}
+
+/// HTTP health endpoint for container healthchecks and orchestrators
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Address to serve `GET /health` on during `prowl capture`, e.g.
+    /// `0.0.0.0:8080`; off when unset
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Report unhealthy when no probe has been stored for this long. Unset
+    /// means only a stopped capture is unhealthy; quiet sites may see no
+    /// probes for a while.
+    #[serde(default)]
+    pub stale_secs: Option<u64>,
+}
+
+/// Checking for newer prowl releases. Off unless enabled; nothing is sent
+/// beyond the request for the latest release.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    /// Check once when `prowl capture` starts and log when a newer release
+    /// is out
+    #[serde(default)]
+    pub check_on_start: bool,
+    /// Release API endpoint returning JSON with a `tag_name`
+    #[serde(default = "default_update_url")]
+    pub url: String,
+}
+
+fn default_update_url() -> String {
+    "https://api.github.com/repos/kjruan/prawl/releases/latest".to_string()
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        UpdateConfig {
+            check_on_start: false,
+            url: default_update_url(),
+        }
+    }
+}
+
+/// Battery monitoring on portable sensors
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerConfig {
+    /// Power supply to read, a name under /sys/class/power_supply or a
+    /// directory path; the first battery found when unset
+    #[serde(default)]
+    pub supply: Option<String>,
+    /// Battery percentage below which the hopper parks on the first
+    /// configured channel while discharging
+    #[serde(default)]
+    pub low_power_percent: Option<u8>,
+    /// Battery percentage at which capture flushes the database and stops
+    /// while discharging
+    #[serde(default)]
+    pub shutdown_percent: Option<u8>,
+}
+
+/// Watching the disk the database lives on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskConfig {
+    /// Warn once the disk is projected to fill within this many hours
+    #[serde(default = "default_disk_warn_hours")]
+    pub warn_hours: u64,
+    /// Stop storing low-value probes (broadcast, or weaker than
+    /// `weak_signal_dbm`) while the disk is about to fill, instead of
+    /// failing mid-capture once it has
+    #[serde(default)]
+    pub shed_low_value: bool,
+    /// About to fill: projected full within this many hours...
+    #[serde(default = "default_disk_shed_hours")]
+    pub shed_hours: u64,
+    /// ...or less than this many MB free
+    #[serde(default = "default_disk_min_free_mb")]
+    pub min_free_mb: u64,
+    #[serde(default = "default_weak_signal_dbm")]
+    pub weak_signal_dbm: i32,
+}
+
+fn default_disk_warn_hours() -> u64 { 24 }
+fn default_disk_shed_hours() -> u64 { 2 }
+fn default_disk_min_free_mb() -> u64 { 200 }
+fn default_weak_signal_dbm() -> i32 { -80 }
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            warn_hours: default_disk_warn_hours(),
+            shed_low_value: false,
+            shed_hours: default_disk_shed_hours(),
+            min_free_mb: default_disk_min_free_mb(),
+            weak_signal_dbm: default_weak_signal_dbm(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSchedule {
+    #[default]
+    Daily,
+    /// Mondays
+    Weekly,
+}
+
+impl ReportSchedule {
+    /// Hours covered by one report
+    pub fn period_hours(&self) -> u32 {
+        match self {
+            ReportSchedule::Daily => 24,
+            ReportSchedule::Weekly => 24 * 7,
+        }
+    }
+}
+
+/// New-device rate spike detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Bucket size for counting new devices
+    #[serde(default = "default_anomaly_bucket_secs")]
+    pub bucket_secs: u64,
+    /// Standard deviations above the baseline that count as a spike
+    #[serde(default = "default_spike_sigma")]
+    pub spike_sigma: f64,
+    /// Never flag buckets with fewer new devices than this
+    #[serde(default = "default_min_new_devices")]
+    pub min_new_devices: usize,
+    /// Buckets observed before spikes are reported
+    #[serde(default = "default_warmup_buckets")]
+    pub warmup_buckets: usize,
+}
+
+fn default_anomaly_bucket_secs() -> u64 { 60 }
+fn default_spike_sigma() -> f64 { 3.0 }
+fn default_min_new_devices() -> usize { 10 }
+fn default_warmup_buckets() -> usize { 10 }
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            enabled: true,
+            bucket_secs: default_anomaly_bucket_secs(),
+            spike_sigma: default_spike_sigma(),
+            min_new_devices: default_min_new_devices(),
+            warmup_buckets: default_warmup_buckets(),
+        }
+    }
+}
+
+/// Per-device probe burst detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurstConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Window probes are counted in
+    #[serde(default = "default_burst_window_secs")]
+    pub window_secs: u64,
+    /// Earlier windows a device's own baseline is averaged over
+    #[serde(default = "default_burst_training_windows")]
+    pub training_windows: usize,
+    /// Windows right before the one tested that stay out of the baseline,
+    /// so the leading edge of a burst doesn't raise its own bar
+    #[serde(default = "default_burst_guard_windows")]
+    pub guard_windows: usize,
+    /// How many times its baseline a device has to probe to burst
+    #[serde(default = "default_burst_scale")]
+    pub scale: f64,
+    /// Never flag a window with fewer probes than this
+    #[serde(default = "default_burst_min_probes")]
+    pub min_probes: u32,
+}
+
+fn default_burst_window_secs() -> u64 { 60 }
+fn default_burst_training_windows() -> usize { 30 }
+fn default_burst_guard_windows() -> usize { 1 }
+fn default_burst_scale() -> f64 { 4.0 }
+fn default_burst_min_probes() -> u32 { 8 }
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        BurstConfig {
+            enabled: true,
+            window_secs: default_burst_window_secs(),
+            training_windows: default_burst_training_windows(),
+            guard_windows: default_burst_guard_windows(),
+            scale: default_burst_scale(),
+            min_probes: default_burst_min_probes(),
+        }
+    }
+}
+
+/// Capacities and overflow policies for the queues between capture and sinks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueConfig {
+    #[serde(default = "default_db_queue_capacity")]
+    pub db_capacity: usize,
+    /// `block` stalls capture while the writer catches up; the default
+    /// drops the oldest queued record and logs a `queue_overflow` event
+    #[serde(default)]
+    pub db_policy: OverflowPolicy,
+    #[serde(default = "default_ui_queue_capacity")]
+    pub ui_capacity: usize,
+    /// Records written per database transaction
+    #[serde(default = "default_db_batch_size")]
+    pub db_batch_size: usize,
+    /// Longest a record waits for its batch to fill before being written
+    #[serde(default = "default_db_flush_ms")]
+    pub db_flush_ms: u64,
+}
+
+fn default_db_queue_capacity() -> usize { 10_000 }
+fn default_ui_queue_capacity() -> usize { 1000 }
+fn default_db_batch_size() -> usize { 500 }
+fn default_db_flush_ms() -> u64 { 250 }
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        QueueConfig {
+            db_capacity: default_db_queue_capacity(),
+            db_policy: OverflowPolicy::default(),
+            ui_capacity: default_ui_queue_capacity(),
+            db_batch_size: default_db_batch_size(),
+            db_flush_ms: default_db_flush_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OccupancyConfig {
+    /// Time series bucket size in minutes
+    #[serde(default = "default_bucket_minutes")]
+    pub bucket_minutes: u32,
+    /// Average number of Wi-Fi devices carried per person
+    #[serde(default = "default_devices_per_person")]
+    pub devices_per_person: f64,
+}
+
+fn default_bucket_minutes() -> u32 { 5 }
+fn default_devices_per_person() -> f64 { 1.3 }
+
+impl Default for OccupancyConfig {
+    fn default() -> Self {
+        OccupancyConfig {
+            bucket_minutes: 5,
+            devices_per_person: 1.3,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,9 +537,188 @@ impl Default for DistanceConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CaptureConfig {
     pub interface: String,
-    pub channels: Vec<u8>,
+    /// A second monitor interface. With one set, the channels are split by
+    /// band: `interface` hops the 2.4 GHz ones and this one the 5 GHz ones,
+    /// and frames from both are captured together.
+    #[serde(default)]
+    pub second_interface: Option<String>,
+    pub channels: Vec<ChannelEntry>,
+    /// Dwell time for channels listed without their own `ms`
     pub hop_interval_ms: u64,
     pub database: String,
+    /// Packet capture backend: "pcap" (libpcap), "afpacket" (raw Linux socket)
+    /// or "mmap" (PACKET_MMAP rx rings)
+    #[serde(default)]
+    pub backend: CaptureBackend,
+    /// Total rx ring size for the mmap backend, split across fanout sockets
+    #[serde(default = "default_mmap_ring_mb")]
+    pub mmap_ring_mb: usize,
+    /// Number of fanout sockets for the mmap backend (default: one per CPU, max 4)
+    #[serde(default)]
+    pub mmap_fanout: Option<usize>,
+    /// Address the "kismet" backend listens on for remote capture sensors.
+    /// There is no authentication, so only widen it to a trusted network.
+    #[serde(default = "default_kismet_listen")]
+    pub kismet_listen: String,
+    /// UNIX socket the "helper" backend listens on for `prowl capture-helper`
+    #[serde(default = "default_helper_socket")]
+    pub helper_socket: String,
+    /// Backend `prowl capture-helper` opens the interface with
+    #[serde(default)]
+    pub helper_backend: CaptureBackend,
+    /// Synthetic device population for the "simulated" backend
+    #[serde(default)]
+    pub simulation: SimulationConfig,
+    /// Also capture beacons and probe responses to build the access point
+    /// table and recover hidden SSIDs from directed probes
+    #[serde(default)]
+    pub capture_beacons: bool,
+    /// Retransmitted probes (retry bit set, same sequence number) from a
+    /// device within this many ms of the original are stored once; 0 keeps
+    /// every copy
+    #[serde(default = "default_retry_window_ms")]
+    pub retry_window_ms: u64,
+    /// Store at most this many probes per device per minute; the rest are
+    /// only counted, per device, as suppressed
+    #[serde(default)]
+    pub max_probes_per_mac_per_minute: Option<u32>,
+    /// Also append every stored probe request frame to this pcap file
+    /// (needs the pcap-export feature)
+    #[serde(default)]
+    pub pcap_output: Option<String>,
+    /// Start a new pcap file once the current one reaches this many MB.
+    /// With either rotation limit set, `pcap_output` names the series:
+    /// `capture.pcap` becomes `capture-YYYYMMDD-HHMM.pcap`.
+    #[serde(default)]
+    pub pcap_rotate_mb: Option<u64>,
+    /// Start a new pcap file after this many minutes
+    #[serde(default)]
+    pub pcap_rotate_minutes: Option<u64>,
+    /// Also capture deauthentication and disassociation frames and flag
+    /// bursts of them as attacks
+    #[serde(default)]
+    pub detect_deauth: bool,
+    /// Capture every frame, not just the filtered types, to estimate how
+    /// busy each hopped channel is. Costs noticeably more CPU on busy air.
+    #[serde(default)]
+    pub measure_utilization: bool,
+    /// BPF filter for the pcap backend. `capture_beacons` and
+    /// `detect_deauth` add their frame types to it; empty captures everything,
+    /// as does `measure_utilization`.
+    #[serde(default = "default_bpf_filter")]
+    pub bpf_filter: String,
+    /// Once the capture handle is open, switch from root to an unprivileged
+    /// user, keeping only what channel hopping needs
+    #[serde(default = "default_true")]
+    pub drop_privileges: bool,
+    /// User to switch to; defaults to the one who ran `sudo`. The database,
+    /// pcap output and export directories must be writable by them.
+    #[serde(default)]
+    pub run_as: Option<String>,
+}
+
+/// A channel to hop to: a bare number dwells for `hop_interval_ms`, while
+/// `{"channel": 1, "ms": 500}` sets its own dwell time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ChannelEntry {
+    Channel(u8),
+    Dwell { channel: u8, ms: u64 },
+}
+
+impl ChannelEntry {
+    pub fn channel(&self) -> u8 {
+        match self {
+            ChannelEntry::Channel(channel) | ChannelEntry::Dwell { channel, .. } => *channel,
+        }
+    }
+
+    /// Dwell time, `default_ms` for bare channels
+    pub fn dwell_ms(&self, default_ms: u64) -> u64 {
+        match self {
+            ChannelEntry::Channel(_) => default_ms,
+            ChannelEntry::Dwell { ms, .. } => *ms,
+        }
+    }
+}
+
+fn default_mmap_ring_mb() -> usize { 4 }
+fn default_kismet_listen() -> String { "127.0.0.1:3501".to_string() }
+fn default_helper_socket() -> String { "./prowl-capture.sock".to_string() }
+fn default_bpf_filter() -> String { "type mgt subtype probe-req".to_string() }
+fn default_retry_window_ms() -> u64 { 200 }
+
+/// Synthetic traffic generated by `prowl simulate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    #[serde(default = "default_sim_commuters")]
+    pub commuters: usize,
+    #[serde(default = "default_sim_randomized_phones")]
+    pub randomized_phones: usize,
+    #[serde(default = "default_sim_followers")]
+    pub followers: usize,
+    /// Average probe rate with every device in range
+    #[serde(default = "default_sim_probes_per_sec")]
+    pub probes_per_sec: f64,
+    #[serde(default = "default_sim_seed")]
+    pub seed: u64,
+}
+
+fn default_sim_commuters() -> usize { 40 }
+fn default_sim_randomized_phones() -> usize { 8 }
+fn default_sim_followers() -> usize { 1 }
+fn default_sim_probes_per_sec() -> f64 { 8.0 }
+fn default_sim_seed() -> u64 { 0x5EED }
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            commuters: default_sim_commuters(),
+            randomized_phones: default_sim_randomized_phones(),
+            followers: default_sim_followers(),
+            probes_per_sec: default_sim_probes_per_sec(),
+            seed: default_sim_seed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureBackend {
+    #[cfg_attr(feature = "pcap", default)]
+    Pcap,
+    #[cfg_attr(not(feature = "pcap"), default)]
+    AfPacket,
+    Mmap,
+    /// Synthetic traffic from `capture.simulation`, no interface needed
+    Simulated,
+    /// Frames streamed by Kismet remote capture sensors to `kismet_listen`
+    Kismet,
+    /// Frames forwarded by a privileged `prowl capture-helper` over
+    /// `helper_socket`, so this process needs no privileges
+    Helper,
+}
+
+impl CaptureBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptureBackend::Pcap => "pcap",
+            CaptureBackend::AfPacket => "afpacket",
+            CaptureBackend::Mmap => "mmap",
+            CaptureBackend::Simulated => "simulated",
+            CaptureBackend::Kismet => "kismet",
+            CaptureBackend::Helper => "helper",
+        }
+    }
+
+    /// Whether frames come from a local radio, which needs monitor mode,
+    /// privileges and a channel hopper
+    pub fn has_radio(&self) -> bool {
+        !matches!(
+            self,
+            CaptureBackend::Simulated | CaptureBackend::Kismet | CaptureBackend::Helper
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,12 +732,46 @@ pub struct GpsConfig {
 pub struct AnalysisConfig {
     pub time_windows_minutes: Vec<u32>,
     pub persistence_threshold: f64,
+    /// How to score devices that only send broadcast (wildcard) probes
+    #[serde(default)]
+    pub broadcast_only: BroadcastOnlyPolicy,
+    /// Score multiplier for broadcast-only devices with the "downweight" policy
+    #[serde(default = "default_broadcast_only_weight")]
+    pub broadcast_only_weight: f64,
+    /// Your own networks (home, work). A device probing for one that few
+    /// other devices here know is called out in its alert reasons.
+    #[serde(default)]
+    pub my_ssids: Vec<String>,
+    /// Alert as soon as a device probes for one of `my_ssids` for the first
+    /// time, whatever its score
+    #[serde(default = "default_true")]
+    pub alert_my_ssid_probes: bool,
+}
+
+fn default_broadcast_only_weight() -> f64 { 0.5 }
+
+/// Treatment of devices that never name a network in their probes. Most
+/// benign phones and laptops behave this way, so they carry less signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastOnlyPolicy {
+    /// Score like any other device
+    #[default]
+    Include,
+    /// Multiply the score by `broadcast_only_weight`
+    Downweight,
+    /// Never alert on them
+    Exclude,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IgnoreListsConfig {
     pub mac: String,
     pub ssid: String,
+    /// Ignore this machine's own interface MACs during live capture. They
+    /// are held in memory only and never written to the MAC list.
+    #[serde(default = "default_true")]
+    pub ignore_own_macs: bool,
 }
 
 impl Config {
@@ -95,6 +782,18 @@ impl Config {
         let config: Config = serde_json::from_str(&content)
             .with_context(|| "Failed to parse config file")?;
 
+        let hop_ms = config.capture.hop_interval_ms;
+        if let Some(entry) = config.capture.channels.iter().find(|c| c.dwell_ms(hop_ms) == 0) {
+            anyhow::bail!("Channel {} has a dwell time of 0 ms", entry.channel());
+        }
+        if config.capture.pcap_rotate_mb == Some(0) || config.capture.pcap_rotate_minutes == Some(0) {
+            anyhow::bail!("pcap rotation limits must be greater than 0");
+        }
+        let power = &config.power;
+        if power.low_power_percent.into_iter().chain(power.shutdown_percent).any(|p| p > 100) {
+            anyhow::bail!("power thresholds are percentages and must be at most 100");
+        }
+
         Ok(config)
     }
 
@@ -102,9 +801,28 @@ impl Config {
         Config {
             capture: CaptureConfig {
                 interface: "wlan1".to_string(),
-                channels: vec![1, 6, 11],
+                second_interface: None,
+                channels: vec![ChannelEntry::Channel(1), ChannelEntry::Channel(6), ChannelEntry::Channel(11)],
                 hop_interval_ms: 250,
                 database: "./prowl.db".to_string(),
+                backend: CaptureBackend::default(),
+                mmap_ring_mb: default_mmap_ring_mb(),
+                mmap_fanout: None,
+                kismet_listen: default_kismet_listen(),
+                helper_socket: default_helper_socket(),
+                helper_backend: CaptureBackend::default(),
+                simulation: SimulationConfig::default(),
+                capture_beacons: false,
+                retry_window_ms: default_retry_window_ms(),
+                max_probes_per_mac_per_minute: None,
+                pcap_output: None,
+                pcap_rotate_mb: None,
+                pcap_rotate_minutes: None,
+                detect_deauth: false,
+                measure_utilization: false,
+                bpf_filter: default_bpf_filter(),
+                drop_privileges: true,
+                run_as: None,
             },
             gps: GpsConfig {
                 enabled: true,
@@ -114,15 +832,90 @@ impl Config {
             analysis: AnalysisConfig {
                 time_windows_minutes: vec![5, 10, 15, 20],
                 persistence_threshold: 0.7,
+                broadcast_only: BroadcastOnlyPolicy::default(),
+                broadcast_only_weight: default_broadcast_only_weight(),
+                my_ssids: Vec::new(),
+                alert_my_ssid_probes: true,
             },
             ignore_lists: IgnoreListsConfig {
                 mac: "ignore_lists/mac_list.json".to_string(),
                 ssid: "ignore_lists/ssid_list.json".to_string(),
+                ignore_own_macs: true,
             },
             distance: DistanceConfig::default(),
+            occupancy: OccupancyConfig::default(),
+            queues: QueueConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            burst: BurstConfig::default(),
+            email: EmailConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            risk: RiskConfig::default(),
+            power: PowerConfig::default(),
+            disk: DiskConfig::default(),
+            privacy: PrivacyConfig::default(),
+            retention: RetentionConfig::default(),
+            updates: UpdateConfig::default(),
+            health: HealthConfig::default(),
+            tui: TuiConfig::default(),
         }
     }
 
+    /// Apply `PROWL_*` environment overrides, for containers configured
+    /// through their environment rather than a mounted config file.
+    /// `lookup` is `std::env::var` outside of tests.
+    ///
+    /// `PROWL_DATA_DIR` anchors relative data paths (database, ignore lists,
+    /// pcap output) in one directory, typically a mounted volume; the other
+    /// variables set single values: `PROWL_INTERFACE`, `PROWL_DATABASE`,
+    /// `PROWL_GPS_ENABLED`, `PROWL_GPS_HOST`, `PROWL_GPS_PORT` and
+    /// `PROWL_HEALTH_LISTEN`.
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let var = |name: &str| lookup(name).filter(|v| !v.trim().is_empty());
+
+        if let Some(interface) = var("PROWL_INTERFACE") {
+            self.capture.interface = interface;
+        }
+        if let Some(database) = var("PROWL_DATABASE") {
+            self.capture.database = database;
+        }
+        if let Some(enabled) = var("PROWL_GPS_ENABLED") {
+            self.gps.enabled = match enabled.trim().to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" => false,
+                other => anyhow::bail!("PROWL_GPS_ENABLED must be true or false, not {:?}", other),
+            };
+        }
+        if let Some(host) = var("PROWL_GPS_HOST") {
+            self.gps.host = host;
+        }
+        if let Some(port) = var("PROWL_GPS_PORT") {
+            self.gps.port = port
+                .trim()
+                .parse()
+                .with_context(|| format!("PROWL_GPS_PORT is not a port number: {:?}", port))?;
+        }
+        if let Some(listen) = var("PROWL_HEALTH_LISTEN") {
+            self.health.listen = Some(listen);
+        }
+
+        if let Some(dir) = var("PROWL_DATA_DIR") {
+            let dir = Path::new(&dir);
+            let anchor = |path: &mut String| {
+                if Path::new(path.as_str()).is_relative() {
+                    *path = dir.join(path.as_str()).to_string_lossy().into_owned();
+                }
+            };
+            anchor(&mut self.capture.database);
+            anchor(&mut self.capture.helper_socket);
+            anchor(&mut self.ignore_lists.mac);
+            anchor(&mut self.ignore_lists.ssid);
+            if let Some(pcap) = self.capture.pcap_output.as_mut() {
+                anchor(pcap);
+            }
+        }
+        Ok(())
+    }
+
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let content = serde_json::to_string_pretty(self)?;
         fs::write(path, content)?;
@@ -135,3 +928,40 @@ impl Default for Config {
         Self::default_config()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_env() {
+        let vars: BTreeMap<&str, &str> = [
+            ("PROWL_INTERFACE", "wlan2"),
+            ("PROWL_GPS_ENABLED", "false"),
+            ("PROWL_GPS_PORT", "2948"),
+            ("PROWL_HEALTH_LISTEN", "0.0.0.0:8080"),
+            ("PROWL_DATA_DIR", "/data"),
+            ("PROWL_GPS_HOST", ""),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut config = Config::default_config();
+        config.ignore_lists.ssid = "/etc/prowl/ssid_list.json".to_string();
+        config.apply_env(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.capture.interface, "wlan2");
+        assert!(!config.gps.enabled);
+        assert_eq!(config.gps.port, 2948);
+        // Empty values are treated as unset
+        assert_eq!(config.gps.host, "localhost");
+        assert_eq!(config.health.listen.as_deref(), Some("0.0.0.0:8080"));
+        assert_eq!(Path::new(&config.capture.database), Path::new("/data/./prowl.db"));
+        assert_eq!(config.ignore_lists.mac, "/data/ignore_lists/mac_list.json");
+        assert_eq!(config.ignore_lists.ssid, "/etc/prowl/ssid_list.json");
+
+        let mut config = Config::default_config();
+        let bad = config.apply_env(|name| (name == "PROWL_GPS_PORT").then(|| "gpsd".to_string()));
+        assert!(bad.is_err());
+    }
+}
@@ -1,14 +1,85 @@
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags, OptionalExtension};
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
-use crate::parser::ProbeCapabilities;
+use crate::anomaly::AnomalyBaseline;
+use crate::channels::AdapterInfo;
+use crate::fingerprint;
+use crate::location_history::LocationPoint;
+use crate::oui::{attribute_vendor, is_randomized_mac, VendorAttribution};
+use crate::parser::{DeauthKind, ProbeCapabilities};
+use crate::residency::{Residency, ResidencyRecord};
+use crate::utilization::ChannelUsage;
+
+/// Schema version kept in the database's `user_version` pragma. Bump it when
+/// a change means older builds would misread the data, e.g. a column whose
+/// meaning changes; additive tables and columns don't need a bump.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// How long a connection waits for another one's write lock before failing
+/// with SQLITE_BUSY. Capture, the TUI, retention and one-off commands all
+/// share the file.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The database was written by a newer prowl than this one
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "{path} has schema version {found}, written by prowl {written_by}, but this prowl ({current}) only \
+     understands schema version {supported}. Upgrade prowl, or point --database at another file.",
+    current = env!("CARGO_PKG_VERSION"),
+    supported = SCHEMA_VERSION
+)]
+pub struct IncompatibleDatabase {
+    pub path: String,
+    pub found: i64,
+    pub written_by: String,
+}
+
+/// Schema version and the prowl versions that created and last opened a
+/// database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaInfo {
+    pub version: i64,
+    /// First prowl version to open the database once versions were recorded
+    pub created_by: Option<String>,
+    pub last_opened_by: Option<String>,
+}
+
+/// Fail with `IncompatibleDatabase` if the database behind `conn` has a
+/// newer schema than this build understands. Checked before anything is
+/// created or migrated so a newer database is never touched.
+pub fn check_schema_version(conn: &Connection, path: &str) -> Result<()> {
+    let found: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if found <= SCHEMA_VERSION {
+        return Ok(());
+    }
+    let written_by: Option<String> = conn
+        .query_row("SELECT value FROM schema_info WHERE key = 'last_opened_by'", [], |row| row.get(0))
+        .optional()
+        .unwrap_or(None);
+    Err(IncompatibleDatabase {
+        path: path.to_string(),
+        found,
+        written_by: written_by.unwrap_or_else(|| "unknown".to_string()),
+    }
+    .into())
+}
 
 pub struct Database {
     conn: Connection,
+    /// Capture session that probes stored through this connection belong to
+    session_id: Option<i64>,
+    /// The session's start point is still to be taken from the first
+    /// geotagged probe
+    session_unlocated: Cell<bool>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Device {
     pub id: i64,
     pub mac: String,
@@ -16,17 +87,104 @@ pub struct Device {
     pub last_seen: i64,
 }
 
+/// A device as last recorded, for picking the live view back up after a
+/// restart
 #[derive(Debug, Clone)]
+pub struct DeviceHistory {
+    pub mac: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub probe_count: usize,
+    pub last_signal: Option<i32>,
+    pub ssids: Vec<String>,
+    /// Capabilities from the most recent probe that carried them
+    pub capabilities: Option<ProbeCapabilities>,
+}
+
+/// GPS state at the moment a probe was captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpsStatus {
+    /// GPS tagging was turned off for the session
+    Disabled,
+    /// GPS was enabled but had no position fix
+    NoFix,
+    /// Probe was geotagged
+    Fix,
+}
+
+impl GpsStatus {
+    pub fn from_position(enabled: bool, position: Option<(f64, f64)>) -> Self {
+        match (enabled, position) {
+            (_, Some(_)) => GpsStatus::Fix,
+            (true, None) => GpsStatus::NoFix,
+            (false, None) => GpsStatus::Disabled,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GpsStatus::Disabled => "disabled",
+            GpsStatus::NoFix => "no_fix",
+            GpsStatus::Fix => "fix",
+        }
+    }
+
+    fn from_db(value: Option<String>, has_position: bool) -> Option<Self> {
+        match value.as_deref() {
+            Some("disabled") => Some(GpsStatus::Disabled),
+            Some("no_fix") => Some(GpsStatus::NoFix),
+            Some("fix") => Some(GpsStatus::Fix),
+            // Rows written before the status was recorded
+            _ if has_position => Some(GpsStatus::Fix),
+            _ => None,
+        }
+    }
+}
+
+/// Probe counts by GPS status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GpsFixStats {
+    pub fix: usize,
+    pub no_fix: usize,
+    pub disabled: usize,
+    /// Legacy probes without a recorded status or coordinates
+    pub unknown: usize,
+}
+
+impl GpsFixStats {
+    /// Fraction of probes geotagged while GPS was enabled
+    pub fn fix_rate(&self) -> Option<f64> {
+        let enabled = self.fix + self.no_fix;
+        if enabled == 0 {
+            None
+        } else {
+            Some(self.fix as f64 / enabled as f64)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Probe {
     pub id: i64,
     pub device_id: i64,
     pub ssid: String,
     pub timestamp: i64,
+    /// Sub-second part of the capture time; None for probes stored before
+    /// it was recorded
+    pub timestamp_micros: Option<u32>,
     pub lat: Option<f64>,
     pub lon: Option<f64>,
     pub signal_dbm: Option<i32>,
     pub channel: Option<u8>,
     pub distance_m: Option<f64>,
+    /// None for legacy probes with no coordinates and no recorded status
+    pub gps_status: Option<GpsStatus>,
+    /// AP targeted by a directed probe; None for broadcast probes
+    pub bssid: Option<String>,
+    /// 802.11 sequence number; None for synthetic probes and ones stored
+    /// before it was recorded
+    pub sequence_number: Option<u16>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,27 +192,409 @@ pub struct ProbeCapture {
     pub mac: String,
     pub ssid: String,
     pub timestamp: i64,
+    /// Sub-second part of the capture time
+    pub timestamp_micros: u32,
     pub lat: Option<f64>,
     pub lon: Option<f64>,
     pub signal_dbm: Option<i32>,
     pub channel: Option<u8>,
     pub distance_m: Option<f64>,
+    pub gps_status: GpsStatus,
+    pub bssid: Option<String>,
+    /// 802.11 sequence number; None where there is no frame behind the probe
+    pub sequence_number: Option<u16>,
     pub capabilities: Option<ProbeCapabilities>,
 }
 
+#[cfg(test)]
+impl ProbeCapture {
+    /// Probe at -60 dBm on channel 6 with GPS disabled, for test fixtures
+    pub(crate) fn test(mac: &str, ssid: &str, timestamp: i64) -> Self {
+        ProbeCapture {
+            mac: mac.to_string(),
+            ssid: ssid.to_string(),
+            timestamp,
+            timestamp_micros: 0,
+            lat: None,
+            lon: None,
+            signal_dbm: Some(-60),
+            channel: Some(6),
+            distance_m: None,
+            gps_status: GpsStatus::Disabled,
+            bssid: None,
+            sequence_number: None,
+            capabilities: None,
+        }
+    }
+}
+
+/// Beacon or probe response observation queued for the database
+#[derive(Debug, Clone)]
+pub struct BeaconCapture {
+    pub bssid: String,
+    pub ssid: String,
+    pub hidden: bool,
+    pub channel: Option<u8>,
+    pub timestamp: i64,
+    pub probe_response: bool,
+    /// Station a probe response answered
+    pub destination: Option<String>,
+}
+
+/// A deauthentication or disassociation frame seen on the air
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeauthEvent {
+    pub timestamp: i64,
+    pub kind: DeauthKind,
+    pub source: String,
+    pub destination: String,
+    pub bssid: String,
+    pub reason_code: Option<u16>,
+    pub signal_dbm: Option<i32>,
+    pub channel: Option<u8>,
+}
+
+/// Anything the capture loop hands to the database writer
+#[derive(Debug, Clone)]
+pub enum CaptureRecord {
+    Probe(ProbeCapture),
+    Beacon(BeaconCapture),
+    Deauth(DeauthEvent),
+    /// Frames and airtime per channel since the previous flush
+    Utilization(Vec<ChannelUsage>),
+    /// Probes per device held back by the ingest rate limit
+    Suppressed(Vec<(String, u64)>),
+}
+
+/// Access point seen in beacons
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessPoint {
+    pub bssid: String,
+    /// Broadcast SSID, or the recovered one for hidden networks
+    pub ssid: Option<String>,
+    pub hidden: bool,
+    /// "beacon", "probe_response" or "directed_probe"
+    pub ssid_source: Option<String>,
+    pub channel: Option<u8>,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// An AP that answered probes from a particular device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeResponder {
+    pub bssid: String,
+    /// SSID the AP answered with; empty if it answered anonymously
+    pub ssid: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    /// Stored responses, at most one per AP and station a minute
+    pub responses: u64,
+}
+
+/// An SSID and the devices that probed for it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SsidSummary {
+    pub ssid: String,
+    pub devices: usize,
+    pub probes: usize,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// Devices attributed to one vendor ("Unknown" when unresolved)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VendorSummary {
+    pub vendor: String,
+    pub devices: usize,
+    /// Devices with a locally administered (randomized) MAC
+    pub randomized: usize,
+}
+
+impl VendorSummary {
+    pub fn randomized_share(&self) -> f64 {
+        if self.devices == 0 {
+            0.0
+        } else {
+            self.randomized as f64 / self.devices as f64
+        }
+    }
+}
+
+/// Detection event (anomalies and other notable moments during capture)
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub id: i64,
+    pub timestamp: i64,
+    pub event_type: String,
+    pub message: String,
+    pub data_json: Option<String>,
+}
+
+/// One run of a live capture and the adapter that made it
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub id: i64,
+    pub started_at: i64,
+    /// None while the session is running, or if it never shut down cleanly
+    pub ended_at: Option<i64>,
+    pub interface: String,
+    pub driver: Option<String>,
+    pub adapter_mac: Option<String>,
+    pub backend: String,
+    /// Channels the hopper was configured with, comma-separated
+    pub channels: Option<String>,
+    /// First GPS fix of the session, if any probe was geotagged
+    pub start_lat: Option<f64>,
+    pub start_lon: Option<f64>,
+    /// Configuration the session ran with, as JSON
+    pub config_json: Option<String>,
+    pub probes: i64,
+}
+
+/// How many distinct days a device was heard on, and over what span
+#[derive(Debug, Clone)]
+pub struct DeviceDays {
+    pub mac: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub days_seen: i64,
+}
+
+/// A user's verdict on whether two MACs are one device, with the evidence
+/// it was made against
+#[derive(Debug, Clone)]
+pub struct DeviceLinkRecord {
+    /// The pair, in sorted order
+    pub mac_a: String,
+    pub mac_b: String,
+    /// "confirmed" or "rejected"
+    pub verdict: String,
+    pub confidence: f64,
+    pub evidence_json: String,
+    pub note: Option<String>,
+    pub updated_at: i64,
+}
+
+/// A named investigation grouping related evidence
+#[derive(Debug, Clone, Serialize)]
+pub struct Case {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: i64,
+}
+
+/// What a case item refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseItemKind {
+    Device,
+    /// Snapshot of an analysis result, stored in `data_json`
+    Alert,
+    Note,
+    TimeRange,
+}
+
+impl CaseItemKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaseItemKind::Device => "device",
+            CaseItemKind::Alert => "alert",
+            CaseItemKind::Note => "note",
+            CaseItemKind::TimeRange => "time_range",
+        }
+    }
+
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "device" => Some(CaseItemKind::Device),
+            "alert" => Some(CaseItemKind::Alert),
+            "note" => Some(CaseItemKind::Note),
+            "time_range" => Some(CaseItemKind::TimeRange),
+            _ => None,
+        }
+    }
+}
+
+/// One piece of evidence attached to a case. `id` is assigned on insert.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseItem {
+    pub id: i64,
+    pub kind: CaseItemKind,
+    pub mac: Option<String>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub note: Option<String>,
+    pub data_json: Option<String>,
+    pub added_at: i64,
+}
+
+impl CaseItem {
+    fn new(kind: CaseItemKind, added_at: i64) -> Self {
+        CaseItem {
+            id: 0,
+            kind,
+            mac: None,
+            start: None,
+            end: None,
+            note: None,
+            data_json: None,
+            added_at,
+        }
+    }
+
+    pub fn device(mac: &str, added_at: i64) -> Self {
+        CaseItem {
+            mac: Some(mac.to_string()),
+            ..CaseItem::new(CaseItemKind::Device, added_at)
+        }
+    }
+
+    pub fn alert(mac: &str, data_json: String, added_at: i64) -> Self {
+        CaseItem {
+            mac: Some(mac.to_string()),
+            data_json: Some(data_json),
+            ..CaseItem::new(CaseItemKind::Alert, added_at)
+        }
+    }
+
+    pub fn note(text: &str, added_at: i64) -> Self {
+        CaseItem {
+            note: Some(text.to_string()),
+            ..CaseItem::new(CaseItemKind::Note, added_at)
+        }
+    }
+
+    pub fn time_range(start: i64, end: i64, note: Option<&str>, added_at: i64) -> Self {
+        CaseItem {
+            start: Some(start),
+            end: Some(end),
+            note: note.map(str::to_string),
+            ..CaseItem::new(CaseItemKind::TimeRange, added_at)
+        }
+    }
+}
+
+/// Lightweight per-probe view used by occupancy estimation
+#[derive(Debug, Clone)]
+pub struct ProbeObservation {
+    pub mac: String,
+    pub timestamp: i64,
+    pub ssid: String,
+    pub wifi_generation: Option<String>,
+}
+
+/// Rows affected by a targeted deletion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    pub devices: usize,
+    pub probes: usize,
+    pub capabilities: usize,
+    /// Labels, links, watch list entries, risk and residency
+    pub device_records: usize,
+    pub probe_responses: usize,
+    pub deauth_frames: usize,
+    pub case_items: usize,
+    pub events: usize,
+}
+
+/// Counts kept for one hour of probes once the raw rows are purged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ProbeRollup {
+    pub hour_start: i64,
+    pub devices: usize,
+    /// Devices first seen in the hour
+    pub new_devices: usize,
+    pub randomized_devices: usize,
+    pub probes: usize,
+    /// Distinct SSIDs asked for by name
+    pub directed_ssids: usize,
+}
+
+/// Rows removed by a retention purge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeSummary {
+    /// Hours added to the rollups first
+    pub hours_rolled_up: usize,
+    pub probes: usize,
+    pub devices: usize,
+    pub events: usize,
+    /// Probe responses and deauth frames
+    pub other: usize,
+}
+
+/// Views in `REPORTING_VIEWS`, checked for on open
+const REPORTING_VIEW_NAMES: [&str; 4] =
+    ["devices_recent", "probes_per_hour", "alerts_active", "vendor_counts"];
+
+/// Read-only views over the raw schema. Times are unix seconds; surveillance
+/// scores are computed at analysis time, so `alerts_active` lists the
+/// detection events recorded during the last 24 hours.
+const REPORTING_VIEWS: &str = r#"
+    DROP VIEW IF EXISTS devices_recent;
+    CREATE VIEW devices_recent AS
+        SELECT d.mac, d.vendor, d.first_seen, d.last_seen,
+               COUNT(p.id) AS probe_count,
+               COUNT(DISTINCT p.ssid) AS ssid_count,
+               MAX(p.signal_dbm) AS max_signal_dbm
+        FROM devices d
+        LEFT JOIN probes p ON p.device_id = d.id
+        WHERE d.last_seen >= CAST(strftime('%s', 'now') AS INTEGER) - 86400
+        GROUP BY d.id;
+
+    DROP VIEW IF EXISTS probes_per_hour;
+    CREATE VIEW probes_per_hour AS
+        SELECT (timestamp / 3600) * 3600 AS hour,
+               COUNT(*) AS probes,
+               COUNT(DISTINCT device_id) AS devices
+        FROM probes
+        GROUP BY hour;
+
+    DROP VIEW IF EXISTS alerts_active;
+    CREATE VIEW alerts_active AS
+        SELECT id, timestamp, event_type, message, data_json
+        FROM events
+        WHERE timestamp >= CAST(strftime('%s', 'now') AS INTEGER) - 86400;
+
+    DROP VIEW IF EXISTS vendor_counts;
+    CREATE VIEW vendor_counts AS
+        SELECT COALESCE(vendor, 'Unknown') AS vendor,
+               COUNT(*) AS devices,
+               MAX(last_seen) AS last_seen
+        FROM devices
+        GROUP BY COALESCE(vendor, 'Unknown');
+"#;
+
 impl Database {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let conn = Connection::open(path.as_ref())
             .with_context(|| format!("Failed to open database: {:?}", path.as_ref()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        check_schema_version(&conn, &path.as_ref().display().to_string())?;
+        // Readers carry on while the capture writer commits
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
 
-        let db = Database { conn };
+        let db = Database { conn, session_id: None, session_unlocated: Cell::new(false) };
         db.initialize()?;
         Ok(db)
     }
 
+    /// Open without creating, migrating or writing anything, for looking at
+    /// a database another prowl is capturing into
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let flags =
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path.as_ref(), flags)
+            .with_context(|| format!("Failed to open database: {:?}", path.as_ref()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        check_schema_version(&conn, &path.as_ref().display().to_string())?;
+        Ok(Database { conn, session_id: None, session_unlocated: Cell::new(false) })
+    }
+
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
-        let db = Database { conn };
+        let db = Database { conn, session_id: None, session_unlocated: Cell::new(false) };
         db.initialize()?;
         Ok(db)
     }
@@ -93,6 +633,159 @@ impl Database {
                 FOREIGN KEY (probe_id) REFERENCES probes(id) ON DELETE CASCADE
             );
 
+            CREATE TABLE IF NOT EXISTS events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                message TEXT NOT NULL,
+                data_json TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS access_points (
+                bssid TEXT PRIMARY KEY,
+                ssid TEXT,
+                hidden INTEGER NOT NULL DEFAULT 0,
+                ssid_source TEXT,
+                channel INTEGER,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS ap_ssids (
+                bssid TEXT NOT NULL,
+                ssid TEXT NOT NULL,
+                source TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                PRIMARY KEY (bssid, ssid, source)
+            );
+
+            CREATE TABLE IF NOT EXISTS probe_responses (
+                bssid TEXT NOT NULL,
+                device_mac TEXT NOT NULL,
+                ssid TEXT NOT NULL,
+                first_seen INTEGER NOT NULL,
+                last_seen INTEGER NOT NULL,
+                responses INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (bssid, device_mac, ssid)
+            );
+
+            CREATE TABLE IF NOT EXISTS deauth_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                source_mac TEXT NOT NULL,
+                destination_mac TEXT NOT NULL,
+                bssid TEXT NOT NULL,
+                reason_code INTEGER,
+                signal_dbm INTEGER,
+                channel INTEGER
+            );
+
+            CREATE TABLE IF NOT EXISTS cases (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT UNIQUE NOT NULL,
+                description TEXT,
+                created_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS case_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                case_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                mac TEXT,
+                start_ts INTEGER,
+                end_ts INTEGER,
+                note TEXT,
+                data_json TEXT,
+                added_at INTEGER NOT NULL,
+                FOREIGN KEY (case_id) REFERENCES cases(id) ON DELETE CASCADE
+            );
+
+            CREATE TABLE IF NOT EXISTS device_labels (
+                mac TEXT PRIMARY KEY,
+                label TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS watchlist (
+                mac TEXT PRIMARY KEY,
+                added_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS device_links (
+                mac_a TEXT NOT NULL,
+                mac_b TEXT NOT NULL,
+                verdict TEXT NOT NULL,
+                confidence REAL NOT NULL,
+                evidence_json TEXT NOT NULL,
+                note TEXT,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (mac_a, mac_b)
+            );
+
+            CREATE TABLE IF NOT EXISTS device_residency (
+                mac TEXT PRIMARY KEY,
+                class TEXT NOT NULL,
+                days_seen INTEGER NOT NULL,
+                first_seen INTEGER NOT NULL,
+                resident_since INTEGER,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS device_risk (
+                mac TEXT PRIMARY KEY,
+                score REAL NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                interface TEXT NOT NULL,
+                driver TEXT,
+                adapter_mac TEXT,
+                backend TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS channel_utilization (
+                session_id INTEGER NOT NULL,
+                channel INTEGER NOT NULL,
+                frames INTEGER NOT NULL,
+                airtime_us INTEGER NOT NULL,
+                dwell_us INTEGER NOT NULL,
+                PRIMARY KEY (session_id, channel)
+            );
+
+            CREATE TABLE IF NOT EXISTS location_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                lat REAL NOT NULL,
+                lon REAL NOT NULL,
+                accuracy_m REAL,
+                source TEXT NOT NULL,
+                UNIQUE(timestamp, lat, lon)
+            );
+
+            CREATE TABLE IF NOT EXISTS probe_rollups (
+                hour_start INTEGER PRIMARY KEY,
+                devices INTEGER NOT NULL,
+                new_devices INTEGER NOT NULL,
+                randomized_devices INTEGER NOT NULL,
+                probes INTEGER NOT NULL,
+                directed_ssids INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS anomaly_baseline (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                mean REAL NOT NULL,
+                variance REAL NOT NULL,
+                buckets INTEGER NOT NULL,
+                bucket_secs INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
             CREATE INDEX IF NOT EXISTS idx_devices_mac ON devices(mac);
             CREATE INDEX IF NOT EXISTS idx_devices_last_seen ON devices(last_seen);
             CREATE INDEX IF NOT EXISTS idx_probes_timestamp ON probes(timestamp);
@@ -100,6 +793,12 @@ impl Database {
             CREATE INDEX IF NOT EXISTS idx_probes_device_id ON probes(device_id);
             CREATE INDEX IF NOT EXISTS idx_probe_caps_probe_id ON probe_capabilities(probe_id);
             CREATE INDEX IF NOT EXISTS idx_probe_caps_wifi_gen ON probe_capabilities(wifi_generation);
+            CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_case_items_case_id ON case_items(case_id);
+            CREATE INDEX IF NOT EXISTS idx_ap_ssids_ssid ON ap_ssids(ssid);
+            CREATE INDEX IF NOT EXISTS idx_probe_responses_device ON probe_responses(device_mac);
+            CREATE INDEX IF NOT EXISTS idx_deauth_events_timestamp ON deauth_events(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_location_history_timestamp ON location_history(timestamp);
             "#,
         )?;
 
@@ -109,14 +808,184 @@ impl Database {
             [],
         );
 
+        // Migration: vendor attribution provenance on devices
+        let _ = self.conn.execute("ALTER TABLE devices ADD COLUMN vendor TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE devices ADD COLUMN vendor_oui TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE devices ADD COLUMN vendor_source TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE devices ADD COLUMN oui_db_version TEXT", []);
+
+        // Migration: probes dropped by the per-device ingest rate limit
+        let _ = self.conn.execute(
+            "ALTER TABLE devices ADD COLUMN suppressed_probes INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Migration: GPS status per probe
+        let _ = self.conn.execute("ALTER TABLE probes ADD COLUMN gps_status TEXT", []);
+
+        // Migration: target BSSID of directed probes
+        let _ = self.conn.execute("ALTER TABLE probes ADD COLUMN bssid TEXT", []);
+        let _ = self.conn.execute("CREATE INDEX IF NOT EXISTS idx_probes_bssid ON probes(bssid)", []);
+
+        // Migration: microseconds of the capture time, from the packet header
+        let _ = self.conn.execute("ALTER TABLE probes ADD COLUMN timestamp_micros INTEGER", []);
+
+        // Migration: 802.11 sequence number, which advances per transmission
+        // across MAC randomization on many chipsets
+        let _ = self.conn.execute("ALTER TABLE probes ADD COLUMN sequence_number INTEGER", []);
+
+        // Migration: capability fingerprint and the algorithm version behind it
+        let _ = self.conn.execute("ALTER TABLE probe_capabilities ADD COLUMN fingerprint TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE probe_capabilities ADD COLUMN fingerprint_version INTEGER", []);
+        let _ = self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_probe_caps_fingerprint ON probe_capabilities(fingerprint)",
+            [],
+        );
+
+        // Migration: capture session (and so adapter) behind each probe
+        let _ = self.conn.execute("ALTER TABLE probes ADD COLUMN session_id INTEGER", []);
+        let _ = self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_probes_session_id ON probes(session_id)",
+            [],
+        );
+
+        // Migration: what each session ran with and where it started
+        let _ = self.conn.execute("ALTER TABLE sessions ADD COLUMN channels TEXT", []);
+        let _ = self.conn.execute("ALTER TABLE sessions ADD COLUMN start_lat REAL", []);
+        let _ = self.conn.execute("ALTER TABLE sessions ADD COLUMN start_lon REAL", []);
+        let _ = self.conn.execute("ALTER TABLE sessions ADD COLUMN config_json TEXT", []);
+
+        self.record_schema_version()?;
+        self.ensure_reporting_views()?;
+
+        Ok(())
+    }
+
+    /// Stamp the schema version and the prowl version opening the database,
+    /// so a newer database can be recognized by older builds. Only written
+    /// when something changed, so opening with the same build stays a read.
+    fn record_schema_version(&self) -> Result<()> {
+        let version = env!("CARGO_PKG_VERSION");
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_info (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+        )?;
+        let stored = self.schema_info()?;
+        if stored.version == SCHEMA_VERSION
+            && stored.created_by.is_some()
+            && stored.last_opened_by.as_deref() == Some(version)
+        {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO schema_info (key, value) VALUES ('created_by', ?)",
+            params![version],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO schema_info (key, value) VALUES ('last_opened_by', ?)",
+            params![version],
+        )?;
+        self.conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Reporting views for Grafana and other SQL tools. Recreated when one is
+    /// missing or a different prowl version built them, so their definitions
+    /// follow the schema without every open taking the write lock.
+    fn ensure_reporting_views(&self) -> Result<()> {
+        let version = env!("CARGO_PKG_VERSION");
+        let present: usize = self.conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'view' AND name IN (?, ?, ?, ?)",
+            params_from_iter(REPORTING_VIEW_NAMES),
+            |row| row.get(0),
+        )?;
+        let built_by: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM schema_info WHERE key = 'views_built_by'", [], |row| row.get(0))
+            .optional()?;
+        if present == REPORTING_VIEW_NAMES.len() && built_by.as_deref() == Some(version) {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute_batch(REPORTING_VIEWS)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO schema_info (key, value) VALUES ('views_built_by', ?)",
+            params![version],
+        )?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn insert_probe(&self, capture: &ProbeCapture) -> Result<()> {
+    /// The secret kept in `schema_info` under `name`, stored from
+    /// `generate` on first use
+    pub fn stored_key(&self, name: &str, generate: impl FnOnce() -> String) -> Result<String> {
+        let query = || -> Result<Option<String>> {
+            let value = self
+                .conn
+                .query_row("SELECT value FROM schema_info WHERE key = ?", params![name], |row| row.get(0))
+                .optional()?;
+            Ok(value)
+        };
+        if let Some(key) = query()? {
+            return Ok(key);
+        }
+        self.conn.execute(
+            "INSERT OR IGNORE INTO schema_info (key, value) VALUES (?, ?)",
+            params![name, generate()],
+        )?;
+        // Another prowl may have stored one first
+        query()?.context("Failed to store a key in schema_info")
+    }
+
+    pub fn schema_info(&self) -> Result<SchemaInfo> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let value = |key: &str| -> Result<Option<String>> {
+            Ok(self
+                .conn
+                .query_row("SELECT value FROM schema_info WHERE key = ?", params![key], |row| row.get(0))
+                .optional()?)
+        };
+        Ok(SchemaInfo {
+            version,
+            created_by: value("created_by")?,
+            last_opened_by: value("last_opened_by")?,
+        })
+    }
+
+    /// Run `f` inside one transaction, committed if it returns Ok
+    pub fn in_transaction<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+        let tx = self.conn.unchecked_transaction()?;
+        let result = f(self)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    /// Run `f` inside a savepoint of the current transaction. If it fails,
+    /// whatever it wrote is rolled back and the transaction carries on.
+    pub fn in_savepoint<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT record")?;
+        match f(self) {
+            Ok(result) => {
+                self.conn.execute_batch("RELEASE record")?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.conn.execute_batch("ROLLBACK TO record; RELEASE record")?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Insert a probe, creating the device if needed. Returns true if the
+    /// device had never been seen before.
+    pub fn insert_probe(&self, capture: &ProbeCapture) -> Result<bool> {
         let now = capture.timestamp;
 
         // Insert or update device
-        let device_id: i64 = {
+        let (device_id, new_device): (i64, bool) = {
             // Try to get existing device
             let existing: Option<i64> = self.conn
                 .query_row(
@@ -133,43 +1002,80 @@ impl Database {
                         "UPDATE devices SET last_seen = ? WHERE id = ?",
                         params![now, id],
                     )?;
-                    id
+                    (id, false)
                 }
                 None => {
-                    // Insert new device
+                    // Insert new device, recording which OUI table resolved its vendor
+                    let attribution = attribute_vendor(&capture.mac);
                     self.conn.execute(
-                        "INSERT INTO devices (mac, first_seen, last_seen) VALUES (?, ?, ?)",
-                        params![&capture.mac, now, now],
+                        "INSERT INTO devices (mac, first_seen, last_seen, vendor, vendor_oui, vendor_source, oui_db_version)
+                         VALUES (?, ?, ?, ?, ?, ?, ?)",
+                        params![
+                            &capture.mac,
+                            now,
+                            now,
+                            attribution.as_ref().and_then(|a| a.vendor.as_deref()),
+                            attribution.as_ref().map(|a| a.oui.as_str()),
+                            attribution.as_ref().map(|a| a.source.as_str()),
+                            attribution.as_ref().map(|a| a.db_version.as_str()),
+                        ],
                     )?;
-                    self.conn.last_insert_rowid()
+                    (self.conn.last_insert_rowid(), true)
                 }
             }
         };
 
         // Insert probe
         self.conn.execute(
-            "INSERT INTO probes (device_id, ssid, timestamp, lat, lon, signal_dbm, channel, distance_m)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO probes (device_id, ssid, timestamp, timestamp_micros, lat, lon, signal_dbm, channel, distance_m, gps_status, bssid,
+                                 session_id, sequence_number)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 device_id,
                 &capture.ssid,
                 capture.timestamp,
+                capture.timestamp_micros,
                 capture.lat,
                 capture.lon,
                 capture.signal_dbm,
                 capture.channel.map(|c| c as i32),
                 capture.distance_m,
+                capture.gps_status.as_str(),
+                capture.bssid.as_deref(),
+                self.session_id,
+                capture.sequence_number,
             ],
         )?;
 
         let probe_id = self.conn.last_insert_rowid();
 
+        if let (true, Some(id), Some(lat), Some(lon)) =
+            (self.session_unlocated.get(), self.session_id, capture.lat, capture.lon)
+        {
+            self.conn.execute(
+                "UPDATE sessions SET start_lat = ?, start_lon = ? WHERE id = ?",
+                params![lat, lon, id],
+            )?;
+            self.session_unlocated.set(false);
+        }
+
+        // A directed probe naming a hidden AP reveals its SSID
+        if let (Some(bssid), false) = (&capture.bssid, capture.ssid.is_empty()) {
+            self.conn.execute(
+                "UPDATE access_points SET ssid = ?, ssid_source = 'directed_probe'
+                 WHERE bssid = ? AND hidden = 1 AND ssid IS NULL",
+                params![&capture.ssid, bssid],
+            )?;
+        }
+
         // Insert capabilities if present
         if let Some(caps) = &capture.capabilities {
             if let Ok(caps_json) = serde_json::to_string(caps) {
-                let _ = self.conn.execute(
-                    "INSERT INTO probe_capabilities (probe_id, capabilities_json, has_ht, has_vht, has_he, wifi_generation)
-                     VALUES (?, ?, ?, ?, ?, ?)",
+                let algorithm = fingerprint::current();
+                self.conn.execute(
+                    "INSERT INTO probe_capabilities (probe_id, capabilities_json, has_ht, has_vht, has_he, wifi_generation,
+                                                     fingerprint, fingerprint_version)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
                     params![
                         probe_id,
                         caps_json,
@@ -177,14 +1083,389 @@ impl Database {
                         caps.has_vht as i32,
                         caps.has_he as i32,
                         &caps.wifi_generation,
+                        algorithm.fingerprint(caps),
+                        algorithm.version(),
                     ],
-                );
+                )?;
             }
         }
 
+        Ok(new_device)
+    }
+
+    /// Record a beacon or probe response. Beacons decide whether the AP is
+    /// hidden and which SSID it broadcasts; probe responses only fill in a
+    /// missing name. Every SSID an AP used is also kept in `ap_ssids`, and
+    /// each probe response in `probe_responses` against the station it
+    /// answered. A newly seen hidden AP picks up its SSID from earlier
+    /// directed probes.
+    pub fn upsert_access_point(&self, beacon: &BeaconCapture) -> Result<()> {
+        let ssid = (!beacon.hidden).then_some(beacon.ssid.as_str());
+        let source = if beacon.probe_response { "probe_response" } else { "beacon" };
+        self.conn.execute(
+            "INSERT INTO access_points (bssid, ssid, hidden, ssid_source, channel, first_seen, last_seen)
+             VALUES (?1, ?2, ?3, CASE WHEN ?2 IS NULL THEN NULL ELSE ?6 END, ?4, ?5, ?5)
+             ON CONFLICT(bssid) DO UPDATE SET
+                 last_seen = MAX(last_seen, excluded.last_seen),
+                 channel = COALESCE(excluded.channel, channel),
+                 hidden = CASE WHEN ?7 THEN hidden ELSE excluded.hidden END,
+                 ssid = CASE WHEN ?7 THEN COALESCE(ssid, excluded.ssid)
+                             ELSE COALESCE(excluded.ssid, ssid) END,
+                 ssid_source = CASE WHEN ?7 THEN COALESCE(ssid_source, excluded.ssid_source)
+                                    ELSE COALESCE(excluded.ssid_source, ssid_source) END",
+            params![
+                &beacon.bssid,
+                ssid,
+                beacon.hidden as i32,
+                beacon.channel.map(|c| c as i32),
+                beacon.timestamp,
+                source,
+                beacon.probe_response,
+            ],
+        )?;
+
+        if let Some(ssid) = ssid {
+            self.conn.execute(
+                "INSERT INTO ap_ssids (bssid, ssid, source, first_seen, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(bssid, ssid, source) DO UPDATE SET
+                     last_seen = MAX(last_seen, excluded.last_seen)",
+                params![&beacon.bssid, ssid, source, beacon.timestamp],
+            )?;
+        }
+
+        if let Some(station) = beacon.destination.as_deref().filter(|d| *d != "FF:FF:FF:FF:FF:FF") {
+            self.conn.execute(
+                "INSERT INTO probe_responses (bssid, device_mac, ssid, first_seen, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(bssid, device_mac, ssid) DO UPDATE SET
+                     last_seen = MAX(last_seen, excluded.last_seen),
+                     responses = responses + 1",
+                params![&beacon.bssid, station, ssid.unwrap_or(""), beacon.timestamp],
+            )?;
+        }
+
+        if beacon.hidden {
+            self.conn.execute(
+                "UPDATE access_points SET
+                     ssid = (SELECT ssid FROM probes WHERE bssid = ?1 AND ssid != ''
+                             ORDER BY timestamp DESC LIMIT 1),
+                     ssid_source = 'directed_probe'
+                 WHERE bssid = ?1 AND ssid IS NULL
+                   AND EXISTS (SELECT 1 FROM probes WHERE bssid = ?1 AND ssid != '')",
+                params![&beacon.bssid],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_access_points(&self) -> Result<Vec<AccessPoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT bssid, ssid, hidden, ssid_source, channel, first_seen, last_seen
+             FROM access_points ORDER BY last_seen DESC"
+        )?;
+
+        let aps = stmt
+            .query_map([], |row| {
+                Ok(AccessPoint {
+                    bssid: row.get(0)?,
+                    ssid: row.get(1)?,
+                    hidden: row.get::<_, i32>(2)? != 0,
+                    ssid_source: row.get(3)?,
+                    channel: row.get::<_, Option<i32>>(4)?.map(|c| c as u8),
+                    first_seen: row.get(5)?,
+                    last_seen: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(aps)
+    }
+
+    /// SSIDs this device probed for that an AP in range broadcast or answered to
+    pub fn get_nearby_ssids_for_device(&self, device_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT p.ssid FROM probes p
+             JOIN ap_ssids a ON a.ssid = p.ssid
+             WHERE p.device_id = ? AND p.ssid != ''
+             ORDER BY p.ssid"
+        )?;
+
+        let ssids = stmt
+            .query_map(params![device_id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        Ok(ssids)
+    }
+
+    /// Each SSID this device probed for, with how many distinct devices in
+    /// the database probe for it (the device itself included)
+    pub fn get_ssid_popularity_for_device(&self, device_id: i64) -> Result<Vec<(String, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT p.ssid, (SELECT COUNT(DISTINCT q.device_id) FROM probes q WHERE q.ssid = p.ssid)
+             FROM probes p
+             WHERE p.device_id = ? AND p.ssid != ''
+             GROUP BY p.ssid
+             ORDER BY p.ssid"
+        )?;
+
+        let popularity = stmt
+            .query_map(params![device_id], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(popularity)
+    }
+
+    /// APs that sent probe responses to `mac`, most recent first
+    pub fn get_probe_responders_for_device(&self, mac: &str) -> Result<Vec<ProbeResponder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT bssid, ssid, first_seen, last_seen, responses FROM probe_responses
+             WHERE device_mac = ?
+             ORDER BY last_seen DESC"
+        )?;
+
+        let responders = stmt
+            .query_map(params![mac], |row| {
+                Ok(ProbeResponder {
+                    bssid: row.get(0)?,
+                    ssid: row.get(1)?,
+                    first_seen: row.get(2)?,
+                    last_seen: row.get(3)?,
+                    responses: row.get::<_, i64>(4)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(responders)
+    }
+
+    /// APs that answered probes for at least `min_ssids` different SSIDs, as
+    /// (bssid, ssids) pairs. A real AP answers for one or two networks; a
+    /// karma-style rogue answers for whatever it is asked.
+    pub fn get_multi_ssid_responders(&self, min_ssids: usize) -> Result<Vec<(String, Vec<String>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT bssid, GROUP_CONCAT(ssid, char(31)) FROM ap_ssids
+             WHERE source = 'probe_response'
+             GROUP BY bssid HAVING COUNT(*) >= ?
+             ORDER BY COUNT(*) DESC"
+        )?;
+
+        let responders = stmt
+            .query_map(params![min_ssids as i64], |row| {
+                let ssids: String = row.get(1)?;
+                Ok((row.get(0)?, ssids.split('\u{1f}').map(str::to_string).collect()))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(responders)
+    }
+
+    /// Recovered SSIDs of hidden APs this device sent directed probes to,
+    /// as (bssid, ssid) pairs
+    pub fn get_hidden_ssids_for_device(&self, device_id: i64) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT ap.bssid, ap.ssid FROM probes p
+             JOIN access_points ap ON ap.bssid = p.bssid
+             WHERE p.device_id = ? AND ap.hidden = 1 AND ap.ssid IS NOT NULL
+             ORDER BY ap.bssid"
+        )?;
+
+        let pairs = stmt
+            .query_map(params![device_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(pairs)
+    }
+
+    /// Record a detection event
+    pub fn insert_event(&self, timestamp: i64, event_type: &str, message: &str, data: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO events (timestamp, event_type, message, data_json) VALUES (?, ?, ?, ?)",
+            params![timestamp, event_type, message, data],
+        )?;
+        Ok(())
+    }
+
+    /// Events since `since`, newest first, optionally of one type
+    pub fn get_events_since(&self, since: i64, event_type: Option<&str>) -> Result<Vec<Event>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, timestamp, event_type, message, data_json FROM events
+             WHERE timestamp >= ?1 AND (?2 IS NULL OR event_type = ?2)
+             ORDER BY timestamp DESC"
+        )?;
+
+        let events = stmt
+            .query_map(params![since, event_type], |row| {
+                Ok(Event {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    event_type: row.get(2)?,
+                    message: row.get(3)?,
+                    data_json: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    pub fn insert_deauth_event(&self, event: &DeauthEvent) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO deauth_events (timestamp, kind, source_mac, destination_mac, bssid, reason_code, signal_dbm, channel)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                event.timestamp,
+                event.kind.as_str(),
+                &event.source,
+                &event.destination,
+                &event.bssid,
+                event.reason_code,
+                event.signal_dbm,
+                event.channel.map(|c| c as i32),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn count_deauth_events(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM deauth_events", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Deauth and disassoc frames since `since`, newest first
+    pub fn get_deauth_events_since(&self, since: i64) -> Result<Vec<DeauthEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, kind, source_mac, destination_mac, bssid, reason_code, signal_dbm, channel
+             FROM deauth_events WHERE timestamp >= ?
+             ORDER BY timestamp DESC"
+        )?;
+
+        let events = stmt
+            .query_map(params![since], |row| {
+                let kind: String = row.get(1)?;
+                Ok(DeauthEvent {
+                    timestamp: row.get(0)?,
+                    kind: DeauthKind::from_db(&kind).unwrap_or(DeauthKind::Deauth),
+                    source: row.get(2)?,
+                    destination: row.get(3)?,
+                    bssid: row.get(4)?,
+                    reason_code: row.get(5)?,
+                    signal_dbm: row.get(6)?,
+                    channel: row.get::<_, Option<i32>>(7)?.map(|c| c as u8),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// Devices first seen within a time range
+    pub fn count_new_devices(&self, start: i64, end: i64) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM devices WHERE first_seen >= ? AND first_seen <= ?",
+            params![start, end],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// First-seen times of devices first seen within `[start, end)`
+    pub fn get_first_seen_times(&self, start: i64, end: i64) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT first_seen FROM devices WHERE first_seen >= ? AND first_seen < ?")?;
+        let times = stmt
+            .query_map(params![start, end], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(times)
+    }
+
+    /// Store imported location history points; points already stored are
+    /// skipped, so re-importing an export is harmless. Returns points added.
+    pub fn insert_location_points(&self, points: &[LocationPoint], source: &str) -> Result<usize> {
+        self.in_transaction(|db| {
+            let mut stmt = db.conn.prepare(
+                "INSERT OR IGNORE INTO location_history (timestamp, lat, lon, accuracy_m, source)
+                 VALUES (?, ?, ?, ?, ?)",
+            )?;
+            let mut added = 0;
+            for point in points {
+                added += stmt.execute(params![point.timestamp, point.lat, point.lon, point.accuracy_m, source])?;
+            }
+            Ok(added)
+        })
+    }
+
+    /// Imported location history within `[start, end]`, oldest first
+    pub fn get_location_history(&self, start: i64, end: i64) -> Result<Vec<LocationPoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, lat, lon, accuracy_m FROM location_history
+             WHERE timestamp >= ? AND timestamp <= ? ORDER BY timestamp ASC",
+        )?;
+        let points = stmt
+            .query_map(params![start, end], |row| {
+                Ok(LocationPoint {
+                    timestamp: row.get(0)?,
+                    lat: row.get(1)?,
+                    lon: row.get(2)?,
+                    accuracy_m: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(points)
+    }
+
+    /// (MAC, timestamp) of every probe within `[start, end]`
+    pub fn get_sighting_times(&self, start: i64, end: i64) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.mac, p.timestamp FROM probes p
+             JOIN devices d ON p.device_id = d.id
+             WHERE p.timestamp >= ? AND p.timestamp <= ?",
+        )?;
+        let sightings = stmt
+            .query_map(params![start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sightings)
+    }
+
+    /// Store the learned new-device baseline, replacing the previous one
+    pub fn set_anomaly_baseline(&self, baseline: &AnomalyBaseline) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO anomaly_baseline (id, mean, variance, buckets, bucket_secs, updated_at)
+             VALUES (1, ?, ?, ?, ?, ?)",
+            params![
+                baseline.mean,
+                baseline.variance,
+                baseline.buckets as i64,
+                baseline.bucket_secs,
+                baseline.updated_at
+            ],
+        )?;
         Ok(())
     }
 
+    pub fn get_anomaly_baseline(&self) -> Result<Option<AnomalyBaseline>> {
+        let baseline = self
+            .conn
+            .query_row(
+                "SELECT mean, variance, buckets, bucket_secs, updated_at FROM anomaly_baseline WHERE id = 1",
+                [],
+                |row| {
+                    Ok(AnomalyBaseline {
+                        mean: row.get(0)?,
+                        variance: row.get(1)?,
+                        buckets: row.get::<_, i64>(2)? as usize,
+                        bucket_secs: row.get(3)?,
+                        updated_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(baseline)
+    }
+
     /// Get the most recent capabilities for a device
     pub fn get_device_capabilities(&self, device_id: i64) -> Result<Option<ProbeCapabilities>> {
         let caps_json: Option<String> = self
@@ -207,6 +1488,102 @@ impl Database {
         }
     }
 
+    /// Latest fingerprint for a device, with the algorithm version behind it
+    pub fn get_device_fingerprint(&self, device_id: i64) -> Result<Option<(String, u32)>> {
+        self.conn
+            .query_row(
+                "SELECT pc.fingerprint, pc.fingerprint_version
+                 FROM probe_capabilities pc
+                 JOIN probes p ON pc.probe_id = p.id
+                 WHERE p.device_id = ? AND pc.fingerprint IS NOT NULL
+                 ORDER BY p.timestamp DESC
+                 LIMIT 1",
+                params![device_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Devices other than `mac` with a stored probe carrying fingerprint `fp`
+    /// from algorithm `version`
+    pub fn get_macs_with_fingerprint(&self, fp: &str, version: u32, mac: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT d.mac
+             FROM probe_capabilities pc
+             JOIN probes p ON pc.probe_id = p.id
+             JOIN devices d ON p.device_id = d.id
+             WHERE pc.fingerprint = ? AND pc.fingerprint_version = ? AND d.mac != ?
+             ORDER BY d.mac",
+        )?;
+        let macs = stmt
+            .query_map(params![fp, version, mac], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(macs)
+    }
+
+    /// Timestamp and sequence number of a device's first and last probes
+    /// that recorded one
+    pub fn get_device_sequence_span(&self, device_id: i64) -> Result<Option<((i64, u16), (i64, u16))>> {
+        let end = |order: &str| -> Result<Option<(i64, u16)>> {
+            let sql = format!(
+                "SELECT timestamp, sequence_number FROM probes
+                 WHERE device_id = ? AND sequence_number IS NOT NULL
+                 ORDER BY timestamp {0}, timestamp_micros {0} LIMIT 1",
+                order
+            );
+            self.conn
+                .query_row(&sql, params![device_id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()
+                .map_err(Into::into)
+        };
+        Ok(end("ASC")?.zip(end("DESC")?))
+    }
+
+    /// Stored fingerprints per algorithm version; None counts rows from
+    /// before fingerprints were recorded
+    pub fn count_fingerprints_by_version(&self) -> Result<Vec<(Option<u32>, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT fingerprint_version, COUNT(*) FROM probe_capabilities
+             GROUP BY fingerprint_version ORDER BY fingerprint_version"
+        )?;
+
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    }
+
+    /// Recompute fingerprints not produced by the current algorithm from the
+    /// stored capabilities. Returns the number of rows updated.
+    pub fn reprocess_fingerprints(&self) -> Result<usize> {
+        let algorithm = fingerprint::current();
+        let mut stmt = self.conn.prepare(
+            "SELECT id, capabilities_json FROM probe_capabilities
+             WHERE fingerprint_version IS NULL OR fingerprint_version != ?"
+        )?;
+        let stale = stmt
+            .query_map(params![algorithm.version()], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut updated = 0;
+        for (id, caps_json) in stale {
+            let caps: ProbeCapabilities = match serde_json::from_str(&caps_json) {
+                Ok(caps) => caps,
+                Err(_) => continue,
+            };
+            tx.execute(
+                "UPDATE probe_capabilities SET fingerprint = ?, fingerprint_version = ? WHERE id = ?",
+                params![algorithm.fingerprint(&caps), algorithm.version(), id],
+            )?;
+            updated += 1;
+        }
+        tx.commit()?;
+        Ok(updated)
+    }
+
     /// Get WiFi generation for a device
     pub fn get_device_wifi_generation(&self, device_id: i64) -> Result<Option<String>> {
         self.conn
@@ -224,6 +1601,29 @@ impl Database {
             .map_err(Into::into)
     }
 
+    /// Get the vendor attribution stored when the device was first seen.
+    /// Devices recorded before provenance tracking fall back to a fresh lookup.
+    pub fn get_device_vendor_attribution(&self, device_id: i64) -> Result<Option<VendorAttribution>> {
+        let row: Option<(String, Option<String>, Option<String>, Option<String>, Option<String>)> = self
+            .conn
+            .query_row(
+                "SELECT mac, vendor, vendor_oui, vendor_source, oui_db_version FROM devices WHERE id = ?",
+                params![device_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .optional()?;
+
+        Ok(row.and_then(|(mac, vendor, oui, source, version)| match (oui, source, version) {
+            (Some(oui), Some(source), Some(db_version)) => Some(VendorAttribution {
+                oui,
+                vendor,
+                source,
+                db_version,
+            }),
+            _ => attribute_vendor(&mac),
+        }))
+    }
+
     pub fn get_device_by_mac(&self, mac: &str) -> Result<Option<Device>> {
         let device = self.conn
             .query_row(
@@ -263,24 +1663,72 @@ impl Database {
         Ok(devices)
     }
 
+    /// Devices seen since `since` with their probe counts, SSIDs and latest
+    /// capabilities, most recently seen first
+    pub fn get_recent_devices(&self, since: i64) -> Result<Vec<DeviceHistory>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.id, d.mac, d.first_seen, d.last_seen,
+                    (SELECT COUNT(*) FROM probes p WHERE p.device_id = d.id),
+                    (SELECT p.signal_dbm FROM probes p WHERE p.device_id = d.id
+                     ORDER BY p.timestamp DESC LIMIT 1)
+             FROM devices d
+             WHERE d.last_seen >= ?
+             ORDER BY d.last_seen DESC"
+        )?;
+
+        let rows = stmt
+            .query_map(params![since], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, Option<i32>>(5)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(id, mac, first_seen, last_seen, probe_count, last_signal)| {
+                Ok(DeviceHistory {
+                    mac,
+                    first_seen,
+                    last_seen,
+                    probe_count: probe_count as usize,
+                    last_signal,
+                    ssids: self.get_unique_ssids_for_device(id)?,
+                    capabilities: self.get_device_capabilities(id)?,
+                })
+            })
+            .collect()
+    }
+
     pub fn get_probes_for_device(&self, device_id: i64) -> Result<Vec<Probe>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, device_id, ssid, timestamp, lat, lon, signal_dbm, channel, distance_m
+            "SELECT id, device_id, ssid, timestamp, lat, lon, signal_dbm, channel, distance_m, gps_status, bssid, timestamp_micros,
+                    sequence_number
              FROM probes WHERE device_id = ? ORDER BY timestamp DESC"
         )?;
 
         let probes = stmt
             .query_map(params![device_id], |row| {
+                let lat: Option<f64> = row.get(4)?;
+                let lon: Option<f64> = row.get(5)?;
                 Ok(Probe {
                     id: row.get(0)?,
                     device_id: row.get(1)?,
                     ssid: row.get(2)?,
                     timestamp: row.get(3)?,
-                    lat: row.get(4)?,
-                    lon: row.get(5)?,
+                    timestamp_micros: row.get(11)?,
+                    lat,
+                    lon,
                     signal_dbm: row.get(6)?,
                     channel: row.get::<_, Option<i32>>(7)?.map(|c| c as u8),
                     distance_m: row.get(8)?,
+                    gps_status: GpsStatus::from_db(row.get(9)?, lat.is_some() && lon.is_some()),
+                    bssid: row.get(10)?,
+                    sequence_number: row.get(12)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -290,23 +1738,30 @@ impl Database {
 
     pub fn get_probes_in_time_range(&self, start: i64, end: i64) -> Result<Vec<Probe>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, device_id, ssid, timestamp, lat, lon, signal_dbm, channel, distance_m
+            "SELECT id, device_id, ssid, timestamp, lat, lon, signal_dbm, channel, distance_m, gps_status, bssid, timestamp_micros,
+                    sequence_number
              FROM probes WHERE timestamp >= ? AND timestamp <= ?
              ORDER BY timestamp DESC"
         )?;
 
         let probes = stmt
             .query_map(params![start, end], |row| {
+                let lat: Option<f64> = row.get(4)?;
+                let lon: Option<f64> = row.get(5)?;
                 Ok(Probe {
                     id: row.get(0)?,
                     device_id: row.get(1)?,
                     ssid: row.get(2)?,
                     timestamp: row.get(3)?,
-                    lat: row.get(4)?,
-                    lon: row.get(5)?,
+                    timestamp_micros: row.get(11)?,
+                    lat,
+                    lon,
                     signal_dbm: row.get(6)?,
                     channel: row.get::<_, Option<i32>>(7)?.map(|c| c as u8),
                     distance_m: row.get(8)?,
+                    gps_status: GpsStatus::from_db(row.get(9)?, lat.is_some() && lon.is_some()),
+                    bssid: row.get(10)?,
+                    sequence_number: row.get(12)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -314,6 +1769,31 @@ impl Database {
         Ok(probes)
     }
 
+    /// Get MAC, SSID and WiFi generation for every probe in a time range
+    pub fn get_probe_observations(&self, start: i64, end: i64) -> Result<Vec<ProbeObservation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.mac, p.timestamp, p.ssid, pc.wifi_generation
+             FROM probes p
+             JOIN devices d ON p.device_id = d.id
+             LEFT JOIN probe_capabilities pc ON pc.probe_id = p.id
+             WHERE p.timestamp >= ? AND p.timestamp <= ?
+             ORDER BY p.timestamp ASC"
+        )?;
+
+        let observations = stmt
+            .query_map(params![start, end], |row| {
+                Ok(ProbeObservation {
+                    mac: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    ssid: row.get(2)?,
+                    wifi_generation: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(observations)
+    }
+
     pub fn get_unique_ssids_for_device(&self, device_id: i64) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
             "SELECT DISTINCT ssid FROM probes WHERE device_id = ? AND ssid != ''"
@@ -326,6 +1806,30 @@ impl Database {
         Ok(ssids)
     }
 
+    /// Whether an `event_type` event was recorded whose data names `mac` and
+    /// `ssid`
+    pub fn has_device_ssid_event(&self, event_type: &str, mac: &str, ssid: &str) -> Result<bool> {
+        let found = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM events WHERE event_type = ?
+                                AND json_extract(data_json, '$.mac') = ?
+                                AND json_extract(data_json, '$.ssid') = ?)",
+            params![event_type, mac, ssid],
+            |row| row.get(0),
+        )?;
+        Ok(found)
+    }
+
+    /// Probes stored from `mac` for `ssid`
+    pub fn count_device_ssid_probes(&self, mac: &str, ssid: &str) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM probes p JOIN devices d ON p.device_id = d.id
+             WHERE d.mac = ? AND p.ssid = ?",
+            params![mac, ssid],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
     pub fn get_device_location_count(&self, device_id: i64) -> Result<usize> {
         let count: i64 = self.conn.query_row(
             "SELECT COUNT(DISTINCT CAST(lat * 1000 AS INTEGER) || ',' || CAST(lon * 1000 AS INTEGER))
@@ -345,6 +1849,35 @@ impl Database {
         Ok(count as usize)
     }
 
+    /// Count probes by GPS status, optionally for a single device
+    pub fn get_gps_fix_stats(&self, device_id: Option<i64>) -> Result<GpsFixStats> {
+        let mut stmt = self.conn.prepare(
+            "SELECT CASE
+                 WHEN gps_status IS NOT NULL THEN gps_status
+                 WHEN lat IS NOT NULL AND lon IS NOT NULL THEN 'fix'
+                 ELSE 'unknown'
+             END AS status, COUNT(*)
+             FROM probes WHERE ?1 IS NULL OR device_id = ?1
+             GROUP BY status"
+        )?;
+
+        let mut stats = GpsFixStats::default();
+        let rows = stmt.query_map(params![device_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+        for row in rows {
+            let (status, count) = row?;
+            match status.as_str() {
+                "fix" => stats.fix += count,
+                "no_fix" => stats.no_fix += count,
+                "disabled" => stats.disabled += count,
+                _ => stats.unknown += count,
+            }
+        }
+
+        Ok(stats)
+    }
+
     pub fn count_probes(&self) -> Result<usize> {
         let count: i64 = self.conn.query_row(
             "SELECT COUNT(*) FROM probes",
@@ -354,22 +1887,1312 @@ impl Database {
         Ok(count as usize)
     }
 
-    pub fn get_all_devices(&self) -> Result<Vec<Device>> {
+    /// Devices present during the range, by vendor ("Unknown" when unresolved)
+    pub fn get_vendor_counts(&self, start: i64, end: i64) -> Result<Vec<(String, usize)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, mac, first_seen, last_seen FROM devices ORDER BY last_seen DESC"
+            "SELECT COALESCE(vendor, 'Unknown'), COUNT(*) FROM devices
+             WHERE last_seen >= ? AND first_seen <= ?
+             GROUP BY 1 ORDER BY 2 DESC"
         )?;
+        let counts = stmt
+            .query_map(params![start, end], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
 
-        let devices = stmt
+    /// Every directed SSID probed for, most widely probed first
+    pub fn get_ssid_summaries(&self) -> Result<Vec<SsidSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT ssid, COUNT(DISTINCT device_id), COUNT(*), MIN(timestamp), MAX(timestamp)
+             FROM probes
+             WHERE ssid != ''
+             GROUP BY ssid
+             ORDER BY 2 DESC, ssid"
+        )?;
+        let summaries = stmt
             .query_map([], |row| {
-                Ok(Device {
-                    id: row.get(0)?,
-                    mac: row.get(1)?,
-                    first_seen: row.get(2)?,
-                    last_seen: row.get(3)?,
+                Ok(SsidSummary {
+                    ssid: row.get(0)?,
+                    devices: row.get::<_, i64>(1)? as usize,
+                    probes: row.get::<_, i64>(2)? as usize,
+                    first_seen: row.get(3)?,
+                    last_seen: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
+        Ok(summaries)
+    }
 
-        Ok(devices)
+    /// Device counts per vendor with how many of each use randomized MACs,
+    /// largest first
+    pub fn get_vendor_summaries(&self) -> Result<Vec<VendorSummary>> {
+        let mut stmt = self.conn.prepare("SELECT mac, COALESCE(vendor, 'Unknown') FROM devices")?;
+        let mut by_vendor: HashMap<String, VendorSummary> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (mac, vendor) = row?;
+            let summary = by_vendor.entry(vendor.clone()).or_insert(VendorSummary {
+                vendor,
+                devices: 0,
+                randomized: 0,
+            });
+            summary.devices += 1;
+            if is_randomized_mac(&mac) {
+                summary.randomized += 1;
+            }
+        }
+
+        let mut summaries: Vec<VendorSummary> = by_vendor.into_values().collect();
+        summaries.sort_by(|a, b| b.devices.cmp(&a.devices).then_with(|| a.vendor.cmp(&b.vendor)));
+        Ok(summaries)
+    }
+
+    /// MACs with at least one probe in the range
+    pub fn get_macs_in_time_range(&self, start: i64, end: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT d.mac FROM probes p JOIN devices d ON d.id = p.device_id
+             WHERE p.timestamp >= ? AND p.timestamp <= ?
+             ORDER BY d.mac"
+        )?;
+        let macs = stmt
+            .query_map(params![start, end], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(macs)
+    }
+
+    /// Count probes the rate limit kept out of the database for `mac`
+    pub fn add_suppressed_probes(&self, mac: &str, count: u64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE devices SET suppressed_probes = suppressed_probes + ? WHERE mac = ?",
+            params![count as i64, mac],
+        )?;
+        Ok(())
+    }
+
+    /// Probes suppressed by the rate limit across all devices
+    pub fn count_suppressed_probes(&self) -> Result<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(suppressed_probes), 0) FROM devices",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as u64)
+    }
+
+    pub fn count_probes_in_range(&self, start: i64, end: i64) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM probes WHERE timestamp >= ? AND timestamp <= ?",
+            params![start, end],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Devices that sent at least one probe within `[start, end]`
+    pub fn count_active_devices(&self, start: i64, end: i64) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(DISTINCT device_id) FROM probes WHERE timestamp >= ? AND timestamp <= ?",
+            params![start, end],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Known vendors of the devices that probed within `[start, end]`
+    pub fn get_active_vendors(&self, start: i64, end: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT d.vendor FROM probes p JOIN devices d ON p.device_id = d.id
+             WHERE p.timestamp >= ? AND p.timestamp <= ? AND d.vendor IS NOT NULL
+             ORDER BY 1"
+        )?;
+        let vendors = stmt
+            .query_map(params![start, end], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(vendors)
+    }
+
+    pub fn get_all_devices(&self) -> Result<Vec<Device>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, mac, first_seen, last_seen FROM devices ORDER BY last_seen DESC"
+        )?;
+
+        let devices = stmt
+            .query_map([], |row| {
+                Ok(Device {
+                    id: row.get(0)?,
+                    mac: row.get(1)?,
+                    first_seen: row.get(2)?,
+                    last_seen: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(devices)
+    }
+
+    /// Delete everything stored about a MAC: the device, its probes and what
+    /// is kept per MAC for it, the probe responses and deauth frames it sent
+    /// or received, case items about it and events that name it. With
+    /// `dry_run` the deletion is rolled back and only the counts are
+    /// returned.
+    pub fn prune_mac(&self, mac: &str, dry_run: bool) -> Result<PruneSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let capabilities = tx.execute(
+            "DELETE FROM probe_capabilities WHERE probe_id IN
+             (SELECT p.id FROM probes p JOIN devices d ON p.device_id = d.id WHERE d.mac = ?)",
+            params![mac],
+        )?;
+        let probes = tx.execute(
+            "DELETE FROM probes WHERE device_id IN (SELECT id FROM devices WHERE mac = ?)",
+            params![mac],
+        )?;
+        let (devices, device_records) = self.delete_devices(&[mac.to_string()])?;
+        let probe_responses = tx.execute("DELETE FROM probe_responses WHERE device_mac = ?", params![mac])?;
+        let deauth_frames = tx.execute(
+            "DELETE FROM deauth_events WHERE source_mac = ?1 OR destination_mac = ?1",
+            params![mac],
+        )?;
+        let case_items = tx.execute("DELETE FROM case_items WHERE mac = ?", params![mac])?;
+        // Alerts keep the MAC in their message or data
+        let events = tx.execute(
+            "DELETE FROM events WHERE message LIKE ?1 OR data_json LIKE ?1",
+            params![format!("%{}%", mac)],
+        )?;
+
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+
+        Ok(PruneSummary {
+            devices,
+            probes,
+            capabilities,
+            device_records,
+            probe_responses,
+            deauth_frames,
+            case_items,
+            events,
+        })
+    }
+
+    /// MACs of the devices matching `condition`
+    fn device_macs_where<P: rusqlite::Params>(&self, condition: &str, params: P) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT mac FROM devices WHERE {}", condition))?;
+        let macs = stmt.query_map(params, |row| row.get(0))?.collect::<Result<Vec<_>, _>>()?;
+        Ok(macs)
+    }
+
+    /// Delete the devices with these MACs along with what is kept per MAC
+    /// for them: labels, links, watch list entries, risk and residency.
+    /// Returns the devices and the other rows deleted.
+    fn delete_devices(&self, macs: &[String]) -> Result<(usize, usize)> {
+        let (mut devices, mut records) = (0, 0);
+        for mac in macs {
+            devices += self.conn.execute("DELETE FROM devices WHERE mac = ?", params![mac])?;
+            for table in ["device_risk", "device_residency", "device_labels", "watchlist"] {
+                records += self.conn.execute(&format!("DELETE FROM {} WHERE mac = ?", table), params![mac])?;
+            }
+            records +=
+                self.conn.execute("DELETE FROM device_links WHERE mac_a = ?1 OR mac_b = ?1", params![mac])?;
+        }
+        Ok((devices, records))
+    }
+
+    /// Create a case. Fails if the name is already taken.
+    pub fn create_case(&self, name: &str, description: Option<&str>, created_at: i64) -> Result<i64> {
+        self.conn
+            .execute(
+                "INSERT INTO cases (name, description, created_at) VALUES (?, ?, ?)",
+                params![name, description, created_at],
+            )
+            .with_context(|| format!("Failed to create case {:?}", name))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_case(&self, name: &str) -> Result<Option<Case>> {
+        let case = self.conn
+            .query_row(
+                "SELECT id, name, description, created_at FROM cases WHERE name = ?",
+                params![name],
+                |row| {
+                    Ok(Case {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        description: row.get(2)?,
+                        created_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(case)
+    }
+
+    /// All cases with their item counts, newest first
+    pub fn list_cases(&self) -> Result<Vec<(Case, usize)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.id, c.name, c.description, c.created_at, COUNT(i.id)
+             FROM cases c LEFT JOIN case_items i ON i.case_id = c.id
+             GROUP BY c.id ORDER BY c.created_at DESC",
+        )?;
+
+        let cases = stmt
+            .query_map([], |row| {
+                Ok((
+                    Case {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        description: row.get(2)?,
+                        created_at: row.get(3)?,
+                    },
+                    row.get::<_, i64>(4)? as usize,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(cases)
+    }
+
+    pub fn add_case_item(&self, case_id: i64, item: &CaseItem) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO case_items (case_id, kind, mac, start_ts, end_ts, note, data_json, added_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                case_id,
+                item.kind.as_str(),
+                item.mac,
+                item.start,
+                item.end,
+                item.note,
+                item.data_json,
+                item.added_at,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Items of a case in the order they were added
+    pub fn get_case_items(&self, case_id: i64) -> Result<Vec<CaseItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, kind, mac, start_ts, end_ts, note, data_json, added_at
+             FROM case_items WHERE case_id = ? ORDER BY id",
+        )?;
+
+        let items = stmt
+            .query_map(params![case_id], |row| {
+                let kind: String = row.get(1)?;
+                Ok(CaseItem {
+                    id: row.get(0)?,
+                    // Kinds added by newer versions read back as notes
+                    kind: CaseItemKind::from_db(&kind).unwrap_or(CaseItemKind::Note),
+                    mac: row.get(2)?,
+                    start: row.get(3)?,
+                    end: row.get(4)?,
+                    note: row.get(5)?,
+                    data_json: row.get(6)?,
+                    added_at: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(items)
+    }
+
+    /// Delete every probe for an SSID, plus the devices that probed for it
+    /// and are left with no probes, along with what is kept per MAC for
+    /// them. With `dry_run` the deletion is rolled back and only the counts
+    /// are returned.
+    pub fn prune_ssid(&self, ssid: &str, dry_run: bool) -> Result<PruneSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        // Devices whose probes were purged earlier didn't probe for it here
+        let only_this_ssid = self.device_macs_where(
+            "id IN (SELECT device_id FROM probes WHERE ssid = ?1)
+             AND id NOT IN (SELECT device_id FROM probes WHERE ssid != ?1)",
+            params![ssid],
+        )?;
+        let capabilities = tx.execute(
+            "DELETE FROM probe_capabilities WHERE probe_id IN (SELECT id FROM probes WHERE ssid = ?)",
+            params![ssid],
+        )?;
+        let probes = tx.execute("DELETE FROM probes WHERE ssid = ?", params![ssid])?;
+        let (devices, device_records) = self.delete_devices(&only_this_ssid)?;
+
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+
+        Ok(PruneSummary {
+            devices,
+            probes,
+            capabilities,
+            device_records,
+            ..Default::default()
+        })
+    }
+
+    /// Roll probes from before `cutoff` up into hourly counts, then delete
+    /// them with the events, probe responses and deauth frames of the same
+    /// age, and the devices left without probes along with their labels,
+    /// links, watch list entries, risk and residency. Devices attached to a
+    /// case are kept, as are events of the `keep_events` types, cases and
+    /// access points. Probes imported late for an hour already rolled up are
+    /// added to its counts. `cutoff` should fall on an hour so no hour is
+    /// rolled up half-purged.
+    pub fn purge_raw_before(&self, cutoff: i64, keep_events: &[&str]) -> Result<PurgeSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let hours_rolled_up = tx.execute(
+            "INSERT INTO probe_rollups
+                 (hour_start, devices, new_devices, randomized_devices, probes, directed_ssids)
+             SELECT p.timestamp / 3600 * 3600 AS hour,
+                    COUNT(DISTINCT p.device_id),
+                    COUNT(DISTINCT CASE WHEN d.first_seen / 3600 * 3600 = p.timestamp / 3600 * 3600
+                                        THEN p.device_id END),
+                    COUNT(DISTINCT CASE WHEN upper(substr(d.mac, 2, 1)) IN ('2', '6', 'A', 'E')
+                                        THEN p.device_id END),
+                    COUNT(*),
+                    COUNT(DISTINCT NULLIF(p.ssid, ''))
+             FROM probes p JOIN devices d ON p.device_id = d.id
+             WHERE p.timestamp < ?
+             GROUP BY hour
+             ON CONFLICT(hour_start) DO UPDATE SET
+                 devices = devices + excluded.devices,
+                 new_devices = new_devices + excluded.new_devices,
+                 randomized_devices = randomized_devices + excluded.randomized_devices,
+                 probes = probes + excluded.probes,
+                 directed_ssids = directed_ssids + excluded.directed_ssids",
+            params![cutoff],
+        )?;
+
+        tx.execute(
+            "DELETE FROM probe_capabilities WHERE probe_id IN (SELECT id FROM probes WHERE timestamp < ?)",
+            params![cutoff],
+        )?;
+        let probes = tx.execute("DELETE FROM probes WHERE timestamp < ?", params![cutoff])?;
+        let orphaned = self.device_macs_where(
+            "id NOT IN (SELECT DISTINCT device_id FROM probes)
+             AND mac NOT IN (SELECT mac FROM case_items WHERE mac IS NOT NULL)",
+            [],
+        )?;
+        let (devices, _) = self.delete_devices(&orphaned)?;
+        let kept = vec!["?"; keep_events.len()].join(", ");
+        let mut values = vec![Value::from(cutoff)];
+        values.extend(keep_events.iter().map(|t| Value::from(t.to_string())));
+        let events = tx.execute(
+            &format!("DELETE FROM events WHERE timestamp < ? AND event_type NOT IN ({})", kept),
+            params_from_iter(values),
+        )?;
+        let other = tx.execute("DELETE FROM probe_responses WHERE last_seen < ?", params![cutoff])?
+            + tx.execute("DELETE FROM deauth_events WHERE timestamp < ?", params![cutoff])?;
+
+        tx.commit()?;
+        Ok(PurgeSummary {
+            hours_rolled_up,
+            probes,
+            devices,
+            events,
+            other,
+        })
+    }
+
+    /// Hourly rollups for hours starting in `start..end`, oldest first
+    pub fn get_probe_rollups(&self, start: i64, end: i64) -> Result<Vec<ProbeRollup>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT hour_start, devices, new_devices, randomized_devices, probes, directed_ssids
+             FROM probe_rollups WHERE hour_start >= ? AND hour_start < ? ORDER BY hour_start",
+        )?;
+        let rollups = stmt
+            .query_map(params![start, end], |row| {
+                Ok(ProbeRollup {
+                    hour_start: row.get(0)?,
+                    devices: row.get::<_, i64>(1)? as usize,
+                    new_devices: row.get::<_, i64>(2)? as usize,
+                    randomized_devices: row.get::<_, i64>(3)? as usize,
+                    probes: row.get::<_, i64>(4)? as usize,
+                    directed_ssids: row.get::<_, i64>(5)? as usize,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rollups)
+    }
+
+    /// Set or replace the free-text label for a device
+    pub fn set_device_label(&self, mac: &str, label: &str, now: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO device_labels (mac, label, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(mac) DO UPDATE SET label = excluded.label, updated_at = excluded.updated_at",
+            params![mac, label, now],
+        )?;
+        Ok(())
+    }
+
+    /// Every label, keyed by MAC
+    pub fn get_device_labels(&self) -> Result<HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT mac, label FROM device_labels")?;
+        let labels = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(labels)
+    }
+
+    /// Record a confirmed or rejected link between two MACs, replacing any
+    /// earlier verdict on the pair
+    pub fn set_device_link(&self, link: &DeviceLinkRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO device_links (mac_a, mac_b, verdict, confidence, evidence_json, note, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(mac_a, mac_b) DO UPDATE SET verdict = excluded.verdict,
+                 confidence = excluded.confidence, evidence_json = excluded.evidence_json,
+                 note = excluded.note, updated_at = excluded.updated_at",
+            params![
+                link.mac_a,
+                link.mac_b,
+                link.verdict,
+                link.confidence,
+                link.evidence_json,
+                link.note,
+                link.updated_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the verdict on a pair. Returns false if there was none.
+    pub fn clear_device_link(&self, mac_a: &str, mac_b: &str) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM device_links WHERE mac_a = ? AND mac_b = ?",
+            params![mac_a, mac_b],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Verdicts on every link involving `mac`
+    pub fn get_device_links(&self, mac: &str) -> Result<Vec<DeviceLinkRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT mac_a, mac_b, verdict, confidence, evidence_json, note, updated_at
+             FROM device_links WHERE mac_a = ?1 OR mac_b = ?1 ORDER BY updated_at",
+        )?;
+        let links = stmt
+            .query_map(params![mac], |row| {
+                Ok(DeviceLinkRecord {
+                    mac_a: row.get(0)?,
+                    mac_b: row.get(1)?,
+                    verdict: row.get(2)?,
+                    confidence: row.get(3)?,
+                    evidence_json: row.get(4)?,
+                    note: row.get(5)?,
+                    updated_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(links)
+    }
+
+    /// Add a device to the watch list. Returns false if it was already on it.
+    pub fn add_to_watchlist(&self, mac: &str, now: i64) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO watchlist (mac, added_at) VALUES (?, ?)",
+            params![mac, now],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_watchlist(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT mac FROM watchlist ORDER BY added_at")?;
+        let macs = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(macs)
+    }
+
+    /// Distinct UTC days each device was heard on, over its whole history
+    pub fn get_device_days(&self) -> Result<Vec<DeviceDays>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.mac, MIN(p.timestamp), MAX(p.timestamp), COUNT(DISTINCT p.timestamp / 86400)
+             FROM devices d
+             JOIN probes p ON p.device_id = d.id
+             GROUP BY d.id",
+        )?;
+        let days = stmt
+            .query_map([], |row| {
+                Ok(DeviceDays {
+                    mac: row.get(0)?,
+                    first_seen: row.get(1)?,
+                    last_seen: row.get(2)?,
+                    days_seen: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(days)
+    }
+
+    pub fn set_residency(&self, record: &ResidencyRecord) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO device_residency (mac, class, days_seen, first_seen, resident_since, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(mac) DO UPDATE SET class = excluded.class, days_seen = excluded.days_seen,
+                 first_seen = excluded.first_seen, resident_since = excluded.resident_since,
+                 updated_at = excluded.updated_at",
+            params![
+                &record.mac,
+                record.class.as_str(),
+                record.days_seen,
+                record.first_seen,
+                record.resident_since,
+                record.updated_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stored residency class of every classified device, keyed by MAC
+    pub fn get_residency(&self) -> Result<HashMap<String, ResidencyRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT mac, class, days_seen, first_seen, resident_since, updated_at FROM device_residency",
+        )?;
+        let rows = stmt
+            .query_map([], residency_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows.into_iter().flatten().map(|r| (r.mac.clone(), r)).collect())
+    }
+
+    /// Replace the live risk scores with `scores`; devices left out lose theirs
+    pub fn set_risk_scores(&self, scores: &[(String, f64)], updated_at: i64) -> Result<()> {
+        self.in_transaction(|db| {
+            db.conn.execute("DELETE FROM device_risk", [])?;
+            let mut stmt = db.conn.prepare("INSERT INTO device_risk (mac, score, updated_at) VALUES (?, ?, ?)")?;
+            for (mac, score) in scores {
+                stmt.execute(params![mac, score, updated_at])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Live risk score of every recently seen device, keyed by MAC
+    pub fn get_risk_scores(&self) -> Result<HashMap<String, f64>> {
+        let mut stmt = self.conn.prepare("SELECT mac, score FROM device_risk")?;
+        let scores = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(scores)
+    }
+
+    pub fn get_device_residency(&self, mac: &str) -> Result<Option<ResidencyRecord>> {
+        let record = self
+            .conn
+            .query_row(
+                "SELECT mac, class, days_seen, first_seen, resident_since, updated_at
+                 FROM device_residency WHERE mac = ?",
+                params![mac],
+                residency_from_row,
+            )
+            .optional()?;
+        Ok(record.flatten())
+    }
+
+    /// Record the start of a capture session on `adapter`. Probes inserted
+    /// through this connection from now on are tagged with it, and the
+    /// first geotagged one sets its start point.
+    pub fn start_session(
+        &mut self,
+        adapter: &AdapterInfo,
+        backend: &str,
+        channels: &str,
+        config_json: Option<&str>,
+        started_at: i64,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO sessions (started_at, interface, driver, adapter_mac, backend, channels, config_json)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                started_at,
+                &adapter.interface,
+                adapter.driver.as_deref(),
+                adapter.mac.as_deref(),
+                backend,
+                channels,
+                config_json
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.session_id = Some(id);
+        self.session_unlocated.set(true);
+        Ok(id)
+    }
+
+    /// Close the session started on this connection, if any
+    pub fn end_session(&mut self, ended_at: i64) -> Result<()> {
+        self.session_unlocated.set(false);
+        if let Some(id) = self.session_id.take() {
+            self.conn.execute(
+                "UPDATE sessions SET ended_at = ? WHERE id = ?",
+                params![ended_at, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Every capture session with its probe count, newest first
+    pub fn get_sessions(&self) -> Result<Vec<Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.id, s.started_at, s.ended_at, s.interface, s.driver, s.adapter_mac, s.backend,
+                    (SELECT COUNT(*) FROM probes p WHERE p.session_id = s.id),
+                    s.channels, s.start_lat, s.start_lon, s.config_json
+             FROM sessions s
+             ORDER BY s.started_at DESC, s.id DESC",
+        )?;
+        let sessions = stmt
+            .query_map([], |row| {
+                Ok(Session {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    ended_at: row.get(2)?,
+                    interface: row.get(3)?,
+                    driver: row.get(4)?,
+                    adapter_mac: row.get(5)?,
+                    backend: row.get(6)?,
+                    channels: row.get(8)?,
+                    start_lat: row.get(9)?,
+                    start_lon: row.get(10)?,
+                    config_json: row.get(11)?,
+                    probes: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    /// The most recently started capture session
+    pub fn latest_session_id(&self) -> Result<Option<i64>> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT id FROM sessions ORDER BY started_at DESC, id DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// Add usage measured since the last flush to this connection's session
+    /// (session 0 outside one)
+    pub fn add_channel_usage(&self, usage: &[ChannelUsage]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO channel_utilization (session_id, channel, frames, airtime_us, dwell_us)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(session_id, channel) DO UPDATE SET
+                frames = frames + excluded.frames,
+                airtime_us = airtime_us + excluded.airtime_us,
+                dwell_us = dwell_us + excluded.dwell_us",
+        )?;
+        let session_id = self.session_id.unwrap_or(0);
+        for u in usage {
+            stmt.execute(params![
+                session_id,
+                u.channel as i64,
+                u.frames as i64,
+                u.airtime_us as i64,
+                u.dwell_us as i64
+            ])?;
+        }
+        Ok(())
+    }
+
+    /// Usage per channel for one session, or summed over all of them
+    pub fn get_channel_usage(&self, session_id: Option<i64>) -> Result<Vec<ChannelUsage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT channel, SUM(frames), SUM(airtime_us), SUM(dwell_us)
+             FROM channel_utilization
+             WHERE ?1 IS NULL OR session_id = ?1
+             GROUP BY channel
+             ORDER BY channel",
+        )?;
+        let usage = stmt
+            .query_map(params![session_id], |row| {
+                Ok(ChannelUsage {
+                    channel: row.get::<_, i64>(0)? as u8,
+                    frames: row.get::<_, i64>(1)? as u64,
+                    airtime_us: row.get::<_, i64>(2)? as u64,
+                    dwell_us: row.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(usage)
+    }
+}
+
+/// Rows with a class this version doesn't know come back as None
+fn residency_from_row(row: &rusqlite::Row) -> rusqlite::Result<Option<ResidencyRecord>> {
+    let class: String = row.get(1)?;
+    let class = match Residency::parse(&class) {
+        Some(class) => class,
+        None => return Ok(None),
+    };
+    Ok(Some(ResidencyRecord {
+        mac: row.get(0)?,
+        class,
+        days_seen: row.get(2)?,
+        first_seen: row.get(3)?,
+        resident_since: row.get(4)?,
+        updated_at: row.get(5)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_mac_dry_run_and_commit() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100)).unwrap());
+        assert!(!db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Work", 200)).unwrap());
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", 300)).unwrap();
+
+        let dry = db.prune_mac("AA:BB:CC:DD:EE:01", true).unwrap();
+        assert_eq!((dry.devices, dry.probes), (1, 2));
+        assert_eq!(db.count_probes().unwrap(), 3);
+
+        db.prune_mac("AA:BB:CC:DD:EE:01", false).unwrap();
+        assert_eq!(db.count_devices().unwrap(), 1);
+        assert_eq!(db.count_probes().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_prune_mac_erases_everything_naming_it() {
+        let db = Database::open_in_memory().unwrap();
+        let (mac, other) = ("AA:BB:CC:DD:EE:01", "AA:BB:CC:DD:EE:02");
+        db.insert_probe(&ProbeCapture::test(mac, "Home", 100)).unwrap();
+        db.insert_probe(&ProbeCapture::test(other, "Home", 100)).unwrap();
+        db.set_device_label(mac, "neighbour", 100).unwrap();
+        db.add_to_watchlist(mac, 100).unwrap();
+        db.add_to_watchlist(other, 100).unwrap();
+        db.set_device_link(&DeviceLinkRecord {
+            mac_a: mac.to_string(),
+            mac_b: other.to_string(),
+            verdict: "rejected".to_string(),
+            confidence: 0.4,
+            evidence_json: "{}".to_string(),
+            note: None,
+            updated_at: 100,
+        })
+        .unwrap();
+        let case = db.create_case("stalker", None, 100).unwrap();
+        db.add_case_item(case, &CaseItem::device(mac, 100)).unwrap();
+        let data = format!("{{\"mac\":\"{}\"}}", mac);
+        db.insert_event(100, "probe_burst", "burst", Some(&data)).unwrap();
+        db.insert_event(100, "queue_overflow", "dropped 3", None).unwrap();
+
+        let dry = db.prune_mac(mac, true).unwrap();
+        let expected = PruneSummary {
+            devices: 1,
+            probes: 1,
+            capabilities: 0,
+            device_records: 3,
+            probe_responses: 0,
+            deauth_frames: 0,
+            case_items: 1,
+            events: 1,
+        };
+        assert_eq!(dry, expected);
+        assert_eq!(db.get_watchlist().unwrap().len(), 2);
+
+        assert_eq!(db.prune_mac(mac, false).unwrap(), expected);
+        assert!(db.get_device_labels().unwrap().is_empty());
+        assert_eq!(db.get_watchlist().unwrap(), vec![other.to_string()]);
+        assert!(db.get_device_links(other).unwrap().is_empty());
+        assert!(db.get_case_items(case).unwrap().is_empty());
+        assert_eq!(db.get_events_since(0, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_ssid_removes_orphaned_devices() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", 200)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Cafe", 300)).unwrap();
+
+        // Kept by its case after retention purged its probes; it never
+        // probed for Home
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:03", "Cafe", 50)).unwrap();
+        let case = db.create_case("stalker", None, 0).unwrap();
+        db.add_case_item(case, &CaseItem::device("AA:BB:CC:DD:EE:03", 0)).unwrap();
+        db.purge_raw_before(60, &[]).unwrap();
+        db.set_device_label("AA:BB:CC:DD:EE:01", "visitor", 100).unwrap();
+
+        let summary = db.prune_ssid("Home", false).unwrap();
+        assert_eq!(summary.probes, 2);
+        assert_eq!(summary.devices, 1);
+        assert_eq!(summary.device_records, 1);
+        assert_eq!(db.count_devices().unwrap(), 2);
+        assert!(db.get_device_by_mac("AA:BB:CC:DD:EE:03").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_gps_fix_stats() {
+        let db = Database::open_in_memory().unwrap();
+        let mut fixed = ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100);
+        fixed.lat = Some(51.5);
+        fixed.lon = Some(-0.1);
+        fixed.gps_status = GpsStatus::Fix;
+        db.insert_probe(&fixed).unwrap();
+
+        let mut no_fix = ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 200);
+        no_fix.gps_status = GpsStatus::NoFix;
+        db.insert_probe(&no_fix).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", 300)).unwrap();
+
+        let stats = db.get_gps_fix_stats(None).unwrap();
+        assert_eq!((stats.fix, stats.no_fix, stats.disabled), (1, 1, 1));
+        assert_eq!(stats.fix_rate(), Some(0.5));
+
+        let probes = db.get_probes_for_device(1).unwrap();
+        assert!(probes.iter().any(|p| p.gps_status == Some(GpsStatus::NoFix)));
+    }
+
+    fn beacon(bssid: &str, ssid: &str, timestamp: i64) -> BeaconCapture {
+        BeaconCapture {
+            bssid: bssid.to_string(),
+            ssid: ssid.to_string(),
+            hidden: ssid.is_empty(),
+            channel: Some(6),
+            timestamp,
+            probe_response: false,
+            destination: None,
+        }
+    }
+
+    #[test]
+    fn test_hidden_ssid_recovered_from_directed_probe() {
+        let db = Database::open_in_memory().unwrap();
+        let hidden = "00:11:22:33:44:55";
+
+        // Probe before the beacon, then probe after it
+        let mut early = ProbeCapture::test("AA:BB:CC:DD:EE:01", "SecretNet", 100);
+        early.bssid = Some(hidden.to_string());
+        db.insert_probe(&early).unwrap();
+        db.upsert_access_point(&beacon(hidden, "", 150)).unwrap();
+        db.upsert_access_point(&beacon("00:11:22:33:44:66", "Visible", 150)).unwrap();
+
+        let aps = db.get_access_points().unwrap();
+        let ap = aps.iter().find(|a| a.bssid == hidden).unwrap();
+        assert_eq!(ap.ssid.as_deref(), Some("SecretNet"));
+        assert_eq!(ap.ssid_source.as_deref(), Some("directed_probe"));
+
+        // Later hidden beacons don't wipe the recovered name
+        db.upsert_access_point(&beacon(hidden, "", 200)).unwrap();
+
+        // A device probing the BSSID with a wildcard SSID gets the name too
+        let mut wildcard = ProbeCapture::test("AA:BB:CC:DD:EE:02", "", 250);
+        wildcard.bssid = Some(hidden.to_string());
+        db.insert_probe(&wildcard).unwrap();
+        let device = db.get_device_by_mac("AA:BB:CC:DD:EE:02").unwrap().unwrap();
+        assert_eq!(
+            db.get_hidden_ssids_for_device(device.id).unwrap(),
+            vec![(hidden.to_string(), "SecretNet".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_directed_probe_bssid_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        let mut directed = ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100);
+        directed.bssid = Some("00:11:22:33:44:55".to_string());
+        db.insert_probe(&directed).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "", 200)).unwrap();
+
+        let probes = db.get_probes_for_device(1).unwrap();
+        assert_eq!(probes[0].bssid, None);
+        assert_eq!(probes[1].bssid.as_deref(), Some("00:11:22:33:44:55"));
+    }
+
+    #[test]
+    fn test_reporting_views() {
+        let db = Database::open_in_memory().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let hour = (now / 3600) * 3600;
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", hour)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Work", hour + 1)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", hour - 7 * 86400)).unwrap();
+        db.insert_event(now, "new_device_spike", "spike", None).unwrap();
+
+        let (mac, probes, ssids): (String, i64, i64) = db
+            .conn
+            .query_row("SELECT mac, probe_count, ssid_count FROM devices_recent", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((mac.as_str(), probes, ssids), ("AA:BB:CC:DD:EE:01", 2, 2));
+
+        let (probes, devices): (i64, i64) = db
+            .conn
+            .query_row("SELECT probes, devices FROM probes_per_hour WHERE hour = ?", params![hour], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((probes, devices), (2, 1));
+
+        let alerts: i64 = db.conn.query_row("SELECT COUNT(*) FROM alerts_active", [], |row| row.get(0)).unwrap();
+        assert_eq!(alerts, 1);
+
+        let vendors: i64 = db.conn.query_row("SELECT SUM(devices) FROM vendor_counts", [], |row| row.get(0)).unwrap();
+        assert_eq!(vendors, 2);
+    }
+
+    #[test]
+    fn test_probe_responses_name_but_do_not_unhide() {
+        let db = Database::open_in_memory().unwrap();
+        let hidden = "00:11:22:33:44:55";
+        db.upsert_access_point(&beacon(hidden, "", 100)).unwrap();
+
+        let mut response = beacon(hidden, "SecretNet", 110);
+        response.probe_response = true;
+        db.upsert_access_point(&response).unwrap();
+
+        let aps = db.get_access_points().unwrap();
+        assert!(aps[0].hidden);
+        assert_eq!(aps[0].ssid.as_deref(), Some("SecretNet"));
+        assert_eq!(aps[0].ssid_source.as_deref(), Some("probe_response"));
+
+        let mut probe = ProbeCapture::test("AA:BB:CC:DD:EE:01", "SecretNet", 120);
+        probe.bssid = None;
+        db.insert_probe(&probe).unwrap();
+        let device = db.get_device_by_mac("AA:BB:CC:DD:EE:01").unwrap().unwrap();
+        assert_eq!(db.get_nearby_ssids_for_device(device.id).unwrap(), vec!["SecretNet".to_string()]);
+    }
+
+    #[test]
+    fn test_multi_ssid_responders() {
+        let db = Database::open_in_memory().unwrap();
+        for (i, ssid) in ["Home", "Cafe", "Airport"].iter().enumerate() {
+            let mut response = beacon("66:55:44:33:22:11", ssid, 100 + i as i64);
+            response.probe_response = true;
+            db.upsert_access_point(&response).unwrap();
+        }
+        db.upsert_access_point(&beacon("00:11:22:33:44:55", "Home", 100)).unwrap();
+
+        let responders = db.get_multi_ssid_responders(3).unwrap();
+        assert_eq!(responders.len(), 1);
+        assert_eq!(responders[0].0, "66:55:44:33:22:11");
+        assert_eq!(responders[0].1.len(), 3);
+    }
+
+    #[test]
+    fn test_probe_responders_for_device() {
+        let db = Database::open_in_memory().unwrap();
+        let station = "AA:BB:CC:DD:EE:01";
+        for timestamp in [100, 200] {
+            let mut response = beacon("00:11:22:33:44:55", "Home", timestamp);
+            response.probe_response = true;
+            response.destination = Some(station.to_string());
+            db.upsert_access_point(&response).unwrap();
+        }
+        let mut other = beacon("00:11:22:33:44:66", "Cafe", 150);
+        other.probe_response = true;
+        other.destination = Some("AA:BB:CC:DD:EE:02".to_string());
+        db.upsert_access_point(&other).unwrap();
+
+        let responders = db.get_probe_responders_for_device(station).unwrap();
+        assert_eq!(responders, vec![ProbeResponder {
+            bssid: "00:11:22:33:44:55".to_string(),
+            ssid: "Home".to_string(),
+            first_seen: 100,
+            last_seen: 200,
+            responses: 2,
+        }]);
+    }
+
+    #[test]
+    fn test_deauth_events_round_trip() {
+        let db = Database::open_in_memory().unwrap();
+        let event = DeauthEvent {
+            timestamp: 100,
+            kind: DeauthKind::Disassoc,
+            source: "00:11:22:33:44:55".to_string(),
+            destination: "AA:BB:CC:DD:EE:01".to_string(),
+            bssid: "00:11:22:33:44:55".to_string(),
+            reason_code: None,
+            signal_dbm: Some(-48),
+            channel: Some(11),
+        };
+        db.insert_deauth_event(&event).unwrap();
+
+        assert_eq!(db.count_deauth_events().unwrap(), 1);
+        assert_eq!(db.get_deauth_events_since(50).unwrap(), vec![event]);
+        assert!(db.get_deauth_events_since(101).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recent_devices_keep_capabilities() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100)).unwrap();
+        let mut probe = ProbeCapture::test("AA:BB:CC:DD:EE:01", "Work", 200);
+        probe.capabilities = Some(ProbeCapabilities {
+            wifi_generation: "802.11ax (WiFi 6)".to_string(),
+            has_he: true,
+            ..Default::default()
+        });
+        db.insert_probe(&probe).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Home", 50)).unwrap();
+
+        let devices = db.get_recent_devices(100).unwrap();
+        assert_eq!(devices.len(), 1);
+        let device = &devices[0];
+        assert_eq!((device.first_seen, device.last_seen, device.probe_count), (100, 200, 2));
+        assert_eq!(device.ssids.len(), 2);
+        let caps = device.capabilities.as_ref().unwrap();
+        assert!(caps.has_he);
+        assert_eq!(caps.wifi_generation, "802.11ax (WiFi 6)");
+    }
+
+    #[test]
+    fn test_reprocess_fingerprints() {
+        let db = Database::open_in_memory().unwrap();
+        let mut probe = ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 100);
+        probe.capabilities = Some(ProbeCapabilities {
+            raw_ie_ids: vec![0, 1, 50, 45],
+            ..Default::default()
+        });
+        db.insert_probe(&probe).unwrap();
+
+        let current = fingerprint::current();
+        let (hash, version) = db.get_device_fingerprint(1).unwrap().unwrap();
+        assert_eq!(version, current.version());
+        assert_eq!(db.reprocess_fingerprints().unwrap(), 0);
+
+        // Rows from an older database, or an older algorithm, get recomputed
+        db.conn
+            .execute("UPDATE probe_capabilities SET fingerprint = NULL, fingerprint_version = NULL", [])
+            .unwrap();
+        assert_eq!(db.count_fingerprints_by_version().unwrap(), vec![(None, 1)]);
+        assert_eq!(db.reprocess_fingerprints().unwrap(), 1);
+        assert_eq!(db.get_device_fingerprint(1).unwrap(), Some((hash, version)));
+    }
+
+    #[test]
+    fn test_labels_and_watchlist() {
+        let db = Database::open_in_memory().unwrap();
+        db.set_device_label("AA:BB:CC:DD:EE:01", "printer", 100).unwrap();
+        db.set_device_label("AA:BB:CC:DD:EE:01", "office printer", 200).unwrap();
+        assert_eq!(db.get_device_labels().unwrap().get("AA:BB:CC:DD:EE:01").unwrap(), "office printer");
+
+        assert!(db.add_to_watchlist("AA:BB:CC:DD:EE:02", 100).unwrap());
+        assert!(!db.add_to_watchlist("AA:BB:CC:DD:EE:02", 200).unwrap());
+        assert_eq!(db.get_watchlist().unwrap(), vec!["AA:BB:CC:DD:EE:02"]);
+    }
+
+    #[test]
+    fn test_sessions_tag_probes() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 50)).unwrap();
+
+        let adapter = AdapterInfo {
+            interface: "wlan1".to_string(),
+            driver: Some("ath9k_htc".to_string()),
+            mac: Some("00:C0:CA:11:22:33".to_string()),
+        };
+        let id = db.start_session(&adapter, "pcap", "1,6,11", Some("{}"), 100).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 110)).unwrap();
+        // The first geotagged probe sets the start point, later ones don't
+        for (lat, t) in [(52.5, 115), (48.1, 118)] {
+            let mut located = ProbeCapture::test("AA:BB:CC:DD:EE:03", "Cafe", t);
+            located.lat = Some(lat);
+            located.lon = Some(13.4);
+            db.insert_probe(&located).unwrap();
+        }
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Work", 120)).unwrap();
+        db.end_session(130).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "Work", 140)).unwrap();
+
+        let sessions = db.get_sessions().unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].id, id);
+        assert_eq!(sessions[0].ended_at, Some(130));
+        assert_eq!(sessions[0].driver.as_deref(), Some("ath9k_htc"));
+        assert_eq!(sessions[0].channels.as_deref(), Some("1,6,11"));
+        assert_eq!((sessions[0].start_lat, sessions[0].start_lon), (Some(52.5), Some(13.4)));
+        assert_eq!(sessions[0].probes, 4);
+    }
+
+    #[test]
+    fn test_suppressed_probes_counted_per_device() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "Home", 50)).unwrap();
+        db.add_suppressed_probes("AA:BB:CC:DD:EE:01", 40).unwrap();
+        db.add_suppressed_probes("AA:BB:CC:DD:EE:01", 2).unwrap();
+        // Unknown devices are ignored
+        db.add_suppressed_probes("AA:BB:CC:DD:EE:09", 5).unwrap();
+        assert_eq!(db.count_suppressed_probes().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_channel_usage_accumulates_per_session() {
+        let mut db = Database::open_in_memory().unwrap();
+        let usage = |channel, frames, airtime_us, dwell_us| ChannelUsage {
+            channel,
+            frames,
+            airtime_us,
+            dwell_us,
+        };
+        let adapter = AdapterInfo {
+            interface: "wlan1".to_string(),
+            driver: None,
+            mac: None,
+        };
+
+        let first = db.start_session(&adapter, "pcap", "1,6", None, 100).unwrap();
+        db.add_channel_usage(&[usage(1, 10, 5_000, 100_000), usage(6, 0, 0, 100_000)]).unwrap();
+        db.add_channel_usage(&[usage(1, 30, 15_000, 100_000)]).unwrap();
+        db.end_session(200).unwrap();
+        let second = db.start_session(&adapter, "pcap", "1", None, 300).unwrap();
+        db.add_channel_usage(&[usage(1, 5, 1_000, 50_000)]).unwrap();
+
+        assert_eq!(db.latest_session_id().unwrap(), Some(second));
+        let first_usage = db.get_channel_usage(Some(first)).unwrap();
+        assert_eq!(first_usage, vec![usage(1, 40, 20_000, 200_000), usage(6, 0, 0, 100_000)]);
+        assert_eq!(first_usage[0].utilization(), Some(0.1));
+        let all = db.get_channel_usage(None).unwrap();
+        assert_eq!(all[0], usage(1, 45, 21_000, 250_000));
+    }
+
+    #[test]
+    fn test_ssid_and_vendor_summaries() {
+        let db = Database::open_in_memory().unwrap();
+        db.insert_probe(&ProbeCapture::test("00:03:93:00:00:01", "Home", 100)).unwrap();
+        db.insert_probe(&ProbeCapture::test("00:03:93:00:00:01", "Cafe", 150)).unwrap();
+        db.insert_probe(&ProbeCapture::test("DA:A1:19:00:00:02", "Home", 300)).unwrap();
+        db.insert_probe(&ProbeCapture::test("DA:A1:19:00:00:02", "", 400)).unwrap();
+
+        let ssids = db.get_ssid_summaries().unwrap();
+        assert_eq!(ssids.len(), 2);
+        assert_eq!(
+            ssids[0],
+            SsidSummary {
+                ssid: "Home".to_string(),
+                devices: 2,
+                probes: 2,
+                first_seen: 100,
+                last_seen: 300,
+            }
+        );
+        assert_eq!(ssids[1].ssid, "Cafe");
+
+        let vendors = db.get_vendor_summaries().unwrap();
+        let total: usize = vendors.iter().map(|v| v.devices).sum();
+        assert_eq!(total, 2);
+        let randomized = vendors.iter().find(|v| v.randomized > 0).unwrap();
+        assert_eq!(randomized.randomized_share(), 1.0);
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let path = std::env::temp_dir().join(format!("prowl-schema-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let info = Database::open(&path).unwrap().schema_info().unwrap();
+        assert_eq!(info.version, SCHEMA_VERSION);
+        assert_eq!(info.created_by.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+
+        // As a future prowl would leave it
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+            conn.execute("UPDATE schema_info SET value = '9.0.0' WHERE key = 'last_opened_by'", []).unwrap();
+        }
+        let err = Database::open(&path).err().unwrap();
+        let incompatible = err.downcast_ref::<IncompatibleDatabase>().unwrap();
+        assert_eq!(incompatible.found, SCHEMA_VERSION + 1);
+        assert_eq!(incompatible.written_by, "9.0.0");
+
+        // Left untouched for the newer build
+        let conn = Connection::open(&path).unwrap();
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, SCHEMA_VERSION + 1);
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reporting_views_rebuilt_when_missing() {
+        let path = std::env::temp_dir().join(format!("prowl-views-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        drop(Database::open(&path).unwrap());
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch("DROP VIEW vendor_counts").unwrap();
+        }
+        let db = Database::open(&path).unwrap();
+        let views: usize = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'view'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(views, REPORTING_VIEW_NAMES.len());
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+
+    #[test]
+    fn test_reopening_does_not_write() {
+        let path = std::env::temp_dir().join(format!("prowl-reopen-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let db = Database::open(&path).unwrap();
+        // Changes whenever another connection commits to the file
+        let data_version = || -> i64 {
+            db.conn.query_row("PRAGMA data_version", [], |row| row.get(0)).unwrap()
+        };
+        let before = data_version();
+        drop(Database::open(&path).unwrap());
+        assert_eq!(data_version(), before);
+
+        // A different build stamps itself once
+        db.conn.execute("UPDATE schema_info SET value = '0.0.1' WHERE key = 'last_opened_by'", []).unwrap();
+        let reopened = Database::open(&path).unwrap();
+        let info = reopened.schema_info().unwrap();
+        assert_eq!(info.last_opened_by.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        drop((db, reopened));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_only_open_refuses_writes() {
+        let path = std::env::temp_dir().join(format!("prowl-readonly-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        drop(Database::open(&path).unwrap());
+
+        let db = Database::open_read_only(&path).unwrap();
+        assert_eq!(db.count_devices().unwrap(), 0);
+        assert!(db.set_device_label("AA:BB:CC:00:00:01", "mine", 0).is_err());
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_savepoint_rolls_back_only_itself() {
+        let db = Database::open_in_memory().unwrap();
+        db.in_transaction(|db| {
+            db.insert_event(1, "kept", "first", None)?;
+            let failed: Result<()> = db.in_savepoint(|db| {
+                db.insert_event(2, "dropped", "half written", None)?;
+                anyhow::bail!("second write failed")
+            });
+            assert!(failed.is_err());
+            db.in_savepoint(|db| db.insert_event(3, "kept", "after", None))
+        })
+        .unwrap();
+
+        let events = db.get_events_since(0, None).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.event_type == "kept"));
     }
 }
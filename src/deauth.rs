@@ -0,0 +1,132 @@
+//! Deauthentication attack detection.
+//!
+//! Clients and APs send the odd deauth or disassoc frame during normal
+//! roaming. A burst of them against one BSSID, often addressed to broadcast,
+//! is how deauth attacks knock clients off a network to capture handshakes
+//! or push them onto an evil twin. Bursts are reported once per window.
+
+use crate::database::DeauthEvent;
+use std::collections::{HashMap, VecDeque};
+
+/// Event type recorded in the events table for deauth bursts
+pub const EVENT_DEAUTH_ATTACK: &str = "deauth_attack";
+
+/// Frames against one BSSID within the window that count as an attack
+pub const DEAUTH_BURST_FRAMES: usize = 10;
+
+pub const DEAUTH_BURST_WINDOW_SECS: i64 = 10;
+
+/// A burst of deauth/disassoc frames against one BSSID
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeauthAttack {
+    pub bssid: String,
+    pub start: i64,
+    pub end: i64,
+    pub frames: usize,
+    pub broadcast_frames: usize,
+}
+
+impl DeauthAttack {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} deauth/disassoc frames against {} in {}s ({} to broadcast)",
+            self.frames,
+            self.bssid,
+            (self.end - self.start).max(1),
+            self.broadcast_frames
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct BssidWindow {
+    /// (timestamp, broadcast) per frame inside the window
+    frames: VecDeque<(i64, bool)>,
+    reported_at: Option<i64>,
+}
+
+/// Sliding window of deauth frames per BSSID
+#[derive(Debug, Default)]
+pub struct DeauthMonitor {
+    windows: HashMap<String, BssidWindow>,
+}
+
+impl DeauthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one frame. Returns an attack the first time a BSSID's window
+    /// fills, then stays quiet for that BSSID until a full window has passed.
+    pub fn observe(&mut self, event: &DeauthEvent) -> Option<DeauthAttack> {
+        let window = self.windows.entry(event.bssid.clone()).or_default();
+        let now = event.timestamp;
+        window
+            .frames
+            .push_back((now, event.destination == "FF:FF:FF:FF:FF:FF"));
+        while let Some(&(at, _)) = window.frames.front() {
+            if now - at < DEAUTH_BURST_WINDOW_SECS {
+                break;
+            }
+            window.frames.pop_front();
+        }
+
+        if window.frames.len() < DEAUTH_BURST_FRAMES {
+            return None;
+        }
+        if matches!(window.reported_at, Some(at) if now - at < DEAUTH_BURST_WINDOW_SECS) {
+            return None;
+        }
+        window.reported_at = Some(now);
+
+        Some(DeauthAttack {
+            bssid: event.bssid.clone(),
+            start: window.frames.front().map(|&(at, _)| at).unwrap_or(now),
+            end: now,
+            frames: window.frames.len(),
+            broadcast_frames: window.frames.iter().filter(|&&(_, broadcast)| broadcast).count(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::DeauthKind;
+
+    fn deauth(bssid: &str, timestamp: i64) -> DeauthEvent {
+        DeauthEvent {
+            timestamp,
+            kind: DeauthKind::Deauth,
+            source: bssid.to_string(),
+            destination: "FF:FF:FF:FF:FF:FF".to_string(),
+            bssid: bssid.to_string(),
+            reason_code: Some(7),
+            signal_dbm: Some(-40),
+            channel: Some(6),
+        }
+    }
+
+    #[test]
+    fn test_occasional_deauth_is_quiet() {
+        let mut monitor = DeauthMonitor::new();
+        for i in 0..20 {
+            assert!(monitor.observe(&deauth("00:11:22:33:44:55", i * 30)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_burst_reported_once_per_window() {
+        let mut monitor = DeauthMonitor::new();
+        let bssid = "00:11:22:33:44:55";
+        let attacks: Vec<DeauthAttack> = (0..40)
+            .filter_map(|i| monitor.observe(&deauth(bssid, 1000 + i / 2)))
+            .collect();
+
+        assert_eq!(attacks.len(), 2);
+        assert_eq!(attacks[0].frames, DEAUTH_BURST_FRAMES);
+        assert_eq!(attacks[0].broadcast_frames, DEAUTH_BURST_FRAMES);
+        assert_eq!(attacks[1].start, 1005);
+        assert!(monitor.observe(&deauth("00:11:22:33:44:66", 1020)).is_none());
+    }
+}
@@ -0,0 +1,239 @@
+//! Merging copies of one probe heard by several sensors.
+//!
+//! Sensors deployed near each other hear the same transmission. Copies share
+//! the source MAC and 802.11 sequence number and arrive within a few hundred
+//! milliseconds, so they are folded into one observation that carries every
+//! sensor's RSSI. That per-sensor signal list is what position estimation
+//! works from. There is no multi-sensor aggregator yet; this is the merge
+//! step it is meant to run on incoming feeds.
+//!
+//! A single sensor also hears a probe more than once when the device
+//! retransmits it. `RetryFilter` drops those copies at capture time so they
+//! don't inflate probe counts and persistence scores.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Devices remembered by `RetryFilter` before stale entries are pruned
+const RETRY_FILTER_PRUNE_AT: usize = 4096;
+
+/// Copies further apart than this are treated as separate transmissions
+pub const DEFAULT_DEDUP_WINDOW_MS: i64 = 500;
+
+/// One probe as reported by one sensor
+#[derive(Debug, Clone)]
+pub struct SensorObservation {
+    pub sensor: String,
+    pub mac: String,
+    pub ssid: String,
+    pub sequence_number: u16,
+    pub timestamp_ms: i64,
+    pub signal_dbm: Option<i32>,
+}
+
+/// A single sensor's view of a merged observation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensorReading {
+    pub sensor: String,
+    pub signal_dbm: Option<i32>,
+    pub timestamp_ms: i64,
+}
+
+/// One transmission, with a reading from every sensor that heard it
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedObservation {
+    pub mac: String,
+    pub ssid: String,
+    pub sequence_number: u16,
+    /// Earliest time any sensor heard it
+    pub timestamp_ms: i64,
+    pub readings: Vec<SensorReading>,
+}
+
+impl MergedObservation {
+    /// Reading with the highest signal, if any sensor reported one
+    pub fn strongest(&self) -> Option<&SensorReading> {
+        self.readings
+            .iter()
+            .filter(|r| r.signal_dbm.is_some())
+            .max_by_key(|r| r.signal_dbm)
+    }
+
+    fn add(&mut self, obs: SensorObservation) {
+        match self.readings.iter_mut().find(|r| r.sensor == obs.sensor) {
+            // Retransmission heard again by the same sensor: keep the stronger copy
+            Some(existing) => {
+                if obs.signal_dbm > existing.signal_dbm {
+                    existing.signal_dbm = obs.signal_dbm;
+                    existing.timestamp_ms = obs.timestamp_ms;
+                }
+            }
+            None => self.readings.push(SensorReading {
+                sensor: obs.sensor,
+                signal_dbm: obs.signal_dbm,
+                timestamp_ms: obs.timestamp_ms,
+            }),
+        }
+    }
+}
+
+/// Merge observations from all sensors into logical observations, ordered
+/// by first-heard time. Copies match on MAC and sequence number within
+/// `window_ms` of the first copy.
+pub fn merge_observations(mut observations: Vec<SensorObservation>, window_ms: i64) -> Vec<MergedObservation> {
+    observations.sort_by_key(|o| o.timestamp_ms);
+
+    let mut merged: Vec<MergedObservation> = Vec::new();
+    // Latest merged entry for each (MAC, sequence number)
+    let mut latest: HashMap<(String, u16), usize> = HashMap::new();
+
+    for obs in observations {
+        let key = (obs.mac.clone(), obs.sequence_number);
+        if let Some(&idx) = latest.get(&key) {
+            if obs.timestamp_ms - merged[idx].timestamp_ms <= window_ms {
+                merged[idx].add(obs);
+                continue;
+            }
+        }
+
+        latest.insert(key, merged.len());
+        merged.push(MergedObservation {
+            mac: obs.mac.clone(),
+            ssid: obs.ssid.clone(),
+            sequence_number: obs.sequence_number,
+            timestamp_ms: obs.timestamp_ms,
+            readings: Vec::new(),
+        });
+        merged.last_mut().unwrap().add(obs);
+    }
+
+    merged
+}
+
+/// Recognizes 802.11 retransmissions of a probe already seen: the retry
+/// bit set and the same sequence number as the device's previous frame,
+/// within the window
+#[derive(Debug)]
+pub struct RetryFilter {
+    window_ms: i64,
+    /// Last sequence number and arrival time per source MAC
+    last: HashMap<String, (u16, i64)>,
+}
+
+impl RetryFilter {
+    /// A filter that collapses copies within `window_ms`; 0 disables it
+    pub fn new(window_ms: u64) -> Self {
+        RetryFilter {
+            window_ms: window_ms as i64,
+            last: HashMap::new(),
+        }
+    }
+
+    /// True if this frame repeats the last one from `mac`. Frames that
+    /// aren't duplicates become the new reference for the device.
+    pub fn is_retransmission(
+        &mut self,
+        mac: &str,
+        sequence_number: u16,
+        retry: bool,
+        timestamp_ms: i64,
+    ) -> bool {
+        if self.window_ms <= 0 {
+            return false;
+        }
+        if retry {
+            if let Some(&(seq, at)) = self.last.get(mac) {
+                if seq == sequence_number && (timestamp_ms - at).abs() <= self.window_ms {
+                    return true;
+                }
+            }
+        }
+
+        if self.last.len() >= RETRY_FILTER_PRUNE_AT {
+            let window_ms = self.window_ms;
+            self.last.retain(|_, (_, at)| timestamp_ms - *at <= window_ms);
+        }
+        self.last.insert(mac.to_string(), (sequence_number, timestamp_ms));
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obs(sensor: &str, seq: u16, timestamp_ms: i64, signal: i32) -> SensorObservation {
+        SensorObservation {
+            sensor: sensor.to_string(),
+            mac: "AA:BB:CC:DD:EE:01".to_string(),
+            ssid: "Home".to_string(),
+            sequence_number: seq,
+            timestamp_ms,
+            signal_dbm: Some(signal),
+        }
+    }
+
+    #[test]
+    fn test_copies_merge_across_sensors() {
+        let merged = merge_observations(
+            vec![
+                obs("north", 100, 1_030, -70),
+                obs("south", 100, 1_000, -55),
+                obs("east", 100, 1_120, -80),
+            ],
+            DEFAULT_DEDUP_WINDOW_MS,
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].timestamp_ms, 1_000);
+        assert_eq!(merged[0].readings.len(), 3);
+        assert_eq!(merged[0].strongest().unwrap().sensor, "south");
+    }
+
+    #[test]
+    fn test_distinct_transmissions_stay_apart() {
+        let merged = merge_observations(
+            vec![
+                obs("north", 100, 1_000, -70),
+                obs("north", 101, 1_010, -70),
+                // Sequence number reused after wrapping, long after the first
+                obs("south", 100, 60_000, -60),
+            ],
+            DEFAULT_DEDUP_WINDOW_MS,
+        );
+        assert_eq!(merged.len(), 3);
+        assert!(merged.iter().all(|m| m.readings.len() == 1));
+    }
+
+    #[test]
+    fn test_retransmission_keeps_strongest_copy() {
+        let merged = merge_observations(
+            vec![obs("north", 7, 1_000, -75), obs("north", 7, 1_004, -68)],
+            DEFAULT_DEDUP_WINDOW_MS,
+        );
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].readings, vec![SensorReading {
+            sensor: "north".to_string(),
+            signal_dbm: Some(-68),
+            timestamp_ms: 1_004,
+        }]);
+    }
+
+    #[test]
+    fn test_retry_filter() {
+        let mut filter = RetryFilter::new(200);
+        let mac = "AA:BB:CC:DD:EE:01";
+        assert!(!filter.is_retransmission(mac, 42, false, 1_000));
+        assert!(filter.is_retransmission(mac, 42, true, 1_003));
+        // Same sequence number without the retry bit is a new frame
+        assert!(!filter.is_retransmission(mac, 42, false, 1_010));
+        // Retry of a frame we never heard is kept
+        assert!(!filter.is_retransmission(mac, 43, true, 1_020));
+        assert!(!filter.is_retransmission("AA:BB:CC:DD:EE:02", 43, true, 1_021));
+        // Outside the window
+        assert!(!filter.is_retransmission(mac, 43, true, 1_500));
+
+        let mut disabled = RetryFilter::new(0);
+        assert!(!disabled.is_retransmission(mac, 1, false, 0));
+        assert!(!disabled.is_retransmission(mac, 1, true, 1));
+    }
+}
@@ -0,0 +1,281 @@
+//! Disk space guard.
+//!
+//! A sensor left capturing for weeks fills its SD card sooner or later, and
+//! SQLite fails mid-write once it has. A monitor thread samples the size of
+//! the database files and the free space on their filesystem, projects from
+//! the recent growth rate when the disk will be full, and publishes that for
+//! the status line, the health endpoint and the TUI. With `shed_low_value`
+//! on, capture stops storing broadcast probes and weak signals while the
+//! disk is about to fill, which keeps the probes that matter most flowing.
+
+use crate::config::DiskConfig;
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Growth is measured over this much history
+const GROWTH_WINDOW_SECS: i64 = 3600;
+/// Shedding only stops once there is twice the room that started it, so it
+/// doesn't flap as the slower growth pushes the projection out
+const SHED_RECOVERY_FACTOR: u64 = 2;
+const UNKNOWN: u64 = u64::MAX;
+
+/// Disk figures shared between the monitor and whoever displays them
+#[derive(Debug)]
+pub struct DiskState {
+    free_mb: AtomicU64,
+    full_in_secs: AtomicU64,
+    low: AtomicBool,
+    shedding: AtomicBool,
+    shed: AtomicU64,
+}
+
+impl Default for DiskState {
+    fn default() -> Self {
+        DiskState {
+            free_mb: AtomicU64::new(UNKNOWN),
+            full_in_secs: AtomicU64::new(UNKNOWN),
+            low: AtomicBool::new(false),
+            shedding: AtomicBool::new(false),
+            shed: AtomicU64::new(0),
+        }
+    }
+}
+
+impl DiskState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether capture should skip low-value probes right now
+    pub fn is_shedding(&self) -> bool {
+        self.shedding.load(Ordering::Relaxed)
+    }
+
+    /// Count a probe left unstored while shedding
+    pub fn record_shed(&self) {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> DiskStatus {
+        let known = |value: u64| (value != UNKNOWN).then_some(value);
+        DiskStatus {
+            free_mb: known(self.free_mb.load(Ordering::Relaxed)),
+            full_in_secs: known(self.full_in_secs.load(Ordering::Relaxed)),
+            low: self.low.load(Ordering::Relaxed),
+            shedding: self.shedding.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A reading of `DiskState`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskStatus {
+    pub free_mb: Option<u64>,
+    /// Projected time until the disk is full; `None` while the database
+    /// isn't growing or before there is enough history
+    pub full_in_secs: Option<u64>,
+    /// Projected to fill within `disk.warn_hours`
+    pub low: bool,
+    pub shedding: bool,
+    /// Probes left unstored while shedding
+    pub shed: u64,
+}
+
+impl DiskStatus {
+    /// e.g. "812 MB free, full in ~5h"
+    pub fn describe(&self) -> String {
+        let free = match self.free_mb {
+            Some(mb) if mb >= 10 * 1024 => format!("{:.0} GB free", mb as f64 / 1024.0),
+            Some(mb) => format!("{} MB free", mb),
+            None => "free space unknown".to_string(),
+        };
+        match self.full_in_secs {
+            Some(secs) if secs < 3600 => format!("{}, full in ~{}m", free, (secs / 60).max(1)),
+            Some(secs) => format!("{}, full in ~{}h", free, secs / 3600),
+            None => free,
+        }
+    }
+}
+
+/// Database size over time, for the growth rate
+#[derive(Debug, Default)]
+pub struct GrowthTracker {
+    samples: VecDeque<(i64, u64)>,
+}
+
+impl GrowthTracker {
+    pub fn add(&mut self, now: i64, bytes: u64) {
+        self.samples.push_back((now, bytes));
+        // Keep two samples however old, so a rate is always available
+        while self.samples.len() > 2 {
+            match self.samples.front() {
+                Some((at, _)) if now - at > GROWTH_WINDOW_SECS => self.samples.pop_front(),
+                _ => break,
+            };
+        }
+    }
+
+    /// Bytes per second over the window; `None` until two samples are at
+    /// least a minute apart
+    pub fn bytes_per_sec(&self) -> Option<f64> {
+        let (first_at, first) = *self.samples.front()?;
+        let (last_at, last) = *self.samples.back()?;
+        let elapsed = last_at - first_at;
+        (elapsed >= 60).then(|| (last as f64 - first as f64) / elapsed as f64)
+    }
+}
+
+/// Seconds until `free_bytes` are used up at `bytes_per_sec`, `None` when
+/// not growing
+pub fn time_to_full(free_bytes: u64, bytes_per_sec: f64) -> Option<u64> {
+    (bytes_per_sec > 0.0).then(|| (free_bytes as f64 / bytes_per_sec) as u64)
+}
+
+/// Whether to shed given the projection, and whether shedding already is
+pub fn should_shed(config: &DiskConfig, free_mb: u64, full_in_secs: Option<u64>, shedding: bool) -> bool {
+    if !config.shed_low_value {
+        return false;
+    }
+    let factor = if shedding { SHED_RECOVERY_FACTOR } else { 1 };
+    free_mb < config.min_free_mb * factor
+        || full_in_secs.is_some_and(|secs| secs < config.shed_hours * 3600 * factor)
+}
+
+/// Probes worth least when space runs out: broadcast probes, which name no
+/// network, and weak signals from devices far away
+pub fn is_low_value(ssid: &str, signal_dbm: Option<i32>, weak_signal_dbm: i32) -> bool {
+    ssid.is_empty() || signal_dbm.is_some_and(|s| s < weak_signal_dbm)
+}
+
+/// Size of the database and its journal files
+pub fn database_bytes(database: &str) -> u64 {
+    ["", "-wal", "-journal"]
+        .iter()
+        .filter_map(|suffix| std::fs::metadata(format!("{}{}", database, suffix)).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Bytes available to this user on the filesystem holding `path`
+#[cfg(unix)]
+pub fn available_bytes(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Watch the disk under `database` until `running` clears, publishing into
+/// `state`. Returns `None` where free space can't be read.
+pub fn spawn_disk_monitor(
+    config: &DiskConfig,
+    database: &str,
+    state: Arc<DiskState>,
+    running: Arc<AtomicBool>,
+) -> Option<thread::JoinHandle<()>> {
+    let dir = match Path::new(database).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    };
+    available_bytes(&dir)?;
+
+    let config = config.clone();
+    let database = database.to_string();
+    Some(thread::spawn(move || {
+        let mut growth = GrowthTracker::default();
+
+        while running.load(Ordering::SeqCst) {
+            if let Some(free) = available_bytes(&dir) {
+                growth.add(chrono::Utc::now().timestamp(), database_bytes(&database));
+                let free_mb = free / (1024 * 1024);
+                let full_in_secs = growth.bytes_per_sec().and_then(|rate| time_to_full(free, rate));
+                let low = free_mb < config.min_free_mb
+                    || full_in_secs.is_some_and(|secs| secs < config.warn_hours * 3600);
+                let was_shedding = state.is_shedding();
+                let shedding = should_shed(&config, free_mb, full_in_secs, was_shedding);
+
+                state.free_mb.store(free_mb, Ordering::Relaxed);
+                state.full_in_secs.store(full_in_secs.unwrap_or(UNKNOWN), Ordering::Relaxed);
+                let was_low = state.low.swap(low, Ordering::Relaxed);
+                state.shedding.store(shedding, Ordering::Relaxed);
+
+                let status = state.status();
+                if low && !was_low {
+                    warn!("Disk space running low: {}", status.describe());
+                }
+                if shedding && !was_shedding {
+                    warn!(
+                        "Disk nearly full ({}), no longer storing broadcast or weak probes",
+                        status.describe()
+                    );
+                } else if was_shedding && !shedding {
+                    info!("Disk space recovered ({}), storing every probe again", status.describe());
+                }
+            }
+
+            // Sleep in short steps so shutdown isn't held up by the poll
+            let mut slept = Duration::ZERO;
+            while slept < POLL_INTERVAL && running.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_secs(1));
+                slept += Duration::from_secs(1);
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_growth_projection() {
+        let mut growth = GrowthTracker::default();
+        growth.add(0, 1_000_000);
+        assert_eq!(growth.bytes_per_sec(), None);
+        growth.add(600, 1_600_000);
+        assert_eq!(growth.bytes_per_sec(), Some(1000.0));
+        // 3.6 MB free at 1 KB/s lasts an hour
+        assert_eq!(growth.bytes_per_sec().and_then(|r| time_to_full(3_600_000, r)), Some(3600));
+
+        // Old samples age out of the window
+        growth.add(600 + GROWTH_WINDOW_SECS + 1, 1_600_000);
+        assert_eq!(growth.bytes_per_sec(), Some(0.0));
+        assert_eq!(time_to_full(3_600_000, 0.0), None);
+    }
+
+    #[test]
+    fn test_shedding_thresholds() {
+        let mut config = DiskConfig::default();
+        assert!(!should_shed(&config, 10, Some(60), false));
+
+        config.shed_low_value = true;
+        assert!(should_shed(&config, 10, None, false));
+        assert!(should_shed(&config, 10_000, Some(3600), false));
+        assert!(!should_shed(&config, 10_000, Some(3 * 3600), false));
+        // Keeps shedding until there is twice the room
+        assert!(should_shed(&config, 10_000, Some(3 * 3600), true));
+        assert!(!should_shed(&config, 10_000, Some(5 * 3600), true));
+
+        assert!(is_low_value("", Some(-40), -80));
+        assert!(is_low_value("HomeNet", Some(-85), -80));
+        assert!(!is_low_value("HomeNet", Some(-60), -80));
+        assert!(!is_low_value("HomeNet", None, -80));
+    }
+}
@@ -0,0 +1,194 @@
+//! Scheduled summary reports by email.
+//!
+//! A background thread wakes at the configured hour, renders
+//! `ReportGenerator::write_summary_report` for the elapsed period and pipes
+//! it to a local sendmail-compatible command. Relaying is left to the MTA so
+//! no SMTP credentials live in config.json.
+
+use crate::analysis::SurveillanceAnalyzer;
+use crate::config::{Config, EmailConfig, ReportSchedule};
+use crate::database::Database;
+use crate::report::ReportGenerator;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, TimeZone, Utc};
+use log::{error, info, warn};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Event type recorded in the events table for each summary sent
+pub const EVENT_SUMMARY_EMAIL: &str = "summary_email";
+
+/// First send time strictly after `now`
+pub fn next_send_time(schedule: ReportSchedule, hour_utc: u32, now: DateTime<Utc>) -> DateTime<Utc> {
+    let today = now.date_naive();
+    let mut next = Utc.from_utc_datetime(&today.and_hms_opt(hour_utc.min(23), 0, 0).unwrap());
+
+    if schedule == ReportSchedule::Weekly {
+        let days_to_monday = (7 - today.weekday().num_days_from_monday()) % 7;
+        next += ChronoDuration::days(days_to_monday as i64);
+    }
+    if next <= now {
+        next += ChronoDuration::hours(schedule.period_hours() as i64);
+    }
+    next
+}
+
+/// Full RFC 5322 message ready for `sendmail -t`
+pub fn build_message(config: &EmailConfig, subject: &str, body: &str, date: DateTime<Utc>) -> String {
+    let mut message = String::new();
+    message.push_str(&format!("From: {}\r\n", config.from));
+    message.push_str(&format!("To: {}\r\n", config.to.join(", ")));
+    message.push_str(&format!("Subject: {}\r\n", subject));
+    message.push_str(&format!("Date: {}\r\n", date.to_rfc2822()));
+    message.push_str("MIME-Version: 1.0\r\n");
+    message.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    message.push_str("\r\n");
+    for line in body.lines() {
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+/// Pipe `message` to the configured sendmail command
+pub fn send_message(config: &EmailConfig, message: &str) -> Result<()> {
+    let mut parts = config.sendmail_command.split_whitespace();
+    let program = match parts.next() {
+        Some(p) => p,
+        None => bail!("email.sendmail_command is empty"),
+    };
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    child
+        .stdin
+        .take()
+        .context("sendmail stdin unavailable")?
+        .write_all(message.as_bytes())?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Render and send one summary covering the last schedule period
+pub fn send_summary(config: &Config, db: &Database) -> Result<()> {
+    let email = &config.email;
+    let hours = email.schedule.period_hours();
+
+    let analyzer = SurveillanceAnalyzer::new(
+        config.analysis.time_windows_minutes.clone(),
+        config.analysis.persistence_threshold,
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+
+    let mut body = Vec::new();
+    ReportGenerator::write_summary_report(db, &analyzer, hours, email.top_devices, &mut body)?;
+
+    let now = Utc::now();
+    let label = match email.schedule {
+        ReportSchedule::Daily => "daily",
+        ReportSchedule::Weekly => "weekly",
+    };
+    let subject = format!("Prowl {} summary for {}", label, now.format("%Y-%m-%d"));
+    let message = build_message(email, &subject, &String::from_utf8_lossy(&body), now);
+    send_message(email, &message)?;
+
+    let note = format!("Sent {} summary to {}", label, email.to.join(", "));
+    db.insert_event(now.timestamp(), EVENT_SUMMARY_EMAIL, &note, None)?;
+    Ok(())
+}
+
+/// Send summaries on schedule until `running` clears. Returns `None` when
+/// email is disabled or has no recipients.
+pub fn spawn_summary_mailer(config: &Config, running: Arc<AtomicBool>) -> Option<thread::JoinHandle<()>> {
+    if !config.email.enabled {
+        return None;
+    }
+    if config.email.to.is_empty() {
+        warn!("Email summaries enabled but email.to is empty; not scheduling");
+        return None;
+    }
+
+    let config = config.clone();
+    Some(thread::spawn(move || {
+        let db = match Database::open(&config.capture.database) {
+            Ok(db) => db,
+            Err(e) => {
+                error!("Summary mailer could not open database: {}", e);
+                return;
+            }
+        };
+
+        while running.load(Ordering::SeqCst) {
+            let due = next_send_time(config.email.schedule, config.email.send_hour_utc, Utc::now());
+            info!("Next summary email at {}", due.format("%Y-%m-%d %H:%M UTC"));
+
+            while running.load(Ordering::SeqCst) && Utc::now() < due {
+                thread::sleep(Duration::from_secs(1));
+            }
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match send_summary(&config, &db) {
+                Ok(()) => info!("Summary email sent to {}", config.email.to.join(", ")),
+                Err(e) => error!("Failed to send summary email: {}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_daily_later_today() {
+        let next = next_send_time(ReportSchedule::Daily, 7, at("2026-10-14T05:30:00Z"));
+        assert_eq!(next, at("2026-10-14T07:00:00Z"));
+    }
+
+    #[test]
+    fn test_daily_rolls_to_tomorrow() {
+        let next = next_send_time(ReportSchedule::Daily, 7, at("2026-10-14T07:00:00Z"));
+        assert_eq!(next, at("2026-10-15T07:00:00Z"));
+    }
+
+    #[test]
+    fn test_weekly_goes_to_monday() {
+        // 2026-10-14 is a Wednesday
+        let next = next_send_time(ReportSchedule::Weekly, 7, at("2026-10-14T12:00:00Z"));
+        assert_eq!(next, at("2026-10-19T07:00:00Z"));
+
+        let next = next_send_time(ReportSchedule::Weekly, 7, at("2026-10-19T08:00:00Z"));
+        assert_eq!(next, at("2026-10-26T07:00:00Z"));
+    }
+
+    #[test]
+    fn test_build_message_headers() {
+        let config = EmailConfig {
+            to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+            ..EmailConfig::default()
+        };
+        let message = build_message(&config, "Summary", "line one\nline two", at("2026-10-14T07:00:00Z"));
+        assert!(message.starts_with("From: prowl@localhost\r\n"));
+        assert!(message.contains("To: a@example.com, b@example.com\r\n"));
+        assert!(message.ends_with("\r\n\r\nline one\r\nline two\r\n"));
+    }
+}
@@ -0,0 +1,100 @@
+//! Process exit codes.
+//!
+//! Scripts and cron jobs wrapping prowl can branch on these:
+//!
+//! | Code | Meaning                                                                    |
+//! |------|----------------------------------------------------------------------------|
+//! | 0    | Success (for `analyze`: no alerts)                                         |
+//! | 1    | Unexpected error                                                           |
+//! | 2    | Invalid arguments or configuration                                         |
+//! | 3    | `analyze` found alerts (with `--fail-on-alert`: at or above `--min-score`) |
+//! | 4    | No monitor-mode interface available                                        |
+//! | 5    | GPS required but gpsd unavailable                                          |
+//! | 6    | Database could not be opened, read or written                              |
+//! | 7    | No data to work with (empty window, unknown device)                        |
+//! | 8    | Missing capture privileges (CAP_NET_RAW / CAP_NET_ADMIN)                   |
+
+use crate::database::IncompatibleDatabase;
+use crate::formats::UnknownFormat;
+use crate::validation::ValidationError;
+
+pub const SUCCESS: u8 = 0;
+pub const FAILURE: u8 = 1;
+pub const USAGE: u8 = 2;
+pub const ALERTS_FOUND: u8 = 3;
+pub const NO_MONITOR_INTERFACE: u8 = 4;
+pub const GPS_UNAVAILABLE: u8 = 5;
+pub const DATABASE: u8 = 6;
+pub const NO_DATA: u8 = 7;
+pub const PERMISSION: u8 = 8;
+
+/// Short help text listing the exit codes, shown after `--help`
+pub const EXIT_CODES_HELP: &str = "\
+Exit status:
+  0  success (analyze: no alerts)
+  1  unexpected error
+  2  invalid arguments or configuration
+  3  analyze found alerts (with --fail-on-alert: scoring >= --min-score)
+  4  no monitor-mode interface
+  5  GPS required but unavailable
+  6  database error
+  7  no data (empty time window, unknown device)
+  8  missing capture privileges (CAP_NET_RAW/CAP_NET_ADMIN)";
+
+/// An error that carries a specific exit code
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ExitError {
+    pub code: u8,
+    pub message: String,
+}
+
+impl ExitError {
+    pub fn new(code: u8, message: impl Into<String>) -> Self {
+        ExitError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Pick the exit code for an error by looking through its cause chain
+pub fn code_for(err: &anyhow::Error) -> u8 {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<ExitError>() {
+            return e.code;
+        }
+        if let Some(e) = cause.downcast_ref::<ValidationError>() {
+            return match e {
+                ValidationError::NoMonitorInterface { .. } => NO_MONITOR_INTERFACE,
+                ValidationError::GpsUnavailable { .. } => GPS_UNAVAILABLE,
+                ValidationError::MissingPrivileges { .. } => PERMISSION,
+            };
+        }
+        if cause.is::<UnknownFormat>() {
+            return USAGE;
+        }
+        if cause.downcast_ref::<rusqlite::Error>().is_some() || cause.is::<IncompatibleDatabase>() {
+            return DATABASE;
+        }
+    }
+    FAILURE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_code_for_walks_cause_chain() {
+        let err = anyhow::Error::new(ExitError::new(NO_DATA, "nothing here")).context("outer");
+        assert_eq!(code_for(&err), NO_DATA);
+
+        let db_err: anyhow::Result<()> =
+            Err(rusqlite::Error::InvalidQuery).context("Failed to open database");
+        assert_eq!(code_for(&db_err.unwrap_err()), DATABASE);
+
+        assert_eq!(code_for(&anyhow::anyhow!("boom")), FAILURE);
+    }
+}
@@ -0,0 +1,276 @@
+//! Structured per-device exports.
+//!
+//! A dossier gathers everything known about one MAC address (device record,
+//! probes, presence sessions, analysis result, vendor and capability
+//! fingerprint) into a single JSON document. With the pcap-export feature,
+//! the device's frames can also be pulled out of the capture's pcap files
+//! into a pcapng whose per-frame comments carry the same context.
+
+use crate::analysis::{device_ssids, SurveillanceAlert, SurveillanceAnalyzer};
+use crate::database::{Database, Device, Probe};
+use crate::formats::Format;
+use crate::oui::{is_randomized_mac, VendorAttribution};
+use crate::parser::ProbeCapabilities;
+#[cfg(feature = "pcap-export")]
+use crate::parser::parse_probe_request;
+#[cfg(feature = "pcap-export")]
+use crate::pcap_dump::{read_pcap, PcapngWriter};
+use anyhow::Result;
+use serde::Serialize;
+#[cfg(feature = "pcap-export")]
+use std::collections::HashMap;
+#[cfg(feature = "pcap-export")]
+use std::path::{Path, PathBuf};
+
+/// Gap between probes that starts a new presence session
+const SESSION_GAP_SECS: i64 = 600;
+
+/// Formats for `prowl export --format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Pcapng,
+}
+
+impl Format for ExportFormat {
+    const KIND: &'static str = "export format";
+    const ALL: &'static [Self] = &[ExportFormat::Json, ExportFormat::Pcapng];
+
+    fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Pcapng => "pcapng",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "the device's dossier",
+            ExportFormat::Pcapng => "the device's frames, prowl's context in frame comments (needs --output)",
+        }
+    }
+}
+
+/// A contiguous run of probes with no gap longer than `SESSION_GAP_SECS`
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceSession {
+    pub start: i64,
+    pub end: i64,
+    pub probe_count: usize,
+}
+
+/// Device identity as seen on the air
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceFingerprint {
+    pub randomized_mac: bool,
+    pub vendor: Option<VendorAttribution>,
+    pub wifi_generation: Option<String>,
+    pub capabilities: Option<ProbeCapabilities>,
+    /// Capability fingerprint and the algorithm version that produced it
+    pub fingerprint: Option<String>,
+    pub fingerprint_version: Option<u32>,
+}
+
+/// Complete per-device export
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceDossier {
+    pub generated_at: String,
+    pub device: Device,
+    pub fingerprint: DeviceFingerprint,
+    pub probed_ssids: Vec<String>,
+    pub sessions: Vec<PresenceSession>,
+    /// Persistence analysis over the device's full observed lifetime
+    pub analysis: Option<SurveillanceAlert>,
+    /// Whether the persistence score crosses the configured alert threshold
+    pub alerted: bool,
+    pub probes: Vec<Probe>,
+}
+
+/// Build a dossier for `mac`, or None if the device is unknown
+pub fn build_device_dossier(
+    db: &Database,
+    mac: &str,
+    analyzer: &SurveillanceAnalyzer,
+) -> Result<Option<DeviceDossier>> {
+    let normalized = mac.to_uppercase().replace(['-', '.'], ":");
+    let device = match db.get_device_by_mac(&normalized)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    let mut probes = db.get_probes_for_device(device.id)?;
+    probes.sort_by_key(|p| p.timestamp);
+
+    let fingerprint = db.get_device_fingerprint(device.id)?;
+    let analysis = analyzer.evaluate_device(db, &device, device.first_seen, device.last_seen.max(device.first_seen + 1))?;
+    let alerted = analysis
+        .as_ref()
+        .map(|a| a.score >= analyzer.persistence_threshold())
+        .unwrap_or(false);
+
+    Ok(Some(DeviceDossier {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        fingerprint: DeviceFingerprint {
+            randomized_mac: is_randomized_mac(&device.mac),
+            vendor: db.get_device_vendor_attribution(device.id)?,
+            wifi_generation: db.get_device_wifi_generation(device.id)?,
+            capabilities: db.get_device_capabilities(device.id)?,
+            fingerprint: fingerprint.as_ref().map(|(hash, _)| hash.clone()),
+            fingerprint_version: fingerprint.map(|(_, version)| version),
+        },
+        probed_ssids: device_ssids(db, device.id)?,
+        sessions: presence_sessions(&probes, SESSION_GAP_SECS),
+        analysis,
+        alerted,
+        probes,
+        device,
+    }))
+}
+
+/// Split time-ordered probes into presence sessions
+pub fn presence_sessions(probes: &[Probe], gap_secs: i64) -> Vec<PresenceSession> {
+    let mut sessions: Vec<PresenceSession> = Vec::new();
+
+    for probe in probes {
+        match sessions.last_mut() {
+            Some(session) if probe.timestamp - session.end <= gap_secs => {
+                session.end = probe.timestamp;
+                session.probe_count += 1;
+            }
+            _ => sessions.push(PresenceSession {
+                start: probe.timestamp,
+                end: probe.timestamp,
+                probe_count: 1,
+            }),
+        }
+    }
+
+    sessions
+}
+
+/// Comment attached to one exported frame: the device and its label, why
+/// it was flagged, and what prowl stored for the probe
+pub fn frame_comment(dossier: &DeviceDossier, label: Option<&str>, probe: Option<&Probe>) -> String {
+    let mut lines = vec![match label {
+        Some(label) => format!("prowl: {} ({})", dossier.device.mac, label),
+        None => format!("prowl: {}", dossier.device.mac),
+    }];
+    if let Some(alert) = dossier.analysis.as_ref().filter(|_| dossier.alerted) {
+        lines.push(format!("Alert: persistence {:.0}%", alert.score * 100.0));
+        lines.extend(alert.reasons.iter().map(|r| format!("  {}", r)));
+    }
+    if let Some(probe) = probe {
+        if let (Some(lat), Some(lon)) = (probe.lat, probe.lon) {
+            lines.push(format!("GPS: {:.6}, {:.6}", lat, lon));
+        }
+        if let Some(distance) = probe.distance_m {
+            lines.push(format!("Estimated distance: {:.1} m", distance));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Copy the device's probe requests out of `inputs` into a pcapng at
+/// `output`, commenting each with `frame_comment`. Frames are matched to
+/// stored probes by capture time. Returns the number of frames written.
+#[cfg(feature = "pcap-export")]
+pub fn export_device_frames(
+    dossier: &DeviceDossier,
+    label: Option<&str>,
+    inputs: &[PathBuf],
+    output: &Path,
+) -> Result<usize> {
+    let stored: HashMap<(i64, u32), &Probe> = dossier
+        .probes
+        .iter()
+        .filter_map(|p| p.timestamp_micros.map(|micros| ((p.timestamp, micros), p)))
+        .collect();
+
+    let mut frames = Vec::new();
+    for input in inputs {
+        for record in read_pcap(input)? {
+            let from_device = parse_probe_request(&record.data, None)
+                .is_some_and(|probe| probe.source_mac == dossier.device.mac);
+            if from_device {
+                frames.push(record);
+            }
+        }
+    }
+    frames.sort_by_key(|r| (r.secs, r.micros));
+
+    let mut writer = PcapngWriter::create(output)?;
+    for frame in &frames {
+        let comment = frame_comment(dossier, label, stored.get(&(frame.secs, frame.micros)).copied());
+        writer.write_frame(frame.secs, frame.micros, &frame.data, Some(&comment))?;
+    }
+    writer.finish()?;
+    Ok(frames.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe_at(timestamp: i64) -> Probe {
+        Probe {
+            id: 0,
+            device_id: 1,
+            ssid: String::new(),
+            timestamp,
+            timestamp_micros: None,
+            lat: None,
+            lon: None,
+            signal_dbm: None,
+            channel: None,
+            distance_m: None,
+            gps_status: None,
+            bssid: None,
+            sequence_number: None,
+        }
+    }
+
+    #[test]
+    fn test_presence_sessions() {
+        let probes: Vec<Probe> = [0, 60, 120, 2000, 2100, 9000].iter().map(|&t| probe_at(t)).collect();
+        let sessions = presence_sessions(&probes, 600);
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].probe_count, 3);
+        assert_eq!((sessions[1].start, sessions[1].end), (2000, 2100));
+        assert_eq!(sessions[2].probe_count, 1);
+    }
+
+    #[test]
+    fn test_frame_comment() {
+        let dossier = DeviceDossier {
+            generated_at: String::new(),
+            device: Device {
+                id: 1,
+                mac: "AA:BB:CC:DD:EE:01".to_string(),
+                first_seen: 0,
+                last_seen: 60,
+            },
+            fingerprint: DeviceFingerprint {
+                randomized_mac: false,
+                vendor: None,
+                wifi_generation: None,
+                capabilities: None,
+                fingerprint: None,
+                fingerprint_version: None,
+            },
+            probed_ssids: Vec::new(),
+            sessions: Vec::new(),
+            analysis: None,
+            alerted: false,
+            probes: Vec::new(),
+        };
+        let mut probe = probe_at(30);
+        probe.lat = Some(52.52);
+        probe.lon = Some(13.405);
+
+        assert_eq!(frame_comment(&dossier, None, None), "prowl: AA:BB:CC:DD:EE:01");
+        assert_eq!(
+            frame_comment(&dossier, Some("grey van"), Some(&probe)),
+            "prowl: AA:BB:CC:DD:EE:01 (grey van)\nGPS: 52.520000, 13.405000"
+        );
+    }
+}
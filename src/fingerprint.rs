@@ -0,0 +1,219 @@
+//! Versioned device fingerprints from probe request capabilities.
+//!
+//! A fingerprint hashes the parts of a probe request that depend on the
+//! chipset and driver rather than on the network being probed for, so MACs
+//! rotated by the same phone tend to share one. Every stored fingerprint
+//! records the algorithm version that produced it. When the algorithm
+//! changes, `prowl db reprocess` recomputes old rows from the stored
+//! capabilities instead of mixing hashes from different algorithms.
+
+use crate::parser::ProbeCapabilities;
+
+/// A way of turning capabilities into a fingerprint string
+pub trait FingerprintAlgorithm: Sync {
+    /// Stored with each fingerprint; bump on any change to the output
+    fn version(&self) -> u32;
+
+    fn fingerprint(&self, caps: &ProbeCapabilities) -> String;
+}
+
+/// Rates, HT/VHT/HE capabilities, IE order and vendor IEs, hashed with FNV-1a
+pub struct IeOrderV1;
+
+impl FingerprintAlgorithm for IeOrderV1 {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn fingerprint(&self, caps: &ProbeCapabilities) -> String {
+        format!("{:016x}", fnv1a(v1_signature(caps).as_bytes()))
+    }
+}
+
+fn v1_signature(caps: &ProbeCapabilities) -> String {
+    let mut canonical = String::new();
+    let rates: Vec<String> = caps
+        .supported_rates_mbps
+        .iter()
+        .chain(&caps.extended_rates_mbps)
+        .map(|r| format!("{:.1}", r))
+        .collect();
+    canonical.push_str(&rates.join(","));
+    canonical.push_str(&format!("|{}{}{}", caps.has_ht as u8, caps.has_vht as u8, caps.has_he as u8));
+    if let Some(ht) = &caps.ht_caps {
+        canonical.push_str(&format!(
+            "|ht:{}{}{}{}{}",
+            ht.channel_width_40mhz as u8, ht.short_gi_20 as u8, ht.short_gi_40 as u8, ht.tx_stbc as u8, ht.rx_stbc
+        ));
+    }
+    if let Some(vht) = &caps.vht_caps {
+        canonical.push_str(&format!(
+            "|vht:{}/{}{}{}{}{}",
+            vht.max_mpdu_length,
+            vht.supported_channel_width,
+            vht.short_gi_80 as u8,
+            vht.short_gi_160 as u8,
+            vht.su_beamformer as u8,
+            vht.mu_beamformer as u8
+        ));
+    }
+    let ies: Vec<String> = caps.raw_ie_ids.iter().map(|id| id.to_string()).collect();
+    canonical.push_str(&format!("|ies:{}", ies.join(",")));
+    for vendor in &caps.vendor_ies {
+        canonical.push_str(&format!("|v:{}/{}", vendor.oui, vendor.oui_type));
+    }
+    canonical
+}
+
+/// Wi-Fi taxonomy style: the full element order, with vendor elements by
+/// OUI and type, plus the raw rate sets and HT, VHT, HE and extended
+/// capability bits. The SSID and channel are left out, as are the bits of
+/// HT capabilities that change with power saving. Capabilities stored
+/// before the element list was kept fall back to the v1 signature.
+pub struct TaxonomyV2;
+
+impl FingerprintAlgorithm for TaxonomyV2 {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn fingerprint(&self, caps: &ProbeCapabilities) -> String {
+        let signature = taxonomy_signature(caps).unwrap_or_else(|| v1_signature(caps));
+        format!("{:016x}", fnv1a(signature.as_bytes()))
+    }
+}
+
+/// e.g. `ies:0,1,50,45,127,221(0050f2/8),255.35|rates:...|htcap:...`; None
+/// without an element list
+pub fn taxonomy_signature(caps: &ProbeCapabilities) -> Option<String> {
+    if caps.information_elements.is_empty() {
+        return None;
+    }
+
+    let mut order = Vec::new();
+    let mut fields = Vec::new();
+    for ie in &caps.information_elements {
+        match (ie.id, ie.ext_id) {
+            (221, _) if ie.data.len() >= 4 => {
+                order.push(format!("221({}/{})", hex(&ie.data[..3]), ie.data[3]))
+            }
+            (255, Some(ext)) => order.push(format!("255.{}", ext)),
+            (id, _) => order.push(id.to_string()),
+        }
+        match (ie.id, ie.ext_id) {
+            (1 | 50, _) => fields.push(format!("rates:{}", hex(&ie.data))),
+            (45, _) if ie.data.len() >= 7 => {
+                // SM power save (bits 2-3) follows the battery, not the chipset
+                let cap_info = u16::from_le_bytes([ie.data[0], ie.data[1]]) & !0x000c;
+                fields.push(format!("htcap:{:04x}", cap_info));
+                fields.push(format!("htagg:{:02x}", ie.data[2]));
+                fields.push(format!("htmcs:{}", hex(&ie.data[3..7])));
+            }
+            (191, _) if ie.data.len() >= 4 => fields.push(format!("vhtcap:{}", hex(&ie.data[..4]))),
+            (127, _) => fields.push(format!("extcap:{}", hex(&ie.data))),
+            (255, Some(35)) => fields.push(format!("hecap:{}", hex(&ie.data[..ie.data.len().min(17)]))),
+            _ => {}
+        }
+    }
+
+    Some(format!("ies:{}|{}", order.join(","), fields.join("|")))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Every known algorithm, oldest first; the last one is current
+static ALGORITHMS: &[&dyn FingerprintAlgorithm] = &[&IeOrderV1, &TaxonomyV2];
+
+/// The algorithm used for newly captured probes
+pub fn current() -> &'static dyn FingerprintAlgorithm {
+    ALGORITHMS[ALGORITHMS.len() - 1]
+}
+
+/// Look up an algorithm by the version stored next to a fingerprint
+pub fn algorithm(version: u32) -> Option<&'static dyn FingerprintAlgorithm> {
+    ALGORITHMS.iter().copied().find(|a| a.version() == version)
+}
+
+/// 64-bit FNV-1a; stable across Rust releases, unlike `DefaultHasher`
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::InformationElement;
+
+    fn caps(ie_ids: &[u8]) -> ProbeCapabilities {
+        ProbeCapabilities {
+            supported_rates_mbps: vec![1.0, 2.0, 5.5, 11.0],
+            has_ht: true,
+            raw_ie_ids: ie_ids.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_channel_and_ssid_fields() {
+        let mut a = caps(&[0, 1, 45, 127]);
+        let b = caps(&[0, 1, 45, 127]);
+        a.ds_channel = Some(11);
+        let algo = current();
+        assert_eq!(algo.fingerprint(&a), algo.fingerprint(&b));
+        assert_eq!(algo.fingerprint(&a).len(), 16);
+        assert_ne!(algo.fingerprint(&a), algo.fingerprint(&caps(&[0, 1, 127, 45])));
+    }
+
+    fn element(id: u8, data: &[u8]) -> InformationElement {
+        InformationElement {
+            id,
+            ext_id: None,
+            data: data.to_vec(),
+        }
+    }
+
+    fn taxonomy_caps(ssid: &[u8], channel: u8, ht_cap_info: u8) -> ProbeCapabilities {
+        ProbeCapabilities {
+            information_elements: vec![
+                element(0, ssid),
+                element(1, &[0x02, 0x04, 0x0b, 0x16]),
+                element(3, &[channel]),
+                element(45, &[ht_cap_info, 0x01, 0x17, 0xff, 0xff, 0, 0]),
+                element(221, &[0x00, 0x50, 0xf2, 0x08, 0x00, 0x10]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_taxonomy_fingerprint() {
+        let algo = TaxonomyV2;
+        let home = taxonomy_caps(b"HomeNet", 1, 0x6f);
+        // Another SSID, channel and SM power save state: same device
+        assert_eq!(algo.fingerprint(&home), algo.fingerprint(&taxonomy_caps(b"Cafe", 11, 0x63)));
+        // A different 40 MHz bit is a different chipset
+        assert_ne!(algo.fingerprint(&home), algo.fingerprint(&taxonomy_caps(b"HomeNet", 1, 0x6d)));
+        assert_eq!(
+            taxonomy_signature(&home).unwrap(),
+            "ies:0,1,3,45,221(0050f2/8)|rates:02040b16|htcap:0163|htagg:17|htmcs:ffff0000"
+        );
+
+        // Older capabilities without an element list hash as in v1
+        let old = caps(&[0, 1, 45, 127]);
+        assert_eq!(algo.fingerprint(&old), IeOrderV1.fingerprint(&old));
+    }
+
+    #[test]
+    fn test_algorithm_lookup() {
+        assert_eq!(algorithm(current().version()).unwrap().version(), current().version());
+        assert!(algorithm(0).is_none());
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    }
+}
@@ -0,0 +1,109 @@
+//! Named output formats.
+//!
+//! Report types and export formats are enums whose `ALL` lists every value
+//! in one place. `Format` turns a name from the command line into a variant
+//! and builds both the `--help` text and the error for a name it doesn't
+//! know from that list, so a new format only has to be added to its enum.
+
+/// A set of named formats a command chooses between
+pub trait Format: Copy + Sized + 'static {
+    /// What the values are called in messages, e.g. "report type"
+    const KIND: &'static str;
+    /// Every value, in the order they are listed
+    const ALL: &'static [Self];
+
+    /// Name given on the command line
+    fn name(self) -> &'static str;
+    /// One-line description for `--help`
+    fn description(self) -> &'static str;
+
+    /// Look up a value by name, ignoring case
+    fn parse(name: &str) -> Result<Self, UnknownFormat> {
+        let name = name.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| UnknownFormat {
+                kind: Self::KIND,
+                name: name.to_string(),
+                supported: Self::names(),
+            })
+    }
+
+    /// Supported names, comma separated
+    fn names() -> String {
+        Self::ALL.iter().map(|format| format.name()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Short help listing the names
+    fn help() -> String {
+        format!("{} ({})", capitalize(Self::KIND), Self::names())
+    }
+
+    /// Long help with a line per value
+    fn long_help() -> String {
+        let width = Self::ALL.iter().map(|format| format.name().len()).max().unwrap_or(0);
+        let mut help = format!("{}:", capitalize(Self::KIND));
+        for format in Self::ALL {
+            help.push_str(&format!("\n  {:<width$}  {}", format.name(), format.description(), width = width));
+        }
+        help
+    }
+}
+
+/// A format name that isn't in the registry
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown {kind}: {name} (supported: {supported})")]
+pub struct UnknownFormat {
+    pub kind: &'static str,
+    pub name: String,
+    pub supported: String,
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Shape {
+        Circle,
+        Square,
+    }
+
+    impl Format for Shape {
+        const KIND: &'static str = "shape";
+        const ALL: &'static [Self] = &[Shape::Circle, Shape::Square];
+
+        fn name(self) -> &'static str {
+            match self {
+                Shape::Circle => "circle",
+                Shape::Square => "square",
+            }
+        }
+
+        fn description(self) -> &'static str {
+            match self {
+                Shape::Circle => "Round",
+                Shape::Square => "Four corners",
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_lists_supported_names() {
+        assert_eq!(Shape::parse("Square"), Ok(Shape::Square));
+        let err = Shape::parse("hexagon").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown shape: hexagon (supported: circle, square)");
+        assert_eq!(Shape::help(), "Shape (circle, square)");
+        assert_eq!(Shape::long_help(), "Shape:\n  circle  Round\n  square  Four corners");
+    }
+}
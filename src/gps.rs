@@ -1,17 +1,19 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use log::{debug, info, warn};
 use std::io::{BufRead, BufReader, Write};
 use std::net::TcpStream;
 use std::ops::RangeInclusive;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 
 pub struct GpsClient {
     host: String,
     port: u16,
+    report_tx: Option<mpsc::Sender<GpsReport>>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -20,12 +22,43 @@ pub struct GpsPosition {
     pub lon: f64,
     pub alt: Option<f64>,
     pub speed: Option<f64>,
+    /// Course over ground in degrees from true north
+    pub track: Option<f64>,
+    /// gpsd fix mode: 0/1 = no fix, 2 = 2D, 3 = 3D
+    pub mode: u8,
     pub timestamp: i64,
 }
 
+/// Satellite summary from a gpsd SKY message
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GpsSky {
+    pub satellites_used: Option<u32>,
+    pub satellites_visible: Option<u32>,
+    pub hdop: Option<f64>,
+}
+
+/// Everything the client extracts from the gpsd stream
+#[derive(Debug, Clone, Copy)]
+pub enum GpsReport {
+    Position(GpsPosition),
+    /// TPV without a usable position, carrying the fix mode
+    NoFix(u8),
+    Sky(GpsSky),
+}
+
 impl GpsClient {
     pub fn new(host: String, port: u16) -> Self {
-        GpsClient { host, port }
+        GpsClient {
+            host,
+            port,
+            report_tx: None,
+        }
+    }
+
+    /// Also forward fix quality and satellite reports (best effort, never blocks)
+    pub fn with_reports(mut self, tx: mpsc::Sender<GpsReport>) -> Self {
+        self.report_tx = Some(tx);
+        self
     }
 
     pub async fn run(&self, tx: mpsc::Sender<(f64, f64)>, running: Arc<AtomicBool>) -> Result<()> {
@@ -39,10 +72,11 @@ impl GpsClient {
             let host = self.host.clone();
             let port = self.port;
             let tx_clone = tx.clone();
+            let report_tx = self.report_tx.clone();
             let running_clone = running.clone();
 
             let result = tokio::task::spawn_blocking(move || {
-                connect_and_read(&host, port, &tx_clone, &running_clone)
+                connect_and_read(&host, port, &tx_clone, report_tx.as_ref(), &running_clone)
             })
             .await;
 
@@ -68,6 +102,7 @@ fn connect_and_read(
     host: &str,
     port: u16,
     tx: &mpsc::Sender<(f64, f64)>,
+    report_tx: Option<&mpsc::Sender<GpsReport>>,
     running: &Arc<AtomicBool>,
 ) -> Result<()> {
     let addr = format!("{}:{}", host, port);
@@ -93,9 +128,19 @@ fn connect_and_read(
             Ok(json) => {
                 if let Some(pos) = parse_gpsd_json(&json) {
                     debug!("GPS: lat={}, lon={}", pos.lat, pos.lon);
+                    if let Some(report_tx) = report_tx {
+                        let _ = report_tx.try_send(GpsReport::Position(pos));
+                    }
                     if tx.blocking_send((pos.lat, pos.lon)).is_err() {
                         break;
                     }
+                } else if let Some(report_tx) = report_tx {
+                    if let Some(sky) = parse_gpsd_sky(&json) {
+                        let _ = report_tx.try_send(GpsReport::Sky(sky));
+                    } else if json.contains("\"class\":\"TPV\"") {
+                        let mode = extract_number(&json, "\"mode\":").unwrap_or(0.0) as u8;
+                        let _ = report_tx.try_send(GpsReport::NoFix(mode));
+                    }
                 }
             }
             Err(e) => {
@@ -112,6 +157,137 @@ fn connect_and_read(
     Ok(())
 }
 
+/// One report seen while testing the gpsd connection
+#[derive(Debug, Clone, Copy)]
+pub struct GpsSample {
+    /// Time since the test connected
+    pub elapsed: Duration,
+    pub report: GpsReport,
+    /// Age of a position when it arrived, from the TPV timestamp; includes
+    /// any clock offset between gpsd's receiver time and this host
+    pub latency: Option<Duration>,
+}
+
+/// What `prowl gps test` learned about the gpsd feed
+#[derive(Debug, Clone, Default)]
+pub struct GpsTestSummary {
+    pub connect_time: Duration,
+    /// TPV reports, with or without a fix
+    pub reports: usize,
+    pub fixes: usize,
+    /// Highest fix mode seen (2 = 2D, 3 = 3D)
+    pub best_mode: u8,
+    pub time_to_first_fix: Option<Duration>,
+    pub last_position: Option<GpsPosition>,
+    pub sky: Option<GpsSky>,
+    latencies: Vec<Duration>,
+    report_times: Vec<Duration>,
+}
+
+impl GpsTestSummary {
+    /// Take one line of the gpsd stream, received `elapsed` after connecting
+    /// at wall-clock time `now`. Returns the sample it carried, if any.
+    pub fn observe(&mut self, json: &str, elapsed: Duration, now: DateTime<Utc>) -> Option<GpsSample> {
+        let report = if let Some(pos) = parse_gpsd_json(json) {
+            GpsReport::Position(pos)
+        } else if let Some(sky) = parse_gpsd_sky(json) {
+            self.sky = Some(sky);
+            GpsReport::Sky(sky)
+        } else if json.contains("\"class\":\"TPV\"") {
+            GpsReport::NoFix(extract_number(json, "\"mode\":").unwrap_or(0.0) as u8)
+        } else {
+            return None;
+        };
+
+        let mut latency = None;
+        match report {
+            GpsReport::Position(pos) => {
+                self.reports += 1;
+                self.report_times.push(elapsed);
+                self.fixes += 1;
+                self.best_mode = self.best_mode.max(pos.mode);
+                self.time_to_first_fix.get_or_insert(elapsed);
+                self.last_position = Some(pos);
+                latency = extract_string(json, "\"time\":")
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .and_then(|t| (now - t.with_timezone(&Utc)).to_std().ok());
+                if let Some(latency) = latency {
+                    self.latencies.push(latency);
+                }
+            }
+            GpsReport::NoFix(mode) => {
+                self.reports += 1;
+                self.report_times.push(elapsed);
+                self.best_mode = self.best_mode.max(mode);
+            }
+            GpsReport::Sky(_) => {}
+        }
+        Some(GpsSample { elapsed, report, latency })
+    }
+
+    pub fn mean_latency(&self) -> Option<Duration> {
+        mean(&self.latencies)
+    }
+
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.latencies.iter().max().copied()
+    }
+
+    /// Mean time between TPV reports, i.e. the receiver's update rate
+    pub fn mean_interval(&self) -> Option<Duration> {
+        let gaps: Vec<Duration> = self.report_times.windows(2).map(|w| w[1] - w[0]).collect();
+        mean(&gaps)
+    }
+}
+
+fn mean(durations: &[Duration]) -> Option<Duration> {
+    if durations.is_empty() {
+        return None;
+    }
+    Some(durations.iter().sum::<Duration>() / durations.len() as u32)
+}
+
+/// Connect to gpsd and watch its stream for `duration`, handing every
+/// report to `on_sample` as it arrives
+pub fn test_gpsd(
+    host: &str,
+    port: u16,
+    duration: Duration,
+    running: &AtomicBool,
+    mut on_sample: impl FnMut(&GpsSample),
+) -> Result<GpsTestSummary> {
+    let addr = format!("{}:{}", host, port);
+    let started = Instant::now();
+    let mut stream =
+        TcpStream::connect(&addr).with_context(|| format!("Failed to connect to gpsd at {}", addr))?;
+    let mut summary = GpsTestSummary {
+        connect_time: started.elapsed(),
+        ..Default::default()
+    };
+
+    stream.set_read_timeout(Some(Duration::from_millis(500)))?;
+    stream.write_all(b"?WATCH={\"enable\":true,\"json\":true}\n")?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while started.elapsed() < duration && running.load(Ordering::SeqCst) {
+        match reader.read_line(&mut line) {
+            Ok(0) => anyhow::bail!("gpsd closed the connection"),
+            Ok(_) => {
+                if let Some(sample) = summary.observe(line.trim(), started.elapsed(), Utc::now()) {
+                    on_sample(&sample);
+                }
+                line.clear();
+            }
+            // A partial line stays in `line` until the rest arrives
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(summary)
+}
+
 fn parse_gpsd_json(json: &str) -> Option<GpsPosition> {
     // Simple JSON parsing for gpsd TPV (Time-Position-Velocity) messages
     // Format: {"class":"TPV","lat":..., "lon":..., ...}
@@ -137,13 +313,52 @@ fn parse_gpsd_json(json: &str) -> Option<GpsPosition> {
 
     let alt = extract_number(json, "\"alt\":");
     let speed = extract_number(json, "\"speed\":");
+    let track = extract_number(json, "\"track\":");
+    let mode = extract_number(json, "\"mode\":").unwrap_or(2.0) as u8;
 
     Some(GpsPosition {
         lat,
         lon,
         alt,
         speed,
-        timestamp: chrono::Utc::now().timestamp(),
+        track,
+        mode,
+        timestamp: Utc::now().timestamp(),
+    })
+}
+
+fn parse_gpsd_sky(json: &str) -> Option<GpsSky> {
+    // SKY messages carry HDOP and the satellite list
+    // Format: {"class":"SKY","hdop":0.9,"nSat":12,"uSat":8,"satellites":[{...,"used":true},...]}
+
+    if !json.contains("\"class\":\"SKY\"") {
+        return None;
+    }
+
+    let hdop = extract_number(json, "\"hdop\":");
+
+    // Newer gpsd reports counts directly; older versions only list satellites
+    let satellites_visible = extract_number(json, "\"nSat\":")
+        .map(|n| n as u32)
+        .or_else(|| {
+            json.contains("\"satellites\":")
+                .then(|| json.matches("\"PRN\":").count() as u32)
+        });
+    let satellites_used = extract_number(json, "\"uSat\":")
+        .map(|n| n as u32)
+        .or_else(|| {
+            json.contains("\"satellites\":")
+                .then(|| json.matches("\"used\":true").count() as u32)
+        });
+
+    if hdop.is_none() && satellites_visible.is_none() {
+        return None;
+    }
+
+    Some(GpsSky {
+        satellites_used,
+        satellites_visible,
+        hdop,
     })
 }
 
@@ -159,6 +374,12 @@ fn extract_number(json: &str, key: &str) -> Option<f64> {
     rest[..end].trim().parse().ok()
 }
 
+fn extract_string<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let start = json.find(key)? + key.len();
+    let rest = json[start..].trim_start().strip_prefix('"')?;
+    rest.find('"').map(|end| &rest[..end])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +392,18 @@ mod tests {
         assert!((pos.lat - 33.4484).abs() < 0.0001);
         assert!((pos.lon - (-112.0740)).abs() < 0.0001);
         assert!((pos.alt.unwrap() - 350.0).abs() < 0.1);
+        assert_eq!(pos.mode, 3);
+        assert!((pos.track.unwrap() - 90.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_parse_gpsd_sky() {
+        let json = r#"{"class":"SKY","device":"/dev/ttyACM0","hdop":1.2,"satellites":[{"PRN":5,"el":45,"az":120,"ss":38,"used":true},{"PRN":12,"el":10,"az":300,"ss":20,"used":false},{"PRN":17,"el":60,"az":40,"ss":41,"used":true}]}"#;
+
+        let sky = parse_gpsd_sky(json).unwrap();
+        assert_eq!(sky.satellites_visible, Some(3));
+        assert_eq!(sky.satellites_used, Some(2));
+        assert!((sky.hdop.unwrap() - 1.2).abs() < 0.01);
     }
 
     #[test]
@@ -178,4 +411,29 @@ mod tests {
         let json = r#"{"class":"VERSION","release":"3.24","rev":"3.24"}"#;
         assert!(parse_gpsd_json(json).is_none());
     }
+
+    #[test]
+    fn test_gps_test_summary() {
+        let now = DateTime::parse_from_rfc3339("2024-01-15T10:30:01.250Z").unwrap().with_timezone(&Utc);
+        let mut summary = GpsTestSummary::default();
+
+        assert!(summary.observe(r#"{"class":"VERSION","release":"3.24"}"#, Duration::ZERO, now).is_none());
+        summary.observe(r#"{"class":"TPV","mode":1}"#, Duration::from_millis(100), now).unwrap();
+        summary.observe(r#"{"class":"SKY","hdop":1.1,"nSat":9,"uSat":6}"#, Duration::from_millis(400), now);
+        let sample = summary
+            .observe(
+                r#"{"class":"TPV","mode":3,"time":"2024-01-15T10:30:01.000Z","lat":33.4484,"lon":-112.0740}"#,
+                Duration::from_millis(1100),
+                now,
+            )
+            .unwrap();
+
+        assert_eq!(sample.latency, Some(Duration::from_millis(250)));
+        assert_eq!(summary.reports, 2);
+        assert_eq!(summary.fixes, 1);
+        assert_eq!(summary.best_mode, 3);
+        assert_eq!(summary.time_to_first_fix, Some(Duration::from_millis(1100)));
+        assert_eq!(summary.mean_interval(), Some(Duration::from_millis(1000)));
+        assert_eq!(summary.sky.unwrap().satellites_used, Some(6));
+    }
 }
@@ -0,0 +1,226 @@
+//! HTTP health endpoint for containerized sensors.
+//!
+//! Docker/Podman healthchecks and orchestrators poll `GET /health` on the
+//! address in `health.listen`. It answers 200 with the capture counters
+//! while capture runs, and 503 once it is stopping or, with
+//! `health.stale_secs` set, when no probe has been stored for that long.
+//! A paused capture is quiet on purpose and still answers 200.
+//! The server is a blocking accept loop on its own thread; requests are
+//! tiny and rare, so there is no need for an HTTP stack.
+
+use crate::config::HealthConfig;
+use crate::status::CaptureStats;
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+/// The whole request, however slowly the client sends it, since one slow
+/// client holds up every healthcheck behind it
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+/// Request line and headers together; a healthcheck sends a few hundred bytes
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+/// Status code and JSON body for a health request
+pub fn health_response(
+    stats: &CaptureStats,
+    running: bool,
+    uptime: Duration,
+    stale_secs: Option<u64>,
+    now: i64,
+) -> (u16, serde_json::Value) {
+    let snapshot = stats.snapshot();
+    // Before the first probe, staleness counts from startup
+    let quiet_secs = match stats.last_probe_at() {
+        Some(at) => (now - at).max(0) as u64,
+        None => uptime.as_secs(),
+    };
+
+    let status = if !running {
+        "stopping"
+    } else if snapshot.paused {
+        "paused"
+    } else if stale_secs.is_some_and(|limit| quiet_secs > limit) {
+        "stale"
+    } else {
+        "ok"
+    };
+    let body = json!({
+        "status": status,
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": uptime.as_secs(),
+        "probes": snapshot.probes,
+        "devices": snapshot.devices,
+        "last_probe_at": stats.last_probe_at(),
+        "kernel_dropped": snapshot.kernel_dropped,
+        "queue_dropped": snapshot.queue_dropped,
+        "disk_free_mb": snapshot.disk.free_mb,
+        "disk_full_in_secs": snapshot.disk.full_in_secs,
+        "disk_shedding": snapshot.disk.shedding,
+    });
+    (if matches!(status, "ok" | "paused") { 200 } else { 503 }, body)
+}
+
+/// Serve the health endpoint until `running` clears. Returns `None` when
+/// no listen address is configured.
+pub fn spawn_health_server(
+    config: &HealthConfig,
+    stats: Arc<CaptureStats>,
+    running: Arc<AtomicBool>,
+) -> Result<Option<thread::JoinHandle<()>>> {
+    let listen = match &config.listen {
+        Some(listen) => listen,
+        None => return Ok(None),
+    };
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Failed to bind health endpoint on {}", listen))?;
+    // Non-blocking so the loop notices shutdown between requests
+    listener.set_nonblocking(true)?;
+    info!("Health endpoint listening on http://{}/health", listen);
+
+    let stale_secs = config.stale_secs;
+    Ok(Some(thread::spawn(move || {
+        let started = Instant::now();
+        while running.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let (code, body) = health_response(
+                        &stats,
+                        running.load(Ordering::SeqCst),
+                        started.elapsed(),
+                        stale_secs,
+                        chrono::Utc::now().timestamp(),
+                    );
+                    if let Err(e) = respond(stream, code, &body) {
+                        debug!("Health request failed: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                Err(e) => {
+                    debug!("Health endpoint accept failed: {}", e);
+                    thread::sleep(ACCEPT_POLL);
+                }
+            }
+        }
+    })))
+}
+
+/// Reads from `stream` until `deadline`, failing once it has passed
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "health request took too long"));
+        }
+        let mut stream = self.stream;
+        stream.set_read_timeout(Some(remaining))?;
+        stream.read(buf)
+    }
+}
+
+fn respond(stream: TcpStream, code: u16, body: &serde_json::Value) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut reader = BufReader::new(DeadlineReader { stream: &stream, deadline }.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the client sees a clean close
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let (code, body) = match path.split('?').next() {
+        Some("/health") | Some("/") => (code, body.to_string()),
+        _ => (404, json!({ "error": "not found" }).to_string()),
+    };
+    let reason = match code {
+        200 => "OK",
+        404 => "Not Found",
+        _ => "Service Unavailable",
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_response() {
+        let stats = CaptureStats::new();
+        let now = chrono::Utc::now().timestamp();
+
+        // Nothing heard yet, but only just started
+        let (code, body) = health_response(&stats, true, Duration::from_secs(30), Some(600), now);
+        assert_eq!(code, 200);
+        assert_eq!(body["status"], "ok");
+
+        let (code, body) = health_response(&stats, true, Duration::from_secs(900), Some(600), now);
+        assert_eq!(code, 503);
+        assert_eq!(body["status"], "stale");
+
+        stats.record_probe(true);
+        let (code, body) = health_response(&stats, true, Duration::from_secs(900), Some(600), now);
+        assert_eq!(code, 200);
+        assert_eq!(body["probes"], 1);
+
+        stats.paused.store(true, Ordering::Relaxed);
+        let (code, body) = health_response(&stats, true, Duration::from_secs(99_999), Some(600), now);
+        assert_eq!(code, 200);
+        assert_eq!(body["status"], "paused");
+
+        let (code, body) = health_response(&stats, false, Duration::from_secs(900), None, now);
+        assert_eq!(code, 503);
+        assert_eq!(body["status"], "stopping");
+    }
+
+    #[test]
+    fn test_slow_requests_are_cut_off() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let body = json!({ "status": "ok" });
+
+        // A byte at a time without a newline, well within the per-read
+        // timeout but for longer than the request may take
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let started = Instant::now();
+            while started.elapsed() < REQUEST_TIMEOUT * 2 {
+                if stream.write_all(b"a").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let started = Instant::now();
+        let _ = respond(stream, 200, &body);
+        assert!(started.elapsed() < REQUEST_TIMEOUT + Duration::from_millis(500));
+        client.join().unwrap();
+    }
+}
@@ -0,0 +1,399 @@
+//! Privilege separation: a small capture helper runs as root and streams
+//! frames to an unprivileged prowl over a UNIX socket.
+//!
+//! `sudo prowl capture-helper` opens `capture.helper_backend` on the
+//! interface, hops channels, and writes every frame it reads to
+//! `capture.helper_socket`. The prowl doing the work (capture, TUI, watch)
+//! runs as an ordinary user with `"backend": "helper"`, listens on that
+//! socket, and never needs root, so the database, analysis and TUI code
+//! never run with privileges.
+//!
+//! The helper forwards frames as captured rather than parsed probes: it
+//! never parses what the radio hands it, so a malformed frame can't reach
+//! root through the parser, and the main process still sees everything it
+//! would have captured itself (beacons, deauths, the raw frame for the pcap
+//! writer and hex view). Each frame is a 20-byte big-endian header (magic,
+//! payload length, capture seconds, microseconds) followed by the frame.
+//!
+//! The listener only accepts root or its own user as the helper, and the
+//! socket is created mode 0600. The helper in turn only forwards frames to
+//! a listener running as `capture.run_as`, or as the owner of the config
+//! file when that isn't set, so whoever can create the socket path can't
+//! collect the radio's traffic. Peers are identified with `SO_PEERCRED` on
+//! Linux and `getpeereid` on the BSDs and macOS; elsewhere every peer is
+//! refused. It reconnects whenever prowl restarts, so it can stay running
+//! as a service.
+
+use crate::channels::ChannelHopper;
+use crate::config::CaptureConfig;
+use crate::privileges::{lookup_user, PrivilegeDrop, CAP_NET_ADMIN};
+use crate::source::{capture_filter, open_source, FrameTime, PacketSource};
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// "PRWL"
+const MAGIC: u32 = 0x5052_574c;
+const HEADER_LEN: usize = 20;
+/// Larger than any 802.11 frame with its radiotap header
+const MAX_FRAME_LEN: usize = 64 * 1024;
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+/// How often the helper tries again while prowl isn't listening
+const CONNECT_RETRY: Duration = Duration::from_secs(1);
+/// How long the helper waits on a listener that stopped reading
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One frame as sent over the socket
+pub fn encode_frame(data: &[u8], time: FrameTime) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN + data.len());
+    buf.extend_from_slice(&MAGIC.to_be_bytes());
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(&time.secs.to_be_bytes());
+    buf.extend_from_slice(&time.micros.to_be_bytes());
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// The first frame in `buf` and the bytes it used, or `None` until the
+/// whole frame has arrived
+pub fn decode_frame(buf: &[u8]) -> Result<Option<(Vec<u8>, FrameTime, usize)>> {
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let word = |at: usize| u32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+    if word(0) != MAGIC {
+        bail!("Not a prowl capture helper stream (bad frame magic)");
+    }
+    let len = word(4) as usize;
+    if len > MAX_FRAME_LEN {
+        bail!("Capture helper frame of {} bytes is too large", len);
+    }
+    if buf.len() < HEADER_LEN + len {
+        return Ok(None);
+    }
+    let mut secs = [0u8; 8];
+    secs.copy_from_slice(&buf[8..16]);
+    let time = FrameTime {
+        secs: i64::from_be_bytes(secs),
+        micros: word(16),
+    };
+    Ok(Some((buf[HEADER_LEN..HEADER_LEN + len].to_vec(), time, HEADER_LEN + len)))
+}
+
+/// Frames streamed by `prowl capture-helper`, the "helper" backend
+pub struct HelperSource {
+    listener: UnixListener,
+    path: PathBuf,
+    helper: Option<UnixStream>,
+    rx: Vec<u8>,
+    timeout: Duration,
+    current: Vec<u8>,
+}
+
+impl HelperSource {
+    /// Listen for the helper on `capture.helper_socket`
+    pub fn open(config: &CaptureConfig, timeout_ms: i32) -> Result<Self> {
+        let path = PathBuf::from(&config.helper_socket);
+        if path.exists() {
+            // A socket nobody answers on was left behind by an earlier run
+            if UnixStream::connect(&path).is_ok() {
+                bail!("Another prowl is already listening for the capture helper on {}", path.display());
+            }
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove the stale socket {}", path.display()))?;
+        }
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to listen for the capture helper on {}", path.display()))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        // Non-blocking so reads time out while the helper isn't connected
+        listener.set_nonblocking(true)?;
+        info!("Waiting for `prowl capture-helper` on {}", path.display());
+        Ok(HelperSource {
+            listener,
+            path,
+            helper: None,
+            rx: Vec::new(),
+            timeout: Duration::from_millis(timeout_ms.max(1) as u64),
+            current: Vec::new(),
+        })
+    }
+
+    fn accept(&mut self, deadline: Instant) -> Result<()> {
+        while Instant::now() < deadline {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if !trusted_peer(&stream) {
+                        warn!("Refused a capture helper that is neither root nor this user");
+                        continue;
+                    }
+                    stream.set_nonblocking(false)?;
+                    info!("Capture helper connected on {}", self.path.display());
+                    self.helper = Some(stream);
+                    self.rx.clear();
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+                Err(e) => return Err(e).context("Failed to accept the capture helper"),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_helper(&mut self, deadline: Instant) -> Result<Option<(Vec<u8>, FrameTime)>> {
+        loop {
+            let helper = match self.helper.as_mut() {
+                Some(helper) => helper,
+                None => return Ok(None),
+            };
+            if let Some((frame, time, used)) = decode_frame(&self.rx)? {
+                self.rx.drain(..used);
+                return Ok(Some((frame, time)));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            helper.set_read_timeout(Some(remaining))?;
+            let mut chunk = [0u8; 16384];
+            match helper.read(&mut chunk) {
+                Ok(0) => bail!("Capture helper disconnected"),
+                Ok(n) => self.rx.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+                Err(e) => return Err(e).context("Failed to read from the capture helper"),
+            }
+        }
+    }
+}
+
+impl PacketSource for HelperSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.next_timestamped()?.map(|(data, _)| data))
+    }
+
+    fn name(&self) -> &'static str {
+        "helper"
+    }
+
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        let deadline = Instant::now() + self.timeout;
+        if self.helper.is_none() {
+            self.accept(deadline)?;
+        }
+        // A helper restarting ends its session, not the capture
+        match self.read_helper(deadline) {
+            Ok(Some((frame, time))) => {
+                self.current = frame;
+                Ok(Some((self.current.as_slice(), Some(time))))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                warn!("{:#}; waiting for the capture helper to reconnect", e);
+                self.helper = None;
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Drop for HelperSource {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Only root (the helper) or the user prowl runs as may feed it frames
+fn trusted_peer(stream: &UnixStream) -> bool {
+    peer_is(stream, &[0, unsafe { libc::geteuid() }])
+}
+
+/// Whether the process on the other end of `stream` runs as one of `uids`
+#[cfg(target_os = "linux")]
+fn peer_is(stream: &UnixStream, uids: &[u32]) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    rc == 0 && uids.contains(&cred.uid)
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+fn peer_is(stream: &UnixStream, uids: &[u32]) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let (mut uid, mut gid) = (0, 0);
+    let rc = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    rc == 0 && uids.contains(&uid)
+}
+
+/// Nowhere to ask who the peer is, so nobody is trusted
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+)))]
+fn peer_is(_stream: &UnixStream, _uids: &[u32]) -> bool {
+    false
+}
+
+/// The user the helper may forward frames to: `capture.run_as`, or the
+/// owner of the config file at `config_path`
+pub fn listener_uid(config: &CaptureConfig, config_path: &Path) -> Result<u32> {
+    if let Some(name) = &config.run_as {
+        return Ok(lookup_user(name)?.uid);
+    }
+    let metadata = std::fs::metadata(config_path).with_context(|| {
+        format!(
+            "Set capture.run_as or use a config file owned by the prowl user; can't read {}",
+            config_path.display()
+        )
+    })?;
+    Ok(metadata.uid())
+}
+
+/// The helper itself: open the radio described by `config`, hop its
+/// channels, and forward frames to `config.helper_socket` until `running`
+/// is cleared. Frames only go to a listener running as root or
+/// `listener_uid`.
+pub fn run_helper(config: &CaptureConfig, listener_uid: u32, running: Arc<AtomicBool>) -> Result<()> {
+    let mut source = open_source(config, Some(capture_filter(config).as_str()), 1000)
+        .context("Failed to activate capture")?;
+    info!("Capturing on {} ({})", config.interface, source.name());
+
+    // The helper drops root like any other capture; only its hopper thread
+    // keeps CAP_NET_ADMIN
+    let _hopper = if config.backend.has_radio() {
+        let hopper = ChannelHopper::new(
+            config.interface.clone(),
+            config.channels.clone(),
+            config.hop_interval_ms,
+        )
+        .with_band_split(config.second_interface.clone());
+        let privilege_drop = PrivilegeDrop::plan(config)?;
+        Some(hopper.spawn_thread(running.clone(), move || match privilege_drop {
+            Some(plan) => plan.apply(&[CAP_NET_ADMIN]),
+            None => Ok(()),
+        })?)
+    } else {
+        None
+    };
+
+    let path = Path::new(&config.helper_socket);
+    let mut stream: Option<UnixStream> = None;
+    let mut waiting = false;
+    while running.load(Ordering::SeqCst) {
+        let conn = match stream.as_mut() {
+            Some(conn) => conn,
+            None => match UnixStream::connect(path) {
+                Ok(conn) if !peer_is(&conn, &[0, listener_uid]) => {
+                    if !waiting {
+                        warn!(
+                            "Refusing to forward frames to {}: it isn't listened on by uid {}",
+                            path.display(),
+                            listener_uid
+                        );
+                        waiting = true;
+                    }
+                    std::thread::sleep(CONNECT_RETRY);
+                    continue;
+                }
+                Ok(conn) => {
+                    // A listener that stops reading mustn't stall capture
+                    conn.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                    info!("Forwarding frames to {}", path.display());
+                    waiting = false;
+                    stream.insert(conn)
+                }
+                Err(e) => {
+                    if !waiting {
+                        info!("Waiting for prowl to listen on {} ({})", path.display(), e);
+                        waiting = true;
+                    }
+                    std::thread::sleep(CONNECT_RETRY);
+                    continue;
+                }
+            },
+        };
+        let (data, time) = match source.next_timestamped()? {
+            Some(frame) => frame,
+            None => continue,
+        };
+        let frame = encode_frame(data, time.unwrap_or_else(FrameTime::now));
+        if let Err(e) = conn.write_all(&frame) {
+            warn!("prowl stopped listening ({}); waiting for it to come back", e);
+            stream = None;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let time = FrameTime {
+            secs: 1_700_000_000,
+            micros: 250_000,
+        };
+        let mut stream = encode_frame(&[0x00, 0x00, 0x08, 0x00, 0x40], time);
+        stream.extend(encode_frame(&[], time));
+
+        // Incomplete frames wait for more data
+        assert!(decode_frame(&stream[..HEADER_LEN + 4]).unwrap().is_none());
+
+        let (frame, decoded, used) = decode_frame(&stream).unwrap().unwrap();
+        assert_eq!(frame, vec![0x00, 0x00, 0x08, 0x00, 0x40]);
+        assert_eq!(decoded.secs, 1_700_000_000);
+        assert_eq!(decoded.micros, 250_000);
+        assert_eq!(used, HEADER_LEN + 5);
+        let (frame, _, used) = decode_frame(&stream[used..]).unwrap().unwrap();
+        assert!(frame.is_empty());
+        assert_eq!(used, HEADER_LEN);
+
+        let mut corrupt = stream.clone();
+        corrupt[0] ^= 0xff;
+        assert!(decode_frame(&corrupt).is_err());
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    ))]
+    #[test]
+    fn test_peer_uid_check() {
+        let (stream, _peer) = UnixStream::pair().unwrap();
+        let uid = unsafe { libc::geteuid() };
+        assert!(peer_is(&stream, &[uid]));
+        assert!(!peer_is(&stream, &[uid.wrapping_add(1)]));
+    }
+}
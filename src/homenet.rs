@@ -0,0 +1,129 @@
+//! Alerts for directed probes to your own networks.
+//!
+//! A device asking by name for one of `analysis.my_ssids` has that network
+//! in its saved list. Your own phones and laptops do, and so does anything
+//! set up by someone who learned the name. The first directed probe from
+//! each device for each of your networks is stored as a `my_ssid_probe`
+//! event as it is captured, whatever the device's persistence score, and
+//! shown in the TUI and by `prowl analyze`. Devices already known to probe
+//! for the network don't raise it again, even once retention has purged
+//! the probes that made them known; the alert itself is kept.
+
+use crate::config::AnalysisConfig;
+use crate::database::{Database, ProbeCapture};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Event type recorded in the events table for first probes to your networks
+pub const EVENT_MY_SSID_PROBE: &str = "my_ssid_probe";
+
+/// A device's first directed probe for one of your networks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MySsidProbe {
+    pub mac: String,
+    pub ssid: String,
+    pub timestamp: i64,
+    pub signal_dbm: Option<i32>,
+    /// The device was new to the database with this probe
+    pub new_device: bool,
+}
+
+impl MySsidProbe {
+    pub fn describe(&self) -> String {
+        let signal = self.signal_dbm.map(|s| format!(" at {} dBm", s)).unwrap_or_default();
+        let device = if self.new_device { "new device" } else { "device" };
+        format!("{} {} probed for your network {:?}{}", device, self.mac, self.ssid, signal)
+    }
+}
+
+/// Spots the first probe from each device for each of your networks
+#[derive(Debug, Clone)]
+pub struct MySsidWatch {
+    ssids: HashSet<String>,
+}
+
+impl MySsidWatch {
+    /// `None` when there are no networks to watch or the alert is off
+    pub fn new(config: &AnalysisConfig) -> Option<Self> {
+        let ssids: HashSet<String> = config.my_ssids.iter().filter(|s| !s.is_empty()).cloned().collect();
+        (config.alert_my_ssid_probes && !ssids.is_empty()).then_some(MySsidWatch { ssids })
+    }
+
+    /// Check a probe once it is stored; `new_device` is true if it created
+    /// the device
+    pub fn observe(
+        &self,
+        db: &Database,
+        capture: &ProbeCapture,
+        new_device: bool,
+    ) -> Result<Option<MySsidProbe>> {
+        if !self.ssids.contains(&capture.ssid) {
+            return Ok(None);
+        }
+        // The probe just stored is the only one from this device for it,
+        // and there was no alert for probes since purged
+        if db.count_device_ssid_probes(&capture.mac, &capture.ssid)? != 1
+            || db.has_device_ssid_event(EVENT_MY_SSID_PROBE, &capture.mac, &capture.ssid)?
+        {
+            return Ok(None);
+        }
+        Ok(Some(MySsidProbe {
+            mac: capture.mac.clone(),
+            ssid: capture.ssid.clone(),
+            timestamp: capture.timestamp,
+            signal_dbm: capture.signal_dbm,
+            new_device,
+        }))
+    }
+}
+
+/// First probes to your networks recorded since `since`, newest first
+pub fn my_ssid_probes(db: &Database, since: i64) -> Result<Vec<MySsidProbe>> {
+    let probes = db
+        .get_events_since(since, Some(EVENT_MY_SSID_PROBE))?
+        .into_iter()
+        .filter_map(|event| serde_json::from_str(event.data_json.as_deref()?).ok())
+        .collect();
+    Ok(probes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_first_probe_for_my_network() {
+        let db = Database::open_in_memory().unwrap();
+        let config = AnalysisConfig {
+            my_ssids: vec!["HomeNet".to_string()],
+            ..Config::default_config().analysis
+        };
+        let watch = MySsidWatch::new(&config).unwrap();
+
+        let observe = |mac: &str, ssid: &str, timestamp: i64| {
+            let probe = ProbeCapture::test(mac, ssid, timestamp);
+            let new_device = db.insert_probe(&probe).unwrap();
+            watch.observe(&db, &probe, new_device).unwrap()
+        };
+
+        let first = observe("AA:BB:CC:00:00:01", "HomeNet", 1_000).expect("alert");
+        assert!(first.new_device);
+        assert_eq!(
+            first.describe(),
+            "new device AA:BB:CC:00:00:01 probed for your network \"HomeNet\" at -60 dBm"
+        );
+        // Once per device and network, and never for other networks
+        assert!(observe("AA:BB:CC:00:00:01", "HomeNet", 1_060).is_none());
+        assert!(observe("AA:BB:CC:00:00:02", "CoffeeShop", 1_100).is_none());
+        let known = observe("AA:BB:CC:00:00:02", "HomeNet", 1_200).expect("alert");
+        assert!(!known.new_device);
+
+        let disabled = AnalysisConfig {
+            alert_my_ssid_probes: false,
+            ..config
+        };
+        assert!(MySsidWatch::new(&disabled).is_none());
+    }
+}
@@ -1,19 +1,32 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
 #[derive(Debug, Clone, Default)]
 pub struct IgnoreLists {
-    mac_list: HashSet<String>,
+    /// Normalized MAC -> expiry; `None` ignores the device permanently
+    mac_list: HashMap<String, Option<DateTime<Utc>>>,
     ssid_list: HashSet<String>,
+    /// Normalized MACs ignored for this run only and never saved, such as
+    /// the host's own interfaces
+    runtime_macs: HashSet<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct MacListFile {
-    macs: Vec<String>,
+    macs: Vec<MacEntry>,
+}
+
+/// A MAC list entry: a bare address, or an address with an expiry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum MacEntry {
+    Permanent(String),
+    Expiring { mac: String, expires: DateTime<Utc> },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,9 +76,21 @@ impl IgnoreLists {
     }
 
     pub fn should_ignore_mac(&self, mac: &str) -> bool {
+        self.should_ignore_mac_at(mac, Utc::now())
+    }
+
+    /// Like `should_ignore_mac`, with entries that expired before `now` ignored
+    pub fn should_ignore_mac_at(&self, mac: &str, now: DateTime<Utc>) -> bool {
         // Normalize MAC address for comparison
         let normalized = mac.to_uppercase().replace(['-', '.'], ":");
-        self.mac_list.contains(&normalized)
+        if self.runtime_macs.contains(&normalized) {
+            return true;
+        }
+        match self.mac_list.get(&normalized) {
+            Some(Some(expires)) => *expires > now,
+            Some(None) => true,
+            None => false,
+        }
     }
 
     pub fn should_ignore_ssid(&self, ssid: &str) -> bool {
@@ -74,7 +99,51 @@ impl IgnoreLists {
 
     pub fn add_mac(&mut self, mac: &str) {
         let normalized = mac.to_uppercase().replace(['-', '.'], ":");
-        self.mac_list.insert(normalized);
+        self.mac_list.insert(normalized, None);
+    }
+
+    /// Ignore `mac` for the lifetime of these lists without adding it to the
+    /// saved MAC list
+    pub fn add_runtime_mac(&mut self, mac: &str) {
+        let normalized = mac.to_uppercase().replace(['-', '.'], ":");
+        self.runtime_macs.insert(normalized);
+    }
+
+    /// Ignore every local interface MAC for this run. Returns how many were
+    /// added.
+    pub fn ignore_local_macs(&mut self) -> usize {
+        let macs = local_interface_macs();
+        for mac in &macs {
+            self.add_runtime_mac(mac);
+        }
+        if !macs.is_empty() {
+            info!("Ignoring {} local interface MAC(s): {}", macs.len(), macs.join(", "));
+        }
+        macs.len()
+    }
+
+    /// Ignore `mac` until `expires`. A permanent entry for the same MAC is
+    /// left as is.
+    pub fn mute_mac(&mut self, mac: &str, expires: DateTime<Utc>) {
+        let normalized = mac.to_uppercase().replace(['-', '.'], ":");
+        let entry = self.mac_list.entry(normalized).or_insert(Some(expires));
+        if let Some(current) = entry {
+            *current = expires;
+        }
+    }
+
+    /// Ignored MACs with their expiry, sorted by MAC
+    pub fn mac_entries(&self) -> Vec<(String, Option<DateTime<Utc>>)> {
+        let mut entries: Vec<_> = self.mac_list.iter().map(|(m, e)| (m.clone(), *e)).collect();
+        entries.sort();
+        entries
+    }
+
+    /// Drop entries that expired before `now`. Returns how many were removed.
+    pub fn purge_expired(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.mac_list.len();
+        self.mac_list.retain(|_, expires| !matches!(expires, Some(e) if *e <= now));
+        before - self.mac_list.len()
     }
 
     pub fn add_ssid(&mut self, ssid: &str) {
@@ -83,7 +152,7 @@ impl IgnoreLists {
 
     pub fn remove_mac(&mut self, mac: &str) -> bool {
         let normalized = mac.to_uppercase().replace(['-', '.'], ":");
-        self.mac_list.remove(&normalized)
+        self.mac_list.remove(&normalized).is_some()
     }
 
     pub fn remove_ssid(&mut self, ssid: &str) -> bool {
@@ -100,7 +169,14 @@ impl IgnoreLists {
 
     pub fn save_mac_list<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let file = MacListFile {
-            macs: self.mac_list.iter().cloned().collect(),
+            macs: self
+                .mac_entries()
+                .into_iter()
+                .map(|(mac, expires)| match expires {
+                    Some(expires) => MacEntry::Expiring { mac, expires },
+                    None => MacEntry::Permanent(mac),
+                })
+                .collect(),
         };
         let content = serde_json::to_string_pretty(&file)?;
         fs::write(path, content)?;
@@ -117,7 +193,7 @@ impl IgnoreLists {
     }
 }
 
-fn load_mac_list(path: &Path) -> Result<HashSet<String>> {
+fn load_mac_list(path: &Path) -> Result<HashMap<String, Option<DateTime<Utc>>>> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read MAC list: {:?}", path))?;
 
@@ -125,10 +201,14 @@ fn load_mac_list(path: &Path) -> Result<HashSet<String>> {
         .with_context(|| "Failed to parse MAC list JSON")?;
 
     // Normalize all MAC addresses
-    let macs: HashSet<String> = file
+    let macs = file
         .macs
         .into_iter()
-        .map(|m| m.to_uppercase().replace(['-', '.'], ":"))
+        .map(|entry| match entry {
+            MacEntry::Permanent(mac) => (mac, None),
+            MacEntry::Expiring { mac, expires } => (mac, Some(expires)),
+        })
+        .map(|(m, expires)| (m.to_uppercase().replace(['-', '.'], ":"), expires))
         .collect();
 
     Ok(macs)
@@ -144,6 +224,53 @@ fn load_ssid_list(path: &Path) -> Result<HashSet<String>> {
     Ok(file.ssids.into_iter().collect())
 }
 
+/// MAC addresses of this machine's network interfaces, from sysfs. The
+/// loopback and other all-zero addresses are skipped.
+pub fn local_interface_macs() -> Vec<String> {
+    interface_macs_in(Path::new("/sys/class/net"))
+}
+
+fn interface_macs_in(root: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Cannot list network interfaces in {:?}: {}", root, e);
+            return Vec::new();
+        }
+    };
+
+    let mut macs: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path().join("address")).ok())
+        .map(|address| address.trim().to_uppercase())
+        // Only Ethernet-style addresses; tunnels and the like have none or longer ones
+        .filter(|mac| mac.len() == 17 && mac != "00:00:00:00:00:00")
+        .collect();
+    macs.sort();
+    macs.dedup();
+    macs
+}
+
+/// Parse a mute duration such as `30m`, `24h` or `7d`
+pub fn parse_mute_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.len() - text.chars().last().map_or(0, |c| c.len_utf8());
+    let (amount, unit) = text.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid duration {:?} (expected e.g. 30m, 24h, 7d)", text))?;
+    if amount <= 0 {
+        bail!("Duration must be positive: {:?}", text);
+    }
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => bail!("Invalid duration {:?} (expected e.g. 30m, 24h, 7d)", text),
+    }
+}
+
 /// Create default ignore list files if they don't exist
 pub fn create_default_ignore_lists<P: AsRef<Path>>(dir: P) -> Result<()> {
     let dir = dir.as_ref();
@@ -185,6 +312,35 @@ mod tests {
         assert!(!lists.should_ignore_mac("11:22:33:44:55:66"));
     }
 
+    #[test]
+    fn test_interface_macs_and_runtime_ignores() {
+        let root = std::env::temp_dir().join(format!("prowl-net-{}", std::process::id()));
+        for (name, address) in [
+            ("lo", "00:00:00:00:00:00\n"),
+            ("wlan0", "a4:5e:60:11:22:33\n"),
+            ("wlan0mon", "a4:5e:60:11:22:33\n"),
+            ("usb0", "02:1a:11:f0:00:01\n"),
+            ("wg0", "\n"),
+        ] {
+            fs::create_dir_all(root.join(name)).unwrap();
+            fs::write(root.join(name).join("address"), address).unwrap();
+        }
+
+        let macs = interface_macs_in(&root);
+        assert_eq!(macs, vec!["02:1A:11:F0:00:01", "A4:5E:60:11:22:33"]);
+
+        let mut lists = IgnoreLists::new();
+        for mac in &macs {
+            lists.add_runtime_mac(mac);
+        }
+        assert!(lists.should_ignore_mac("a4:5e:60:11:22:33"));
+        // Runtime entries are never written to the MAC list
+        assert_eq!(lists.mac_count(), 0);
+        assert!(lists.mac_entries().is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn test_ssid_matching() {
         let mut lists = IgnoreLists::new();
@@ -194,4 +350,44 @@ mod tests {
         assert!(!lists.should_ignore_ssid("myhomenetwork")); // Case sensitive
         assert!(!lists.should_ignore_ssid("OtherNetwork"));
     }
+
+    #[test]
+    fn test_mac_mute_expires() {
+        let now = Utc::now();
+        let mut lists = IgnoreLists::new();
+        lists.mute_mac("aa:bb:cc:dd:ee:01", now + Duration::hours(24));
+
+        assert!(lists.should_ignore_mac_at("AA:BB:CC:DD:EE:01", now));
+        assert!(!lists.should_ignore_mac_at("AA:BB:CC:DD:EE:01", now + Duration::hours(25)));
+
+        // Muting never shortens a permanent entry
+        lists.add_mac("AA:BB:CC:DD:EE:02");
+        lists.mute_mac("AA:BB:CC:DD:EE:02", now + Duration::hours(1));
+        assert!(lists.should_ignore_mac_at("AA:BB:CC:DD:EE:02", now + Duration::days(365)));
+
+        assert_eq!(lists.purge_expired(now + Duration::hours(25)), 1);
+        assert_eq!(lists.mac_count(), 1);
+    }
+
+    #[test]
+    fn test_mac_list_file_mixed_entries() {
+        let json = r#"{"macs": ["aa:bb:cc:dd:ee:01", {"mac": "aa:bb:cc:dd:ee:02", "expires": "2030-01-01T00:00:00Z"}]}"#;
+        let path = std::env::temp_dir().join(format!("prowl-macs-{}.json", std::process::id()));
+        fs::write(&path, json).unwrap();
+
+        let macs = load_mac_list(&path).unwrap();
+        assert_eq!(macs.get("AA:BB:CC:DD:EE:01"), Some(&None));
+        assert!(macs.get("AA:BB:CC:DD:EE:02").unwrap().is_some());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_mute_duration() {
+        assert_eq!(parse_mute_duration("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_mute_duration("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_mute_duration("30m").unwrap(), Duration::minutes(30));
+        assert!(parse_mute_duration("0h").is_err());
+        assert!(parse_mute_duration("soon").is_err());
+        assert!(parse_mute_duration("").is_err());
+    }
 }
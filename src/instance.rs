@@ -0,0 +1,161 @@
+//! One capture writer per database.
+//!
+//! SQLite copes with several connections, but two capture processes on one
+//! database each open their own session and race on the same device rows.
+//! Capture therefore takes an exclusive `flock` on `<database>.lock` and
+//! writes its PID, mode and start time into it. The kernel drops the lock
+//! when the process exits, however it exits, so a crash never leaves a
+//! stale lock behind; the file itself is left in place. A second
+//! `prowl capture` is refused with the owner's details, and the TUI attaches
+//! read-only instead of capturing.
+
+use crate::exit::{self, ExitError};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::path::PathBuf;
+
+/// Who holds a capture lock, as written into the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    /// `capture` or `tui`
+    pub mode: String,
+    pub started_at: i64,
+}
+
+impl LockOwner {
+    pub fn describe(&self) -> String {
+        let since = chrono::DateTime::from_timestamp(self.started_at, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "unknown time".to_string());
+        format!("prowl {} (PID {}, since {})", self.mode, self.pid, since)
+    }
+}
+
+/// Outcome of trying to take the capture lock
+#[derive(Debug)]
+pub enum LockAttempt {
+    Acquired(InstanceLock),
+    /// Another process captures into the database; its details are `None`
+    /// if it hasn't written them yet
+    Held(Option<LockOwner>),
+}
+
+/// Exclusive capture lock on a database, held until dropped
+#[derive(Debug)]
+pub struct InstanceLock {
+    // Closing the file releases the flock
+    _file: File,
+    path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Path of the lock file for `database`
+    pub fn lock_path(database: &str) -> PathBuf {
+        PathBuf::from(format!("{}.lock", database))
+    }
+
+    /// Try to take the capture lock on `database` for `mode`
+    pub fn try_acquire(database: &str, mode: &str) -> Result<LockAttempt> {
+        let path = Self::lock_path(database);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        if !try_flock(&file).with_context(|| format!("Failed to lock {}", path.display()))? {
+            let mut contents = String::new();
+            let _ = file.read_to_string(&mut contents);
+            return Ok(LockAttempt::Held(serde_json::from_str(&contents).ok()));
+        }
+
+        let owner = LockOwner {
+            pid: std::process::id(),
+            mode: mode.to_string(),
+            started_at: chrono::Utc::now().timestamp(),
+        };
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(serde_json::to_string(&owner)?.as_bytes())?;
+        file.flush()?;
+        Ok(LockAttempt::Acquired(InstanceLock { _file: file, path }))
+    }
+
+    /// Take the capture lock, or fail with a database error naming the
+    /// process that holds it
+    pub fn acquire(database: &str, mode: &str) -> Result<InstanceLock> {
+        match Self::try_acquire(database, mode)? {
+            LockAttempt::Acquired(lock) => Ok(lock),
+            LockAttempt::Held(owner) => {
+                Err(ExitError::new(exit::DATABASE, held_message(database, owner.as_ref())).into())
+            }
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+/// Refusal shown when another process is capturing into `database`
+pub fn held_message(database: &str, owner: Option<&LockOwner>) -> String {
+    let owner = owner.map(|o| o.describe()).unwrap_or_else(|| "another prowl process".to_string());
+    format!("{} is already capturing into {}; stop it first or use another database", owner, database)
+}
+
+#[cfg(unix)]
+fn try_flock(file: &File) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(not(unix))]
+fn try_flock(_file: &File) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_writer_is_refused() {
+        let db = std::env::temp_dir().join(format!("prowl-instance-{}.db", std::process::id()));
+        let db = db.to_string_lossy().to_string();
+
+        let first = match InstanceLock::try_acquire(&db, "capture").unwrap() {
+            LockAttempt::Acquired(lock) => lock,
+            LockAttempt::Held(_) => panic!("fresh lock was held"),
+        };
+        // flock is per open file, so a second open in this process contends
+        match InstanceLock::try_acquire(&db, "tui").unwrap() {
+            LockAttempt::Held(Some(owner)) => {
+                assert_eq!(owner.pid, std::process::id());
+                assert_eq!(owner.mode, "capture");
+            }
+            other => panic!("expected the lock to be held, got {:?}", other),
+        }
+        let err = InstanceLock::acquire(&db, "capture").unwrap_err();
+        assert_eq!(exit::code_for(&err), exit::DATABASE);
+        assert!(err.to_string().contains("prowl capture (PID"));
+
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(matches!(InstanceLock::try_acquire(&db, "tui").unwrap(), LockAttempt::Acquired(_)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
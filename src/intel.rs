@@ -0,0 +1,353 @@
+//! Suspicious devices in threat-intel sharing formats.
+//!
+//! `prowl report --report-type misp` writes the alerts of the last hours as
+//! one MISP event with a `mac-address` attribute per device, ready for the
+//! event import of a MISP instance. `stix` writes a STIX 2.1 bundle: per
+//! device a `mac-addr` observable, the `observed-data` that saw it and an
+//! `indicator` based on that, all created by a prowl `identity`. Its objects
+//! can be pushed to a TAXII collection with any TAXII client.
+//!
+//! IDs are name-based (UUIDv5), so exporting the same device again gives
+//! the same observable and lets the receiving side merge repeats. MISP
+//! events and attributes, observations and indicators are named after the
+//! device and the reporting window, which `report_window` aligns to whole
+//! hours, so exporting again within the hour updates what was imported
+//! before instead of duplicating it. Their `created` time is the end of the
+//! window and only `modified` is the export time, as STIX expects of
+//! versions of one object. MAC observables use the STIX namespace and
+//! contributing properties, as the specification asks for deterministic
+//! observable IDs.
+
+use crate::analysis::SurveillanceAlert;
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+
+/// Namespace STIX 2.1 prescribes for deterministic observable IDs
+const STIX_NAMESPACE: [u8; 16] = [
+    0x00, 0xab, 0xed, 0xb4, 0xaa, 0x42, 0x46, 0x6c, 0x9c, 0x01, 0xfe, 0xd2, 0x33, 0x15, 0xa9, 0xb7,
+];
+/// Everything else prowl names lives in a namespace derived from this
+const PROWL_NAME: &str = "prowl";
+/// When the prowl identity was created; fixed, since its ID is
+const IDENTITY_CREATED: i64 = 0;
+
+/// The `hours` before `now`, ending on the last whole hour, so exports made
+/// within the same hour describe the same window
+pub fn report_window(now: i64, hours: u32) -> (i64, i64) {
+    let end = now - now.rem_euclid(3600);
+    (end - hours as i64 * 3600, end)
+}
+
+/// MISP event with a `mac-address` attribute per alert
+pub fn misp_event(alerts: &[SurveillanceAlert], start: i64, end: i64, now: i64) -> Value {
+    let namespace = prowl_namespace();
+    let attributes: Vec<Value> = alerts
+        .iter()
+        .map(|alert| {
+            let mac = alert.device.mac.to_lowercase();
+            json!({
+                "uuid": uuid5(&namespace, format!("misp-attribute:{}:{}:{}", mac, start, end).as_bytes()),
+                "type": "mac-address",
+                "category": "Network activity",
+                "value": mac,
+                "to_ids": false,
+                "comment": describe(alert),
+                "first_seen": stix_time(alert.device.first_seen),
+                "last_seen": stix_time(alert.device.last_seen),
+                "timestamp": now.to_string(),
+            })
+        })
+        .collect();
+
+    json!({
+        "Event": {
+            "uuid": uuid5(&namespace, format!("misp-event:{}:{}", start, end).as_bytes()),
+            "info": format!(
+                "prowl: {} suspicious Wi-Fi device(s) between {} and {}",
+                alerts.len(),
+                stix_time(start),
+                stix_time(end)
+            ),
+            "date": Utc.timestamp_opt(now, 0).single().map(|dt| dt.format("%Y-%m-%d").to_string()),
+            "timestamp": now.to_string(),
+            // Low threat, analysis complete, this organisation only
+            "threat_level_id": "3",
+            "analysis": "2",
+            "distribution": "0",
+            "Tag": [{ "name": "tlp:amber" }],
+            "Attribute": attributes,
+        }
+    })
+}
+
+/// STIX 2.1 bundle with an observable, observation and indicator per alert
+pub fn stix_bundle(alerts: &[SurveillanceAlert], start: i64, end: i64, now: i64) -> Value {
+    let namespace = prowl_namespace();
+    let created = stix_time(end);
+    let modified = stix_time(now.max(end));
+    let identity_id = format!("identity--{}", uuid5(&namespace, b"identity"));
+    let mut objects = vec![json!({
+        "type": "identity",
+        "spec_version": "2.1",
+        "id": identity_id,
+        "created": stix_time(IDENTITY_CREATED),
+        "modified": stix_time(IDENTITY_CREATED),
+        "name": "prowl",
+        "description": "Wi-Fi probe request sensor",
+        "identity_class": "system",
+    })];
+
+    for alert in alerts {
+        let mac = alert.device.mac.to_lowercase();
+        // The observable's ID comes from its canonical JSON `value`
+        let canonical = json!({ "value": mac }).to_string();
+        let mac_id = format!("mac-addr--{}", uuid5(&STIX_NAMESPACE, canonical.as_bytes()));
+        let observed_id = format!(
+            "observed-data--{}",
+            uuid5(&namespace, format!("observed-data:{}:{}:{}", mac, start, end).as_bytes())
+        );
+        let indicator_id = format!(
+            "indicator--{}",
+            uuid5(&namespace, format!("indicator:{}:{}:{}", mac, start, end).as_bytes())
+        );
+        let confidence = (alert.score.clamp(0.0, 1.0) * 100.0).round() as u8;
+
+        objects.push(json!({
+            "type": "mac-addr",
+            "spec_version": "2.1",
+            "id": mac_id,
+            "value": mac,
+        }));
+        objects.push(json!({
+            "type": "observed-data",
+            "spec_version": "2.1",
+            "id": observed_id,
+            "created_by_ref": identity_id,
+            "created": created,
+            "modified": modified,
+            "first_observed": stix_time(alert.device.first_seen),
+            "last_observed": stix_time(alert.device.last_seen),
+            "number_observed": alert.appearance_count.max(1),
+            "object_refs": [mac_id],
+        }));
+        objects.push(json!({
+            "type": "indicator",
+            "spec_version": "2.1",
+            "id": indicator_id,
+            "created_by_ref": identity_id,
+            "created": created,
+            "modified": modified,
+            "name": format!("Persistent Wi-Fi device {}", mac),
+            "description": describe(alert),
+            "indicator_types": ["anomalous-activity"],
+            "pattern": format!("[mac-addr:value = '{}']", mac),
+            "pattern_type": "stix",
+            "valid_from": stix_time(alert.device.first_seen),
+            "confidence": confidence,
+        }));
+        objects.push(json!({
+            "type": "relationship",
+            "spec_version": "2.1",
+            "id": format!(
+                "relationship--{}",
+                uuid5(&namespace, format!("based-on:{}:{}:{}", mac, start, end).as_bytes())
+            ),
+            "created_by_ref": identity_id,
+            "created": created,
+            "modified": modified,
+            "relationship_type": "based-on",
+            "source_ref": indicator_id,
+            "target_ref": observed_id,
+        }));
+    }
+
+    json!({
+        "type": "bundle",
+        "id": format!(
+            "bundle--{}",
+            uuid5(&namespace, format!("bundle:{}:{}:{}", start, end, now).as_bytes())
+        ),
+        "objects": objects,
+    })
+}
+
+/// Score, vendor, reasons and probed SSIDs in one line
+fn describe(alert: &SurveillanceAlert) -> String {
+    let mut text = format!("Persistence score {:.0}%", alert.score * 100.0);
+    if let Some(vendor) = alert.vendor.as_ref().and_then(|v| v.vendor.as_deref()) {
+        text.push_str(&format!(", vendor {}", vendor));
+    }
+    if !alert.reasons.is_empty() {
+        text.push_str(&format!(": {}", alert.reasons.join("; ")));
+    }
+    if !alert.probed_ssids.is_empty() {
+        text.push_str(&format!(". Probed for: {}", alert.probed_ssids.join(", ")));
+    }
+    text
+}
+
+/// RFC 3339 UTC with milliseconds, as STIX requires
+fn stix_time(ts: i64) -> String {
+    Utc.timestamp_opt(ts, 0)
+        .single()
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+fn prowl_namespace() -> [u8; 16] {
+    let mut namespace = [0u8; 16];
+    namespace.copy_from_slice(&sha1(&[&STIX_NAMESPACE[..], PROWL_NAME.as_bytes()].concat())[..16]);
+    namespace
+}
+
+/// Name-based UUID (version 5, SHA-1)
+pub fn uuid5(namespace: &[u8; 16], name: &[u8]) -> String {
+    let hash = sha1(&[&namespace[..], name].concat());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// SHA-1, only for UUIDv5; it is not used for anything that needs to resist
+/// collisions
+pub(crate) fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Device;
+
+    fn alert(mac: &str, score: f64) -> SurveillanceAlert {
+        SurveillanceAlert {
+            device: Device {
+                id: 1,
+                mac: mac.to_string(),
+                first_seen: 1_700_000_000,
+                last_seen: 1_700_003_600,
+            },
+            score,
+            reasons: vec!["Seen in 4 of 4 time windows".to_string()],
+            probed_ssids: vec!["HomeNet".to_string()],
+            directed_bssids: Vec::new(),
+            nearby_ssids: Vec::new(),
+            responding_aps: Vec::new(),
+            location_count: 0,
+            appearance_count: 12,
+            vendor: None,
+            wps: None,
+            stability: None,
+            residency: None,
+            location_overlap: None,
+            ssid_popularity: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_uuid5() {
+        let dns = [
+            0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+        ];
+        assert_eq!(uuid5(&dns, b"python.org"), "886313e1-3b8a-5372-9b90-0c9aee199e5d");
+        let abc: String = sha1(b"abc").iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(abc, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_stix_bundle() {
+        let alerts = [alert("AA:BB:CC:DD:EE:FF", 0.87)];
+        let bundle = stix_bundle(&alerts, 1_700_000_000, 1_700_007_200, 1_700_007_200);
+        let objects = bundle["objects"].as_array().unwrap();
+        // Identity, then observable, observation, indicator and relationship
+        assert_eq!(objects.len(), 5);
+        assert_eq!(objects[1]["value"], "aa:bb:cc:dd:ee:ff");
+        assert_eq!(objects[2]["object_refs"][0], objects[1]["id"]);
+        assert_eq!(objects[3]["pattern"], "[mac-addr:value = 'aa:bb:cc:dd:ee:ff']");
+        assert_eq!(objects[3]["confidence"], 87);
+        assert_eq!(objects[3]["valid_from"], "2023-11-14T22:13:20.000Z");
+
+        // The same device gets the same observable next time
+        let again = stix_bundle(&[alert("aa:bb:cc:dd:ee:ff", 0.5)], 0, 1, 2);
+        assert_eq!(again["objects"][1]["id"], objects[1]["id"]);
+
+        // Exported again later in the hour: a new version of the same objects
+        let later = stix_bundle(&alerts, 1_700_000_000, 1_700_007_200, 1_700_009_000);
+        assert_eq!(later["objects"][3]["id"], objects[3]["id"]);
+        assert_eq!(later["objects"][3]["created"], objects[3]["created"]);
+        assert_eq!(later["objects"][3]["modified"], "2023-11-15T00:43:20.000Z");
+        assert_eq!(later["objects"][0], objects[0]);
+    }
+
+    #[test]
+    fn test_report_window() {
+        let (start, end) = report_window(1_700_009_000, 2);
+        assert_eq!((start, end), (1_699_999_200, 1_700_006_400));
+        assert_eq!(report_window(1_700_006_400 + 3_599, 2), (start, end));
+        assert_eq!(report_window(1_700_010_000, 2).1, 1_700_010_000);
+    }
+
+    #[test]
+    fn test_misp_event() {
+        let alerts = [alert("AA:BB:CC:DD:EE:FF", 0.87)];
+        let event = misp_event(&alerts, 1_700_000_000, 1_700_007_200, 1_700_007_200);
+        let attribute = &event["Event"]["Attribute"][0];
+        assert_eq!(attribute["type"], "mac-address");
+        assert_eq!(attribute["value"], "aa:bb:cc:dd:ee:ff");
+        assert_eq!(
+            attribute["comment"],
+            "Persistence score 87%: Seen in 4 of 4 time windows. Probed for: HomeNet"
+        );
+        assert_eq!(event["Event"]["date"], "2023-11-15");
+
+        // Exporting the same window again updates rather than duplicates
+        let again = misp_event(&alerts, 1_700_000_000, 1_700_007_200, 1_700_009_000);
+        assert_eq!(again["Event"]["uuid"], event["Event"]["uuid"]);
+        assert_eq!(again["Event"]["Attribute"][0]["uuid"], attribute["uuid"]);
+    }
+}
@@ -0,0 +1,558 @@
+//! Kismet remote capture as a packet source.
+//!
+//! Kismet's capture tools (`kismet_cap_linux_wifi --connect host:port
+//! --source wlan1`) connect out to a server and stream what they capture,
+//! so sensors already deployed for Kismet can feed prowl by pointing them at
+//! `capture.kismet_listen` instead. This speaks the v2 external protocol:
+//! each frame is a 12-byte big-endian header (0xDECAFBAD signature, Adler-32
+//! of the payload, payload length) around a protobuf `KismetExternal.Command`
+//! whose `content` is another protobuf message chosen by the command name.
+//! Only the handful of fields prowl needs are encoded and decoded, by hand.
+//!
+//! A sensor announces its source (`KDSNEWSOURCE`), is told to open it
+//! (`KDSOPENSOURCE`), and once it reports success is told to hop
+//! `capture.channels`. Its `KDSDATAREPORT`s then carry the frames. One sensor
+//! is served at a time; when it disconnects the listener waits for the next.
+//! The protocol has no authentication, so the listener should only be
+//! reachable from the sensors.
+
+use crate::config::{CaptureConfig, ChannelEntry};
+use crate::source::{FrameTime, PacketSource};
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+const SIGNATURE: u32 = 0xDECA_FBAD;
+/// Second header word of v3 frames (sentinel and version), which aren't
+/// supported; in v2 it is the checksum
+const V3_MARKER: u32 = 0xDECA_0003;
+const HEADER_LEN: usize = 12;
+/// Larger frames mean a confused or hostile peer
+const MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+/// How often the sensor is pinged so it knows the server is alive
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Radiotap-wrapped 802.11
+const DLT_IEEE802_11_RADIO: u32 = 127;
+/// Bare 802.11 frames without a radiotap header
+const DLT_IEEE802_11: u32 = 105;
+
+/// Protobuf field values prowl reads
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos).context("Truncated protobuf varint")?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Protobuf varint too long")
+}
+
+fn take<'a>(buf: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let bytes = pos
+        .checked_add(len)
+        .and_then(|end| buf.get(*pos..end))
+        .context("Truncated protobuf field")?;
+    *pos += len;
+    Ok(bytes)
+}
+
+/// Split a protobuf message into its fields
+fn fields(buf: &[u8]) -> Result<Vec<(u32, Value<'_>)>> {
+    let mut pos = 0;
+    let mut out = Vec::new();
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(buf, &mut pos)?),
+            1 => Value::Fixed64(u64::from_le_bytes(take(buf, &mut pos, 8)?.try_into()?)),
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                Value::Bytes(take(buf, &mut pos, len)?)
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(take(buf, &mut pos, 4)?.try_into()?)),
+            wire => bail!("Unsupported protobuf wire type {}", wire),
+        };
+        out.push(((key >> 3) as u32, value));
+    }
+    Ok(out)
+}
+
+fn field<'a>(fields: &[(u32, Value<'a>)], number: u32) -> Option<Value<'a>> {
+    fields.iter().find(|(n, _)| *n == number).map(|(_, v)| *v)
+}
+
+fn field_bytes<'a>(fields: &[(u32, Value<'a>)], number: u32) -> &'a [u8] {
+    match field(fields, number) {
+        Some(Value::Bytes(bytes)) => bytes,
+        _ => &[],
+    }
+}
+
+fn field_string(fields: &[(u32, Value<'_>)], number: u32) -> String {
+    String::from_utf8_lossy(field_bytes(fields, number)).into_owned()
+}
+
+fn field_u64(fields: &[(u32, Value<'_>)], number: u32) -> Option<u64> {
+    match field(fields, number)? {
+        Value::Varint(v) | Value::Fixed64(v) => Some(v),
+        Value::Fixed32(v) => Some(v as u64),
+        Value::Bytes(_) => None,
+    }
+}
+
+fn field_f64(fields: &[(u32, Value<'_>)], number: u32) -> Option<f64> {
+    match field(fields, number)? {
+        Value::Fixed64(bits) => Some(f64::from_bits(bits)),
+        _ => None,
+    }
+}
+
+/// Builds a protobuf message
+#[derive(Default)]
+struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    fn key(&mut self, number: u32, wire: u8) {
+        put_varint(&mut self.buf, ((number as u64) << 3) | wire as u64);
+    }
+
+    fn uint(mut self, number: u32, value: u64) -> Self {
+        self.key(number, 0);
+        put_varint(&mut self.buf, value);
+        self
+    }
+
+    fn double(mut self, number: u32, value: f64) -> Self {
+        self.key(number, 1);
+        self.buf.extend_from_slice(&value.to_bits().to_le_bytes());
+        self
+    }
+
+    fn bytes(mut self, number: u32, value: &[u8]) -> Self {
+        self.key(number, 2);
+        put_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    fn string(self, number: u32, value: &str) -> Self {
+        self.bytes(number, value.as_bytes())
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// One `KismetExternal.Command`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub seqno: u32,
+    pub command: String,
+    pub content: Vec<u8>,
+}
+
+/// Frame `command` for the wire
+pub fn encode_frame(command: &str, seqno: u32, content: &[u8]) -> Vec<u8> {
+    let payload = Message::default()
+        .uint(1, seqno as u64)
+        .string(2, command)
+        .bytes(3, content)
+        .buf;
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&SIGNATURE.to_be_bytes());
+    frame.extend_from_slice(&adler32(&payload).to_be_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+/// Decode the first frame in `buf`, returning it and the bytes it used, or
+/// `None` if it hasn't fully arrived
+pub fn decode_frame(buf: &[u8]) -> Result<Option<(Command, usize)>> {
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let word = |at: usize| u32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+    if word(0) != SIGNATURE {
+        bail!("Not a Kismet remote capture stream (bad frame signature)");
+    }
+    if word(4) == V3_MARKER {
+        bail!("Sensor speaks Kismet's v3 capture protocol; run it with a capture tool from before 2023");
+    }
+    let len = word(8) as usize;
+    if len > MAX_FRAME_LEN {
+        bail!("Kismet frame of {} bytes is too large", len);
+    }
+    if buf.len() < HEADER_LEN + len {
+        return Ok(None);
+    }
+    let payload = &buf[HEADER_LEN..HEADER_LEN + len];
+    if adler32(payload) != word(4) {
+        bail!("Kismet frame checksum mismatch");
+    }
+    let fields = fields(payload)?;
+    let command = Command {
+        seqno: field_u64(&fields, 1).unwrap_or(0) as u32,
+        command: field_string(&fields, 2),
+        content: field_bytes(&fields, 3).to_vec(),
+    };
+    Ok(Some((command, HEADER_LEN + len)))
+}
+
+/// A captured frame from a `KDSDATAREPORT`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPacket {
+    pub time: FrameTime,
+    pub dlt: u32,
+    pub data: Vec<u8>,
+    pub signal_dbm: Option<i8>,
+}
+
+/// Packet and signal from a `KismetDatasource.DataReport`
+pub fn parse_data_report(content: &[u8]) -> Result<Option<DataPacket>> {
+    let report = fields(content)?;
+    let packet = match field(&report, 3) {
+        Some(Value::Bytes(packet)) => fields(packet)?,
+        _ => return Ok(None),
+    };
+    let signal_dbm = match field(&report, 2) {
+        Some(Value::Bytes(signal)) => field_f64(&fields(signal)?, 1)
+            .filter(|dbm| *dbm < 0.0)
+            .map(|dbm| dbm.max(i8::MIN as f64) as i8),
+        _ => None,
+    };
+    Ok(Some(DataPacket {
+        time: FrameTime {
+            secs: field_u64(&packet, 1).unwrap_or(0) as i64,
+            micros: (field_u64(&packet, 2).unwrap_or(0) as u32).min(999_999),
+        },
+        dlt: field_u64(&packet, 3).unwrap_or(0) as u32,
+        data: field_bytes(&packet, 5).to_vec(),
+        signal_dbm,
+    }))
+}
+
+/// Success flag and message from a report's `SubSuccess` (field 1)
+fn report_success(content: &[u8]) -> Result<(bool, String)> {
+    let success = fields(field_bytes(&fields(content)?, 1))?;
+    Ok((field_u64(&success, 1) == Some(1), field_string(&success, 3)))
+}
+
+/// `KismetDatasource.ConfigureSource` asking the sensor to hop `channels`
+pub fn hop_request(channels: &[ChannelEntry], hop_interval_ms: u64) -> Vec<u8> {
+    let mut hop = Message::default().double(1, 1000.0 / hop_interval_ms.max(1) as f64);
+    for entry in channels {
+        hop = hop.string(2, &entry.channel().to_string());
+    }
+    Message::default().bytes(2, &hop.buf).buf
+}
+
+/// Give bare 802.11 a radiotap header, with the reported signal if any, so
+/// the parser sees the same layout as a local capture
+fn wrap_radiotap(data: &[u8], signal_dbm: Option<i8>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 9);
+    match signal_dbm {
+        Some(signal) => {
+            frame.extend_from_slice(&[0, 0, 9, 0]);
+            frame.extend_from_slice(&(1u32 << 5).to_le_bytes());
+            frame.push(signal as u8);
+        }
+        None => frame.extend_from_slice(&[0, 0, 8, 0, 0, 0, 0, 0]),
+    }
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// A connected sensor
+struct Sensor {
+    stream: TcpStream,
+    peer: SocketAddr,
+    rx: Vec<u8>,
+    seqno: u32,
+    last_ping: Instant,
+}
+
+impl Sensor {
+    fn send(&mut self, command: &str, content: &[u8]) -> Result<()> {
+        self.seqno = self.seqno.wrapping_add(1);
+        self.stream
+            .write_all(&encode_frame(command, self.seqno, content))
+            .with_context(|| format!("Failed to send {} to Kismet sensor {}", command, self.peer))
+    }
+}
+
+/// Frames streamed by a Kismet remote capture sensor
+pub struct KismetSource {
+    listener: TcpListener,
+    sensor: Option<Sensor>,
+    timeout: Duration,
+    channels: Vec<ChannelEntry>,
+    hop_interval_ms: u64,
+    current: Vec<u8>,
+    /// Link types already warned about
+    skipped_dlts: Vec<u32>,
+}
+
+impl KismetSource {
+    /// Listen for sensors on `capture.kismet_listen`
+    pub fn open(config: &CaptureConfig, timeout_ms: i32) -> Result<Self> {
+        let listen = &config.kismet_listen;
+        let listener = TcpListener::bind(listen)
+            .with_context(|| format!("Failed to listen for Kismet sensors on {}", listen))?;
+        // Non-blocking so reads time out while no sensor is connected
+        listener.set_nonblocking(true)?;
+        info!("Waiting for Kismet remote capture sensors on {}", listen);
+        Ok(KismetSource {
+            listener,
+            sensor: None,
+            timeout: Duration::from_millis(timeout_ms.max(1) as u64),
+            channels: config.channels.clone(),
+            hop_interval_ms: config.hop_interval_ms,
+            current: Vec::new(),
+            skipped_dlts: Vec::new(),
+        })
+    }
+
+    fn accept(&mut self, deadline: Instant) -> Result<()> {
+        while Instant::now() < deadline {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    stream.set_nonblocking(false)?;
+                    stream.set_nodelay(true)?;
+                    info!("Kismet sensor connected from {}", peer);
+                    self.sensor = Some(Sensor {
+                        stream,
+                        peer,
+                        rx: Vec::new(),
+                        seqno: 0,
+                        last_ping: Instant::now(),
+                    });
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+                Err(e) => return Err(e).context("Failed to accept Kismet sensor"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Act on one command from the sensor, returning the frame it carried
+    fn handle(&mut self, command: Command) -> Result<Option<(Vec<u8>, FrameTime)>> {
+        let sensor = match self.sensor.as_mut() {
+            Some(sensor) => sensor,
+            None => return Ok(None),
+        };
+        match command.command.as_str() {
+            "KDSDATAREPORT" => {
+                let packet = match parse_data_report(&command.content)? {
+                    Some(packet) => packet,
+                    None => return Ok(None),
+                };
+                let frame = match packet.dlt {
+                    DLT_IEEE802_11_RADIO => packet.data,
+                    DLT_IEEE802_11 => wrap_radiotap(&packet.data, packet.signal_dbm),
+                    other => {
+                        if !self.skipped_dlts.contains(&other) {
+                            warn!("Skipping Kismet frames with link type {}; expected 802.11", other);
+                            self.skipped_dlts.push(other);
+                        }
+                        return Ok(None);
+                    }
+                };
+                return Ok(Some((frame, packet.time)));
+            }
+            "KDSNEWSOURCE" => {
+                let source = fields(&command.content)?;
+                let definition = field_string(&source, 1);
+                info!(
+                    "Kismet sensor {} offers {} source {}",
+                    sensor.peer,
+                    field_string(&source, 2),
+                    definition
+                );
+                sensor.send("KDSOPENSOURCE", &Message::default().string(1, &definition).buf)?;
+            }
+            "KDSOPENSOURCEREPORT" => {
+                let (success, message) = report_success(&command.content)?;
+                if !success {
+                    bail!("Kismet sensor {} failed to open its source: {}", sensor.peer, message);
+                }
+                info!("Kismet sensor {} is capturing", sensor.peer);
+                if !self.channels.is_empty() {
+                    sensor.send("KDSCONFIGURE", &hop_request(&self.channels, self.hop_interval_ms))?;
+                }
+            }
+            "KDSCONFIGUREREPORT" => {
+                let (success, message) = report_success(&command.content)?;
+                if !success {
+                    warn!("Kismet sensor {} refused the channel list: {}", sensor.peer, message);
+                }
+            }
+            "KDSERRORREPORT" => {
+                let (_, message) = report_success(&command.content)?;
+                bail!("Kismet sensor {} reported an error: {}", sensor.peer, message);
+            }
+            "MESSAGE" => {
+                let message = fields(&command.content)?;
+                info!("Kismet sensor {}: {}", sensor.peer, field_string(&message, 1));
+            }
+            "PING" => {
+                let pong = Message::default().uint(1, command.seqno as u64).buf;
+                sensor.send("PONG", &pong)?;
+            }
+            "SHUTDOWN" => bail!("Kismet sensor {} shut down", sensor.peer),
+            other => debug!("Ignoring Kismet command {} from {}", other, sensor.peer),
+        }
+        Ok(None)
+    }
+
+    /// Next frame from the connected sensor before `deadline`
+    fn read_sensor(&mut self, deadline: Instant) -> Result<Option<(Vec<u8>, FrameTime)>> {
+        loop {
+            let sensor = match self.sensor.as_mut() {
+                Some(sensor) => sensor,
+                None => return Ok(None),
+            };
+            if let Some((command, used)) = decode_frame(&sensor.rx)? {
+                sensor.rx.drain(..used);
+                if let Some(frame) = self.handle(command)? {
+                    return Ok(Some(frame));
+                }
+                continue;
+            }
+
+            if sensor.last_ping.elapsed() >= PING_INTERVAL {
+                sensor.last_ping = Instant::now();
+                sensor.send("PING", &[])?;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            sensor.stream.set_read_timeout(Some(remaining))?;
+            let mut chunk = [0u8; 16384];
+            match sensor.stream.read(&mut chunk) {
+                Ok(0) => bail!("Kismet sensor {} disconnected", sensor.peer),
+                Ok(n) => sensor.rx.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+                Err(e) => {
+                    let peer = sensor.peer;
+                    return Err(e).with_context(|| format!("Failed to read from Kismet sensor {}", peer));
+                }
+            }
+        }
+    }
+}
+
+impl PacketSource for KismetSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.next_timestamped()?.map(|(data, _)| data))
+    }
+
+    fn name(&self) -> &'static str {
+        "kismet"
+    }
+
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        let deadline = Instant::now() + self.timeout;
+        if self.sensor.is_none() {
+            self.accept(deadline)?;
+        }
+        // A sensor going away ends its session, not the capture
+        match self.read_sensor(deadline) {
+            Ok(Some((frame, time))) => {
+                self.current = frame;
+                Ok(Some((self.current.as_slice(), Some(time))))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                warn!("{:#}; waiting for a sensor to reconnect", e);
+                self.sensor = None;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = encode_frame("PING", 7, &[]);
+        assert_eq!(&frame[..4], &[0xde, 0xca, 0xfb, 0xad]);
+        // Incomplete frames wait for more data
+        assert_eq!(decode_frame(&frame[..frame.len() - 1]).unwrap(), None);
+
+        let (command, used) = decode_frame(&frame).unwrap().unwrap();
+        assert_eq!(used, frame.len());
+        assert_eq!(command.seqno, 7);
+        assert_eq!(command.command, "PING");
+
+        let mut corrupt = frame.clone();
+        *corrupt.last_mut().unwrap() ^= 0xff;
+        assert!(decode_frame(&corrupt).is_err());
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_parse_data_report() {
+        let packet = Message::default()
+            .uint(1, 1_700_000_000)
+            .uint(2, 250_000)
+            .uint(3, DLT_IEEE802_11 as u64)
+            .uint(4, 3)
+            .bytes(5, &[0x40, 0x00, 0x00]);
+        let signal = Message::default().double(1, -62.0);
+        let report = Message::default().bytes(2, &signal.buf).bytes(3, &packet.buf);
+
+        let packet = parse_data_report(&report.buf).unwrap().unwrap();
+        assert_eq!(packet.time, FrameTime { secs: 1_700_000_000, micros: 250_000 });
+        assert_eq!(packet.dlt, DLT_IEEE802_11);
+        assert_eq!(packet.signal_dbm, Some(-62));
+        let wrapped = wrap_radiotap(&packet.data, packet.signal_dbm);
+        assert_eq!(&wrapped[..2], &[0, 0]);
+        assert_eq!(wrapped[8] as i8, -62);
+        assert_eq!(&wrapped[9..], &[0x40, 0x00, 0x00]);
+
+        // Reports without a packet (GPS or spectrum only) are skipped
+        assert_eq!(parse_data_report(&signal.buf).unwrap(), None);
+    }
+}
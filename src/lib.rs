@@ -1,16 +1,62 @@
+pub mod alias;
 pub mod analysis;
+pub mod anomaly;
+pub mod burst;
 pub mod capture;
+pub mod cases;
 pub mod channels;
 pub mod config;
 pub mod database;
+pub mod deauth;
+pub mod dedup;
+pub mod disk;
 pub mod distance;
+pub mod email;
+pub mod exit;
+pub mod export;
+pub mod fingerprint;
+pub mod formats;
+#[cfg(feature = "gps")]
 pub mod gps;
+#[cfg(feature = "serve")]
+pub mod health;
+pub mod homenet;
+#[cfg(unix)]
+pub mod helper;
 pub mod ignore;
+pub mod instance;
+pub mod intel;
+pub mod kismet;
+pub mod location_history;
+pub mod maintenance;
+#[cfg(target_os = "linux")]
+pub mod nl80211;
+#[cfg(windows)]
+pub mod npcap;
+pub mod occupancy;
 pub mod oui;
+pub mod output;
 pub mod parser;
+#[cfg(feature = "pcap-export")]
+pub mod pcap_dump;
+pub mod power;
+pub mod privileges;
+pub mod privacy;
+pub mod queue;
+pub mod radiotap;
 pub mod report;
+pub mod residency;
+pub mod retention;
+pub mod risk;
+pub mod simulate;
+pub mod source;
+pub mod status;
+#[cfg(feature = "tui")]
 pub mod tui;
+pub mod update;
+pub mod utilization;
 pub mod validation;
+pub mod watch;
 
 pub use config::Config;
 pub use database::Database;
@@ -0,0 +1,534 @@
+//! The user's own location history, imported for co-travel correlation.
+//!
+//! prowl's GPS track only covers the times the sensor was running with a
+//! fix. A phone's location history covers much more, so importing it lets
+//! us ask how often a device was heard at the places the user actually
+//! went: "present at 80% of your recorded locations this week" is far
+//! stronger evidence of being followed than a persistence score.
+//!
+//! Supported exports:
+//! - Google Takeout `Records.json` (raw `locations`)
+//! - Google Takeout Semantic Location History (`timelineObjects`)
+//! - Google Maps on-device Timeline export (`semanticSegments`)
+//! - GPX tracks, which is how Apple Health exports workout routes
+//!
+//! Points are grouped into places on the same ~100 m grid the analyzer uses
+//! for location diversity, and consecutive points at one place into visits.
+
+use crate::database::Database;
+use anyhow::{bail, Context, Result};
+use chrono::DateTime;
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Points at one place further apart than this are separate visits
+const VISIT_GAP_SECS: i64 = 30 * 60;
+/// A device heard this long before or after a visit still counts as
+/// present; phones log location sparsely while stationary
+const PRESENCE_SLACK_SECS: i64 = 10 * 60;
+
+/// One recorded position of the user
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocationPoint {
+    pub timestamp: i64,
+    pub lat: f64,
+    pub lon: f64,
+    /// Reported accuracy radius in meters, when the export has one
+    pub accuracy_m: Option<f64>,
+}
+
+/// Export formats `parse_history_file` recognizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    GoogleRecords,
+    GoogleSemantic,
+    GoogleTimeline,
+    Gpx,
+}
+
+impl HistoryFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryFormat::GoogleRecords => "google_records",
+            HistoryFormat::GoogleSemantic => "google_semantic",
+            HistoryFormat::GoogleTimeline => "google_timeline",
+            HistoryFormat::Gpx => "gpx",
+        }
+    }
+}
+
+/// Points read from one export file
+#[derive(Debug, Clone)]
+pub struct ParsedHistory {
+    pub format: HistoryFormat,
+    pub points: Vec<LocationPoint>,
+}
+
+/// Parse one export file, telling the format from its extension and content
+pub fn parse_history_file(path: &Path) -> Result<ParsedHistory> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_gpx = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("gpx"))
+        .unwrap_or(false);
+    if is_gpx {
+        return Ok(ParsedHistory {
+            format: HistoryFormat::Gpx,
+            points: parse_gpx(&text)?,
+        });
+    }
+    parse_google_json(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Every `.json` and `.gpx` file under `path` (or `path` itself), so an
+/// unpacked Takeout or Apple Health archive can be imported in one go
+pub fn history_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        for entry in entries {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+                continue;
+            }
+            let wanted = entry_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("gpx"))
+                .unwrap_or(false);
+            if wanted {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse any of the Google location history JSON exports
+pub fn parse_google_json(text: &str) -> Result<ParsedHistory> {
+    let root: Value = serde_json::from_str(text)?;
+
+    if let Some(locations) = root.get("locations").and_then(Value::as_array) {
+        let points = locations.iter().filter_map(parse_record).collect();
+        return Ok(ParsedHistory {
+            format: HistoryFormat::GoogleRecords,
+            points,
+        });
+    }
+    if let Some(objects) = root.get("timelineObjects").and_then(Value::as_array) {
+        let points = objects.iter().flat_map(parse_timeline_object).collect();
+        return Ok(ParsedHistory {
+            format: HistoryFormat::GoogleSemantic,
+            points,
+        });
+    }
+    if let Some(segments) = root.get("semanticSegments").and_then(Value::as_array) {
+        let points = segments.iter().flat_map(parse_semantic_segment).collect();
+        return Ok(ParsedHistory {
+            format: HistoryFormat::GoogleTimeline,
+            points,
+        });
+    }
+    bail!("not a recognized location history export")
+}
+
+/// `Records.json` entry: E7 coordinates with an RFC 3339 or millisecond time
+fn parse_record(record: &Value) -> Option<LocationPoint> {
+    let timestamp = match record.get("timestamp").and_then(Value::as_str) {
+        Some(ts) => parse_time(ts)?,
+        None => parse_millis(record.get("timestampMs")?)?,
+    };
+    let (lat, lon) = e7_position(record)?;
+    Some(LocationPoint {
+        timestamp,
+        lat,
+        lon,
+        accuracy_m: record.get("accuracy").and_then(Value::as_f64),
+    })
+}
+
+/// A `placeVisit` becomes a point at its start and end; an
+/// `activitySegment` a point at each end of the trip
+fn parse_timeline_object(object: &Value) -> Vec<LocationPoint> {
+    let mut points = Vec::new();
+    if let Some(visit) = object.get("placeVisit") {
+        if let (Some(position), Some((start, end))) = (
+            visit.get("location").and_then(e7_position),
+            visit.get("duration").and_then(duration_bounds),
+        ) {
+            points.push(point_at(start, position));
+            points.push(point_at(end, position));
+        }
+    }
+    if let Some(segment) = object.get("activitySegment") {
+        if let Some((start, end)) = segment.get("duration").and_then(duration_bounds) {
+            if let Some(position) = segment.get("startLocation").and_then(e7_position) {
+                points.push(point_at(start, position));
+            }
+            if let Some(position) = segment.get("endLocation").and_then(e7_position) {
+                points.push(point_at(end, position));
+            }
+        }
+    }
+    points
+}
+
+/// On-device Timeline segment: a visit's place, or a path of timed points
+fn parse_semantic_segment(segment: &Value) -> Vec<LocationPoint> {
+    let mut points = Vec::new();
+    if let Some(path) = segment.get("timelinePath").and_then(Value::as_array) {
+        for entry in path {
+            let time = entry.get("time").and_then(Value::as_str).and_then(parse_time);
+            let position = entry.get("point").and_then(Value::as_str).and_then(parse_lat_lng);
+            if let (Some(time), Some(position)) = (time, position) {
+                points.push(point_at(time, position));
+            }
+        }
+    }
+    let place = segment
+        .get("visit")
+        .and_then(|v| v.get("topCandidate"))
+        .and_then(|c| c.get("placeLocation"))
+        .and_then(|l| l.get("latLng"))
+        .and_then(Value::as_str)
+        .and_then(parse_lat_lng);
+    let start = segment.get("startTime").and_then(Value::as_str).and_then(parse_time);
+    let end = segment.get("endTime").and_then(Value::as_str).and_then(parse_time);
+    if let (Some(position), Some(start), Some(end)) = (place, start, end) {
+        points.push(point_at(start, position));
+        points.push(point_at(end, position));
+    }
+    points
+}
+
+fn point_at(timestamp: i64, (lat, lon): (f64, f64)) -> LocationPoint {
+    LocationPoint {
+        timestamp,
+        lat,
+        lon,
+        accuracy_m: None,
+    }
+}
+
+fn e7_position(value: &Value) -> Option<(f64, f64)> {
+    let lat = value.get("latitudeE7")?.as_f64()? / 1e7;
+    let lon = value.get("longitudeE7")?.as_f64()? / 1e7;
+    valid_position(lat, lon)
+}
+
+fn duration_bounds(duration: &Value) -> Option<(i64, i64)> {
+    let bound = |key: &str, millis_key: &str| match duration.get(key).and_then(Value::as_str) {
+        Some(ts) => parse_time(ts),
+        None => duration.get(millis_key).and_then(parse_millis),
+    };
+    Some((
+        bound("startTimestamp", "startTimestampMs")?,
+        bound("endTimestamp", "endTimestampMs")?,
+    ))
+}
+
+/// "48.1372°, 11.5756°" as used by the on-device Timeline export
+fn parse_lat_lng(text: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = text.split_once(',')?;
+    let lat = lat.trim().trim_end_matches('°').parse().ok()?;
+    let lon = lon.trim().trim_end_matches('°').parse().ok()?;
+    valid_position(lat, lon)
+}
+
+fn valid_position(lat: f64, lon: f64) -> Option<(f64, f64)> {
+    let in_range = (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon);
+    // 0,0 is what a missing fix looks like, as in prowl's own probes
+    (in_range && (lat != 0.0 || lon != 0.0)).then_some((lat, lon))
+}
+
+fn parse_time(text: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(text).ok().map(|t| t.timestamp())
+}
+
+/// Milliseconds since the epoch, as a number or a numeric string
+fn parse_millis(value: &Value) -> Option<i64> {
+    let millis = match value {
+        Value::String(s) => s.parse::<i64>().ok()?,
+        other => other.as_i64()?,
+    };
+    Some(millis / 1000)
+}
+
+/// Track points of a GPX file. Points without a time are skipped since
+/// they can't be matched against sightings.
+pub fn parse_gpx(text: &str) -> Result<Vec<LocationPoint>> {
+    if !text.contains("<gpx") {
+        bail!("not a GPX file");
+    }
+    let mut points = Vec::new();
+    let mut rest = text;
+    while let Some(at) = rest.find("<trkpt") {
+        rest = &rest[at + "<trkpt".len()..];
+        let tag_end = match rest.find('>') {
+            Some(i) => i,
+            None => break,
+        };
+        let attributes = &rest[..tag_end];
+        let body = if attributes.ends_with('/') {
+            ""
+        } else {
+            let body_end = rest.find("</trkpt>").unwrap_or(rest.len());
+            &rest[tag_end + 1..body_end]
+        };
+
+        let lat = xml_attribute(attributes, "lat").and_then(|v| v.parse().ok());
+        let lon = xml_attribute(attributes, "lon").and_then(|v| v.parse().ok());
+        let time = xml_element(body, "time").and_then(parse_time);
+        if let (Some(lat), Some(lon), Some(timestamp)) = (lat, lon, time) {
+            if let Some(position) = valid_position(lat, lon) {
+                points.push(point_at(timestamp, position));
+            }
+        }
+    }
+    Ok(points)
+}
+
+fn xml_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    for quote in ['"', '\''] {
+        let key = format!(" {}={}", name, quote);
+        if let Some(at) = attributes.find(&key) {
+            let value = &attributes[at + key.len()..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+    }
+    None
+}
+
+fn xml_element<'a>(body: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&close)? + start;
+    Some(body[start..end].trim())
+}
+
+/// Grid cell of a position, ~100 m, matching the analyzer's location score
+pub fn place_cell(lat: f64, lon: f64) -> (i64, i64) {
+    ((lat * 1000.0) as i64, (lon * 1000.0) as i64)
+}
+
+/// A stretch of time the user spent at one place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visit {
+    pub place: (i64, i64),
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Group time-ordered points into visits: consecutive points in one cell,
+/// no more than `VISIT_GAP_SECS` apart
+pub fn visits(points: &[LocationPoint]) -> Vec<Visit> {
+    let mut sorted: Vec<&LocationPoint> = points.iter().collect();
+    sorted.sort_by_key(|p| p.timestamp);
+
+    let mut visits: Vec<Visit> = Vec::new();
+    for point in sorted {
+        let place = place_cell(point.lat, point.lon);
+        if let Some(last) = visits.last_mut() {
+            if last.place == place && point.timestamp - last.end <= VISIT_GAP_SECS {
+                last.end = point.timestamp;
+                continue;
+            }
+        }
+        visits.push(Visit {
+            place,
+            start: point.timestamp,
+            end: point.timestamp,
+        });
+    }
+    visits
+}
+
+/// How many of the user's places a device was heard at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocationOverlap {
+    pub mac: String,
+    /// Distinct places in the user's history
+    pub places: usize,
+    /// Places where the device was heard during at least one visit
+    pub places_present: usize,
+    pub visits: usize,
+    pub visits_present: usize,
+}
+
+impl LocationOverlap {
+    /// Share of the user's places the device was present at, 0-100
+    pub fn percent(&self) -> f64 {
+        if self.places == 0 {
+            0.0
+        } else {
+            self.places_present as f64 * 100.0 / self.places as f64
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "Present at {} of {} of your recorded locations ({:.0}%)",
+            self.places_present,
+            self.places,
+            self.percent()
+        )
+    }
+}
+
+/// Overlap of one device's sighting times with the user's visits
+pub fn location_overlap(mac: &str, visits: &[Visit], sightings: &[i64]) -> LocationOverlap {
+    let mut sorted = sightings.to_vec();
+    sorted.sort_unstable();
+
+    let mut places = HashSet::new();
+    let mut places_present = HashSet::new();
+    let mut visits_present = 0;
+    for visit in visits {
+        places.insert(visit.place);
+        let from = visit.start - PRESENCE_SLACK_SECS;
+        let to = visit.end + PRESENCE_SLACK_SECS;
+        let first = sorted.partition_point(|&t| t < from);
+        if sorted.get(first).map(|&t| t <= to).unwrap_or(false) {
+            visits_present += 1;
+            places_present.insert(visit.place);
+        }
+    }
+
+    LocationOverlap {
+        mac: mac.to_string(),
+        places: places.len(),
+        places_present: places_present.len(),
+        visits: visits.len(),
+        visits_present,
+    }
+}
+
+/// Overlap of every device heard in `[start, end]` with the user's visits in
+/// that range, highest first. Empty when no history covers the range.
+pub fn correlate(db: &Database, start: i64, end: i64) -> Result<Vec<LocationOverlap>> {
+    let history = db.get_location_history(start, end)?;
+    let user_visits = visits(&history);
+    if user_visits.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut sightings: HashMap<String, Vec<i64>> = HashMap::new();
+    for (mac, timestamp) in db.get_sighting_times(start - PRESENCE_SLACK_SECS, end + PRESENCE_SLACK_SECS)? {
+        sightings.entry(mac).or_default().push(timestamp);
+    }
+
+    let mut overlaps: Vec<LocationOverlap> = sightings
+        .iter()
+        .map(|(mac, times)| location_overlap(mac, &user_visits, times))
+        .filter(|overlap| overlap.places_present > 0)
+        .collect();
+    overlaps.sort_by(|a, b| {
+        b.percent()
+            .partial_cmp(&a.percent())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.visits_present.cmp(&a.visits_present))
+            .then(a.mac.cmp(&b.mac))
+    });
+    Ok(overlaps)
+}
+
+/// Import one file or every export under a directory. Returns points read
+/// and points newly stored; files that aren't location history are skipped.
+pub fn import_history(db: &Database, path: &Path) -> Result<(usize, usize)> {
+    let mut read = 0;
+    let mut stored = 0;
+    for file in history_files(path)? {
+        let parsed = match parse_history_file(&file) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                warn!("Skipping {}: {:#}", file.display(), e);
+                continue;
+            }
+        };
+        read += parsed.points.len();
+        stored += db.insert_location_points(&parsed.points, parsed.format.as_str())?;
+    }
+    Ok((read, stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_google_exports() {
+        let records = r#"{"locations": [
+            {"latitudeE7": 481372000, "longitudeE7": 115756000, "accuracy": 12, "timestamp": "2026-10-12T08:00:00Z"},
+            {"latitudeE7": 481372000, "longitudeE7": 115756000, "timestampMs": "1760256000000"},
+            {"latitudeE7": 0, "longitudeE7": 0, "timestamp": "2026-10-12T09:00:00Z"}
+        ]}"#;
+        let parsed = parse_google_json(records).unwrap();
+        assert_eq!(parsed.format, HistoryFormat::GoogleRecords);
+        assert_eq!(parsed.points.len(), 2);
+        assert_eq!(parsed.points[0].accuracy_m, Some(12.0));
+        assert!((parsed.points[0].lat - 48.1372).abs() < 1e-9);
+        assert_eq!(parsed.points[1].timestamp, 1_760_256_000);
+
+        let timeline = r#"{"semanticSegments": [
+            {"startTime": "2026-10-12T08:00:00.000+02:00", "endTime": "2026-10-12T09:00:00.000+02:00",
+             "visit": {"topCandidate": {"placeLocation": {"latLng": "48.1372°, 11.5756°"}}}},
+            {"timelinePath": [{"point": "48.2000°, 11.6000°", "time": "2026-10-12T09:10:00.000+02:00"}]}
+        ]}"#;
+        let parsed = parse_google_json(timeline).unwrap();
+        assert_eq!(parsed.format, HistoryFormat::GoogleTimeline);
+        assert_eq!(parsed.points.len(), 3);
+
+        assert!(parse_google_json(r#"{"something": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_gpx() {
+        let gpx = r#"<?xml version="1.0"?>
+            <gpx version="1.1"><trk><trkseg>
+            <trkpt lon="11.5756" lat="48.1372"><ele>520</ele><time>2026-10-12T08:00:00Z</time></trkpt>
+            <trkpt lat='48.1380' lon='11.5760'><time>2026-10-12T08:01:00Z</time></trkpt>
+            <trkpt lat="48.1390" lon="11.5770"/>
+            </trkseg></trk></gpx>"#;
+        let points = parse_gpx(gpx).unwrap();
+        assert_eq!(points.len(), 2);
+        assert!((points[1].lon - 11.576).abs() < 1e-9);
+        assert!(parse_gpx("{}").is_err());
+    }
+
+    #[test]
+    fn test_overlap_counts_places() {
+        let point = |timestamp, lat, lon| LocationPoint {
+            timestamp,
+            lat,
+            lon,
+            accuracy_m: None,
+        };
+        // Home twice, office, cafe
+        let history = vec![
+            point(0, 48.1372, 11.5756),
+            point(600, 48.1372, 11.5756),
+            point(10_000, 48.1500, 11.5800),
+            point(20_000, 48.1600, 11.5900),
+            point(30_000, 48.1372, 11.5756),
+        ];
+        let user_visits = visits(&history);
+        assert_eq!(user_visits.len(), 4);
+
+        // Heard at home (first visit) and at the office, not at the cafe
+        let overlap = location_overlap("AA:BB:CC:DD:EE:01", &user_visits, &[900, 10_300]);
+        assert_eq!(overlap.places, 3);
+        assert_eq!(overlap.places_present, 2);
+        assert_eq!(overlap.visits_present, 2);
+        assert!((overlap.percent() - 66.666).abs() < 0.01);
+    }
+}
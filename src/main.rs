@@ -1,21 +1,57 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use log::{error, info, warn, LevelFilter};
-use pcap::Capture;
-use prowl::analysis::SurveillanceAnalyzer;
+use prowl::alias::{self, LinkVerdict};
+use prowl::analysis::{device_ssids, diff_alerts, SurveillanceAnalyzer, FOLLOW_MIN_SCORE_CHANGE};
+#[cfg(unix)]
+use prowl::capture::spawn_pause_signal;
 use prowl::capture::CaptureEngine;
+use prowl::cases::{alert_snapshot, build_case_bundle, parse_time_range};
 use prowl::channels::{
-    find_monitor_interface, is_monitor_mode, list_wireless_interfaces, set_monitor_mode,
+    find_monitor_interface, is_monitor_mode, list_wireless_interfaces, set_monitor_mode, ChannelHopper,
 };
 use prowl::validation::validate_startup;
-use prowl::config::Config;
-use prowl::database::Database;
+use prowl::config::{CaptureBackend, Config};
+use prowl::database::{check_schema_version, Case, CaseItem, CaseItemKind, Database, SCHEMA_VERSION};
+use prowl::disk::{spawn_disk_monitor, DiskState};
 use prowl::distance::calibrate_tx_power;
-use prowl::ignore::{create_default_ignore_lists, IgnoreLists};
-use prowl::report::ReportGenerator;
+use prowl::email::spawn_summary_mailer;
+use prowl::exit::{self, ExitError};
+use prowl::export::{build_device_dossier, ExportFormat};
+#[cfg(feature = "pcap-export")]
+use prowl::export::export_device_frames;
+#[cfg(feature = "pcap-export")]
+use prowl::pcap_dump::PcapOutput;
+use prowl::fingerprint;
+use prowl::formats::Format;
+#[cfg(feature = "serve")]
+use prowl::health::spawn_health_server;
+use prowl::homenet::my_ssid_probes;
+use prowl::ignore::{create_default_ignore_lists, parse_mute_duration, IgnoreLists};
+use prowl::instance::InstanceLock;
+use prowl::location_history::{correlate, import_history};
+use prowl::maintenance::spawn_maintenance;
+use prowl::output::{self, paint, Cell, Severity, Table};
+use prowl::power::{spawn_power_monitor, BATTERY_UNKNOWN};
+use prowl::privileges::{PrivilegeDrop, CAP_NET_ADMIN};
+use prowl::report::{format_fix_rate, format_timestamp, ReportContext, ReportGenerator, ReportType};
+use prowl::residency::{update_residency, Residency};
+use prowl::retention::{self, spawn_purge};
+use prowl::risk::spawn_risk_updater;
+use prowl::parser::parse_probe_request;
+use prowl::radiotap::parse_radiotap;
+use prowl::simulate;
+use prowl::source::{open_source, FrameTime, ScriptedSource, PROBE_REQUEST_FILTER};
+use prowl::status::{self, spawn_status_line, CaptureStats};
+use prowl::update::{self, spawn_update_check};
+use prowl::watch::{Sighting, WatchTarget, Watcher, DEFAULT_ABSENCE_SECS};
+#[cfg(feature = "tui")]
 use prowl::tui;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -24,15 +60,20 @@ use std::time::{Duration, Instant};
 #[command(author = "spikehead")]
 #[command(version = "0.1.0")]
 #[command(about = "Wi-Fi probe request analyzer for surveillance detection")]
+#[command(after_help = exit::EXIT_CODES_HELP)]
 struct Cli {
     /// Config file path
-    #[arg(short, long, default_value = "config.json")]
+    #[arg(short, long, env = "PROWL_CONFIG", default_value = "config.json")]
     config: PathBuf,
 
     /// Wi-Fi interface (overrides config)
     #[arg(short, long)]
     interface: Option<String>,
 
+    /// Second Wi-Fi interface to split the bands with (overrides config)
+    #[arg(long)]
+    second_interface: Option<String>,
+
     /// Database file (overrides config)
     #[arg(short, long)]
     database: Option<PathBuf>,
@@ -41,13 +82,26 @@ struct Cli {
     #[arg(short, long)]
     verbose: bool,
 
+    /// Disable colored output
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Machine-readable output (tab-separated, no color)
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Defaults to a headless `capture`, so a container can run the bare
+    /// binary
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Start capturing probe requests
+    ///
+    /// Send SIGUSR1 to pause capture and again to resume it. Only one
+    /// capture may write to a database at a time; a second is refused.
     Capture {
         /// Set interface to monitor mode before capture
         #[arg(long)]
@@ -56,6 +110,31 @@ enum Commands {
         /// Disable GPS functionality
         #[arg(long)]
         no_gps: bool,
+
+        /// Replay a probe script (JSON lines) instead of capturing from an interface
+        #[arg(long, value_name = "SCRIPT")]
+        simulate: Option<PathBuf>,
+
+        /// Read probe requests from a pcap/pcapng file, keeping packet timestamps
+        #[arg(long, value_name = "PATH", conflicts_with = "simulate")]
+        from_file: Option<PathBuf>,
+
+        /// Show a live status line instead of logging every probe (ignored with --verbose)
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Capture as root and forward frames to an unprivileged prowl
+    ///
+    /// Opens the interface with `capture.helper_backend`, hops channels and
+    /// streams every frame to `capture.helper_socket`, where a capture, TUI
+    /// or watch configured with `"backend": "helper"` reads them. Only this
+    /// small process needs root or capture capabilities.
+    #[cfg(unix)]
+    CaptureHelper {
+        /// Set interface to monitor mode before capture
+        #[arg(long)]
+        set_monitor: bool,
     },
 
     /// Analyze captured data for surveillance patterns
@@ -65,8 +144,26 @@ enum Commands {
         last_hours: u32,
 
         /// Output file (stdout if not specified)
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "follow")]
         output: Option<PathBuf>,
+
+        /// Re-run analysis periodically and print only changes
+        #[arg(long)]
+        follow: bool,
+
+        /// Minutes between runs in follow mode
+        #[arg(long, default_value = "5")]
+        interval: u64,
+
+        /// Print nothing and exit 0 unless an alert reaches --min-score;
+        /// otherwise report only those alerts and exit 3
+        #[arg(long, conflicts_with = "follow")]
+        fail_on_alert: bool,
+
+        /// Minimum score that counts for --fail-on-alert
+        /// (defaults to analysis.persistence_threshold)
+        #[arg(long, requires = "fail_on_alert")]
+        min_score: Option<f64>,
     },
 
     /// Generate reports from database
@@ -75,9 +172,19 @@ enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// Report type
-        #[arg(long, default_value = "devices")]
-        report_type: String,
+        #[arg(
+            long,
+            default_value = "devices",
+            value_parser = ReportType::parse,
+            help = ReportType::help(),
+            long_help = ReportType::long_help()
+        )]
+        report_type: ReportType,
+
+        /// Number of hours covered by time series, summary, public and threat-intel reports,
+        /// and compared with the hours before them by stats
+        #[arg(long, default_value = "24")]
+        last_hours: u32,
     },
 
     /// List captured devices and probes
@@ -89,14 +196,101 @@ enum Commands {
         /// Show detailed probe information
         #[arg(long)]
         detailed: bool,
+
+        /// Most suspicious devices first, by their live risk score
+        #[arg(long)]
+        by_risk: bool,
+    },
+
+    /// Export everything known about a single device
+    Export {
+        /// MAC address of the device
+        #[arg(long)]
+        device: String,
+
+        #[arg(
+            long,
+            default_value = "json",
+            value_parser = ExportFormat::parse,
+            help = ExportFormat::help(),
+            long_help = ExportFormat::long_help()
+        )]
+        format: ExportFormat,
+
+        /// Capture files to take frames from for pcapng (default: the
+        /// capture.pcap_output file and its rotated series)
+        #[arg(long = "pcap")]
+        pcap: Vec<PathBuf>,
+
+        /// Output file (stdout if not specified; required for pcapng)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Delete all data for a MAC address or SSID
+    Prune {
+        /// Remove this device and all of its probes
+        #[arg(long, conflicts_with = "ssid", required_unless_present = "ssid")]
+        mac: Option<String>,
+
+        /// Remove all probes for this SSID (and devices left without probes)
+        #[arg(long)]
+        ssid: Option<String>,
+
+        /// Only report what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Group devices, alerts, notes and time ranges under named investigations
+    Case {
+        #[command(subcommand)]
+        action: CaseCommands,
+    },
+
+    /// Manage the MAC ignore list
+    Ignore {
+        #[command(subcommand)]
+        action: IgnoreCommands,
+    },
+
+    /// Audit and override the links between the randomized MACs of one device
+    Link {
+        #[command(subcommand)]
+        action: LinkCommands,
     },
 
     /// Show database statistics
-    Stats,
+    Stats {
+        /// Compare the last N hours with the N hours before them
+        #[arg(long, default_value = "24")]
+        last_hours: u32,
+    },
+
+    /// Reclassify devices as transient, visitor or resident and list them
+    Residency {
+        /// Only list devices of this class (transient, visitor, resident)
+        #[arg(long)]
+        class: Option<String>,
+    },
+
+    /// Import your own location history and see which devices went where you went
+    Locations {
+        #[command(subcommand)]
+        action: LocationCommands,
+    },
 
     /// Initialize configuration and ignore lists
     Init,
 
+    /// Show the prowl and database schema versions
+    Version {
+        /// Also check whether a newer release is available (needs curl and
+        /// network access)
+        #[arg(long)]
+        check: bool,
+    },
+
     /// Direct SQLite database access
     Db {
         #[command(subcommand)]
@@ -104,6 +298,10 @@ enum Commands {
     },
 
     /// Start interactive TUI dashboard with live capture
+    ///
+    /// If another prowl is already capturing into the database, the TUI
+    /// attaches read-only and shows its progress instead.
+    #[cfg(feature = "tui")]
     Tui {
         /// Set interface to monitor mode before capture
         #[arg(long)]
@@ -112,11 +310,46 @@ enum Commands {
         /// Disable GPS functionality
         #[arg(long)]
         no_gps: bool,
+
+        /// Browse the database without capturing; also what happens when
+        /// capture privileges are missing
+        #[arg(long, conflicts_with = "set_monitor")]
+        browse: bool,
+    },
+
+    /// Run against synthetic probe traffic (no monitor-mode hardware needed)
+    Simulate {
+        /// Database for synthetic data (kept apart from real captures)
+        #[arg(long, default_value = "prowl-sim.db")]
+        db: PathBuf,
+
+        /// Hours of synthetic history to write before going live (0 = none)
+        #[arg(long, default_value = "2")]
+        backfill_hours: u32,
+
+        /// Stop live generation after this many seconds (0 = until Ctrl+C)
+        #[arg(long, default_value = "60")]
+        duration: u64,
+
+        /// Show the live dashboard instead of printing an analysis at the end
+        #[cfg(feature = "tui")]
+        #[arg(long)]
+        tui: bool,
+
+        /// Random seed for the synthetic population
+        #[arg(long)]
+        seed: Option<u64>,
     },
 
     /// Scan for wireless interfaces
     Scan,
 
+    /// Check the GPS setup without starting a capture
+    Gps {
+        #[command(subcommand)]
+        action: GpsCommands,
+    },
+
     /// Calibrate distance estimation by capturing at known distance
     Calibrate {
         /// Known distance in meters to the probe source
@@ -131,6 +364,162 @@ enum Commands {
         #[arg(long)]
         set_monitor: bool,
     },
+
+    /// Follow one device live: print its probes and distance trend, and
+    /// beep when it shows up after an absence
+    Watch {
+        /// MAC address, or a fingerprint from `prowl export`
+        target: String,
+
+        /// Follow every MAC sharing the stored fingerprint of this MAC, with
+        /// the `prowl link` verdicts on it applied
+        #[arg(long)]
+        group: bool,
+
+        /// Seconds of silence after which a sighting counts as a return
+        #[arg(long, default_value_t = DEFAULT_ABSENCE_SECS)]
+        absence: i64,
+
+        /// Don't ring the terminal bell on arrival
+        #[arg(long)]
+        no_beep: bool,
+
+        /// Set interface to monitor mode before capture
+        #[arg(long)]
+        set_monitor: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CaseCommands {
+    /// Open a new case
+    Create {
+        name: String,
+
+        /// What the case is about
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// List cases
+    List,
+
+    /// Show the items attached to a case
+    Show { name: String },
+
+    /// Attach a device
+    AddDevice { name: String, mac: String },
+
+    /// Attach a snapshot of a device's current analysis result
+    AddAlert {
+        name: String,
+        mac: String,
+
+        /// Hours of history the analysis covers
+        #[arg(long, default_value = "24")]
+        last_hours: u32,
+    },
+
+    /// Attach a free-text note
+    Note { name: String, text: String },
+
+    /// Attach a time range (RFC 3339, "YYYY-MM-DD HH:MM" UTC, or unix seconds)
+    AddRange {
+        name: String,
+
+        #[arg(long)]
+        start: String,
+
+        #[arg(long)]
+        end: String,
+
+        /// What happened during the range
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// Export the case with device dossiers as JSON
+    Export {
+        name: String,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum IgnoreCommands {
+    /// Ignore a MAC temporarily (e.g. a visitor's phone)
+    Mute {
+        /// MAC address to mute
+        mac: String,
+
+        /// How long to mute it: 30m, 24h, 7d, 2w
+        duration: String,
+    },
+
+    /// Show ignored MACs and when mutes expire
+    List,
+}
+
+#[derive(Subcommand)]
+enum LinkCommands {
+    /// Show the MACs linked to a device with the evidence and confidence for each
+    Show { mac: String },
+
+    /// Mark two MACs as the same device
+    Confirm {
+        mac: String,
+        other: String,
+
+        /// Why, for whoever audits the link later
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// Mark two MACs as different devices, even if the evidence links them
+    Reject {
+        mac: String,
+        other: String,
+
+        /// Why, for whoever audits the link later
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// Forget a confirmation or rejection and go back to the evidence
+    Clear { mac: String, other: String },
+}
+
+#[derive(Subcommand)]
+enum GpsCommands {
+    /// Stream positions from gpsd and report fix quality and latency
+    Test {
+        /// How long to watch the feed (seconds)
+        #[arg(short = 't', long, default_value = "10")]
+        duration: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum LocationCommands {
+    /// Import a Google Takeout or on-device Timeline export, or GPX routes
+    /// from an Apple Health export (a file or a directory of them)
+    Import {
+        path: PathBuf,
+    },
+
+    /// Rank devices by how many of your recorded locations they were heard at
+    Overlap {
+        /// Days of history to correlate
+        #[arg(long, default_value = "7")]
+        days: u32,
+
+        /// Only list devices present at this share of your locations or more
+        #[arg(long, default_value = "50")]
+        min_percent: f64,
+    },
 }
 
 #[derive(Subcommand)]
@@ -163,10 +552,35 @@ enum DbCommands {
 
     /// Vacuum/optimize the database
     Vacuum,
+
+    /// Recompute derived data (capability fingerprints) made by an older
+    /// version of prowl
+    Reprocess,
+
+    /// List capture sessions and the adapter behind each
+    Sessions,
+
+    /// Roll up and delete raw probe data now, as `retention.auto_purge`
+    /// does while capturing
+    Purge {
+        /// Hours of raw data to keep (defaults to retention.raw_hours)
+        #[arg(long)]
+        keep_hours: Option<u32>,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit::code_for(&e))
+        }
+    }
+}
+
+async fn run() -> Result<u8> {
     let cli = Cli::parse();
 
     // Initialize logging
@@ -177,63 +591,253 @@ async fn main() -> Result<()> {
     };
     env_logger::Builder::new()
         .filter_level(log_level)
-        .format_timestamp_secs()
+        .format(|buf, record| {
+            // Move off the capture status line before writing a record
+            if status::is_active() {
+                write!(buf, "{}", status::CLEAR_LINE)?;
+            }
+            writeln!(
+                buf,
+                "[{} {:<5} {}] {}",
+                buf.timestamp_seconds(),
+                record.level(),
+                record.target(),
+                record.args()
+            )
+        })
         .init();
 
+    output::init(cli.no_color, cli.plain);
+
+    let command = cli.command.unwrap_or(Commands::Capture {
+        set_monitor: false,
+        no_gps: false,
+        simulate: None,
+        from_file: None,
+        status: false,
+    });
+
     // Handle init command before loading config
-    if matches!(cli.command, Commands::Init) {
-        return handle_init();
+    if matches!(command, Commands::Init) {
+        return handle_init().map(|()| exit::SUCCESS);
     }
 
     // Load configuration
     let mut config = if cli.config.exists() {
-        Config::load(&cli.config).context("Failed to load config")?
+        Config::load(&cli.config).map_err(|e| ExitError::new(exit::USAGE, format!("{:#}", e)))?
     } else {
         info!("Config file not found, using defaults");
         Config::default()
     };
 
+    // PROWL_* environment variables override the file, CLI args override both
+    config
+        .apply_env(|name| std::env::var(name).ok())
+        .map_err(|e| ExitError::new(exit::USAGE, format!("{:#}", e)))?;
+
     // Override config with CLI args
     if let Some(interface) = cli.interface {
         config.capture.interface = interface;
     }
+    if let Some(interface) = cli.second_interface {
+        config.capture.second_interface = Some(interface);
+    }
     if let Some(database) = cli.database {
         config.capture.database = database.to_string_lossy().to_string();
     }
 
+    // Builds without the gps feature never talk to gpsd
+    #[cfg(not(feature = "gps"))]
+    {
+        config.gps.enabled = false;
+    }
+
     // Execute command
-    match cli.command {
-        Commands::Capture { set_monitor, no_gps } => {
+    let result = match command {
+        Commands::Capture {
+            set_monitor,
+            no_gps,
+            simulate,
+            from_file,
+            status,
+        } => {
             if no_gps {
                 config.gps.enabled = false;
             }
-            handle_capture(config, set_monitor).await
+            let status = status && !cli.verbose && std::io::stderr().is_terminal();
+            match (simulate, from_file) {
+                (Some(script), _) => handle_simulated_capture(config, script, status).await,
+                (None, Some(path)) => handle_file_capture(config, path, status).await,
+                (None, None) => handle_capture(config, set_monitor, status).await,
+            }
+        }
+        #[cfg(unix)]
+        Commands::CaptureHelper { set_monitor } => handle_capture_helper(config, &cli.config, set_monitor),
+        Commands::Analyze {
+            last_hours,
+            output,
+            follow,
+            interval,
+            fail_on_alert,
+            min_score,
+        } => {
+            if follow {
+                handle_analyze_follow(config, last_hours, interval)
+            } else {
+                let fail_on = fail_on_alert
+                    .then(|| min_score.unwrap_or(config.analysis.persistence_threshold));
+                return handle_analyze(config, last_hours, output, fail_on);
+            }
         }
-        Commands::Analyze { last_hours, output } => handle_analyze(config, last_hours, output),
         Commands::Report {
             output,
             report_type,
-        } => handle_report(config, output, report_type),
+            last_hours,
+        } => handle_report(config, output, report_type, last_hours),
         Commands::List {
             last_hours,
             detailed,
-        } => handle_list(config, last_hours, detailed),
-        Commands::Stats => handle_stats(config),
+            by_risk,
+        } => handle_list(config, last_hours, detailed, by_risk),
+        Commands::Export {
+            device,
+            format,
+            pcap,
+            output,
+        } => handle_export(config, device, format, pcap, output),
+        Commands::Prune { mac, ssid, dry_run } => handle_prune(config, mac, ssid, dry_run),
+        Commands::Case { action } => handle_case(config, action),
+        Commands::Ignore { action } => handle_ignore(config, action),
+        Commands::Link { action } => handle_link(config, action),
+        Commands::Stats { last_hours } => handle_stats(config, last_hours),
+        Commands::Residency { class } => handle_residency(config, class),
+        Commands::Locations { action } => handle_locations(config, action),
         Commands::Init => unreachable!(),
+        Commands::Version { check } => handle_version(config, check),
         Commands::Db { action } => handle_db(config, action),
-        Commands::Tui { set_monitor, no_gps } => {
+        #[cfg(feature = "tui")]
+        Commands::Tui { set_monitor, no_gps, browse } => {
             if no_gps {
                 config.gps.enabled = false;
             }
-            tui::run_tui(config, set_monitor).await
+            tui::run_tui(config, set_monitor, browse).await
         }
+        #[cfg(feature = "tui")]
+        Commands::Simulate {
+            db,
+            backfill_hours,
+            duration,
+            tui,
+            seed,
+        } => handle_simulate(config, db, backfill_hours, duration, tui, seed).await,
+        #[cfg(not(feature = "tui"))]
+        Commands::Simulate {
+            db,
+            backfill_hours,
+            duration,
+            seed,
+        } => handle_simulate(config, db, backfill_hours, duration, false, seed).await,
         Commands::Scan => handle_scan(),
+        Commands::Gps { action } => handle_gps(config, action),
         Commands::Calibrate {
             distance,
             duration,
             set_monitor,
         } => handle_calibrate(config, distance, duration, set_monitor).await,
+        Commands::Watch {
+            target,
+            group,
+            absence,
+            no_beep,
+            set_monitor,
+        } => handle_watch(config, target, group, absence, !no_beep, set_monitor).await,
+    };
+    result.map(|()| exit::SUCCESS)
+}
+
+#[cfg(feature = "gps")]
+fn handle_gps(config: Config, action: GpsCommands) -> Result<()> {
+    use prowl::gps::{test_gpsd, GpsReport};
+
+    let fix_name = |mode: u8| match mode {
+        3 => "3D",
+        2 => "2D",
+        _ => "none",
+    };
+
+    match action {
+        GpsCommands::Test { duration } => {
+            let (host, port) = (config.gps.host.as_str(), config.gps.port);
+            println!("Watching gpsd at {}:{} for {}s (Ctrl+C to stop early)\n", host, port, duration);
+
+            let running = Arc::new(AtomicBool::new(true));
+            let r = running.clone();
+            ctrlc::set_handler(move || {
+                r.store(false, Ordering::SeqCst);
+            })?;
+
+            let summary = test_gpsd(host, port, Duration::from_secs(duration), &running, |sample| {
+                let at = format!("[{:>5.1}s]", sample.elapsed.as_secs_f64());
+                match sample.report {
+                    GpsReport::Position(pos) => println!(
+                        "{} {} fix  {:.6}, {:.6}{}{}",
+                        at,
+                        fix_name(pos.mode),
+                        pos.lat,
+                        pos.lon,
+                        pos.alt.map(|a| format!("  alt {:.0} m", a)).unwrap_or_default(),
+                        sample
+                            .latency
+                            .map(|l| format!("  latency {} ms", l.as_millis()))
+                            .unwrap_or_default()
+                    ),
+                    GpsReport::NoFix(mode) => println!("{} no fix (mode {})", at, mode),
+                    GpsReport::Sky(sky) => println!(
+                        "{} satellites {}/{} used, HDOP {}",
+                        at,
+                        sky.satellites_used.map(|n| n.to_string()).unwrap_or_else(|| "?".to_string()),
+                        sky.satellites_visible.map(|n| n.to_string()).unwrap_or_else(|| "?".to_string()),
+                        sky.hdop.map(|h| format!("{:.1}", h)).unwrap_or_else(|| "?".to_string())
+                    ),
+                }
+            })
+            .map_err(|e| ExitError::new(exit::GPS_UNAVAILABLE, format!("{:#}", e)))?;
+
+            let ms = |d: Duration| format!("{} ms", d.as_millis());
+            println!();
+            println!("Connected in:      {}", ms(summary.connect_time));
+            println!("Position reports:  {} ({} with a fix)", summary.reports, summary.fixes);
+            println!("Best fix:          {}", fix_name(summary.best_mode));
+            println!(
+                "First fix after:   {}",
+                summary.time_to_first_fix.map(ms).unwrap_or_else(|| "-".to_string())
+            );
+            println!("Update interval:   {}", summary.mean_interval().map(ms).unwrap_or_else(|| "-".to_string()));
+            println!(
+                "Latency:           {}",
+                match (summary.mean_latency(), summary.max_latency()) {
+                    (Some(mean), Some(max)) => format!("{} mean, {} max", ms(mean), ms(max)),
+                    _ => "-".to_string(),
+                }
+            );
+
+            if summary.fixes == 0 {
+                let reason = if summary.reports == 0 {
+                    "gpsd sent no position reports; is a receiver attached?"
+                } else {
+                    "gpsd is running but the receiver has no fix yet"
+                };
+                return Err(ExitError::new(exit::GPS_UNAVAILABLE, reason).into());
+            }
+            println!("\n{}", paint("GPS OK", Severity::Ok));
+        }
     }
+    Ok(())
+}
+
+#[cfg(not(feature = "gps"))]
+fn handle_gps(_config: Config, _action: GpsCommands) -> Result<()> {
+    Err(ExitError::new(exit::GPS_UNAVAILABLE, "This build of prowl has no GPS support").into())
 }
 
 fn handle_scan() -> Result<()> {
@@ -249,26 +853,33 @@ fn handle_scan() -> Result<()> {
 
     let mut found_monitor = false;
 
+    let mut table = Table::new(["Interface", "Mode"]);
     for (iface, mode) in &interfaces {
-        if mode == "monitor" {
-            println!("\x1b[32m[MONITOR]\x1b[0m {}", iface);
+        let severity = if mode == "monitor" {
             found_monitor = true;
+            Severity::Ok
         } else {
-            println!("\x1b[33m[{}]\x1b[0m {}", mode, iface);
-        }
+            Severity::Warning
+        };
+        table.add_row([Cell::new(iface.as_str()), Cell::new(mode.as_str()).severity(severity)]);
+    }
+    table.print();
+
+    if output::style().machine {
+        return Ok(());
     }
 
     println!();
 
     if found_monitor {
         if let Ok(Some(iface)) = find_monitor_interface() {
-            println!("\x1b[32mMonitor interface found: {}\x1b[0m", iface);
+            println!("{}", paint(&format!("Monitor interface found: {}", iface), Severity::Ok));
             println!("\nStart capturing with:");
             println!("  sudo prowl capture");
             println!("  sudo prowl tui");
         }
     } else {
-        println!("\x1b[33mNo monitor mode interfaces found.\x1b[0m");
+        println!("{}", paint("No monitor mode interfaces found.", Severity::Warning));
         println!("\nTo enable monitor mode:");
         println!("  sudo prowl capture --set-monitor");
         println!("  sudo prowl tui --set-monitor");
@@ -300,7 +911,7 @@ async fn handle_calibrate(
 
     // Set up interface
     let interface = if set_monitor {
-        set_monitor_mode(&config.capture.interface)?;
+        config.capture.interface = set_monitor_mode(&config.capture.interface)?;
         config.capture.interface.clone()
     } else if is_monitor_mode(&config.capture.interface)? {
         config.capture.interface.clone()
@@ -314,7 +925,7 @@ async fn handle_calibrate(
             config.capture.interface
         );
         error!("Use --set-monitor to enable monitor mode.");
-        return Ok(());
+        return Err(ExitError::new(exit::NO_MONITOR_INTERFACE, "No monitor-mode interface").into());
     };
 
     println!("Using interface: {}", interface);
@@ -322,17 +933,12 @@ async fn handle_calibrate(
     println!();
 
     // Open capture
-    let mut cap = Capture::from_device(interface.as_str())
-        .context("Failed to open capture device")?
-        .promisc(true)
-        .snaplen(65535)
-        .timeout(100)
-        .open()
-        .context("Failed to activate capture")?;
-
-    if let Err(e) = cap.filter("type mgt subtype probe-req", true) {
-        warn!("Failed to set BPF filter: {}", e);
-    }
+    let mut source = open_source(
+        &config.capture,
+        Some(PROBE_REQUEST_FILTER),
+        100,
+    )
+    .context("Failed to activate capture")?;
 
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -347,9 +953,9 @@ async fn handle_calibrate(
     let mut rssi_samples: Vec<i32> = Vec::new();
 
     while running.load(Ordering::SeqCst) && start.elapsed() < duration {
-        match cap.next_packet() {
-            Ok(packet) => {
-                if let Some(signal) = extract_signal_for_calibration(packet.data) {
+        match source.next_packet() {
+            Ok(Some(data)) => {
+                if let Some(signal) = extract_signal_for_calibration(data) {
                     rssi_samples.push(signal);
                     print!(
                         "\rSamples collected: {} (avg: {:.1} dBm)    ",
@@ -359,7 +965,7 @@ async fn handle_calibrate(
                     std::io::Write::flush(&mut std::io::stdout())?;
                 }
             }
-            Err(pcap::Error::TimeoutExpired) => continue,
+            Ok(None) => continue,
             Err(e) => {
                 error!("Capture error: {}", e);
                 break;
@@ -375,7 +981,7 @@ async fn handle_calibrate(
         error!("Make sure:");
         error!("  - A device is sending probe requests (WiFi on, scanning)");
         error!("  - The interface is in monitor mode");
-        return Ok(());
+        return Err(ExitError::new(exit::NO_DATA, "No probe requests captured").into());
     }
 
     // Calculate calibration
@@ -420,6 +1026,156 @@ async fn handle_calibrate(
     Ok(())
 }
 
+async fn handle_watch(
+    mut config: Config,
+    target: String,
+    group: bool,
+    absence_secs: i64,
+    beep: bool,
+    set_monitor: bool,
+) -> Result<()> {
+    let target =
+        WatchTarget::parse(&target).map_err(|e| ExitError::new(exit::USAGE, format!("{:#}", e)))?;
+    let target = match (group, target) {
+        (true, WatchTarget::Mac(mac)) => {
+            let db = Database::open(&config.capture.database).context("Failed to open database")?;
+            let device = db
+                .get_device_by_mac(&mac)?
+                .ok_or_else(|| ExitError::new(exit::NO_DATA, format!("{} is not in the database", mac)))?;
+            let fingerprint = match db.get_device_fingerprint(device.id)? {
+                Some((fp, version)) if version == fingerprint::current().version() => Some(fp),
+                Some(_) => {
+                    return Err(ExitError::new(
+                        exit::NO_DATA,
+                        format!("Fingerprint for {} is outdated; run `prowl db reprocess` first", mac),
+                    )
+                    .into())
+                }
+                None => None,
+            };
+            // `prowl link` verdicts override what the fingerprint says
+            let mut confirmed = BTreeSet::new();
+            let mut rejected = BTreeSet::new();
+            for (other, verdict) in alias::link_verdicts(&db, &mac)? {
+                match verdict {
+                    LinkVerdict::Confirmed => confirmed.insert(other),
+                    LinkVerdict::Rejected => rejected.insert(other),
+                };
+            }
+            if fingerprint.is_none() && confirmed.is_empty() {
+                let message = format!("No fingerprint stored for {} and no MACs confirmed as it", mac);
+                return Err(ExitError::new(exit::NO_DATA, message).into());
+            }
+            WatchTarget::Group {
+                mac,
+                fingerprint,
+                confirmed,
+                rejected,
+            }
+        }
+        (_, target) => target,
+    };
+
+    // Positions aren't recorded, so don't wait on gpsd
+    config.gps.enabled = false;
+    let validation = validate_startup(&config, set_monitor)?;
+    config.capture.interface = validation.interface;
+    config.capture.second_interface = validation.second_interface;
+
+    let mut source = open_source(&config.capture, Some(PROBE_REQUEST_FILTER), 100)
+        .context("Failed to activate capture")?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("\nStopping watch...");
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    // The hopper's thread gives up root once the handle is open
+    let _hopper = if config.capture.backend.has_radio() {
+        let hopper = ChannelHopper::new(
+            config.capture.interface.clone(),
+            config.capture.channels.clone(),
+            config.capture.hop_interval_ms,
+        )
+        .with_band_split(config.capture.second_interface.clone());
+        let privilege_drop = PrivilegeDrop::plan(&config.capture)?;
+        Some(hopper.spawn_thread(running.clone(), move || match privilege_drop {
+            Some(plan) => plan.apply(&[CAP_NET_ADMIN]),
+            None => Ok(()),
+        })?)
+    } else {
+        None
+    };
+
+    let mut watcher = Watcher::new(target, &config.distance).with_absence_secs(absence_secs);
+    println!(
+        "Watching for {} on {} (Ctrl+C to stop)",
+        watcher.target().describe(),
+        config.capture.interface
+    );
+
+    while running.load(Ordering::SeqCst) {
+        let (data, captured_at) = match source.next_timestamped() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => continue,
+            Err(e) => {
+                error!("Capture error: {}", e);
+                break;
+            }
+        };
+        let radiotap = parse_radiotap(data);
+        if radiotap.is_corrupt(data) {
+            continue;
+        }
+        if let Some(probe) = parse_probe_request(radiotap.strip_fcs(data), radiotap.signal_dbm) {
+            let now = captured_at.unwrap_or_else(FrameTime::now).secs;
+            if let Some(sighting) = watcher.observe(&probe, now) {
+                print_sighting(&sighting, now, beep);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_sighting(sighting: &Sighting, timestamp: i64, beep: bool) {
+    let time = format_timestamp(timestamp);
+    if sighting.arrived {
+        let away = match sighting.gap_secs {
+            Some(gap) if gap >= 3600 => format!(" after {}h {}m away", gap / 3600, gap % 3600 / 60),
+            Some(gap) => format!(" after {}m {}s away", gap / 60, gap % 60),
+            None => String::new(),
+        };
+        let bell = if beep { "\x07" } else { "" };
+        println!(
+            "{}{}",
+            bell,
+            paint(&format!("{}  {} is here{}", time, sighting.mac, away), Severity::Alert)
+        );
+    }
+
+    let signal = sighting
+        .signal_dbm
+        .map(|s| format!("{} dBm", s))
+        .unwrap_or_else(|| "? dBm".to_string());
+    let smoothed = sighting
+        .smoothed_dbm
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "?".to_string());
+    let distance = sighting
+        .distance_m
+        .map(|d| format!("~{:.1} m", d))
+        .unwrap_or_else(|| "N/A".to_string());
+    let trend = sighting.trend.map(|t| t.as_str()).unwrap_or("");
+    let ssid = if sighting.ssid.is_empty() { "<broadcast>" } else { sighting.ssid.as_str() };
+    println!(
+        "{}  {}  {:>8} (avg {:>4})  {:>8} {:<7}  {}",
+        time, sighting.mac, signal, smoothed, distance, trend, ssid
+    );
+}
+
 fn extract_signal_for_calibration(data: &[u8]) -> Option<i32> {
     // Basic radiotap signal extraction
     if data.len() < 8 || data[0] != 0 {
@@ -479,18 +1235,19 @@ fn extract_signal_for_calibration(data: &[u8]) -> Option<i32> {
     None
 }
 
-async fn handle_capture(mut config: Config, set_monitor: bool) -> Result<()> {
+async fn handle_capture(mut config: Config, set_monitor: bool, status: bool) -> Result<()> {
     // Perform startup validation (GPS + monitor mode)
     let validation = match validate_startup(&config, set_monitor) {
         Ok(v) => v,
         Err(e) => {
             error!("{}", e);
-            std::process::exit(1);
+            std::process::exit(exit::code_for(&anyhow::Error::from(e)) as i32);
         }
     };
 
     // Update config with resolved interface
     config.capture.interface = validation.interface.clone();
+    config.capture.second_interface = validation.second_interface.clone();
     info!("Using interface: {}", validation.interface);
 
     // Log GPS status
@@ -505,67 +1262,393 @@ async fn handle_capture(mut config: Config, set_monitor: bool) -> Result<()> {
         None => info!("GPS disabled"),
     }
 
+    // Refuse to become a second writer on the database
+    let _lock = InstanceLock::acquire(&config.capture.database, "capture")?;
+
     // Open database
     let db = Database::open(&config.capture.database).context("Failed to open database")?;
 
     // Load ignore lists
-    let ignore_lists =
+    let mut ignore_lists =
         IgnoreLists::load(&config.ignore_lists.mac, &config.ignore_lists.ssid).unwrap_or_default();
+    if config.ignore_lists.ignore_own_macs {
+        ignore_lists.ignore_local_macs();
+    }
 
     // Set up shared running flag for signal handling
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
 
-    // Set up Ctrl+C handler
+    // Ctrl+C, and SIGTERM from `docker stop` or systemd, stop capture cleanly
     ctrlc::set_handler(move || {
-        eprintln!("\nReceived Ctrl+C, stopping capture...");
+        eprintln!("\nReceived stop signal, stopping capture...");
         r.store(false, Ordering::SeqCst);
     })?;
 
-    // Create capture engine with shared running flag
-    let engine = CaptureEngine::new(config.clone(), db, ignore_lists, running);
+    // Scheduled summary emails run alongside capture on their own connection
+    let _mailer = spawn_summary_mailer(&config, running.clone());
+    // So does the weekly refresh of residency and the new-device baseline
+    let _maintenance = spawn_maintenance(&config, running.clone());
+    // and the live risk scores the TUI and `prowl list` sort by
+    let _risk = spawn_risk_updater(&config, running.clone());
+    // and, when enabled, the rolling purge of raw data
+    let _purge = spawn_purge(&config, running.clone());
+    // Opt-in release check; logs its result and is never waited on
+    let _update_check = spawn_update_check(&config.updates);
+
+    // On battery-powered sensors: level on the status line, channel parking
+    // when low, and a clean stop before the battery dies
+    // The health endpoint reads the same counters as the status line
+    let stats = (status || config.health.listen.is_some()).then(|| Arc::new(CaptureStats::new()));
+    let battery_level = match &stats {
+        Some(stats) => stats.battery.clone(),
+        None => Arc::new(AtomicU8::new(BATTERY_UNKNOWN)),
+    };
+    let (channel_updates, channel_rx) = tokio::sync::watch::channel(config.capture.channels.clone());
+    let _power = spawn_power_monitor(
+        &config.power,
+        &config.capture.database,
+        running.clone(),
+        battery_level,
+        Some((channel_updates, config.capture.channels.clone())),
+    );
 
-    // Run capture
-    if let Err(e) = engine.run().await {
-        error!("Capture failed: {}", e);
-        std::process::exit(1);
-    }
+    // Free space and time until the disk fills, for the status line and
+    // health endpoint; with `disk.shed_low_value`, capture sheds weak and
+    // broadcast probes when it is about to
+    let disk = match &stats {
+        Some(stats) => stats.disk.clone(),
+        None => Arc::new(DiskState::new()),
+    };
+    let _disk = spawn_disk_monitor(&config.disk, &config.capture.database, disk.clone(), running.clone());
 
-    // Force exit to ensure all threads terminate
+    // SIGUSR1 pauses and resumes capture; the status line shows which
+    let paused = match &stats {
+        Some(stats) => stats.paused.clone(),
+        None => Arc::new(AtomicBool::new(false)),
+    };
+    #[cfg(unix)]
+    let _pause = spawn_pause_signal(paused.clone())?;
+
+    // Create capture engine with shared running flag
+    let mut engine = CaptureEngine::new(config.clone(), db, ignore_lists, running.clone())
+        .with_channel_updates(channel_rx)
+        .with_pause_flag(paused)
+        .with_disk_state(disk);
+    let mut status_line = None;
+    let mut health = None;
+    if let Some(stats) = stats {
+        engine = engine.with_stats(stats.clone());
+        health = spawn_health_server(&config.health, stats.clone(), running.clone())?;
+        if status {
+            status_line = Some(spawn_status_line(stats, running.clone()));
+        }
+    }
+
+    // Run capture
+    let result = engine.run().await;
+    running.store(false, Ordering::SeqCst);
+    if let Some(handle) = status_line {
+        let _ = handle.join();
+    }
+    if let Some(handle) = health {
+        let _ = handle.join();
+    }
+    if let Err(e) = result {
+        error!("Capture failed: {}", e);
+        std::process::exit(exit::code_for(&e) as i32);
+    }
+
+    // Force exit to ensure all threads terminate
     info!("Exiting...");
     std::process::exit(0);
 }
 
-fn handle_analyze(config: Config, last_hours: u32, output: Option<PathBuf>) -> Result<()> {
+#[cfg(unix)]
+fn handle_capture_helper(mut config: Config, config_path: &Path, set_monitor: bool) -> Result<()> {
+    if config.capture.helper_backend == CaptureBackend::Helper {
+        return Err(ExitError::new(
+            exit::USAGE,
+            "capture.helper_backend must open the interface itself, not be \"helper\"",
+        )
+        .into());
+    }
+    config.capture.backend = config.capture.helper_backend;
+    // Positions are recorded by the prowl the frames go to
+    config.gps.enabled = false;
+    let validation = validate_startup(&config, set_monitor)?;
+    config.capture.interface = validation.interface;
+    config.capture.second_interface = validation.second_interface;
+    let listener_uid = prowl::helper::listener_uid(&config.capture, config_path)?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("\nReceived stop signal, stopping the capture helper...");
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    prowl::helper::run_helper(&config.capture, listener_uid, running)
+}
+
+async fn handle_simulated_capture(config: Config, script: PathBuf, status: bool) -> Result<()> {
+    let source = ScriptedSource::from_file(&script)?;
+    info!("Replaying {} scripted probes from {:?}", source.remaining(), script);
+
+    let _lock = InstanceLock::acquire(&config.capture.database, "capture")?;
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let ignore_lists =
+        IgnoreLists::load(&config.ignore_lists.mac, &config.ignore_lists.ssid).unwrap_or_default();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("\nReceived Ctrl+C, stopping capture...");
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let mut engine = CaptureEngine::new(config, db, ignore_lists, running.clone());
+    let mut status_line = None;
+    if status {
+        let stats = Arc::new(CaptureStats::new());
+        engine = engine.with_stats(stats.clone());
+        status_line = Some(spawn_status_line(stats, running.clone()));
+    }
+
+    let result = engine.run_with_source(Box::new(source)).await;
+    running.store(false, Ordering::SeqCst);
+    if let Some(handle) = status_line {
+        let _ = handle.join();
+    }
+    result
+}
+
+async fn handle_file_capture(mut config: Config, path: PathBuf, status: bool) -> Result<()> {
+    // The live GPS position says nothing about where the file was recorded
+    config.gps.enabled = false;
+
+    let _lock = InstanceLock::acquire(&config.capture.database, "capture")?;
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let ignore_lists =
+        IgnoreLists::load(&config.ignore_lists.mac, &config.ignore_lists.ssid).unwrap_or_default();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        eprintln!("\nReceived Ctrl+C, stopping capture...");
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let mut engine = CaptureEngine::new(config, db, ignore_lists, running.clone());
+    let mut status_line = None;
+    if status {
+        let stats = Arc::new(CaptureStats::new());
+        engine = engine.with_stats(stats.clone());
+        status_line = Some(spawn_status_line(stats, running.clone()));
+    }
+
+    let result = engine.run_from_file(&path).await;
+    running.store(false, Ordering::SeqCst);
+    if let Some(handle) = status_line {
+        let _ = handle.join();
+    }
+    result
+}
+
+async fn handle_simulate(
+    mut config: Config,
+    db_path: PathBuf,
+    backfill_hours: u32,
+    duration: u64,
+    use_tui: bool,
+    seed: Option<u64>,
+) -> Result<()> {
+    config.capture.database = db_path.to_string_lossy().to_string();
+    config.capture.backend = CaptureBackend::Simulated;
+    config.capture.interface = "simulated".to_string();
+    config.gps.enabled = false;
+    if let Some(seed) = seed {
+        config.capture.simulation.seed = seed;
+    }
+
+    // Refuse to become a second writer on the database. The TUI takes the
+    // lock itself, so it is let go before starting it.
+    let _lock = InstanceLock::acquire(&config.capture.database, "capture")?;
+
+    if backfill_hours > 0 {
+        let db = Database::open(&config.capture.database).context("Failed to open database")?;
+        let now = chrono::Utc::now().timestamp();
+        // Live traffic continues the same scenario, so shift the seed for history
+        let mut history = config.capture.simulation.clone();
+        history.seed = history.seed.wrapping_add(1);
+        let written = simulate::backfill(&db, &history, backfill_hours, now)?;
+        info!("Backfilled {} synthetic probes over {}h into {:?}", written, backfill_hours, db_path);
+    }
+
+    #[cfg(feature = "tui")]
+    if use_tui {
+        drop(_lock);
+        return tui::run_tui(config, false, false).await;
+    }
+    #[cfg(not(feature = "tui"))]
+    let _ = use_tui;
+
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    if duration > 0 {
+        let r = running.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(duration)).await;
+            r.store(false, Ordering::SeqCst);
+        });
+    }
+
+    info!("Generating synthetic traffic (Ctrl+C to stop)...");
+    let engine = CaptureEngine::new(config.clone(), db, IgnoreLists::default(), running);
+    engine.run().await?;
+
+    handle_analyze(config, backfill_hours.max(1), None, None).map(|_| ())
+}
+
+/// Run one analysis pass. Returns `exit::ALERTS_FOUND` when anything was
+/// flagged so wrappers can branch without parsing the report.
+///
+/// With `fail_on` set, only alerts scoring at least that much count: they
+/// are the only ones reported, and nothing is printed when there are none,
+/// so `prowl analyze --fail-on-alert || mail ...` style cron jobs stay quiet.
+fn handle_analyze(
+    config: Config,
+    last_hours: u32,
+    output: Option<PathBuf>,
+    fail_on: Option<f64>,
+) -> Result<u8> {
     let db = Database::open(&config.capture.database).context("Failed to open database")?;
 
+    let now = chrono::Utc::now().timestamp();
+    if db.get_devices_in_time_range(now - last_hours as i64 * 3600, now)?.is_empty() {
+        return Err(ExitError::new(
+            exit::NO_DATA,
+            format!("No devices seen in the last {}h", last_hours),
+        )
+        .into());
+    }
+
     let analyzer = SurveillanceAnalyzer::new(
         config.analysis.time_windows_minutes,
         config.analysis.persistence_threshold,
-    );
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+
+    let mut alerts = analyzer.analyze(&db, last_hours)?;
+    // First probes for your own networks count whatever the scores
+    let home_probes = if config.analysis.alert_my_ssid_probes {
+        my_ssid_probes(&db, now - last_hours as i64 * 3600)?
+    } else {
+        Vec::new()
+    };
 
-    let alerts = analyzer.analyze(&db, last_hours)?;
+    if let Some(min_score) = fail_on {
+        alerts.retain(|a| a.score >= min_score);
+        if alerts.is_empty() && home_probes.is_empty() {
+            info!("No alerts scored {:.2} or higher", min_score);
+            return Ok(exit::SUCCESS);
+        }
+    }
 
-    ReportGenerator::generate_surveillance_report(&alerts, output.as_deref())
+    match output {
+        Some(path) => {
+            ReportGenerator::generate_surveillance_report(&alerts, Some(&path))?;
+            for probe in &home_probes {
+                warn!("Probe for your network: {}", probe.describe());
+            }
+        }
+        None => {
+            ReportGenerator::print_alert_table(&alerts, config.analysis.persistence_threshold);
+            ReportGenerator::print_my_ssid_probes(&home_probes);
+        }
+    }
+
+    Ok(if alerts.is_empty() && home_probes.is_empty() {
+        exit::SUCCESS
+    } else {
+        exit::ALERTS_FOUND
+    })
 }
 
-fn handle_report(config: Config, output: Option<PathBuf>, report_type: String) -> Result<()> {
+fn handle_analyze_follow(config: Config, last_hours: u32, interval_minutes: u64) -> Result<()> {
     let db = Database::open(&config.capture.database).context("Failed to open database")?;
 
-    match report_type.as_str() {
-        "devices" => ReportGenerator::generate_device_list(&db, output.as_deref()),
-        "stats" => ReportGenerator::generate_stats(&db),
-        _ => {
-            error!("Unknown report type: {}", report_type);
-            Ok(())
+    let analyzer = SurveillanceAnalyzer::new(
+        config.analysis.time_windows_minutes,
+        config.analysis.persistence_threshold,
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    println!(
+        "Following analysis of the last {}h every {} min (Ctrl+C to stop)",
+        last_hours, interval_minutes
+    );
+
+    let interval = Duration::from_secs(interval_minutes.max(1) * 60);
+    let mut previous: HashMap<String, f64> = HashMap::new();
+
+    while running.load(Ordering::SeqCst) {
+        match analyzer.analyze(&db, last_hours) {
+            Ok(alerts) => {
+                let delta = diff_alerts(&previous, &alerts, FOLLOW_MIN_SCORE_CHANGE);
+                ReportGenerator::print_alert_delta(&delta);
+
+                previous = alerts
+                    .iter()
+                    .map(|a| (a.device.mac.clone(), a.score))
+                    .collect();
+            }
+            // A busy or briefly unreadable database shouldn't end the watch
+            Err(e) => error!("Analysis failed, retrying next interval: {:#}", e),
+        }
+
+        let started = Instant::now();
+        while running.load(Ordering::SeqCst) && started.elapsed() < interval {
+            std::thread::sleep(Duration::from_millis(250));
         }
     }
+
+    Ok(())
+}
+
+fn handle_report(
+    config: Config,
+    output: Option<PathBuf>,
+    report_type: ReportType,
+    last_hours: u32,
+) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+
+    report_type.generate(&ReportContext {
+        db: &db,
+        config: &config,
+        last_hours,
+        output: output.as_deref(),
+    })
 }
 
-fn handle_list(config: Config, last_hours: Option<u32>, detailed: bool) -> Result<()> {
+fn handle_list(config: Config, last_hours: Option<u32>, detailed: bool, by_risk: bool) -> Result<()> {
     let db = Database::open(&config.capture.database).context("Failed to open database")?;
 
-    let devices = if let Some(hours) = last_hours {
+    let mut devices = if let Some(hours) = last_hours {
         let now = chrono::Utc::now().timestamp();
         let start = now - (hours as i64 * 3600);
         db.get_devices_in_time_range(start, now)?
@@ -573,41 +1656,577 @@ fn handle_list(config: Config, last_hours: Option<u32>, detailed: bool) -> Resul
         db.get_all_devices()?
     };
 
-    println!("Found {} devices", devices.len());
-    println!();
+    let risk_scores = db.get_risk_scores()?;
+    if by_risk {
+        // Unscored devices sort last
+        let risk = |mac: &str| risk_scores.get(mac).copied().unwrap_or(-1.0);
+        devices.sort_by(|a, b| risk(&b.mac).total_cmp(&risk(&a.mac)));
+    }
+
+    let mut table = if detailed {
+        Table::new(["MAC", "Risk", "Probes", "GPS fix", "SSIDs", "Recent probes"])
+    } else {
+        Table::new(["MAC", "Risk", "Probes", "GPS fix", "SSIDs"])
+    };
 
     for device in &devices {
         let probes = db.get_probes_for_device(device.id)?;
-        let ssids = db.get_unique_ssids_for_device(device.id)?;
+        let ssids = device_ssids(&db, device.id)?;
+        let gps = db.get_gps_fix_stats(Some(device.id))?;
+        let risk = match risk_scores.get(&device.mac) {
+            Some(score) => Cell::new(format!("{:.0}%", score * 100.0))
+                .severity(Severity::from_score(*score, config.analysis.persistence_threshold)),
+            None => Cell::new("-"),
+        };
+
+        let mut row = vec![
+            Cell::new(device.mac.as_str()),
+            risk,
+            Cell::new(probes.len().to_string()),
+            Cell::new(format_fix_rate(&gps)),
+            Cell::new(ssids.join(", ")),
+        ];
+
+        if detailed {
+            let recent: Vec<String> = probes
+                .iter()
+                .take(5)
+                .map(|probe| {
+                    let ssid = if probe.ssid.is_empty() {
+                        "<broadcast>"
+                    } else {
+                        &probe.ssid
+                    };
+                    let channel = probe.channel.map(|c| c.to_string()).unwrap_or_else(|| "-".into());
+                    let signal = probe
+                        .signal_dbm
+                        .map(|s| format!("{}dBm", s))
+                        .unwrap_or_else(|| "-".into());
+                    let gps = probe.gps_status.map(|g| g.as_str()).unwrap_or("unknown");
+                    let target = probe
+                        .bssid
+                        .as_deref()
+                        .map(|b| format!(" -> {}", b))
+                        .unwrap_or_default();
+                    format!("{}{} (ch {}, {}, gps {})", ssid, target, channel, signal, gps)
+                })
+                .collect();
+            row.push(Cell::new(recent.join("\n")));
+        }
 
-        println!("MAC: {}", device.mac);
-        println!("  Probes: {}", probes.len());
-        println!("  SSIDs: {}", ssids.join(", "));
+        table.add_row(row);
+    }
 
-        if detailed && !probes.is_empty() {
-            println!("  Recent probes:");
-            for probe in probes.iter().take(5) {
-                let ssid = if probe.ssid.is_empty() {
-                    "<broadcast>"
-                } else {
-                    &probe.ssid
+    table.print();
+    if !output::style().machine {
+        println!("Found {} devices", devices.len());
+    }
+
+    Ok(())
+}
+
+fn handle_export(
+    config: Config,
+    mac: String,
+    format: ExportFormat,
+    pcap: Vec<PathBuf>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+
+    let analyzer = SurveillanceAnalyzer::new(
+        config.analysis.time_windows_minutes,
+        config.analysis.persistence_threshold,
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+
+    let dossier = match build_device_dossier(&db, &mac, &analyzer)? {
+        Some(d) => d,
+        None => {
+            return Err(ExitError::new(exit::NO_DATA, format!("Device {} not found in database", mac)).into())
+        }
+    };
+
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&dossier)?,
+        ExportFormat::Pcapng => {
+            let output = output
+                .ok_or_else(|| ExitError::new(exit::USAGE, "pcapng export needs --output"))?;
+            return export_device_pcapng(&config, &db, &dossier, pcap, &output);
+        }
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, content)?;
+            info!("Exported device {} to {:?}", dossier.device.mac, path);
+        }
+        None => println!("{}", content),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "pcap-export")]
+fn export_device_pcapng(
+    config: &Config,
+    db: &Database,
+    dossier: &prowl::export::DeviceDossier,
+    mut inputs: Vec<PathBuf>,
+    output: &std::path::Path,
+) -> Result<()> {
+    if inputs.is_empty() {
+        if let Some(path) = &config.capture.pcap_output {
+            inputs = PcapOutput::series_files(path);
+        }
+    }
+    if inputs.is_empty() {
+        return Err(ExitError::new(
+            exit::NO_DATA,
+            "No capture files to read; pass --pcap or set capture.pcap_output",
+        )
+        .into());
+    }
+
+    let label = db.get_device_labels()?.remove(&dossier.device.mac);
+    let written = export_device_frames(dossier, label.as_deref(), &inputs, output)?;
+    if written == 0 {
+        return Err(ExitError::new(
+            exit::NO_DATA,
+            format!("No frames from {} in {} capture file(s)", dossier.device.mac, inputs.len()),
+        )
+        .into());
+    }
+    info!("Exported {} frames from {} to {:?}", written, dossier.device.mac, output);
+    Ok(())
+}
+
+#[cfg(not(feature = "pcap-export"))]
+fn export_device_pcapng(
+    _config: &Config,
+    _db: &Database,
+    _dossier: &prowl::export::DeviceDossier,
+    _inputs: Vec<PathBuf>,
+    _output: &std::path::Path,
+) -> Result<()> {
+    Err(ExitError::new(exit::USAGE, "pcapng export needs a build with the pcap-export feature").into())
+}
+
+#[cfg(not(feature = "serve"))]
+fn spawn_health_server(
+    config: &prowl::config::HealthConfig,
+    _stats: Arc<CaptureStats>,
+    _running: Arc<AtomicBool>,
+) -> Result<Option<std::thread::JoinHandle<()>>> {
+    if config.listen.is_some() {
+        warn!("health.listen is set but this build lacks the serve feature");
+    }
+    Ok(None)
+}
+
+fn handle_prune(
+    config: Config,
+    mac: Option<String>,
+    ssid: Option<String>,
+    dry_run: bool,
+) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+
+    let (target, summary) = match (mac, ssid) {
+        (Some(mac), _) => {
+            let normalized = mac.to_uppercase().replace(['-', '.'], ":");
+            let summary = db.prune_mac(&normalized, dry_run)?;
+            (format!("MAC {}", normalized), summary)
+        }
+        (None, Some(ssid)) => {
+            let summary = db.prune_ssid(&ssid, dry_run)?;
+            (format!("SSID {:?}", ssid), summary)
+        }
+        (None, None) => anyhow::bail!("Specify --mac or --ssid"),
+    };
+
+    let verb = if dry_run { "Would delete" } else { "Deleted" };
+    println!("{} for {}:", verb, target);
+    println!("  Devices:         {}", summary.devices);
+    println!("  Probes:          {}", summary.probes);
+    println!("  Capabilities:    {}", summary.capabilities);
+    println!("  Device records:  {}", summary.device_records);
+    println!("  Probe responses: {}", summary.probe_responses);
+    println!("  Deauth frames:   {}", summary.deauth_frames);
+    println!("  Case items:      {}", summary.case_items);
+    println!("  Events:          {}", summary.events);
+
+    if !dry_run && summary.probes > 0 {
+        println!();
+        println!("Run 'prowl db vacuum' to reclaim disk space.");
+    }
+
+    Ok(())
+}
+
+fn handle_case(config: Config, action: CaseCommands) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let analyzer = SurveillanceAnalyzer::new(
+        config.analysis.time_windows_minutes,
+        config.analysis.persistence_threshold,
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+    let now = chrono::Utc::now().timestamp();
+
+    let find_case = |name: &str| -> Result<Case> {
+        db.get_case(name)?
+            .ok_or_else(|| ExitError::new(exit::NO_DATA, format!("No case named {:?}", name)).into())
+    };
+    let normalize = |mac: &str| mac.to_uppercase().replace(['-', '.'], ":");
+
+    match action {
+        CaseCommands::Create { name, description } => {
+            db.create_case(&name, description.as_deref(), now)?;
+            println!("Created case {:?}", name);
+        }
+        CaseCommands::List => {
+            let cases = db.list_cases()?;
+            if cases.is_empty() {
+                println!("No cases.");
+                return Ok(());
+            }
+            let mut table = Table::new(["Case", "Created", "Items", "Description"]);
+            for (case, items) in cases {
+                table.add_row([
+                    Cell::new(case.name),
+                    Cell::new(format_timestamp(case.created_at)),
+                    Cell::new(items.to_string()),
+                    Cell::new(case.description.unwrap_or_default()),
+                ]);
+            }
+            table.print();
+        }
+        CaseCommands::Show { name } => {
+            let case = find_case(&name)?;
+            output::heading(&format!("Case: {}", case.name));
+            if let Some(description) = &case.description {
+                println!("{}", description);
+            }
+            println!("Created {}", format_timestamp(case.created_at));
+            println!();
+
+            let mut table = Table::new(["Added", "Kind", "Detail"]);
+            for item in db.get_case_items(case.id)? {
+                let detail = match item.kind {
+                    CaseItemKind::Device => item.mac.unwrap_or_default(),
+                    CaseItemKind::Alert => {
+                        let score = item
+                            .data_json
+                            .as_deref()
+                            .and_then(|j| serde_json::from_str::<serde_json::Value>(j).ok())
+                            .and_then(|v| v["score"].as_f64())
+                            .unwrap_or(0.0);
+                        format!("{} ({:.0}%)", item.mac.unwrap_or_default(), score * 100.0)
+                    }
+                    CaseItemKind::Note => item.note.unwrap_or_default(),
+                    CaseItemKind::TimeRange => {
+                        let range = format!(
+                            "{} - {}",
+                            format_timestamp(item.start.unwrap_or(0)),
+                            format_timestamp(item.end.unwrap_or(0))
+                        );
+                        match item.note {
+                            Some(note) => format!("{}  {}", range, note),
+                            None => range,
+                        }
+                    }
                 };
-                println!(
-                    "    - {} (ch:{:?}, signal:{:?})",
-                    ssid, probe.channel, probe.signal_dbm
-                );
+                table.add_row([
+                    Cell::new(format_timestamp(item.added_at)),
+                    Cell::new(item.kind.as_str()),
+                    Cell::new(detail),
+                ]);
+            }
+            table.print();
+        }
+        CaseCommands::AddDevice { name, mac } => {
+            let case = find_case(&name)?;
+            let mac = normalize(&mac);
+            if db.get_device_by_mac(&mac)?.is_none() {
+                warn!("Device {} has not been captured yet", mac);
+            }
+            db.add_case_item(case.id, &CaseItem::device(&mac, now))?;
+            println!("Added device {} to case {:?}", mac, case.name);
+        }
+        CaseCommands::AddAlert { name, mac, last_hours } => {
+            let case = find_case(&name)?;
+            let mac = normalize(&mac);
+            let alert = alert_snapshot(&db, &analyzer, &mac, last_hours)?.ok_or_else(|| {
+                ExitError::new(exit::NO_DATA, format!("No probes from {} to analyze", mac))
+            })?;
+            db.add_case_item(case.id, &CaseItem::alert(&mac, serde_json::to_string(&alert)?, now))?;
+            println!(
+                "Added alert for {} ({:.0}%) to case {:?}",
+                mac,
+                alert.score * 100.0,
+                case.name
+            );
+        }
+        CaseCommands::Note { name, text } => {
+            let case = find_case(&name)?;
+            db.add_case_item(case.id, &CaseItem::note(&text, now))?;
+            println!("Added note to case {:?}", case.name);
+        }
+        CaseCommands::AddRange { name, start, end, note } => {
+            let case = find_case(&name)?;
+            let (start, end) = parse_time_range(&start, &end)
+                .map_err(|e| ExitError::new(exit::USAGE, format!("{:#}", e)))?;
+            db.add_case_item(case.id, &CaseItem::time_range(start, end, note.as_deref(), now))?;
+            println!(
+                "Added {} - {} to case {:?}",
+                format_timestamp(start),
+                format_timestamp(end),
+                case.name
+            );
+        }
+        CaseCommands::Export { name, output } => {
+            let bundle = build_case_bundle(&db, &name, &analyzer)?
+                .ok_or_else(|| ExitError::new(exit::NO_DATA, format!("No case named {:?}", name)))?;
+            let content = serde_json::to_string_pretty(&bundle)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, content)?;
+                    info!("Exported case {:?} to {:?}", name, path);
+                }
+                None => println!("{}", content),
             }
         }
-        println!();
     }
 
     Ok(())
 }
 
-fn handle_stats(config: Config) -> Result<()> {
+fn handle_ignore(config: Config, action: IgnoreCommands) -> Result<()> {
+    let mac_path = PathBuf::from(&config.ignore_lists.mac);
+    let ssid_path = PathBuf::from(&config.ignore_lists.ssid);
+    let mut lists = IgnoreLists::load(&mac_path, &ssid_path)?;
+    let now = chrono::Utc::now();
+
+    match action {
+        IgnoreCommands::Mute { mac, duration } => {
+            let duration = parse_mute_duration(&duration)
+                .map_err(|e| ExitError::new(exit::USAGE, format!("{:#}", e)))?;
+            let expires = now + duration;
+            lists.purge_expired(now);
+            lists.mute_mac(&mac, expires);
+
+            if let Some(dir) = mac_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            lists.save_mac_list(&mac_path)?;
+            println!(
+                "Muted {} until {}",
+                mac.to_uppercase().replace(['-', '.'], ":"),
+                expires.format("%Y-%m-%d %H:%M UTC")
+            );
+        }
+        IgnoreCommands::List => {
+            let entries = lists.mac_entries();
+            if entries.is_empty() {
+                println!("No MAC addresses are ignored.");
+                return Ok(());
+            }
+
+            let mut table = Table::new(["MAC", "Until"]);
+            for (mac, expires) in entries {
+                let until = match expires {
+                    Some(e) if e <= now => Cell::new("expired").severity(Severity::Info),
+                    Some(e) => Cell::new(e.format("%Y-%m-%d %H:%M UTC").to_string()).severity(Severity::Warning),
+                    None => Cell::new("permanent"),
+                };
+                table.add_row([Cell::new(mac), until]);
+            }
+            table.print();
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_stats(config: Config, last_hours: u32) -> Result<()> {
     let db = Database::open(&config.capture.database).context("Failed to open database")?;
 
-    ReportGenerator::generate_stats(&db)
+    ReportGenerator::generate_stats(&db, last_hours)
+}
+
+fn handle_link(config: Config, action: LinkCommands) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let normalize = |mac: &str| mac.to_uppercase().replace(['-', '.'], ":");
+    let no_data = |e: anyhow::Error| ExitError::new(exit::NO_DATA, format!("{:#}", e));
+    let now = chrono::Utc::now().timestamp();
+
+    let (mac, other, verdict, note) = match action {
+        LinkCommands::Show { mac } => {
+            let mac = normalize(&mac);
+            let links = alias::device_links(&db, &mac).map_err(no_data)?;
+            if links.is_empty() {
+                println!("No other MACs share a fingerprint or a verdict with {}.", mac);
+                return Ok(());
+            }
+            let mut table = Table::new(["MAC", "Confidence", "Evidence", "Status"]);
+            for link in &links {
+                let status = match &link.user_override {
+                    Some(o) => {
+                        let mut status = format!(
+                            "{} {} (at {:.0}%)",
+                            o.verdict.as_str(),
+                            format_timestamp(o.updated_at),
+                            o.confidence * 100.0
+                        );
+                        if let Some(note) = &o.note {
+                            status.push_str(&format!(": {}", note));
+                        }
+                        let severity = if link.linked() { Severity::Ok } else { Severity::Info };
+                        Cell::new(status).severity(severity)
+                    }
+                    None if link.linked() => Cell::new("linked"),
+                    None => Cell::new("-"),
+                };
+                table.add_row([
+                    Cell::new(link.mac.as_str()),
+                    Cell::new(format!("{:.0}%", link.confidence * 100.0)),
+                    Cell::new(link.evidence.describe()),
+                    status,
+                ]);
+            }
+            table.print();
+            return Ok(());
+        }
+        LinkCommands::Clear { mac, other } => {
+            let (mac, other) = (normalize(&mac), normalize(&other));
+            if alias::clear_verdict(&db, &mac, &other)? {
+                println!("Cleared the verdict on {} and {}", mac, other);
+            } else {
+                println!("No verdict recorded for {} and {}", mac, other);
+            }
+            return Ok(());
+        }
+        LinkCommands::Confirm { mac, other, note } => (mac, other, LinkVerdict::Confirmed, note),
+        LinkCommands::Reject { mac, other, note } => (mac, other, LinkVerdict::Rejected, note),
+    };
+
+    let (mac, other) = (normalize(&mac), normalize(&other));
+    if mac == other {
+        return Err(ExitError::new(exit::USAGE, "Give two different MAC addresses").into());
+    }
+    let evidence = alias::set_verdict(&db, &mac, &other, verdict, note.as_deref(), now).map_err(no_data)?;
+    let outcome = match verdict {
+        LinkVerdict::Confirmed => "one device",
+        LinkVerdict::Rejected => "different devices",
+    };
+    println!(
+        "Marked {} and {} as {} (evidence: {}, {:.0}% confidence)",
+        mac,
+        other,
+        outcome,
+        evidence.describe(),
+        evidence.confidence() * 100.0
+    );
+    Ok(())
+}
+
+fn handle_residency(config: Config, class: Option<String>) -> Result<()> {
+    let filter = match class.as_deref() {
+        Some(name) => Some(Residency::parse(name).ok_or_else(|| {
+            ExitError::new(
+                exit::USAGE,
+                format!("Unknown class '{}' (expected transient, visitor or resident)", name),
+            )
+        })?),
+        None => None,
+    };
+
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    let now = chrono::Utc::now().timestamp();
+    let changes = update_residency(&db, now)?;
+    for change in &changes {
+        let from = change.from.map(|c| c.as_str()).unwrap_or("unclassified");
+        info!("{}: {} -> {}", change.mac, from, change.to.as_str());
+    }
+
+    let mut records: Vec<_> = db
+        .get_residency()?
+        .into_values()
+        .filter(|r| filter.is_none() || filter == Some(r.class))
+        .collect();
+    if records.is_empty() {
+        return Err(ExitError::new(exit::NO_DATA, "No devices to classify").into());
+    }
+    records.sort_by(|a, b| b.days_seen.cmp(&a.days_seen).then_with(|| a.mac.cmp(&b.mac)));
+
+    let mut table = Table::new(["MAC", "Class", "Days Seen", "First Seen", "Resident Since"]);
+    for record in &records {
+        let class = if record.is_new_resident(now) {
+            Cell::new("resident (new)").severity(Severity::Alert)
+        } else {
+            Cell::new(record.class.as_str())
+        };
+        table.add_row([
+            Cell::new(record.mac.as_str()),
+            class,
+            Cell::new(record.days_seen.to_string()),
+            Cell::new(format_timestamp(record.first_seen)),
+            Cell::new(record.resident_since.map(format_timestamp).unwrap_or_else(|| "-".to_string())),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+fn handle_locations(config: Config, action: LocationCommands) -> Result<()> {
+    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+
+    match action {
+        LocationCommands::Import { path } => {
+            let (read, stored) = import_history(&db, &path)?;
+            if read == 0 {
+                return Err(ExitError::new(
+                    exit::NO_DATA,
+                    format!("No location history found in {}", path.display()),
+                )
+                .into());
+            }
+            info!("Read {} location points, {} new", read, stored);
+        }
+        LocationCommands::Overlap { days, min_percent } => {
+            let now = chrono::Utc::now().timestamp();
+            let overlaps = correlate(&db, now - days as i64 * 86400, now)?;
+
+            let mut table = Table::new(["MAC", "Your Locations", "Share", "Visits"]);
+            for overlap in overlaps.iter().filter(|o| o.percent() >= min_percent) {
+                let severity = if overlap.percent() >= 80.0 {
+                    Severity::Alert
+                } else {
+                    Severity::Warning
+                };
+                table.add_row([
+                    Cell::new(overlap.mac.as_str()),
+                    Cell::new(format!("{}/{}", overlap.places_present, overlap.places)),
+                    Cell::new(format!("{:.0}%", overlap.percent())).severity(severity),
+                    Cell::new(format!("{}/{}", overlap.visits_present, overlap.visits)),
+                ]);
+            }
+            if table.is_empty() {
+                return Err(ExitError::new(
+                    exit::NO_DATA,
+                    format!(
+                        "No device was heard at {:.0}% or more of your recorded locations in the last {} days",
+                        min_percent, days
+                    ),
+                )
+                .into());
+            }
+            table.print();
+        }
+    }
+    Ok(())
 }
 
 fn handle_init() -> Result<()> {
@@ -628,6 +2247,30 @@ fn handle_init() -> Result<()> {
     Ok(())
 }
 
+fn handle_version(config: Config, check: bool) -> Result<()> {
+    println!("prowl {} (database schema {})", update::CURRENT_VERSION, SCHEMA_VERSION);
+
+    // Don't create a database just to describe it
+    if Path::new(&config.capture.database).exists() {
+        let db = Database::open(&config.capture.database).context("Failed to open database")?;
+        let info = db.schema_info()?;
+        println!(
+            "Database {}: schema {}, created by prowl {}",
+            config.capture.database,
+            info.version,
+            info.created_by.as_deref().unwrap_or("unknown")
+        );
+    }
+
+    if check {
+        match update::check_for_update(&config.updates)? {
+            Some(latest) => println!("prowl {} is available", latest),
+            None => println!("Up to date"),
+        }
+    }
+    Ok(())
+}
+
 fn handle_db(config: Config, action: DbCommands) -> Result<()> {
     use rusqlite::Connection;
     use std::fs::File;
@@ -692,6 +2335,19 @@ fn handle_db(config: Config, action: DbCommands) -> Result<()> {
                 let sql: String = row.get(1)?;
                 println!("{};", sql);
             }
+
+            // Get all views
+            let mut stmt = conn
+                .prepare("SELECT name, sql FROM sqlite_master WHERE type='view' ORDER BY name")?;
+            let mut rows = stmt.query([])?;
+
+            while let Some(row) = rows.next()? {
+                let name: String = row.get(0)?;
+                let sql: String = row.get(1)?;
+                println!();
+                println!("-- View: {}", name);
+                println!("{};", sql);
+            }
         }
 
         DbCommands::Export { table, output } => {
@@ -731,6 +2387,7 @@ fn handle_db(config: Config, action: DbCommands) -> Result<()> {
 
         DbCommands::Import { source } => {
             let src_conn = Connection::open(&source).context("Failed to open source database")?;
+            check_schema_version(&src_conn, &source.display().to_string())?;
             let dst_conn =
                 Connection::open(db_path).context("Failed to open destination database")?;
 
@@ -805,6 +2462,65 @@ fn handle_db(config: Config, action: DbCommands) -> Result<()> {
             println!("  After:  {} bytes", size_after);
             println!("  Saved:  {} bytes", size_before - size_after);
         }
+
+        DbCommands::Reprocess => {
+            let db = Database::open(db_path)?;
+            let current = prowl::fingerprint::current().version();
+            for (version, count) in db.count_fingerprints_by_version()? {
+                let label = match version {
+                    Some(v) if v == current => format!("v{} (current)", v),
+                    Some(v) => format!("v{}", v),
+                    None => "none".to_string(),
+                };
+                println!("  Fingerprints {}: {}", label, count);
+            }
+            let updated = db.reprocess_fingerprints()?;
+            println!("Recomputed {} fingerprints with algorithm v{}", updated, current);
+        }
+
+        DbCommands::Purge { keep_hours } => {
+            let db = Database::open(db_path)?;
+            let now = chrono::Utc::now().timestamp();
+            let keep_hours = keep_hours.unwrap_or(config.retention.raw_hours);
+            let summary = retention::run_purge(&db, keep_hours, now)?;
+            println!("{}", retention::describe(&summary));
+            println!(
+                "Raw data from before {} is gone; hourly rollups remain",
+                format_timestamp(retention::purge_cutoff(now, keep_hours))
+            );
+        }
+
+        DbCommands::Sessions => {
+            let db = Database::open(db_path)?;
+            let sessions = db.get_sessions()?;
+            if sessions.is_empty() {
+                println!("No capture sessions recorded");
+                return Ok(());
+            }
+
+            let mut table = Table::new([
+                "ID", "Started", "Ended", "Interface", "Driver", "Adapter MAC", "Backend", "Channels", "Start point",
+                "Probes",
+            ]);
+            for session in &sessions {
+                table.add_row([
+                    session.id.to_string(),
+                    format_timestamp(session.started_at),
+                    session.ended_at.map(format_timestamp).unwrap_or_else(|| "-".to_string()),
+                    session.interface.clone(),
+                    session.driver.clone().unwrap_or_else(|| "unknown".to_string()),
+                    session.adapter_mac.clone().unwrap_or_else(|| "unknown".to_string()),
+                    session.backend.clone(),
+                    session.channels.clone().unwrap_or_else(|| "-".to_string()),
+                    match (session.start_lat, session.start_lon) {
+                        (Some(lat), Some(lon)) => format!("{:.5}, {:.5}", lat, lon),
+                        _ => "-".to_string(),
+                    },
+                    session.probes.to_string(),
+                ]);
+            }
+            table.print();
+        }
     }
 
     Ok(())
@@ -0,0 +1,156 @@
+//! Weekly refresh of what prowl learns about its surroundings.
+//!
+//! A sensor left running for months would otherwise judge today's traffic
+//! by whatever it learned when it was started. Alongside `prowl capture` a
+//! background thread wakes every Monday at the configured hour, reclassifies
+//! device residency (the environment baseline analysis suppresses residents
+//! against) and relearns the new-device rate that spike detection compares
+//! with. Each run is recorded in the events table.
+
+use crate::anomaly::{learn_baseline, AnomalyBaseline};
+use crate::config::{Config, ReportSchedule};
+use crate::database::Database;
+use crate::email::next_send_time;
+use crate::residency::{update_residency, Residency};
+use anyhow::Result;
+use chrono::Utc;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Event type recorded in the events table for each maintenance run
+pub const EVENT_MAINTENANCE: &str = "maintenance";
+
+const DAY_SECS: i64 = 86_400;
+
+/// What one maintenance run changed
+#[derive(Debug, Clone)]
+pub struct MaintenanceSummary {
+    /// Devices whose residency class changed
+    pub residency_changes: usize,
+    /// Of those, devices that became resident
+    pub new_residents: usize,
+    /// Newly learned new-device baseline; `None` when anomaly detection is
+    /// off or there was no history to learn from
+    pub baseline: Option<AnomalyBaseline>,
+}
+
+impl MaintenanceSummary {
+    pub fn describe(&self) -> String {
+        let baseline = match &self.baseline {
+            Some(b) => format!(
+                "new-device baseline {:.2} per {}s (stddev {:.2})",
+                b.mean,
+                b.bucket_secs,
+                b.variance.sqrt()
+            ),
+            None => "new-device baseline unchanged".to_string(),
+        };
+        format!(
+            "Weekly maintenance: {} residency change(s), {} new resident(s); {}",
+            self.residency_changes, self.new_residents, baseline
+        )
+    }
+}
+
+/// Refresh residency and the new-device baseline, and log the run as an event
+pub fn run_maintenance(db: &Database, config: &Config, now: i64) -> Result<MaintenanceSummary> {
+    let changes = update_residency(db, now)?;
+    let new_residents = changes.iter().filter(|c| c.to == Residency::Resident).count();
+
+    let baseline = if config.anomaly.enabled {
+        let start = now - config.maintenance.baseline_days.max(1) as i64 * DAY_SECS;
+        let first_seen = db.get_first_seen_times(start, now)?;
+        // An empty history would teach the detector that nothing ever arrives
+        match learn_baseline(&first_seen, start, now, &config.anomaly) {
+            Some(baseline) if !first_seen.is_empty() => {
+                db.set_anomaly_baseline(&baseline)?;
+                Some(baseline)
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    let summary = MaintenanceSummary {
+        residency_changes: changes.len(),
+        new_residents,
+        baseline,
+    };
+    let data = serde_json::json!({
+        "residency_changes": summary.residency_changes,
+        "new_residents": summary.new_residents,
+        "baseline_mean": summary.baseline.map(|b| b.mean),
+        "baseline_variance": summary.baseline.map(|b| b.variance),
+        "baseline_buckets": summary.baseline.map(|b| b.buckets),
+    });
+    db.insert_event(now, EVENT_MAINTENANCE, &summary.describe(), Some(&data.to_string()))?;
+    Ok(summary)
+}
+
+/// Run maintenance weekly until `running` clears. Returns `None` when
+/// maintenance is disabled.
+pub fn spawn_maintenance(config: &Config, running: Arc<AtomicBool>) -> Option<thread::JoinHandle<()>> {
+    if !config.maintenance.enabled {
+        return None;
+    }
+
+    let config = config.clone();
+    Some(thread::spawn(move || {
+        let db = match Database::open(&config.capture.database) {
+            Ok(db) => db,
+            Err(e) => {
+                error!("Maintenance could not open database: {}", e);
+                return;
+            }
+        };
+
+        while running.load(Ordering::SeqCst) {
+            let due = next_send_time(ReportSchedule::Weekly, config.maintenance.hour_utc, Utc::now());
+            info!("Next maintenance run at {}", due.format("%Y-%m-%d %H:%M UTC"));
+
+            while running.load(Ordering::SeqCst) && Utc::now() < due {
+                thread::sleep(Duration::from_secs(1));
+            }
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            match run_maintenance(&db, &config, Utc::now().timestamp()) {
+                Ok(summary) => info!("{}", summary.describe()),
+                Err(e) => error!("Maintenance failed: {}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ProbeCapture;
+
+    #[test]
+    fn test_run_maintenance_stores_baseline_and_event() {
+        let db = Database::open_in_memory().unwrap();
+        let config = Config::default_config();
+        let now = 30 * DAY_SECS;
+        for i in 0..20 {
+            let mac = format!("02:00:00:00:00:{:02X}", i);
+            db.insert_probe(&ProbeCapture::test(&mac, "", now - i * 3600)).unwrap();
+        }
+
+        let summary = run_maintenance(&db, &config, now).unwrap();
+        assert_eq!(summary.residency_changes, 20);
+        assert_eq!(summary.new_residents, 0);
+
+        let stored = db.get_anomaly_baseline().unwrap().unwrap();
+        assert_eq!(stored.updated_at, now);
+        assert_eq!(Some(stored), summary.baseline);
+
+        let events = db.get_events_since(now, Some(EVENT_MAINTENANCE)).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,365 @@
+//! Minimal nl80211 client over generic netlink.
+//!
+//! Covers only what capture needs: switching channel, changing an
+//! interface's type, reading the type back and bringing a link up or down.
+//! The channel hopper keeps one socket open, so a hop costs a single
+//! request/ack round trip instead of spawning `iw`, and failures carry the
+//! kernel's errno instead of scraped stderr.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+// <linux/netlink.h>
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_ACK: u16 = 0x04;
+const NLMSG_ERROR: u16 = 0x02;
+const NLMSG_DONE: u16 = 0x03;
+const NLMSG_HDRLEN: usize = 16;
+const NLA_HDRLEN: usize = 4;
+/// Strips NLA_F_NESTED and NLA_F_NET_BYTEORDER from an attribute type
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+// <linux/genetlink.h>
+const GENL_HDRLEN: usize = 4;
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+// <linux/nl80211.h>
+const NL80211_CMD_SET_WIPHY: u8 = 2;
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_SET_INTERFACE: u8 = 6;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_IFTYPE: u16 = 5;
+const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+const NL80211_ATTR_WIPHY_CHANNEL_TYPE: u16 = 39;
+const NL80211_CHAN_NO_HT: u32 = 0;
+pub const NL80211_IFTYPE_MONITOR: u32 = 6;
+
+const RECV_BUF_SIZE: usize = 16384;
+
+/// Center frequency of a 2.4 or 5 GHz channel number
+pub fn channel_frequency(channel: u8) -> Option<u32> {
+    match channel {
+        1..=13 => Some(2407 + 5 * channel as u32),
+        14 => Some(2484),
+        32..=177 => Some(5000 + 5 * channel as u32),
+        _ => None,
+    }
+}
+
+/// Kernel index of a network interface
+pub fn ifindex(interface: &str) -> Result<u32> {
+    let name = CString::new(interface).context("Invalid interface name")?;
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        return Err(io::Error::last_os_error()).with_context(|| format!("Interface {} not found", interface));
+    }
+    Ok(index)
+}
+
+/// Bring `interface` up or down, as `ip link set <interface> up|down` would
+pub fn set_link_up(interface: &str, up: bool) -> Result<()> {
+    if interface.len() >= libc::IFNAMSIZ {
+        bail!("Interface name {} is too long", interface);
+    }
+    let raw = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if raw < 0 {
+        return Err(io::Error::last_os_error()).context("Failed to open control socket");
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+    let mut req: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in req.ifr_name.iter_mut().zip(interface.bytes()) {
+        *dst = src as libc::c_char;
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCGIFFLAGS as _, &mut req) } < 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to read flags of {}", interface));
+    }
+    unsafe {
+        if up {
+            req.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+        } else {
+            req.ifr_ifru.ifru_flags &= !(libc::IFF_UP as libc::c_short);
+        }
+    }
+    if unsafe { libc::ioctl(fd.as_raw_fd(), libc::SIOCSIFFLAGS as _, &req) } < 0 {
+        let state = if up { "up" } else { "down" };
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to bring {} {}", interface, state));
+    }
+    Ok(())
+}
+
+/// A generic netlink request under construction
+struct Request {
+    buf: Vec<u8>,
+}
+
+impl Request {
+    fn new(family: u16, seq: u32, cmd: u8) -> Self {
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&0u32.to_ne_bytes()); // length, filled in by finish()
+        buf.extend_from_slice(&family.to_ne_bytes());
+        buf.extend_from_slice(&(NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+        buf.extend_from_slice(&seq.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes()); // port id: the kernel fills it in
+        buf.extend_from_slice(&[cmd, 1, 0, 0]); // genlmsghdr: cmd, version, reserved
+        Request { buf }
+    }
+
+    fn attr(mut self, kind: u16, payload: &[u8]) -> Self {
+        self.buf.extend_from_slice(&((NLA_HDRLEN + payload.len()) as u16).to_ne_bytes());
+        self.buf.extend_from_slice(&kind.to_ne_bytes());
+        self.buf.extend_from_slice(payload);
+        self.buf.resize(align(self.buf.len()), 0);
+        self
+    }
+
+    fn attr_u32(self, kind: u16, value: u32) -> Self {
+        self.attr(kind, &value.to_ne_bytes())
+    }
+
+    fn attr_str(self, kind: u16, value: &str) -> Self {
+        let mut payload = value.as_bytes().to_vec();
+        payload.push(0);
+        self.attr(kind, &payload)
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let len = self.buf.len() as u32;
+        self.buf[..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf
+    }
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Top-level attributes of a generic netlink payload (after the genl header)
+fn parse_attrs(payload: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attrs = Vec::new();
+    let mut at = 0;
+    while let (Some(len), Some(kind)) = (read_u16(payload, at), read_u16(payload, at + 2)) {
+        let len = len as usize;
+        if len < NLA_HDRLEN || at + len > payload.len() {
+            break;
+        }
+        attrs.push((kind & NLA_TYPE_MASK, &payload[at + NLA_HDRLEN..at + len]));
+        at += align(len);
+    }
+    attrs
+}
+
+/// Generic netlink socket talking to the nl80211 family
+pub struct Nl80211 {
+    fd: OwnedFd,
+    family: u16,
+    seq: u32,
+}
+
+impl Nl80211 {
+    /// Open a socket and look up the nl80211 family id
+    pub fn connect() -> Result<Self> {
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_GENERIC,
+            )
+        };
+        if raw < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to open netlink socket");
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to bind netlink socket");
+        }
+
+        // Never hang the hopper on a reply that doesn't come
+        let timeout = libc::timeval { tv_sec: 1, tv_usec: 0 };
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error()).context("Failed to set netlink read timeout");
+        }
+
+        let mut nl = Nl80211 { fd, family: GENL_ID_CTRL, seq: 0 };
+        let request = nl.request(GENL_ID_CTRL, CTRL_CMD_GETFAMILY).attr_str(CTRL_ATTR_FAMILY_NAME, "nl80211");
+        let replies = nl.send(request).context("nl80211 is not available (is cfg80211 loaded?)")?;
+        nl.family = replies
+            .iter()
+            .flat_map(|payload| parse_attrs(payload))
+            .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+            .and_then(|(_, value)| read_u16(value, 0))
+            .context("Kernel did not report the nl80211 family id")?;
+        Ok(nl)
+    }
+
+    /// Tune the radio behind `ifindex` to `channel` (20 MHz, no HT)
+    pub fn set_channel(&mut self, ifindex: u32, channel: u8) -> Result<()> {
+        let freq = channel_frequency(channel).with_context(|| format!("Unknown channel {}", channel))?;
+        let request = self
+            .request(self.family, NL80211_CMD_SET_WIPHY)
+            .attr_u32(NL80211_ATTR_IFINDEX, ifindex)
+            .attr_u32(NL80211_ATTR_WIPHY_FREQ, freq)
+            .attr_u32(NL80211_ATTR_WIPHY_CHANNEL_TYPE, NL80211_CHAN_NO_HT);
+        self.send(request)
+            .with_context(|| format!("Failed to set channel {} ({} MHz)", channel, freq))?;
+        Ok(())
+    }
+
+    /// Change the interface type; most drivers need the link down first
+    pub fn set_iftype(&mut self, ifindex: u32, iftype: u32) -> Result<()> {
+        let request = self
+            .request(self.family, NL80211_CMD_SET_INTERFACE)
+            .attr_u32(NL80211_ATTR_IFINDEX, ifindex)
+            .attr_u32(NL80211_ATTR_IFTYPE, iftype);
+        self.send(request).context("Failed to change interface type")?;
+        Ok(())
+    }
+
+    /// Current interface type (one of the `NL80211_IFTYPE_*` values)
+    pub fn iftype(&mut self, ifindex: u32) -> Result<u32> {
+        let request = self
+            .request(self.family, NL80211_CMD_GET_INTERFACE)
+            .attr_u32(NL80211_ATTR_IFINDEX, ifindex);
+        let replies = self.send(request).context("Failed to read interface type")?;
+        replies
+            .iter()
+            .flat_map(|payload| parse_attrs(payload))
+            .find(|(kind, _)| *kind == NL80211_ATTR_IFTYPE)
+            .and_then(|(_, value)| read_u32(value, 0))
+            .context("Kernel did not report the interface type")
+    }
+
+    fn request(&mut self, family: u16, cmd: u8) -> Request {
+        self.seq = self.seq.wrapping_add(1);
+        Request::new(family, self.seq, cmd)
+    }
+
+    /// Send a request and collect the attribute payloads of its replies
+    /// until the kernel acks it
+    fn send(&mut self, request: Request) -> Result<Vec<Vec<u8>>> {
+        let message = request.finish();
+        let sent = unsafe {
+            libc::send(
+                self.fd.as_raw_fd(),
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let mut replies = Vec::new();
+        let mut buf = vec![0u8; RECV_BUF_SIZE];
+        loop {
+            let n = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if n < 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+
+            let data = &buf[..n as usize];
+            let mut at = 0;
+            while let (Some(len), Some(kind), Some(seq)) =
+                (read_u32(data, at), read_u16(data, at + 4), read_u32(data, at + 8))
+            {
+                let len = len as usize;
+                if len < NLMSG_HDRLEN || at + len > data.len() {
+                    break;
+                }
+                let body = &data[at + NLMSG_HDRLEN..at + len];
+                at += align(len);
+                if seq != self.seq {
+                    continue;
+                }
+                match kind {
+                    NLMSG_ERROR => {
+                        let errno = read_u32(body, 0).map(|e| e as i32).unwrap_or(0);
+                        if errno == 0 {
+                            return Ok(replies);
+                        }
+                        return Err(io::Error::from_raw_os_error(-errno).into());
+                    }
+                    NLMSG_DONE => return Ok(replies),
+                    _ => replies.push(body.get(GENL_HDRLEN..).unwrap_or_default().to_vec()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_frequency() {
+        assert_eq!(channel_frequency(1), Some(2412));
+        assert_eq!(channel_frequency(6), Some(2437));
+        assert_eq!(channel_frequency(14), Some(2484));
+        assert_eq!(channel_frequency(36), Some(5180));
+        assert_eq!(channel_frequency(165), Some(5825));
+        assert_eq!(channel_frequency(0), None);
+    }
+
+    #[test]
+    fn test_request_encoding_round_trips() {
+        let message = Request::new(0x1c, 7, NL80211_CMD_SET_WIPHY)
+            .attr_u32(NL80211_ATTR_IFINDEX, 3)
+            .attr_str(CTRL_ATTR_FAMILY_NAME, "nl80211")
+            .finish();
+        // 16 header + 4 genl + 8 u32 attr + 12 string attr ("nl80211\0" padded)
+        assert_eq!(message.len(), 40);
+        assert_eq!(read_u32(&message, 0), Some(40));
+        assert_eq!(read_u16(&message, 4), Some(0x1c));
+        assert_eq!(read_u32(&message, 8), Some(7));
+        assert_eq!(message[16], NL80211_CMD_SET_WIPHY);
+
+        let attrs = parse_attrs(&message[NLMSG_HDRLEN + GENL_HDRLEN..]);
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].0, NL80211_ATTR_IFINDEX);
+        assert_eq!(read_u32(attrs[0].1, 0), Some(3));
+        assert_eq!(attrs[1].1, b"nl80211\0");
+    }
+}
@@ -0,0 +1,242 @@
+//! Radio control on Windows through Npcap.
+//!
+//! Npcap captures raw 802.11 when it was installed with "Support raw 802.11
+//! traffic", and ships `WlanHelper.exe` to switch an adapter between managed
+//! and monitor mode and to tune its channel. Wireless adapters are listed
+//! with `netsh wlan show interfaces` in place of `iw dev`. This module has
+//! the same shape as the nl80211 client so the channel hopper and monitor
+//! mode setup work unchanged; a channel hop is one WlanHelper run.
+//!
+//! Interfaces can be named by their friendly name ("Wi-Fi"), GUID, or Npcap
+//! device path (`\Device\NPF_{GUID}`). netsh labels its fields in the
+//! system language, so adapters are only found on an English Windows unless
+//! given by GUID or device path.
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+/// Stand-in for nl80211's interface type, only compared against
+pub const NL80211_IFTYPE_MONITOR: u32 = 6;
+const IFTYPE_MANAGED: u32 = 2;
+
+/// A wireless adapter as netsh reports it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WlanInterface {
+    /// Friendly name, e.g. "Wi-Fi"
+    pub name: String,
+    pub description: String,
+    /// Lowercase, without braces
+    pub guid: String,
+}
+
+impl WlanInterface {
+    /// Device name Npcap captures on
+    pub fn device(&self) -> String {
+        format!("\\Device\\NPF_{{{}}}", self.guid.to_uppercase())
+    }
+
+    fn matches(&self, interface: &str) -> bool {
+        let wanted = normalize_guid(interface);
+        self.name.eq_ignore_ascii_case(interface.trim()) || self.guid == wanted
+    }
+}
+
+/// GUID from a bare GUID, `{GUID}` or `\Device\NPF_{GUID}`
+fn normalize_guid(interface: &str) -> String {
+    let interface = interface.trim();
+    let guid = interface.rsplit('_').next().unwrap_or(interface);
+    guid.trim_matches(|c| c == '{' || c == '}').to_lowercase()
+}
+
+/// Parse `netsh wlan show interfaces`
+pub fn parse_netsh_interfaces(output: &str) -> Vec<WlanInterface> {
+    let mut interfaces = Vec::new();
+    let mut current: Option<WlanInterface> = None;
+
+    for line in output.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        match key {
+            "Name" => {
+                if let Some(done) = current.take().filter(|i| !i.guid.is_empty()) {
+                    interfaces.push(done);
+                }
+                current = Some(WlanInterface {
+                    name: value.to_string(),
+                    description: String::new(),
+                    guid: String::new(),
+                });
+            }
+            "Description" => {
+                if let Some(iface) = current.as_mut() {
+                    iface.description = value.to_string();
+                }
+            }
+            "GUID" => {
+                if let Some(iface) = current.as_mut() {
+                    iface.guid = normalize_guid(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(done) = current.filter(|i| !i.guid.is_empty()) {
+        interfaces.push(done);
+    }
+    interfaces
+}
+
+/// Wireless adapters on this machine
+pub fn wlan_interfaces() -> Result<Vec<WlanInterface>> {
+    let output = Command::new("netsh")
+        .args(["wlan", "show", "interfaces"])
+        .output()
+        .context("Failed to list wireless adapters with netsh")?;
+    Ok(parse_netsh_interfaces(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The adapter `interface` names
+pub fn find_interface(interface: &str) -> Result<WlanInterface> {
+    wlan_interfaces()?
+        .into_iter()
+        .find(|i| i.matches(interface))
+        .ok_or_else(|| anyhow!("No wireless adapter named {}", interface))
+}
+
+/// Npcap device for `interface`, or the name unchanged if it isn't a known
+/// wireless adapter (e.g. already a device path netsh can't resolve)
+pub fn pcap_device(interface: &str) -> String {
+    match find_interface(interface) {
+        Ok(iface) => iface.device(),
+        Err(_) => interface.to_string(),
+    }
+}
+
+fn wlan_helper_path() -> PathBuf {
+    let root = std::env::var_os("SystemRoot").unwrap_or_else(|| "C:\\Windows".into());
+    PathBuf::from(root).join("System32").join("Npcap").join("WlanHelper.exe")
+}
+
+/// Run WlanHelper for the adapter with `guid` and return its output
+fn wlan_helper(guid: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(wlan_helper_path())
+        .arg(guid)
+        .args(args)
+        .output()
+        .context("Failed to run Npcap's WlanHelper.exe; is Npcap installed with raw 802.11 support?")?;
+    if !output.status.success() {
+        bail!(
+            "WlanHelper {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Current mode of the adapter with `guid` ("managed", "monitor", ...)
+pub fn mode(guid: &str) -> Result<String> {
+    Ok(wlan_helper(guid, &["mode"])?.to_lowercase())
+}
+
+/// Wireless adapters with their modes, as `iw dev` lists them on Linux
+pub fn list_wireless_interfaces() -> Result<Vec<(String, String)>> {
+    Ok(wlan_interfaces()?
+        .into_iter()
+        .map(|iface| {
+            let mode = mode(&iface.guid).unwrap_or_else(|_| "unknown".to_string());
+            (iface.name, mode)
+        })
+        .collect())
+}
+
+/// GUIDs handed out as interface indexes, so an index stays valid for the
+/// life of the process
+static HANDLES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+pub fn ifindex(interface: &str) -> Result<u32> {
+    let guid = find_interface(interface)?.guid;
+    let mut handles = HANDLES.lock().map_err(|_| anyhow!("Interface table poisoned"))?;
+    let index = match handles.iter().position(|g| *g == guid) {
+        Some(index) => index,
+        None => {
+            handles.push(guid);
+            handles.len() - 1
+        }
+    };
+    Ok(index as u32)
+}
+
+fn guid_for(ifindex: u32) -> Result<String> {
+    HANDLES
+        .lock()
+        .map_err(|_| anyhow!("Interface table poisoned"))?
+        .get(ifindex as usize)
+        .cloned()
+        .ok_or_else(|| anyhow!("Unknown interface index {}", ifindex))
+}
+
+/// WlanHelper changes the mode of a running adapter, so there is no link to
+/// take down first
+pub fn set_link_up(_interface: &str, _up: bool) -> Result<()> {
+    Ok(())
+}
+
+/// Radio control through WlanHelper
+pub struct Nl80211;
+
+impl Nl80211 {
+    pub fn connect() -> Result<Self> {
+        Ok(Nl80211)
+    }
+
+    pub fn set_channel(&mut self, ifindex: u32, channel: u8) -> Result<()> {
+        wlan_helper(&guid_for(ifindex)?, &["channel", &channel.to_string()])?;
+        Ok(())
+    }
+
+    pub fn set_iftype(&mut self, ifindex: u32, iftype: u32) -> Result<()> {
+        let mode = if iftype == NL80211_IFTYPE_MONITOR { "monitor" } else { "managed" };
+        wlan_helper(&guid_for(ifindex)?, &["mode", mode])?;
+        Ok(())
+    }
+
+    pub fn iftype(&mut self, ifindex: u32) -> Result<u32> {
+        Ok(match mode(&guid_for(ifindex)?)?.as_str() {
+            "monitor" => NL80211_IFTYPE_MONITOR,
+            _ => IFTYPE_MANAGED,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netsh_interfaces() {
+        let output = "\r
+There is 1 interface on the system:\r
+\r
+    Name                   : Wi-Fi\r
+    Description            : Intel(R) Wi-Fi 6 AX201 160MHz\r
+    GUID                   : 5c1a3f0e-8d2b-4e7a-9f11-2b3c4d5e6f70\r
+    Physical address       : 3c:58:c2:11:22:33\r
+    State                  : disconnected\r
+";
+        let interfaces = parse_netsh_interfaces(output);
+        assert_eq!(interfaces.len(), 1);
+        assert_eq!(interfaces[0].name, "Wi-Fi");
+        assert_eq!(interfaces[0].description, "Intel(R) Wi-Fi 6 AX201 160MHz");
+        assert_eq!(interfaces[0].device(), "\\Device\\NPF_{5C1A3F0E-8D2B-4E7A-9F11-2B3C4D5E6F70}");
+
+        assert!(interfaces[0].matches("wi-fi"));
+        assert!(interfaces[0].matches("\\Device\\NPF_{5C1A3F0E-8D2B-4E7A-9F11-2B3C4D5E6F70}"));
+        assert!(!interfaces[0].matches("Ethernet"));
+    }
+}
@@ -0,0 +1,174 @@
+//! Occupancy estimation for stationary sensors.
+//!
+//! Estimates how many distinct people are near a fixed sensor by counting
+//! devices per time bucket and converting device counts to people with a
+//! devices-per-person ratio. Randomized MACs are grouped by a coarse
+//! fingerprint (probed SSIDs + WiFi generation) so a phone rotating its MAC
+//! within a bucket is not counted several times. A MAC that only sent
+//! broadcast probes has no SSIDs to group by, so each one counts as a device
+//! of its own rather than all of them folding into one per generation.
+
+use crate::database::{Database, ProbeObservation};
+use crate::oui::is_randomized_mac;
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Occupancy estimate for a single time bucket
+#[derive(Debug, Clone, Default)]
+pub struct OccupancyEstimate {
+    /// Distinct globally-administered MACs
+    pub real_devices: usize,
+    /// Randomized MACs after fingerprint grouping
+    pub randomized_devices: usize,
+    /// Raw randomized MAC count before grouping
+    pub randomized_macs: usize,
+    /// Estimated number of people
+    pub people: f64,
+}
+
+impl OccupancyEstimate {
+    pub fn devices(&self) -> usize {
+        self.real_devices + self.randomized_devices
+    }
+}
+
+/// One point in an occupancy time series
+#[derive(Debug, Clone)]
+pub struct OccupancySample {
+    pub bucket_start: i64,
+    pub estimate: OccupancyEstimate,
+}
+
+/// Estimate occupancy from the probe observations of a single time window
+pub fn estimate_occupancy(observations: &[ProbeObservation], devices_per_person: f64) -> OccupancyEstimate {
+    let mut real: HashSet<&str> = HashSet::new();
+    let mut randomized: HashMap<&str, (BTreeSet<&str>, Option<&str>)> = HashMap::new();
+
+    for obs in observations {
+        if is_randomized_mac(&obs.mac) {
+            let entry = randomized.entry(obs.mac.as_str()).or_default();
+            if !obs.ssid.is_empty() {
+                entry.0.insert(obs.ssid.as_str());
+            }
+            if entry.1.is_none() {
+                entry.1 = obs.wifi_generation.as_deref();
+            }
+        } else {
+            real.insert(obs.mac.as_str());
+        }
+    }
+
+    // Randomized MACs with an identical SSID set and WiFi generation are
+    // assumed to be the same device rotating its address
+    let mut fingerprints: HashSet<(Vec<&str>, Option<&str>)> = HashSet::new();
+    let mut broadcast_only = 0;
+    for (ssids, generation) in randomized.values() {
+        if ssids.is_empty() {
+            broadcast_only += 1;
+        } else {
+            fingerprints.insert((ssids.iter().copied().collect(), *generation));
+        }
+    }
+
+    let mut estimate = OccupancyEstimate {
+        real_devices: real.len(),
+        randomized_devices: fingerprints.len() + broadcast_only,
+        randomized_macs: randomized.len(),
+        people: 0.0,
+    };
+
+    let ratio = if devices_per_person > 0.0 { devices_per_person } else { 1.0 };
+    estimate.people = estimate.devices() as f64 / ratio;
+    estimate
+}
+
+/// Build an occupancy time series between `start` and `end` in fixed buckets
+pub fn occupancy_time_series(
+    db: &Database,
+    start: i64,
+    end: i64,
+    bucket_secs: i64,
+    devices_per_person: f64,
+) -> Result<Vec<OccupancySample>> {
+    let bucket_secs = bucket_secs.max(60);
+    let observations = db.get_probe_observations(start, end)?;
+
+    let mut buckets: HashMap<i64, Vec<ProbeObservation>> = HashMap::new();
+    for obs in observations {
+        let bucket = start + ((obs.timestamp - start) / bucket_secs) * bucket_secs;
+        buckets.entry(bucket).or_default().push(obs);
+    }
+
+    let mut samples = Vec::new();
+    let mut bucket_start = start;
+    while bucket_start <= end {
+        let estimate = buckets
+            .get(&bucket_start)
+            .map(|obs| estimate_occupancy(obs, devices_per_person))
+            .unwrap_or_default();
+        samples.push(OccupancySample {
+            bucket_start,
+            estimate,
+        });
+        bucket_start += bucket_secs;
+    }
+
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obs(mac: &str, ssid: &str, generation: Option<&str>) -> ProbeObservation {
+        ProbeObservation {
+            mac: mac.to_string(),
+            timestamp: 0,
+            ssid: ssid.to_string(),
+            wifi_generation: generation.map(|g| g.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_randomized_macs_grouped_by_fingerprint() {
+        let observations = vec![
+            obs("00:03:93:00:00:01", "", None),
+            obs("00:03:93:00:00:01", "Home", None),
+            obs("02:00:00:00:00:01", "Cafe", Some("802.11ax (WiFi 6)")),
+            obs("06:00:00:00:00:02", "Cafe", Some("802.11ax (WiFi 6)")),
+            obs("0A:00:00:00:00:03", "Office", Some("802.11ac (WiFi 5)")),
+        ];
+
+        let est = estimate_occupancy(&observations, 1.0);
+        assert_eq!(est.real_devices, 1);
+        assert_eq!(est.randomized_macs, 3);
+        assert_eq!(est.randomized_devices, 2);
+        assert!((est.people - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_broadcast_only_macs_counted_individually() {
+        let observations = vec![
+            obs("02:00:00:00:00:01", "", Some("802.11ax (WiFi 6)")),
+            obs("06:00:00:00:00:02", "", Some("802.11ax (WiFi 6)")),
+            obs("0A:00:00:00:00:03", "", Some("802.11ax (WiFi 6)")),
+            obs("0E:00:00:00:00:04", "", None),
+            obs("12:00:00:00:00:05", "", None),
+        ];
+
+        let est = estimate_occupancy(&observations, 1.0);
+        assert_eq!(est.randomized_macs, 5);
+        assert_eq!(est.randomized_devices, 5);
+    }
+
+    #[test]
+    fn test_devices_per_person_ratio() {
+        let observations = vec![
+            obs("00:03:93:00:00:01", "", None),
+            obs("00:03:93:00:00:02", "", None),
+            obs("00:03:93:00:00:03", "", None),
+        ];
+        let est = estimate_occupancy(&observations, 1.5);
+        assert!((est.people - 2.0).abs() < 0.001);
+    }
+}
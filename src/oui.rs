@@ -2,8 +2,38 @@
 //! and MAC randomization detection.
 
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Version of the bundled OUI table. Bump whenever entries are added,
+/// removed or reassigned so stored attributions can be traced back.
+pub const OUI_DB_VERSION: &str = "2024.1";
+
+/// Where the bundled OUI table entries originate
+pub const OUI_DB_SOURCE: &str = "bundled IEEE MA-L subset";
+
+/// Vendor attribution together with the data it was derived from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorAttribution {
+    pub oui: String,
+    pub vendor: Option<String>,
+    pub source: String,
+    pub db_version: String,
+}
+
+impl VendorAttribution {
+    /// Format as "Apple (OUI 00:03:93, bundled IEEE MA-L subset v2024.1)"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (OUI {}, {} v{})",
+            self.vendor.as_deref().unwrap_or("Unknown"),
+            self.oui,
+            self.source,
+            self.db_version
+        )
+    }
+}
+
 /// Common OUI prefixes mapped to vendor names
 /// This is a subset of the IEEE OUI database for common device manufacturers
 static OUI_DATABASE: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
@@ -378,21 +408,37 @@ pub fn is_randomized_mac(mac: &str) -> bool {
     }
 }
 
-/// Look up the vendor/manufacturer for a MAC address
-pub fn lookup_vendor(mac: &str) -> Option<&'static str> {
-    // Normalize MAC format to XX:XX:XX
+/// Normalize the first three octets of a MAC address to XX:XX:XX
+fn oui_prefix(mac: &str) -> Option<String> {
     let mac_upper = mac.to_uppercase();
-    let parts: Vec<&str> = mac_upper.split(['.', '-', '.']).collect();
-    // let parts: Vec<&str> = mac_upper.split(|c| c == ':' || c == '-' || c == '.').collect();
+    let parts: Vec<&str> = mac_upper.split([':', '-', '.']).collect();
 
     if parts.len() < 3 {
         return None;
     }
 
-    let oui = format!("{}:{}:{}", parts[0], parts[1], parts[2]);
+    Some(format!("{}:{}:{}", parts[0], parts[1], parts[2]))
+}
+
+/// Look up the vendor/manufacturer for a MAC address
+pub fn lookup_vendor(mac: &str) -> Option<&'static str> {
+    let oui = oui_prefix(mac)?;
     OUI_DATABASE.get(oui.as_str()).copied()
 }
 
+/// Look up the vendor and record which OUI table version produced the answer
+pub fn attribute_vendor(mac: &str) -> Option<VendorAttribution> {
+    let oui = oui_prefix(mac)?;
+    let vendor = OUI_DATABASE.get(oui.as_str()).map(|v| v.to_string());
+
+    Some(VendorAttribution {
+        oui,
+        vendor,
+        source: OUI_DB_SOURCE.to_string(),
+        db_version: OUI_DB_VERSION.to_string(),
+    })
+}
+
 /// Get a short vendor code (3-4 chars) for display
 pub fn vendor_short(mac: &str) -> String {
     if is_randomized_mac(mac) {
@@ -464,4 +510,16 @@ mod tests {
         assert_eq!(lookup_vendor("5C:CF:7F:00:00:00"), Some("Espressif"));
         assert_eq!(lookup_vendor("FF:FF:FF:00:00:00"), None);
     }
+
+    #[test]
+    fn test_vendor_attribution() {
+        let attr = attribute_vendor("00-03-93-00-00-00").unwrap();
+        assert_eq!(attr.oui, "00:03:93");
+        assert_eq!(attr.vendor.as_deref(), Some("Apple"));
+        assert_eq!(attr.db_version, OUI_DB_VERSION);
+
+        let unknown = attribute_vendor("FF:FF:FF:00:00:00").unwrap();
+        assert_eq!(unknown.vendor, None);
+        assert_eq!(unknown.source, OUI_DB_SOURCE);
+    }
 }
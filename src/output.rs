@@ -0,0 +1,219 @@
+//! Shared terminal output for the CLI commands.
+//!
+//! Tables are laid out with comfy-table so long MACs and wide SSIDs stay
+//! aligned. Color is applied through a single severity scale and is disabled
+//! by `--no-color`, the `NO_COLOR` environment variable, or a non-terminal
+//! stdout. `--plain` switches tables to tab-separated rows for scripting.
+
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{Attribute, Cell as TableCell, Color, ContentArrangement, Table as ComfyTable};
+use once_cell::sync::OnceCell;
+use std::io::IsTerminal;
+
+/// Global output settings, fixed once at startup
+#[derive(Debug, Clone, Copy)]
+pub struct OutputStyle {
+    pub color: bool,
+    /// Tab-separated output without borders or color
+    pub machine: bool,
+}
+
+impl Default for OutputStyle {
+    fn default() -> Self {
+        OutputStyle {
+            color: true,
+            machine: false,
+        }
+    }
+}
+
+static STYLE: OnceCell<OutputStyle> = OnceCell::new();
+
+/// Configure output from the CLI flags. Later calls are ignored.
+pub fn init(no_color: bool, machine: bool) {
+    let color = !no_color
+        && !machine
+        && std::env::var_os("NO_COLOR").is_none()
+        && std::io::stdout().is_terminal();
+    let _ = STYLE.set(OutputStyle { color, machine });
+}
+
+pub fn style() -> OutputStyle {
+    STYLE.get().copied().unwrap_or_default()
+}
+
+/// Severity scale shared by every command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Info,
+    Warning,
+    Alert,
+}
+
+impl Severity {
+    /// Map a persistence score (0.0 - 1.0) onto the severity scale
+    pub fn from_score(score: f64, threshold: f64) -> Self {
+        if score >= 0.9 {
+            Severity::Alert
+        } else if score >= threshold {
+            Severity::Warning
+        } else {
+            Severity::Info
+        }
+    }
+
+    fn ansi(self) -> &'static str {
+        match self {
+            Severity::Ok => "32",
+            Severity::Info => "36",
+            Severity::Warning => "33",
+            Severity::Alert => "1;31",
+        }
+    }
+
+    fn table_color(self) -> Color {
+        match self {
+            Severity::Ok => Color::Green,
+            Severity::Info => Color::Cyan,
+            Severity::Warning => Color::Yellow,
+            Severity::Alert => Color::Red,
+        }
+    }
+}
+
+/// Wrap `text` in the color for `severity` when color is enabled
+pub fn paint(text: &str, severity: Severity) -> String {
+    if style().color {
+        format!("\x1b[{}m{}\x1b[0m", severity.ansi(), text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Print a section heading
+pub fn heading(title: &str) {
+    if style().machine {
+        return;
+    }
+    if style().color {
+        println!("\x1b[1m{}\x1b[0m", title);
+    } else {
+        println!("{}", title);
+        println!("{}", "-".repeat(title.chars().count()));
+    }
+}
+
+/// A table cell with an optional severity color
+#[derive(Debug, Clone)]
+pub struct Cell {
+    text: String,
+    severity: Option<Severity>,
+}
+
+impl Cell {
+    pub fn new(text: impl Into<String>) -> Self {
+        Cell {
+            text: text.into(),
+            severity: None,
+        }
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::new(text)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::new(text)
+    }
+}
+
+/// Column-aligned table rendered according to the global output style
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Table {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn add_row<C: Into<Cell>>(&mut self, cells: impl IntoIterator<Item = C>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn render(&self) -> String {
+        let style = style();
+
+        if style.machine {
+            let mut lines = vec![self.headers.join("\t")];
+            for row in &self.rows {
+                let fields: Vec<String> = row
+                    .iter()
+                    .map(|c| c.text.replace(['\t', '\n'], " "))
+                    .collect();
+                lines.push(fields.join("\t"));
+            }
+            return lines.join("\n");
+        }
+
+        let mut table = ComfyTable::new();
+        table
+            .load_preset(UTF8_FULL_CONDENSED)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        if !style.color {
+            table.force_no_tty();
+        }
+
+        table.set_header(
+            self.headers
+                .iter()
+                .map(|h| TableCell::new(h).add_attribute(Attribute::Bold)),
+        );
+
+        for row in &self.rows {
+            table.add_row(row.iter().map(|c| {
+                let cell = TableCell::new(&c.text);
+                match c.severity {
+                    Some(sev) if style.color => cell.fg(sev.table_color()),
+                    _ => cell,
+                }
+            }));
+        }
+
+        table.to_string()
+    }
+
+    pub fn print(&self) {
+        println!("{}", self.render());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_from_score() {
+        assert_eq!(Severity::from_score(0.95, 0.7), Severity::Alert);
+        assert_eq!(Severity::from_score(0.75, 0.7), Severity::Warning);
+        assert_eq!(Severity::from_score(0.5, 0.7), Severity::Info);
+    }
+}
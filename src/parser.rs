@@ -12,9 +12,40 @@ pub struct ParsedProbeRequest {
     pub source_mac: String,
     pub ssid: String,
     pub signal_dbm: Option<i32>,
+    /// AP targeted by a directed probe; None for broadcast probes
+    pub bssid: Option<String>,
+    /// 12-bit 802.11 sequence number, shared by every copy of one transmission
+    pub sequence_number: u16,
+    /// 4-bit fragment number; probes fit in one frame, so this is 0 from
+    /// well-behaved devices
+    pub fragment_number: u8,
+    /// Retry bit: this frame is a retransmission of an earlier copy
+    pub retry: bool,
     pub capabilities: ProbeCapabilities,
 }
 
+impl ParsedProbeRequest {
+    /// Channel the probe was received on, or else the one its DS Parameter
+    /// Set says it was sent on
+    pub fn channel(&self, received: Option<u8>) -> Option<u8> {
+        received.or(self.capabilities.ds_channel)
+    }
+}
+
+/// An access point announcing itself in a beacon or probe response
+#[derive(Debug, Clone)]
+pub struct ParsedBeacon {
+    pub bssid: String,
+    /// Empty when the network is hidden
+    pub ssid: String,
+    pub hidden: bool,
+    pub channel: Option<u8>,
+    /// Sent in answer to a probe rather than broadcast
+    pub probe_response: bool,
+    /// Station a probe response was addressed to; None for beacons
+    pub destination: Option<String>,
+}
+
 /// Extracted capabilities from 802.11 probe request
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProbeCapabilities {
@@ -31,8 +62,52 @@ pub struct ProbeCapabilities {
     pub wpa_info: Option<WpaSummary>,
     pub wps_info: Option<WpsSummary>,
     pub vendor_ies: Vec<VendorIeSummary>,
+    /// Channel the sender says it is on, from the DS Parameter Set element
     pub ds_channel: Option<u8>,
+    /// IDs of the elements libwifi didn't decode itself, for debugging
     pub raw_ie_ids: Vec<u8>,
+    #[serde(default)]
+    pub he_caps: Option<HeCapsSummary>,
+    /// Extended Capabilities bitfield, as sent
+    #[serde(default)]
+    pub extended_capabilities: Vec<u8>,
+    #[serde(default)]
+    pub ext_caps: Option<ExtCapsSummary>,
+    /// Every information element in the order the device sent them
+    #[serde(default)]
+    pub information_elements: Vec<InformationElement>,
+    /// Supported and extended rates as a bitfield (see `rate_bits`)
+    #[serde(default)]
+    pub rate_bits: u32,
+}
+
+/// One tagged parameter from the frame body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InformationElement {
+    pub id: u8,
+    /// Element ID Extension, for elements under ID 255
+    pub ext_id: Option<u8>,
+    /// Payload, after the extension ID when there is one
+    pub data: Vec<u8>,
+}
+
+impl InformationElement {
+    pub fn name(&self) -> &'static str {
+        match (self.id, self.ext_id) {
+            (IE_SSID, _) => "SSID",
+            (IE_SUPPORTED_RATES, _) => "Supported Rates",
+            (IE_DS_PARAMETER_SET, _) => "DS Parameter Set",
+            (IE_HT_CAPABILITIES, _) => "HT Capabilities",
+            (IE_RSN, _) => "RSN",
+            (IE_EXTENDED_RATES, _) => "Extended Rates",
+            (IE_EXTENDED_CAPABILITIES, _) => "Extended Capabilities",
+            (IE_VHT_CAPABILITIES, _) => "VHT Capabilities",
+            (IE_VENDOR_SPECIFIC, _) => "Vendor Specific",
+            (IE_EXTENSION, Some(EXT_HE_CAPABILITIES)) => "HE Capabilities",
+            (IE_EXTENSION, _) => "Extension",
+            _ => "Other",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -54,6 +129,61 @@ pub struct VhtCapsSummary {
     pub mu_beamformer: bool,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HeCapsSummary {
+    /// Channel Width Set field of the HE PHY capabilities
+    pub channel_width_set: u8,
+    pub su_beamformer: bool,
+    pub mu_beamformer: bool,
+    pub twt_requester: bool,
+}
+
+/// Extended Capabilities bits that say something about the client
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtCapsSummary {
+    pub bss_coexistence: bool,
+    pub extended_channel_switching: bool,
+    /// 802.11v BSS transition management
+    pub bss_transition: bool,
+    pub timing_measurement: bool,
+    /// 802.11u, which Hotspot 2.0 builds on
+    pub interworking: bool,
+    pub qos_map: bool,
+    pub tdls: bool,
+    pub utf8_ssid: bool,
+    pub operating_mode_notification: bool,
+    pub ftm_responder: bool,
+    pub ftm_initiator: bool,
+    pub fils: bool,
+    pub twt_requester: bool,
+    pub twt_responder: bool,
+}
+
+impl ExtCapsSummary {
+    /// Names of the bits that are set, in bit order
+    pub fn features(&self) -> Vec<&'static str> {
+        [
+            (self.bss_coexistence, "20/40 coexistence"),
+            (self.extended_channel_switching, "ext channel switching"),
+            (self.bss_transition, "BSS transition"),
+            (self.timing_measurement, "timing measurement"),
+            (self.interworking, "interworking"),
+            (self.qos_map, "QoS map"),
+            (self.tdls, "TDLS"),
+            (self.utf8_ssid, "UTF-8 SSID"),
+            (self.operating_mode_notification, "op mode notification"),
+            (self.ftm_responder, "FTM responder"),
+            (self.ftm_initiator, "FTM initiator"),
+            (self.fils, "FILS"),
+            (self.twt_requester, "TWT requester"),
+            (self.twt_responder, "TWT responder"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect()
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RsnSummary {
     pub version: u16,
@@ -81,6 +211,29 @@ pub struct WpsSummary {
     pub serial_number: String,
     pub device_type: String,
     pub configured: bool,
+    /// UUID-E, fixed per device and kept across MAC randomization
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+impl WpsSummary {
+    /// e.g. `samsung SM-G991B (Galaxy S21) "Jane's phone"`; None when
+    /// the device said nothing about itself
+    pub fn describe(&self) -> Option<String> {
+        let name = format!("{:?}", self.device_name);
+        let product = format!("({})", self.model);
+        let mut model: Vec<&str> = [self.manufacturer.as_str(), self.model_number.as_str()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !self.model.is_empty() && self.model != self.model_number {
+            model.push(&product);
+        }
+        if !self.device_name.is_empty() {
+            model.push(&name);
+        }
+        (!model.is_empty()).then(|| model.join(" "))
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -89,6 +242,89 @@ pub struct VendorIeSummary {
     pub oui_type: u8,
     pub vendor_name: Option<String>,
     pub data_len: usize,
+    /// Contents, for the element kinds prowl knows how to read
+    #[serde(default)]
+    pub decoded: Option<VendorIeDetail>,
+}
+
+/// What a vendor element says. These stay the same when the MAC is
+/// randomized, and which ones a device sends, and how, tells device
+/// families apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VendorIeDetail {
+    /// Microsoft WMM information or parameter element
+    Wmm { version: u8, qos_info: u8 },
+    /// Apple's element, sent by iPhones, iPads and Macs
+    Apple {
+        oui_type: u8,
+        subtype: Option<u8>,
+        version: Option<u8>,
+        flags: Vec<u8>,
+    },
+    /// Broadcom's element, sent by drivers for its chipsets
+    Broadcom { oui_type: u8, data: Vec<u8> },
+    /// Wi-Fi Direct (P2P) capability attribute
+    P2p {
+        device_capability: Option<u8>,
+        group_capability: Option<u8>,
+    },
+    /// Hotspot 2.0 (Passpoint) indication
+    Hotspot20 { release: u8 },
+    /// Multi-Band Operation, with the cellular data capability if sent
+    Mbo { cellular_data: Option<u8> },
+}
+
+impl VendorIeDetail {
+    pub fn describe(&self) -> String {
+        match self {
+            VendorIeDetail::Wmm { version, qos_info } => {
+                // U-APSD flags of a station, one per access category
+                let uapsd: Vec<&str> = ["VO", "VI", "BK", "BE"]
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| qos_info & (1 << bit) != 0)
+                    .map(|(_, ac)| *ac)
+                    .collect();
+                if uapsd.is_empty() {
+                    format!("WMM v{}", version)
+                } else {
+                    format!("WMM v{}, U-APSD {}", version, uapsd.join(" "))
+                }
+            }
+            VendorIeDetail::Apple { oui_type, subtype, version, flags } => {
+                let mut text = format!("Apple type {}", oui_type);
+                if let Some(subtype) = subtype {
+                    text.push_str(&format!(", subtype {}", subtype));
+                }
+                if let Some(version) = version {
+                    text.push_str(&format!(" v{}", version));
+                }
+                if !flags.is_empty() {
+                    text.push_str(&format!(", flags {}", hex_bytes(flags)));
+                }
+                text
+            }
+            VendorIeDetail::Broadcom { oui_type, data } => {
+                format!("Broadcom type {} ({})", oui_type, hex_bytes(data))
+            }
+            VendorIeDetail::P2p { device_capability, group_capability } => {
+                let caps = |cap: &Option<u8>| cap.map_or("?".to_string(), |c| format!("0x{:02x}", c));
+                format!(
+                    "Wi-Fi Direct, device caps {}, group caps {}",
+                    caps(device_capability),
+                    caps(group_capability)
+                )
+            }
+            VendorIeDetail::Hotspot20 { release } => format!("Hotspot 2.0 release {}", release),
+            VendorIeDetail::Mbo { cellular_data } => match cellular_data {
+                Some(1) => "MBO, cellular data available".to_string(),
+                Some(2) => "MBO, cellular data not available".to_string(),
+                Some(3) => "MBO, not cellular capable".to_string(),
+                _ => "MBO".to_string(),
+            },
+        }
+    }
 }
 
 pub fn parse_probe_request(data: &[u8], signal_dbm: Option<i32>) -> Option<ParsedProbeRequest> {
@@ -116,9 +352,20 @@ pub fn parse_probe_request(data: &[u8], signal_dbm: Option<i32>) -> Option<Parse
                     // address_2 is the source address (transmitter) in probe requests
                     let source_mac = format_mac(&probe_req.header.address_2);
                     let ssid = probe_req.station_info.ssid.clone().unwrap_or_default();
+                    // address_3 is the BSSID, address_1 the destination; either
+                    // is broadcast unless the device is looking for one AP
+                    let bssid = [&probe_req.header.address_3, &probe_req.header.address_1]
+                        .into_iter()
+                        .find(|addr| addr.0 != [0xff; 6])
+                        .map(|addr| format_mac(addr));
 
-                    // Extract all capabilities
-                    let capabilities = extract_capabilities(&probe_req.station_info);
+                    let (sequence_number, fragment_number) = sequence_control(frame_data);
+                    let retry = frame_data[1] & FC_RETRY != 0;
+
+                    // Extract all capabilities. A probe request has no fixed
+                    // fields, so the elements start right after the header.
+                    let mut capabilities = extract_capabilities(&probe_req.station_info);
+                    add_information_elements(&mut capabilities, &frame_data[24..]);
 
                     debug!(
                         "Parsed probe request: MAC={}, SSID={:?}, WiFi={}",
@@ -129,6 +376,10 @@ pub fn parse_probe_request(data: &[u8], signal_dbm: Option<i32>) -> Option<Parse
                         source_mac,
                         ssid,
                         signal_dbm,
+                        bssid,
+                        sequence_number,
+                        fragment_number,
+                        retry,
                         capabilities,
                     })
                 }
@@ -145,6 +396,125 @@ pub fn parse_probe_request(data: &[u8], signal_dbm: Option<i32>) -> Option<Parse
     }
 }
 
+/// Management frame that tears down a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeauthKind {
+    Deauth,
+    Disassoc,
+}
+
+impl DeauthKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeauthKind::Deauth => "deauth",
+            DeauthKind::Disassoc => "disassoc",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Option<Self> {
+        match s {
+            "deauth" => Some(DeauthKind::Deauth),
+            "disassoc" => Some(DeauthKind::Disassoc),
+            _ => None,
+        }
+    }
+}
+
+/// A deauthentication or disassociation frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedDeauth {
+    pub kind: DeauthKind,
+    pub source: String,
+    pub destination: String,
+    pub bssid: String,
+    /// None when the frame is protected (802.11w) and the reason is encrypted
+    pub reason_code: Option<u16>,
+}
+
+/// Parse a deauthentication or disassociation frame. The body is only a
+/// reason code, so the header is read directly.
+pub fn parse_deauth(data: &[u8]) -> Option<ParsedDeauth> {
+    let frame_data = if data.len() > 4 && data[0] == 0 {
+        let radiotap_len = u16::from_le_bytes([data[2], data[3]]) as usize;
+        if radiotap_len > data.len() {
+            return None;
+        }
+        &data[radiotap_len..]
+    } else {
+        data
+    };
+
+    if frame_data.len() < 24 {
+        return None;
+    }
+
+    // Management type with subtype 12 (deauth) or 10 (disassoc)
+    let kind = match frame_data[0] {
+        0xc0 => DeauthKind::Deauth,
+        0xa0 => DeauthKind::Disassoc,
+        _ => return None,
+    };
+    let protected = frame_data[1] & 0x40 != 0;
+    let reason_code = match frame_data.get(24..26) {
+        Some(bytes) if !protected => Some(u16::from_le_bytes([bytes[0], bytes[1]])),
+        _ => None,
+    };
+
+    let address = |offset: usize| {
+        let mut mac = [0u8; 6];
+        mac.copy_from_slice(&frame_data[offset..offset + 6]);
+        format_mac(&MacAddress(mac))
+    };
+    Some(ParsedDeauth {
+        kind,
+        destination: address(4),
+        source: address(10),
+        bssid: address(16),
+        reason_code,
+    })
+}
+
+/// Parse a beacon or probe response. Probe responses always carry the real
+/// SSID, so they also name hidden networks.
+pub fn parse_beacon(data: &[u8]) -> Option<ParsedBeacon> {
+    let frame_data = if data.len() > 4 && data[0] == 0 {
+        let radiotap_len = u16::from_le_bytes([data[2], data[3]]) as usize;
+        if radiotap_len > data.len() {
+            return None;
+        }
+        &data[radiotap_len..]
+    } else {
+        data
+    };
+
+    if frame_data.len() < 24 {
+        return None;
+    }
+
+    let (header, station_info, probe_response) = match parse_frame(frame_data, false) {
+        Ok(Frame::Beacon(beacon)) => (beacon.header, beacon.station_info, false),
+        Ok(Frame::ProbeResponse(response)) => (response.header, response.station_info, true),
+        Ok(_) => return None,
+        Err(e) => {
+            trace!("Failed to parse frame: {:?}", e);
+            return None;
+        }
+    };
+
+    let ssid = station_info.ssid.unwrap_or_default();
+    // Hidden networks send an empty SSID or one made of NUL bytes
+    let hidden = ssid.chars().all(|c| c == '\0');
+    Some(ParsedBeacon {
+        bssid: format_mac(&header.address_3),
+        ssid: if hidden { String::new() } else { ssid },
+        hidden,
+        channel: station_info.ds_parameter_set,
+        probe_response,
+        destination: probe_response.then(|| format_mac(&header.address_1)),
+    })
+}
+
 /// Extract all capabilities from StationInfo
 fn extract_capabilities(station_info: &StationInfo) -> ProbeCapabilities {
     let mut caps = ProbeCapabilities {
@@ -221,6 +591,323 @@ fn extract_capabilities(station_info: &StationInfo) -> ProbeCapabilities {
     caps
 }
 
+const IE_SSID: u8 = 0;
+const IE_SUPPORTED_RATES: u8 = 1;
+const IE_DS_PARAMETER_SET: u8 = 3;
+const IE_HT_CAPABILITIES: u8 = 45;
+const IE_RSN: u8 = 48;
+const IE_EXTENDED_RATES: u8 = 50;
+const IE_EXTENDED_CAPABILITIES: u8 = 127;
+const IE_VHT_CAPABILITIES: u8 = 191;
+const IE_VENDOR_SPECIFIC: u8 = 221;
+const IE_EXTENSION: u8 = 255;
+const EXT_HE_CAPABILITIES: u8 = 35;
+
+/// Walk the tagged parameters of a frame body. Stops at the first element
+/// that runs past the end, which is where a truncated capture gets cut.
+pub fn parse_information_elements(body: &[u8]) -> Vec<InformationElement> {
+    let mut elements = Vec::new();
+    let mut rest = body;
+    while rest.len() >= 2 {
+        let (id, len) = (rest[0], rest[1] as usize);
+        if rest.len() < 2 + len {
+            break;
+        }
+        let payload = &rest[2..2 + len];
+        let element = match (id, payload.split_first()) {
+            (IE_EXTENSION, Some((ext_id, data))) => InformationElement {
+                id,
+                ext_id: Some(*ext_id),
+                data: data.to_vec(),
+            },
+            _ => InformationElement {
+                id,
+                ext_id: None,
+                data: payload.to_vec(),
+            },
+        };
+        elements.push(element);
+        rest = &rest[2 + len..];
+    }
+    elements
+}
+
+/// A span of a raw frame and what it decodes to, for the hex viewer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameField {
+    pub offset: usize,
+    pub len: usize,
+    pub name: String,
+    pub value: String,
+}
+
+/// Label every byte range of a captured management frame: the radiotap
+/// header, the 802.11 header fields and each information element. Bytes
+/// that can't be placed end up in one trailing field, so a misparsed frame
+/// still shows where parsing stopped.
+pub fn annotate_frame(data: &[u8]) -> Vec<FrameField> {
+    let mut fields = Vec::new();
+    let field = |offset: usize, len: usize, name: &str, value: String| FrameField {
+        offset,
+        len,
+        name: name.to_string(),
+        value,
+    };
+
+    let mut offset = 0;
+    if data.len() > 4 && data[0] == 0 {
+        let radiotap_len = (u16::from_le_bytes([data[2], data[3]]) as usize).min(data.len());
+        fields.push(field(0, radiotap_len, "Radiotap header", format!("{} bytes", radiotap_len)));
+        offset = radiotap_len;
+    }
+
+    let frame = &data[offset..];
+    if frame.len() >= 24 {
+        let (fc, flags) = (frame[0], frame[1]);
+        let kind = format!("type {} subtype {}, flags 0x{:02x}", (fc >> 2) & 0x03, fc >> 4, flags);
+        fields.push(field(offset, 2, "Frame control", kind));
+        let duration = u16::from_le_bytes([frame[2], frame[3]]);
+        fields.push(field(offset + 2, 2, "Duration", format!("{} us", duration)));
+        let addresses = ["Address 1 (receiver)", "Address 2 (transmitter)", "Address 3 (BSSID)"];
+        for (i, name) in addresses.into_iter().enumerate() {
+            let start = 4 + i * 6;
+            let mac = MacAddress(frame[start..start + 6].try_into().expect("6 bytes"));
+            fields.push(field(offset + start, 6, name, format_mac(&mac)));
+        }
+        let (sequence_number, fragment_number) = sequence_control(frame);
+        let sequence = format!("seq {}, frag {}", sequence_number, fragment_number);
+        fields.push(field(offset + 22, 2, "Sequence control", sequence));
+        offset += 24;
+
+        // Only probe requests start their elements right after the header
+        if fc & 0xfc == 0x40 {
+            for ie in parse_information_elements(&data[offset..]) {
+                let len = 2 + ie.data.len() + ie.ext_id.map_or(0, |_| 1);
+                let value = match ie.id {
+                    IE_SSID => format!("{:?}", String::from_utf8_lossy(&ie.data)),
+                    IE_SUPPORTED_RATES | IE_EXTENDED_RATES => {
+                        let rates: Vec<String> =
+                            ie.data.iter().map(|r| format!("{}", (r & 0x7f) as f32 / 2.0)).collect();
+                        format!("{} Mbps", rates.join(", "))
+                    }
+                    IE_DS_PARAMETER_SET if !ie.data.is_empty() => format!("channel {}", ie.data[0]),
+                    IE_VENDOR_SPECIFIC if ie.data.len() >= 4 => format!(
+                        "OUI {:02X}:{:02X}:{:02X} type {}, {} bytes",
+                        ie.data[0],
+                        ie.data[1],
+                        ie.data[2],
+                        ie.data[3],
+                        ie.data.len()
+                    ),
+                    _ => format!("{} bytes", ie.data.len()),
+                };
+                let name = match ie.ext_id {
+                    Some(ext) => format!("{} ({}.{})", ie.name(), ie.id, ext),
+                    None => format!("{} ({})", ie.name(), ie.id),
+                };
+                fields.push(FrameField {
+                    offset,
+                    len,
+                    name,
+                    value,
+                });
+                offset += len;
+            }
+        }
+    }
+
+    if offset < data.len() {
+        fields.push(field(offset, data.len() - offset, "Trailing bytes", "not decoded".to_string()));
+    }
+    fields
+}
+
+/// Channel from a DS Parameter Set element, when it names a 2.4 or 5 GHz
+/// channel; some devices send 0 or leftovers from their last scan
+fn ds_channel(data: &[u8]) -> Option<u8> {
+    data.first().copied().filter(|channel| matches!(channel, 1..=14 | 32..=177))
+}
+
+/// Fill in what libwifi leaves out: the full element list, HE capabilities
+/// and the extended capabilities bitfield
+fn add_information_elements(caps: &mut ProbeCapabilities, body: &[u8]) {
+    caps.information_elements = parse_information_elements(body);
+    caps.rate_bits = rate_bits(&caps.information_elements);
+    for element in &caps.information_elements {
+        match (element.id, element.ext_id) {
+            (IE_DS_PARAMETER_SET, _) => caps.ds_channel = ds_channel(&element.data),
+            (IE_EXTENDED_CAPABILITIES, _) => {
+                caps.extended_capabilities = element.data.clone();
+                caps.ext_caps = Some(parse_extended_capabilities(&element.data));
+            }
+            (IE_EXTENSION, Some(EXT_HE_CAPABILITIES)) => {
+                caps.he_caps = Some(parse_he_capabilities(&element.data))
+            }
+            _ => {}
+        }
+    }
+
+    // libwifi doesn't read UUID-E, and misses WPS split over several elements
+    let wps: Vec<u8> = caps
+        .information_elements
+        .iter()
+        .filter(|ie| ie.id == IE_VENDOR_SPECIFIC && ie.data.starts_with(&WPS_OUI_TYPE))
+        .flat_map(|ie| ie.data[WPS_OUI_TYPE.len()..].iter().copied())
+        .collect();
+    if let Some(parsed) = parse_wps_attributes(&wps) {
+        caps.wps_info = Some(parsed);
+    }
+
+    // libwifi only names vendor elements; pair each with the element it
+    // came from, by OUI and type in the order they were sent
+    let mut vendor_elements: Vec<&[u8]> = caps
+        .information_elements
+        .iter()
+        .filter(|ie| ie.id == IE_VENDOR_SPECIFIC && ie.data.len() >= 4)
+        .map(|ie| ie.data.as_slice())
+        .collect();
+    for vie in caps.vendor_ies.iter_mut() {
+        let position = vendor_elements.iter().position(|data| {
+            let oui = format!("{:02X}:{:02X}:{:02X}", data[0], data[1], data[2]);
+            oui == vie.oui && data[3] == vie.oui_type
+        });
+        if let Some(position) = position {
+            vie.decoded = decode_vendor_ie(vendor_elements.remove(position));
+        }
+    }
+}
+
+/// Rates in 500 kb/s units, in the order of their bits in `rate_bits`
+const RATE_BITS: [u8; 14] = [2, 4, 11, 12, 18, 22, 24, 36, 44, 48, 66, 72, 96, 108];
+/// A BSS membership selector (HT, VHT, SAE H2E, HE...) in the rate list
+pub const RATE_BIT_SELECTOR: u32 = 1 << 14;
+/// Any rate not in the table
+pub const RATE_BIT_OTHER: u32 = 1 << 15;
+const MEMBERSHIP_SELECTORS: [u8; 6] = [122, 123, 124, 125, 126, 127];
+
+/// The supported and extended rate elements as one number: the low 16 bits
+/// mark the rates offered (1, 2, 5.5, 6, 9, 11, 12, 18, 22, 24, 33, 36, 48
+/// and 54 Mb/s, then `RATE_BIT_SELECTOR` and `RATE_BIT_OTHER`), the high 16
+/// the same rates flagged basic. Which rates a client lists, and which it
+/// marks basic, depends on the chipset and driver, so equal rate sets are
+/// cheap to compare when grouping randomized MACs.
+pub fn rate_bits(elements: &[InformationElement]) -> u32 {
+    let mut bits = 0;
+    let rates = elements
+        .iter()
+        .filter(|ie| matches!(ie.id, IE_SUPPORTED_RATES | IE_EXTENDED_RATES))
+        .flat_map(|ie| ie.data.iter());
+    for byte in rates {
+        let rate = byte & 0x7f;
+        let bit = match RATE_BITS.iter().position(|&r| r == rate) {
+            Some(index) => 1 << index,
+            None if MEMBERSHIP_SELECTORS.contains(&rate) => RATE_BIT_SELECTOR,
+            None => RATE_BIT_OTHER,
+        };
+        bits |= bit;
+        if byte & 0x80 != 0 {
+            bits |= bit << 16;
+        }
+    }
+    bits
+}
+
+/// Microsoft OUI and the WPS vendor element type
+const WPS_OUI_TYPE: [u8; 4] = [0x00, 0x50, 0xf2, 0x04];
+
+const WPS_CONFIG_STATE: u16 = 0x1044;
+const WPS_UUID_E: u16 = 0x1047;
+const WPS_DEVICE_NAME: u16 = 0x1011;
+const WPS_MANUFACTURER: u16 = 0x1021;
+const WPS_MODEL_NAME: u16 = 0x1023;
+const WPS_MODEL_NUMBER: u16 = 0x1024;
+const WPS_SERIAL_NUMBER: u16 = 0x1042;
+const WPS_PRIMARY_DEVICE_TYPE: u16 = 0x1054;
+
+/// Read the type-length-value attributes of a WPS element body; None when
+/// there are none
+pub fn parse_wps_attributes(data: &[u8]) -> Option<WpsSummary> {
+    let mut wps = WpsSummary::default();
+    let mut found = false;
+    let mut rest = data;
+    while rest.len() >= 4 {
+        let kind = u16::from_be_bytes([rest[0], rest[1]]);
+        let len = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+        if rest.len() < 4 + len {
+            break;
+        }
+        let value = &rest[4..4 + len];
+        let text = || String::from_utf8_lossy(value).trim_end_matches('\0').trim().to_string();
+        match kind {
+            WPS_CONFIG_STATE => wps.configured = value.first() == Some(&0x02),
+            WPS_UUID_E if len == 16 => wps.uuid = Some(format_uuid(value)),
+            WPS_DEVICE_NAME => wps.device_name = text(),
+            WPS_MANUFACTURER => wps.manufacturer = text(),
+            WPS_MODEL_NAME => wps.model = text(),
+            WPS_MODEL_NUMBER => wps.model_number = text(),
+            WPS_SERIAL_NUMBER => wps.serial_number = text(),
+            WPS_PRIMARY_DEVICE_TYPE if len == 8 => {
+                let category = u16::from_be_bytes([value[0], value[1]]);
+                let subcategory = u16::from_be_bytes([value[6], value[7]]);
+                let oui = format!("{:02X}{:02X}{:02X}{:02X}", value[2], value[3], value[4], value[5]);
+                wps.device_type = format!("{}-{}-{}", category, oui, subcategory);
+            }
+            _ => {}
+        }
+        found = true;
+        rest = &rest[4 + len..];
+    }
+    found.then_some(wps)
+}
+
+fn format_uuid(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// HE capabilities: 6 bytes of MAC capabilities, then 11 of PHY
+fn parse_he_capabilities(raw: &[u8]) -> HeCapsSummary {
+    let mut summary = HeCapsSummary::default();
+
+    if let Some(mac) = raw.first() {
+        summary.twt_requester = mac & 0x02 != 0;
+    }
+    if raw.len() >= 11 {
+        let phy = &raw[6..];
+        summary.channel_width_set = phy[0] >> 1;
+        summary.su_beamformer = phy[3] & 0x80 != 0;
+        summary.mu_beamformer = phy[4] & 0x02 != 0;
+    }
+
+    summary
+}
+
+/// Bit `bit` of a little-endian bitfield; bits past the end are clear
+fn bit_set(field: &[u8], bit: usize) -> bool {
+    field.get(bit / 8).is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// Extended Capabilities, numbered as in 802.11-2020 table 9-153. Clients
+/// send as many bytes as they need to reach their last set bit.
+fn parse_extended_capabilities(raw: &[u8]) -> ExtCapsSummary {
+    ExtCapsSummary {
+        bss_coexistence: bit_set(raw, 0),
+        extended_channel_switching: bit_set(raw, 2),
+        bss_transition: bit_set(raw, 19),
+        timing_measurement: bit_set(raw, 23),
+        interworking: bit_set(raw, 31),
+        qos_map: bit_set(raw, 32),
+        tdls: bit_set(raw, 37),
+        utf8_ssid: bit_set(raw, 48),
+        operating_mode_notification: bit_set(raw, 62),
+        ftm_responder: bit_set(raw, 70),
+        ftm_initiator: bit_set(raw, 71),
+        fils: bit_set(raw, 72),
+        twt_requester: bit_set(raw, 77),
+        twt_responder: bit_set(raw, 78),
+    }
+}
+
 fn check_for_he_capability(data: &[(u8, Vec<u8>)]) -> bool {
     // HE Capabilities IE has ID 255 (Extension) with extension ID 35
     data.iter()
@@ -369,14 +1056,94 @@ fn extract_vendor_ie_summary(vie: &VendorSpecificInfo) -> VendorIeSummary {
         oui_type: vie.oui_type,
         vendor_name,
         data_len: vie.data.len(),
+        decoded: None,
+    }
+}
+
+const OUI_MICROSOFT: [u8; 3] = [0x00, 0x50, 0xf2];
+const OUI_APPLE: [u8; 3] = [0x00, 0x17, 0xf2];
+const OUI_BROADCOM: [u8; 3] = [0x00, 0x10, 0x18];
+const OUI_WFA: [u8; 3] = [0x50, 0x6f, 0x9a];
+
+const MS_TYPE_WMM: u8 = 2;
+const WFA_TYPE_P2P: u8 = 9;
+const WFA_TYPE_HOTSPOT20: u8 = 0x10;
+const WFA_TYPE_MBO: u8 = 0x16;
+const P2P_ATTR_CAPABILITY: u8 = 2;
+const MBO_ATTR_CELLULAR_DATA: u8 = 3;
+
+/// Read a vendor element's body (OUI and type included) when it's one of
+/// the kinds prowl knows
+pub fn decode_vendor_ie(data: &[u8]) -> Option<VendorIeDetail> {
+    if data.len() < 4 {
+        return None;
     }
+    let oui = [data[0], data[1], data[2]];
+    let (oui_type, body) = (data[3], &data[4..]);
+    match (oui, oui_type) {
+        // Subtype 0 is the information element, 1 the parameter element
+        (OUI_MICROSOFT, MS_TYPE_WMM) if body.len() >= 3 && body[0] <= 1 => Some(VendorIeDetail::Wmm {
+            version: body[1],
+            qos_info: body[2],
+        }),
+        (OUI_APPLE, _) => Some(VendorIeDetail::Apple {
+            oui_type,
+            subtype: body.first().copied(),
+            version: body.get(1).copied(),
+            flags: body.get(2..).unwrap_or_default().to_vec(),
+        }),
+        (OUI_BROADCOM, _) => Some(VendorIeDetail::Broadcom {
+            oui_type,
+            data: body.to_vec(),
+        }),
+        (OUI_WFA, WFA_TYPE_P2P) => {
+            // Attributes: ID, two-byte little-endian length, value
+            let mut rest = body;
+            let mut capability = None;
+            while rest.len() >= 3 {
+                let len = u16::from_le_bytes([rest[1], rest[2]]) as usize;
+                let value = rest.get(3..3 + len)?;
+                if rest[0] == P2P_ATTR_CAPABILITY {
+                    capability = Some(value);
+                }
+                rest = &rest[3 + len..];
+            }
+            Some(VendorIeDetail::P2p {
+                device_capability: capability.and_then(|c| c.first().copied()),
+                group_capability: capability.and_then(|c| c.get(1).copied()),
+            })
+        }
+        // The release number counts from 0 for release 1
+        (OUI_WFA, WFA_TYPE_HOTSPOT20) => body.first().map(|config| VendorIeDetail::Hotspot20 {
+            release: (config >> 4) + 1,
+        }),
+        (OUI_WFA, WFA_TYPE_MBO) => {
+            // Attributes: ID, one-byte length, value
+            let mut rest = body;
+            let mut cellular_data = None;
+            while rest.len() >= 2 {
+                let len = rest[1] as usize;
+                let value = rest.get(2..2 + len)?;
+                if rest[0] == MBO_ATTR_CELLULAR_DATA {
+                    cellular_data = value.first().copied();
+                }
+                rest = &rest[2 + len..];
+            }
+            Some(VendorIeDetail::Mbo { cellular_data })
+        }
+        _ => None,
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 fn lookup_vendor_ie_oui(oui: &[u8; 3]) -> Option<String> {
     match oui {
         [0x00, 0x50, 0xF2] => Some("Microsoft".to_string()),
         [0x00, 0x0F, 0xAC] => Some("IEEE 802.11".to_string()),
-        [0x00, 0x17, 0xF2] => Some("Qualcomm".to_string()),
+        [0x00, 0x17, 0xF2] => Some("Apple".to_string()),
         [0x00, 0x10, 0x18] => Some("Broadcom".to_string()),
         [0x00, 0x03, 0x7F] => Some("Atheros".to_string()),
         [0x00, 0x13, 0x74] => Some("Ralink".to_string()),
@@ -425,6 +1192,37 @@ pub fn is_probe_request(data: &[u8]) -> bool {
     frame_type == 0 && subtype == 4
 }
 
+/// Retry bit in the second byte of the frame control field
+const FC_RETRY: u8 = 0x08;
+
+/// Sequence numbers count modulo 4096
+pub const SEQUENCE_NUMBERS: u16 = 4096;
+
+/// Sequence and fragment number from the sequence control field of a
+/// management header (at least 24 bytes, radiotap already skipped)
+pub fn sequence_control(frame_data: &[u8]) -> (u16, u8) {
+    let control = u16::from_le_bytes([frame_data[22], frame_data[23]]);
+    (control >> 4, (control & 0x0f) as u8)
+}
+
+/// Frames a device sent from `previous` up to `next`, allowing for the
+/// counter wrapping: 1 for consecutive frames, 0 for a retransmission.
+/// Devices number every frame they send, so a gap counts frames sent on
+/// other channels or to other stations, and a gap near 4096 usually means
+/// the counter was reset rather than that it ran all the way round.
+pub fn sequence_gap(previous: u16, next: u16) -> u16 {
+    next.wrapping_sub(previous) % SEQUENCE_NUMBERS
+}
+
+/// Gaps between consecutive sequence numbers of one MAC, in the order the
+/// probes were heard
+pub fn sequence_gaps(sequence_numbers: &[u16]) -> Vec<u16> {
+    sequence_numbers
+        .windows(2)
+        .map(|pair| sequence_gap(pair[0], pair[1]))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -434,4 +1232,276 @@ mod tests {
         let mac = MacAddress([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
         assert_eq!(format_mac(&mac), "AA:BB:CC:DD:EE:FF");
     }
+
+    fn beacon_frame(bssid: [u8; 6], ssid: &[u8], channel: u8) -> Vec<u8> {
+        let mut frame = vec![0x80, 0x00, 0x00, 0x00];
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&bssid);
+        frame.extend_from_slice(&bssid);
+        frame.extend_from_slice(&[0x00, 0x00]);
+        // Timestamp, beacon interval, capabilities
+        frame.extend_from_slice(&[0; 8]);
+        frame.extend_from_slice(&[0x64, 0x00, 0x11, 0x04]);
+        frame.push(0);
+        frame.push(ssid.len() as u8);
+        frame.extend_from_slice(ssid);
+        frame.extend_from_slice(&[3, 1, channel]);
+        frame
+    }
+
+    #[test]
+    fn test_parse_beacon_hidden_and_visible() {
+        let bssid = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+
+        let visible = parse_beacon(&beacon_frame(bssid, b"HomeNet", 6)).unwrap();
+        assert_eq!(visible.bssid, "00:11:22:33:44:55");
+        assert_eq!(visible.ssid, "HomeNet");
+        assert!(!visible.hidden);
+        assert_eq!(visible.channel, Some(6));
+
+        let hidden = parse_beacon(&beacon_frame(bssid, &[0; 7], 11)).unwrap();
+        assert!(hidden.hidden);
+        assert_eq!(hidden.ssid, "");
+        assert!(!hidden.probe_response);
+        assert_eq!(hidden.destination, None);
+    }
+
+    #[test]
+    fn test_parse_probe_response() {
+        let bssid = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let mut frame = beacon_frame(bssid, b"SecretNet", 1);
+        // Probe response subtype, addressed to the probing station
+        frame[0] = 0x50;
+        frame[4..10].copy_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+
+        let response = parse_beacon(&frame).unwrap();
+        assert!(response.probe_response);
+        assert_eq!(response.ssid, "SecretNet");
+        assert_eq!(response.bssid, "00:11:22:33:44:55");
+        assert_eq!(response.destination.as_deref(), Some("02:00:00:00:00:01"));
+    }
+
+    #[test]
+    fn test_parse_deauth() {
+        let ap = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let mut frame = vec![0xc0, 0x00, 0x00, 0x00];
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&ap);
+        frame.extend_from_slice(&ap);
+        frame.extend_from_slice(&[0x00, 0x00, 0x07, 0x00]);
+
+        let deauth = parse_deauth(&frame).unwrap();
+        assert_eq!(deauth.kind, DeauthKind::Deauth);
+        assert_eq!(deauth.destination, "FF:FF:FF:FF:FF:FF");
+        assert_eq!(deauth.source, "00:11:22:33:44:55");
+        assert_eq!(deauth.reason_code, Some(7));
+
+        // Protected disassoc: reason is encrypted
+        frame[0] = 0xa0;
+        frame[1] = 0x40;
+        let disassoc = parse_deauth(&frame).unwrap();
+        assert_eq!(disassoc.kind, DeauthKind::Disassoc);
+        assert_eq!(disassoc.reason_code, None);
+
+        assert!(parse_deauth(&beacon_frame(ap, b"HomeNet", 6)).is_none());
+    }
+
+    #[test]
+    fn test_probe_sequence_number() {
+        let mut frame = crate::source::build_probe_request([0x02, 0, 0, 0, 0, 0x01], "HomeNet", Some(-50));
+        // Sequence control follows the 9-byte radiotap header and 22 header bytes
+        let control = (1234u16 << 4) | 3;
+        frame[9 + 22..9 + 24].copy_from_slice(&control.to_le_bytes());
+
+        let probe = parse_probe_request(&frame, Some(-50)).unwrap();
+        assert_eq!(probe.sequence_number, 1234);
+        assert_eq!(probe.fragment_number, 3);
+        assert!(!probe.retry);
+
+        frame[9 + 1] |= FC_RETRY;
+        assert!(parse_probe_request(&frame, Some(-50)).unwrap().retry);
+
+        // Wrapping past 4095 is still a gap of one
+        assert_eq!(sequence_gaps(&[4094, 4095, 0, 0, 10]), vec![1, 1, 0, 10]);
+        assert_eq!(sequence_gap(10, 5), 4091);
+    }
+
+    #[test]
+    fn test_wps_attributes() {
+        let mut body = Vec::new();
+        let mut attr = |kind: u16, value: &[u8]| {
+            body.extend_from_slice(&kind.to_be_bytes());
+            body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            body.extend_from_slice(value);
+        };
+        attr(WPS_CONFIG_STATE, &[0x01]);
+        attr(WPS_UUID_E, &[0x12; 16]);
+        attr(WPS_MANUFACTURER, b"samsung");
+        attr(WPS_MODEL_NAME, b"Galaxy S21");
+        attr(WPS_MODEL_NUMBER, b"SM-G991B");
+        attr(WPS_DEVICE_NAME, b"Jane's phone\0");
+        attr(WPS_PRIMARY_DEVICE_TYPE, &[0x00, 0x0a, 0x00, 0x50, 0xf2, 0x04, 0x00, 0x05]);
+
+        // Split over two vendor elements, as long WPS bodies are
+        let mut frame = crate::source::build_probe_request([0x02, 0, 0, 0, 0, 0x01], "", Some(-50));
+        let (first, second) = body.split_at(25);
+        for part in [first, second] {
+            frame.extend_from_slice(&[IE_VENDOR_SPECIFIC, (part.len() + 4) as u8]);
+            frame.extend_from_slice(&WPS_OUI_TYPE);
+            frame.extend_from_slice(part);
+        }
+
+        let wps = parse_probe_request(&frame, Some(-50)).unwrap().capabilities.wps_info.unwrap();
+        assert_eq!(wps.uuid.as_deref(), Some("12121212-1212-1212-1212-121212121212"));
+        assert_eq!(wps.device_type, "10-0050F204-5");
+        assert!(!wps.configured);
+        assert_eq!(wps.describe().unwrap(), "samsung SM-G991B (Galaxy S21) \"Jane's phone\"");
+        assert_eq!(parse_wps_attributes(&[]).map(|w| w.uuid), None);
+    }
+
+    #[test]
+    fn test_annotate_frame() {
+        let mut frame = crate::source::build_probe_request([0x02, 0, 0, 0, 0, 0x01], "Home", Some(-50));
+        frame.extend_from_slice(&[221, 40, 0x00]);
+
+        let fields = annotate_frame(&frame);
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Radiotap header",
+                "Frame control",
+                "Duration",
+                "Address 1 (receiver)",
+                "Address 2 (transmitter)",
+                "Address 3 (BSSID)",
+                "Sequence control",
+                "SSID (0)",
+                "Supported Rates (1)",
+                "Trailing bytes",
+            ]
+        );
+        assert_eq!(fields[1].value, "type 0 subtype 4, flags 0x00");
+        assert_eq!(fields[4].value, "02:00:00:00:00:01");
+        assert_eq!((fields[7].offset, fields[7].len), (9 + 24, 6));
+        assert_eq!(fields[7].value, "\"Home\"");
+        assert_eq!(fields[8].value, "1, 2, 5.5, 11, 6, 9, 12, 18 Mbps");
+        // The truncated vendor element is left undecoded
+        assert_eq!(fields[9].len, 3);
+        // Every byte is covered exactly once
+        assert_eq!(fields.iter().map(|f| f.len).sum::<usize>(), frame.len());
+    }
+
+    #[test]
+    fn test_probe_information_elements() {
+        let mut frame = crate::source::build_probe_request([0x02, 0, 0, 0, 0, 0x01], "HomeNet", Some(-50));
+        // DS Parameter Set, extended capabilities, then HE capabilities with
+        // an SU beamformer
+        frame.extend_from_slice(&[3, 1, 11]);
+        frame.extend_from_slice(&[127, 3, 0x04, 0x00, 0x08]);
+        let mut he = vec![255, 18, 35, 0x02, 0, 0, 0, 0, 0];
+        he.extend_from_slice(&[0x04, 0, 0, 0x80, 0, 0, 0, 0, 0, 0, 0]);
+        frame.extend_from_slice(&he);
+
+        let probe = parse_probe_request(&frame, Some(-50)).unwrap();
+        // The receive channel wins when there is one
+        assert_eq!(probe.channel(None), Some(11));
+        assert_eq!(probe.channel(Some(6)), Some(6));
+        assert_eq!(ds_channel(&[0]), None);
+        let caps = probe.capabilities;
+        let names: Vec<&str> = caps.information_elements.iter().map(|ie| ie.name()).collect();
+        assert_eq!(names.first(), Some(&"SSID"));
+        assert_eq!(&names[names.len() - 2..], ["Extended Capabilities", "HE Capabilities"]);
+        assert_eq!(caps.information_elements[0].data, b"HomeNet");
+        assert_eq!(caps.extended_capabilities, vec![0x04, 0x00, 0x08]);
+        let ext = caps.ext_caps.unwrap();
+        assert_eq!(ext.features(), vec!["ext channel switching", "BSS transition"]);
+        assert!(!ext.ftm_initiator && !ext.twt_requester);
+
+        // FTM initiator and TWT requester sit in the ninth and tenth bytes
+        let ext = parse_extended_capabilities(&[0x05, 0, 0x08, 0x80, 0, 0, 0, 0x40, 0x80, 0x21]);
+        assert_eq!(
+            ext.features(),
+            vec![
+                "20/40 coexistence",
+                "ext channel switching",
+                "BSS transition",
+                "interworking",
+                "op mode notification",
+                "FTM initiator",
+                "FILS",
+                "TWT requester"
+            ]
+        );
+
+        let he = caps.he_caps.unwrap();
+        assert!(caps.has_he);
+        assert!(he.twt_requester && he.su_beamformer && !he.mu_beamformer);
+        assert_eq!(he.channel_width_set, 2);
+
+        // A truncated trailing element is dropped
+        let elements = parse_information_elements(&[0, 2, b'h', b'i', 221, 40, 0x00]);
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].data, b"hi");
+    }
+
+    #[test]
+    fn test_rate_bits() {
+        let element = |id: u8, data: &[u8]| InformationElement {
+            id,
+            ext_id: None,
+            data: data.to_vec(),
+        };
+        // 1, 2, 5.5 and 11 Mb/s flagged basic, then 6-54 in extended rates
+        let elements = [
+            element(IE_SSID, b"HomeNet"),
+            element(IE_SUPPORTED_RATES, &[0x82, 0x84, 0x8b, 0x96, 0x0c, 0x12, 0x18, 0x24]),
+            element(IE_EXTENDED_RATES, &[0x30, 0x48, 0x60, 0x6c]),
+        ];
+        let bits = rate_bits(&elements);
+        // Every OFDM and DSSS rate except PBCC 22 and 33 Mb/s
+        assert_eq!(bits & 0xffff, 0x3aff);
+        assert_eq!(bits >> 16, 0b10_0111);
+
+        // Same rates, none basic, plus the SAE H2E selector and an odd rate
+        let elements = [
+            element(IE_SUPPORTED_RATES, &[0x02, 0x04, 0x0b, 0x16, 0x0c, 0x12, 0x18, 0x24]),
+            element(IE_EXTENDED_RATES, &[0x30, 0x48, 0x60, 0x6c, 0xfb, 0x07]),
+        ];
+        let other = rate_bits(&elements);
+        assert_eq!(other & 0x3fff, bits & 0x3fff);
+        assert_eq!(other >> 16, RATE_BIT_SELECTOR);
+        assert!(other & RATE_BIT_OTHER != 0);
+        assert_eq!(rate_bits(&[]), 0);
+    }
+
+    #[test]
+    fn test_vendor_ie_decoding() {
+        let mut frame = crate::source::build_probe_request([0x02, 0, 0, 0, 0, 0x02], "", Some(-60));
+        // Apple's element as an iPhone sends it, then WMM with U-APSD on VO
+        frame.extend_from_slice(&[221, 11, 0x00, 0x17, 0xf2, 0x0a, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00]);
+        frame.extend_from_slice(&[221, 7, 0x00, 0x50, 0xf2, 0x02, 0x00, 0x01, 0x01]);
+
+        let caps = parse_probe_request(&frame, Some(-60)).unwrap().capabilities;
+        let decoded: Vec<VendorIeDetail> = caps.vendor_ies.iter().filter_map(|v| v.decoded.clone()).collect();
+        assert_eq!(
+            decoded[0],
+            VendorIeDetail::Apple {
+                oui_type: 10,
+                subtype: Some(0),
+                version: Some(1),
+                flags: vec![0x04, 0, 0, 0, 0],
+            }
+        );
+        assert_eq!(decoded[1].describe(), "WMM v1, U-APSD VO");
+
+        // Wi-Fi Direct capability attribute, Hotspot 2.0 release 2, MBO
+        let p2p = decode_vendor_ie(&[0x50, 0x6f, 0x9a, 0x09, 0x02, 0x02, 0x00, 0x25, 0x00]).unwrap();
+        assert_eq!(p2p.describe(), "Wi-Fi Direct, device caps 0x25, group caps 0x00");
+        let hs20 = decode_vendor_ie(&[0x50, 0x6f, 0x9a, 0x10, 0x10]).unwrap();
+        assert_eq!(hs20, VendorIeDetail::Hotspot20 { release: 2 });
+        let mbo = decode_vendor_ie(&[0x50, 0x6f, 0x9a, 0x16, 0x03, 0x01, 0x01]).unwrap();
+        assert_eq!(mbo.describe(), "MBO, cellular data available");
+        assert!(decode_vendor_ie(&[0x00, 0x0c, 0xe7, 0x08, 0x00]).is_none());
+    }
 }
@@ -0,0 +1,441 @@
+//! Raw frame dump in classic pcap format.
+//!
+//! Written by hand rather than through libpcap so AF_PACKET-only builds can
+//! keep a copy of every stored probe request for Wireshark or a later
+//! re-parse. Frames keep their radiotap header. Long captures can rotate
+//! through a series of timestamped files instead of growing one forever.
+//!
+//! Evidence exports go the other way: frames are read back from these files
+//! and written as pcapng, where each frame can carry a comment that
+//! Wireshark shows in its packet details.
+
+use anyhow::{bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const LINKTYPE_IEEE802_11_RADIOTAP: u32 = 127;
+const SNAPLEN: u32 = 65535;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const OPT_END: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const OPT_SHB_USERAPPL: u16 = 4;
+
+/// Appends frames to a pcap file, writing the file header if it is new
+pub struct PcapWriter {
+    file: File,
+    size: u64,
+}
+
+impl PcapWriter {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open pcap output {:?}", path))?;
+
+        if file.metadata()?.len() == 0 {
+            let mut header = Vec::with_capacity(24);
+            header.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+            header.extend_from_slice(&2u16.to_le_bytes());
+            header.extend_from_slice(&4u16.to_le_bytes());
+            header.extend_from_slice(&0i32.to_le_bytes());
+            header.extend_from_slice(&0u32.to_le_bytes());
+            header.extend_from_slice(&SNAPLEN.to_le_bytes());
+            header.extend_from_slice(&LINKTYPE_IEEE802_11_RADIOTAP.to_le_bytes());
+            file.write_all(&header)?;
+        }
+
+        let size = file.metadata()?.len();
+        Ok(PcapWriter { file, size })
+    }
+
+    /// Bytes in the file, header included
+    pub fn size_bytes(&self) -> u64 {
+        self.size
+    }
+
+    /// Append one frame captured at `secs`.`micros`. Each record goes out in a
+    /// single write so a crash never leaves half a record behind.
+    pub fn write_frame(&mut self, secs: i64, micros: u32, data: &[u8]) -> Result<()> {
+        let caplen = data.len().min(SNAPLEN as usize);
+        let mut record = Vec::with_capacity(16 + caplen);
+        record.extend_from_slice(&(secs as u32).to_le_bytes());
+        record.extend_from_slice(&micros.to_le_bytes());
+        record.extend_from_slice(&(caplen as u32).to_le_bytes());
+        record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        record.extend_from_slice(&data[..caplen]);
+        self.file.write_all(&record)?;
+        self.size += record.len() as u64;
+        Ok(())
+    }
+}
+
+/// One frame read back from a pcap file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PcapRecord {
+    pub secs: i64,
+    pub micros: u32,
+    pub data: Vec<u8>,
+}
+
+/// Read every frame of a classic pcap file, in either byte order and with
+/// micro- or nanosecond timestamps. A truncated last record is ignored.
+pub fn read_pcap<P: AsRef<Path>>(path: P) -> Result<Vec<PcapRecord>> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    if bytes.len() < 24 {
+        bail!("{:?} is not a pcap file", path);
+    }
+
+    let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let (big_endian, nanos) = match magic {
+        PCAP_MAGIC => (false, false),
+        PCAP_MAGIC_NANOS => (false, true),
+        m if m.swap_bytes() == PCAP_MAGIC => (true, false),
+        m if m.swap_bytes() == PCAP_MAGIC_NANOS => (true, true),
+        _ => bail!("{:?} is not a classic pcap file (pcapng input isn't supported)", path),
+    };
+    let read_u32 = |at: usize| {
+        let word = [bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]];
+        if big_endian {
+            u32::from_be_bytes(word)
+        } else {
+            u32::from_le_bytes(word)
+        }
+    };
+
+    let mut records = Vec::new();
+    let mut pos = 24;
+    while pos + 16 <= bytes.len() {
+        let secs = read_u32(pos) as i64;
+        let fraction = read_u32(pos + 4);
+        let caplen = read_u32(pos + 8) as usize;
+        pos += 16;
+        if pos + caplen > bytes.len() {
+            break;
+        }
+        records.push(PcapRecord {
+            secs,
+            micros: if nanos { fraction / 1000 } else { fraction },
+            data: bytes[pos..pos + caplen].to_vec(),
+        });
+        pos += caplen;
+    }
+    Ok(records)
+}
+
+/// Writes a pcapng file with one radiotap interface, so frames can carry
+/// comments
+pub struct PcapngWriter {
+    file: BufWriter<File>,
+}
+
+impl PcapngWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).with_context(|| format!("Failed to create {:?}", path))?;
+        let mut writer = PcapngWriter { file: BufWriter::new(file) };
+
+        let mut section = Vec::new();
+        section.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+        section.extend_from_slice(&1u16.to_le_bytes());
+        section.extend_from_slice(&0u16.to_le_bytes());
+        // Section length not given
+        section.extend_from_slice(&(-1i64).to_le_bytes());
+        push_option(&mut section, OPT_SHB_USERAPPL, format!("prowl {}", env!("CARGO_PKG_VERSION")).as_bytes());
+        push_option(&mut section, OPT_END, &[]);
+        writer.write_block(PCAPNG_SECTION_HEADER, &section)?;
+
+        // Default timestamp resolution is microseconds
+        let mut interface = Vec::new();
+        interface.extend_from_slice(&(LINKTYPE_IEEE802_11_RADIOTAP as u16).to_le_bytes());
+        interface.extend_from_slice(&0u16.to_le_bytes());
+        interface.extend_from_slice(&SNAPLEN.to_le_bytes());
+        writer.write_block(PCAPNG_INTERFACE_DESCRIPTION, &interface)?;
+
+        Ok(writer)
+    }
+
+    /// Append one frame captured at `secs`.`micros`, with an optional comment
+    pub fn write_frame(&mut self, secs: i64, micros: u32, data: &[u8], comment: Option<&str>) -> Result<()> {
+        let caplen = data.len().min(SNAPLEN as usize);
+        let ts = secs as u64 * 1_000_000 + micros as u64;
+
+        let mut packet = Vec::with_capacity(20 + caplen + 8);
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+        packet.extend_from_slice(&(ts as u32).to_le_bytes());
+        packet.extend_from_slice(&(caplen as u32).to_le_bytes());
+        packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&data[..caplen]);
+        pad_to_word(&mut packet);
+        if let Some(comment) = comment {
+            push_option(&mut packet, OPT_COMMENT, comment.as_bytes());
+            push_option(&mut packet, OPT_END, &[]);
+        }
+        self.write_block(PCAPNG_ENHANCED_PACKET, &packet)
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<()> {
+        let total = (12 + body.len()) as u32;
+        self.file.write_all(&block_type.to_le_bytes())?;
+        self.file.write_all(&total.to_le_bytes())?;
+        self.file.write_all(body)?;
+        self.file.write_all(&total.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+fn push_option(buf: &mut Vec<u8>, code: u16, value: &[u8]) {
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    buf.extend_from_slice(value);
+    pad_to_word(buf);
+}
+
+fn pad_to_word(buf: &mut Vec<u8>) {
+    while buf.len() % 4 != 0 {
+        buf.push(0);
+    }
+}
+
+/// When to move on to the next file of a rotating series
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rotation {
+    pub max_bytes: Option<u64>,
+    pub max_secs: Option<i64>,
+}
+
+impl Rotation {
+    pub fn is_enabled(&self) -> bool {
+        self.max_bytes.is_some() || self.max_secs.is_some()
+    }
+}
+
+/// A pcap output that is either a single file or, with rotation enabled, a
+/// series named `<stem>-YYYYMMDD-HHMM.pcap` next to the configured path
+pub struct PcapOutput {
+    base: PathBuf,
+    rotation: Rotation,
+    /// Open file, its path and the timestamp of its first frame
+    current: Option<(PcapWriter, PathBuf, i64)>,
+}
+
+impl PcapOutput {
+    pub fn open<P: AsRef<Path>>(path: P, rotation: Rotation) -> Result<Self> {
+        let base = path.as_ref().to_path_buf();
+        let current = if rotation.is_enabled() {
+            // The first file is named after the first frame's timestamp
+            None
+        } else {
+            Some((PcapWriter::open(&base)?, base.clone(), 0))
+        };
+        Ok(PcapOutput { base, rotation, current })
+    }
+
+    /// Append one frame, starting a new file first if the current one is full
+    /// or too old
+    pub fn write_frame(&mut self, secs: i64, micros: u32, data: &[u8]) -> Result<()> {
+        let due = match &self.current {
+            Some((writer, _, started)) => {
+                self.rotation.max_bytes.is_some_and(|max| writer.size_bytes() >= max)
+                    || self.rotation.max_secs.is_some_and(|max| secs - started >= max)
+            }
+            None => true,
+        };
+        if due {
+            let path = self.next_path(secs);
+            self.current = Some((PcapWriter::open(&path)?, path, secs));
+        }
+
+        match self.current.as_mut() {
+            Some((writer, _, _)) => writer.write_frame(secs, micros, data),
+            None => Ok(()),
+        }
+    }
+
+    /// Path of the file in use, if one is open
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current.as_ref().map(|(_, path, _)| path.as_path())
+    }
+
+    /// Files written for `path`: the file itself and any rotated ones,
+    /// oldest first
+    pub fn series_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
+        let base = path.as_ref();
+        let stem = base
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "capture".to_string());
+        let prefix = format!("{}-", stem);
+        let dir = match base.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let mut rotated: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        let name = p.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                        name.starts_with(&prefix) && name.ends_with(".pcap")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // Timestamped names sort in time order
+        rotated.sort();
+
+        let mut files = Vec::new();
+        if base.exists() {
+            files.push(base.to_path_buf());
+        }
+        files.extend(rotated);
+        files
+    }
+
+    /// First name in the series for `secs` that isn't taken yet, so
+    /// size-based rotation within one minute never appends to a full file
+    fn next_path(&self, secs: i64) -> PathBuf {
+        (0..)
+            .map(|n| self.series_path(secs, n))
+            .find(|path| !path.exists())
+            .unwrap_or_else(|| self.series_path(secs, 0))
+    }
+
+    fn series_path(&self, secs: i64, n: u32) -> PathBuf {
+        let stem = self
+            .base
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "capture".to_string());
+        let stamp = Utc
+            .timestamp_opt(secs, 0)
+            .single()
+            .map(|dt| dt.format("%Y%m%d-%H%M").to_string())
+            .unwrap_or_else(|| secs.to_string());
+        let name = if n == 0 {
+            format!("{}-{}.pcap", stem, stamp)
+        } else {
+            format!("{}-{}-{}.pcap", stem, stamp, n)
+        };
+        self.base.with_file_name(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_written_once() {
+        let path = std::env::temp_dir().join(format!("prowl-dump-{}.pcap", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut writer = PcapWriter::open(&path).unwrap();
+        writer.write_frame(1_700_000_000, 250, &[1, 2, 3]).unwrap();
+        drop(writer);
+        let mut writer = PcapWriter::open(&path).unwrap();
+        writer.write_frame(1_700_000_001, 0, &[4]).unwrap();
+        drop(writer);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 24 + (16 + 3) + (16 + 1));
+        assert_eq!(&bytes[..4], &PCAP_MAGIC.to_le_bytes());
+        assert_eq!(&bytes[20..24], &127u32.to_le_bytes());
+        assert_eq!(&bytes[24..28], &1_700_000_000u32.to_le_bytes());
+        assert_eq!(&bytes[40..43], &[1, 2, 3]);
+
+        let records = read_pcap(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0], PcapRecord { secs: 1_700_000_000, micros: 250, data: vec![1, 2, 3] });
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_pcapng_frame_comment() {
+        let path = std::env::temp_dir().join(format!("prowl-evidence-{}.pcapng", std::process::id()));
+        let mut writer = PcapngWriter::create(&path).unwrap();
+        writer.write_frame(1_700_000_000, 5, &[0xaa; 5], Some("device AA")).unwrap();
+        writer.write_frame(1_700_000_001, 0, &[0xbb; 4], None).unwrap();
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let block = |at: usize| {
+            let kind = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+            let len = u32::from_le_bytes(bytes[at + 4..at + 8].try_into().unwrap()) as usize;
+            assert_eq!(&bytes[at + 4..at + 8], &bytes[at + len - 4..at + len]);
+            (kind, len)
+        };
+
+        let (kind, shb_len) = block(0);
+        assert_eq!(kind, PCAPNG_SECTION_HEADER);
+        let (kind, idb_len) = block(shb_len);
+        assert_eq!(kind, PCAPNG_INTERFACE_DESCRIPTION);
+        let first = shb_len + idb_len;
+        let (kind, epb_len) = block(first);
+        assert_eq!(kind, PCAPNG_ENHANCED_PACKET);
+        // 5 data bytes pad to 8, then the comment option pads to 4 + 12
+        assert_eq!(epb_len, 12 + 20 + 8 + 4 + 12 + 4);
+        let ts = ((u32::from_le_bytes(bytes[first + 12..first + 16].try_into().unwrap()) as u64) << 32)
+            | u32::from_le_bytes(bytes[first + 16..first + 20].try_into().unwrap()) as u64;
+        assert_eq!(ts, 1_700_000_000_000_005);
+        assert_eq!(&bytes[first + 40..first + 49], b"device AA");
+        let (_, plain_len) = block(first + epb_len);
+        assert_eq!(plain_len, 12 + 20 + 4);
+        assert_eq!(first + epb_len + plain_len, bytes.len());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rotation_by_size_and_time() {
+        let dir = std::env::temp_dir().join(format!("prowl-rotate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let rotation = Rotation {
+            max_bytes: Some(24 + 2 * (16 + 10)),
+            max_secs: Some(600),
+        };
+        let mut output = PcapOutput::open(dir.join("capture.pcap"), rotation).unwrap();
+        // 2023-11-14 22:13:20 UTC
+        let t = 1_700_000_000;
+        assert!(output.current_path().is_none());
+        for _ in 0..3 {
+            output.write_frame(t, 0, &[0; 10]).unwrap();
+        }
+        // The third frame didn't fit, so it went to a second file that minute
+        assert_eq!(output.current_path().unwrap(), dir.join("capture-20231114-2213-1.pcap"));
+        output.write_frame(t + 600, 0, &[0; 10]).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["capture-20231114-2213-1.pcap", "capture-20231114-2213.pcap", "capture-20231114-2223.pcap"]
+        );
+        assert!(!dir.join("capture.pcap").exists());
+        assert_eq!(PcapOutput::series_files(dir.join("capture.pcap")).len(), 3);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,262 @@
+//! Battery awareness for portable sensors.
+//!
+//! Laptops and most UPS HATs for single-board computers (PiSugar, Waveshare
+//! UPS, Geekworm X-series with their kernel drivers) report charge through
+//! the kernel's power_supply class, so a reading is two sysfs files. A
+//! monitor thread publishes the level for the status line and, when
+//! configured, parks the hopper on one channel as the battery runs low and
+//! stops capture cleanly before it dies, so the last database transaction
+//! is committed rather than cut off mid-write.
+
+use crate::config::{ChannelEntry, PowerConfig};
+use crate::database::Database;
+use log::{error, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Event type recorded in the events table when a low battery stops capture
+pub const EVENT_BATTERY_SHUTDOWN: &str = "battery_shutdown";
+
+/// Value of a shared battery level before the first reading, or when there
+/// is no battery
+pub const BATTERY_UNKNOWN: u8 = u8::MAX;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Points above the low-power threshold the battery must climb back to
+/// before full hopping resumes, so it doesn't flap at the boundary
+const LOW_POWER_HYSTERESIS: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    Unknown,
+}
+
+impl PowerState {
+    fn parse(status: &str) -> Self {
+        match status.trim() {
+            "Charging" => PowerState::Charging,
+            "Discharging" => PowerState::Discharging,
+            "Full" => PowerState::Full,
+            "Not charging" => PowerState::NotCharging,
+            _ => PowerState::Unknown,
+        }
+    }
+
+    /// Running off the battery; thresholds only apply then
+    pub fn on_battery(&self) -> bool {
+        *self == PowerState::Discharging
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatteryReading {
+    /// power_supply name, e.g. BAT0
+    pub name: String,
+    pub percent: u8,
+    pub state: PowerState,
+}
+
+impl BatteryReading {
+    pub fn describe(&self) -> String {
+        let state = match self.state {
+            PowerState::Charging => ", charging",
+            PowerState::Discharging => ", on battery",
+            PowerState::Full => ", full",
+            PowerState::NotCharging | PowerState::Unknown => "",
+        };
+        format!("{} {}%{}", self.name, self.percent, state)
+    }
+}
+
+/// Read the configured supply, or the first battery the kernel reports
+pub fn read_battery(supply: Option<&str>) -> Option<BatteryReading> {
+    read_battery_in(Path::new("/sys/class/power_supply"), supply)
+}
+
+fn read_battery_in(root: &Path, supply: Option<&str>) -> Option<BatteryReading> {
+    if let Some(supply) = supply {
+        let dir = if supply.contains('/') {
+            PathBuf::from(supply)
+        } else {
+            root.join(supply)
+        };
+        return read_supply(&dir);
+    }
+
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(root).ok()?.flatten().map(|e| e.path()).collect();
+    dirs.sort();
+    dirs.into_iter()
+        .filter(|dir| {
+            let kind = std::fs::read_to_string(dir.join("type")).unwrap_or_default();
+            matches!(kind.trim(), "Battery" | "UPS")
+        })
+        .find_map(|dir| read_supply(&dir))
+}
+
+fn read_supply(dir: &Path) -> Option<BatteryReading> {
+    let percent: u8 = std::fs::read_to_string(dir.join("capacity")).ok()?.trim().parse().ok()?;
+    let state = std::fs::read_to_string(dir.join("status"))
+        .map(|s| PowerState::parse(&s))
+        .unwrap_or(PowerState::Unknown);
+    Some(BatteryReading {
+        name: dir.file_name()?.to_string_lossy().into_owned(),
+        percent: percent.min(100),
+        state,
+    })
+}
+
+/// What the monitor should do about a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+    Normal,
+    LowPower,
+    Shutdown,
+}
+
+/// Action for `reading` given whether low-power mode is already on
+pub fn power_action(config: &PowerConfig, reading: &BatteryReading, low_power: bool) -> PowerAction {
+    if !reading.state.on_battery() {
+        return PowerAction::Normal;
+    }
+    if config.shutdown_percent.is_some_and(|p| reading.percent <= p) {
+        return PowerAction::Shutdown;
+    }
+    match config.low_power_percent {
+        Some(p) if reading.percent <= p => PowerAction::LowPower,
+        Some(p) if low_power && reading.percent < p.saturating_add(LOW_POWER_HYSTERESIS) => PowerAction::LowPower,
+        _ => PowerAction::Normal,
+    }
+}
+
+/// Poll the battery until `running` clears, publishing the level into
+/// `level`. In low-power mode `channel_updates` (when given) is parked on
+/// the first of `channels`; at the shutdown threshold `running` is cleared
+/// so capture winds down and flushes. Returns `None` without a battery.
+pub fn spawn_power_monitor(
+    config: &PowerConfig,
+    database: &str,
+    running: Arc<AtomicBool>,
+    level: Arc<AtomicU8>,
+    channel_updates: Option<(watch::Sender<Vec<ChannelEntry>>, Vec<ChannelEntry>)>,
+) -> Option<thread::JoinHandle<()>> {
+    let first = read_battery(config.supply.as_deref())?;
+    info!("Battery: {}", first.describe());
+    level.store(first.percent, Ordering::Relaxed);
+
+    let config = config.clone();
+    let database = database.to_string();
+    Some(thread::spawn(move || {
+        let mut low_power = false;
+        let mut reading = Some(first);
+
+        while running.load(Ordering::SeqCst) {
+            if let Some(battery) = &reading {
+                level.store(battery.percent, Ordering::Relaxed);
+                match power_action(&config, battery, low_power) {
+                    PowerAction::Shutdown => {
+                        stop_for_battery(&database, battery);
+                        running.store(false, Ordering::SeqCst);
+                        break;
+                    }
+                    PowerAction::LowPower if !low_power => {
+                        low_power = true;
+                        warn!("Battery low ({}), entering low-power mode", battery.describe());
+                        if let Some((updates, channels)) = &channel_updates {
+                            let _ = updates.send(channels.iter().take(1).copied().collect());
+                        }
+                    }
+                    PowerAction::Normal if low_power => {
+                        low_power = false;
+                        info!("Battery recovered ({}), leaving low-power mode", battery.describe());
+                        if let Some((updates, channels)) = &channel_updates {
+                            let _ = updates.send(channels.clone());
+                        }
+                    }
+                    _ => {}
+                }
+            } else {
+                level.store(BATTERY_UNKNOWN, Ordering::Relaxed);
+            }
+
+            // Sleep in short steps so shutdown isn't held up by the poll
+            let mut slept = Duration::ZERO;
+            while slept < POLL_INTERVAL && running.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_secs(1));
+                slept += Duration::from_secs(1);
+            }
+            reading = read_battery(config.supply.as_deref());
+        }
+    }))
+}
+
+fn stop_for_battery(database: &str, battery: &BatteryReading) {
+    let message = format!("Battery at {}, stopping capture", battery.describe());
+    warn!("{}", message);
+    match Database::open(database) {
+        Ok(db) => {
+            let now = chrono::Utc::now().timestamp();
+            if let Err(e) = db.insert_event(now, EVENT_BATTERY_SHUTDOWN, &message, None) {
+                error!("Failed to record event: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to open database to record battery shutdown: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(root: &Path, name: &str, kind: &str, capacity: &str, status: &str) {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("type"), kind).unwrap();
+        std::fs::write(dir.join("capacity"), capacity).unwrap();
+        std::fs::write(dir.join("status"), status).unwrap();
+    }
+
+    #[test]
+    fn test_read_battery_skips_mains() {
+        let root = std::env::temp_dir().join(format!("prowl-power-{}", std::process::id()));
+        supply(&root, "AC", "Mains", "0\n", "Unknown\n");
+        supply(&root, "BAT0", "Battery", "42\n", "Discharging\n");
+
+        let reading = read_battery_in(&root, None).unwrap();
+        assert_eq!(reading.name, "BAT0");
+        assert_eq!(reading.percent, 42);
+        assert_eq!(reading.state, PowerState::Discharging);
+        assert!(read_battery_in(&root, Some("BAT1")).is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_power_action_thresholds() {
+        let config = PowerConfig {
+            supply: None,
+            low_power_percent: Some(20),
+            shutdown_percent: Some(5),
+        };
+        let reading = |percent, state| BatteryReading {
+            name: "BAT0".to_string(),
+            percent,
+            state,
+        };
+
+        assert_eq!(power_action(&config, &reading(50, PowerState::Discharging), false), PowerAction::Normal);
+        assert_eq!(power_action(&config, &reading(20, PowerState::Discharging), false), PowerAction::LowPower);
+        // Stays in low-power mode until the battery is clearly back up
+        assert_eq!(power_action(&config, &reading(23, PowerState::Discharging), true), PowerAction::LowPower);
+        assert_eq!(power_action(&config, &reading(25, PowerState::Discharging), true), PowerAction::Normal);
+        assert_eq!(power_action(&config, &reading(5, PowerState::Discharging), true), PowerAction::Shutdown);
+        assert_eq!(power_action(&config, &reading(3, PowerState::Charging), false), PowerAction::Normal);
+    }
+}
@@ -0,0 +1,219 @@
+//! Aggregate statistics safe to publish.
+//!
+//! Only distinct-device counts are released, so one device changes any
+//! released number by at most one. Each count gets Laplace noise scaled to
+//! `1 / epsilon`, and groups whose noisy count falls below the minimum group
+//! size are suppressed. Small vendor groups are folded into "Other" before
+//! release.
+//!
+//! `epsilon` is the budget per released count, not per series: a device
+//! counts towards every occupancy bucket it was present in and towards a
+//! vendor group, so a release of n buckets spends up to n times epsilon on
+//! it. The noise makes a single device's comings and goings hard to read
+//! off, not impossible.
+//!
+//! The noise for a count is drawn from a secret key kept in the database,
+//! the period and the group, so releasing the same period again gives the
+//! same numbers rather than fresh noise that averaging could cancel out.
+
+use crate::config::PrivacyConfig;
+use crate::database::Database;
+use crate::intel::sha1;
+use crate::occupancy::OccupancySample;
+use anyhow::Result;
+use serde::Serialize;
+
+/// One released occupancy bucket; `None` means suppressed
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicBucket {
+    pub bucket_start: i64,
+    pub devices: Option<u64>,
+    pub people: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicVendorCount {
+    pub vendor: String,
+    pub devices: u64,
+}
+
+/// Everything released for one period, with the parameters used
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicStats {
+    pub generated_at: String,
+    pub start: i64,
+    pub end: i64,
+    pub min_group_size: usize,
+    pub epsilon: f64,
+    pub occupancy: Vec<PublicBucket>,
+    pub vendors: Vec<PublicVendorCount>,
+}
+
+/// `schema_info` key the secret noise key is stored under
+const NOISE_KEY: &str = "privacy_noise_key";
+
+/// The database's secret noise key, created on first use
+pub fn noise_key(db: &Database) -> Result<String> {
+    db.stored_key(NOISE_KEY, || format!("{:016x}{:016x}", entropy_seed(), entropy_seed()))
+}
+
+/// Applies noise and suppression to the counts of one period
+pub struct Anonymizer {
+    min_group_size: usize,
+    scale: f64,
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+impl Anonymizer {
+    /// Noise for `start`..`end` drawn from `key`
+    pub fn new(config: &PrivacyConfig, key: &str, start: i64, end: i64) -> Self {
+        Anonymizer {
+            min_group_size: config.min_group_size,
+            scale: 1.0 / config.epsilon.max(f64::MIN_POSITIVE),
+            key: key.to_string(),
+            start,
+            end,
+        }
+    }
+
+    /// The same for the same key, period and group
+    fn laplace(&self, group: &str) -> f64 {
+        let hash = sha1(format!("{}:{}:{}:{}", self.key, self.start, self.end, group).as_bytes());
+        let mut bits = [0u8; 8];
+        bits.copy_from_slice(&hash[..8]);
+        let u = (u64::from_le_bytes(bits) >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+        -self.scale * u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln()
+    }
+
+    /// Noisy, rounded count for `group`, or `None` if it falls below the
+    /// minimum group size
+    pub fn release(&self, group: &str, count: usize) -> Option<u64> {
+        let noisy = (count as f64 + self.laplace(group)).round().max(0.0) as u64;
+        (noisy as usize >= self.min_group_size.max(1)).then_some(noisy)
+    }
+}
+
+fn entropy_seed() -> u64 {
+    let mut bytes = [0u8; 8];
+    if let Ok(mut f) = std::fs::File::open("/dev/urandom") {
+        if std::io::Read::read_exact(&mut f, &mut bytes).is_ok() {
+            return u64::from_le_bytes(bytes);
+        }
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+/// Release an occupancy series and vendor breakdown for `start`..`end`
+pub fn public_stats(
+    db: &Database,
+    samples: &[OccupancySample],
+    start: i64,
+    end: i64,
+    devices_per_person: f64,
+    anonymizer: &Anonymizer,
+    config: &PrivacyConfig,
+) -> Result<PublicStats> {
+    let ratio = if devices_per_person > 0.0 { devices_per_person } else { 1.0 };
+    let occupancy = samples
+        .iter()
+        .map(|sample| {
+            let group = format!("occupancy:{}", sample.bucket_start);
+            let devices = anonymizer.release(&group, sample.estimate.devices());
+            PublicBucket {
+                bucket_start: sample.bucket_start,
+                devices,
+                people: devices.map(|d| (d as f64 / ratio * 10.0).round() / 10.0),
+            }
+        })
+        .collect();
+
+    // Fold vendors below the group size into "Other" before adding noise
+    let mut vendors = Vec::new();
+    let mut other = 0;
+    for (vendor, count) in db.get_vendor_counts(start, end)? {
+        if count < config.min_group_size || vendor == "Unknown" {
+            other += count;
+        } else {
+            vendors.push((vendor, count));
+        }
+    }
+    vendors.push(("Other".to_string(), other));
+    let vendors = vendors
+        .into_iter()
+        .filter_map(|(vendor, count)| {
+            anonymizer
+                .release(&format!("vendor:{}", vendor), count)
+                .map(|devices| PublicVendorCount { vendor, devices })
+        })
+        .collect();
+
+    Ok(PublicStats {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        start,
+        end,
+        min_group_size: config.min_group_size,
+        epsilon: config.epsilon,
+        occupancy,
+        vendors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_counts_suppressed() {
+        let config = PrivacyConfig {
+            min_group_size: 5,
+            epsilon: 100.0,
+        };
+        let anonymizer = Anonymizer::new(&config, "key", 0, 3600);
+        assert_eq!(anonymizer.release("a", 0), None);
+        assert_eq!(anonymizer.release("b", 2), None);
+        assert_eq!(anonymizer.release("c", 40), Some(40));
+    }
+
+    #[test]
+    fn test_noise_is_unbiased() {
+        let config = PrivacyConfig {
+            min_group_size: 0,
+            epsilon: 0.5,
+        };
+        let anonymizer = Anonymizer::new(&config, "key", 0, 3600);
+        let n = 5000;
+        let released: Vec<u64> = (0..n).filter_map(|i| anonymizer.release(&i.to_string(), 100)).collect();
+        assert_eq!(released.len(), n);
+        let mean = released.iter().sum::<u64>() as f64 / n as f64;
+        assert!((mean - 100.0).abs() < 0.5, "mean {}", mean);
+        assert!(released.iter().any(|&c| c != 100));
+    }
+
+    #[test]
+    fn test_noise_repeats_for_the_same_period() {
+        let config = PrivacyConfig {
+            min_group_size: 0,
+            epsilon: 0.1,
+        };
+        let first = Anonymizer::new(&config, "key", 0, 3600);
+        let again = Anonymizer::new(&config, "key", 0, 3600);
+        let groups: Vec<String> = (0..20).map(|i| format!("occupancy:{}", i * 300)).collect();
+        let release = |a: &Anonymizer| groups.iter().map(|g| a.release(g, 100)).collect::<Vec<_>>();
+        assert_eq!(release(&first), release(&again));
+
+        // Another period or key draws other noise
+        assert_ne!(release(&first), release(&Anonymizer::new(&config, "key", 3600, 7200)));
+        assert_ne!(release(&first), release(&Anonymizer::new(&config, "other", 0, 3600)));
+
+        let db = Database::open_in_memory().unwrap();
+        let key = noise_key(&db).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(noise_key(&db).unwrap(), key);
+    }
+}
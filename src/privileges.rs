@@ -0,0 +1,317 @@
+//! Capture privileges: checked up front, given up once the handle is open.
+//!
+//! Live capture needs CAP_NET_RAW to open the capture socket and
+//! CAP_NET_ADMIN to change channels and interface modes. Both are checked
+//! from `/proc/self/status` before anything is opened, so a missing one is
+//! reported with how to grant it instead of as a pcap error. Running as
+//! root inside a container without them fails the same way. The TUI doesn't
+//! fail: it opens read-only on the database, as `prowl tui --browse` does.
+//!
+//! Once the handle is open, a capture started as root switches to an
+//! unprivileged user: `capture.run_as`, or whoever ran `sudo`. glibc applies
+//! the uid change to every thread, but Linux keeps capabilities per thread,
+//! so the drop runs on the channel hopper's thread, which alone holds on to
+//! CAP_NET_ADMIN. The open capture socket keeps working without privileges.
+
+use crate::config::CaptureConfig;
+use crate::instance::InstanceLock;
+use crate::validation::ValidationError;
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub const CAP_NET_ADMIN: u32 = 12;
+pub const CAP_NET_RAW: u32 = 13;
+
+/// Capabilities live capture can't do without
+const CAPTURE_CAPABILITIES: &[u32] = &[CAP_NET_RAW, CAP_NET_ADMIN];
+
+pub fn capability_name(cap: u32) -> &'static str {
+    match cap {
+        CAP_NET_ADMIN => "CAP_NET_ADMIN",
+        CAP_NET_RAW => "CAP_NET_RAW",
+        _ => "unknown capability",
+    }
+}
+
+/// Effective capability mask from the `CapEff:` line of /proc/<pid>/status
+pub fn effective_capabilities(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+}
+
+/// Names of the capabilities in `needed` that `effective` lacks
+pub fn missing_capabilities(effective: u64, needed: &[u32]) -> Vec<&'static str> {
+    needed
+        .iter()
+        .filter(|cap| effective & (1 << **cap) == 0)
+        .map(|cap| capability_name(*cap))
+        .collect()
+}
+
+/// Fail before opening anything when live capture lacks the capabilities it
+/// needs. Simulated and remote capture need none.
+pub fn check_capture_privileges(config: &CaptureConfig) -> Result<(), ValidationError> {
+    if !config.backend.has_radio() {
+        return Ok(());
+    }
+    let effective = match current_capabilities() {
+        Some(effective) => effective,
+        // Nothing to check against; let opening the handle decide
+        None => return Ok(()),
+    };
+    let missing = missing_capabilities(effective, CAPTURE_CAPABILITIES);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Capture needs {} but this process lacks {}.\n\n\
+        Run prowl as root (sudo prowl ...), or grant the capabilities once:\n  \
+        sudo setcap cap_net_raw,cap_net_admin=eip \"$(command -v prowl)\"\n\
+        In a container, add them with --cap-add=NET_RAW --cap-add=NET_ADMIN.\n\n\
+        Without them, `prowl tui` still browses what was captured, and analyze,\n\
+        report, list and export work as usual.",
+        CAPTURE_CAPABILITIES.iter().map(|cap| capability_name(*cap)).collect::<Vec<_>>().join(" and "),
+        missing.join(" and ")
+    );
+    Err(ValidationError::MissingPrivileges { missing, message })
+}
+
+#[cfg(target_os = "linux")]
+fn current_capabilities() -> Option<u64> {
+    effective_capabilities(&std::fs::read_to_string("/proc/self/status").ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_capabilities() -> Option<u64> {
+    None
+}
+
+/// The unprivileged user capture switches to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// The user who ran `sudo`, from its environment variables
+pub fn sudo_user(uid: Option<&str>, gid: Option<&str>, name: Option<&str>) -> Option<TargetUser> {
+    let uid: u32 = uid?.parse().ok()?;
+    let gid: u32 = gid?.parse().ok()?;
+    // `sudo` from a root shell leaves nobody to switch to
+    (uid != 0).then(|| TargetUser {
+        name: name.unwrap_or("").to_string(),
+        uid,
+        gid,
+    })
+}
+
+/// Switch from root to `user` once the capture handle is open
+#[derive(Debug)]
+pub struct PrivilegeDrop {
+    pub user: TargetUser,
+    /// Files created as root that the user keeps writing to
+    files: Vec<PathBuf>,
+}
+
+impl PrivilegeDrop {
+    /// What to drop to for `config`, or `None` when capture isn't running as
+    /// root, the drop is turned off, or there is no user to switch to
+    pub fn plan(config: &CaptureConfig) -> Result<Option<PrivilegeDrop>> {
+        if !config.drop_privileges || !config.backend.has_radio() || !is_root() {
+            return Ok(None);
+        }
+        let user = match &config.run_as {
+            Some(name) => lookup_user(name)?,
+            None => {
+                let (uid, gid, name) = (env("SUDO_UID"), env("SUDO_GID"), env("SUDO_USER"));
+                match sudo_user(uid.as_deref(), gid.as_deref(), name.as_deref()) {
+                    Some(user) => user,
+                    None => {
+                        log::warn!("Capture keeps running as root; set capture.run_as to drop privileges");
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+        if user.uid == 0 {
+            return Ok(None);
+        }
+
+        let database = &config.database;
+        let mut files: Vec<PathBuf> = ["", "-wal", "-shm", "-journal"]
+            .iter()
+            .map(|suffix| PathBuf::from(format!("{}{}", database, suffix)))
+            .collect();
+        files.push(InstanceLock::lock_path(database));
+        Ok(Some(PrivilegeDrop { user, files }))
+    }
+
+    /// Hand the database files to the user and switch to them, keeping
+    /// `keep` on the calling thread only
+    pub fn apply(&self, keep: &[u32]) -> Result<()> {
+        for file in self.files.iter().filter(|f| f.exists()) {
+            chown(file, &self.user)?;
+        }
+        switch_user(&self.user, keep)?;
+        log::info!(
+            "Dropped root privileges, now running as {} (uid {})",
+            if self.user.name.is_empty() { "sudo user" } else { &self.user.name },
+            self.user.uid
+        );
+        Ok(())
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+#[cfg(target_os = "linux")]
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_root() -> bool {
+    false
+}
+
+/// Look up `name` as `capture.run_as`
+#[cfg(target_os = "linux")]
+pub fn lookup_user(name: &str) -> Result<TargetUser> {
+    use crate::exit::{self, ExitError};
+    use std::ffi::CString;
+
+    let unknown = || ExitError::new(exit::USAGE, format!("Unknown user in capture.run_as: {}", name));
+    let cname = CString::new(name).map_err(|_| unknown())?;
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut found = std::ptr::null_mut();
+    let rc = unsafe { libc::getpwnam_r(cname.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut found) };
+    if rc != 0 || found.is_null() {
+        return Err(unknown().into());
+    }
+    Ok(TargetUser {
+        name: name.to_string(),
+        uid: pwd.pw_uid,
+        gid: pwd.pw_gid,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn lookup_user(name: &str) -> Result<TargetUser> {
+    anyhow::bail!("capture.run_as ({}) is only supported on Linux", name)
+}
+
+#[cfg(target_os = "linux")]
+fn chown(path: &std::path::Path, user: &TargetUser) -> Result<()> {
+    use anyhow::Context;
+
+    std::os::unix::fs::chown(path, Some(user.uid), Some(user.gid))
+        .with_context(|| format!("Failed to hand {} to uid {}", path.display(), user.uid))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn chown(_path: &std::path::Path, _user: &TargetUser) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn switch_user(user: &TargetUser, keep: &[u32]) -> Result<()> {
+    use anyhow::{bail, Context};
+    use std::io::Error;
+
+    // Without KEEPCAPS this thread would lose its capabilities with root
+    if !keep.is_empty() && unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 1, 0, 0, 0) } != 0 {
+        return Err(Error::last_os_error()).context("Failed to keep capabilities across the user switch");
+    }
+    let groups = [user.gid];
+    if unsafe { libc::setgroups(groups.len(), groups.as_ptr()) } != 0 {
+        return Err(Error::last_os_error()).context("Failed to drop supplementary groups");
+    }
+    if unsafe { libc::setgid(user.gid) } != 0 {
+        return Err(Error::last_os_error()).with_context(|| format!("Failed to switch to gid {}", user.gid));
+    }
+    if unsafe { libc::setuid(user.uid) } != 0 {
+        return Err(Error::last_os_error()).with_context(|| format!("Failed to switch to uid {}", user.uid));
+    }
+    if !keep.is_empty() {
+        set_thread_capabilities(keep)?;
+        unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 0, 0, 0, 0) };
+    }
+    if unsafe { libc::setuid(0) } == 0 {
+        bail!("Root could be regained after dropping privileges");
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn switch_user(_user: &TargetUser, _keep: &[u32]) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: i32,
+}
+
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+/// Limit the calling thread to exactly `keep`, effective and permitted
+#[cfg(target_os = "linux")]
+fn set_thread_capabilities(keep: &[u32]) -> Result<()> {
+    use anyhow::Context;
+
+    const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+    let mask = keep.iter().fold(0u64, |mask, cap| mask | 1 << cap);
+    let header = CapHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    let data = [mask as u32, (mask >> 32) as u32].map(|bits| CapData {
+        effective: bits,
+        permitted: bits,
+        inheritable: 0,
+    });
+    if unsafe { libc::syscall(libc::SYS_capset, &header, data.as_ptr()) } != 0 {
+        let names = keep.iter().map(|cap| capability_name(*cap)).collect::<Vec<_>>().join(", ");
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("Failed to keep {}", names));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_capabilities() {
+        let status = "Name:\tprowl\nCapInh:\t0000000000000000\nCapPrm:\t0000000000002000\n\
+            CapEff:\t0000000000002000\nCapBnd:\t000001ffffffffff\n";
+        let effective = effective_capabilities(status).unwrap();
+        assert_eq!(missing_capabilities(effective, CAPTURE_CAPABILITIES), vec!["CAP_NET_ADMIN"]);
+        assert!(missing_capabilities(0x1ff_ffff_ffff, CAPTURE_CAPABILITIES).is_empty());
+        assert_eq!(effective_capabilities("Name:\tprowl\n"), None);
+    }
+
+    #[test]
+    fn test_sudo_user() {
+        let user = sudo_user(Some("1000"), Some("1000"), Some("alice")).unwrap();
+        assert_eq!((user.uid, user.gid, user.name.as_str()), (1000, 1000, "alice"));
+        assert_eq!(sudo_user(Some("0"), Some("0"), Some("root")), None);
+        assert_eq!(sudo_user(None, Some("1000"), None), None);
+    }
+}
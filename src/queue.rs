@@ -0,0 +1,343 @@
+//! Bounded queues between the capture loop and its sinks.
+//!
+//! The capture loop must never block on a slow consumer (SQLite flush, TUI
+//! redraw, network sink). Each sink gets a fixed-capacity queue with an
+//! explicit overflow policy, and every dropped item is counted so the loss
+//! is visible instead of silent. `OverflowTracker` turns the running drop
+//! count into overflow episodes a sink can log or store.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// What to do when a queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Discard the incoming item
+    DropNewest,
+    /// Evict the oldest queued item to make room
+    #[default]
+    DropOldest,
+    /// Wait for space (only for sinks that must never lose data)
+    Block,
+}
+
+struct Inner<T> {
+    items: Mutex<VecDeque<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+    dropped: AtomicU64,
+    enqueued: AtomicU64,
+    /// Most items ever queued at once
+    high_water: AtomicUsize,
+    closed: AtomicBool,
+}
+
+/// Multi-producer, multi-consumer bounded queue with drop accounting
+pub struct BoundedQueue<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for BoundedQueue<T> {
+    fn clone(&self) -> Self {
+        BoundedQueue {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> BoundedQueue<T> {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        let capacity = capacity.max(1);
+        BoundedQueue {
+            inner: Arc::new(Inner {
+                items: Mutex::new(VecDeque::with_capacity(capacity)),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+                capacity,
+                policy,
+                dropped: AtomicU64::new(0),
+                enqueued: AtomicU64::new(0),
+                high_water: AtomicUsize::new(0),
+                closed: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Push an item, applying the overflow policy. Returns false if an item
+    /// (this one or an older one) was dropped.
+    pub fn push(&self, item: T) -> bool {
+        let mut items = self.inner.items.lock().unwrap();
+        let mut kept = true;
+
+        if items.len() >= self.inner.capacity {
+            match self.inner.policy {
+                OverflowPolicy::DropNewest => {
+                    self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+                OverflowPolicy::DropOldest => {
+                    items.pop_front();
+                    self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                    kept = false;
+                }
+                OverflowPolicy::Block => {
+                    while items.len() >= self.inner.capacity && !self.is_closed() {
+                        items = self.inner.not_full.wait(items).unwrap();
+                    }
+                    // Closed while waiting: nobody will make room any more
+                    if items.len() >= self.inner.capacity {
+                        self.inner.dropped.fetch_add(1, Ordering::Relaxed);
+                        return false;
+                    }
+                }
+            }
+        }
+
+        items.push_back(item);
+        self.inner.enqueued.fetch_add(1, Ordering::Relaxed);
+        self.inner.high_water.fetch_max(items.len(), Ordering::Relaxed);
+        self.inner.not_empty.notify_one();
+        kept
+    }
+
+    /// Pop the next item, waiting up to `timeout` for one to arrive
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let items = self.inner.items.lock().unwrap();
+        let (mut items, _) = self
+            .inner
+            .not_empty
+            .wait_timeout_while(items, timeout, |q| q.is_empty() && !self.is_closed())
+            .unwrap();
+        let item = items.pop_front();
+        if item.is_some() {
+            self.inner.not_full.notify_one();
+        }
+        item
+    }
+
+    /// Wait up to `timeout` for an item, then keep collecting until `max`
+    /// items are queued or `timeout` has passed since the first one. Returns
+    /// an empty batch if nothing arrived.
+    pub fn pop_batch(&self, max: usize, timeout: Duration) -> Vec<T> {
+        let max = max.max(1);
+        let items = self.inner.items.lock().unwrap();
+        let (items, _) = self
+            .inner
+            .not_empty
+            .wait_timeout_while(items, timeout, |q| q.is_empty() && !self.is_closed())
+            .unwrap();
+        if items.is_empty() {
+            return Vec::new();
+        }
+
+        let deadline = Instant::now() + timeout;
+        let (mut items, _) = self
+            .inner
+            .not_empty
+            .wait_timeout_while(items, deadline.saturating_duration_since(Instant::now()), |q| {
+                q.len() < max && !self.is_closed()
+            })
+            .unwrap();
+        let n = max.min(items.len());
+        let batch: Vec<T> = items.drain(..n).collect();
+        self.inner.not_full.notify_all();
+        batch
+    }
+
+    /// Take up to `max` queued items without waiting
+    pub fn drain(&self, max: usize) -> Vec<T> {
+        let mut items = self.inner.items.lock().unwrap();
+        let n = max.min(items.len());
+        let batch: Vec<T> = items.drain(..n).collect();
+        if !batch.is_empty() {
+            self.inner.not_full.notify_all();
+        }
+        batch
+    }
+
+    /// Wake all waiters; consumers drain what is left and then stop
+    pub fn close(&self) {
+        self.inner.closed.store(true, Ordering::SeqCst);
+        self.inner.not_empty.notify_all();
+        self.inner.not_full.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::SeqCst)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    /// Number of items discarded because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Number of items accepted into the queue
+    pub fn enqueued(&self) -> u64 {
+        self.inner.enqueued.load(Ordering::Relaxed)
+    }
+
+    /// Most items the queue has held at once
+    pub fn high_water(&self) -> usize {
+        self.inner.high_water.load(Ordering::Relaxed)
+    }
+}
+
+/// Items a queue dropped since the previous report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub dropped: u64,
+    /// Drops since the queue was created
+    pub total_dropped: u64,
+    pub high_water: usize,
+    pub capacity: usize,
+}
+
+/// Reports a queue's drops at most once per interval, so a burst that
+/// overflows for minutes is one record per interval rather than one per item
+#[derive(Debug)]
+pub struct OverflowTracker {
+    interval: Duration,
+    reported: u64,
+    last_report: Option<Instant>,
+}
+
+impl OverflowTracker {
+    pub fn new(interval: Duration) -> Self {
+        OverflowTracker {
+            interval,
+            reported: 0,
+            last_report: None,
+        }
+    }
+
+    /// Drops not yet reported, if there are any and the interval has passed
+    pub fn poll<T>(&mut self, queue: &BoundedQueue<T>) -> Option<Overflow> {
+        let due = self
+            .last_report
+            .map(|at| at.elapsed() >= self.interval)
+            .unwrap_or(true);
+        if !due {
+            return None;
+        }
+        self.flush(queue)
+    }
+
+    /// Drops not yet reported, regardless of the interval
+    pub fn flush<T>(&mut self, queue: &BoundedQueue<T>) -> Option<Overflow> {
+        let total = queue.dropped();
+        if total <= self.reported {
+            return None;
+        }
+        let overflow = Overflow {
+            dropped: total - self.reported,
+            total_dropped: total,
+            high_water: queue.high_water(),
+            capacity: queue.capacity(),
+        };
+        self.reported = total;
+        self.last_report = Some(Instant::now());
+        Some(overflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_newest() {
+        let q = BoundedQueue::new(2, OverflowPolicy::DropNewest);
+        assert!(q.push(1));
+        assert!(q.push(2));
+        assert!(!q.push(3));
+        assert_eq!(q.dropped(), 1);
+        assert_eq!(q.drain(10), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let q = BoundedQueue::new(2, OverflowPolicy::DropOldest);
+        q.push(1);
+        q.push(2);
+        assert!(!q.push(3));
+        assert_eq!(q.dropped(), 1);
+        assert_eq!(q.drain(10), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_block_drops_once_closed() {
+        let q = BoundedQueue::new(1, OverflowPolicy::Block);
+        assert!(q.push(1));
+        let producer = {
+            let q = q.clone();
+            std::thread::spawn(move || q.push(2))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        q.close();
+        assert!(!producer.join().unwrap());
+        assert_eq!(q.dropped(), 1);
+        assert_eq!(q.drain(10), vec![1]);
+    }
+
+    #[test]
+    fn test_overflow_tracker_reports_once_per_interval() {
+        let q = BoundedQueue::new(2, OverflowPolicy::DropOldest);
+        let mut tracker = OverflowTracker::new(Duration::from_secs(3600));
+        for i in 0..5 {
+            q.push(i);
+        }
+        assert_eq!(q.high_water(), 2);
+
+        let overflow = tracker.poll(&q).unwrap();
+        assert_eq!(overflow.dropped, 3);
+        assert_eq!(overflow.capacity, 2);
+
+        q.push(5);
+        assert!(tracker.poll(&q).is_none());
+        let rest = tracker.flush(&q).unwrap();
+        assert_eq!(rest.dropped, 1);
+        assert_eq!(rest.total_dropped, 4);
+        assert!(tracker.flush(&q).is_none());
+    }
+
+    #[test]
+    fn test_pop_timeout_empty() {
+        let q: BoundedQueue<u8> = BoundedQueue::new(4, OverflowPolicy::Block);
+        assert_eq!(q.pop_timeout(Duration::from_millis(10)), None);
+        q.push(7);
+        assert_eq!(q.pop_timeout(Duration::from_millis(10)), Some(7));
+    }
+
+    #[test]
+    fn test_pop_batch() {
+        let q = BoundedQueue::new(10, OverflowPolicy::DropOldest);
+        assert!(q.pop_batch(3, Duration::from_millis(10)).is_empty());
+        for i in 0..5 {
+            q.push(i);
+        }
+        assert_eq!(q.pop_batch(3, Duration::from_secs(5)), vec![0, 1, 2]);
+        // Fewer than `max` queued: returns what is there once the timeout passes
+        assert_eq!(q.pop_batch(3, Duration::from_millis(10)), vec![3, 4]);
+        q.push(5);
+        q.close();
+        assert_eq!(q.pop_batch(3, Duration::from_secs(5)), vec![5]);
+    }
+}
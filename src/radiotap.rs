@@ -0,0 +1,395 @@
+//! Radiotap header decoding.
+//!
+//! Monitor-mode interfaces put a radiotap header in front of every frame:
+//! one or more 32-bit presence words, then each announced field in bit
+//! order, aligned to its natural size from the start of the header. Bit 31
+//! of a presence word chains another word. Bit 29 starts the standard field
+//! numbering over for the next word, which is how Linux drivers repeat the
+//! signal and antenna for each receive chain after the combined values. Bit
+//! 30 switches to a vendor namespace, whose data is skipped by the length
+//! in its 6-byte header. A field of unknown size ends the walk, since
+//! nothing after it can be located; what was decoded before it is kept.
+//!
+//! The flags field says whether the frame still carries its FCS and
+//! whether the driver already found it bad. Corrupted frames are dropped
+//! before parsing, since their addresses would otherwise turn into devices
+//! that never existed.
+
+use crate::channels::frequency_channel;
+
+const PRESENT_RADIOTAP_NS: u32 = 1 << 29;
+const PRESENT_VENDOR_NS: u32 = 1 << 30;
+const PRESENT_EXT: u32 = 1 << 31;
+
+const FIELD_TSFT: u32 = 0;
+const FIELD_FLAGS: u32 = 1;
+const FIELD_RATE: u32 = 2;
+const FIELD_CHANNEL: u32 = 3;
+const FIELD_ANTENNA_SIGNAL: u32 = 5;
+const FIELD_ANTENNA_NOISE: u32 = 6;
+const FIELD_ANTENNA: u32 = 11;
+const FIELD_XCHANNEL: u32 = 18;
+const FIELD_MCS: u32 = 19;
+const FIELD_VHT: u32 = 21;
+const FIELD_TIMESTAMP: u32 = 22;
+
+/// Flags field: the frame ends with its 4-byte FCS
+const FLAG_FCS_AT_END: u8 = 0x10;
+/// Flags field: the frame failed its FCS check
+const FLAG_BAD_FCS: u8 = 0x40;
+
+const FCS_LEN: usize = 4;
+
+/// MCS field "known" flag for the MCS index
+const MCS_KNOWN_INDEX: u8 = 0x02;
+
+/// Receive metadata from a frame's radiotap header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RadiotapInfo {
+    /// Combined antenna signal, or the first chain's when that's all there is
+    pub signal_dbm: Option<i32>,
+    pub noise_dbm: Option<i32>,
+    pub frequency_mhz: Option<u16>,
+    /// Channel number for `frequency_mhz`
+    pub channel: Option<u8>,
+    /// Legacy data rate in 500 kbit/s units
+    pub rate_500kbps: Option<u8>,
+    /// HT MCS index, or the VHT MCS of the first user
+    pub mcs: Option<u8>,
+    /// VHT spatial streams of the first user
+    pub nss: Option<u8>,
+    pub antenna: Option<u8>,
+    /// Receive time in microseconds, from the TSFT field or else the
+    /// timestamp field
+    pub timestamp_us: Option<u64>,
+    /// The frame ends with its FCS
+    pub fcs_at_end: bool,
+    /// The driver flagged the frame as failing its FCS check
+    pub bad_fcs: bool,
+    /// Length of the radiotap header; the 802.11 frame follows it
+    pub header_len: usize,
+}
+
+impl RadiotapInfo {
+    /// Whether `data`, the frame this header was read from, is corrupted:
+    /// the driver flagged a bad FCS, or the FCS it kept doesn't match
+    pub fn is_corrupt(&self, data: &[u8]) -> bool {
+        if self.bad_fcs {
+            return true;
+        }
+        if !self.fcs_at_end {
+            return false;
+        }
+        let frame = &data[self.header_len..];
+        if frame.len() < FCS_LEN {
+            return true;
+        }
+        let (body, fcs) = frame.split_at(frame.len() - FCS_LEN);
+        crc32(body) != u32::from_le_bytes([fcs[0], fcs[1], fcs[2], fcs[3]])
+    }
+
+    /// `data` without a trailing FCS, radiotap header kept, ready for the
+    /// frame parsers
+    pub fn strip_fcs<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        if self.fcs_at_end && data.len() >= self.header_len + FCS_LEN {
+            &data[..data.len() - FCS_LEN]
+        } else {
+            data
+        }
+    }
+}
+
+/// The IEEE 802.3 CRC-32 the 802.11 FCS uses
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Alignment and size of each standard field, by presence bit
+fn field_layout(field: u32) -> Option<(usize, usize)> {
+    Some(match field {
+        0 => (8, 8),        // TSFT
+        1 | 2 => (1, 1),    // flags, rate
+        3 => (2, 4),        // channel
+        4 => (1, 2),        // FHSS
+        5 | 6 => (1, 1),    // antenna signal and noise (dBm)
+        7..=9 => (2, 2),    // lock quality, TX attenuation, dB TX attenuation
+        10..=13 => (1, 1),  // dBm TX power, antenna, dB antenna signal and noise
+        14 | 15 => (2, 2),  // RX and TX flags
+        16 | 17 => (1, 1),  // RTS and data retries
+        18 => (4, 8),       // XChannel
+        19 => (1, 3),       // MCS
+        20 => (4, 8),       // A-MPDU status
+        21 => (2, 12),      // VHT
+        22 => (8, 12),      // timestamp
+        23 | 24 => (2, 12), // HE, HE-MU
+        25 => (2, 6),       // HE-MU other user
+        26 => (1, 1),       // zero-length PSDU
+        27 => (2, 4),       // L-SIG
+        _ => return None,
+    })
+}
+
+fn decode_field(info: &mut RadiotapInfo, field: u32, bytes: &[u8]) {
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u64_at = |at: usize| {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[at..at + 8]);
+        u64::from_le_bytes(word)
+    };
+
+    // Later namespaces repeat fields per chain; the first value wins
+    match field {
+        FIELD_TSFT => info.timestamp_us = Some(u64_at(0)),
+        FIELD_FLAGS => {
+            info.fcs_at_end |= bytes[0] & FLAG_FCS_AT_END != 0;
+            info.bad_fcs |= bytes[0] & FLAG_BAD_FCS != 0;
+        }
+        FIELD_RATE if bytes[0] != 0 => info.rate_500kbps = Some(bytes[0]),
+        FIELD_CHANNEL | FIELD_XCHANNEL if info.frequency_mhz.is_none() => {
+            let mhz = if field == FIELD_CHANNEL { u16_at(0) } else { u16_at(4) };
+            if mhz != 0 {
+                info.frequency_mhz = Some(mhz);
+                info.channel = frequency_channel(mhz);
+            }
+        }
+        FIELD_ANTENNA_SIGNAL if info.signal_dbm.is_none() => info.signal_dbm = Some(bytes[0] as i8 as i32),
+        FIELD_ANTENNA_NOISE if info.noise_dbm.is_none() => info.noise_dbm = Some(bytes[0] as i8 as i32),
+        FIELD_ANTENNA if info.antenna.is_none() => info.antenna = Some(bytes[0]),
+        FIELD_MCS if bytes[0] & MCS_KNOWN_INDEX != 0 => info.mcs = Some(bytes[2]),
+        FIELD_VHT => {
+            // High nibble MCS, low nibble streams; no streams, no user
+            let user = bytes[4];
+            if user & 0x0f != 0 {
+                info.mcs = Some(user >> 4);
+                info.nss = Some(user & 0x0f);
+            }
+        }
+        FIELD_TIMESTAMP if info.timestamp_us.is_none() => {
+            let ts = u64_at(0);
+            info.timestamp_us = match bytes[10] & 0x0f {
+                0 => Some(ts.saturating_mul(1000)),
+                1 => Some(ts),
+                2 => Some(ts / 1000),
+                _ => None,
+            };
+        }
+        _ => {}
+    }
+}
+
+fn align(offset: usize, to: usize) -> usize {
+    (offset + to - 1) & !(to - 1)
+}
+
+/// Decode the radiotap header at the start of `data`. Missing or truncated
+/// fields are left empty; a frame without a radiotap header gives an empty
+/// result with `header_len` 0.
+pub fn parse_radiotap(data: &[u8]) -> RadiotapInfo {
+    let mut info = RadiotapInfo::default();
+    if data.len() < 8 || data[0] != 0 {
+        return info;
+    }
+    let header_len = u16::from_le_bytes([data[2], data[3]]) as usize;
+    if header_len > data.len() || header_len < 8 {
+        return info;
+    }
+    info.header_len = header_len;
+    let header = &data[..header_len];
+
+    let mut words = Vec::new();
+    let mut offset = 4;
+    loop {
+        if offset + 4 > header_len {
+            return info;
+        }
+        let mut word = [0u8; 4];
+        word.copy_from_slice(&header[offset..offset + 4]);
+        let word = u32::from_le_bytes(word);
+        words.push(word);
+        offset += 4;
+        if word & PRESENT_EXT == 0 {
+            break;
+        }
+    }
+
+    let mut vendor = false;
+    // Field number of bit 0 in the current word
+    let mut base = 0;
+    for word in words {
+        if !vendor {
+            for bit in (0..29).filter(|bit| word & (1 << bit) != 0) {
+                let field = base + bit;
+                let (alignment, size) = match field_layout(field) {
+                    Some(layout) => layout,
+                    None => return info,
+                };
+                offset = align(offset, alignment);
+                if offset + size > header_len {
+                    return info;
+                }
+                decode_field(&mut info, field, &header[offset..offset + size]);
+                offset += size;
+            }
+        }
+
+        if word & PRESENT_VENDOR_NS != 0 {
+            // OUI, sub-namespace, then the length of the vendor's data
+            offset = align(offset, 2);
+            if offset + 6 > header_len {
+                return info;
+            }
+            offset += 6 + u16::from_le_bytes([header[offset + 4], header[offset + 5]]) as usize;
+            vendor = true;
+        } else if word & PRESENT_RADIOTAP_NS != 0 {
+            vendor = false;
+            base = 0;
+        } else if !vendor {
+            base += 32;
+        }
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_radiotap_channel_and_signal() {
+        // Flags, channel (2437 MHz, 2-byte aligned) and antenna signal
+        let mut frame = vec![0, 0, 15, 0];
+        frame.extend_from_slice(&((1u32 << 1) | (1 << 3) | (1 << 5)).to_le_bytes());
+        frame.push(0x10);
+        frame.push(0); // alignment padding
+        frame.extend_from_slice(&2437u16.to_le_bytes());
+        frame.extend_from_slice(&0x00a0u16.to_le_bytes());
+        frame.push(-52i8 as u8);
+
+        let info = parse_radiotap(&frame);
+        assert_eq!(info.frequency_mhz, Some(2437));
+        assert_eq!(info.channel, Some(6));
+        assert_eq!(info.signal_dbm, Some(-52));
+        assert_eq!(info.header_len, 15);
+
+        // Rate (1 Mbit/s) fills the byte the padding took above
+        frame[4] |= 1 << 2;
+        frame[9] = 2;
+        let info = parse_radiotap(&frame);
+        assert_eq!(info.rate_500kbps, Some(2));
+        assert_eq!(info.channel, Some(6));
+
+        // Signal only, as the scripted source builds it
+        let info = parse_radiotap(&[0, 0, 9, 0, 0x20, 0, 0, 0, 0xc4]);
+        assert_eq!(info.signal_dbm, Some(-60));
+        assert_eq!(info.channel, None);
+    }
+
+    #[test]
+    fn test_parse_radiotap_extended_presence() {
+        // As iwlwifi sends it: combined values, then one chain's signal and
+        // antenna, then a vendor namespace, then the MCS back in radiotap's
+        let words = [
+            (1u32 << FIELD_TSFT) | (1 << 1) | (1 << FIELD_CHANNEL) | (1 << FIELD_ANTENNA_SIGNAL)
+                | (1 << FIELD_ANTENNA_NOISE) | PRESENT_RADIOTAP_NS | PRESENT_EXT,
+            (1 << FIELD_ANTENNA_SIGNAL) | (1 << FIELD_ANTENNA) | PRESENT_VENDOR_NS | PRESENT_EXT,
+            1 | PRESENT_RADIOTAP_NS | PRESENT_EXT,
+            1 << FIELD_MCS,
+        ];
+        let mut frame = vec![0, 0, 0, 0];
+        for word in words {
+            frame.extend_from_slice(&word.to_le_bytes());
+        }
+        frame.extend_from_slice(&[0; 4]); // TSFT is 8-byte aligned
+        frame.extend_from_slice(&1_700_000_000_123_456u64.to_le_bytes());
+        frame.push(0x00); // flags
+        frame.push(0); // alignment padding
+        frame.extend_from_slice(&5180u16.to_le_bytes());
+        frame.extend_from_slice(&0x0140u16.to_le_bytes());
+        frame.push(-48i8 as u8);
+        frame.push(-95i8 as u8);
+        // Chain 0
+        frame.push(-51i8 as u8);
+        frame.push(1);
+        // Vendor namespace header, 2-byte aligned, and 3 bytes of its data
+        frame.extend_from_slice(&[0x00, 0x13, 0x74, 0x00, 0x03, 0x00, 0xaa, 0xbb, 0xcc]);
+        // MCS: index known, flags, MCS 7
+        frame.extend_from_slice(&[MCS_KNOWN_INDEX, 0x00, 7]);
+        let len = frame.len() as u16;
+        frame[2..4].copy_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&[0x40, 0x00]); // start of the 802.11 frame
+
+        let info = parse_radiotap(&frame);
+        assert_eq!(info.header_len, len as usize);
+        assert_eq!(info.timestamp_us, Some(1_700_000_000_123_456));
+        assert_eq!(info.channel, Some(36));
+        assert_eq!(info.signal_dbm, Some(-48));
+        assert_eq!(info.noise_dbm, Some(-95));
+        assert_eq!(info.antenna, Some(1));
+        assert_eq!(info.mcs, Some(7));
+
+        // An unknown field stops the walk but keeps what came before it
+        let mut unknown = frame.clone();
+        unknown[16..20].copy_from_slice(&((1u32 << 28) | (1 << FIELD_MCS)).to_le_bytes());
+        let info = parse_radiotap(&unknown);
+        assert_eq!(info.signal_dbm, Some(-48));
+        assert_eq!(info.mcs, None);
+    }
+
+    #[test]
+    fn test_parse_radiotap_vht() {
+        // VHT: known, flags, bandwidth, then MCS 9 on 2 streams for user 0
+        let mut frame = vec![0, 0, 20, 0];
+        frame.extend_from_slice(&(1u32 << FIELD_VHT).to_le_bytes());
+        frame.extend_from_slice(&[0x44, 0x00, 0x00, 0x04, 0x92, 0, 0, 0, 0, 0, 0, 0]);
+        let info = parse_radiotap(&frame);
+        assert_eq!((info.mcs, info.nss), (Some(9), Some(2)));
+
+        // Bare 802.11
+        assert_eq!(parse_radiotap(&[0x40, 0x00, 0x00, 0x00, 0, 0, 0, 0]), RadiotapInfo::default());
+    }
+
+    #[test]
+    fn test_fcs_validation() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        // Flags with FCS at end, then a probe request header and its FCS
+        let mut frame = vec![0, 0, 9, 0, 0x02, 0, 0, 0, FLAG_FCS_AT_END];
+        frame.extend_from_slice(&[0x40, 0x00, 0x00, 0x00]);
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&[0xaa, 0xbb, 0xcc, 0x00, 0x00, 0x01]);
+        frame.extend_from_slice(&[0xff; 6]);
+        frame.extend_from_slice(&[0x10, 0x00]);
+        let fcs = crc32(&frame[9..]);
+        frame.extend_from_slice(&fcs.to_le_bytes());
+
+        let info = parse_radiotap(&frame);
+        assert!(info.fcs_at_end);
+        assert!(!info.is_corrupt(&frame));
+        assert_eq!(info.strip_fcs(&frame).len(), frame.len() - FCS_LEN);
+
+        // One flipped bit in the source address
+        let mut damaged = frame.clone();
+        damaged[20] ^= 0x04;
+        assert!(parse_radiotap(&damaged).is_corrupt(&damaged));
+
+        // Flagged by the driver, FCS kept or not
+        frame[8] |= FLAG_BAD_FCS;
+        assert!(parse_radiotap(&frame).is_corrupt(&frame));
+        frame[8] = FLAG_BAD_FCS;
+        let info = parse_radiotap(&frame);
+        assert!(info.is_corrupt(&frame));
+        assert_eq!(info.strip_fcs(&frame).len(), frame.len());
+
+        // No flags, nothing to check
+        let plain = [0, 0, 9, 0, 0x20, 0, 0, 0, 0xc4, 0x40, 0x00];
+        assert!(!parse_radiotap(&plain).is_corrupt(&plain));
+    }
+}
@@ -1,11 +1,181 @@
-use crate::analysis::SurveillanceAlert;
-use crate::database::Database;
+use crate::analysis::{AlertDelta, SurveillanceAlert, SurveillanceAnalyzer, KARMA_MIN_SSIDS};
+use crate::config::Config;
+use crate::database::{Database, GpsFixStats};
+use crate::deauth::EVENT_DEAUTH_ATTACK;
+use crate::formats::Format;
+use crate::homenet::MySsidProbe;
+use crate::intel::{misp_event, report_window, stix_bundle};
+use crate::occupancy::{occupancy_time_series, OccupancySample};
+use crate::oui::{OUI_DB_SOURCE, OUI_DB_VERSION};
+use crate::output::{self, heading, Cell, Severity, Table};
+use crate::privacy::{noise_key, public_stats, Anonymizer};
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{self, Write};
 use std::path::Path;
 
+/// Report types for `prowl report --report-type`. A new report is a
+/// variant here, its entry in `ALL`, and its arms below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportType {
+    Devices,
+    Ssids,
+    Vendors,
+    Stats,
+    Occupancy,
+    Summary,
+    Public,
+    Misp,
+    Stix,
+}
+
+impl Format for ReportType {
+    const KIND: &'static str = "report type";
+    const ALL: &'static [Self] = &[
+        ReportType::Devices,
+        ReportType::Ssids,
+        ReportType::Vendors,
+        ReportType::Stats,
+        ReportType::Occupancy,
+        ReportType::Summary,
+        ReportType::Public,
+        ReportType::Misp,
+        ReportType::Stix,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ReportType::Devices => "devices",
+            ReportType::Ssids => "ssids",
+            ReportType::Vendors => "vendors",
+            ReportType::Stats => "stats",
+            ReportType::Occupancy => "occupancy",
+            ReportType::Summary => "summary",
+            ReportType::Public => "public",
+            ReportType::Misp => "misp",
+            ReportType::Stix => "stix",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ReportType::Devices => "every device with its first and last sighting",
+            ReportType::Ssids => "SSIDs probed for and how many devices asked for each",
+            ReportType::Vendors => "devices per vendor and their share of randomized MACs",
+            ReportType::Stats => "database statistics, change since the previous period, channel utilization",
+            ReportType::Occupancy => "estimated people present over the last hours",
+            ReportType::Summary => "activity and suspicious devices over the last hours",
+            ReportType::Public => "anonymized occupancy statistics as JSON",
+            ReportType::Misp => "suspicious devices over the last hours as a MISP event",
+            ReportType::Stix => "suspicious devices over the last hours as a STIX 2.1 bundle",
+        }
+    }
+}
+
+/// What a report is generated from and where it goes
+pub struct ReportContext<'a> {
+    pub db: &'a Database,
+    pub config: &'a Config,
+    /// Hours covered by time series, summary, public and threat-intel
+    /// reports, and compared with the hours before by stats
+    pub last_hours: u32,
+    /// Output file; stdout when `None`
+    pub output: Option<&'a Path>,
+}
+
+impl ReportType {
+    pub fn generate(self, ctx: &ReportContext) -> Result<()> {
+        let db = ctx.db;
+        let config = ctx.config;
+        let now = Utc::now().timestamp();
+        let start = now - ctx.last_hours as i64 * 3600;
+        let analyzer = || {
+            SurveillanceAnalyzer::new(
+                config.analysis.time_windows_minutes.clone(),
+                config.analysis.persistence_threshold,
+            )
+            .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+            .with_my_ssids(config.analysis.my_ssids.clone())
+        };
+
+        match self {
+            ReportType::Devices => ReportGenerator::generate_device_list(db, ctx.output),
+            ReportType::Ssids => ReportGenerator::generate_ssid_report(db, ctx.output),
+            ReportType::Vendors => ReportGenerator::generate_vendor_report(db, ctx.output),
+            ReportType::Stats => ReportGenerator::generate_stats(db, ctx.last_hours),
+            ReportType::Summary => {
+                let analyzer = analyzer();
+                let mut writer: Box<dyn Write> = match ctx.output {
+                    Some(path) => Box::new(File::create(path)?),
+                    None => Box::new(io::stdout()),
+                };
+                ReportGenerator::write_summary_report(
+                    db,
+                    &analyzer,
+                    ctx.last_hours,
+                    config.email.top_devices,
+                    &mut writer,
+                )
+            }
+            ReportType::Occupancy => {
+                let samples = occupancy_time_series(
+                    db,
+                    start,
+                    now,
+                    config.occupancy.bucket_minutes as i64 * 60,
+                    config.occupancy.devices_per_person,
+                )?;
+                ReportGenerator::generate_occupancy_report(&samples, ctx.output)
+            }
+            ReportType::Public => {
+                // Whole hours, so releasing again gives the same noise
+                // instead of a fresh draw to average out
+                let (start, end) = report_window(now, ctx.last_hours);
+                let samples = occupancy_time_series(
+                    db,
+                    start,
+                    end,
+                    config.occupancy.bucket_minutes as i64 * 60,
+                    config.occupancy.devices_per_person,
+                )?;
+                let anonymizer = Anonymizer::new(&config.privacy, &noise_key(db)?, start, end);
+                let stats = public_stats(
+                    db,
+                    &samples,
+                    start,
+                    end,
+                    config.occupancy.devices_per_person,
+                    &anonymizer,
+                    &config.privacy,
+                )?;
+                let content = serde_json::to_string_pretty(&stats)?;
+                match ctx.output {
+                    Some(path) => std::fs::write(path, content)?,
+                    None => println!("{}", content),
+                }
+                Ok(())
+            }
+            ReportType::Misp | ReportType::Stix => {
+                let alerts = analyzer().analyze(db, ctx.last_hours)?;
+                // Whole hours, so exporting again names the same objects
+                let (start, end) = report_window(now, ctx.last_hours);
+                let document = match self {
+                    ReportType::Misp => misp_event(&alerts, start, end, now),
+                    _ => stix_bundle(&alerts, start, end, now),
+                };
+                let content = serde_json::to_string_pretty(&document)?;
+                match ctx.output {
+                    Some(path) => std::fs::write(path, content)?,
+                    None => println!("{}", content),
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
 pub struct ReportGenerator;
 
 impl ReportGenerator {
@@ -28,6 +198,7 @@ impl ReportGenerator {
             Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
         )?;
         writeln!(writer, "Suspicious devices found: {}", alerts.len())?;
+        writeln!(writer, "OUI database: {} v{}", OUI_DB_SOURCE, OUI_DB_VERSION)?;
         writeln!(writer)?;
 
         if alerts.is_empty() {
@@ -40,6 +211,12 @@ impl ReportGenerator {
             writeln!(writer, "Device #{}: {}", i + 1, alert.device.mac)?;
             writeln!(writer, "----------------------------------------")?;
             writeln!(writer, "  Persistence Score: {:.2}%", alert.score * 100.0)?;
+            if let Some(vendor) = &alert.vendor {
+                writeln!(writer, "  Vendor: {}", vendor.describe())?;
+            }
+            if let Some(device) = alert.wps.as_ref().and_then(|wps| wps.describe()) {
+                writeln!(writer, "  WPS Device: {}", device)?;
+            }
             writeln!(
                 writer,
                 "  First Seen: {}",
@@ -52,6 +229,27 @@ impl ReportGenerator {
             )?;
             writeln!(writer, "  Appearances: {}", alert.appearance_count)?;
             writeln!(writer, "  Locations: {}", alert.location_count)?;
+            if let Some(residency) = &alert.residency {
+                writeln!(writer, "  Residency: {}", residency.as_str())?;
+            }
+            if let Some(overlap) = &alert.location_overlap {
+                writeln!(
+                    writer,
+                    "  Your Locations: {} of {} ({:.0}%)",
+                    overlap.places_present,
+                    overlap.places,
+                    overlap.percent()
+                )?;
+            }
+            if let Some(stability) = &alert.stability {
+                writeln!(
+                    writer,
+                    "  Mobility: {} (RSSI ±{:.1} dB over {} readings)",
+                    stability.mobility.as_str(),
+                    stability.stddev_db,
+                    stability.samples
+                )?;
+            }
             writeln!(writer)?;
 
             if !alert.probed_ssids.is_empty() {
@@ -62,6 +260,38 @@ impl ReportGenerator {
                 writeln!(writer)?;
             }
 
+            if !alert.directed_bssids.is_empty() {
+                writeln!(writer, "  Directed Probe Targets (BSSID):")?;
+                for bssid in &alert.directed_bssids {
+                    writeln!(writer, "    - {}", bssid)?;
+                }
+                writeln!(writer)?;
+            }
+
+            if !alert.nearby_ssids.is_empty() {
+                writeln!(writer, "  Probed SSIDs With An AP In Range:")?;
+                for ssid in &alert.nearby_ssids {
+                    writeln!(writer, "    - {}", ssid)?;
+                }
+                writeln!(writer)?;
+            }
+
+            if !alert.responding_aps.is_empty() {
+                writeln!(writer, "  APs That Answered Its Probes:")?;
+                for ap in &alert.responding_aps {
+                    let ssid = if ap.ssid.is_empty() { "<hidden>" } else { &ap.ssid };
+                    writeln!(
+                        writer,
+                        "    - {} ({}) x{}, last {}",
+                        ap.bssid,
+                        ssid,
+                        ap.responses,
+                        format_timestamp(ap.last_seen)
+                    )?;
+                }
+                writeln!(writer)?;
+            }
+
             writeln!(writer, "  Alert Reasons:")?;
             for reason in &alert.reasons {
                 writeln!(writer, "    * {}", reason)?;
@@ -76,6 +306,114 @@ impl ReportGenerator {
         Ok(())
     }
 
+    /// Print alerts as a severity-colored table on stdout
+    /// Devices that probed for one of your networks for the first time;
+    /// prints nothing when there are none
+    pub fn print_my_ssid_probes(probes: &[MySsidProbe]) {
+        if probes.is_empty() {
+            return;
+        }
+        let mut table = Table::new(["MAC", "Network", "Signal", "First Probe", "New Device"]);
+        for probe in probes {
+            table.add_row([
+                Cell::new(probe.mac.as_str()).severity(Severity::Alert),
+                Cell::new(probe.ssid.as_str()),
+                Cell::new(probe.signal_dbm.map_or("-".to_string(), |s| format!("{} dBm", s))),
+                Cell::new(format_timestamp(probe.timestamp)),
+                Cell::new(if probe.new_device { "yes" } else { "no" }),
+            ]);
+        }
+        if !output::style().machine {
+            println!();
+            heading("Probes for your networks");
+        }
+        table.print();
+    }
+
+    pub fn print_alert_table(alerts: &[SurveillanceAlert], threshold: f64) {
+        let mut table = Table::new([
+            "MAC",
+            "Score",
+            "Appearances",
+            "Locations",
+            "Mobility",
+            "Vendor",
+            "Last Seen",
+            "Reasons",
+        ]);
+
+        for alert in alerts {
+            let severity = Severity::from_score(alert.score, threshold);
+            let vendor = alert
+                .vendor
+                .as_ref()
+                .and_then(|v| v.vendor.clone())
+                .unwrap_or_else(|| "Unknown".to_string());
+            table.add_row([
+                Cell::new(alert.device.mac.as_str()).severity(severity),
+                Cell::new(format!("{:.0}%", alert.score * 100.0)).severity(severity),
+                Cell::new(alert.appearance_count.to_string()),
+                Cell::new(alert.location_count.to_string()),
+                Cell::new(alert.stability.map(|s| s.mobility.as_str()).unwrap_or("-")),
+                Cell::new(vendor),
+                Cell::new(format_timestamp(alert.device.last_seen)),
+                Cell::new(alert.reasons.join("\n")),
+            ]);
+        }
+
+        if output::style().machine {
+            table.print();
+            return;
+        }
+
+        if table.is_empty() {
+            println!("{}", output::paint("No suspicious devices detected.", Severity::Ok));
+            return;
+        }
+
+        table.print();
+        println!(
+            "{} suspicious device(s), OUI database: {} v{}",
+            alerts.len(),
+            OUI_DB_SOURCE,
+            OUI_DB_VERSION
+        );
+    }
+
+    /// Print only what changed since the previous analysis run
+    pub fn print_alert_delta(delta: &AlertDelta) {
+        let timestamp = Utc::now().format("%H:%M:%S");
+
+        if delta.is_empty() {
+            println!("[{}] No changes", timestamp);
+            return;
+        }
+
+        for alert in &delta.new_alerts {
+            println!(
+                "[{}] NEW     {} score {:.0}%  {}",
+                timestamp,
+                alert.device.mac,
+                alert.score * 100.0,
+                alert.reasons.join("; ")
+            );
+        }
+        for (alert, old) in &delta.score_changes {
+            let arrow = if alert.score > *old { "↑" } else { "↓" };
+            println!(
+                "[{}] CHANGED {} score {:.0}% {} {:.0}%",
+                timestamp,
+                alert.device.mac,
+                old * 100.0,
+                arrow,
+                alert.score * 100.0
+            );
+        }
+        for mac in &delta.cleared {
+            println!("[{}] CLEARED {}", timestamp, mac);
+        }
+    }
+
     pub fn generate_device_list(db: &Database, output: Option<&Path>) -> Result<()> {
         let mut writer: Box<dyn Write> = match output {
             Some(path) => Box::new(File::create(path)?),
@@ -84,36 +422,219 @@ impl ReportGenerator {
 
         let devices = db.get_all_devices()?;
 
-        writeln!(writer, "MAC Address          | First Seen           | Last Seen            | Probes")?;
-        writeln!(writer, "---------------------|----------------------|----------------------|-------")?;
+        writeln!(writer, "MAC Address          | First Seen           | Last Seen            | Probes | Vendor (OUI DB)")?;
+        writeln!(writer, "---------------------|----------------------|----------------------|--------|----------------")?;
 
         for device in &devices {
             let probes = db.get_probes_for_device(device.id)?;
+            let vendor = db
+                .get_device_vendor_attribution(device.id)?
+                .map(|v| format!("{} (v{})", v.vendor.as_deref().unwrap_or("Unknown"), v.db_version))
+                .unwrap_or_else(|| "Unknown".to_string());
             writeln!(
                 writer,
-                "{} | {} | {} | {}",
+                "{} | {} | {} | {:>6} | {}",
                 device.mac,
                 format_timestamp(device.first_seen),
                 format_timestamp(device.last_seen),
-                probes.len()
+                probes.len(),
+                vendor
             )?;
         }
 
         writeln!(writer)?;
+        writeln!(writer, "OUI database: {} v{}", OUI_DB_SOURCE, OUI_DB_VERSION)?;
         writeln!(writer, "Total devices: {}", devices.len())?;
         writeln!(writer, "Total probes: {}", db.count_probes()?)?;
 
         Ok(())
     }
 
-    pub fn generate_stats(db: &Database) -> Result<()> {
+    /// SSIDs devices probed for, with how many devices asked for each
+    pub fn generate_ssid_report(db: &Database, output: Option<&Path>) -> Result<()> {
+        let mut writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+
+        let ssids = db.get_ssid_summaries()?;
+
+        writeln!(writer, "SSID                             | Devices | Probes | First Seen           | Last Seen")?;
+        writeln!(writer, "---------------------------------|---------|--------|----------------------|---------------------")?;
+
+        for summary in &ssids {
+            writeln!(
+                writer,
+                "{:<32} | {:>7} | {:>6} | {} | {}",
+                summary.ssid,
+                summary.devices,
+                summary.probes,
+                format_timestamp(summary.first_seen),
+                format_timestamp(summary.last_seen)
+            )?;
+        }
+
+        writeln!(writer)?;
+        writeln!(writer, "Total SSIDs: {}", ssids.len())?;
+
+        Ok(())
+    }
+
+    /// Device counts per vendor and the share of each using randomized MACs
+    pub fn generate_vendor_report(db: &Database, output: Option<&Path>) -> Result<()> {
+        let mut writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+
+        let vendors = db.get_vendor_summaries()?;
+
+        writeln!(writer, "Vendor                           | Devices | Randomized")?;
+        writeln!(writer, "---------------------------------|---------|-----------")?;
+
+        for summary in &vendors {
+            writeln!(
+                writer,
+                "{:<32} | {:>7} | {:>5} ({:>3.0}%)",
+                summary.vendor,
+                summary.devices,
+                summary.randomized,
+                summary.randomized_share() * 100.0
+            )?;
+        }
+
+        let devices: usize = vendors.iter().map(|v| v.devices).sum();
+        let randomized: usize = vendors.iter().map(|v| v.randomized).sum();
+        writeln!(writer)?;
+        writeln!(writer, "OUI database: {} v{}", OUI_DB_SOURCE, OUI_DB_VERSION)?;
+        writeln!(writer, "Total devices: {}", devices)?;
+        if devices > 0 {
+            writeln!(
+                writer,
+                "Randomized MACs: {} ({:.0}%)",
+                randomized,
+                randomized as f64 * 100.0 / devices as f64
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_occupancy_report(
+        samples: &[OccupancySample],
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let mut writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(File::create(path)?),
+            None => Box::new(io::stdout()),
+        };
+
+        writeln!(writer, "Bucket Start         | Devices | Randomized (MACs) | Est. People")?;
+        writeln!(writer, "---------------------|---------|-------------------|------------")?;
+
+        for sample in samples {
+            let est = &sample.estimate;
+            writeln!(
+                writer,
+                "{} | {:>7} | {:>8} ({:>6}) | {:>10.1}",
+                format_timestamp(sample.bucket_start),
+                est.devices(),
+                est.randomized_devices,
+                est.randomized_macs,
+                est.people
+            )?;
+        }
+
+        if let Some(peak) = samples
+            .iter()
+            .max_by(|a, b| a.estimate.people.partial_cmp(&b.estimate.people).unwrap_or(std::cmp::Ordering::Equal))
+        {
+            writeln!(writer)?;
+            writeln!(
+                writer,
+                "Peak occupancy: ~{:.0} people at {}",
+                peak.estimate.people,
+                format_timestamp(peak.bucket_start)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Plain-text summary of the last `hours`: activity counts, alerts for
+    /// devices first seen in the period, and the top suspicious devices
+    pub fn write_summary_report(
+        db: &Database,
+        analyzer: &SurveillanceAnalyzer,
+        hours: u32,
+        top_devices: usize,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        let end = Utc::now().timestamp();
+        let start = end - hours as i64 * 3600;
+
+        let devices_seen = db.get_devices_in_time_range(start, end)?.len();
+        let new_devices = db.count_new_devices(start, end)?;
+        let probes = db.count_probes_in_range(start, end)?;
+        let alerts = analyzer.analyze(db, hours)?;
+        let new_alerts: Vec<&SurveillanceAlert> = alerts
+            .iter()
+            .filter(|a| a.device.first_seen >= start)
+            .collect();
+
+        writeln!(writer, "PROWL SUMMARY REPORT")?;
+        writeln!(writer, "Period: {} to {} UTC", format_timestamp(start), format_timestamp(end))?;
+        writeln!(writer)?;
+        writeln!(writer, "Devices seen:       {}", devices_seen)?;
+        writeln!(writer, "New devices:        {}", new_devices)?;
+        writeln!(writer, "Probes captured:    {}", probes)?;
+        writeln!(writer, "Suspicious devices: {}", alerts.len())?;
+        writeln!(writer, "New alerts:         {}", new_alerts.len())?;
+        writeln!(writer)?;
+
+        if !new_alerts.is_empty() {
+            writeln!(writer, "New alerts")?;
+            for alert in &new_alerts {
+                writeln!(
+                    writer,
+                    "  {}  {:.0}%  first seen {}",
+                    alert.device.mac,
+                    alert.score * 100.0,
+                    format_timestamp(alert.device.first_seen)
+                )?;
+            }
+            writeln!(writer)?;
+        }
+
+        writeln!(writer, "Top suspicious devices")?;
+        if alerts.is_empty() {
+            writeln!(writer, "  None")?;
+        }
+        for alert in alerts.iter().take(top_devices) {
+            let vendor = alert
+                .vendor
+                .as_ref()
+                .and_then(|v| v.vendor.clone())
+                .unwrap_or_else(|| "Unknown".to_string());
+            writeln!(writer, "  {}  {:.0}%  {}", alert.device.mac, alert.score * 100.0, vendor)?;
+            for reason in &alert.reasons {
+                writeln!(writer, "      - {}", reason)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Totals for the whole database, then the last `period_hours` against
+    /// the same length of time before them
+    pub fn generate_stats(db: &Database, period_hours: u32) -> Result<()> {
         let device_count = db.count_devices()?;
         let probe_count = db.count_probes()?;
 
-        println!("Database Statistics");
-        println!("-------------------");
-        println!("Total devices: {}", device_count);
-        println!("Total probes:  {}", probe_count);
+        let mut rows: Vec<(&str, String)> = vec![
+            ("Total devices", device_count.to_string()),
+            ("Total probes", probe_count.to_string()),
+        ];
 
         if device_count > 0 {
             let devices = db.get_all_devices()?;
@@ -122,27 +643,170 @@ impl ReportGenerator {
             let first = devices.iter().map(|d| d.first_seen).min().unwrap_or(0);
             let last = devices.iter().map(|d| d.last_seen).max().unwrap_or(0);
 
-            println!();
-            println!("Time Range");
-            println!("----------");
-            println!("First seen: {}", format_timestamp(first));
-            println!("Last seen:  {}", format_timestamp(last));
+            rows.push(("First seen", format_timestamp(first)));
+            rows.push(("Last seen", format_timestamp(last)));
 
             let duration_hours = (last - first) as f64 / 3600.0;
             if duration_hours > 0.0 {
-                println!();
-                println!("Averages");
-                println!("--------");
-                println!("Probes/hour: {:.2}", probe_count as f64 / duration_hours);
-                println!("Devices/hour: {:.2}", device_count as f64 / duration_hours);
+                rows.push(("Probes/hour", format!("{:.2}", probe_count as f64 / duration_hours)));
+                rows.push(("Devices/hour", format!("{:.2}", device_count as f64 / duration_hours)));
+            }
+
+            let gps = db.get_gps_fix_stats(None)?;
+            rows.push(("GPS fix rate", format_fix_rate(&gps)));
+            rows.push(("Probes with fix", gps.fix.to_string()));
+            rows.push(("Probes without fix", gps.no_fix.to_string()));
+            rows.push(("Probes with GPS disabled", gps.disabled.to_string()));
+            if gps.unknown > 0 {
+                rows.push(("Probes with unknown GPS", gps.unknown.to_string()));
+            }
+        }
+
+        let suppressed = db.count_suppressed_probes()?;
+        if suppressed > 0 {
+            rows.push(("Probes suppressed by rate limit", suppressed.to_string()));
+        }
+
+        let aps = db.get_access_points()?;
+        if !aps.is_empty() {
+            rows.push(("Access points", aps.len().to_string()));
+            let karma = db.get_multi_ssid_responders(KARMA_MIN_SSIDS)?;
+            if !karma.is_empty() {
+                let bssids: Vec<&str> = karma.iter().map(|(bssid, _)| bssid.as_str()).collect();
+                rows.push(("APs answering for many SSIDs", bssids.join(", ")));
+            }
+        }
+
+        let deauths = db.count_deauth_events()?;
+        if deauths > 0 {
+            rows.push(("Deauth/disassoc frames", deauths.to_string()));
+            let attacks = db.get_events_since(0, Some(EVENT_DEAUTH_ATTACK))?;
+            rows.push(("Deauth bursts flagged", attacks.len().to_string()));
+        }
+
+        heading("Database Statistics");
+        let mut table = Table::new(["Metric", "Value"]);
+        for (metric, value) in rows {
+            table.add_row([Cell::new(metric), Cell::new(value)]);
+        }
+        table.print();
+
+        if device_count > 0 {
+            let comparison = PeriodComparison::between(db, Utc::now().timestamp(), period_hours)?;
+            heading(&format!("Last {}h vs the {}h Before", comparison.hours, comparison.hours));
+            let mut table = Table::new(["Metric", "This period", "Previous", "Change"]);
+            for (metric, (current, previous)) in [
+                ("Devices", comparison.devices),
+                ("New devices", comparison.new_devices),
+                ("Probes", comparison.probes),
+            ] {
+                table.add_row([
+                    Cell::new(metric),
+                    Cell::new(current.to_string()),
+                    Cell::new(previous.to_string()),
+                    Cell::new(format_change(current, previous)),
+                ]);
+            }
+            if !comparison.new_vendors.is_empty() {
+                table.add_row([
+                    Cell::new("New vendors"),
+                    Cell::new(comparison.new_vendors.len().to_string()),
+                    Cell::new("-"),
+                    Cell::new(comparison.new_vendors.join(", ")),
+                ]);
+            }
+            table.print();
+        }
+
+        let usage = db.get_channel_usage(None)?;
+        if !usage.is_empty() {
+            heading("Channel Utilization");
+            let mut table = Table::new(["Channel", "Frames", "Listened", "Frames/s", "Est. airtime"]);
+            for u in &usage {
+                let busy = match u.utilization() {
+                    Some(busy) => {
+                        let severity = if busy >= 0.5 {
+                            Severity::Alert
+                        } else if busy >= 0.2 {
+                            Severity::Warning
+                        } else {
+                            Severity::Ok
+                        };
+                        Cell::new(format!("{:.1}%", busy * 100.0)).severity(severity)
+                    }
+                    None => Cell::new("n/a"),
+                };
+                table.add_row([
+                    Cell::new(u.channel.to_string()),
+                    Cell::new(u.frames.to_string()),
+                    Cell::new(format!("{:.0}s", u.dwell_us as f64 / 1e6)),
+                    Cell::new(u.frames_per_sec().map(|f| format!("{:.1}", f)).unwrap_or_else(|| "n/a".into())),
+                    busy,
+                ]);
             }
+            table.print();
         }
 
         Ok(())
     }
 }
 
-fn format_timestamp(ts: i64) -> String {
+/// One period's totals next to those of the equally long period before it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodComparison {
+    pub hours: u32,
+    /// Devices that probed, this period and the previous one
+    pub devices: (usize, usize),
+    /// Devices first seen
+    pub new_devices: (usize, usize),
+    pub probes: (usize, usize),
+    /// Vendors of devices that probed this period but not the previous one
+    pub new_vendors: Vec<String>,
+}
+
+impl PeriodComparison {
+    /// The `hours` up to `end` against the `hours` before them
+    pub fn between(db: &Database, end: i64, hours: u32) -> Result<Self> {
+        let hours = hours.max(1);
+        let span = hours as i64 * 3600;
+        let start = end - span;
+        let (prev_start, prev_end) = (start - span, start - 1);
+
+        let previous_vendors: HashSet<String> =
+            db.get_active_vendors(prev_start, prev_end)?.into_iter().collect();
+        let new_vendors = db
+            .get_active_vendors(start, end)?
+            .into_iter()
+            .filter(|vendor| !previous_vendors.contains(vendor))
+            .collect();
+        Ok(PeriodComparison {
+            hours,
+            devices: (db.count_active_devices(start, end)?, db.count_active_devices(prev_start, prev_end)?),
+            new_devices: (db.count_new_devices(start, end)?, db.count_new_devices(prev_start, prev_end)?),
+            probes: (db.count_probes_in_range(start, end)?, db.count_probes_in_range(prev_start, prev_end)?),
+            new_vendors,
+        })
+    }
+}
+
+/// Change from `previous` to `current` as a percentage, e.g. "+12%"
+pub fn format_change(current: usize, previous: usize) -> String {
+    match (current, previous) {
+        (0, 0) => "-".to_string(),
+        (_, 0) => "new".to_string(),
+        _ => format!("{:+.0}%", (current as f64 - previous as f64) * 100.0 / previous as f64),
+    }
+}
+
+/// Fix rate among probes captured with GPS enabled
+pub fn format_fix_rate(stats: &GpsFixStats) -> String {
+    match stats.fix_rate() {
+        Some(rate) => format!("{:.1}% ({}/{})", rate * 100.0, stats.fix, stats.fix + stats.no_fix),
+        None => "n/a (GPS disabled)".to_string(),
+    }
+}
+
+pub fn format_timestamp(ts: i64) -> String {
     Utc.timestamp_opt(ts, 0)
         .single()
         .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
@@ -161,3 +825,52 @@ pub fn print_probe_realtime(mac: &str, ssid: &str, signal: Option<i32>) {
         timestamp, mac, signal_display, ssid_display
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{GpsStatus, ProbeCapture};
+
+    fn probe(db: &Database, mac: &str, timestamp: i64) {
+        db.insert_probe(&ProbeCapture {
+            mac: mac.to_string(),
+            ssid: String::new(),
+            timestamp,
+            timestamp_micros: 0,
+            lat: None,
+            lon: None,
+            signal_dbm: Some(-60),
+            channel: Some(1),
+            distance_m: None,
+            gps_status: GpsStatus::Disabled,
+            bssid: None,
+            sequence_number: None,
+            capabilities: None,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_period_comparison() {
+        let db = Database::open_in_memory().unwrap();
+        let end = 100_000;
+        // Previous hour: one Apple device, four probes
+        for i in 0..4 {
+            probe(&db, "00:03:93:00:00:01", end - 7_000 + i);
+        }
+        // This hour: the same device again, plus a Samsung one
+        probe(&db, "00:03:93:00:00:01", end - 100);
+        probe(&db, "00:00:F0:00:00:02", end - 50);
+
+        let comparison = PeriodComparison::between(&db, end, 1).unwrap();
+        assert_eq!(comparison.devices, (2, 1));
+        assert_eq!(comparison.new_devices, (1, 1));
+        assert_eq!(comparison.probes, (2, 4));
+        assert_eq!(comparison.new_vendors, vec!["Samsung".to_string()]);
+
+        assert_eq!(format_change(2, 1), "+100%");
+        assert_eq!(format_change(2, 4), "-50%");
+        assert_eq!(format_change(3, 0), "new");
+        assert_eq!(format_change(0, 0), "-");
+    }
+}
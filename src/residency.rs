@@ -0,0 +1,175 @@
+//! Long-term device classes: transient, recurring visitor and resident.
+//!
+//! A device's class comes from how many distinct days it was heard on over
+//! its whole history. Classes are stored so analysis can tell a neighbour's
+//! router that has been around for months (not worth alerting on) from a
+//! device that showed up recently and has been present every day since
+//! (worth a closer look).
+
+use crate::database::{Database, DeviceDays};
+use anyhow::Result;
+use serde::Serialize;
+
+/// Distinct days a device must be heard on to count as resident
+pub const RESIDENT_MIN_DAYS: i64 = 5;
+/// Share of the days between first and last sighting a resident must have
+/// been heard on; a visitor who drops by once a week never gets there
+const RESIDENT_MIN_PRESENCE: f64 = 0.5;
+/// A resident first seen longer ago than this is established and its alerts
+/// are suppressed; one first seen more recently is a new resident
+pub const ESTABLISHED_AFTER_DAYS: i64 = 21;
+
+const DAY_SECS: i64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Residency {
+    /// Heard on a single day
+    Transient,
+    /// Comes back, but not most days
+    Visitor,
+    /// Heard on most days over a stretch of at least `RESIDENT_MIN_DAYS`
+    Resident,
+}
+
+impl Residency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Residency::Transient => "transient",
+            Residency::Visitor => "visitor",
+            Residency::Resident => "resident",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "transient" => Some(Residency::Transient),
+            "visitor" => Some(Residency::Visitor),
+            "resident" => Some(Residency::Resident),
+            _ => None,
+        }
+    }
+
+    /// Class for a device heard on `days_seen` distinct days between
+    /// `first_seen` and `last_seen`
+    pub fn classify(days_seen: i64, first_seen: i64, last_seen: i64) -> Self {
+        let span_days = (last_seen - first_seen) / DAY_SECS + 1;
+        if days_seen >= RESIDENT_MIN_DAYS && days_seen as f64 >= span_days as f64 * RESIDENT_MIN_PRESENCE {
+            Residency::Resident
+        } else if days_seen >= 2 {
+            Residency::Visitor
+        } else {
+            Residency::Transient
+        }
+    }
+}
+
+/// A device's stored class
+#[derive(Debug, Clone, Serialize)]
+pub struct ResidencyRecord {
+    pub mac: String,
+    pub class: Residency,
+    pub days_seen: i64,
+    pub first_seen: i64,
+    /// When the device last became resident; None unless it is one
+    pub resident_since: Option<i64>,
+    pub updated_at: i64,
+}
+
+impl ResidencyRecord {
+    /// Resident since before `ESTABLISHED_AFTER_DAYS` ago
+    pub fn is_established(&self, now: i64) -> bool {
+        self.class == Residency::Resident && now - self.first_seen >= ESTABLISHED_AFTER_DAYS * DAY_SECS
+    }
+
+    /// Resident, but first seen within the last `ESTABLISHED_AFTER_DAYS`
+    pub fn is_new_resident(&self, now: i64) -> bool {
+        self.class == Residency::Resident && !self.is_established(now)
+    }
+}
+
+/// A device whose class differs from the stored one
+#[derive(Debug, Clone)]
+pub struct ResidencyChange {
+    pub mac: String,
+    pub from: Option<Residency>,
+    pub to: Residency,
+}
+
+/// Reclassify every device from its full history and store the result.
+/// Returns the devices whose class changed.
+pub fn update_residency(db: &Database, now: i64) -> Result<Vec<ResidencyChange>> {
+    let previous = db.get_residency()?;
+    let history = db.get_device_days()?;
+
+    db.in_transaction(|db| {
+        let mut changes = Vec::new();
+        for DeviceDays { mac, first_seen, last_seen, days_seen } in history {
+            let class = Residency::classify(days_seen, first_seen, last_seen);
+            let before = previous.get(&mac);
+            let resident_since = match (class, before) {
+                (Residency::Resident, Some(record)) if record.class == Residency::Resident => {
+                    record.resident_since
+                }
+                (Residency::Resident, _) => Some(now),
+                _ => None,
+            };
+            if before.map(|record| record.class) != Some(class) {
+                changes.push(ResidencyChange {
+                    mac: mac.clone(),
+                    from: before.map(|record| record.class),
+                    to: class,
+                });
+            }
+            db.set_residency(&ResidencyRecord {
+                mac,
+                class,
+                days_seen,
+                first_seen,
+                resident_since,
+                updated_at: now,
+            })?;
+        }
+        Ok(changes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ProbeCapture;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(Residency::classify(1, 0, 3600), Residency::Transient);
+        assert_eq!(Residency::classify(3, 0, 20 * DAY_SECS), Residency::Visitor);
+        assert_eq!(Residency::classify(6, 0, 6 * DAY_SECS), Residency::Resident);
+        // Once a week for two months is a regular visitor, not a resident
+        assert_eq!(Residency::classify(8, 0, 56 * DAY_SECS), Residency::Visitor);
+    }
+
+    #[test]
+    fn test_update_residency_tracks_new_residents() {
+        let db = Database::open_in_memory().unwrap();
+        let now = 100 * DAY_SECS;
+        for day in 0..60 {
+            db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:01", "", now - day * DAY_SECS)).unwrap();
+        }
+        for day in 0..6 {
+            db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:02", "", now - day * DAY_SECS)).unwrap();
+        }
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:DD:EE:03", "", now)).unwrap();
+
+        let changes = update_residency(&db, now).unwrap();
+        assert_eq!(changes.len(), 3);
+        assert!(update_residency(&db, now + 60).unwrap().is_empty());
+
+        let records = db.get_residency().unwrap();
+        let established = &records["AA:BB:CC:DD:EE:01"];
+        assert!(established.is_established(now));
+        assert_eq!(established.resident_since, Some(now));
+        let newcomer = &records["AA:BB:CC:DD:EE:02"];
+        assert!(newcomer.is_new_resident(now));
+        assert_eq!(records["AA:BB:CC:DD:EE:03"].class, Residency::Transient);
+    }
+}
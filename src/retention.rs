@@ -0,0 +1,215 @@
+//! Time-boxed retention of raw probe data.
+//!
+//! Community deployments often may only keep what they need. With
+//! `retention.auto_purge` on, a background thread alongside capture rolls
+//! every hour older than `retention.raw_hours` up into per-hour counts
+//! (devices, new and randomized devices, probes, directed SSIDs) and then
+//! deletes the raw rows behind it: probes and their capabilities, devices
+//! with no probes left and everything kept per MAC for them (labels, links,
+//! watch list, risk, residency), routine events, probe responses and deauth
+//! frames. What is kept long-term is the rollups, the alerts in
+//! `ALERT_EVENTS` and whatever was confirmed by attaching it to a case; a
+//! device attached to a case keeps its row. `prowl db purge` runs the same
+//! purge by hand.
+
+use crate::anomaly::EVENT_NEW_DEVICE_SPIKE;
+use crate::burst::EVENT_PROBE_BURST;
+use crate::config::Config;
+use crate::database::{Database, PurgeSummary};
+use crate::deauth::EVENT_DEAUTH_ATTACK;
+use crate::homenet::EVENT_MY_SSID_PROBE;
+use anyhow::Result;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Event type recorded in the events table for each purge that removed data
+pub const EVENT_PURGE: &str = "retention_purge";
+
+/// Events kept however old: alerts, and the purges' own record
+pub const ALERT_EVENTS: [&str; 5] = [
+    EVENT_PROBE_BURST,
+    EVENT_NEW_DEVICE_SPIKE,
+    EVENT_MY_SSID_PROBE,
+    EVENT_DEAUTH_ATTACK,
+    EVENT_PURGE,
+];
+
+const HOUR_SECS: i64 = 3600;
+
+/// Start of the oldest hour still kept raw when keeping `raw_hours`
+pub fn purge_cutoff(now: i64, raw_hours: u32) -> i64 {
+    let cutoff = now - raw_hours as i64 * HOUR_SECS;
+    cutoff - cutoff.rem_euclid(HOUR_SECS)
+}
+
+pub fn describe(summary: &PurgeSummary) -> String {
+    format!(
+        "Purged {} probe(s), {} device(s), {} event(s) and {} other row(s); {} hour(s) rolled up",
+        summary.probes, summary.devices, summary.events, summary.other, summary.hours_rolled_up
+    )
+}
+
+/// Roll up and purge everything older than `raw_hours`, and log the run as
+/// an event when it removed anything
+pub fn run_purge(db: &Database, raw_hours: u32, now: i64) -> Result<PurgeSummary> {
+    let summary = db.purge_raw_before(purge_cutoff(now, raw_hours), &ALERT_EVENTS)?;
+    if summary != PurgeSummary::default() {
+        let data = serde_json::json!({
+            "raw_hours": raw_hours,
+            "hours_rolled_up": summary.hours_rolled_up,
+            "probes": summary.probes,
+            "devices": summary.devices,
+            "events": summary.events,
+            "other": summary.other,
+        });
+        db.insert_event(now, EVENT_PURGE, &describe(&summary), Some(&data.to_string()))?;
+    }
+    Ok(summary)
+}
+
+/// Purge every `retention.interval_minutes` until `running` clears. Returns
+/// `None` when auto-purge is off.
+pub fn spawn_purge(config: &Config, running: Arc<AtomicBool>) -> Option<thread::JoinHandle<()>> {
+    if !config.retention.auto_purge {
+        return None;
+    }
+
+    let config = config.clone();
+    let interval = Duration::from_secs(config.retention.interval_minutes.max(1) as u64 * 60);
+    Some(thread::spawn(move || {
+        let db = match Database::open(&config.capture.database) {
+            Ok(db) => db,
+            Err(e) => {
+                error!("Retention purge could not open database: {}", e);
+                return;
+            }
+        };
+        info!("Raw probe data is purged after {} hours", config.retention.raw_hours);
+
+        while running.load(Ordering::SeqCst) {
+            let started = Instant::now();
+            match run_purge(&db, config.retention.raw_hours, chrono::Utc::now().timestamp()) {
+                Ok(summary) if summary.probes > 0 => info!("{}", describe(&summary)),
+                Ok(_) => {}
+                Err(e) => error!("Retention purge failed: {}", e),
+            }
+
+            while running.load(Ordering::SeqCst) && started.elapsed() < interval {
+                thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AnalysisConfig;
+    use crate::database::{CaseItem, DeviceLinkRecord, ProbeCapture, ProbeRollup};
+    use crate::homenet::MySsidWatch;
+
+    #[test]
+    fn test_purge_keeps_rollups_alerts_and_case_devices() {
+        let db = Database::open_in_memory().unwrap();
+        let now = 100 * HOUR_SECS + 1_800;
+        let old = 40 * HOUR_SECS;
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:00:00:01", "HomeNet", old + 10)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:00:00:01", "", old + 20)).unwrap();
+        db.insert_probe(&ProbeCapture::test("02:00:00:00:00:02", "Cafe", old + 30)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:00:00:03", "", old + 40)).unwrap();
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:00:00:03", "", now - 60)).unwrap();
+        db.insert_event(old, EVENT_PROBE_BURST, "burst", None).unwrap();
+        db.insert_event(old, "queue_overflow", "dropped 3", None).unwrap();
+
+        // What is kept per MAC goes with the device
+        db.set_device_label("AA:BB:CC:00:00:01", "neighbour", old).unwrap();
+        db.add_to_watchlist("AA:BB:CC:00:00:01", old).unwrap();
+        db.set_device_link(&DeviceLinkRecord {
+            mac_a: "AA:BB:CC:00:00:01".to_string(),
+            mac_b: "AA:BB:CC:00:00:03".to_string(),
+            verdict: "confirmed".to_string(),
+            confidence: 0.8,
+            evidence_json: "{}".to_string(),
+            note: None,
+            updated_at: old,
+        })
+        .unwrap();
+
+        // Attaching a device to a case confirms it
+        let case = db.create_case("stalker", None, old).unwrap();
+        db.add_case_item(case, &CaseItem::device("02:00:00:00:00:02", old)).unwrap();
+
+        assert_eq!(purge_cutoff(now, 48), 52 * HOUR_SECS);
+        let summary = run_purge(&db, 48, now).unwrap();
+        assert_eq!(summary.hours_rolled_up, 1);
+        assert_eq!(summary.probes, 4);
+        assert_eq!(summary.devices, 1);
+        assert_eq!(summary.events, 1);
+
+        let rollup = ProbeRollup {
+            hour_start: old,
+            devices: 3,
+            new_devices: 3,
+            randomized_devices: 3,
+            probes: 4,
+            directed_ssids: 2,
+        };
+        assert_eq!(db.get_probe_rollups(0, now).unwrap(), vec![rollup]);
+        assert!(db.get_device_by_mac("AA:BB:CC:00:00:01").unwrap().is_none());
+        assert!(db.get_device_by_mac("02:00:00:00:00:02").unwrap().is_some());
+        assert!(db.get_device_by_mac("AA:BB:CC:00:00:03").unwrap().is_some());
+        assert!(db.get_device_labels().unwrap().is_empty());
+        assert!(db.get_watchlist().unwrap().is_empty());
+        assert!(db.get_device_links("AA:BB:CC:00:00:03").unwrap().is_empty());
+        assert_eq!(db.get_events_since(0, Some(EVENT_PROBE_BURST)).unwrap().len(), 1);
+
+        // Nothing left to purge; a quiet run isn't logged
+        assert_eq!(run_purge(&db, 48, now).unwrap(), PurgeSummary::default());
+
+        // A probe imported late for a rolled-up hour adds to its counts
+        db.insert_probe(&ProbeCapture::test("AA:BB:CC:00:00:04", "", old + 50)).unwrap();
+        run_purge(&db, 48, now).unwrap();
+        let merged = ProbeRollup {
+            devices: 4,
+            new_devices: 4,
+            randomized_devices: 4,
+            probes: 5,
+            ..rollup
+        };
+        assert_eq!(db.get_probe_rollups(0, now).unwrap(), vec![merged]);
+    }
+
+    #[test]
+    fn test_purge_does_not_repeat_my_ssid_alerts() {
+        let db = Database::open_in_memory().unwrap();
+        let analysis = AnalysisConfig {
+            my_ssids: vec!["HomeNet".to_string()],
+            ..Config::default_config().analysis
+        };
+        let watch = MySsidWatch::new(&analysis).unwrap();
+        let now = 100 * HOUR_SECS;
+
+        // Stored and recorded as the capture writer does
+        let observe = |mac: &str, timestamp: i64| {
+            let probe = ProbeCapture::test(mac, "HomeNet", timestamp);
+            let new_device = db.insert_probe(&probe).unwrap();
+            let alert = watch.observe(&db, &probe, new_device).unwrap();
+            if let Some(alert) = &alert {
+                let data = serde_json::to_string(alert).unwrap();
+                db.insert_event(timestamp, EVENT_MY_SSID_PROBE, &alert.describe(), Some(&data)).unwrap();
+            }
+            alert
+        };
+
+        assert!(observe("AA:BB:CC:00:00:01", 40 * HOUR_SECS).is_some());
+        run_purge(&db, 48, now).unwrap();
+        assert!(db.get_device_by_mac("AA:BB:CC:00:00:01").unwrap().is_none());
+
+        // Back after the purge: known, so no second alert
+        assert!(observe("AA:BB:CC:00:00:01", now).is_none());
+        assert!(observe("AA:BB:CC:00:00:02", now).is_some());
+    }
+}
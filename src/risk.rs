@@ -0,0 +1,95 @@
+//! Live risk scores.
+//!
+//! `prowl analyze` scores devices only when asked, so a suspicious device
+//! surfaces at report time rather than while it is still around. Alongside
+//! capture a background thread re-scores every device seen in the last
+//! `risk.window_hours` each `risk.interval_minutes`, with the same analyzer
+//! and settings as `analyze`, and stores the scores for the TUI device table
+//! and `prowl list` to sort by. A device that leaves the window loses its
+//! score.
+
+use crate::analysis::SurveillanceAnalyzer;
+use crate::config::Config;
+use crate::database::Database;
+use anyhow::Result;
+use log::{debug, error};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Re-score every device seen within the risk window and store the scores.
+/// Returns how many devices were scored.
+pub fn update_risk_scores(db: &Database, config: &Config, now: i64) -> Result<usize> {
+    let analyzer = SurveillanceAnalyzer::new(
+        config.analysis.time_windows_minutes.clone(),
+        config.analysis.persistence_threshold,
+    )
+    .with_broadcast_only(config.analysis.broadcast_only, config.analysis.broadcast_only_weight)
+    .with_my_ssids(config.analysis.my_ssids.clone());
+
+    let start = now - config.risk.window_hours.max(1) as i64 * 3600;
+    let scores = analyzer.score_devices(db, start, now)?;
+    db.set_risk_scores(&scores, now)?;
+    Ok(scores.len())
+}
+
+/// Refresh risk scores until `running` clears. Returns `None` when risk
+/// scoring is disabled.
+pub fn spawn_risk_updater(config: &Config, running: Arc<AtomicBool>) -> Option<thread::JoinHandle<()>> {
+    if !config.risk.enabled {
+        return None;
+    }
+
+    let config = config.clone();
+    let interval = Duration::from_secs(config.risk.interval_minutes.max(1) as u64 * 60);
+    Some(thread::spawn(move || {
+        let db = match Database::open(&config.capture.database) {
+            Ok(db) => db,
+            Err(e) => {
+                error!("Risk scoring could not open database: {}", e);
+                return;
+            }
+        };
+
+        while running.load(Ordering::SeqCst) {
+            let started = Instant::now();
+            match update_risk_scores(&db, &config, chrono::Utc::now().timestamp()) {
+                Ok(scored) => debug!("Risk scores refreshed for {} device(s)", scored),
+                Err(e) => error!("Risk scoring failed: {}", e),
+            }
+
+            while running.load(Ordering::SeqCst) && started.elapsed() < interval {
+                thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ProbeCapture;
+
+    #[test]
+    fn test_scores_replace_previous_run() {
+        let db = Database::open_in_memory().unwrap();
+        let config = Config::default_config();
+        let now = 1_700_000_000;
+        for minute in 0..30 {
+            let probe = ProbeCapture::test("00:11:22:33:44:55", "CoffeeShop", now - minute * 60);
+            db.insert_probe(&probe).unwrap();
+        }
+        db.insert_probe(&ProbeCapture::test("00:11:22:33:44:66", "CoffeeShop", now - 10 * 86_400)).unwrap();
+
+        assert_eq!(update_risk_scores(&db, &config, now).unwrap(), 1);
+        let scores = db.get_risk_scores().unwrap();
+        assert!((0.0..=1.0).contains(&scores["00:11:22:33:44:55"]));
+        // Outside the window, so not scored
+        assert!(!scores.contains_key("00:11:22:33:44:66"));
+
+        // A day later neither device is in the window
+        assert_eq!(update_risk_scores(&db, &config, now + 2 * 86_400).unwrap(), 0);
+        assert!(db.get_risk_scores().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+//! Synthetic probe traffic for demos, UI development and training.
+//!
+//! A scenario mixes three kinds of devices: commuters that come and go,
+//! phones that rotate randomized MACs, and followers that stay in range the
+//! whole time and probe for distinctive networks. `SyntheticSource` plays the
+//! scenario live through the normal capture pipeline; `backfill` writes a few
+//! hours of history (with a moving GPS track) so analysis has something to
+//! find straight away.
+
+use crate::config::SimulationConfig;
+use crate::database::{Database, GpsStatus, ProbeCapture};
+use crate::source::{build_probe_request, PacketSource};
+use anyhow::Result;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const COMMUTER_SSIDS: &[&str] = &[
+    "eduroam", "Starbucks WiFi", "xfinitywifi", "NETGEAR42", "linksys", "ATT-WIFI-2210",
+    "Airport_Free_WiFi", "HOME-4A2F", "TP-Link_8C1E", "Guest", "Library Public",
+];
+
+const FOLLOWER_SSIDS: &[&str] = &["SurveillanceVan_5G", "Cellebrite-Lab", "HOME-8812-5G"];
+
+/// Real vendor OUIs so the vendor column has something to show
+const COMMUTER_OUIS: &[[u8; 3]] = &[
+    [0x00, 0x03, 0x93], // Apple
+    [0x00, 0x1A, 0x11], // Google
+    [0x00, 0x12, 0xFB], // Samsung
+    [0x00, 0x1D, 0x0F], // TP-Link
+    [0x00, 0x0D, 0x3A], // Microsoft
+];
+
+/// Small deterministic PRNG (xorshift64*) so scenarios are reproducible
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    /// Uniform in [0, 1)
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Commuter,
+    RandomizedPhone,
+    Follower,
+}
+
+#[derive(Debug, Clone)]
+struct Persona {
+    kind: Kind,
+    mac: [u8; 6],
+    ssids: Vec<&'static str>,
+    /// Base RSSI; each probe adds jitter
+    signal_dbm: i8,
+    present: bool,
+    /// Seconds until the MAC rotates (randomized phones only)
+    rotate_in: i64,
+}
+
+/// One generated probe request
+#[derive(Debug, Clone)]
+pub struct SyntheticProbe {
+    pub mac: String,
+    pub ssid: String,
+    pub signal_dbm: i8,
+}
+
+/// Population of synthetic devices and their behaviour over time
+pub struct Scenario {
+    rng: Rng,
+    personas: Vec<Persona>,
+    last_step: Option<i64>,
+}
+
+impl Scenario {
+    pub fn new(config: &SimulationConfig) -> Self {
+        let mut rng = Rng::new(config.seed);
+        let mut personas = Vec::new();
+
+        for _ in 0..config.commuters {
+            let oui = COMMUTER_OUIS[rng.below(COMMUTER_OUIS.len())];
+            let mut mac = [oui[0], oui[1], oui[2], 0, 0, 0];
+            for b in &mut mac[3..] {
+                *b = rng.next_u64() as u8;
+            }
+            let ssids = (0..1 + rng.below(3))
+                .map(|_| COMMUTER_SSIDS[rng.below(COMMUTER_SSIDS.len())])
+                .collect();
+            personas.push(Persona {
+                kind: Kind::Commuter,
+                mac,
+                ssids,
+                signal_dbm: -55 - rng.below(30) as i8,
+                present: rng.chance(0.3),
+                rotate_in: 0,
+            });
+        }
+
+        for _ in 0..config.randomized_phones {
+            let ssids = (0..rng.below(2))
+                .map(|_| COMMUTER_SSIDS[rng.below(COMMUTER_SSIDS.len())])
+                .collect();
+            personas.push(Persona {
+                kind: Kind::RandomizedPhone,
+                mac: random_local_mac(&mut rng),
+                ssids,
+                signal_dbm: -50 - rng.below(25) as i8,
+                present: true,
+                rotate_in: 300 + rng.below(600) as i64,
+            });
+        }
+
+        for i in 0..config.followers {
+            let mut mac = [0x00, 0x0D, 0x3A, 0xF0, 0x11, 0x00];
+            mac[5] = i as u8;
+            personas.push(Persona {
+                kind: Kind::Follower,
+                mac,
+                ssids: FOLLOWER_SSIDS.to_vec(),
+                signal_dbm: -48,
+                present: true,
+                rotate_in: 0,
+            });
+        }
+
+        Scenario {
+            rng,
+            personas,
+            last_step: None,
+        }
+    }
+
+    /// Advance arrivals, departures and MAC rotation to time `now` (seconds)
+    fn step(&mut self, now: i64) {
+        let elapsed = match self.last_step {
+            Some(last) if now <= last => return,
+            Some(last) => now - last,
+            None => 1,
+        };
+        self.last_step = Some(now);
+
+        for persona in &mut self.personas {
+            match persona.kind {
+                // Commuters stay around ten minutes on average and return
+                // roughly every half hour
+                Kind::Commuter => {
+                    for _ in 0..elapsed.min(3600) {
+                        let flip = if persona.present { 1.0 / 600.0 } else { 1.0 / 1800.0 };
+                        if self.rng.chance(flip) {
+                            persona.present = !persona.present;
+                        }
+                    }
+                }
+                Kind::RandomizedPhone => {
+                    persona.rotate_in -= elapsed;
+                    if persona.rotate_in <= 0 {
+                        persona.mac = random_local_mac(&mut self.rng);
+                        persona.rotate_in = 300 + self.rng.below(600) as i64;
+                    }
+                }
+                Kind::Follower => {}
+            }
+        }
+    }
+
+    /// Generate the next probe at time `now`
+    pub fn next_probe(&mut self, now: i64) -> SyntheticProbe {
+        self.step(now);
+
+        let present: Vec<usize> = (0..self.personas.len())
+            .filter(|&i| self.personas[i].present)
+            .collect();
+        let idx = if present.is_empty() {
+            self.rng.below(self.personas.len())
+        } else {
+            present[self.rng.below(present.len())]
+        };
+
+        let jitter = self.rng.below(9) as i8 - 4;
+        let persona = &self.personas[idx];
+        // Most probes are broadcast; some name a remembered network
+        let ssid = if !persona.ssids.is_empty() && self.rng.chance(0.6) {
+            persona.ssids[self.rng.below(persona.ssids.len())].to_string()
+        } else {
+            String::new()
+        };
+
+        SyntheticProbe {
+            mac: format_mac(&persona.mac),
+            ssid,
+            signal_dbm: persona.signal_dbm.saturating_add(jitter),
+        }
+    }
+
+    /// Number of probes a present device population emits per second
+    fn probes_per_sec(&self, base: f64) -> f64 {
+        let present = self.personas.iter().filter(|p| p.present).count().max(1);
+        base * present as f64 / self.personas.len().max(1) as f64
+    }
+}
+
+/// Live capture source backed by a `Scenario`
+pub struct SyntheticSource {
+    scenario: Scenario,
+    probes_per_sec: f64,
+    timeout: Duration,
+    next_at: Instant,
+    current: Vec<u8>,
+}
+
+impl SyntheticSource {
+    pub fn new(config: &SimulationConfig, timeout_ms: i32) -> Self {
+        SyntheticSource {
+            scenario: Scenario::new(config),
+            probes_per_sec: config.probes_per_sec.max(0.1),
+            timeout: Duration::from_millis(timeout_ms.max(1) as u64),
+            next_at: Instant::now(),
+            current: Vec::new(),
+        }
+    }
+}
+
+impl PacketSource for SyntheticSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        let now = Instant::now();
+        if self.next_at > now {
+            let wait = self.next_at - now;
+            if wait > self.timeout {
+                std::thread::sleep(self.timeout);
+                return Ok(None);
+            }
+            std::thread::sleep(wait);
+        }
+        self.next_at = Instant::now() + Duration::from_secs_f64(1.0 / self.probes_per_sec);
+
+        let probe = self.scenario.next_probe(unix_now());
+        let mac = parse_mac_bytes(&probe.mac);
+        self.current = build_probe_request(mac, &probe.ssid, Some(probe.signal_dbm));
+        Ok(Some(self.current.as_slice()))
+    }
+
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+}
+
+/// Write `hours` of synthetic history ending at `now` straight to the
+/// database. The sensor moves along a straight track so followers show up
+/// at many locations. Returns the number of probes written.
+pub fn backfill(db: &Database, config: &SimulationConfig, hours: u32, now: i64) -> Result<usize> {
+    let mut scenario = Scenario::new(config);
+    let start = now - hours as i64 * 3600;
+    let (origin_lat, origin_lon) = (37.7749, -122.4194);
+
+    let mut written = 0;
+    let mut t = start;
+    while t < now {
+        // Probe bursts every 10 seconds, scaled by how many devices are around
+        let burst = (scenario.probes_per_sec(config.probes_per_sec) * 10.0).round().max(1.0) as usize;
+        let progress = (t - start) as f64 / 3600.0;
+        let position = (origin_lat + progress * 0.01, origin_lon + progress * 0.015);
+
+        for _ in 0..burst {
+            let probe = scenario.next_probe(t);
+            db.insert_probe(&ProbeCapture {
+                mac: probe.mac,
+                ssid: probe.ssid,
+                timestamp: t,
+                timestamp_micros: 0,
+                lat: Some(position.0),
+                lon: Some(position.1),
+                signal_dbm: Some(probe.signal_dbm as i32),
+                channel: Some([1u8, 6, 11][written % 3]),
+                distance_m: None,
+                gps_status: GpsStatus::Fix,
+                bssid: None,
+                sequence_number: None,
+                capabilities: None,
+            })?;
+            written += 1;
+        }
+        t += 10;
+    }
+
+    Ok(written)
+}
+
+fn random_local_mac(rng: &mut Rng) -> [u8; 6] {
+    let mut mac = [0u8; 6];
+    for b in &mut mac {
+        *b = rng.next_u64() as u8;
+    }
+    // Locally administered, unicast
+    mac[0] = (mac[0] | 0x02) & 0xFE;
+    mac
+}
+
+fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn parse_mac_bytes(mac: &str) -> [u8; 6] {
+    let mut out = [0u8; 6];
+    for (i, part) in mac.split(':').take(6).enumerate() {
+        out[i] = u8::from_str_radix(part, 16).unwrap_or(0);
+    }
+    out
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oui::is_randomized_mac;
+
+    fn config() -> SimulationConfig {
+        SimulationConfig {
+            commuters: 10,
+            randomized_phones: 3,
+            followers: 1,
+            probes_per_sec: 2.0,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn test_scenario_is_deterministic() {
+        let mut a = Scenario::new(&config());
+        let mut b = Scenario::new(&config());
+        for t in 0..100 {
+            assert_eq!(a.next_probe(t).mac, b.next_probe(t).mac);
+        }
+    }
+
+    #[test]
+    fn test_randomized_phones_rotate_macs() {
+        let mut scenario = Scenario::new(&config());
+        let mut randomized = std::collections::HashSet::new();
+        for t in (0..7200).step_by(5) {
+            let probe = scenario.next_probe(t);
+            if is_randomized_mac(&probe.mac) {
+                randomized.insert(probe.mac);
+            }
+        }
+        assert!(randomized.len() > 3);
+    }
+
+    #[test]
+    fn test_backfill_flags_follower() {
+        let db = Database::open_in_memory().unwrap();
+        let now = 1_700_000_000;
+        let written = backfill(&db, &config(), 1, now).unwrap();
+        assert!(written > 0);
+
+        let follower = db.get_device_by_mac("00:0D:3A:F0:11:00").unwrap().unwrap();
+        assert!(db.get_device_location_count(follower.id).unwrap() > 1);
+    }
+}
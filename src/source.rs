@@ -0,0 +1,1156 @@
+//! Packet capture backends.
+//!
+//! `pcap` uses libpcap and supports kernel BPF filtering. `afpacket` reads
+//! straight from a Linux AF_PACKET socket so static musl builds for routers
+//! can capture without linking libpcap at all; filtering happens in software.
+//! `mmap` uses PACKET_MMAP rx rings shared with the kernel, optionally spread
+//! over several sockets with per-CPU fanout, for busy environments.
+//! `PcapFileSource` reads a saved pcap/pcapng file (tcpdump, Kismet) for
+//! `capture --from-file`, keeping the original packet timestamps.
+//! On Windows, `pcap` captures through Npcap in monitor mode; the adapter can
+//! be given by its friendly name.
+//! `kismet` accepts frames from Kismet remote capture sensors over TCP, and
+//! `helper` from a privileged `prowl capture-helper` over a UNIX socket.
+//! With `second_interface` set, `MergedSource` reads both adapters at once.
+//! `ScriptedSource` replays synthetic probe requests for tests and
+//! `capture --simulate`; `simulated` generates live demo traffic. Neither
+//! needs hardware or root.
+
+use crate::config::{CaptureBackend, CaptureConfig};
+#[cfg(unix)]
+use crate::helper::HelperSource;
+use crate::kismet::KismetSource;
+use crate::simulate::SyntheticSource;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "pcap")]
+use log::{debug, warn};
+
+/// BPF filter for management frames type 0 subtype 4 (probe request)
+pub const PROBE_REQUEST_FILTER: &str = "type mgt subtype probe-req";
+/// Added to the filter when beacon capture is on
+pub const BEACON_FILTER: &str = "type mgt subtype beacon or type mgt subtype probe-resp";
+/// Added to the filter when deauth detection is on
+pub const DEAUTH_FILTER: &str = "type mgt subtype deauth or type mgt subtype disassoc";
+
+/// Capture filter: the configured `bpf_filter`, widened with whatever the
+/// beacon and deauth options need. An empty `bpf_filter` captures everything,
+/// and so does measuring utilization, which has to see every frame.
+pub fn capture_filter(config: &CaptureConfig) -> String {
+    if config.measure_utilization {
+        return String::new();
+    }
+    let base = config.bpf_filter.trim();
+    let mut extra = Vec::new();
+    if config.capture_beacons {
+        extra.push(BEACON_FILTER);
+    }
+    if config.detect_deauth {
+        extra.push(DEAUTH_FILTER);
+    }
+    if base.is_empty() || extra.is_empty() {
+        return base.to_string();
+    }
+    // pcap gives `and` and `or` equal precedence, so keep the user's
+    // expression together
+    format!("({}) or {}", base, extra.join(" or "))
+}
+
+/// Unix time at which a frame was captured, to the microsecond
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTime {
+    pub secs: i64,
+    pub micros: u32,
+}
+
+impl FrameTime {
+    pub fn as_millis(&self) -> i64 {
+        self.secs * 1000 + (self.micros / 1000) as i64
+    }
+
+    pub fn now() -> Self {
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        FrameTime {
+            secs: elapsed.as_secs() as i64,
+            micros: elapsed.subsec_micros(),
+        }
+    }
+
+    #[cfg(feature = "pcap")]
+    fn from_timeval(ts: &libc::timeval) -> Self {
+        FrameTime {
+            secs: ts.tv_sec as i64,
+            micros: (ts.tv_usec as u32).min(999_999),
+        }
+    }
+}
+
+/// Frame counters kept by the kernel or libpcap for a live capture, totals
+/// since the source was opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DropStats {
+    /// Frames that reached the capture socket
+    pub received: u64,
+    /// Frames dropped because the capture buffer was full
+    pub dropped: u64,
+    /// Frames dropped by the interface or driver
+    pub if_dropped: u64,
+}
+
+impl DropStats {
+    pub fn total_dropped(&self) -> u64 {
+        self.dropped + self.if_dropped
+    }
+}
+
+/// A source of raw 802.11 frames (radiotap header included)
+pub trait PacketSource: Send {
+    /// Read the next frame. `Ok(None)` means the read timed out.
+    fn next_packet(&mut self) -> Result<Option<&[u8]>>;
+
+    /// Short backend name for logging
+    fn name(&self) -> &'static str;
+
+    /// Like `next_packet`, plus the time the frame was captured as reported
+    /// by the kernel or capture file. Sources without one return `None` and
+    /// the frame is stamped on arrival.
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        Ok(self.next_packet()?.map(|data| (data, None)))
+    }
+
+    /// Kernel receive and drop counters; `None` for sources without any
+    /// (files, scripts, simulation)
+    fn drop_stats(&mut self) -> Option<DropStats> {
+        None
+    }
+}
+
+/// Returned by sources with a finite script once every frame has been read
+#[derive(Debug, thiserror::Error)]
+#[error("end of capture script")]
+pub struct SourceExhausted;
+
+/// Open a capture source on the configured interface using the configured
+/// backend, or on both interfaces when a second one is set
+pub fn open_source(
+    config: &CaptureConfig,
+    filter: Option<&str>,
+    timeout_ms: i32,
+) -> Result<Box<dyn PacketSource>> {
+    match config.second_interface.as_deref().filter(|_| config.backend.has_radio()) {
+        Some(second) => {
+            let first = open_interface(config, &config.interface, filter, timeout_ms)?;
+            let second = open_interface(config, second, filter, timeout_ms)
+                .with_context(|| format!("Failed to open the second interface {}", second))?;
+            Ok(Box::new(MergedSource::new(vec![first, second], timeout_ms)?))
+        }
+        None => open_interface(config, &config.interface, filter, timeout_ms),
+    }
+}
+
+fn open_interface(
+    config: &CaptureConfig,
+    interface: &str,
+    filter: Option<&str>,
+    timeout_ms: i32,
+) -> Result<Box<dyn PacketSource>> {
+    match config.backend {
+        #[cfg(feature = "pcap")]
+        CaptureBackend::Pcap => Ok(Box::new(PcapSource::open(interface, filter, timeout_ms)?)),
+        #[cfg(not(feature = "pcap"))]
+        CaptureBackend::Pcap => {
+            let _ = (interface, filter, timeout_ms);
+            anyhow::bail!(
+                "This build was compiled without libpcap support. \
+                Set \"backend\": \"afpacket\" in the capture config."
+            )
+        }
+        #[cfg(target_os = "linux")]
+        CaptureBackend::AfPacket => Ok(Box::new(afpacket::AfPacketSource::open(interface, timeout_ms)?)),
+        #[cfg(target_os = "linux")]
+        CaptureBackend::Mmap => Ok(Box::new(afpacket::MmapSource::open(
+            interface,
+            config.mmap_ring_mb,
+            config.mmap_fanout.unwrap_or_else(default_fanout),
+            timeout_ms,
+        )?)),
+        #[cfg(not(target_os = "linux"))]
+        CaptureBackend::AfPacket | CaptureBackend::Mmap => {
+            let _ = interface;
+            anyhow::bail!("The {:?} capture backend is only available on Linux", config.backend)
+        }
+        CaptureBackend::Simulated => Ok(Box::new(SyntheticSource::new(&config.simulation, timeout_ms))),
+        CaptureBackend::Kismet => Ok(Box::new(KismetSource::open(config, timeout_ms)?)),
+        #[cfg(unix)]
+        CaptureBackend::Helper => Ok(Box::new(HelperSource::open(config, timeout_ms)?)),
+        #[cfg(not(unix))]
+        CaptureBackend::Helper => anyhow::bail!("The capture helper needs UNIX sockets"),
+    }
+}
+
+/// Frames read on the reading thread's behalf, or the error that stopped it
+type ReadResult = Result<(Vec<u8>, Option<FrameTime>)>;
+
+/// Frames from several live sources, each read on a thread of its own so a
+/// quiet adapter never holds up a busy one. Frames arrive in the order they
+/// were read; their timestamps say when each was captured.
+pub struct MergedSource {
+    frames: mpsc::Receiver<ReadResult>,
+    current: Vec<u8>,
+    timeout: Duration,
+    /// Latest counters of each source, refreshed by its reader
+    drops: Vec<Arc<Mutex<Option<DropStats>>>>,
+    name: &'static str,
+    running: Arc<AtomicBool>,
+}
+
+/// Frames buffered between the readers and the capture loop
+const MERGED_QUEUE: usize = 4096;
+/// How often readers refresh their source's drop counters
+const DROP_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+impl MergedSource {
+    pub fn new(sources: Vec<Box<dyn PacketSource>>, timeout_ms: i32) -> Result<Self> {
+        let (tx, frames) = mpsc::sync_channel(MERGED_QUEUE);
+        let running = Arc::new(AtomicBool::new(true));
+        let name = sources.first().map(|s| s.name()).unwrap_or("merged");
+        let mut drops = Vec::new();
+
+        for (idx, mut source) in sources.into_iter().enumerate() {
+            let tx = tx.clone();
+            let running = running.clone();
+            let counters = Arc::new(Mutex::new(source.drop_stats()));
+            drops.push(counters.clone());
+            thread::Builder::new()
+                .name(format!("capture-reader-{}", idx))
+                .spawn(move || {
+                    let mut refreshed = Instant::now();
+                    while running.load(Ordering::Relaxed) {
+                        let frame = match source.next_timestamped() {
+                            Ok(Some((data, time))) => Some(Ok((data.to_vec(), time))),
+                            Ok(None) => None,
+                            Err(e) => Some(Err(e)),
+                        };
+                        let failed = frame.as_ref().is_some_and(|f| f.is_err());
+                        if let Some(frame) = frame {
+                            if tx.send(frame).is_err() || failed {
+                                break;
+                            }
+                        }
+                        if refreshed.elapsed() >= DROP_STATS_INTERVAL {
+                            if let Ok(mut counters) = counters.lock() {
+                                *counters = source.drop_stats();
+                            }
+                            refreshed = Instant::now();
+                        }
+                    }
+                })
+                .context("Failed to start a capture reader")?;
+        }
+
+        Ok(MergedSource {
+            frames,
+            current: Vec::new(),
+            timeout: Duration::from_millis(timeout_ms.max(1) as u64),
+            drops,
+            name,
+            running,
+        })
+    }
+}
+
+impl PacketSource for MergedSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.next_timestamped()?.map(|(data, _)| data))
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        match self.frames.recv_timeout(self.timeout) {
+            Ok(frame) => {
+                let (data, time) = frame?;
+                self.current = data;
+                Ok(Some((&self.current, time)))
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => anyhow::bail!("Every capture reader has stopped"),
+        }
+    }
+
+    fn drop_stats(&mut self) -> Option<DropStats> {
+        let counters: Vec<DropStats> = self.drops.iter().filter_map(|d| *d.lock().ok()?).collect();
+        if counters.is_empty() {
+            return None;
+        }
+        Some(counters.iter().fold(DropStats::default(), |sum, c| DropStats {
+            received: sum.received + c.received,
+            dropped: sum.dropped + c.dropped,
+            if_dropped: sum.if_dropped + c.if_dropped,
+        }))
+    }
+}
+
+impl Drop for MergedSource {
+    fn drop(&mut self) {
+        // Readers notice within one read timeout
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+/// One fanout socket per CPU, capped so small routers don't over-allocate rings
+#[cfg(target_os = "linux")]
+fn default_fanout() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(4)
+}
+
+/// One scripted probe request
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptedProbe {
+    pub mac: String,
+    #[serde(default)]
+    pub ssid: String,
+    #[serde(default)]
+    pub signal_dbm: Option<i8>,
+    /// Target AP for a directed probe (broadcast if unset)
+    #[serde(default)]
+    pub bssid: Option<String>,
+    /// Pause before this frame is delivered
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Replays synthetic radiotap + probe request frames
+pub struct ScriptedSource {
+    frames: VecDeque<(Vec<u8>, Duration)>,
+    current: Vec<u8>,
+}
+
+impl ScriptedSource {
+    pub fn new(probes: &[ScriptedProbe]) -> Result<Self> {
+        let frames = probes
+            .iter()
+            .map(|p| {
+                let mac = parse_mac(&p.mac)?;
+                let bssid = p.bssid.as_deref().map(parse_mac).transpose()?;
+                Ok((
+                    build_directed_probe_request(mac, &p.ssid, p.signal_dbm, bssid),
+                    Duration::from_millis(p.delay_ms),
+                ))
+            })
+            .collect::<Result<VecDeque<_>>>()?;
+
+        Ok(ScriptedSource {
+            frames,
+            current: Vec::new(),
+        })
+    }
+
+    /// Load a script with one JSON `ScriptedProbe` per line
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .with_context(|| format!("Failed to read capture script {:?}", path.as_ref()))?;
+
+        let probes = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| {
+                serde_json::from_str(line).with_context(|| format!("Invalid script line {}", i + 1))
+            })
+            .collect::<Result<Vec<ScriptedProbe>>>()?;
+
+        Self::new(&probes)
+    }
+
+    /// Frames not yet delivered
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+impl PacketSource for ScriptedSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        let (frame, delay) = self.frames.pop_front().ok_or(SourceExhausted)?;
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        self.current = frame;
+        Ok(Some(self.current.as_slice()))
+    }
+
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+}
+
+fn parse_mac(mac: &str) -> Result<[u8; 6]> {
+    let bytes: Vec<u8> = mac
+        .split([':', '-'])
+        .map(|b| u8::from_str_radix(b, 16))
+        .collect::<std::result::Result<_, _>>()
+        .with_context(|| format!("Invalid MAC address: {}", mac))?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid MAC address: {}", mac))
+}
+
+/// Build a radiotap-wrapped 802.11 probe request from `mac` for `ssid`
+/// (empty for a broadcast probe)
+pub fn build_probe_request(mac: [u8; 6], ssid: &str, signal_dbm: Option<i8>) -> Vec<u8> {
+    build_directed_probe_request(mac, ssid, signal_dbm, None)
+}
+
+/// Like `build_probe_request`, but addressed to `bssid` (DA and BSSID)
+/// when given
+pub fn build_directed_probe_request(
+    mac: [u8; 6],
+    ssid: &str,
+    signal_dbm: Option<i8>,
+    bssid: Option<[u8; 6]>,
+) -> Vec<u8> {
+    let target = bssid.unwrap_or([0xff; 6]);
+    let mut frame = Vec::with_capacity(64 + ssid.len());
+
+    // Radiotap: version, pad, length, present flags (bit 5 = dBm antenna signal)
+    match signal_dbm {
+        Some(signal) => {
+            frame.extend_from_slice(&[0, 0, 9, 0]);
+            frame.extend_from_slice(&(1u32 << 5).to_le_bytes());
+            frame.push(signal as u8);
+        }
+        None => {
+            frame.extend_from_slice(&[0, 0, 8, 0]);
+            frame.extend_from_slice(&0u32.to_le_bytes());
+        }
+    }
+
+    // Management header: frame control (probe request), duration, DA, SA, BSSID, seq
+    frame.extend_from_slice(&[0x40, 0x00, 0x00, 0x00]);
+    frame.extend_from_slice(&target);
+    frame.extend_from_slice(&mac);
+    frame.extend_from_slice(&target);
+    frame.extend_from_slice(&[0x00, 0x00]);
+
+    // SSID element
+    let ssid = &ssid.as_bytes()[..ssid.len().min(32)];
+    frame.push(0);
+    frame.push(ssid.len() as u8);
+    frame.extend_from_slice(ssid);
+
+    // Supported rates: 1, 2, 5.5, 11, 6, 9, 12, 18 Mbps
+    frame.extend_from_slice(&[1, 8, 0x82, 0x84, 0x8b, 0x96, 0x0c, 0x12, 0x18, 0x24]);
+
+    frame
+}
+
+#[cfg(feature = "pcap")]
+pub struct PcapSource {
+    cap: pcap::Capture<pcap::Active>,
+}
+
+#[cfg(feature = "pcap")]
+impl PcapSource {
+    pub fn open(interface: &str, filter: Option<&str>, timeout_ms: i32) -> Result<Self> {
+        // Npcap names devices by GUID; accept the adapter's friendly name too
+        #[cfg(windows)]
+        let device = crate::npcap::pcap_device(interface);
+        #[cfg(not(windows))]
+        let device = interface.to_string();
+
+        debug!("Opening pcap capture on {}...", device);
+        let inactive = pcap::Capture::from_device(device.as_str())
+            .map_err(|e| anyhow::anyhow!("Failed to open capture device: {}", e))?
+            .promisc(true)
+            .snaplen(65535)
+            .timeout(timeout_ms);
+        // Npcap switches the adapter to monitor mode itself; on Linux the
+        // interface already is one and rfmon would be refused
+        #[cfg(windows)]
+        let inactive = inactive.rfmon(true);
+        let mut cap = inactive
+            .open()
+            .map_err(|e| anyhow::anyhow!("Failed to activate capture: {}", e))?;
+
+        // Npcap offers bare 802.11 first; the parser expects radiotap
+        #[cfg(windows)]
+        if let Err(e) = cap.set_datalink(pcap::Linktype(DLT_IEEE802_11_RADIO)) {
+            warn!("Failed to select radiotap headers on {}: {}", device, e);
+        }
+
+        if let Some(filter) = filter {
+            if let Err(e) = cap.filter(filter, true) {
+                warn!("Failed to set BPF filter, will filter in software: {}", e);
+            }
+        }
+
+        Ok(PcapSource { cap })
+    }
+}
+
+#[cfg(feature = "pcap")]
+impl PacketSource for PcapSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        match self.cap.next_packet() {
+            Ok(packet) => Ok(Some(packet.data)),
+            Err(pcap::Error::TimeoutExpired) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "pcap"
+    }
+
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        match self.cap.next_packet() {
+            Ok(packet) => Ok(Some((packet.data, Some(FrameTime::from_timeval(&packet.header.ts))))),
+            Err(pcap::Error::TimeoutExpired) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn drop_stats(&mut self) -> Option<DropStats> {
+        let stats = self.cap.stats().ok()?;
+        Some(DropStats {
+            received: stats.received as u64,
+            dropped: stats.dropped as u64,
+            if_dropped: stats.if_dropped as u64,
+        })
+    }
+}
+
+/// Radiotap-wrapped 802.11 (tcpdump -i <monitor iface>)
+#[cfg(feature = "pcap")]
+const DLT_IEEE802_11_RADIO: i32 = 127;
+/// Bare 802.11 frames without a radiotap header
+#[cfg(feature = "pcap")]
+const DLT_IEEE802_11: i32 = 105;
+
+/// Reads frames from a saved pcap or pcapng file
+#[cfg(feature = "pcap")]
+pub struct PcapFileSource {
+    cap: pcap::Capture<pcap::Offline>,
+    /// Bare 802.11 frames get an empty radiotap header so the parser sees
+    /// the same layout as live captures
+    wrap_radiotap: bool,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "pcap")]
+impl PcapFileSource {
+    pub fn open<P: AsRef<Path>>(path: P, filter: Option<&str>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut cap = pcap::Capture::from_file(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open capture file {:?}: {}", path, e))?;
+
+        let wrap_radiotap = match cap.get_datalink().0 {
+            DLT_IEEE802_11_RADIO => false,
+            DLT_IEEE802_11 => true,
+            other => anyhow::bail!(
+                "Capture file {:?} has link type {}; expected 802.11 with or without radiotap",
+                path,
+                other
+            ),
+        };
+
+        if let Some(filter) = filter {
+            if let Err(e) = cap.filter(filter, true) {
+                warn!("Failed to set BPF filter, will filter in software: {}", e);
+            }
+        }
+
+        Ok(PcapFileSource {
+            cap,
+            wrap_radiotap,
+            buf: Vec::new(),
+        })
+    }
+}
+
+#[cfg(feature = "pcap")]
+impl PacketSource for PcapFileSource {
+    fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+        Ok(self.next_timestamped()?.map(|(data, _)| data))
+    }
+
+    fn name(&self) -> &'static str {
+        "pcap-file"
+    }
+
+    fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+        let packet = match self.cap.next_packet() {
+            Ok(packet) => packet,
+            Err(pcap::Error::NoMorePackets) => return Err(SourceExhausted.into()),
+            Err(e) => return Err(e.into()),
+        };
+        let timestamp = Some(FrameTime::from_timeval(&packet.header.ts));
+
+        if !self.wrap_radiotap {
+            return Ok(Some((packet.data, timestamp)));
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(&[0, 0, 8, 0, 0, 0, 0, 0]);
+        self.buf.extend_from_slice(packet.data);
+        Ok(Some((self.buf.as_slice(), timestamp)))
+    }
+}
+
+/// Open a saved capture file for replay
+pub fn open_file_source<P: AsRef<Path>>(path: P, filter: Option<&str>) -> Result<Box<dyn PacketSource>> {
+    #[cfg(feature = "pcap")]
+    {
+        Ok(Box::new(PcapFileSource::open(path, filter)?))
+    }
+    #[cfg(not(feature = "pcap"))]
+    {
+        let _ = (path.as_ref(), filter);
+        anyhow::bail!("This build was compiled without libpcap support, which is needed to read capture files")
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub mod afpacket {
+    use super::{DropStats, FrameTime, PacketSource};
+    use anyhow::{Context, Result};
+    use log::debug;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    const ETH_P_ALL: u16 = 0x0003;
+    const SNAPLEN: usize = 65535;
+
+    /// Capture from a raw AF_PACKET socket bound to a single interface
+    pub struct AfPacketSource {
+        fd: OwnedFd,
+        buf: Vec<u8>,
+        drops: DropStats,
+    }
+
+    /// Kernel ifindex of `interface`
+    fn interface_index(interface: &str) -> Result<u32> {
+        let ifname = CString::new(interface).context("Invalid interface name")?;
+        let ifindex = unsafe { libc::if_nametoindex(ifname.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error())
+                .with_context(|| format!("Interface {} not found", interface));
+        }
+        Ok(ifindex)
+    }
+
+    /// PACKET_FANOUT group id for process `pid` on interface `ifindex`. A
+    /// group is bound to one device, so a process capturing on two adapters
+    /// needs a group for each.
+    pub fn fanout_group_id(pid: u32, ifindex: u32) -> u16 {
+        (pid as u16) ^ (ifindex as u16).wrapping_mul(0x9e37)
+    }
+
+    /// Create an AF_PACKET socket bound to `interface`
+    fn open_bound_socket(interface: &str) -> Result<OwnedFd> {
+        let ifindex = interface_index(interface)?;
+
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_PACKET,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                ETH_P_ALL.to_be() as i32,
+            )
+        };
+        if raw < 0 {
+            return Err(io::Error::last_os_error())
+                .context("Failed to open AF_PACKET socket (requires CAP_NET_RAW)");
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_ll = unsafe { std::mem::zeroed() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = ETH_P_ALL.to_be();
+        addr.sll_ifindex = ifindex as i32;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_ll as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error())
+                .with_context(|| format!("Failed to bind AF_PACKET socket to {}", interface));
+        }
+
+        debug!("AF_PACKET socket bound to {} (ifindex {})", interface, ifindex);
+        Ok(fd)
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct TpacketStats {
+        tp_packets: u32,
+        tp_drops: u32,
+    }
+
+    /// Add the socket's counters to `totals`. The kernel resets them on every
+    /// read, so they have to be accumulated here.
+    fn add_packet_statistics(fd: &OwnedFd, totals: &mut DropStats) -> io::Result<()> {
+        let mut stats = TpacketStats::default();
+        let mut len = std::mem::size_of::<TpacketStats>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                fd.as_raw_fd(),
+                SOL_PACKET,
+                PACKET_STATISTICS,
+                &mut stats as *mut TpacketStats as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // tp_packets already includes the drops
+        totals.received += stats.tp_packets as u64;
+        totals.dropped += stats.tp_drops as u64;
+        Ok(())
+    }
+
+    fn set_sockopt<T>(fd: &OwnedFd, level: i32, name: i32, value: &T) -> io::Result<()> {
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                level,
+                name,
+                value as *const T as *const libc::c_void,
+                std::mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    impl AfPacketSource {
+        pub fn open(interface: &str, timeout_ms: i32) -> Result<Self> {
+            let fd = open_bound_socket(interface)?;
+
+            let timeout = libc::timeval {
+                tv_sec: (timeout_ms / 1000) as libc::time_t,
+                tv_usec: ((timeout_ms % 1000) * 1000) as libc::suseconds_t,
+            };
+            set_sockopt(&fd, libc::SOL_SOCKET, libc::SO_RCVTIMEO, &timeout)
+                .context("Failed to set socket read timeout")?;
+
+            Ok(AfPacketSource {
+                fd,
+                buf: vec![0u8; SNAPLEN],
+                drops: DropStats::default(),
+            })
+        }
+    }
+
+    impl PacketSource for AfPacketSource {
+        fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+            let n = unsafe {
+                libc::recv(
+                    self.fd.as_raw_fd(),
+                    self.buf.as_mut_ptr() as *mut libc::c_void,
+                    self.buf.len(),
+                    0,
+                )
+            };
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                return match err.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted => {
+                        Ok(None)
+                    }
+                    _ => Err(err.into()),
+                };
+            }
+            Ok(Some(&self.buf[..n as usize]))
+        }
+
+        fn name(&self) -> &'static str {
+            "afpacket"
+        }
+
+        fn drop_stats(&mut self) -> Option<DropStats> {
+            add_packet_statistics(&self.fd, &mut self.drops).ok()?;
+            Some(self.drops)
+        }
+    }
+
+    // PACKET_MMAP (TPACKET_V2) definitions from <linux/if_packet.h>
+    const SOL_PACKET: i32 = 263;
+    const PACKET_RX_RING: i32 = 5;
+    const PACKET_STATISTICS: i32 = 6;
+    const PACKET_VERSION: i32 = 10;
+    const PACKET_FANOUT: i32 = 18;
+    const PACKET_FANOUT_CPU: u32 = 2;
+    const TPACKET_V2: i32 = 1;
+    const TP_STATUS_KERNEL: u32 = 0;
+    const TP_STATUS_USER: u32 = 1;
+
+    const FRAME_SIZE: usize = 4096;
+    const BLOCK_SIZE: usize = FRAME_SIZE * 16;
+
+    #[repr(C)]
+    struct TpacketReq {
+        tp_block_size: u32,
+        tp_block_nr: u32,
+        tp_frame_size: u32,
+        tp_frame_nr: u32,
+    }
+
+    #[repr(C)]
+    #[allow(dead_code)]
+    struct Tpacket2Hdr {
+        tp_status: u32,
+        tp_len: u32,
+        tp_snaplen: u32,
+        tp_mac: u16,
+        tp_net: u16,
+        tp_sec: u32,
+        tp_nsec: u32,
+        tp_vlan_tci: u16,
+        tp_vlan_tpid: u16,
+        tp_padding: [u8; 4],
+    }
+
+    /// A single mmap'd rx ring owned by one fanout socket
+    struct Ring {
+        fd: OwnedFd,
+        map: *mut u8,
+        map_len: usize,
+        frame_nr: usize,
+        cursor: usize,
+    }
+
+    impl Ring {
+        fn open(interface: &str, ring_bytes: usize, fanout_group: Option<u16>) -> Result<Self> {
+            let fd = open_bound_socket(interface)?;
+
+            set_sockopt(&fd, SOL_PACKET, PACKET_VERSION, &TPACKET_V2)
+                .context("Failed to select TPACKET_V2")?;
+
+            let block_nr = (ring_bytes / BLOCK_SIZE).max(1);
+            let req = TpacketReq {
+                tp_block_size: BLOCK_SIZE as u32,
+                tp_block_nr: block_nr as u32,
+                tp_frame_size: FRAME_SIZE as u32,
+                tp_frame_nr: (block_nr * BLOCK_SIZE / FRAME_SIZE) as u32,
+            };
+            set_sockopt(&fd, SOL_PACKET, PACKET_RX_RING, &req)
+                .context("Failed to allocate PACKET_RX_RING")?;
+
+            // Joined before mapping, so a failure here has nothing to unmap
+            if let Some(group) = fanout_group {
+                let arg: u32 = group as u32 | (PACKET_FANOUT_CPU << 16);
+                set_sockopt(&fd, SOL_PACKET, PACKET_FANOUT, &arg)
+                    .context("Failed to join PACKET_FANOUT group")?;
+            }
+
+            let map_len = block_nr * BLOCK_SIZE;
+            let map = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    map_len,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd.as_raw_fd(),
+                    0,
+                )
+            };
+            if map == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error()).context("Failed to mmap rx ring");
+            }
+
+            Ok(Ring {
+                fd,
+                map: map as *mut u8,
+                map_len,
+                frame_nr: req.tp_frame_nr as usize,
+                cursor: 0,
+            })
+        }
+
+        fn header(&self, idx: usize) -> *mut Tpacket2Hdr {
+            unsafe { self.map.add(idx * FRAME_SIZE) as *mut Tpacket2Hdr }
+        }
+
+        /// Index of the next frame if the kernel has handed it to user space
+        fn ready(&self) -> Option<usize> {
+            let status = unsafe { std::ptr::read_volatile(&(*self.header(self.cursor)).tp_status) };
+            std::sync::atomic::fence(std::sync::atomic::Ordering::Acquire);
+            if status & TP_STATUS_USER != 0 {
+                Some(self.cursor)
+            } else {
+                None
+            }
+        }
+
+        fn frame(&self, idx: usize) -> &[u8] {
+            unsafe {
+                let hdr = self.header(idx);
+                let start = (hdr as *const u8).add((*hdr).tp_mac as usize);
+                let len = ((*hdr).tp_snaplen as usize).min(FRAME_SIZE - (*hdr).tp_mac as usize);
+                std::slice::from_raw_parts(start, len)
+            }
+        }
+
+        fn timestamp(&self, idx: usize) -> FrameTime {
+            unsafe {
+                let hdr = self.header(idx);
+                FrameTime {
+                    secs: (*hdr).tp_sec as i64,
+                    micros: (*hdr).tp_nsec / 1000,
+                }
+            }
+        }
+
+        fn release(&mut self, idx: usize) {
+            std::sync::atomic::fence(std::sync::atomic::Ordering::Release);
+            unsafe { std::ptr::write_volatile(&mut (*self.header(idx)).tp_status, TP_STATUS_KERNEL) };
+        }
+    }
+
+    impl Drop for Ring {
+        fn drop(&mut self) {
+            unsafe {
+                libc::munmap(self.map as *mut libc::c_void, self.map_len);
+            }
+        }
+    }
+
+    /// Zero-copy capture from PACKET_MMAP rings with per-CPU fanout
+    pub struct MmapSource {
+        rings: Vec<Ring>,
+        next_ring: usize,
+        /// Frame handed out by the last `next_packet` call, returned to the
+        /// kernel on the following call
+        pending: Option<(usize, usize)>,
+        timeout_ms: i32,
+        drops: DropStats,
+    }
+
+    // The mmap'd regions are owned exclusively by this source
+    unsafe impl Send for MmapSource {}
+
+    impl MmapSource {
+        pub fn open(interface: &str, ring_mb: usize, fanout: usize, timeout_ms: i32) -> Result<Self> {
+            let fanout = fanout.max(1);
+            let ring_bytes = (ring_mb.max(1) * 1024 * 1024) / fanout;
+            let group = if fanout > 1 {
+                Some(fanout_group_id(std::process::id(), interface_index(interface)?))
+            } else {
+                None
+            };
+
+            let rings = (0..fanout)
+                .map(|_| Ring::open(interface, ring_bytes, group))
+                .collect::<Result<Vec<_>>>()?;
+
+            debug!(
+                "PACKET_MMAP capture on {}: {} ring(s), {} frames each",
+                interface,
+                rings.len(),
+                rings[0].frame_nr
+            );
+
+            Ok(MmapSource {
+                rings,
+                next_ring: 0,
+                pending: None,
+                timeout_ms,
+                drops: DropStats::default(),
+            })
+        }
+
+        fn find_ready(&mut self) -> Option<(usize, usize)> {
+            for i in 0..self.rings.len() {
+                let r = (self.next_ring + i) % self.rings.len();
+                if let Some(idx) = self.rings[r].ready() {
+                    let ring = &mut self.rings[r];
+                    ring.cursor = (ring.cursor + 1) % ring.frame_nr;
+                    self.next_ring = (r + 1) % self.rings.len();
+                    return Some((r, idx));
+                }
+            }
+            None
+        }
+    }
+
+    impl PacketSource for MmapSource {
+        fn next_packet(&mut self) -> Result<Option<&[u8]>> {
+            Ok(self.next_timestamped()?.map(|(data, _)| data))
+        }
+
+        fn next_timestamped(&mut self) -> Result<Option<(&[u8], Option<FrameTime>)>> {
+            if let Some((r, idx)) = self.pending.take() {
+                self.rings[r].release(idx);
+            }
+
+            let mut found = self.find_ready();
+            if found.is_none() {
+                let mut fds: Vec<libc::pollfd> = self
+                    .rings
+                    .iter()
+                    .map(|ring| libc::pollfd {
+                        fd: ring.fd.as_raw_fd(),
+                        events: libc::POLLIN | libc::POLLERR,
+                        revents: 0,
+                    })
+                    .collect();
+                let ret = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, self.timeout_ms) };
+                if ret < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::Interrupted {
+                        return Ok(None);
+                    }
+                    return Err(err.into());
+                }
+                found = self.find_ready();
+            }
+
+            match found {
+                Some((r, idx)) => {
+                    self.pending = Some((r, idx));
+                    let ring = &self.rings[r];
+                    Ok(Some((ring.frame(idx), Some(ring.timestamp(idx)))))
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "mmap"
+        }
+
+        fn drop_stats(&mut self) -> Option<DropStats> {
+            for ring in &self.rings {
+                add_packet_statistics(&ring.fd, &mut self.drops).ok()?;
+            }
+            Some(self.drops)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::parse_probe_request;
+
+    #[test]
+    fn test_capture_filter_widens_configured_filter() {
+        let mut capture = Config::default_config().capture;
+        assert_eq!(capture_filter(&capture), PROBE_REQUEST_FILTER);
+
+        capture.bpf_filter = "type mgt subtype probe-req and wlan addr2 aa:bb:cc:dd:ee:ff".to_string();
+        capture.detect_deauth = true;
+        assert_eq!(
+            capture_filter(&capture),
+            format!("(type mgt subtype probe-req and wlan addr2 aa:bb:cc:dd:ee:ff) or {}", DEAUTH_FILTER)
+        );
+
+        capture.measure_utilization = true;
+        assert_eq!(capture_filter(&capture), "");
+
+        capture.measure_utilization = false;
+        capture.bpf_filter = String::new();
+        capture.capture_beacons = true;
+        assert_eq!(capture_filter(&capture), "");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_fanout_group_per_interface() {
+        use afpacket::fanout_group_id;
+        // Two adapters in one process get different groups
+        assert_ne!(fanout_group_id(4242, 3), fanout_group_id(4242, 4));
+        assert_eq!(fanout_group_id(4242, 3), fanout_group_id(4242, 3));
+        // As do two processes on one adapter
+        assert_ne!(fanout_group_id(4242, 3), fanout_group_id(4243, 3));
+        let groups: std::collections::HashSet<u16> =
+            (1..=64).map(|ifindex| fanout_group_id(4242, ifindex)).collect();
+        assert_eq!(groups.len(), 64);
+    }
+
+    #[test]
+    fn test_scripted_source_frames_parse() {
+        let probes = vec![
+            ScriptedProbe {
+                mac: "AA:BB:CC:00:00:01".into(),
+                ssid: "HomeNet".into(),
+                signal_dbm: Some(-55),
+                bssid: None,
+                delay_ms: 0,
+            },
+            ScriptedProbe {
+                mac: "02:00:00:00:00:02".into(),
+                ssid: String::new(),
+                signal_dbm: None,
+                bssid: Some("00:11:22:33:44:55".into()),
+                delay_ms: 0,
+            },
+        ];
+        let mut source = ScriptedSource::new(&probes).unwrap();
+
+        let data = source.next_packet().unwrap().unwrap().to_vec();
+        let parsed = parse_probe_request(&data, Some(-55)).unwrap();
+        assert_eq!(parsed.source_mac, "AA:BB:CC:00:00:01");
+        assert_eq!(parsed.ssid, "HomeNet");
+        assert_eq!(parsed.bssid, None);
+
+        let data = source.next_packet().unwrap().unwrap().to_vec();
+        let parsed = parse_probe_request(&data, None).unwrap();
+        assert_eq!(parsed.ssid, "");
+        assert_eq!(parsed.bssid.as_deref(), Some("00:11:22:33:44:55"));
+
+        let err = source.next_packet().unwrap_err();
+        assert!(err.is::<SourceExhausted>());
+    }
+
+    #[test]
+    fn test_merged_source_reads_every_source() {
+        let source = |mac: &str| {
+            let probe = ScriptedProbe {
+                mac: mac.into(),
+                ssid: "HomeNet".into(),
+                signal_dbm: Some(-60),
+                bssid: None,
+                delay_ms: 0,
+            };
+            Box::new(ScriptedSource::new(&[probe]).unwrap()) as Box<dyn PacketSource>
+        };
+        let mut merged =
+            MergedSource::new(vec![source("AA:BB:CC:00:00:01"), source("AA:BB:CC:00:00:02")], 100).unwrap();
+
+        let mut macs = Vec::new();
+        let mut exhausted = 0;
+        for _ in 0..20 {
+            match merged.next_packet() {
+                Ok(Some(data)) => macs.push(parse_probe_request(data, None).unwrap().source_mac),
+                Ok(None) => {}
+                Err(e) if e.is::<SourceExhausted>() => exhausted += 1,
+                // Both readers have stopped
+                Err(_) => break,
+            }
+        }
+        macs.sort();
+        assert_eq!(macs, vec!["AA:BB:CC:00:00:01", "AA:BB:CC:00:00:02"]);
+        assert_eq!(exhausted, 2);
+    }
+}
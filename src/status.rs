@@ -0,0 +1,321 @@
+//! Single self-updating status line for CLI capture.
+//!
+//! The capture loop bumps lock-free counters in `CaptureStats`; a render
+//! thread redraws one line on stderr twice a second, in the spirit of
+//! airodump-ng's header. Log records written while the line is up are
+//! prefixed with `CLEAR_LINE` by the logger so they don't collide with it.
+
+use crate::database::GpsStatus;
+use crate::disk::{DiskState, DiskStatus};
+use crate::output::{paint, Severity};
+use crate::power::BATTERY_UNKNOWN;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Carriage return plus erase-line
+pub const CLEAR_LINE: &str = "\r\x1b[2K";
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(500);
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether a status line currently owns the last terminal row
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Counters shared between the capture loop and the status line
+#[derive(Debug)]
+pub struct CaptureStats {
+    probes: AtomicU64,
+    devices: AtomicU64,
+    /// Unix time of the last stored probe, 0 before the first
+    last_probe_at: AtomicI64,
+    /// Current hopper channel, 0 when unknown
+    pub channel: Arc<AtomicU8>,
+    gps: AtomicU8,
+    kernel_dropped: AtomicU64,
+    queue_dropped: AtomicU64,
+    corrupt_frames: AtomicU64,
+    /// Battery percentage, `BATTERY_UNKNOWN` without a battery
+    pub battery: Arc<AtomicU8>,
+    /// Set while capture is paused
+    pub paused: Arc<AtomicBool>,
+    /// Free space and time until full, from the disk monitor
+    pub disk: Arc<DiskState>,
+}
+
+impl Default for CaptureStats {
+    fn default() -> Self {
+        CaptureStats {
+            probes: AtomicU64::new(0),
+            devices: AtomicU64::new(0),
+            last_probe_at: AtomicI64::new(0),
+            channel: Arc::new(AtomicU8::new(0)),
+            gps: AtomicU8::new(0),
+            kernel_dropped: AtomicU64::new(0),
+            queue_dropped: AtomicU64::new(0),
+            corrupt_frames: AtomicU64::new(0),
+            battery: Arc::new(AtomicU8::new(BATTERY_UNKNOWN)),
+            paused: Arc::new(AtomicBool::new(false)),
+            disk: Arc::new(DiskState::new()),
+        }
+    }
+}
+
+impl CaptureStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_probe(&self, new_device: bool) {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        self.last_probe_at.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+        if new_device {
+            self.devices.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn set_gps(&self, status: GpsStatus) {
+        let value = match status {
+            GpsStatus::Disabled => 0,
+            GpsStatus::NoFix => 1,
+            GpsStatus::Fix => 2,
+        };
+        self.gps.store(value, Ordering::Relaxed);
+    }
+
+    /// Frames the kernel or libpcap dropped since capture started
+    pub fn set_kernel_dropped(&self, dropped: u64) {
+        self.kernel_dropped.store(dropped, Ordering::Relaxed);
+    }
+
+    /// Records the database queue dropped since capture started
+    pub fn set_queue_dropped(&self, dropped: u64) {
+        self.queue_dropped.store(dropped, Ordering::Relaxed);
+    }
+
+    /// A frame failed its FCS check and was discarded
+    pub fn record_corrupt(&self) {
+        self.corrupt_frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Unix time of the last stored probe
+    pub fn last_probe_at(&self) -> Option<i64> {
+        match self.last_probe_at.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(at),
+        }
+    }
+
+    pub fn snapshot(&self) -> StatusSnapshot {
+        let channel = self.channel.load(Ordering::Relaxed);
+        StatusSnapshot {
+            probes: self.probes.load(Ordering::Relaxed),
+            devices: self.devices.load(Ordering::Relaxed),
+            channel: (channel != 0).then_some(channel),
+            gps: match self.gps.load(Ordering::Relaxed) {
+                2 => GpsStatus::Fix,
+                1 => GpsStatus::NoFix,
+                _ => GpsStatus::Disabled,
+            },
+            kernel_dropped: self.kernel_dropped.load(Ordering::Relaxed),
+            queue_dropped: self.queue_dropped.load(Ordering::Relaxed),
+            corrupt_frames: self.corrupt_frames.load(Ordering::Relaxed),
+            battery: match self.battery.load(Ordering::Relaxed) {
+                BATTERY_UNKNOWN => None,
+                percent => Some(percent),
+            },
+            paused: self.paused.load(Ordering::Relaxed),
+            disk: self.disk.status(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusSnapshot {
+    pub probes: u64,
+    pub devices: u64,
+    pub channel: Option<u8>,
+    pub gps: GpsStatus,
+    pub kernel_dropped: u64,
+    pub queue_dropped: u64,
+    pub corrupt_frames: u64,
+    pub battery: Option<u8>,
+    pub paused: bool,
+    pub disk: DiskStatus,
+}
+
+/// Render one status line
+pub fn format_status(snapshot: &StatusSnapshot, probes_per_sec: f64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let channel = snapshot
+        .channel
+        .map(|c| format!("{:>3}", c))
+        .unwrap_or_else(|| "  -".to_string());
+    let gps = match snapshot.gps {
+        GpsStatus::Fix => paint("fix", Severity::Ok),
+        GpsStatus::NoFix => paint("no fix", Severity::Warning),
+        GpsStatus::Disabled => "off".to_string(),
+    };
+    let total_dropped = snapshot.kernel_dropped + snapshot.queue_dropped;
+    let dropped = if total_dropped > 0 {
+        format!(" | {} dropped", paint(&total_dropped.to_string(), Severity::Warning))
+    } else {
+        String::new()
+    };
+
+    let corrupt = if snapshot.corrupt_frames > 0 {
+        format!(" | {} corrupt", snapshot.corrupt_frames)
+    } else {
+        String::new()
+    };
+
+    let battery = match snapshot.battery {
+        Some(percent) => {
+            let severity = if percent <= 20 { Severity::Warning } else { Severity::Ok };
+            format!(" | BAT {}", paint(&format!("{}%", percent), severity))
+        }
+        None => String::new(),
+    };
+    let disk = if snapshot.disk.shedding {
+        format!(" | DISK {} (shedding)", paint(&snapshot.disk.describe(), Severity::Alert))
+    } else if snapshot.disk.low {
+        format!(" | DISK {}", paint(&snapshot.disk.describe(), Severity::Warning))
+    } else {
+        String::new()
+    };
+    let paused = if snapshot.paused {
+        format!(" | {}", paint("PAUSED", Severity::Warning))
+    } else {
+        String::new()
+    };
+
+    format!(
+        "[ {:02}:{:02}:{:02} ] CH {} | {} probes ({:.1}/s) | {} devices{}{} | GPS {}{}{}{}",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60,
+        channel,
+        paint(&snapshot.probes.to_string(), Severity::Info),
+        probes_per_sec,
+        paint(&snapshot.devices.to_string(), Severity::Info),
+        dropped,
+        corrupt,
+        gps,
+        battery,
+        disk,
+        paused
+    )
+}
+
+/// Redraw the status line until `running` clears, then finish it with a
+/// newline so the shutdown log starts on a fresh row.
+pub fn spawn_status_line(stats: Arc<CaptureStats>, running: Arc<AtomicBool>) -> thread::JoinHandle<()> {
+    ACTIVE.store(true, Ordering::Relaxed);
+
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut last_probes = 0u64;
+        let mut last_tick = started;
+
+        while running.load(Ordering::SeqCst) {
+            thread::sleep(REDRAW_INTERVAL);
+
+            let snapshot = stats.snapshot();
+            let now = Instant::now();
+            let window = now.duration_since(last_tick).as_secs_f64();
+            let rate = if window > 0.0 {
+                snapshot.probes.saturating_sub(last_probes) as f64 / window
+            } else {
+                0.0
+            };
+            last_probes = snapshot.probes;
+            last_tick = now;
+
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "{}{}", CLEAR_LINE, format_status(&snapshot, rate, started.elapsed()));
+            let _ = stderr.flush();
+        }
+
+        ACTIVE.store(false, Ordering::Relaxed);
+        let _ = writeln!(std::io::stderr());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_snapshot() {
+        let stats = CaptureStats::new();
+        stats.record_probe(true);
+        stats.record_probe(false);
+        stats.set_gps(GpsStatus::NoFix);
+        stats.channel.store(6, Ordering::Relaxed);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.probes, 2);
+        assert_eq!(snapshot.devices, 1);
+        assert_eq!(snapshot.channel, Some(6));
+        assert_eq!(snapshot.gps, GpsStatus::NoFix);
+    }
+
+    #[test]
+    fn test_format_status() {
+        let snapshot = StatusSnapshot {
+            probes: 120,
+            devices: 7,
+            channel: None,
+            gps: GpsStatus::Disabled,
+            kernel_dropped: 0,
+            queue_dropped: 0,
+            corrupt_frames: 0,
+            battery: None,
+            paused: false,
+            disk: DiskStatus::default(),
+        };
+        let line = format_status(&snapshot, 2.5, Duration::from_secs(3725));
+        assert!(line.starts_with("[ 01:02:05 ] CH   -"));
+        assert!(line.contains("(2.5/s)"));
+        assert!(line.ends_with("GPS off"));
+        assert!(!line.contains("dropped"));
+
+        let dropping = StatusSnapshot {
+            kernel_dropped: 40,
+            queue_dropped: 2,
+            ..snapshot
+        };
+        assert!(format_status(&dropping, 2.5, Duration::from_secs(1)).contains("42"));
+
+        let noisy = StatusSnapshot {
+            corrupt_frames: 9,
+            ..snapshot
+        };
+        assert!(format_status(&noisy, 2.5, Duration::from_secs(1)).contains("| 9 corrupt |"));
+
+        let on_battery = StatusSnapshot {
+            battery: Some(64),
+            ..snapshot
+        };
+        assert!(format_status(&on_battery, 2.5, Duration::from_secs(1)).contains("BAT"));
+
+        let filling = StatusSnapshot {
+            disk: DiskStatus {
+                free_mb: Some(300),
+                full_in_secs: Some(5 * 3600),
+                low: true,
+                ..Default::default()
+            },
+            ..snapshot
+        };
+        assert!(format_status(&filling, 0.0, Duration::from_secs(1)).contains("300 MB free, full in ~5h"));
+
+        let paused = StatusSnapshot { paused: true, ..snapshot };
+        assert!(format_status(&paused, 0.0, Duration::from_secs(1)).contains("PAUSED"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Linear, screen-reader friendly alternative to the dashboard.
+//!
+//! Nothing is drawn or redrawn: each noteworthy change is announced once as
+//! a plain line on stdout, with no color or box drawing, so it reads well in
+//! a screen reader or braille display. Individual probes are not announced;
+//! new devices, alerts, GPS changes and a periodic summary are.
+
+use crate::oui::{is_randomized_mac, lookup_vendor};
+use crate::tui::{Stats, TuiEvent};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How often the running totals are read out
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Turns capture events into the lines to announce
+#[derive(Debug, Default)]
+pub struct Announcer {
+    seen_macs: HashSet<String>,
+    gps_fix: Option<bool>,
+    last_spike: Option<String>,
+    last_my_ssid_probe: Option<String>,
+    latest_stats: Option<Stats>,
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn announce(&mut self, event: &TuiEvent) -> Vec<String> {
+        match event {
+            TuiEvent::ProbeReceived(entry) => {
+                if !self.seen_macs.insert(entry.mac.clone()) {
+                    return Vec::new();
+                }
+                let vendor = if is_randomized_mac(&entry.mac) {
+                    "randomized address".to_string()
+                } else {
+                    lookup_vendor(&entry.mac).unwrap_or("unknown vendor").to_string()
+                };
+                let looking_for = if entry.ssid.is_empty() {
+                    "any network".to_string()
+                } else {
+                    format!("network {}", entry.ssid)
+                };
+                let signal = entry
+                    .signal_dbm
+                    .map(|s| format!(", signal {} dBm", s))
+                    .unwrap_or_default();
+                vec![format!("New device {}, {}, looking for {}{}.", entry.mac, vendor, looking_for, signal)]
+            }
+            TuiEvent::GpsFix { mode, .. } => {
+                let fix = *mode >= 2;
+                if self.gps_fix.replace(fix) == Some(fix) {
+                    return Vec::new();
+                }
+                vec![if fix { "GPS fix acquired." } else { "GPS fix lost." }.to_string()]
+            }
+            TuiEvent::GpsDisconnected => {
+                self.gps_fix = None;
+                vec!["GPS disconnected.".to_string()]
+            }
+            TuiEvent::StatsUpdate(stats) => {
+                let spike = stats.new_device_spike.clone();
+                let mut lines = Vec::new();
+                if spike.is_some() && spike != self.last_spike {
+                    lines.push(format!("Alert: new device spike, {}.", spike.as_deref().unwrap_or_default()));
+                }
+                self.last_spike = spike;
+                let probe = stats.my_ssid_probe.clone();
+                if probe.is_some() && probe != self.last_my_ssid_probe {
+                    lines.push(format!("Alert: {}.", probe.as_deref().unwrap_or_default()));
+                }
+                self.last_my_ssid_probe = probe;
+                self.latest_stats = Some(stats.clone());
+                lines
+            }
+            TuiEvent::CaptureStarted => vec!["Capture started. Press Control C to stop.".to_string()],
+            TuiEvent::CaptureStopped => vec!["Capture stopped.".to_string()],
+            TuiEvent::Error(message) => vec![format!("Error: {}.", message)],
+            TuiEvent::GpsUpdate(..)
+            | TuiEvent::GpsSky { .. }
+            | TuiEvent::ChannelChanged(_)
+            | TuiEvent::AnalysisDone(_) => Vec::new(),
+        }
+    }
+
+    /// Running totals, once stats have arrived
+    pub fn summary(&self) -> Option<String> {
+        let stats = self.latest_stats.as_ref()?;
+        Some(format!(
+            "Summary: {} devices in the last 5 minutes, {} in the last 15, {} total. {:.0} probes per minute, about {:.0} people nearby.",
+            stats.devices_last_5min,
+            stats.devices_last_15min,
+            stats.total_devices,
+            stats.probes_per_minute,
+            stats.estimated_occupancy
+        ))
+    }
+}
+
+/// Announce events on stdout until `running` clears or Ctrl+C is pressed
+pub async fn run(mut event_rx: mpsc::Receiver<TuiEvent>, running: Arc<AtomicBool>) -> Result<()> {
+    let interrupt = running.clone();
+    // Fails only if a handler is already installed, which then owns shutdown
+    let _ = ctrlc::set_handler(move || interrupt.store(false, Ordering::SeqCst));
+
+    let mut announcer = Announcer::new();
+    let mut last_summary = Instant::now();
+    let mut stdout = std::io::stdout();
+
+    while running.load(Ordering::SeqCst) {
+        let event = tokio::time::timeout(Duration::from_millis(250), event_rx.recv()).await;
+        let lines = match event {
+            Ok(Some(event)) => announcer.announce(&event),
+            Ok(None) => break,
+            Err(_) => Vec::new(),
+        };
+        for line in lines {
+            writeln!(stdout, "{}", line)?;
+        }
+
+        if last_summary.elapsed() >= SUMMARY_INTERVAL {
+            last_summary = Instant::now();
+            if let Some(summary) = announcer.summary() {
+                writeln!(stdout, "{}", summary)?;
+            }
+        }
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::ProbeLogEntry;
+
+    fn probe(mac: &str, ssid: &str) -> TuiEvent {
+        TuiEvent::ProbeReceived(ProbeLogEntry {
+            timestamp: 0,
+            mac: mac.to_string(),
+            ssid: ssid.to_string(),
+            signal_dbm: Some(-60),
+            distance_m: None,
+            channel: None,
+            capabilities: None,
+            repeat: 1,
+            frame: None,
+        })
+    }
+
+    #[test]
+    fn test_new_devices_announced_once() {
+        let mut announcer = Announcer::new();
+        let lines = announcer.announce(&probe("02:00:00:00:00:01", "Home"));
+        assert_eq!(
+            lines,
+            vec!["New device 02:00:00:00:00:01, randomized address, looking for network Home, signal -60 dBm."]
+        );
+        assert!(announcer.announce(&probe("02:00:00:00:00:01", "Work")).is_empty());
+    }
+
+    #[test]
+    fn test_spike_and_gps_announced_on_change() {
+        let mut announcer = Announcer::new();
+        assert!(announcer.summary().is_none());
+
+        let stats = Stats {
+            new_device_spike: Some("40 new devices in 60s".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(announcer.announce(&TuiEvent::StatsUpdate(stats.clone())).len(), 1);
+        assert!(announcer.announce(&TuiEvent::StatsUpdate(stats)).is_empty());
+        assert!(announcer.summary().is_some());
+
+        let fix = TuiEvent::GpsFix { mode: 3, speed: None, track: None };
+        assert_eq!(announcer.announce(&fix), vec!["GPS fix acquired."]);
+        assert!(announcer.announce(&fix).is_empty());
+    }
+}
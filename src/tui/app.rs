@@ -1,16 +1,70 @@
+use crate::alias::DeviceLink;
+use crate::analysis::SurveillanceAlert;
+use crate::disk::DiskStatus;
 use crate::distance::{
     AdaptiveCalibrator, CalibrationStatus, DistanceEstimate, RssiTracker,
     estimate_distance_smart, estimate_tx_power_from_wifi_gen,
 };
+use crate::channels::ChannelProfile;
+use crate::database::DeviceHistory;
 use crate::parser::ProbeCapabilities;
-use crate::tui::TuiEvent;
-use std::collections::VecDeque;
+use crate::tui::event::KeyMap;
+use crate::tui::{CaptureControl, TuiEvent};
+use crate::utilization::ChannelUsage;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::time::Instant;
 use tokio::sync::mpsc;
 
 /// Maximum entries in the probe log ring buffer
 const MAX_PROBE_LOG_ENTRIES: usize = 500;
 
+/// Silence between consecutive probes that is marked as a gap in the log
+pub const LOG_GAP_SECS: i64 = 30;
+
+/// A repeat of the newest log line within this many seconds is folded into
+/// it as a ×N counter instead of taking a new line
+const LOG_COALESCE_SECS: i64 = 2;
+
+/// Maximum positions kept for the GPS breadcrumb trail
+const MAX_GPS_TRAIL_POINTS: usize = 120;
+
+/// Minimum movement (degrees, ~5 m) before a new trail point is recorded
+const GPS_TRAIL_MIN_STEP: f64 = 0.00005;
+
+/// Quick analyze overlay
+#[derive(Debug)]
+pub struct QuickAnalysis {
+    pub hours: u32,
+    /// None while the analysis is still running
+    pub result: Option<Result<Vec<SurveillanceAlert>, String>>,
+    /// First alert shown
+    pub scroll: usize,
+}
+
+/// Fix quality and motion details from gpsd TPV/SKY reports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GpsDetail {
+    /// 0/1 = no fix, 2 = 2D, 3 = 3D
+    pub mode: u8,
+    pub satellites_used: Option<u32>,
+    pub satellites_visible: Option<u32>,
+    pub hdop: Option<f64>,
+    /// Speed in m/s
+    pub speed: Option<f64>,
+    /// Heading in degrees from true north
+    pub track: Option<f64>,
+}
+
+impl GpsDetail {
+    pub fn fix_label(&self) -> &'static str {
+        match self.mode {
+            3 => "3D",
+            2 => "2D",
+            _ => "No fix",
+        }
+    }
+}
+
 /// Active panel for focus/navigation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ActivePanel {
@@ -27,6 +81,23 @@ pub enum DeviceSortField {
     LastSeen,
     ProbeCount,
     Signal,
+    Risk,
+}
+
+/// Runtime capture switches offered by the command palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteCommand {
+    Channels(ChannelProfile),
+    ToggleDistance,
+    ToggleGpsTagging,
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
 }
 
 /// Statistics snapshot
@@ -39,6 +110,32 @@ pub struct Stats {
     pub devices_last_15min: usize,
     pub unique_ssids: usize,
     pub capture_duration_secs: u64,
+    /// Estimated people nearby over the last occupancy bucket
+    pub estimated_occupancy: f64,
+    /// Probes dropped because the database queue was full
+    pub dropped_probes: u64,
+    /// Probes the UI never showed because its queue was full
+    pub ui_dropped: u64,
+    /// Probes folded into another log line while the UI queue was congested
+    pub ui_coalesced: u64,
+    /// Frames the kernel or libpcap dropped before prowl could read them
+    pub kernel_dropped: u64,
+    /// Frames discarded for failing their FCS check
+    pub corrupt_frames: u64,
+    /// Battery percentage on battery-powered sensors
+    pub battery_percent: Option<u8>,
+    /// Free space and time until the disk fills
+    pub disk: DiskStatus,
+    /// Never-before-seen devices per minute over the last anomaly bucket
+    pub new_devices_per_min: f64,
+    /// Most recent new-device spike in the last few minutes
+    pub new_device_spike: Option<String>,
+    /// Most recent first probe for one of your networks in the last few minutes
+    pub my_ssid_probe: Option<String>,
+    /// Estimated airtime per channel this session, with `measure_utilization`
+    pub channel_usage: Vec<ChannelUsage>,
+    /// Live risk score per MAC from the background analysis
+    pub risk_scores: HashMap<String, f64>,
 }
 
 /// Device display entry with computed fields
@@ -57,6 +154,11 @@ pub struct DeviceEntry {
     pub rssi_tracker: RssiTracker,
     /// Computed distance estimate with uncertainty
     pub distance_estimate: Option<DistanceEstimate>,
+    /// Latest live risk score, once the device has been scored
+    pub risk: Option<f64>,
+    /// Raw bytes of the last probe, radiotap header included, when
+    /// `tui.keep_frames` is on
+    pub last_frame: Option<Vec<u8>>,
 }
 
 /// Single probe log entry for display
@@ -69,6 +171,30 @@ pub struct ProbeLogEntry {
     pub distance_m: Option<f64>,
     pub channel: Option<u8>,
     pub capabilities: Option<ProbeCapabilities>,
+    /// Consecutive identical probes folded into this line
+    pub repeat: u32,
+    /// Raw frame, handed on to the device entry rather than kept in the log
+    pub frame: Option<Vec<u8>>,
+}
+
+impl ProbeLogEntry {
+    /// Same device and SSID, close enough in time to share a line
+    fn coalesces_with(&self, next: &ProbeLogEntry) -> bool {
+        self.mac == next.mac
+            && self.ssid == next.ssid
+            && next.timestamp - self.timestamp <= LOG_COALESCE_SECS
+    }
+
+    /// Fold a later probe from the same device and SSID into this one
+    pub fn absorb(&mut self, next: ProbeLogEntry) {
+        self.repeat += next.repeat;
+        self.timestamp = next.timestamp;
+        self.signal_dbm = next.signal_dbm.or(self.signal_dbm);
+        self.distance_m = next.distance_m.or(self.distance_m);
+        self.channel = next.channel.or(self.channel);
+        self.capabilities = next.capabilities.or(self.capabilities.take());
+        self.frame = next.frame.or(self.frame.take());
+    }
 }
 
 /// Main application state
@@ -94,6 +220,36 @@ pub struct App {
     /// Probe log scroll offset (0 = bottom/newest)
     pub log_scroll: usize,
 
+    /// Jump-to-timestamp prompt input (Some while the prompt is open)
+    pub jump_input: Option<String>,
+
+    /// Result of the last jump or bulk action, shown in the status bar
+    pub status_message: Option<String>,
+
+    /// Label prompt input for the marked devices (Some while open)
+    pub label_input: Option<String>,
+
+    /// MACs marked for bulk actions
+    pub marked: BTreeSet<String>,
+
+    /// Command palette (Some(selected entry) while open)
+    pub palette: Option<usize>,
+
+    /// Quick analyze overlay (Some while open)
+    pub analysis: Option<QuickAnalysis>,
+
+    /// Channel set chosen from the palette (None = configured channels)
+    pub channel_profile: Option<ChannelProfile>,
+
+    /// Whether the capture loop is estimating distance and tagging GPS
+    /// positions; both can be switched from the palette
+    pub distance_enabled: bool,
+    pub gps_tagging: bool,
+
+    /// Labels and watch list entries from the database
+    pub labels: HashMap<String, String>,
+    pub watched: HashSet<String>,
+
     /// Current sort field and direction
     pub sort_field: DeviceSortField,
     pub sort_ascending: bool,
@@ -109,18 +265,30 @@ pub struct App {
     pub gps_error: Option<String>,
     /// Last time GPS position was updated (for activity indicator)
     pub last_gps_update: Option<Instant>,
+    /// Fix quality, satellites and motion
+    pub gps_detail: GpsDetail,
+    /// Recent positions, oldest first
+    pub gps_trail: VecDeque<(f64, f64)>,
 
     /// Current channel
     pub current_channel: Option<u8>,
 
     /// Capture status
     pub capture_active: bool,
+    /// Another prowl process holds the capture lock; the UI only follows
+    /// the database
+    pub attached: bool,
 
     /// Help overlay visible
     pub show_help: bool,
 
     /// Device detail view (Some = viewing device at index)
     pub detail_view: Option<usize>,
+    /// Other MACs that may be the device in the detail view
+    pub detail_links: Vec<DeviceLink>,
+
+    /// Hex view of a device's last frame (Some = viewing device at index)
+    pub frame_view: Option<usize>,
 
     /// Event receiver
     pub event_rx: mpsc::Receiver<TuiEvent>,
@@ -133,6 +301,9 @@ pub struct App {
 
     /// Current calibration status for display
     pub calibration_status: Option<CalibrationStatus>,
+
+    /// Key bindings, also shown in the help overlay
+    pub keymap: KeyMap,
 }
 
 impl App {
@@ -141,6 +312,7 @@ impl App {
         initial_stats: Stats,
         gps_enabled: bool,
         gps_error: Option<String>,
+        keymap: KeyMap,
     ) -> Self {
         App {
             running: true,
@@ -150,6 +322,17 @@ impl App {
             selected_device: 0,
             device_scroll: 0,
             log_scroll: 0,
+            jump_input: None,
+            status_message: None,
+            label_input: None,
+            palette: None,
+            analysis: None,
+            channel_profile: None,
+            distance_enabled: true,
+            gps_tagging: gps_enabled,
+            marked: BTreeSet::new(),
+            labels: HashMap::new(),
+            watched: HashSet::new(),
             sort_field: DeviceSortField::LastSeen,
             sort_ascending: false,
             stats: initial_stats,
@@ -158,14 +341,20 @@ impl App {
             gps_enabled,
             gps_error,
             last_gps_update: None,
+            gps_detail: GpsDetail::default(),
+            gps_trail: VecDeque::with_capacity(MAX_GPS_TRAIL_POINTS),
             current_channel: None,
             capture_active: false,
+            attached: false,
             show_help: false,
             detail_view: None,
+            detail_links: Vec::new(),
+            frame_view: None,
             event_rx,
             probes_since_start: 0,
             calibrator: AdaptiveCalibrator::default(),
             calibration_status: None,
+            keymap,
         }
     }
 
@@ -207,12 +396,13 @@ impl App {
 
     pub fn handle_event(&mut self, event: TuiEvent) {
         match event {
-            TuiEvent::ProbeReceived(entry) => {
-                self.probes_since_start += 1;
+            TuiEvent::ProbeReceived(mut entry) => {
+                self.probes_since_start += entry.repeat as usize;
+                let frame = entry.frame.take();
 
                 // Update or add device
                 if let Some(device) = self.devices.iter_mut().find(|d| d.mac == entry.mac) {
-                    device.probe_count += 1;
+                    device.probe_count += entry.repeat as usize;
                     device.last_seen = entry.timestamp;
                     device.last_signal = entry.signal_dbm;
                     device.last_distance = entry.distance_m;
@@ -225,6 +415,9 @@ impl App {
                         // Record strong signals for TX power estimation
                         self.calibrator.record_peak_rssi(rssi);
                     }
+                    if frame.is_some() {
+                        device.last_frame = frame;
+                    }
                     // Update capabilities if present (keep most recent)
                     if entry.capabilities.is_some() {
                         device.capabilities = entry.capabilities.clone();
@@ -250,7 +443,7 @@ impl App {
                         mac: entry.mac.clone(),
                         first_seen: entry.timestamp,
                         last_seen: entry.timestamp,
-                        probe_count: 1,
+                        probe_count: entry.repeat as usize,
                         ssids,
                         last_signal: entry.signal_dbm,
                         last_distance: entry.distance_m,
@@ -258,14 +451,21 @@ impl App {
                         wifi_generation,
                         rssi_tracker,
                         distance_estimate: None,
+                        risk: self.stats.risk_scores.get(&entry.mac).copied(),
+                        last_frame: frame,
                     });
                 }
 
-                // Add to log
-                if self.probe_log.len() >= MAX_PROBE_LOG_ENTRIES {
-                    self.probe_log.pop_front();
+                // Add to log, folding bursts from one device into a single line
+                match self.probe_log.back_mut() {
+                    Some(last) if last.coalesces_with(&entry) => last.absorb(entry),
+                    _ => {
+                        if self.probe_log.len() >= MAX_PROBE_LOG_ENTRIES {
+                            self.probe_log.pop_front();
+                        }
+                        self.probe_log.push_back(entry);
+                    }
                 }
-                self.probe_log.push_back(entry);
 
                 // Sort devices
                 self.sort_devices();
@@ -274,6 +474,17 @@ impl App {
                 self.gps_position = Some((lat, lon));
                 self.gps_connected = true;
                 self.last_gps_update = Some(Instant::now());
+                self.record_trail_point(lat, lon);
+            }
+            TuiEvent::GpsFix { mode, speed, track } => {
+                self.gps_detail.mode = mode;
+                self.gps_detail.speed = speed;
+                self.gps_detail.track = track;
+            }
+            TuiEvent::GpsSky { used, visible, hdop } => {
+                self.gps_detail.satellites_used = used.or(self.gps_detail.satellites_used);
+                self.gps_detail.satellites_visible = visible.or(self.gps_detail.satellites_visible);
+                self.gps_detail.hdop = hdop.or(self.gps_detail.hdop);
             }
             TuiEvent::GpsDisconnected => {
                 self.gps_connected = false;
@@ -286,6 +497,12 @@ impl App {
                 let ppm = self.stats.probes_per_minute;
                 self.stats = stats;
                 self.stats.probes_per_minute = ppm;
+                for device in &mut self.devices {
+                    device.risk = self.stats.risk_scores.get(&device.mac).copied();
+                }
+                if self.sort_field == DeviceSortField::Risk {
+                    self.sort_devices();
+                }
             }
             TuiEvent::CaptureStarted => {
                 self.capture_active = true;
@@ -293,12 +510,31 @@ impl App {
             TuiEvent::CaptureStopped => {
                 self.capture_active = false;
             }
+            TuiEvent::AnalysisDone(result) => {
+                // Dropped if the overlay was closed while it ran
+                if let Some(analysis) = self.analysis.as_mut() {
+                    analysis.result = Some(result);
+                    analysis.scroll = 0;
+                }
+            }
             TuiEvent::Error(_msg) => {
                 // Could display in status bar
             }
         }
     }
 
+    fn record_trail_point(&mut self, lat: f64, lon: f64) {
+        if let Some(&(last_lat, last_lon)) = self.gps_trail.back() {
+            if (lat - last_lat).abs() < GPS_TRAIL_MIN_STEP && (lon - last_lon).abs() < GPS_TRAIL_MIN_STEP {
+                return;
+            }
+        }
+        if self.gps_trail.len() >= MAX_GPS_TRAIL_POINTS {
+            self.gps_trail.pop_front();
+        }
+        self.gps_trail.push_back((lat, lon));
+    }
+
     pub fn next_panel(&mut self) {
         self.active_panel = match self.active_panel {
             ActivePanel::ProbeLog => ActivePanel::DeviceTable,
@@ -340,12 +576,227 @@ impl App {
         }
     }
 
+    /// Open the jump-to-timestamp prompt
+    pub fn start_jump(&mut self) {
+        self.active_panel = ActivePanel::ProbeLog;
+        self.jump_input = Some(String::new());
+        self.status_message = None;
+    }
+
+    /// Close the prompt and scroll the log to the entered time
+    pub fn submit_jump(&mut self) {
+        let input = match self.jump_input.take() {
+            Some(input) => input,
+            None => return,
+        };
+
+        self.status_message = Some(match self.jump_to_time(input.trim()) {
+            Some(ts) => {
+                let shown = chrono::DateTime::from_timestamp(ts, 0)
+                    .map(|dt| dt.format("%H:%M:%S").to_string())
+                    .unwrap_or_default();
+                format!("Jumped to {}", shown)
+            }
+            None => format!("No probes at or after '{}' (use HH:MM or HH:MM:SS)", input.trim()),
+        });
+    }
+
+    /// Return the probe log to the live (newest) position
+    pub fn jump_to_live(&mut self) {
+        self.log_scroll = 0;
+        self.status_message = None;
+    }
+
+    /// Scroll so the first probe at or after `HH:MM[:SS]` (UTC, same day as
+    /// the newest entry) is the newest visible line. Returns its timestamp.
+    fn jump_to_time(&mut self, input: &str) -> Option<i64> {
+        let newest = self.probe_log.back()?.timestamp;
+        let time = chrono::NaiveTime::parse_from_str(input, "%H:%M:%S")
+            .or_else(|_| chrono::NaiveTime::parse_from_str(input, "%H:%M"))
+            .ok()?;
+        let day = chrono::DateTime::from_timestamp(newest, 0)?.date_naive();
+        let target = day.and_time(time).and_utc().timestamp();
+
+        let idx = self.probe_log.iter().position(|e| e.timestamp >= target)?;
+        self.log_scroll = self.probe_log.len() - 1 - idx;
+        Some(self.probe_log[idx].timestamp)
+    }
+
+    /// Mark or unmark the selected device and move to the next one
+    pub fn toggle_mark(&mut self) {
+        if let Some(device) = self.devices.get(self.selected_device) {
+            if !self.marked.remove(&device.mac) {
+                self.marked.insert(device.mac.clone());
+            }
+            self.active_panel = ActivePanel::DeviceTable;
+            if self.selected_device + 1 < self.devices.len() {
+                self.selected_device += 1;
+            }
+        }
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// MACs a bulk action applies to: the marked devices, or the selected
+    /// one when nothing is marked
+    pub fn bulk_targets(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            self.devices
+                .get(self.selected_device)
+                .map(|d| vec![d.mac.clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    /// Open the label prompt if there is anything to label
+    pub fn start_label(&mut self) {
+        if !self.bulk_targets().is_empty() {
+            self.label_input = Some(String::new());
+            self.status_message = None;
+        }
+    }
+
+    /// Drop devices that were just ignored from the table
+    pub fn remove_devices(&mut self, macs: &[String]) {
+        self.devices.retain(|d| !macs.contains(&d.mac));
+        for mac in macs {
+            self.marked.remove(mac);
+        }
+        self.selected_device = self.selected_device.min(self.devices.len().saturating_sub(1));
+        self.detail_view = None;
+        self.frame_view = None;
+    }
+
+    /// Open the analysis overlay for the last `hours`. Returns false if an
+    /// analysis is already running.
+    pub fn start_analysis(&mut self, hours: u32) -> bool {
+        if self.analysis.as_ref().is_some_and(|a| a.result.is_none()) {
+            return false;
+        }
+        self.analysis = Some(QuickAnalysis {
+            hours,
+            result: None,
+            scroll: 0,
+        });
+        self.status_message = None;
+        true
+    }
+
+    pub fn analysis_scroll(&mut self, delta: isize) {
+        if let Some(analysis) = self.analysis.as_mut() {
+            let count = match &analysis.result {
+                Some(Ok(alerts)) => alerts.len(),
+                _ => 0,
+            };
+            analysis.scroll = analysis.scroll.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    pub fn open_palette(&mut self) {
+        self.palette = Some(0);
+        self.status_message = None;
+    }
+
+    /// Palette entries; locking needs a current channel to lock on
+    pub fn palette_commands(&self) -> Vec<PaletteCommand> {
+        let mut commands = vec![
+            PaletteCommand::Channels(ChannelProfile::Band2Ghz),
+            PaletteCommand::Channels(ChannelProfile::Band5Ghz),
+            PaletteCommand::Channels(ChannelProfile::All),
+        ];
+        if let Some(channel) = self.current_channel {
+            commands.push(PaletteCommand::Channels(ChannelProfile::Locked(channel)));
+        }
+        commands.push(PaletteCommand::ToggleDistance);
+        commands.push(PaletteCommand::ToggleGpsTagging);
+        commands
+    }
+
+    pub fn palette_label(&self, command: PaletteCommand) -> String {
+        match command {
+            PaletteCommand::Channels(profile) => format!("Channels: {}", profile.label()),
+            PaletteCommand::ToggleDistance => format!("Turn distance estimation {}", on_off(!self.distance_enabled)),
+            PaletteCommand::ToggleGpsTagging => format!("Turn GPS tagging {}", on_off(!self.gps_tagging)),
+        }
+    }
+
+    pub fn palette_move(&mut self, delta: isize) {
+        let count = self.palette_commands().len();
+        if let Some(selected) = self.palette.as_mut() {
+            *selected = selected.saturating_add_signed(delta).min(count.saturating_sub(1));
+        }
+    }
+
+    /// Close the palette and apply the selected command to the app state.
+    /// Returns the message the capture loop needs to follow suit.
+    pub fn submit_palette(&mut self) -> Option<CaptureControl> {
+        let selected = self.palette.take()?;
+        let command = *self.palette_commands().get(selected)?;
+        let (control, message) = match command {
+            PaletteCommand::Channels(profile) => {
+                self.channel_profile = Some(profile);
+                (CaptureControl::SetChannels(profile), format!("Channels: {}", profile.label()))
+            }
+            PaletteCommand::ToggleDistance => {
+                self.distance_enabled = !self.distance_enabled;
+                (
+                    CaptureControl::SetDistance(self.distance_enabled),
+                    format!("Distance estimation {}", on_off(self.distance_enabled)),
+                )
+            }
+            PaletteCommand::ToggleGpsTagging => {
+                self.gps_tagging = !self.gps_tagging;
+                (
+                    CaptureControl::SetGpsTagging(self.gps_tagging),
+                    format!("GPS tagging {}", on_off(self.gps_tagging)),
+                )
+            }
+        };
+        self.status_message = Some(message);
+        Some(control)
+    }
+
+    /// Fill the device table from the database, so devices and their
+    /// capabilities are there from the start instead of after their next probe
+    pub fn restore_devices(&mut self, history: Vec<DeviceHistory>) {
+        for device in history {
+            let wifi_generation = device.capabilities.as_ref()
+                .map(|c| c.wifi_generation.clone())
+                .filter(|s| !s.is_empty());
+            let mut rssi_tracker = RssiTracker::default();
+            if let Some(rssi) = device.last_signal {
+                rssi_tracker.add_sample(rssi);
+            }
+            self.devices.push(DeviceEntry {
+                risk: self.stats.risk_scores.get(&device.mac).copied(),
+                mac: device.mac,
+                first_seen: device.first_seen,
+                last_seen: device.last_seen,
+                probe_count: device.probe_count,
+                ssids: device.ssids,
+                last_signal: device.last_signal,
+                last_distance: None,
+                capabilities: device.capabilities,
+                wifi_generation,
+                rssi_tracker,
+                distance_estimate: None,
+                last_frame: None,
+            });
+        }
+        self.sort_devices();
+    }
+
     pub fn cycle_sort(&mut self) {
         self.sort_field = match self.sort_field {
             DeviceSortField::Mac => DeviceSortField::LastSeen,
             DeviceSortField::LastSeen => DeviceSortField::ProbeCount,
             DeviceSortField::ProbeCount => DeviceSortField::Signal,
-            DeviceSortField::Signal => DeviceSortField::Mac,
+            DeviceSortField::Signal => DeviceSortField::Risk,
+            DeviceSortField::Risk => DeviceSortField::Mac,
         };
         self.sort_devices();
     }
@@ -396,6 +847,17 @@ impl App {
                     }
                 });
             }
+            DeviceSortField::Risk => {
+                self.devices.sort_by(|a, b| {
+                    let a_risk = a.risk.unwrap_or(-1.0);
+                    let b_risk = b.risk.unwrap_or(-1.0);
+                    if ascending {
+                        a_risk.total_cmp(&b_risk)
+                    } else {
+                        b_risk.total_cmp(&a_risk)
+                    }
+                });
+            }
         }
     }
 
@@ -404,4 +866,13 @@ impl App {
             self.detail_view = Some(self.selected_device);
         }
     }
+
+    /// Open the hex view of the selected device's last frame
+    pub fn view_frame(&mut self) {
+        match self.devices.get(self.selected_device) {
+            Some(device) if device.last_frame.is_some() => self.frame_view = Some(self.selected_device),
+            Some(_) => self.status_message = Some("No frame kept for this device yet".to_string()),
+            None => {}
+        }
+    }
 }
@@ -0,0 +1,140 @@
+//! Actions applied to every marked device at once.
+//!
+//! Each action writes where the matching CLI command would, so the result
+//! outlives the session: ignores go to the MAC ignore list file (and take
+//! effect in the running capture), labels and the watch list go to the
+//! database, and exports are written as a JSON array of device dossiers.
+//! The quick analysis lives here too, as it needs the same configuration
+//! and runs off the UI thread on a clone. Attached to another prowl's
+//! capture, everything but exports and analysis is refused.
+
+use crate::alias::{device_links, DeviceLink};
+use crate::analysis::{SurveillanceAlert, SurveillanceAnalyzer};
+use crate::config::Config;
+use crate::database::Database;
+use crate::export::build_device_dossier;
+use crate::ignore::IgnoreLists;
+use crate::tui::open_database;
+use anyhow::{bail, Context, Result};
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone)]
+pub struct BulkActions {
+    config: Config,
+    ignore_lists: Arc<RwLock<IgnoreLists>>,
+    read_only: bool,
+}
+
+impl BulkActions {
+    pub fn new(config: Config, ignore_lists: Arc<RwLock<IgnoreLists>>) -> Self {
+        BulkActions { config, ignore_lists, read_only: false }
+    }
+
+    /// Refuse every action that writes
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn open_db(&self) -> Result<Database> {
+        open_database(&self.config.capture.database, self.read_only)
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            bail!("Read-only while attached to another prowl's capture");
+        }
+        Ok(())
+    }
+
+    fn analyzer(&self) -> SurveillanceAnalyzer {
+        SurveillanceAnalyzer::new(
+            self.config.analysis.time_windows_minutes.clone(),
+            self.config.analysis.persistence_threshold,
+        )
+        .with_broadcast_only(
+            self.config.analysis.broadcast_only,
+            self.config.analysis.broadcast_only_weight,
+        )
+        .with_my_ssids(self.config.analysis.my_ssids.clone())
+    }
+
+    /// Hours the quick analysis covers
+    pub fn analyze_hours(&self) -> u32 {
+        self.config.tui.analyze_hours.max(1)
+    }
+
+    /// Suspicious devices over the last `analyze_hours`, as `prowl analyze`
+    /// reports them. Blocks on the database; run it off the UI thread.
+    pub fn analyze(&self) -> Result<Vec<SurveillanceAlert>> {
+        let db = self.open_db()?;
+        self.analyzer().analyze(&db, self.analyze_hours())
+    }
+
+    /// MACs that may be the same device as `mac`, for the detail view
+    pub fn links(&self, mac: &str) -> Result<Vec<DeviceLink>> {
+        let db = self.open_db()?;
+        device_links(&db, mac)
+    }
+
+    /// Add every MAC to the ignore list and save it
+    pub fn ignore(&self, macs: &[String]) -> Result<String> {
+        self.check_writable()?;
+        let mut lists = self
+            .ignore_lists
+            .write()
+            .map_err(|_| anyhow::anyhow!("Ignore list lock poisoned"))?;
+        for mac in macs {
+            lists.add_mac(mac);
+        }
+        lists
+            .save_mac_list(&self.config.ignore_lists.mac)
+            .context("Failed to save MAC ignore list")?;
+        Ok(format!("Ignored {} device(s)", macs.len()))
+    }
+
+    pub fn label(&self, macs: &[String], label: &str) -> Result<String> {
+        self.check_writable()?;
+        let db = self.open_db()?;
+        let now = chrono::Utc::now().timestamp();
+        db.in_transaction(|db| {
+            for mac in macs {
+                db.set_device_label(mac, label, now)?;
+            }
+            Ok(())
+        })?;
+        Ok(format!("Labeled {} device(s) '{}'", macs.len(), label))
+    }
+
+    pub fn watch(&self, macs: &[String]) -> Result<String> {
+        self.check_writable()?;
+        let db = self.open_db()?;
+        let now = chrono::Utc::now().timestamp();
+        let mut added = 0;
+        for mac in macs {
+            if db.add_to_watchlist(mac, now)? {
+                added += 1;
+            }
+        }
+        Ok(format!("Added {} device(s) to the watch list", added))
+    }
+
+    /// Write dossiers for every MAC to a timestamped file in the working
+    /// directory
+    pub fn export(&self, macs: &[String]) -> Result<String> {
+        let db = self.open_db()?;
+        let analyzer = self.analyzer();
+
+        let mut dossiers = Vec::new();
+        for mac in macs {
+            if let Some(dossier) = build_device_dossier(&db, mac, &analyzer)? {
+                dossiers.push(dossier);
+            }
+        }
+
+        let path = format!("prowl-selection-{}.json", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+        std::fs::write(&path, serde_json::to_string_pretty(&dossiers)?)
+            .with_context(|| format!("Failed to write {}", path))?;
+        Ok(format!("Exported {} device(s) to {}", dossiers.len(), path))
+    }
+}
@@ -1,26 +1,438 @@
-// Event handling utilities for the TUI
-// Currently keyboard events are handled directly in mod.rs
-// This module is reserved for future event handling extensions
-
-use crossterm::event::KeyCode;
-
-/// Convert key code to description for help display
-pub fn key_description(key: KeyCode) -> &'static str {
-    match key {
-        KeyCode::Tab => "Tab",
-        KeyCode::BackTab => "Shift+Tab",
-        KeyCode::Left => "Left",
-        KeyCode::Right => "Right",
-        KeyCode::Up => "Up",
-        KeyCode::Down => "Down",
-        KeyCode::Enter => "Enter",
-        KeyCode::Esc => "Esc",
-        KeyCode::Char('q') => "q",
-        KeyCode::Char('?') => "?",
-        KeyCode::Char('s') => "s",
-        KeyCode::Char('r') => "r",
-        KeyCode::Char('j') => "j",
-        KeyCode::Char('k') => "k",
-        _ => "Unknown",
+//! Key bindings for the dashboard.
+//!
+//! Keys map to actions through a preset (default, vim or emacs) with
+//! per-action overrides from the `tui` config section on top. The map is
+//! built once at startup so a typo or a key bound to two actions fails
+//! before the terminal is taken over, not halfway through a capture.
+
+use crate::config::{KeyPreset, TuiConfig};
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Something a key can do in the dashboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    NextPanel,
+    PrevPanel,
+    ScrollDown,
+    ScrollUp,
+    Select,
+    ViewFrame,
+    CycleSort,
+    ReverseSort,
+    JumpToTime,
+    JumpToLive,
+    ToggleMark,
+    ClearMarks,
+    IgnoreMarked,
+    LabelMarked,
+    ExportMarked,
+    WatchMarked,
+    CommandPalette,
+    QuickAnalyze,
+    ToggleHelp,
+    Close,
+    Quit,
+}
+
+impl Action {
+    /// In help overlay order
+    pub const ALL: [Action; 21] = [
+        Action::NextPanel,
+        Action::PrevPanel,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::Select,
+        Action::ViewFrame,
+        Action::CycleSort,
+        Action::ReverseSort,
+        Action::JumpToTime,
+        Action::JumpToLive,
+        Action::ToggleMark,
+        Action::ClearMarks,
+        Action::IgnoreMarked,
+        Action::LabelMarked,
+        Action::ExportMarked,
+        Action::WatchMarked,
+        Action::CommandPalette,
+        Action::QuickAnalyze,
+        Action::ToggleHelp,
+        Action::Close,
+        Action::Quit,
+    ];
+
+    /// Name used in the `tui.keys` config section
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NextPanel => "next_panel",
+            Action::PrevPanel => "prev_panel",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::Select => "select",
+            Action::ViewFrame => "view_frame",
+            Action::CycleSort => "cycle_sort",
+            Action::ReverseSort => "reverse_sort",
+            Action::JumpToTime => "jump_to_time",
+            Action::JumpToLive => "jump_to_live",
+            Action::ToggleMark => "toggle_mark",
+            Action::ClearMarks => "clear_marks",
+            Action::IgnoreMarked => "ignore_marked",
+            Action::LabelMarked => "label_marked",
+            Action::ExportMarked => "export_marked",
+            Action::WatchMarked => "watch_marked",
+            Action::CommandPalette => "command_palette",
+            Action::QuickAnalyze => "analyze",
+            Action::ToggleHelp => "help",
+            Action::Close => "close",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::NextPanel => "Next panel",
+            Action::PrevPanel => "Previous panel",
+            Action::ScrollDown => "Scroll / select down",
+            Action::ScrollUp => "Scroll / select up",
+            Action::Select => "View device details",
+            Action::ViewFrame => "View last frame as hex",
+            Action::CycleSort => "Cycle sort field",
+            Action::ReverseSort => "Reverse sort order",
+            Action::JumpToTime => "Jump probe log to time",
+            Action::JumpToLive => "Return probe log to live",
+            Action::ToggleMark => "Mark / unmark device",
+            Action::ClearMarks => "Clear all marks",
+            Action::IgnoreMarked => "Ignore marked devices",
+            Action::LabelMarked => "Label marked devices",
+            Action::ExportMarked => "Export marked devices",
+            Action::WatchMarked => "Watch marked devices",
+            Action::CommandPalette => "Capture settings palette",
+            Action::QuickAnalyze => "Analyze recent activity",
+            Action::ToggleHelp => "Toggle this help",
+            Action::Close => "Close popup/overlay",
+            Action::Quit => "Quit application",
+        }
+    }
+}
+
+/// A key plus the modifiers that matter for matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already part of a character's case and of BackTab, and
+        // terminals disagree on whether they also report it as a modifier
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    /// Human-readable form for the help overlay, e.g. `Ctrl+n`
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        match self.code {
+            KeyCode::Char(' ') => label.push_str("Space"),
+            KeyCode::Char(c) => label.push(c),
+            KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+            KeyCode::BackTab => label.push_str("Shift+Tab"),
+            KeyCode::PageUp => label.push_str("PgUp"),
+            KeyCode::PageDown => label.push_str("PgDn"),
+            code => label.push_str(&format!("{:?}", code)),
+        }
+        label
+    }
+}
+
+/// Parse a key from config, e.g. `q`, `G`, `esc`, `shift+tab`, `ctrl+n`
+pub fn parse_key(spec: &str) -> Result<KeyBinding> {
+    let (modifier_part, key_part) = match spec.rsplit_once('+') {
+        Some((mods, key)) if !mods.is_empty() && !key.is_empty() => (mods, key),
+        _ => ("", spec),
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            other => bail!("unknown modifier '{}' in key '{}'", other, spec),
+        };
+    }
+
+    let mut chars = key_part.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key_part.to_ascii_lowercase().as_str() {
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" | "del" => KeyCode::Delete,
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => bail!("unknown key '{}'", spec),
+            },
+        },
+    };
+
+    Ok(KeyBinding::new(code, modifiers))
+}
+
+/// Resolved key bindings
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    /// In preset order, so the help overlay lists keys predictably
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl KeyMap {
+    pub fn preset(preset: KeyPreset) -> Self {
+        let keys: &[(Action, &[&str])] = match preset {
+            KeyPreset::Default => &[
+                (Action::NextPanel, &["tab", "right"]),
+                (Action::PrevPanel, &["shift+tab", "left"]),
+                (Action::ScrollDown, &["down", "j"]),
+                (Action::ScrollUp, &["up", "k"]),
+                (Action::Select, &["enter"]),
+                (Action::ViewFrame, &["x"]),
+                (Action::CycleSort, &["s"]),
+                (Action::ReverseSort, &["r"]),
+                (Action::JumpToTime, &["g"]),
+                (Action::JumpToLive, &["G", "end"]),
+                (Action::ToggleMark, &["space"]),
+                (Action::ClearMarks, &["u"]),
+                (Action::IgnoreMarked, &["i"]),
+                (Action::LabelMarked, &["t"]),
+                (Action::ExportMarked, &["e"]),
+                (Action::WatchMarked, &["w"]),
+                (Action::CommandPalette, &["p"]),
+                (Action::QuickAnalyze, &["a"]),
+                (Action::ToggleHelp, &["?"]),
+                (Action::Close, &["esc"]),
+                (Action::Quit, &["q"]),
+            ],
+            KeyPreset::Vim => &[
+                (Action::NextPanel, &["l", "tab", "right"]),
+                (Action::PrevPanel, &["h", "shift+tab", "left"]),
+                (Action::ScrollDown, &["j", "down"]),
+                (Action::ScrollUp, &["k", "up"]),
+                (Action::Select, &["enter"]),
+                (Action::ViewFrame, &["x"]),
+                (Action::CycleSort, &["s"]),
+                (Action::ReverseSort, &["r"]),
+                (Action::JumpToTime, &[":"]),
+                (Action::JumpToLive, &["G", "end"]),
+                (Action::ToggleMark, &["space"]),
+                (Action::ClearMarks, &["u"]),
+                (Action::IgnoreMarked, &["i"]),
+                (Action::LabelMarked, &["t"]),
+                (Action::ExportMarked, &["e"]),
+                (Action::WatchMarked, &["w"]),
+                (Action::CommandPalette, &["p"]),
+                (Action::QuickAnalyze, &["a"]),
+                (Action::ToggleHelp, &["?"]),
+                (Action::Close, &["esc"]),
+                (Action::Quit, &["q"]),
+            ],
+            KeyPreset::Emacs => &[
+                (Action::NextPanel, &["ctrl+f", "tab", "right"]),
+                (Action::PrevPanel, &["ctrl+b", "shift+tab", "left"]),
+                (Action::ScrollDown, &["ctrl+n", "down"]),
+                (Action::ScrollUp, &["ctrl+p", "up"]),
+                (Action::Select, &["enter"]),
+                (Action::ViewFrame, &["alt+v"]),
+                (Action::CycleSort, &["alt+s"]),
+                (Action::ReverseSort, &["alt+r"]),
+                (Action::JumpToTime, &["alt+g"]),
+                (Action::JumpToLive, &["alt+>", "end"]),
+                (Action::ToggleMark, &["ctrl+space", "space"]),
+                (Action::ClearMarks, &["alt+u"]),
+                (Action::IgnoreMarked, &["alt+i"]),
+                (Action::LabelMarked, &["alt+t"]),
+                (Action::ExportMarked, &["alt+e"]),
+                (Action::WatchMarked, &["alt+w"]),
+                (Action::CommandPalette, &["alt+x"]),
+                (Action::QuickAnalyze, &["alt+a"]),
+                (Action::ToggleHelp, &["f1", "?"]),
+                (Action::Close, &["ctrl+g", "esc"]),
+                (Action::Quit, &["ctrl+x", "q"]),
+            ],
+        };
+
+        let bindings = keys
+            .iter()
+            .flat_map(|(action, specs)| {
+                specs
+                    .iter()
+                    .map(move |spec| (parse_key(spec).expect("preset keys parse"), *action))
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// The configured preset with `keys` overrides applied. Fails on unknown
+    /// actions or keys, a key bound to two actions, or no way to quit.
+    pub fn from_config(config: &TuiConfig) -> Result<Self> {
+        let mut map = Self::preset(config.key_preset);
+
+        for (name, specs) in &config.keys {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => {
+                    let known: Vec<&str> = Action::ALL.iter().map(|a| a.name()).collect();
+                    bail!("unknown action '{}' (expected one of: {})", name, known.join(", "));
+                }
+            };
+            map.bindings.retain(|(_, bound)| *bound != action);
+            for spec in specs {
+                map.bindings.push((parse_key(spec)?, action));
+            }
+        }
+
+        for (i, (key, action)) in map.bindings.iter().enumerate() {
+            if let Some((_, other)) = map.bindings[..i].iter().find(|(k, a)| k == key && a != action) {
+                bail!(
+                    "key '{}' is bound to both {} and {}",
+                    key.label(),
+                    other.name(),
+                    action.name()
+                );
+            }
+        }
+        if map.keys_for(Action::Quit).is_empty() {
+            bail!("no key is bound to quit");
+        }
+
+        Ok(map)
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let key = KeyBinding::new(event.code, event.modifiers);
+        self.bindings.iter().find(|(k, _)| *k == key).map(|(_, a)| *a)
+    }
+
+    /// Labels of every key bound to `action`
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for (key, _) in self.bindings.iter().filter(|(_, a)| *a == action) {
+            let label = key.label();
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::preset(KeyPreset::Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn config(preset: KeyPreset, keys: &[(&str, &[&str])]) -> TuiConfig {
+        TuiConfig {
+            key_preset: preset,
+            keys: keys
+                .iter()
+                .map(|(action, specs)| (action.to_string(), specs.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("q").unwrap(), KeyBinding::new(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert_eq!(parse_key("shift+g").unwrap(), parse_key("G").unwrap());
+        assert_eq!(parse_key("Shift+Tab").unwrap(), parse_key("backtab").unwrap());
+        assert_eq!(parse_key("ctrl+n").unwrap().label(), "Ctrl+n");
+        assert_eq!(parse_key("f5").unwrap().label(), "F5");
+        assert_eq!(parse_key("+").unwrap().label(), "+");
+        assert!(parse_key("hyper+q").is_err());
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("").is_err());
+    }
+
+    #[test]
+    fn test_presets_match_terminal_events() {
+        let default = KeyMap::default();
+        // Terminals may or may not report Shift alongside an uppercase char
+        assert_eq!(default.action(&press(KeyCode::Char('G'), KeyModifiers::SHIFT)), Some(Action::JumpToLive));
+        assert_eq!(default.action(&press(KeyCode::BackTab, KeyModifiers::SHIFT)), Some(Action::PrevPanel));
+        assert_eq!(default.action(&press(KeyCode::Char('n'), KeyModifiers::CONTROL)), None);
+
+        let emacs = KeyMap::preset(KeyPreset::Emacs);
+        assert_eq!(emacs.action(&press(KeyCode::Char('n'), KeyModifiers::CONTROL)), Some(Action::ScrollDown));
+        assert_eq!(emacs.action(&press(KeyCode::Char('n'), KeyModifiers::NONE)), None);
+
+        for preset in [KeyPreset::Default, KeyPreset::Vim, KeyPreset::Emacs] {
+            let map = KeyMap::from_config(&config(preset, &[])).unwrap();
+            assert!(Action::ALL.iter().all(|a| !map.keys_for(*a).is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_overrides_replace_preset_keys() {
+        let map = KeyMap::from_config(&config(KeyPreset::Default, &[("quit", &["ctrl+q"])])).unwrap();
+        assert_eq!(map.action(&press(KeyCode::Char('q'), KeyModifiers::NONE)), None);
+        assert_eq!(map.action(&press(KeyCode::Char('q'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(map.keys_for(Action::Quit), vec!["Ctrl+q"]);
+    }
+
+    #[test]
+    fn test_invalid_overrides_rejected() {
+        let err = KeyMap::from_config(&config(KeyPreset::Default, &[("scroll_down", &["s"])])).unwrap_err();
+        assert!(err.to_string().contains("bound to both"));
+        assert!(KeyMap::from_config(&config(KeyPreset::Default, &[("explode", &["x"])])).is_err());
+        assert!(KeyMap::from_config(&config(KeyPreset::Default, &[("help", &["ctrl+"])])).is_err());
+        assert!(KeyMap::from_config(&config(KeyPreset::Vim, &[("quit", &[])])).is_err());
+        // Moving a key by remapping both actions is fine
+        assert!(KeyMap::from_config(&config(
+            KeyPreset::Default,
+            &[("scroll_down", &["s"]), ("cycle_sort", &["o"])]
+        ))
+        .is_ok());
     }
 }
@@ -0,0 +1,166 @@
+//! Probe events on their way from the capture loop to the UI.
+//!
+//! The UI channel is bounded (`queues.ui_capacity`) so a probe storm can't
+//! stall capture. While the channel has room every probe goes through as
+//! its own event. Once it is more than half full, probes are held back and
+//! folded per device and SSID into one entry with a ×N repeat count, and the
+//! folded entries go out after `COALESCE_HOLD`. Whatever still doesn't fit
+//! is dropped. Both are counted and shown in the stats panel, so it's clear
+//! when the log isn't showing every probe on its own line.
+
+use crate::tui::app::ProbeLogEntry;
+use crate::tui::TuiEvent;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How long the first held-back probe waits for others to fold into it
+pub const COALESCE_HOLD: Duration = Duration::from_millis(500);
+
+/// Distinct device and SSID pairs held back at once; more are dropped
+const MAX_HELD: usize = 256;
+
+/// Sends probes to the UI, coalescing them while the channel is congested
+pub struct ProbeFeed {
+    events: mpsc::Sender<TuiEvent>,
+    held: Vec<ProbeLogEntry>,
+    held_since: Option<Instant>,
+    /// Probes the UI never got
+    dropped: Arc<AtomicU64>,
+    /// Probes folded into another entry's repeat count before sending
+    coalesced: Arc<AtomicU64>,
+}
+
+impl ProbeFeed {
+    pub fn new(events: mpsc::Sender<TuiEvent>, dropped: Arc<AtomicU64>, coalesced: Arc<AtomicU64>) -> Self {
+        ProbeFeed {
+            events,
+            held: Vec::new(),
+            held_since: None,
+            dropped,
+            coalesced,
+        }
+    }
+
+    /// More than half the channel is waiting for the UI
+    fn congested(&self) -> bool {
+        self.events.capacity() * 2 < self.events.max_capacity()
+    }
+
+    /// Send a probe to the UI, or hold it back to fold repeats into while
+    /// the channel is congested
+    pub fn send(&mut self, entry: ProbeLogEntry) {
+        if self.held.is_empty() && !self.congested() {
+            self.try_send(entry);
+            return;
+        }
+
+        match self.held.iter_mut().find(|held| held.mac == entry.mac && held.ssid == entry.ssid) {
+            Some(held) => {
+                self.coalesced.fetch_add(entry.repeat as u64, Ordering::Relaxed);
+                held.absorb(entry);
+            }
+            None if self.held.len() >= MAX_HELD => {
+                self.dropped.fetch_add(entry.repeat as u64, Ordering::Relaxed);
+            }
+            None => {
+                self.held_since.get_or_insert_with(Instant::now);
+                self.held.push(entry);
+            }
+        }
+        self.flush_due();
+    }
+
+    /// Send held-back probes once they've waited `COALESCE_HOLD`. Called
+    /// on every pass of the capture loop, so they go out after a storm too.
+    pub fn flush_due(&mut self) {
+        if self.held_since.is_some_and(|since| since.elapsed() >= COALESCE_HOLD) {
+            self.flush();
+        }
+    }
+
+    /// Send everything held back now
+    pub fn flush(&mut self) {
+        for entry in std::mem::take(&mut self.held) {
+            self.try_send(entry);
+        }
+        self.held_since = None;
+    }
+
+    fn try_send(&self, entry: ProbeLogEntry) {
+        let repeat = entry.repeat as u64;
+        if self.events.try_send(TuiEvent::ProbeReceived(entry)).is_err() {
+            self.dropped.fetch_add(repeat, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(mac: &str, ssid: &str, timestamp: i64) -> ProbeLogEntry {
+        ProbeLogEntry {
+            timestamp,
+            mac: mac.to_string(),
+            ssid: ssid.to_string(),
+            signal_dbm: Some(-60),
+            distance_m: None,
+            channel: Some(6),
+            capabilities: None,
+            repeat: 1,
+            frame: None,
+        }
+    }
+
+    fn received(rx: &mut mpsc::Receiver<TuiEvent>) -> Vec<(String, u32)> {
+        let mut probes = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let TuiEvent::ProbeReceived(entry) = event {
+                probes.push((entry.mac, entry.repeat));
+            }
+        }
+        probes
+    }
+
+    #[test]
+    fn test_feed_coalesces_when_congested() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let coalesced = Arc::new(AtomicU64::new(0));
+        let mut feed = ProbeFeed::new(tx, dropped.clone(), coalesced.clone());
+
+        // Three go straight through and fill most of the channel
+        for mac in ["AA:BB:CC:00:00:01", "AA:BB:CC:00:00:02", "AA:BB:CC:00:00:03"] {
+            feed.send(entry(mac, "", 1));
+        }
+        // Congested: a burst from one device folds into a single entry
+        feed.send(entry("AA:BB:CC:00:00:04", "", 1));
+        for t in 2..12 {
+            feed.send(entry("AA:BB:CC:00:00:01", "Cafe", t));
+        }
+        feed.send(entry("AA:BB:CC:00:00:04", "", 12));
+        assert_eq!(coalesced.load(Ordering::Relaxed), 10);
+        assert_eq!(received(&mut rx).len(), 3);
+
+        feed.flush();
+        assert_eq!(
+            received(&mut rx),
+            vec![("AA:BB:CC:00:00:04".to_string(), 2), ("AA:BB:CC:00:00:01".to_string(), 10)]
+        );
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+
+        // With nobody reading, a full channel drops and counts every probe
+        for mac in ["AA:BB:CC:00:00:04", "AA:BB:CC:00:00:05", "AA:BB:CC:00:00:06"] {
+            feed.send(entry(mac, "", 20));
+        }
+        feed.flush();
+        feed.send(entry("AA:BB:CC:00:00:07", "", 21));
+        let mut burst = entry("AA:BB:CC:00:00:08", "", 21);
+        burst.repeat = 5;
+        feed.send(burst);
+        feed.flush();
+        assert_eq!(dropped.load(Ordering::Relaxed), 5);
+    }
+}
@@ -1,30 +1,54 @@
+pub mod accessible;
 pub mod app;
+pub mod bulk;
 pub mod event;
+pub mod feed;
 pub mod ui;
 pub mod widgets;
 
-use crate::channels::ChannelHopper;
-use crate::validation::validate_startup;
+use crate::analysis::SurveillanceAlert;
+use crate::anomaly::EVENT_NEW_DEVICE_SPIKE;
+use crate::homenet::EVENT_MY_SSID_PROBE;
+use crate::capture::{
+    deauth_event, spawn_db_writer, start_capture_session, BeaconThrottle, DropWatch, ProbeRateLimiter,
+};
+use crate::channels::{ChannelHopper, ChannelProfile};
+use crate::validation::{validate_startup, ValidationError, ValidationResult};
 use crate::config::Config;
-use crate::database::{Database, ProbeCapture};
+use crate::database::{CaptureRecord, Database, GpsStatus, ProbeCapture};
+use crate::dedup::RetryFilter;
+use crate::disk::{is_low_value, spawn_disk_monitor, DiskState};
 use crate::distance::estimate_distance;
-use crate::gps::GpsClient;
+#[cfg(feature = "gps")]
+use crate::gps::{GpsClient, GpsReport};
 use crate::ignore::IgnoreLists;
-use crate::parser::parse_probe_request;
+use crate::instance::{InstanceLock, LockAttempt};
+use crate::occupancy::estimate_occupancy;
+use crate::parser::{parse_beacon, parse_deauth, parse_probe_request};
+use crate::power::{spawn_power_monitor, BATTERY_UNKNOWN};
+use crate::privileges::{check_capture_privileges, PrivilegeDrop, CAP_NET_ADMIN};
+use crate::queue::BoundedQueue;
+use crate::radiotap::parse_radiotap;
+use crate::retention::spawn_purge;
+use crate::risk::spawn_risk_updater;
+use crate::source::{capture_filter, open_source, FrameTime};
+use crate::utilization::{frame_airtime_us, DwellClock, UtilizationTracker};
+use bulk::BulkActions;
+use event::{Action, KeyMap};
+use feed::ProbeFeed;
 use anyhow::{Context, Result};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use log::{warn, LevelFilter};
-use pcap::Capture;
+use log::LevelFilter;
 use ratatui::prelude::*;
 use std::io;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
 
 pub use app::{App, DeviceEntry, ProbeLogEntry, Stats};
 
@@ -33,14 +57,57 @@ pub use app::{App, DeviceEntry, ProbeLogEntry, Stats};
 pub enum TuiEvent {
     ProbeReceived(ProbeLogEntry),
     GpsUpdate(f64, f64),
+    /// Fix mode (0/1 none, 2 = 2D, 3 = 3D), speed (m/s) and track (degrees)
+    GpsFix {
+        mode: u8,
+        speed: Option<f64>,
+        track: Option<f64>,
+    },
+    GpsSky {
+        used: Option<u32>,
+        visible: Option<u32>,
+        hdop: Option<f64>,
+    },
     GpsDisconnected,
     ChannelChanged(u8),
     StatsUpdate(Stats),
+    /// Quick analysis finished, with its alerts or the error
+    AnalysisDone(Result<Vec<SurveillanceAlert>, String>),
     CaptureStarted,
     CaptureStopped,
     Error(String),
 }
 
+/// Control messages from the TUI to the capture loop
+#[derive(Debug, Clone)]
+pub enum CaptureControl {
+    SetChannels(ChannelProfile),
+    SetDistance(bool),
+    SetGpsTagging(bool),
+}
+
+/// The capture loop's side of its channels to the UI
+struct UiLink {
+    events: mpsc::Sender<TuiEvent>,
+    control: mpsc::Receiver<CaptureControl>,
+    /// Probe events dropped because the UI queue was full
+    dropped: Arc<AtomicU64>,
+    /// Probes folded into others while the UI queue was congested
+    coalesced: Arc<AtomicU64>,
+    /// Frames dropped by the capture source, as last reported by it
+    kernel_dropped: Arc<AtomicU64>,
+    /// Frames discarded for failing their FCS check
+    corrupt_frames: Arc<AtomicU64>,
+    /// Whether to shed low-value probes for disk space
+    disk: Arc<DiskState>,
+}
+
+/// Open the database, read-only when attached to another prowl's capture
+pub(crate) fn open_database(path: &str, read_only: bool) -> Result<Database> {
+    let db = if read_only { Database::open_read_only(path) } else { Database::open(path) };
+    db.context("Failed to open database")
+}
+
 /// Setup terminal for TUI mode
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
@@ -63,36 +130,81 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Re
     Ok(())
 }
 
-/// Run the TUI application
-pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
-    // Perform startup validation (GPS + monitor mode)
-    let validation = match validate_startup(&config, set_monitor) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("Startup validation failed:\n{}", e);
-            return Ok(());
+/// Run the TUI application. With `browse`, or without the privileges live
+/// capture needs, it opens read-only on the database instead.
+pub async fn run_tui(mut config: Config, set_monitor: bool, browse: bool) -> Result<()> {
+    let keymap = KeyMap::from_config(&config.tui).context("Invalid tui key bindings")?;
+
+    let browse_reason = if browse {
+        Some("Browsing the database, nothing is captured".to_string())
+    } else {
+        match check_capture_privileges(&config.capture) {
+            Err(ValidationError::MissingPrivileges { missing, .. }) => Some(format!(
+                "Browsing only: capture needs {} (run with sudo to capture)",
+                missing.join(" and ")
+            )),
+            _ => None,
+        }
+    };
+
+    // Perform startup validation (GPS + monitor mode). Browsing leaves the
+    // adapter alone and has nothing to geotag.
+    let validation = if browse_reason.is_some() {
+        config.gps.enabled = false;
+        ValidationResult {
+            interface: config.capture.interface.clone(),
+            second_interface: None,
+            gps_available: None,
+            gps_error: None,
+        }
+    } else {
+        match validate_startup(&config, set_monitor) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(anyhow::Error::new(e).context("Startup validation failed"));
+            }
         }
     };
 
     // Update config with resolved interface
     config.capture.interface = validation.interface;
+    config.capture.second_interface = validation.second_interface;
 
     // Track GPS status from validation
+    #[cfg(feature = "gps")]
     let gps_available = validation.gps_available.unwrap_or(false);
     let gps_error = validation.gps_error;
 
+    // With another prowl already capturing into the database, attach
+    // read-only: the panels follow the database and nothing is captured.
+    // Browsing doesn't take the lock, so a capture can still start.
+    let (_lock, attached_to) = match browse_reason {
+        Some(reason) => (None, Some(reason)),
+        None => match InstanceLock::try_acquire(&config.capture.database, "tui")? {
+            LockAttempt::Acquired(lock) => (Some(lock), None),
+            LockAttempt::Held(owner) => {
+                let owner = owner.map(|o| o.describe()).unwrap_or_else(|| "another prowl process".to_string());
+                (None, Some(format!("Read-only, {} is capturing into this database", owner)))
+            }
+        },
+    };
+
     // Disable logging to prevent interference with TUI display
     log::set_max_level(LevelFilter::Off);
 
     // Create event channel
-    let (event_tx, event_rx) = mpsc::channel::<TuiEvent>(1000);
+    let (event_tx, event_rx) = mpsc::channel::<TuiEvent>(config.queues.ui_capacity.max(1));
 
-    // Open database
-    let db = Database::open(&config.capture.database).context("Failed to open database")?;
+    // Open database; attached, it is only ever read
+    let read_only = attached_to.is_some();
+    let db = open_database(&config.capture.database, read_only)?;
 
     // Load ignore lists
-    let ignore_lists =
-        IgnoreLists::load(&config.ignore_lists.mac, &config.ignore_lists.ssid).unwrap_or_default();
+    let mut lists = IgnoreLists::load(&config.ignore_lists.mac, &config.ignore_lists.ssid).unwrap_or_default();
+    if config.ignore_lists.ignore_own_macs {
+        lists.ignore_local_macs();
+    }
+    let ignore_lists = Arc::new(RwLock::new(lists));
 
     // Get initial stats
     let initial_stats = Stats {
@@ -104,6 +216,21 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
     // Create running flag
     let running = Arc::new(AtomicBool::new(true));
 
+    // Battery level for the stats panel; a dying battery stops capture cleanly
+    let battery_level = Arc::new(AtomicU8::new(BATTERY_UNKNOWN));
+    let _power = spawn_power_monitor(
+        &config.power,
+        &config.capture.database,
+        running.clone(),
+        battery_level.clone(),
+        None,
+    );
+
+    // Free space for the stats panel; capture sheds low-value probes when
+    // the disk is about to fill and `disk.shed_low_value` is on
+    let disk = Arc::new(DiskState::new());
+    let _disk = spawn_disk_monitor(&config.disk, &config.capture.database, disk.clone(), running.clone());
+
     // Create shared GPS position for capture task
     let shared_gps_position: Arc<RwLock<Option<(f64, f64)>>> = Arc::new(RwLock::new(None));
 
@@ -115,27 +242,67 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
         original_hook(panic);
     }));
 
+    // Bounded database queue drained by a writer thread; probe events for the
+    // UI are dropped (and counted) rather than stalling capture
+    let db_queue = BoundedQueue::new(config.queues.db_capacity, config.queues.db_policy);
+    let ui_dropped = Arc::new(AtomicU64::new(0));
+    let ui_coalesced = Arc::new(AtomicU64::new(0));
+    let kernel_dropped = Arc::new(AtomicU64::new(0));
+    let corrupt_frames = Arc::new(AtomicU64::new(0));
+    let (control_tx, control_rx) = mpsc::channel::<CaptureControl>(16);
+
+    // Whoever captures keeps the risk scores fresh, and purges old raw data
+    let _risk = attached_to.is_none().then(|| spawn_risk_updater(&config, running.clone())).flatten();
+    let _purge = attached_to.is_none().then(|| spawn_purge(&config, running.clone())).flatten();
+
     // Spawn capture task
-    let capture_tx = event_tx.clone();
-    let capture_running = running.clone();
-    let capture_config = config.clone();
-    let capture_db = Database::open(&config.capture.database)?;
-    let capture_ignore = ignore_lists.clone();
-    let capture_gps_position = shared_gps_position.clone();
-
-    let capture_handle = tokio::spawn(async move {
-        run_capture_loop(
-            capture_config,
+    let capture = if attached_to.is_none() {
+        let capture_tx = event_tx.clone();
+        let capture_running = running.clone();
+        let capture_config = config.clone();
+        let mut capture_db = Database::open(&config.capture.database)?;
+        start_capture_session(&mut capture_db, &config);
+        let capture_ignore = ignore_lists.clone();
+        let capture_gps_position = shared_gps_position.clone();
+
+        let db_writer = spawn_db_writer(
             capture_db,
-            capture_ignore,
-            capture_running,
-            capture_tx,
-            capture_gps_position,
-        )
-        .await
-    });
+            db_queue.clone(),
+            &config.anomaly,
+            &config.burst,
+            &config.analysis,
+            &config.queues,
+        );
+        let capture_queue = db_queue.clone();
+        let capture_link = UiLink {
+            events: capture_tx,
+            control: control_rx,
+            dropped: ui_dropped.clone(),
+            coalesced: ui_coalesced.clone(),
+            kernel_dropped: kernel_dropped.clone(),
+            corrupt_frames: corrupt_frames.clone(),
+            disk: disk.clone(),
+        };
+
+        // Reading packets blocks, so capture gets a blocking-pool thread and
+        // never holds up the runtime workers driving the UI
+        let capture_handle = tokio::task::spawn_blocking(move || {
+            run_capture_loop(
+                capture_config,
+                capture_queue,
+                capture_ignore,
+                capture_running,
+                capture_gps_position,
+                capture_link,
+            )
+        });
+        Some((capture_handle, db_writer))
+    } else {
+        None
+    };
 
     // Spawn GPS task if enabled and available
+    #[cfg(feature = "gps")]
     if config.gps.enabled && gps_available {
         let gps_tx = event_tx.clone();
         let gps_running = running.clone();
@@ -144,7 +311,8 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
         let gps_shared_position = shared_gps_position.clone();
 
         tokio::spawn(async move {
-            let gps_client = GpsClient::new(gps_host, gps_port);
+            let (report_tx, mut report_rx) = mpsc::channel(16);
+            let gps_client = GpsClient::new(gps_host, gps_port).with_reports(report_tx);
             let (pos_tx, mut pos_rx) = mpsc::channel(1);
 
             let gps_run = gps_running.clone();
@@ -161,6 +329,26 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
                         }
                         let _ = gps_tx.send(TuiEvent::GpsUpdate(lat, lon)).await;
                     }
+                    Some(report) = report_rx.recv() => {
+                        let event = match report {
+                            GpsReport::Position(pos) => TuiEvent::GpsFix {
+                                mode: pos.mode,
+                                speed: pos.speed,
+                                track: pos.track,
+                            },
+                            GpsReport::NoFix(mode) => TuiEvent::GpsFix {
+                                mode,
+                                speed: None,
+                                track: None,
+                            },
+                            GpsReport::Sky(sky) => TuiEvent::GpsSky {
+                                used: sky.satellites_used,
+                                visible: sky.satellites_visible,
+                                hdop: sky.hdop,
+                            },
+                        };
+                        let _ = gps_tx.send(event).await;
+                    }
                     _ = tokio::time::sleep(Duration::from_secs(5)) => {
                         // Timeout, continue
                     }
@@ -173,6 +361,16 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
     let stats_tx = event_tx.clone();
     let stats_running = running.clone();
     let stats_db_path = config.capture.database.clone();
+    let occupancy_window = config.occupancy.bucket_minutes as i64 * 60;
+    let devices_per_person = config.occupancy.devices_per_person;
+    let anomaly_bucket = config.anomaly.bucket_secs.max(1) as i64;
+    let stats_db_queue = db_queue.clone();
+    let stats_ui_dropped = ui_dropped.clone();
+    let stats_ui_coalesced = ui_coalesced.clone();
+    let stats_kernel_dropped = kernel_dropped.clone();
+    let stats_corrupt_frames = corrupt_frames.clone();
+    let stats_battery = battery_level.clone();
+    let stats_disk = disk.clone();
     let start_time = Instant::now();
 
     tokio::spawn(async move {
@@ -180,7 +378,7 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
         while stats_running.load(Ordering::SeqCst) {
             interval.tick().await;
 
-            if let Ok(db) = Database::open(&stats_db_path) {
+            if let Ok(db) = open_database(&stats_db_path, read_only) {
                 let now = chrono::Utc::now().timestamp();
                 let five_min_ago = now - 300;
                 let fifteen_min_ago = now - 900;
@@ -197,6 +395,42 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
                         .map(|d| d.len())
                         .unwrap_or(0),
                     capture_duration_secs: start_time.elapsed().as_secs(),
+                    estimated_occupancy: db
+                        .get_probe_observations(now - occupancy_window, now)
+                        .map(|obs| estimate_occupancy(&obs, devices_per_person).people)
+                        .unwrap_or(0.0),
+                    dropped_probes: stats_db_queue.dropped(),
+                    ui_dropped: stats_ui_dropped.load(Ordering::Relaxed),
+                    ui_coalesced: stats_ui_coalesced.load(Ordering::Relaxed),
+                    kernel_dropped: stats_kernel_dropped.load(Ordering::Relaxed),
+                    corrupt_frames: stats_corrupt_frames.load(Ordering::Relaxed),
+                    battery_percent: match stats_battery.load(Ordering::Relaxed) {
+                        BATTERY_UNKNOWN => None,
+                        percent => Some(percent),
+                    },
+                    new_devices_per_min: db
+                        .count_new_devices(now - anomaly_bucket, now)
+                        .map(|n| n as f64 * 60.0 / anomaly_bucket as f64)
+                        .unwrap_or(0.0),
+                    // Spikes stay visible for ten minutes
+                    new_device_spike: db
+                        .get_events_since(now - 600, Some(EVENT_NEW_DEVICE_SPIKE))
+                        .ok()
+                        .and_then(|events| events.into_iter().next())
+                        .map(|e| e.message),
+                    my_ssid_probe: db
+                        .get_events_since(now - 600, Some(EVENT_MY_SSID_PROBE))
+                        .ok()
+                        .and_then(|events| events.into_iter().next())
+                        .map(|e| e.message),
+                    channel_usage: db
+                        .latest_session_id()
+                        .ok()
+                        .flatten()
+                        .and_then(|id| db.get_channel_usage(Some(id)).ok())
+                        .unwrap_or_default(),
+                    risk_scores: db.get_risk_scores().unwrap_or_default(),
+                    disk: stats_disk.status(),
                     ..Default::default()
                 };
 
@@ -205,21 +439,54 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
         }
     });
 
-    // Create app
-    let mut app = App::new(event_rx, initial_stats, config.gps.enabled, gps_error);
-
-    // Setup terminal
-    let mut terminal = setup_terminal()?;
-
-    // Run event loop
-    let tick_rate = Duration::from_millis(50); // 20 FPS for efficiency
+    if let Some(message) = &attached_to {
+        let _ = event_tx.try_send(TuiEvent::Error(message.clone()));
+    }
 
-    let result = run_event_loop(&mut terminal, &mut app, tick_rate, running.clone()).await;
+    let result = if config.tui.accessible {
+        accessible::run(event_rx, running.clone()).await
+    } else {
+        // Create app
+        let mut app = App::new(event_rx, initial_stats, config.gps.enabled, gps_error, keymap);
+        if let Some(message) = attached_to {
+            app.attached = true;
+            app.status_message = Some(message);
+        }
+        app.labels = db.get_device_labels().unwrap_or_default();
+        let since = chrono::Utc::now().timestamp() - config.tui.analyze_hours.max(1) as i64 * 3600;
+        app.restore_devices(db.get_recent_devices(since).unwrap_or_default());
+        app.watched = db.get_watchlist().unwrap_or_default().into_iter().collect();
+        app.distance_enabled = config.distance.enabled;
+        let actions = BulkActions::new(config.clone(), ignore_lists.clone()).with_read_only(read_only);
+
+        // Setup terminal
+        let mut terminal = setup_terminal()?;
+
+        // Run event loop
+        let tick_rate = Duration::from_millis(50); // 20 FPS for efficiency
+
+        let result = run_event_loop(
+            &mut terminal,
+            &mut app,
+            &actions,
+            &control_tx,
+            &event_tx,
+            tick_rate,
+            running.clone(),
+        )
+        .await;
+        restore_terminal(&mut terminal)?;
+        result
+    };
 
     // Cleanup
     running.store(false, Ordering::SeqCst);
-    capture_handle.abort();
-    restore_terminal(&mut terminal)?;
+    // The source read times out, so the loop sees `running` within a read
+    if let Some((capture_handle, db_writer)) = capture {
+        let _ = capture_handle.await;
+        db_queue.close();
+        let _ = db_writer.join();
+    }
 
     result
 }
@@ -227,6 +494,9 @@ pub async fn run_tui(mut config: Config, set_monitor: bool) -> Result<()> {
 async fn run_event_loop(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
+    actions: &BulkActions,
+    control_tx: &mpsc::Sender<CaptureControl>,
+    events: &mpsc::Sender<TuiEvent>,
     tick_rate: Duration,
     running: Arc<AtomicBool>,
 ) -> Result<()> {
@@ -242,43 +512,160 @@ async fn run_event_loop(
         // Poll for events
         if crossterm::event::poll(timeout)? {
             if let Event::Key(key) = crossterm::event::read()? {
-                if key.kind == KeyEventKind::Press {
+                if key.kind == KeyEventKind::Press && app.jump_input.is_some() {
+                    // Jump-to-timestamp prompt captures all keys while open
                     match key.code {
-                        KeyCode::Char('q') => {
+                        KeyCode::Enter => app.submit_jump(),
+                        KeyCode::Esc => app.jump_input = None,
+                        KeyCode::Backspace => {
+                            if let Some(input) = app.jump_input.as_mut() {
+                                input.pop();
+                            }
+                        }
+                        KeyCode::Char(c) if c.is_ascii_digit() || c == ':' => {
+                            if let Some(input) = app.jump_input.as_mut() {
+                                if input.len() < 8 {
+                                    input.push(c);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press && app.palette.is_some() {
+                    // Capture settings palette also captures all keys while open
+                    match (key.code, app.keymap.action(&key)) {
+                        (KeyCode::Char(c @ '1'..='9'), _) => {
+                            app.palette = Some(c as usize - '1' as usize);
+                            submit_palette(app, control_tx);
+                        }
+                        (KeyCode::Enter, _) => submit_palette(app, control_tx),
+                        (KeyCode::Esc, _) | (_, Some(Action::Close)) => app.palette = None,
+                        (KeyCode::Up, _) | (_, Some(Action::ScrollUp)) => app.palette_move(-1),
+                        (KeyCode::Down, _) | (_, Some(Action::ScrollDown)) => app.palette_move(1),
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press && app.analysis.is_some() {
+                    // So does the analysis overlay
+                    match (key.code, app.keymap.action(&key)) {
+                        (KeyCode::Esc, _) | (_, Some(Action::Close)) => app.analysis = None,
+                        (KeyCode::Up, _) | (_, Some(Action::ScrollUp)) => app.analysis_scroll(-1),
+                        (KeyCode::Down, _) | (_, Some(Action::ScrollDown)) => app.analysis_scroll(1),
+                        (_, Some(Action::QuickAnalyze)) => start_quick_analysis(app, actions, events),
+                        (_, Some(Action::Quit)) => app.running = false,
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press && app.label_input.is_some() {
+                    match key.code {
+                        KeyCode::Enter => {
+                            let label = app.label_input.take().unwrap_or_default();
+                            let label = label.trim();
+                            let macs = app.bulk_targets();
+                            if !label.is_empty() && show_result(app, actions.label(&macs, label)) {
+                                for mac in macs {
+                                    app.labels.insert(mac, label.to_string());
+                                }
+                            }
+                        }
+                        KeyCode::Esc => app.label_input = None,
+                        KeyCode::Backspace => {
+                            if let Some(input) = app.label_input.as_mut() {
+                                input.pop();
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            if let Some(input) = app.label_input.as_mut() {
+                                if input.chars().count() < 32 {
+                                    input.push(c);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if key.kind == KeyEventKind::Press {
+                    match app.keymap.action(&key) {
+                        Some(Action::Quit) => {
                             app.running = false;
                         }
-                        KeyCode::Char('?') => {
+                        Some(Action::ToggleHelp) => {
                             app.show_help = !app.show_help;
                         }
-                        KeyCode::Tab | KeyCode::Right => {
+                        Some(Action::NextPanel) => {
                             app.next_panel();
                         }
-                        KeyCode::BackTab | KeyCode::Left => {
+                        Some(Action::PrevPanel) => {
                             app.prev_panel();
                         }
-                        KeyCode::Down | KeyCode::Char('j') => {
+                        Some(Action::ScrollDown) => {
                             app.scroll_down();
                         }
-                        KeyCode::Up | KeyCode::Char('k') => {
+                        Some(Action::ScrollUp) => {
                             app.scroll_up();
                         }
-                        KeyCode::Char('s') => {
+                        Some(Action::CycleSort) => {
                             app.cycle_sort();
                         }
-                        KeyCode::Char('r') => {
+                        Some(Action::ReverseSort) => {
                             app.reverse_sort();
                         }
-                        KeyCode::Enter => {
+                        Some(Action::JumpToTime) => {
+                            app.start_jump();
+                        }
+                        Some(Action::JumpToLive) => {
+                            app.jump_to_live();
+                        }
+                        Some(Action::Select) => {
                             app.select_device();
+                            if let Some(idx) = app.detail_view {
+                                // A device still queued for the database has no links yet
+                                app.detail_links = actions.links(&app.devices[idx].mac).unwrap_or_default();
+                            }
+                        }
+                        Some(Action::ViewFrame) => {
+                            app.view_frame();
+                        }
+                        Some(Action::ToggleMark) => {
+                            app.toggle_mark();
                         }
-                        KeyCode::Esc => {
+                        Some(Action::ClearMarks) => {
+                            app.clear_marks();
+                        }
+                        Some(Action::IgnoreMarked) => {
+                            let macs = app.bulk_targets();
+                            if !macs.is_empty() && show_result(app, actions.ignore(&macs)) {
+                                app.remove_devices(&macs);
+                            }
+                        }
+                        Some(Action::LabelMarked) => {
+                            app.start_label();
+                        }
+                        Some(Action::ExportMarked) => {
+                            let macs = app.bulk_targets();
+                            if !macs.is_empty() {
+                                show_result(app, actions.export(&macs));
+                            }
+                        }
+                        Some(Action::CommandPalette) => {
+                            app.open_palette();
+                        }
+                        Some(Action::QuickAnalyze) => {
+                            start_quick_analysis(app, actions, events);
+                        }
+                        Some(Action::WatchMarked) => {
+                            let macs = app.bulk_targets();
+                            if !macs.is_empty() && show_result(app, actions.watch(&macs)) {
+                                app.watched.extend(macs);
+                            }
+                        }
+                        Some(Action::Close) => {
                             if app.show_help {
                                 app.show_help = false;
+                            } else if app.frame_view.is_some() {
+                                app.frame_view = None;
                             } else if app.detail_view.is_some() {
                                 app.detail_view = None;
                             }
                         }
-                        _ => {}
+                        None => {}
                     }
                 }
             }
@@ -299,88 +686,216 @@ async fn run_event_loop(
             running.store(false, Ordering::SeqCst);
             break;
         }
+        // Capture stopped underneath us (low battery)
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
     }
 
     Ok(())
 }
 
-/// Capture loop that sends events to TUI
-async fn run_capture_loop(
+/// Run the analyzer on a blocking thread and open the overlay; the result
+/// arrives as an event
+fn start_quick_analysis(app: &mut App, actions: &BulkActions, events: &mpsc::Sender<TuiEvent>) {
+    if !app.start_analysis(actions.analyze_hours()) {
+        return;
+    }
+    let actions = actions.clone();
+    let events = events.clone();
+    tokio::task::spawn_blocking(move || {
+        let result = actions.analyze().map_err(|e| format!("{:#}", e));
+        let _ = events.blocking_send(TuiEvent::AnalysisDone(result));
+    });
+}
+
+/// Apply the selected palette command and pass it on to the capture loop
+fn submit_palette(app: &mut App, control_tx: &mpsc::Sender<CaptureControl>) {
+    if let Some(control) = app.submit_palette() {
+        if control_tx.try_send(control).is_err() {
+            app.status_message = Some("Capture is not running; setting not applied".to_string());
+        }
+    }
+}
+
+/// Show a bulk action's outcome in the status bar. Returns true on success.
+fn show_result(app: &mut App, result: Result<String>) -> bool {
+    let ok = result.is_ok();
+    app.status_message = Some(result.unwrap_or_else(|e| format!("Failed: {:#}", e)));
+    ok
+}
+
+/// Capture loop that sends events to TUI. Runs on a blocking thread; the
+/// hopper and channel tasks it starts still run on the runtime.
+fn run_capture_loop(
     config: Config,
-    db: Database,
-    ignore_lists: IgnoreLists,
+    db_queue: BoundedQueue<CaptureRecord>,
+    ignore_lists: Arc<RwLock<IgnoreLists>>,
     running: Arc<AtomicBool>,
-    event_tx: mpsc::Sender<TuiEvent>,
     shared_gps_position: Arc<RwLock<Option<(f64, f64)>>>,
+    ui: UiLink,
 ) -> Result<()> {
+    let UiLink {
+        events: event_tx,
+        control: mut control_rx,
+        dropped: ui_dropped,
+        coalesced: ui_coalesced,
+        kernel_dropped,
+        corrupt_frames,
+        disk,
+    } = ui;
     let interface = &config.capture.interface;
 
     // Open capture handle
-    let mut cap = Capture::from_device(interface.as_str())
-        .context("Failed to open capture device")?
-        .promisc(true)
-        .snaplen(65535)
-        .timeout(100)
-        .open()
-        .context("Failed to activate capture")?;
-
-    // Set BPF filter
-    if let Err(e) = cap.filter("type mgt subtype probe-req", true) {
-        warn!("Failed to set BPF filter: {}", e);
-    }
-
-    // Start channel hopper
-    let hopper = ChannelHopper::new(
+    let mut source = open_source(
+        &config.capture,
+        Some(capture_filter(&config.capture).as_str()),
+        100,
+    )
+    .context("Failed to activate capture")?;
+
+    // Start channel hopper; the palette can swap its channel set
+    let (channel_updates, channel_rx) = watch::channel(config.capture.channels.clone());
+    let mut hopper = ChannelHopper::new(
         interface.clone(),
         config.capture.channels.clone(),
         config.capture.hop_interval_ms,
-    );
+    )
+    .with_band_split(config.capture.second_interface.clone())
+    .with_channel_updates(channel_rx.clone());
+    let dwell = config.capture.measure_utilization.then(|| Arc::new(DwellClock::new()));
+    if let Some(dwell) = &dwell {
+        hopper = hopper.with_dwell_clock(dwell.clone());
+    }
     let hopper_running = running.clone();
-    let hopper_channels = hopper.channels().to_vec();
+    let mut hopper_channels = hopper.channels().to_vec();
     let hopper_interval = hopper.hop_interval_ms();
-    tokio::spawn(async move {
-        let _ = hopper.run(hopper_running.clone()).await;
-    });
+    // As in `prowl capture`, the hopper's thread gives up root for the
+    // process and keeps CAP_NET_ADMIN
+    let _hopper = if config.capture.backend.has_radio() {
+        let privilege_drop = PrivilegeDrop::plan(&config.capture)?;
+        Some(hopper.spawn_thread(hopper_running, move || match privilege_drop {
+            Some(plan) => plan.apply(&[CAP_NET_ADMIN]),
+            None => Ok(()),
+        })?)
+    } else {
+        None
+    };
 
     // Send channel change events
     let channel_tx = event_tx.clone();
     let channel_running = running.clone();
+    let mut channel_rx = channel_rx;
     tokio::spawn(async move {
         let mut idx = 0;
         while channel_running.load(Ordering::SeqCst) {
+            if channel_rx.has_changed().unwrap_or(false) {
+                hopper_channels = channel_rx.borrow_and_update().clone();
+                idx = 0;
+            }
+            let mut dwell_ms = hopper_interval;
             if !hopper_channels.is_empty() {
-                let ch = hopper_channels[idx % hopper_channels.len()];
-                let _ = channel_tx.send(TuiEvent::ChannelChanged(ch)).await;
+                let entry = hopper_channels[idx % hopper_channels.len()];
+                let _ = channel_tx.send(TuiEvent::ChannelChanged(entry.channel())).await;
+                dwell_ms = entry.dwell_ms(hopper_interval);
                 idx += 1;
             }
-            tokio::time::sleep(Duration::from_millis(hopper_interval)).await;
+            tokio::time::sleep(Duration::from_millis(dwell_ms)).await;
         }
     });
 
-    let _ = event_tx.send(TuiEvent::CaptureStarted).await;
+    let _ = event_tx.blocking_send(TuiEvent::CaptureStarted);
+
+    let mut beacons = config.capture.capture_beacons.then(BeaconThrottle::default);
+    let mut distance_enabled = config.distance.enabled;
+    let mut gps_tagging = config.gps.enabled;
+    let mut drop_watch = DropWatch::new();
+    let mut utilization = config.capture.measure_utilization.then(UtilizationTracker::new);
+    let mut retries = RetryFilter::new(config.capture.retry_window_ms);
+    let mut rate_limit = config.capture.max_probes_per_mac_per_minute.map(ProbeRateLimiter::new);
+    let mut feed = ProbeFeed::new(event_tx.clone(), ui_dropped, ui_coalesced);
 
     while running.load(Ordering::SeqCst) {
-        match cap.next_packet() {
-            Ok(packet) => {
-                // Extract signal from radiotap
-                let signal_dbm = extract_signal_dbm(packet.data);
-
-                if let Some(probe) = parse_probe_request(packet.data, signal_dbm) {
-                    // Check ignore lists
-                    if ignore_lists.should_ignore_mac(&probe.source_mac) {
+        if let Some(drops) = drop_watch.poll(source.as_mut()) {
+            kernel_dropped.store(drops.total_dropped(), Ordering::Relaxed);
+        }
+        feed.flush_due();
+
+        if let Some(usage) = utilization.as_mut().and_then(|u| u.take_due(dwell.as_deref())) {
+            db_queue.push(CaptureRecord::Utilization(usage));
+        }
+        if let Some(counts) = rate_limit.as_mut().and_then(|l| l.take_due()) {
+            db_queue.push(CaptureRecord::Suppressed(counts));
+        }
+
+        while let Ok(control) = control_rx.try_recv() {
+            match control {
+                CaptureControl::SetChannels(profile) => {
+                    let _ = channel_updates.send(profile.entries(&config.capture.channels));
+                }
+                CaptureControl::SetDistance(enabled) => distance_enabled = enabled,
+                CaptureControl::SetGpsTagging(enabled) => gps_tagging = enabled,
+            }
+        }
+
+        match source.next_timestamped() {
+            Ok(Some((data, captured_at))) => {
+                let captured_at = captured_at.unwrap_or_else(FrameTime::now);
+                // Signal and receive channel from radiotap
+                let radiotap = parse_radiotap(data);
+                let signal_dbm = radiotap.signal_dbm;
+
+                if let (Some(tracker), Some(channel)) = (utilization.as_mut(), radiotap.channel) {
+                    let frame_len = data.len().saturating_sub(radiotap.header_len);
+                    tracker.record_frame(channel, frame_airtime_us(frame_len, radiotap.rate_500kbps));
+                }
+
+                // Corrupted frames would show up as devices with bogus MACs
+                if radiotap.is_corrupt(data) {
+                    corrupt_frames.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                let frame = radiotap.strip_fcs(data);
+
+                if config.capture.detect_deauth {
+                    if let Some(deauth) = parse_deauth(frame) {
+                        let event = deauth_event(deauth, captured_at.secs, signal_dbm, radiotap.channel);
+                        db_queue.push(CaptureRecord::Deauth(event));
                         continue;
                     }
-                    if !probe.ssid.is_empty() && ignore_lists.should_ignore_ssid(&probe.ssid) {
+                }
+
+                if let Some(probe) = parse_probe_request(frame, signal_dbm) {
+                    // Check ignore lists, which bulk ignores in the UI add to
+                    let ignored = ignore_lists
+                        .read()
+                        .map(|lists| {
+                            lists.should_ignore_mac(&probe.source_mac)
+                                || (!probe.ssid.is_empty() && lists.should_ignore_ssid(&probe.ssid))
+                        })
+                        .unwrap_or(false);
+                    if ignored
+                        || retries.is_retransmission(
+                            &probe.source_mac,
+                            probe.sequence_number,
+                            probe.retry,
+                            captured_at.as_millis(),
+                        )
+                        || rate_limit.as_mut().is_some_and(|l| !l.admit(&probe.source_mac, captured_at.secs))
+                    {
+                        continue;
+                    }
+                    if disk.is_shedding()
+                        && is_low_value(&probe.ssid, probe.signal_dbm, config.disk.weak_signal_dbm)
+                    {
+                        disk.record_shed();
                         continue;
                     }
 
-                    let now = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as i64;
+                    let now = captured_at.secs;
 
                     // Calculate distance
-                    let distance_m = if config.distance.enabled {
+                    let distance_m = if distance_enabled {
                         probe.signal_dbm.and_then(|rssi| {
                             estimate_distance(
                                 rssi,
@@ -393,27 +908,35 @@ async fn run_capture_loop(
                     };
 
                     // Get current GPS position for this capture
-                    let (lat, lon) = shared_gps_position
-                        .read()
-                        .ok()
-                        .and_then(|pos| *pos)
+                    let position = if gps_tagging {
+                        shared_gps_position.read().ok().and_then(|pos| *pos)
+                    } else {
+                        None
+                    };
+                    let gps_status = GpsStatus::from_position(gps_tagging, position);
+                    let (lat, lon) = position
                         .map(|(lat, lon)| (Some(lat), Some(lon)))
                         .unwrap_or((None, None));
 
                     // Insert into database
+                    let channel = probe.channel(radiotap.channel);
                     let capture = ProbeCapture {
                         mac: probe.source_mac.clone(),
                         ssid: probe.ssid.clone(),
                         timestamp: now,
+                        timestamp_micros: captured_at.micros,
                         lat,
                         lon,
                         signal_dbm: probe.signal_dbm,
-                        channel: None,
+                        channel,
                         distance_m,
+                        gps_status,
+                        bssid: probe.bssid.clone(),
+                        sequence_number: Some(probe.sequence_number),
                         capabilities: Some(probe.capabilities.clone()),
                     };
 
-                    let _ = db.insert_probe(&capture);
+                    db_queue.push(CaptureRecord::Probe(capture));
 
                     // Send to TUI
                     let log_entry = ProbeLogEntry {
@@ -422,98 +945,42 @@ async fn run_capture_loop(
                         ssid: probe.ssid,
                         signal_dbm: probe.signal_dbm,
                         distance_m,
-                        channel: None,
+                        channel,
                         capabilities: Some(probe.capabilities),
+                        repeat: 1,
+                        frame: config.tui.keep_frames.then(|| data.to_vec()),
                     };
 
-                    let _ = event_tx.send(TuiEvent::ProbeReceived(log_entry)).await;
+                    feed.send(log_entry);
+                } else if let Some(throttle) = beacons.as_mut() {
+                    if let Some(beacon) = parse_beacon(frame) {
+                        if let Some(capture) = throttle.admit(beacon, captured_at.secs) {
+                            db_queue.push(CaptureRecord::Beacon(capture));
+                        }
+                    }
                 }
             }
-            Err(pcap::Error::TimeoutExpired) => {
-                // Normal timeout, use async yield
-                tokio::task::yield_now().await;
+            Ok(None) => {
+                // Normal timeout, continue
+                continue;
             }
             Err(e) => {
                 if running.load(Ordering::SeqCst) {
-                    let _ = event_tx
-                        .send(TuiEvent::Error(format!("Capture error: {}", e)))
-                        .await;
+                    let _ = event_tx.blocking_send(TuiEvent::Error(format!("Capture error: {}", e)));
                 }
                 break;
             }
         }
     }
 
-    let _ = event_tx.send(TuiEvent::CaptureStopped).await;
-    Ok(())
-}
-
-fn extract_signal_dbm(data: &[u8]) -> Option<i32> {
-    if data.len() < 8 || data[0] != 0 {
-        return None;
+    if let Some(mut tracker) = utilization {
+        db_queue.push(CaptureRecord::Utilization(tracker.take(dwell.as_deref())));
     }
-
-    let radiotap_len = u16::from_le_bytes([data[2], data[3]]) as usize;
-    if radiotap_len > data.len() || radiotap_len < 8 {
-        return None;
+    if let Some(mut limiter) = rate_limit {
+        db_queue.push(CaptureRecord::Suppressed(limiter.take_suppressed()));
     }
 
-    // Collect all present bitmasks (handle extended flags - bit 31)
-    let mut present_words: Vec<u32> = Vec::new();
-    let mut pos = 4;
-    loop {
-        if pos + 4 > data.len() {
-            return None;
-        }
-        let present = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
-        present_words.push(present);
-        pos += 4;
-        if present & (1 << 31) == 0 {
-            break;
-        }
-    }
-
-    let first_present = present_words[0];
-
-    // Check if DBM Antenna Signal (bit 5) is present
-    if first_present & (1 << 5) == 0 {
-        return None;
-    }
-
-    let mut offset = pos; // Start after all present words
-
-    // Bit 0: TSFT - 8 bytes, requires 8-byte alignment
-    if first_present & (1 << 0) != 0 {
-        offset = (offset + 7) & !7;
-        offset += 8;
-    }
-
-    // Bit 1: Flags - 1 byte
-    if first_present & (1 << 1) != 0 {
-        offset += 1;
-    }
-
-    // Bit 2: Rate - 1 byte
-    if first_present & (1 << 2) != 0 {
-        offset += 1;
-    }
-
-    // Bit 3: Channel - 4 bytes, requires 2-byte alignment
-    if first_present & (1 << 3) != 0 {
-        offset = (offset + 1) & !1;
-        offset += 4;
-    }
-
-    // Bit 4: FHSS - 2 bytes
-    if first_present & (1 << 4) != 0 {
-        offset += 2;
-    }
-
-    // Bit 5: DBM Antenna Signal - 1 byte signed
-    if offset < radiotap_len {
-        let signal = data[offset] as i8;
-        return Some(signal as i32);
-    }
-
-    None
+    feed.flush();
+    let _ = event_tx.blocking_send(TuiEvent::CaptureStopped);
+    Ok(())
 }
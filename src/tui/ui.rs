@@ -1,9 +1,12 @@
 use crate::distance::{estimate_distance_smart, DistanceConfidence};
 use crate::oui::{infer_device_type, is_randomized_mac, lookup_vendor};
+use crate::parser::annotate_frame;
 use crate::tui::app::{ActivePanel, App};
+use crate::tui::event::Action;
 use crate::tui::widgets::{
-    device_table::render_device_table, help_overlay::render_help, probe_log::render_probe_log,
-    stats_panel::render_stats, status_bar::render_status_bar,
+    analysis_overlay::render_analysis, device_table::render_device_table, gps_panel::render_gps_panel,
+    help_overlay::render_help, palette::render_palette, probe_log::render_probe_log, stats_panel::render_stats,
+    status_bar::render_status_bar,
 };
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -52,8 +55,17 @@ pub fn draw(frame: &mut Frame, app: &App) {
     let log_focused = app.active_panel == ActivePanel::ProbeLog;
     render_probe_log(frame, top_chunks[0], app, log_focused);
 
-    // Draw stats panel
-    render_stats(frame, top_chunks[1], app);
+    // Draw stats panel, with the GPS panel beneath it when GPS is enabled
+    if app.gps_enabled {
+        let side_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Length(7)])
+            .split(top_chunks[1]);
+        render_stats(frame, side_chunks[0], app);
+        render_gps_panel(frame, side_chunks[1], app);
+    } else {
+        render_stats(frame, top_chunks[1], app);
+    }
 
     // Draw device table
     let table_focused = app.active_panel == ActivePanel::DeviceTable;
@@ -62,9 +74,17 @@ pub fn draw(frame: &mut Frame, app: &App) {
     // Draw status bar
     render_status_bar(frame, main_chunks[2], app);
 
+    if let Some(selected) = app.palette {
+        render_palette(frame, size, app, selected);
+    }
+
+    if let Some(analysis) = &app.analysis {
+        render_analysis(frame, size, analysis, &app.keymap);
+    }
+
     // Draw help overlay if active
     if app.show_help {
-        render_help(frame, size);
+        render_help(frame, size, &app.keymap);
     }
 
     // Draw device detail view if active
@@ -73,6 +93,87 @@ pub fn draw(frame: &mut Frame, app: &App) {
             draw_device_detail(frame, size, app, idx);
         }
     }
+
+    // The hex view opens on top of the details
+    if let Some(idx) = app.frame_view {
+        if idx < app.devices.len() {
+            draw_frame_view(frame, size, app, idx);
+        }
+    }
+}
+
+/// Colors cycled through so each field's bytes match its annotation
+const FIELD_COLORS: [Color; 4] = [Color::Cyan, Color::Yellow, Color::Green, Color::Magenta];
+
+fn draw_frame_view(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
+    let device = &app.devices[idx];
+    let data = device.last_frame.as_deref().unwrap_or_default();
+    let fields = annotate_frame(data);
+
+    let popup_width = 80.min(area.width.saturating_sub(4));
+    let popup_height = 40.min(area.height.saturating_sub(4));
+    let popup_area = Rect::new(
+        (area.width.saturating_sub(popup_width)) / 2,
+        (area.height.saturating_sub(popup_height)) / 2,
+        popup_width,
+        popup_height,
+    );
+    frame.render_widget(Clear, popup_area);
+
+    let color_at = |offset: usize| {
+        fields
+            .iter()
+            .position(|f| offset >= f.offset && offset < f.offset + f.len)
+            .map(|i| FIELD_COLORS[i % FIELD_COLORS.len()])
+            .unwrap_or(Color::DarkGray)
+    };
+
+    let mut content = vec![Line::from(vec![
+        Span::styled("MAC: ", Style::default().fg(Color::Yellow)),
+        Span::raw(&device.mac),
+        Span::raw(format!("  {} bytes", data.len())),
+    ])];
+    content.push(Line::from(""));
+
+    for (row, chunk) in data.chunks(16).enumerate() {
+        let base = row * 16;
+        let mut spans = vec![Span::styled(format!("{:04x}  ", base), Style::default().fg(Color::DarkGray))];
+        for (i, byte) in chunk.iter().enumerate() {
+            spans.push(Span::styled(format!("{:02x} ", byte), Style::default().fg(color_at(base + i))));
+        }
+        spans.push(Span::raw("   ".repeat(16 - chunk.len())));
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        spans.push(Span::styled(format!(" {}", ascii), Style::default().fg(Color::DarkGray)));
+        content.push(Line::from(spans));
+    }
+
+    content.push(Line::from(""));
+    for (i, field) in fields.iter().enumerate() {
+        let color = FIELD_COLORS[i % FIELD_COLORS.len()];
+        content.push(Line::from(vec![
+            Span::styled(format!("{:04x} ", field.offset), Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{:<28}", field.name), Style::default().fg(color)),
+            Span::raw(field.value.clone()),
+        ]));
+    }
+
+    content.push(Line::from(""));
+    content.push(Line::from(Span::styled(
+        format!("Press {} to close", app.keymap.keys_for(Action::Close).join(" or ")),
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let popup = Paragraph::new(content).block(
+        Block::default()
+            .title(" Last Frame ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(popup, popup_area);
 }
 
 fn draw_header(frame: &mut Frame, area: Rect) {
@@ -196,6 +297,14 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
             Span::styled("Type: ", Style::default().fg(Color::Yellow)),
             Span::raw(device_type),
         ]),
+        Line::from(vec![
+            Span::styled("Label: ", Style::default().fg(Color::Yellow)),
+            Span::raw(app.labels.get(&device.mac).map(String::as_str).unwrap_or("-")),
+            Span::styled(
+                if app.watched.contains(&device.mac) { "  ★ watched" } else { "" },
+                Style::default().fg(Color::Red),
+            ),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("First Seen: ", Style::default().fg(Color::Yellow)),
@@ -225,6 +334,32 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
         ]),
     ];
 
+    // Other MACs this one may be, and why
+    if !app.detail_links.is_empty() {
+        content.push(Line::from(""));
+        content.push(Line::from(Span::styled(
+            "═══ Linked MACs ═══",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        )));
+        for link in &app.detail_links {
+            let status = match &link.user_override {
+                Some(o) => format!(" [{}]", o.verdict.as_str()),
+                None => String::new(),
+            };
+            content.push(Line::from(vec![
+                Span::styled(
+                    format!("{} {:>3.0}%{} ", link.mac, link.confidence * 100.0, status),
+                    Style::default().fg(if link.linked() { Color::Green } else { Color::DarkGray }),
+                ),
+                Span::raw(link.evidence.describe()),
+            ]));
+        }
+        content.push(Line::from(Span::styled(
+            "Override with `prowl link confirm` or `prowl link reject`",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+
     // Add capabilities section if available
     if let Some(caps) = &device.capabilities {
         content.push(Line::from(""));
@@ -262,6 +397,12 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
                 Span::raw(format!("{} Mbps", rates_str.join(", "))),
             ]));
         }
+        if caps.rate_bits != 0 {
+            content.push(Line::from(vec![
+                Span::styled("Rate bits: ", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("0x{:08x}", caps.rate_bits)),
+            ]));
+        }
 
         // HT Capabilities (802.11n)
         if let Some(ht) = &caps.ht_caps {
@@ -315,6 +456,35 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
             ]));
         }
 
+        // HE Capabilities (802.11ax)
+        if let Some(he) = &caps.he_caps {
+            content.push(Line::from(""));
+            content.push(Line::from(Span::styled(
+                "── HE (802.11ax) ──",
+                Style::default().fg(Color::Blue),
+            )));
+            content.push(Line::from(vec![
+                Span::styled("Beamforming: ", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("SU:{} MU:{}",
+                    if he.su_beamformer { "Y" } else { "N" },
+                    if he.mu_beamformer { "Y" } else { "N" }
+                )),
+                Span::raw("  "),
+                Span::styled("TWT: ", Style::default().fg(Color::Yellow)),
+                Span::raw(if he.twt_requester { "Yes" } else { "No" }),
+            ]));
+        }
+
+        // Extended Capabilities
+        if let Some(features) = caps.ext_caps.as_ref().map(|ext| ext.features()).filter(|f| !f.is_empty()) {
+            content.push(Line::from(""));
+            content.push(Line::from(Span::styled(
+                "── Extended Capabilities ──",
+                Style::default().fg(Color::Blue),
+            )));
+            content.push(Line::from(Span::raw(features.join(", "))));
+        }
+
         // RSN Security Info
         if let Some(rsn) = &caps.rsn_info {
             content.push(Line::from(""));
@@ -372,6 +542,12 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
                     Span::raw(&wps.model_number),
                 ]));
             }
+            if let Some(uuid) = &wps.uuid {
+                content.push(Line::from(vec![
+                    Span::styled("UUID-E: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(uuid),
+                ]));
+            }
         }
 
         // Vendor IEs
@@ -383,9 +559,13 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
             )));
             for vie in caps.vendor_ies.iter().take(5) {
                 let vendor_name = vie.vendor_name.as_deref().unwrap_or("Unknown");
+                let detail = match &vie.decoded {
+                    Some(decoded) => decoded.describe(),
+                    None => format!("{} bytes", vie.data_len),
+                };
                 content.push(Line::from(vec![
                     Span::styled(vendor_name, Style::default().fg(Color::Yellow)),
-                    Span::raw(format!(" ({}) - {} bytes", vie.oui, vie.data_len)),
+                    Span::raw(format!(" ({}) - {}", vie.oui, detail)),
                 ]));
             }
             if caps.vendor_ies.len() > 5 {
@@ -395,11 +575,26 @@ fn draw_device_detail(frame: &mut Frame, area: Rect, app: &App, idx: usize) {
                 )));
             }
         }
+
+        // Element order, part of what identifies the driver
+        if !caps.information_elements.is_empty() {
+            let ids: Vec<String> = caps.information_elements.iter()
+                .map(|ie| match ie.ext_id {
+                    Some(ext) => format!("{}.{}", ie.id, ext),
+                    None => ie.id.to_string(),
+                })
+                .collect();
+            content.push(Line::from(""));
+            content.push(Line::from(vec![
+                Span::styled("Elements: ", Style::default().fg(Color::Yellow)),
+                Span::raw(ids.join(" ")),
+            ]));
+        }
     }
 
     content.push(Line::from(""));
     content.push(Line::from(Span::styled(
-        "Press ESC to close",
+        format!("Press {} to close", app.keymap.keys_for(Action::Close).join(" or ")),
         Style::default().fg(Color::DarkGray),
     )));
 
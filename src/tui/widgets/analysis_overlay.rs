@@ -0,0 +1,91 @@
+use crate::tui::app::QuickAnalysis;
+use crate::tui::event::{Action, KeyMap};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Render the quick analyze results
+pub fn render_analysis(frame: &mut Frame, area: Rect, analysis: &QuickAnalysis, keymap: &KeyMap) {
+    let popup_width = (area.width * 4 / 5).max(40).min(area.width.saturating_sub(2));
+    let popup_height = (area.height * 4 / 5).max(10).min(area.height.saturating_sub(2));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Line> = Vec::new();
+    let title = match &analysis.result {
+        None => {
+            lines.push(Line::from(format!(" Analyzing the last {} hours...", analysis.hours)));
+            format!(" Analysis: last {}h ", analysis.hours)
+        }
+        Some(Err(e)) => {
+            lines.push(Line::from(Span::styled(
+                format!(" Analysis failed: {}", e),
+                Style::default().fg(Color::Red),
+            )));
+            format!(" Analysis: last {}h ", analysis.hours)
+        }
+        Some(Ok(alerts)) if alerts.is_empty() => {
+            lines.push(Line::from(Span::styled(
+                format!(" Nothing suspicious in the last {} hours", analysis.hours),
+                Style::default().fg(Color::Green),
+            )));
+            format!(" Analysis: last {}h ", analysis.hours)
+        }
+        Some(Ok(alerts)) => {
+            for alert in alerts.iter().skip(analysis.scroll) {
+                let vendor = alert
+                    .vendor
+                    .as_ref()
+                    .and_then(|v| v.vendor.clone())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let color = if alert.score >= 0.8 { Color::Red } else { Color::Yellow };
+                let bold = Style::default().add_modifier(Modifier::BOLD);
+                lines.push(Line::from(vec![
+                    Span::styled(format!(" {} ", alert.device.mac), bold),
+                    Span::styled(format!("{:.0}% ", alert.score * 100.0), Style::default().fg(color)),
+                    Span::styled(vendor, dim),
+                ]));
+                for reason in &alert.reasons {
+                    lines.push(Line::from(format!("   - {}", reason)));
+                }
+                lines.push(Line::from(""));
+            }
+            format!(
+                " Analysis: last {}h, {} suspicious ({}/{}) ",
+                analysis.hours,
+                alerts.len(),
+                analysis.scroll + 1,
+                alerts.len()
+            )
+        }
+    };
+
+    let inner_height = popup_height.saturating_sub(3) as usize;
+    lines.truncate(inner_height);
+    while lines.len() < inner_height {
+        lines.push(Line::from(""));
+    }
+    let rerun = keymap.keys_for(Action::QuickAnalyze).join("/");
+    lines.push(Line::from(Span::styled(
+        format!(" Up/Down to scroll, {} to re-run, Esc to close", rerun),
+        dim,
+    )));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(popup, popup_area);
+}
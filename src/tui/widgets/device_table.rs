@@ -17,9 +17,13 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
         DeviceSortField::LastSeen => "Last Seen",
         DeviceSortField::ProbeCount => "Probes",
         DeviceSortField::Signal => "Signal",
+        DeviceSortField::Risk => "Risk",
     };
     let sort_arrow = if app.sort_ascending { "▲" } else { "▼" };
-    let title = format!(" Devices [Sort: {} {}] [s]ort [r]everse ", sort_indicator, sort_arrow);
+    let mut title = format!(" Devices [Sort: {} {}] [s]ort [r]everse ", sort_indicator, sort_arrow);
+    if !app.marked.is_empty() {
+        title.push_str(&format!("[{} marked] ", app.marked.len()));
+    }
 
     let block = Block::default()
         .title(title)
@@ -27,7 +31,7 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
         .border_style(Style::default().fg(border_color));
 
     // Table header
-    let header_cells = ["MAC Address", "Vendor", "Last Seen", "Probes", "Signal", "Distance", "SSIDs"]
+    let header_cells = ["", "MAC Address", "Vendor", "Last Seen", "Probes", "Signal", "Distance", "Risk", "SSIDs"]
         .iter()
         .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
     let header = Row::new(header_cells).height(1);
@@ -52,6 +56,11 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
                 .map(|d| format!("{:.1}m", d))
                 .unwrap_or_else(|| "N/A".to_string());
 
+            let risk_str = device
+                .risk
+                .map(|r| format!("{:.0}%", r * 100.0))
+                .unwrap_or_else(|| "-".to_string());
+
             let ssids_str = if device.ssids.is_empty() {
                 "<broadcast>".to_string()
             } else if device.ssids.len() == 1 {
@@ -82,6 +91,17 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
                 }
             }).unwrap_or(Color::DarkGray);
 
+            // Color code risk (higher = more suspicious)
+            let risk_color = device.risk.map(|r| {
+                if r >= 0.8 {
+                    Color::Red
+                } else if r >= 0.5 {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                }
+            }).unwrap_or(Color::DarkGray);
+
             // Get vendor info
             let vendor = vendor_short(&device.mac);
             let is_random = is_randomized_mac(&device.mac);
@@ -93,13 +113,22 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
                 Color::Green
             };
 
+            // ✓ marked for a bulk action, ★ on the watch list
+            let flags = format!(
+                "{}{}",
+                if app.marked.contains(&device.mac) { "✓" } else { " " },
+                if app.watched.contains(&device.mac) { "★" } else { " " }
+            );
+
             let cells = vec![
+                Cell::from(flags).style(Style::default().fg(Color::Yellow)),
                 Cell::from(device.mac.clone()),
                 Cell::from(vendor).style(Style::default().fg(vendor_color)),
                 Cell::from(last_seen),
                 Cell::from(device.probe_count.to_string()),
                 Cell::from(signal_str).style(Style::default().fg(signal_color)),
                 Cell::from(distance_str).style(Style::default().fg(distance_color)),
+                Cell::from(risk_str).style(Style::default().fg(risk_color)),
                 Cell::from(ssids_str).style(Style::default().fg(Color::Cyan)),
             ];
 
@@ -116,12 +145,14 @@ pub fn render_device_table(frame: &mut Frame, area: Rect, app: &App, focused: bo
         .collect();
 
     let widths = [
+        Constraint::Length(2),   // Marked / watched
         Constraint::Length(17),  // MAC
         Constraint::Length(6),   // Vendor
         Constraint::Length(10),  // Last Seen
         Constraint::Length(7),   // Probes
         Constraint::Length(8),   // Signal
         Constraint::Length(9),   // Distance
+        Constraint::Length(5),   // Risk
         Constraint::Min(10),     // SSIDs (flexible)
     ];
 
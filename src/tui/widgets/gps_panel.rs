@@ -0,0 +1,150 @@
+use crate::tui::app::App;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        canvas::{Canvas, Points},
+        Block, Borders, Paragraph,
+    },
+    Frame,
+};
+
+/// Render the GPS panel: fix quality, satellites, motion and a breadcrumb trail
+pub fn render_gps_panel(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .title(" GPS ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(18), Constraint::Length(12)])
+        .split(inner);
+
+    let detail = &app.gps_detail;
+
+    let fix_color = match detail.mode {
+        3 => Color::Green,
+        2 => Color::Yellow,
+        _ => Color::Red,
+    };
+    let fix_label = if app.gps_position.is_none() && detail.mode < 2 {
+        if app.gps_error.is_some() {
+            "Error"
+        } else {
+            "Waiting"
+        }
+    } else {
+        detail.fix_label()
+    };
+
+    let sats = match (detail.satellites_used, detail.satellites_visible) {
+        (Some(used), Some(visible)) => format!("{}/{}", used, visible),
+        (Some(used), None) => used.to_string(),
+        (None, Some(visible)) => format!("?/{}", visible),
+        (None, None) => "-".to_string(),
+    };
+
+    let hdop_color = match detail.hdop {
+        Some(h) if h <= 2.0 => Color::Green,
+        Some(h) if h <= 5.0 => Color::Yellow,
+        Some(_) => Color::Red,
+        None => Color::DarkGray,
+    };
+
+    let speed = detail
+        .speed
+        .map(|s| format!("{:.1} km/h", s * 3.6))
+        .unwrap_or_else(|| "-".to_string());
+    let heading = detail
+        .track
+        .map(|t| format!("{:.0}° {}", t, compass_point(t)))
+        .unwrap_or_else(|| "-".to_string());
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Fix:   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                fix_label,
+                Style::default().fg(fix_color).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Sats:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(sats, Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::styled("HDOP:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                detail
+                    .hdop
+                    .map(|h| format!("{:.1}", h))
+                    .unwrap_or_else(|| "-".to_string()),
+                Style::default().fg(hdop_color),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Speed: ", Style::default().fg(Color::Yellow)),
+            Span::styled(speed, Style::default().fg(Color::Cyan)),
+        ]),
+        Line::from(vec![
+            Span::styled("Head:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(heading, Style::default().fg(Color::Cyan)),
+        ]),
+    ];
+
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    render_trail(frame, chunks[1], app);
+}
+
+/// Draw recent positions scaled to fit the area, newest point highlighted
+fn render_trail(frame: &mut Frame, area: Rect, app: &App) {
+    if app.gps_trail.len() < 2 {
+        return;
+    }
+
+    let (mut min_lat, mut max_lat) = (f64::MAX, f64::MIN);
+    let (mut min_lon, mut max_lon) = (f64::MAX, f64::MIN);
+    for &(lat, lon) in &app.gps_trail {
+        min_lat = min_lat.min(lat);
+        max_lat = max_lat.max(lat);
+        min_lon = min_lon.min(lon);
+        max_lon = max_lon.max(lon);
+    }
+
+    // Keep a square-ish window so a straight walk does not fill the box
+    let span = (max_lat - min_lat).max(max_lon - min_lon).max(0.0001) / 2.0;
+    let (mid_lat, mid_lon) = ((min_lat + max_lat) / 2.0, (min_lon + max_lon) / 2.0);
+
+    let trail: Vec<(f64, f64)> = app.gps_trail.iter().map(|&(lat, lon)| (lon, lat)).collect();
+    let current = trail[trail.len() - 1];
+
+    let canvas = Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([mid_lon - span, mid_lon + span])
+        .y_bounds([mid_lat - span, mid_lat + span])
+        .paint(move |ctx| {
+            ctx.draw(&Points {
+                coords: &trail,
+                color: Color::DarkGray,
+            });
+            ctx.draw(&Points {
+                coords: &[current],
+                color: Color::Green,
+            });
+        });
+
+    frame.render_widget(canvas, area);
+}
+
+fn compass_point(degrees: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    let idx = (((degrees % 360.0) + 360.0) % 360.0 / 45.0).round() as usize % 8;
+    POINTS[idx]
+}
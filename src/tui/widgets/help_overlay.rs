@@ -1,3 +1,4 @@
+use crate::tui::event::{Action, KeyMap};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -6,11 +7,11 @@ use ratatui::{
     Frame,
 };
 
-/// Render the help overlay
-pub fn render_help(frame: &mut Frame, area: Rect) {
+/// Render the help overlay, listing the keys currently bound to each action
+pub fn render_help(frame: &mut Frame, area: Rect, keymap: &KeyMap) {
     // Center the help popup
     let popup_width = 50.min(area.width.saturating_sub(4));
-    let popup_height = 18.min(area.height.saturating_sub(4));
+    let popup_height = 30.min(area.height.saturating_sub(4));
     let popup_x = (area.width.saturating_sub(popup_width)) / 2;
     let popup_y = (area.height.saturating_sub(popup_height)) / 2;
 
@@ -19,7 +20,8 @@ pub fn render_help(frame: &mut Frame, area: Rect) {
     // Clear the area behind the popup
     frame.render_widget(Clear, popup_area);
 
-    let help_text = vec![
+    let key = Style::default().fg(Color::Yellow);
+    let mut help_text = vec![
         Line::from(Span::styled(
             "Keyboard Shortcuts",
             Style::default()
@@ -27,46 +29,26 @@ pub fn render_help(frame: &mut Frame, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
-        Line::from(vec![
-            Span::styled("  Tab / ← →    ", Style::default().fg(Color::Yellow)),
-            Span::raw("Switch panels"),
-        ]),
-        Line::from(vec![
-            Span::styled("  ↑ ↓ / j k    ", Style::default().fg(Color::Yellow)),
-            Span::raw("Scroll / Select"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Enter        ", Style::default().fg(Color::Yellow)),
-            Span::raw("View device details"),
-        ]),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("  s            ", Style::default().fg(Color::Yellow)),
-            Span::raw("Cycle sort field"),
-        ]),
-        Line::from(vec![
-            Span::styled("  r            ", Style::default().fg(Color::Yellow)),
-            Span::raw("Reverse sort order"),
-        ]),
-        Line::from(""),
-        Line::from(vec![
-            Span::styled("  ?            ", Style::default().fg(Color::Yellow)),
-            Span::raw("Toggle this help"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Esc          ", Style::default().fg(Color::Yellow)),
-            Span::raw("Close popup/overlay"),
-        ]),
-        Line::from(vec![
-            Span::styled("  q            ", Style::default().fg(Color::Yellow)),
-            Span::raw("Quit application"),
-        ]),
-        Line::from(""),
-        Line::from(Span::styled(
-            "Press ? or Esc to close",
-            Style::default().fg(Color::DarkGray),
-        )),
     ];
+    for action in Action::ALL {
+        // Blank lines between navigation, sorting, marking, capture and
+        // window actions
+        if matches!(
+            action,
+            Action::CycleSort | Action::ToggleMark | Action::CommandPalette | Action::ToggleHelp
+        ) {
+            help_text.push(Line::from(""));
+        }
+        help_text.push(Line::from(vec![
+            Span::styled(format!("  {:<13}", keymap.keys_for(action).join(" / ")), key),
+            Span::raw(action.description()),
+        ]));
+    }
+    help_text.push(Line::from(""));
+    help_text.push(Line::from(Span::styled(
+        format!("Press {} to close", close_keys(keymap)),
+        Style::default().fg(Color::DarkGray),
+    )));
 
     let help_popup = Paragraph::new(help_text).block(
         Block::default()
@@ -77,3 +59,10 @@ pub fn render_help(frame: &mut Frame, area: Rect) {
 
     frame.render_widget(help_popup, popup_area);
 }
+
+/// Keys that close the help overlay, e.g. `? or Esc`
+fn close_keys(keymap: &KeyMap) -> String {
+    let mut keys = keymap.keys_for(Action::ToggleHelp);
+    keys.extend(keymap.keys_for(Action::Close));
+    keys.join(" or ")
+}
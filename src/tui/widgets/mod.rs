@@ -1,5 +1,8 @@
+pub mod analysis_overlay;
 pub mod device_table;
+pub mod gps_panel;
 pub mod help_overlay;
+pub mod palette;
 pub mod probe_log;
 pub mod stats_panel;
 pub mod status_bar;
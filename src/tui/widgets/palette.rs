@@ -0,0 +1,54 @@
+use crate::tui::app::{App, PaletteCommand};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// Render the capture settings palette
+pub fn render_palette(frame: &mut Frame, area: Rect, app: &App, selected: usize) {
+    let commands = app.palette_commands();
+
+    let popup_width = 44.min(area.width.saturating_sub(4));
+    let popup_height = (commands.len() as u16 + 4).min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines: Vec<Line> = commands
+        .iter()
+        .enumerate()
+        .map(|(idx, command)| {
+            let current = matches!(command, PaletteCommand::Channels(p) if app.channel_profile == Some(*p));
+            let style = if idx == selected {
+                Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", idx + 1), Style::default().fg(Color::Yellow)),
+                Span::styled(app.palette_label(*command), style),
+                Span::styled(if current { " (current)" } else { "" }, Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        " Enter or number to apply, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Capture Settings ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(popup, popup_area);
+}
@@ -1,5 +1,5 @@
 use crate::oui::{is_randomized_mac, vendor_short};
-use crate::tui::app::App;
+use crate::tui::app::{App, ProbeLogEntry, LOG_GAP_SECS};
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
@@ -12,108 +12,61 @@ use ratatui::{
 pub fn render_probe_log(frame: &mut Frame, area: Rect, app: &App, focused: bool) {
     let border_color = if focused { Color::Cyan } else { Color::DarkGray };
 
-    let block = Block::default()
-        .title(" Probe Log (Live) ")
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(border_color));
-
     // Get visible entries (most recent first, but display oldest at top)
     let inner_height = area.height.saturating_sub(2) as usize;
     let total_entries = app.probe_log.len();
 
     // Calculate which entries to show
     let start_idx = app.log_scroll;
-    let entries: Vec<&_> = app
+    let mut entries: Vec<&_> = app
         .probe_log
         .iter()
         .rev()
         .skip(start_idx)
         .take(inner_height)
         .collect();
+    entries.reverse();
+
+    // Live view, or the time window being reviewed when scrolled back
+    let title = match (start_idx, entries.first(), entries.last()) {
+        (0, _, _) | (_, None, _) | (_, _, None) => " Probe Log (Live) ".to_string(),
+        (_, Some(first), Some(last)) => format!(
+            " Probe Log {} – {} [G: live] ",
+            format_time(first.timestamp),
+            format_time(last.timestamp)
+        ),
+    };
 
-    let items: Vec<ListItem> = entries
-        .iter()
-        .rev()
-        .map(|entry| {
-            let timestamp = chrono::DateTime::from_timestamp(entry.timestamp, 0)
-                .map(|dt| dt.format("%H:%M:%S").to_string())
-                .unwrap_or_else(|| "??:??:??".to_string());
-
-            let ssid_display = if entry.ssid.is_empty() {
-                "<broadcast>"
-            } else {
-                &entry.ssid
-            };
-
-            let signal_str = entry
-                .signal_dbm
-                .map(|s| format!("{:>4}dBm", s))
-                .unwrap_or_else(|| "    N/A".to_string());
-
-            let distance_str = entry
-                .distance_m
-                .map(|d| format!("{:>5.1}m", d))
-                .unwrap_or_else(|| "    N/A".to_string());
-
-            // Color code signal strength
-            let signal_color = entry.signal_dbm.map(|s| {
-                if s >= -50 {
-                    Color::Green
-                } else if s >= -70 {
-                    Color::Yellow
-                } else {
-                    Color::Red
-                }
-            }).unwrap_or(Color::DarkGray);
-
-            // Color code distance
-            let distance_color = entry.distance_m.map(|d| {
-                if d < 3.0 {
-                    Color::Red
-                } else if d < 10.0 {
-                    Color::Yellow
-                } else {
-                    Color::Green
-                }
-            }).unwrap_or(Color::DarkGray);
-
-            // Get vendor info
-            let vendor = vendor_short(&entry.mac);
-            let is_random = is_randomized_mac(&entry.mac);
-            let vendor_color = if is_random {
-                Color::Magenta  // Randomized MACs in magenta
-            } else if vendor == "UNK" {
-                Color::DarkGray
-            } else {
-                Color::Green
-            };
-
-            let spans = vec![
-                Span::styled(
-                    format!("[{}] ", timestamp),
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color));
+
+    // Previous entry timestamp, for detecting gaps in the visible window
+    let mut prev_ts = app
+        .probe_log
+        .len()
+        .checked_sub(start_idx + entries.len() + 1)
+        .map(|idx| app.probe_log[idx].timestamp);
+
+    let mut items: Vec<ListItem> = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        if let Some(prev) = prev_ts {
+            let gap = entry.timestamp - prev;
+            if gap >= LOG_GAP_SECS {
+                items.push(ListItem::new(Line::from(Span::styled(
+                    format!("── gap {} ──", format_gap(gap)),
                     Style::default().fg(Color::DarkGray),
-                ),
-                Span::styled(
-                    format!("{:<17} ", entry.mac),
-                    Style::default().fg(Color::White),
-                ),
-                Span::styled(
-                    format!("{:<4} ", vendor),
-                    Style::default().fg(vendor_color),
-                ),
-                Span::styled(signal_str, Style::default().fg(signal_color)),
-                Span::raw(" "),
-                Span::styled(distance_str, Style::default().fg(distance_color)),
-                Span::raw("  "),
-                Span::styled(
-                    truncate_str(ssid_display, 16),
-                    Style::default().fg(Color::Cyan),
-                ),
-            ];
-
-            ListItem::new(Line::from(spans))
-        })
-        .collect();
+                ))));
+            }
+        }
+        prev_ts = Some(entry.timestamp);
+        items.push(probe_item(entry));
+    }
+
+    // Gap markers take lines; keep the newest entries visible
+    let overflow = items.len().saturating_sub(inner_height);
+    let items: Vec<ListItem> = items.into_iter().skip(overflow).collect();
 
     let list = List::new(items).block(block);
 
@@ -140,6 +93,106 @@ pub fn render_probe_log(frame: &mut Frame, area: Rect, app: &App, focused: bool)
     }
 }
 
+fn probe_item(entry: &ProbeLogEntry) -> ListItem<'static> {
+    let timestamp = format_time(entry.timestamp);
+
+    let ssid_display = if entry.ssid.is_empty() {
+        "<broadcast>"
+    } else {
+        &entry.ssid
+    };
+
+    let signal_str = entry
+        .signal_dbm
+        .map(|s| format!("{:>4}dBm", s))
+        .unwrap_or_else(|| "    N/A".to_string());
+
+    let distance_str = entry
+        .distance_m
+        .map(|d| format!("{:>5.1}m", d))
+        .unwrap_or_else(|| "    N/A".to_string());
+
+    // Color code signal strength
+    let signal_color = entry.signal_dbm.map(|s| {
+        if s >= -50 {
+            Color::Green
+        } else if s >= -70 {
+            Color::Yellow
+        } else {
+            Color::Red
+        }
+    }).unwrap_or(Color::DarkGray);
+
+    // Color code distance
+    let distance_color = entry.distance_m.map(|d| {
+        if d < 3.0 {
+            Color::Red
+        } else if d < 10.0 {
+            Color::Yellow
+        } else {
+            Color::Green
+        }
+    }).unwrap_or(Color::DarkGray);
+
+    // Get vendor info
+    let vendor = vendor_short(&entry.mac);
+    let is_random = is_randomized_mac(&entry.mac);
+    let vendor_color = if is_random {
+        Color::Magenta  // Randomized MACs in magenta
+    } else if vendor == "UNK" {
+        Color::DarkGray
+    } else {
+        Color::Green
+    };
+
+    let mut spans = vec![
+        Span::styled(
+            format!("[{}] ", timestamp),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled(
+            format!("{:<17} ", entry.mac),
+            Style::default().fg(Color::White),
+        ),
+        Span::styled(
+            format!("{:<4} ", vendor),
+            Style::default().fg(vendor_color),
+        ),
+        Span::styled(signal_str, Style::default().fg(signal_color)),
+        Span::raw(" "),
+        Span::styled(distance_str, Style::default().fg(distance_color)),
+        Span::raw("  "),
+        Span::styled(
+            truncate_str(ssid_display, 16),
+            Style::default().fg(Color::Cyan),
+        ),
+    ];
+    if entry.repeat > 1 {
+        spans.push(Span::styled(
+            format!(" ×{}", entry.repeat),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    ListItem::new(Line::from(spans))
+}
+
+fn format_time(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "??:??:??".to_string())
+}
+
+fn format_gap(secs: i64) -> String {
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
 fn truncate_str(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -57,6 +57,24 @@ pub fn render_stats(frame: &mut Frame, area: Rect, app: &App) {
                 Style::default().fg(Color::Green),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("New/min:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6.1}", app.stats.new_devices_per_min),
+                Style::default().fg(if app.stats.new_device_spike.is_some() {
+                    Color::Red
+                } else {
+                    Color::Green
+                }),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("People:   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>5.0}~", app.stats.estimated_occupancy),
+                Style::default().fg(Color::Magenta),
+            ),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("Uptime:   ", Style::default().fg(Color::Yellow)),
@@ -66,6 +84,117 @@ pub fn render_stats(frame: &mut Frame, area: Rect, app: &App) {
 
     // Add calibration status if available
     let mut lines = lines;
+    if app.stats.dropped_probes > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Dropped:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6}", app.stats.dropped_probes),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+    if app.stats.ui_dropped > 0 || app.stats.ui_coalesced > 0 {
+        // The log is thinned out; the database still has every probe
+        lines.push(Line::from(vec![
+            Span::styled("Merged:   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6}", app.stats.ui_coalesced),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("Unshown:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6}", app.stats.ui_dropped),
+                Style::default().fg(if app.stats.ui_dropped > 0 { Color::Red } else { Color::DarkGray }),
+            ),
+        ]));
+    }
+    if let Some(percent) = app.stats.battery_percent {
+        let color = if percent <= 20 { Color::Red } else { Color::Green };
+        lines.push(Line::from(vec![
+            Span::styled("Battery:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:>5}%", percent), Style::default().fg(color)),
+        ]));
+    }
+    if app.stats.disk.low || app.stats.disk.shedding {
+        let color = if app.stats.disk.shedding { Color::Red } else { Color::Yellow };
+        lines.push(Line::from(vec![
+            Span::styled("Disk:     ", Style::default().fg(Color::Yellow)),
+            Span::styled(app.stats.disk.describe(), Style::default().fg(color)),
+        ]));
+        if app.stats.disk.shedding {
+            lines.push(Line::from(Span::styled(
+                format!("Shedding weak/broadcast ({})", app.stats.disk.shed),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+    }
+    if app.stats.kernel_dropped > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Kernel:   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6}", app.stats.kernel_dropped),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+    if app.stats.corrupt_frames > 0 {
+        lines.push(Line::from(vec![
+            Span::styled("Corrupt:  ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                format!("{:>6}", app.stats.corrupt_frames),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    if let Some(spike) = &app.stats.new_device_spike {
+        lines.push(Line::from(Span::styled(
+            "NEW DEVICE SPIKE",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(
+            spike.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if let Some(probe) = &app.stats.my_ssid_probe {
+        lines.push(Line::from(Span::styled(
+            "PROBE FOR YOUR NETWORK",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(
+            probe.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if !app.stats.channel_usage.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "─ Airtime ─",
+            Style::default().fg(Color::DarkGray),
+        )));
+        for usage in &app.stats.channel_usage {
+            let (text, color) = match usage.utilization() {
+                Some(busy) => {
+                    let color = if busy >= 0.5 {
+                        Color::Red
+                    } else if busy >= 0.2 {
+                        Color::Yellow
+                    } else {
+                        Color::Green
+                    };
+                    (format!("{:>5.0}%", busy * 100.0), color)
+                }
+                // Not hopped, so no listening time to divide by
+                None => (format!("{:>6}", usage.frames), Color::DarkGray),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("Ch {:>3}:   ", usage.channel), Style::default().fg(Color::Yellow)),
+                Span::styled(text, Style::default().fg(color)),
+            ]));
+        }
+    }
     if let Some(cal) = &app.calibration_status {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
@@ -14,6 +14,38 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray));
 
+    // Jump prompt replaces the status line while open
+    if let Some(input) = &app.jump_input {
+        let prompt = Line::from(vec![
+            Span::styled(
+                " Jump to time (HH:MM[:SS] UTC): ",
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{}_", input), Style::default().fg(Color::White)),
+            Span::styled(
+                "   [Enter] Jump  [Esc] Cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(prompt).block(block), area);
+        return;
+    }
+    if let Some(input) = &app.label_input {
+        let prompt = Line::from(vec![
+            Span::styled(
+                format!(" Label {} device(s): ", app.bulk_targets().len()),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{}_", input), Style::default().fg(Color::White)),
+            Span::styled(
+                "   [Enter] Save  [Esc] Cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(prompt).block(block), area);
+        return;
+    }
+
     // GPS status with activity indicator
     let gps_status = if let Some((lat, lon)) = app.gps_position {
         // Check if GPS has been updated recently (within 3 seconds)
@@ -41,13 +73,24 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
 
     // Channel status
     let channel_status = if let Some(ch) = app.current_channel {
-        Span::styled(format!("Ch: {}", ch), Style::default().fg(Color::Cyan))
+        let profile = app
+            .channel_profile
+            .map(|p| format!(" ({})", p.label()))
+            .unwrap_or_default();
+        Span::styled(format!("Ch: {}{}", ch, profile), Style::default().fg(Color::Cyan))
     } else {
         Span::styled("Ch: --", Style::default().fg(Color::DarkGray))
     };
 
     // Capture status
-    let capture_status = if app.capture_active {
+    let capture_status = if app.attached {
+        Span::styled(
+            "Capture: READ-ONLY",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else if app.capture_active {
         Span::styled(
             "Capture: ACTIVE",
             Style::default()
@@ -81,6 +124,14 @@ pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
         uptime_status,
     ]);
 
+    let mut status_line = status_line;
+    if let Some(msg) = &app.status_message {
+        status_line.spans.push(Span::raw("  │  "));
+        status_line
+            .spans
+            .push(Span::styled(msg.clone(), Style::default().fg(Color::Yellow)));
+    }
+
     let paragraph = Paragraph::new(status_line).block(block);
 
     frame.render_widget(paragraph, area);
@@ -0,0 +1,98 @@
+//! Opt-in check for newer prowl releases.
+//!
+//! prowl has no HTTP client of its own, so the release API is fetched with
+//! `curl`, the same way mail goes out through the system's sendmail. The
+//! check only ever runs when asked for, with `prowl version --check` or
+//! `updates.check_on_start`.
+
+use crate::config::UpdateConfig;
+use anyhow::{bail, Context, Result};
+use log::{info, warn};
+use std::process::Command;
+use std::thread;
+
+/// Version of this build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Parse `v1.2.3` or `1.2.3-rc1` into its numeric parts; a pre-release or
+/// build suffix is ignored
+pub fn parse_version(text: &str) -> Option<(u64, u64, u64)> {
+    let text = text.trim().trim_start_matches('v');
+    let core = text.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|p| p.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// True if `latest` is a higher version than `current`
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// Tag of the latest release published at `url`
+pub fn latest_release(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--max-time", "10"])
+        .args(["--header", "Accept: application/json"])
+        .args(["--user-agent", &format!("prowl/{}", CURRENT_VERSION)])
+        .arg(url)
+        .output()
+        .context("Failed to run curl")?;
+    if !output.status.success() {
+        bail!(
+            "Release check failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let release: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("Release API returned invalid JSON")?;
+    match release.get("tag_name").and_then(|t| t.as_str()) {
+        Some(tag) => Ok(tag.to_string()),
+        None => bail!("Release API response has no tag_name"),
+    }
+}
+
+/// The latest release tag if it is newer than this build
+pub fn check_for_update(config: &UpdateConfig) -> Result<Option<String>> {
+    let latest = latest_release(&config.url)?;
+    Ok(is_newer(&latest, CURRENT_VERSION).then_some(latest))
+}
+
+/// Check once in the background when `check_on_start` is set, logging the
+/// result. Capture never waits on it.
+pub fn spawn_update_check(config: &UpdateConfig) -> Option<thread::JoinHandle<()>> {
+    if !config.check_on_start {
+        return None;
+    }
+
+    let config = config.clone();
+    Some(thread::spawn(move || match check_for_update(&config) {
+        Ok(Some(latest)) => info!("prowl {} is available (running {})", latest, CURRENT_VERSION),
+        Ok(None) => info!("prowl {} is up to date", CURRENT_VERSION),
+        Err(e) => warn!("Update check failed: {:#}", e),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_comparison() {
+        assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("0.4"), Some((0, 4, 0)));
+        assert_eq!(parse_version("2.0.0-rc1"), Some((2, 0, 0)));
+        assert_eq!(parse_version("nightly"), None);
+
+        assert!(is_newer("v0.2.0", "0.1.0"));
+        assert!(is_newer("0.1.10", "0.1.9"));
+        assert!(!is_newer("v0.1.0", "0.1.0"));
+        assert!(!is_newer("nightly", "0.1.0"));
+    }
+}
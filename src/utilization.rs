@@ -0,0 +1,173 @@
+//! Per-channel airtime estimates.
+//!
+//! With `capture.measure_utilization` the capture filter is dropped so every
+//! frame reaches prowl. Each frame's airtime is estimated from its length
+//! and the radiotap rate, and summed per channel against the time the
+//! hopper spent there, which it keeps in a `DwellClock`. The result is a
+//! rough busy fraction: frames too weak to decode and non-Wi-Fi
+//! interference are missed, and HT/VHT frames, whose rate radiotap carries
+//! in other fields, are assumed to go at 6 Mbit/s.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often capture hands accumulated usage to the database writer
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Rate assumed when radiotap has no legacy rate for a frame
+const DEFAULT_RATE_KBPS: u64 = 6_000;
+/// OFDM preamble and header
+const OFDM_PREAMBLE_US: u64 = 20;
+/// DSSS/CCK long preamble and header
+const DSSS_PREAMBLE_US: u64 = 192;
+
+/// Estimated time on air for an 802.11 frame of `len` bytes, with the
+/// radiotap rate in 500 kbit/s units when known
+pub fn frame_airtime_us(len: usize, rate_500kbps: Option<u8>) -> u64 {
+    let rate_kbps = rate_500kbps
+        .filter(|r| *r > 0)
+        .map(|r| r as u64 * 500)
+        .unwrap_or(DEFAULT_RATE_KBPS);
+    let preamble = match rate_kbps {
+        1_000 | 2_000 | 5_500 | 11_000 => DSSS_PREAMBLE_US,
+        _ => OFDM_PREAMBLE_US,
+    };
+    preamble + len as u64 * 8 * 1_000 / rate_kbps
+}
+
+/// Frames and airtime heard on one channel, and how long the radio listened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelUsage {
+    pub channel: u8,
+    pub frames: u64,
+    pub airtime_us: u64,
+    pub dwell_us: u64,
+}
+
+impl ChannelUsage {
+    /// Estimated share of the listening time the channel was busy
+    pub fn utilization(&self) -> Option<f64> {
+        (self.dwell_us > 0).then(|| (self.airtime_us as f64 / self.dwell_us as f64).min(1.0))
+    }
+
+    pub fn frames_per_sec(&self) -> Option<f64> {
+        (self.dwell_us > 0).then(|| self.frames as f64 * 1e6 / self.dwell_us as f64)
+    }
+}
+
+/// Listening time per channel, kept by the channel hopper
+#[derive(Debug, Default)]
+pub struct DwellClock {
+    dwell_us: Mutex<BTreeMap<u8, u64>>,
+}
+
+impl DwellClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, channel: u8, listened: Duration) {
+        if let Ok(mut dwell) = self.dwell_us.lock() {
+            *dwell.entry(channel).or_insert(0) += listened.as_micros() as u64;
+        }
+    }
+
+    /// Time accumulated per channel since the last call
+    pub fn take(&self) -> BTreeMap<u8, u64> {
+        self.dwell_us.lock().map(|mut d| std::mem::take(&mut *d)).unwrap_or_default()
+    }
+}
+
+/// Accumulates frames and airtime between flushes to the database
+#[derive(Debug)]
+pub struct UtilizationTracker {
+    pending: BTreeMap<u8, ChannelUsage>,
+    last_flush: Instant,
+}
+
+impl UtilizationTracker {
+    pub fn new() -> Self {
+        UtilizationTracker {
+            pending: BTreeMap::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    fn entry(&mut self, channel: u8) -> &mut ChannelUsage {
+        self.pending.entry(channel).or_insert(ChannelUsage {
+            channel,
+            ..Default::default()
+        })
+    }
+
+    /// A frame taking `airtime_us` heard on `channel`
+    pub fn record_frame(&mut self, channel: u8, airtime_us: u64) {
+        let usage = self.entry(channel);
+        usage.frames += 1;
+        usage.airtime_us += airtime_us;
+    }
+
+    /// Usage accumulated since the last call, with the listening time from
+    /// `dwell` when a hopper keeps one. Channels the radio listened on
+    /// without hearing anything are included.
+    pub fn take(&mut self, dwell: Option<&DwellClock>) -> Vec<ChannelUsage> {
+        if let Some(dwell) = dwell {
+            for (channel, dwell_us) in dwell.take() {
+                self.entry(channel).dwell_us += dwell_us;
+            }
+        }
+        self.last_flush = Instant::now();
+        std::mem::take(&mut self.pending).into_values().collect()
+    }
+
+    /// Like `take`, but only once `FLUSH_INTERVAL` has passed since the last
+    pub fn take_due(&mut self, dwell: Option<&DwellClock>) -> Option<Vec<ChannelUsage>> {
+        (self.last_flush.elapsed() >= FLUSH_INTERVAL).then(|| self.take(dwell))
+    }
+}
+
+impl Default for UtilizationTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_airtime() {
+        // 100 bytes at 1 Mbit/s: 800 us plus the long preamble
+        assert_eq!(frame_airtime_us(100, Some(2)), 992);
+        // 300 bytes at 24 Mbit/s
+        assert_eq!(frame_airtime_us(300, Some(48)), 120);
+        // Unknown rate falls back to 6 Mbit/s
+        assert_eq!(frame_airtime_us(300, None), 420);
+    }
+
+    #[test]
+    fn test_tracker_merges_dwell() {
+        let dwell = DwellClock::new();
+        let mut tracker = UtilizationTracker::new();
+        dwell.add(1, Duration::from_millis(100));
+        tracker.record_frame(1, 25_000);
+        // A dead channel still accrues listening time
+        dwell.add(13, Duration::from_millis(200));
+
+        let usage = tracker.take(Some(&dwell));
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].channel, 1);
+        assert_eq!(usage[0].utilization(), Some(0.25));
+        assert_eq!(usage[1].channel, 13);
+        assert_eq!(usage[1].frames, 0);
+        assert_eq!(usage[1].utilization(), Some(0.0));
+        assert!(tracker.take(Some(&dwell)).is_empty());
+        assert!(tracker.take_due(Some(&dwell)).is_none());
+
+        // Without a hopper there is nothing to divide by
+        tracker.record_frame(6, 1_000);
+        assert_eq!(tracker.take(None)[0].utilization(), None);
+    }
+}
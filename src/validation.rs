@@ -7,11 +7,14 @@ use std::time::Duration;
 
 use crate::channels::{find_monitor_interface, is_monitor_mode, set_monitor_mode};
 use crate::config::{Config, GpsConfig};
+use crate::privileges::check_capture_privileges;
 
 /// Result of startup validation
 pub struct ValidationResult {
     /// The resolved interface name to use (may differ from config if auto-detected)
     pub interface: String,
+    /// The resolved second interface, when the bands are split
+    pub second_interface: Option<String>,
     /// Whether GPS is available (None if disabled, Some(true) if working, Some(false) if failed)
     pub gps_available: Option<bool>,
     /// Error message if GPS failed to initialize
@@ -30,6 +33,10 @@ pub enum ValidationError {
         port: u16,
         message: String,
     },
+    MissingPrivileges {
+        missing: Vec<&'static str>,
+        message: String,
+    },
 }
 
 impl std::fmt::Display for ValidationError {
@@ -41,6 +48,9 @@ impl std::fmt::Display for ValidationError {
             ValidationError::GpsUnavailable { message, .. } => {
                 write!(f, "{}", message)
             }
+            ValidationError::MissingPrivileges { message, .. } => {
+                write!(f, "{}", message)
+            }
         }
     }
 }
@@ -136,8 +146,9 @@ pub fn resolve_monitor_interface(
 ) -> Result<String, ValidationError> {
     if set_monitor {
         // User explicitly requested to set monitor mode
+        // airmon-ng, the fallback, may hand back a renamed interface
         match set_monitor_mode(configured_interface) {
-            Ok(()) => return Ok(configured_interface.to_string()),
+            Ok(interface) => return Ok(interface),
             Err(e) => {
                 return Err(ValidationError::NoMonitorInterface {
                     configured_interface: configured_interface.to_string(),
@@ -147,8 +158,9 @@ pub fn resolve_monitor_interface(
                         Troubleshooting:\n\
                         1. Ensure you're running with sudo/root privileges\n\
                         2. Check that interface '{}' exists: ip link show\n\
-                        3. Verify the interface supports monitor mode: iw list | grep -A 5 'Supported interface modes'",
-                        configured_interface, e, configured_interface
+                        3. Verify the interface supports monitor mode: iw list | grep -A 5 'Supported interface modes'\n\
+                        4. Try airmon-ng directly: sudo airmon-ng start {}",
+                        configured_interface, e, configured_interface, configured_interface
                     ),
                 });
             }
@@ -166,6 +178,13 @@ pub fn resolve_monitor_interface(
         }
     }
 
+    // airmon-ng renames the interface it puts in monitor mode
+    let renamed = format!("{}mon", configured_interface);
+    if is_monitor_mode(&renamed).unwrap_or(false) {
+        info!("Using {}, the monitor interface airmon-ng made from {}", renamed, configured_interface);
+        return Ok(renamed);
+    }
+
     // Try to auto-detect a monitor interface
     match find_monitor_interface() {
         Ok(Some(iface)) => {
@@ -196,13 +215,48 @@ pub fn resolve_monitor_interface(
     }
 }
 
+/// Resolve the second interface of a band split. Unlike the first it is
+/// never auto-detected, so it can't end up as the same adapter.
+pub fn resolve_second_interface(
+    configured_interface: &str,
+    first: &str,
+    set_monitor: bool,
+) -> Result<String, ValidationError> {
+    let error = |detail: String| ValidationError::NoMonitorInterface {
+        configured_interface: configured_interface.to_string(),
+        message: format!(
+            "Second interface '{}' can't be used to split the bands.\n\n{}\n\n\
+            Run with --set-monitor to enable monitor mode on both interfaces,\n\
+            or remove capture.second_interface to capture on one.",
+            configured_interface, detail
+        ),
+    };
+
+    let interface = if set_monitor {
+        set_monitor_mode(configured_interface).map_err(|e| error(format!("Error: {}", e)))?
+    } else {
+        let renamed = format!("{}mon", configured_interface);
+        if is_monitor_mode(configured_interface).unwrap_or(false) {
+            configured_interface.to_string()
+        } else if is_monitor_mode(&renamed).unwrap_or(false) {
+            renamed
+        } else {
+            return Err(error("It is not in monitor mode.".to_string()));
+        }
+    };
+    if interface == first {
+        return Err(error(format!("It is the same adapter as the first interface ({}).", first)));
+    }
+    Ok(interface)
+}
+
 /// Perform all startup validations for capture/tui modes
 ///
 /// This function should be called early in startup, before database init.
 ///
 /// Returns:
 /// - Ok(ValidationResult) with resolved interface and GPS status
-/// - Err only if WLAN validation fails (GPS errors are non-fatal)
+/// - Err if capture privileges or WLAN validation fail (GPS errors are non-fatal)
 pub fn validate_startup(
     config: &Config,
     set_monitor: bool,
@@ -220,11 +274,25 @@ pub fn validate_startup(
         (None, None)
     };
 
-    // 2. Validate WLAN monitor mode (fatal if fails)
-    let interface = resolve_monitor_interface(&config.capture.interface, set_monitor)?;
+    // 2. Capture privileges (fatal), before anything tries to use them
+    check_capture_privileges(&config.capture)?;
+
+    // 3. Validate WLAN monitor mode (fatal if fails); simulated and remote capture need none
+    let interface = if !config.capture.backend.has_radio() {
+        config.capture.interface.clone()
+    } else {
+        resolve_monitor_interface(&config.capture.interface, set_monitor)?
+    };
+    let second_interface = match &config.capture.second_interface {
+        Some(second) if config.capture.backend.has_radio() => {
+            Some(resolve_second_interface(second, &interface, set_monitor)?)
+        }
+        other => other.clone(),
+    };
 
     Ok(ValidationResult {
         interface,
+        second_interface,
         gps_available,
         gps_error,
     })
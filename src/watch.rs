@@ -0,0 +1,296 @@
+//! Following one device live (`prowl watch`).
+//!
+//! The target is a MAC address or a fingerprint; following a fingerprint
+//! keeps track of a phone across MAC rotations. `--group` follows a stored
+//! device's fingerprint with the `prowl link` verdicts on it applied: MACs
+//! rejected as other devices are left out even when they share the
+//! fingerprint, and MACs confirmed as this one are followed even when they
+//! don't. Every matching probe feeds a smoothed RSSI and distance estimate.
+//! A device heard again after a stretch of silence counts as an arrival,
+//! which the CLI announces with a beep.
+
+use crate::config::DistanceConfig;
+use crate::distance::{estimate_distance_smart, RssiTracker};
+use crate::fingerprint;
+use crate::parser::ParsedProbeRequest;
+use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+
+/// Silence after which the next sighting counts as an arrival
+pub const DEFAULT_ABSENCE_SECS: i64 = 120;
+
+/// Smoothed distance changes smaller than this read as steady
+const TREND_TOLERANCE_M: f64 = 0.5;
+
+/// What to follow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchTarget {
+    Mac(String),
+    Fingerprint(String),
+    /// A stored device and the MACs linked to it
+    Group {
+        mac: String,
+        /// Its fingerprint from the current algorithm, if one is stored
+        fingerprint: Option<String>,
+        confirmed: BTreeSet<String>,
+        rejected: BTreeSet<String>,
+    },
+}
+
+impl WatchTarget {
+    /// A MAC address (`:`, `-` or `.` separated) or a 16-digit fingerprint
+    pub fn parse(text: &str) -> Result<Self> {
+        let normalized = text.trim().to_uppercase().replace(['-', '.'], ":");
+        let octets: Vec<&str> = normalized.split(':').collect();
+        if octets.len() == 6 && octets.iter().all(|o| o.len() == 2 && u8::from_str_radix(o, 16).is_ok()) {
+            return Ok(WatchTarget::Mac(normalized));
+        }
+        let hex = text.trim().to_lowercase();
+        if hex.len() == 16 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(WatchTarget::Fingerprint(hex));
+        }
+        bail!("'{}' is neither a MAC address nor a fingerprint", text)
+    }
+
+    fn matches(&self, probe: &ParsedProbeRequest) -> bool {
+        match self {
+            WatchTarget::Mac(mac) => probe.source_mac == *mac,
+            WatchTarget::Fingerprint(fp) => fingerprint::current().fingerprint(&probe.capabilities) == *fp,
+            WatchTarget::Group {
+                mac,
+                fingerprint: fp,
+                confirmed,
+                rejected,
+            } => {
+                let mac_matches = probe.source_mac == *mac || confirmed.contains(&probe.source_mac);
+                mac_matches
+                    || (!rejected.contains(&probe.source_mac)
+                        && fp.as_ref().is_some_and(|fp| {
+                            fingerprint::current().fingerprint(&probe.capabilities) == *fp
+                        }))
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            WatchTarget::Mac(mac) => mac.clone(),
+            WatchTarget::Fingerprint(fp) => format!("fingerprint {}", fp),
+            WatchTarget::Group { mac, .. } => format!("{} and its linked MACs", mac),
+        }
+    }
+}
+
+/// Direction of the smoothed distance since the previous sighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Closer,
+    Farther,
+    Steady,
+}
+
+impl Trend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trend::Closer => "closer",
+            Trend::Farther => "farther",
+            Trend::Steady => "steady",
+        }
+    }
+}
+
+/// One probe from the target
+#[derive(Debug, Clone)]
+pub struct Sighting {
+    pub mac: String,
+    pub ssid: String,
+    pub signal_dbm: Option<i32>,
+    pub smoothed_dbm: Option<i32>,
+    pub distance_m: Option<f64>,
+    pub trend: Option<Trend>,
+    /// First sighting, or the first after a stretch of silence
+    pub arrived: bool,
+    /// Seconds since the previous sighting
+    pub gap_secs: Option<i64>,
+}
+
+/// Follows one target through a stream of probes
+pub struct Watcher {
+    target: WatchTarget,
+    absence_secs: i64,
+    path_loss_exponent: f64,
+    calibrated_tx_power: Option<f64>,
+    tracker: RssiTracker,
+    last_seen: Option<i64>,
+    last_distance: Option<f64>,
+}
+
+impl Watcher {
+    pub fn new(target: WatchTarget, distance: &DistanceConfig) -> Self {
+        Watcher {
+            target,
+            absence_secs: DEFAULT_ABSENCE_SECS,
+            path_loss_exponent: distance.path_loss_exponent,
+            calibrated_tx_power: distance.calibrated_tx_power,
+            tracker: RssiTracker::new(distance.rssi_average_samples.max(1)),
+            last_seen: None,
+            last_distance: None,
+        }
+    }
+
+    pub fn with_absence_secs(mut self, secs: i64) -> Self {
+        self.absence_secs = secs;
+        self
+    }
+
+    pub fn target(&self) -> &WatchTarget {
+        &self.target
+    }
+
+    /// Returns a sighting if `probe` came from the target
+    pub fn observe(&mut self, probe: &ParsedProbeRequest, timestamp: i64) -> Option<Sighting> {
+        if !self.target.matches(probe) {
+            return None;
+        }
+
+        let gap_secs = self.last_seen.map(|last| timestamp - last);
+        let arrived = gap_secs.map(|gap| gap >= self.absence_secs).unwrap_or(true);
+        if arrived {
+            // Signal from before the absence says nothing about where it is now
+            self.tracker.clear();
+            self.last_distance = None;
+        }
+        self.last_seen = Some(timestamp);
+
+        if let Some(signal) = probe.signal_dbm {
+            self.tracker.add_sample(signal);
+        }
+        let smoothed_dbm = self.tracker.weighted_average();
+        let distance_m = smoothed_dbm.and_then(|rssi| {
+            estimate_distance_smart(
+                rssi,
+                Some(&probe.capabilities.wifi_generation),
+                self.path_loss_exponent,
+                self.tracker.sample_count(),
+                self.calibrated_tx_power,
+            )
+            .map(|estimate| estimate.center)
+        });
+
+        let trend = match (self.last_distance, distance_m) {
+            (Some(before), Some(now)) if now < before - TREND_TOLERANCE_M => Some(Trend::Closer),
+            (Some(before), Some(now)) if now > before + TREND_TOLERANCE_M => Some(Trend::Farther),
+            (Some(_), Some(_)) => Some(Trend::Steady),
+            _ => None,
+        };
+        if distance_m.is_some() {
+            self.last_distance = distance_m;
+        }
+
+        Some(Sighting {
+            mac: probe.source_mac.clone(),
+            ssid: probe.ssid.clone(),
+            signal_dbm: probe.signal_dbm,
+            smoothed_dbm,
+            distance_m,
+            trend,
+            arrived,
+            gap_secs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::parser::ProbeCapabilities;
+
+    fn probe(mac: &str, signal: i32) -> ParsedProbeRequest {
+        ParsedProbeRequest {
+            source_mac: mac.to_string(),
+            ssid: "Home".to_string(),
+            signal_dbm: Some(signal),
+            bssid: None,
+            sequence_number: 0,
+            fragment_number: 0,
+            retry: false,
+            capabilities: ProbeCapabilities {
+                supported_rates_mbps: vec![1.0, 2.0, 5.5, 11.0],
+                raw_ie_ids: vec![0, 1, 50],
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            WatchTarget::parse("aa-bb-cc-dd-ee-01").unwrap(),
+            WatchTarget::Mac("AA:BB:CC:DD:EE:01".to_string())
+        );
+        assert_eq!(
+            WatchTarget::parse("00FF00FF00FF00FF").unwrap(),
+            WatchTarget::Fingerprint("00ff00ff00ff00ff".to_string())
+        );
+        assert!(WatchTarget::parse("AA:BB:CC").is_err());
+        assert!(WatchTarget::parse("not-a-device").is_err());
+    }
+
+    #[test]
+    fn test_arrival_after_absence_and_trend() {
+        let config = Config::default_config();
+        let target = WatchTarget::parse("AA:BB:CC:DD:EE:01").unwrap();
+        let mut watcher = Watcher::new(target, &config.distance).with_absence_secs(60);
+
+        assert!(watcher.observe(&probe("AA:BB:CC:DD:EE:02", -40), 0).is_none());
+
+        let first = watcher.observe(&probe("AA:BB:CC:DD:EE:01", -80), 0).unwrap();
+        assert!(first.arrived);
+        assert_eq!(first.trend, None);
+
+        let closer = watcher.observe(&probe("AA:BB:CC:DD:EE:01", -40), 10).unwrap();
+        assert!(!closer.arrived);
+        assert_eq!(closer.gap_secs, Some(10));
+        assert_eq!(closer.trend, Some(Trend::Closer));
+
+        let back = watcher.observe(&probe("AA:BB:CC:DD:EE:01", -40), 100).unwrap();
+        assert!(back.arrived);
+        assert_eq!(back.smoothed_dbm, Some(-40));
+    }
+
+    #[test]
+    fn test_fingerprint_follows_rotated_macs() {
+        let config = Config::default_config();
+        let fp = fingerprint::current().fingerprint(&probe("02:00:00:00:00:01", -60).capabilities);
+        let mut watcher = Watcher::new(WatchTarget::Fingerprint(fp), &config.distance);
+
+        assert!(watcher.observe(&probe("02:00:00:00:00:01", -60), 0).is_some());
+        let rotated = watcher.observe(&probe("06:00:00:00:00:02", -60), 5).unwrap();
+        assert_eq!(rotated.mac, "06:00:00:00:00:02");
+        assert!(!rotated.arrived);
+    }
+
+    #[test]
+    fn test_group_applies_link_verdicts() {
+        let config = Config::default_config();
+        let fp = fingerprint::current().fingerprint(&probe("02:00:00:00:00:01", -60).capabilities);
+        let target = WatchTarget::Group {
+            mac: "02:00:00:00:00:01".to_string(),
+            fingerprint: Some(fp),
+            confirmed: BTreeSet::from(["0A:00:00:00:00:03".to_string()]),
+            rejected: BTreeSet::from(["06:00:00:00:00:02".to_string()]),
+        };
+        let mut watcher = Watcher::new(target, &config.distance);
+
+        assert!(watcher.observe(&probe("02:00:00:00:00:01", -60), 0).is_some());
+        // Same fingerprint, but the user said it's another phone
+        assert!(watcher.observe(&probe("06:00:00:00:00:02", -60), 5).is_none());
+        // A rotation not seen before
+        assert!(watcher.observe(&probe("0E:00:00:00:00:04", -60), 10).is_some());
+        // Confirmed despite a different fingerprint
+        let mut confirmed = probe("0A:00:00:00:00:03", -60);
+        confirmed.capabilities.raw_ie_ids = vec![0, 1, 45, 127];
+        assert!(watcher.observe(&confirmed, 15).is_some());
+    }
+}
@@ -0,0 +1,169 @@
+//! End-to-end pipeline tests: scripted capture -> database -> analysis -> report.
+//! No hardware or root needed.
+
+use prowl::analysis::SurveillanceAnalyzer;
+use prowl::capture::CaptureEngine;
+use prowl::config::Config;
+use prowl::database::Database;
+use prowl::export::build_device_dossier;
+use prowl::ignore::IgnoreLists;
+use prowl::report::ReportGenerator;
+use prowl::source::{ScriptedProbe, ScriptedSource};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+fn temp_path(name: &str, ext: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("prowl-{}-{}.{}", name, std::process::id(), ext))
+}
+
+fn probe(mac: &str, ssid: &str, signal_dbm: i8) -> ScriptedProbe {
+    ScriptedProbe {
+        mac: mac.to_string(),
+        ssid: ssid.to_string(),
+        signal_dbm: Some(signal_dbm),
+        bssid: None,
+        delay_ms: 0,
+    }
+}
+
+async fn run_scripted(config: Config, ignore_lists: IgnoreLists, probes: &[ScriptedProbe]) {
+    let db = Database::open(&config.capture.database).unwrap();
+    let source = ScriptedSource::new(probes).unwrap();
+    let running = Arc::new(AtomicBool::new(true));
+    CaptureEngine::new(config, db, ignore_lists, running)
+        .run_with_source(Box::new(source))
+        .await
+        .unwrap();
+}
+
+fn test_config(name: &str) -> (Config, PathBuf) {
+    let db_path = temp_path(name, "db");
+    let _ = std::fs::remove_file(&db_path);
+
+    let mut config = Config::default();
+    config.capture.database = db_path.to_string_lossy().to_string();
+    config.gps.enabled = false;
+    (config, db_path)
+}
+
+#[tokio::test]
+async fn test_scripted_capture_to_report() {
+    let (config, db_path) = test_config("pipeline");
+    let analysis = config.analysis.clone();
+
+    let probes = vec![
+        probe("00:03:93:00:00:01", "HomeNet", -45),
+        probe("00:03:93:00:00:01", "CoffeeShop", -47),
+        probe("00:03:93:00:00:01", "", -46),
+        probe("02:11:22:33:44:55", "", -80),
+        probe("00:1A:11:00:00:02", "Office", -62),
+    ];
+    run_scripted(config, IgnoreLists::new(), &probes).await;
+
+    let db = Database::open(&db_path).unwrap();
+    assert_eq!(db.count_devices().unwrap(), 3);
+    assert_eq!(db.count_probes().unwrap(), 5);
+
+    let device = db.get_device_by_mac("00:03:93:00:00:01").unwrap().unwrap();
+    let mut ssids = db.get_unique_ssids_for_device(device.id).unwrap();
+    ssids.sort();
+    assert_eq!(ssids, vec!["CoffeeShop".to_string(), "HomeNet".to_string()]);
+
+    let analyzer = SurveillanceAnalyzer::new(analysis.time_windows_minutes, analysis.persistence_threshold);
+    let alerts = analyzer.analyze(&db, 1).unwrap();
+
+    let report_path = temp_path("pipeline-report", "txt");
+    ReportGenerator::generate_surveillance_report(&alerts, Some(&report_path)).unwrap();
+    let report = std::fs::read_to_string(&report_path).unwrap();
+    assert!(report.contains("PROWL SURVEILLANCE ANALYSIS REPORT"));
+
+    let dossier = build_device_dossier(&db, "00:03:93:00:00:01", &analyzer)
+        .unwrap()
+        .unwrap();
+    assert_eq!(dossier.probes.len(), 3);
+
+    let _ = std::fs::remove_file(&report_path);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+#[tokio::test]
+async fn test_scripted_capture_respects_ignore_lists() {
+    let (config, db_path) = test_config("ignore");
+
+    let mut ignore_lists = IgnoreLists::new();
+    ignore_lists.add_mac("00:03:93:00:00:01");
+    ignore_lists.add_ssid("IgnoredNet");
+
+    let probes = vec![
+        probe("00:03:93:00:00:01", "HomeNet", -45),
+        probe("00:1A:11:00:00:02", "IgnoredNet", -60),
+        probe("00:1A:11:00:00:02", "Office", -60),
+    ];
+    run_scripted(config, ignore_lists, &probes).await;
+
+    let db = Database::open(&db_path).unwrap();
+    assert_eq!(db.count_devices().unwrap(), 1);
+    assert_eq!(db.count_probes().unwrap(), 1);
+
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// Classic little-endian pcap with radiotap link type
+#[cfg(feature = "pcap")]
+fn write_pcap(path: &PathBuf, frames: &[(u32, u32, Vec<u8>)]) {
+    let mut out = Vec::new();
+    out.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&65535u32.to_le_bytes());
+    out.extend_from_slice(&127u32.to_le_bytes());
+    for (secs, micros, frame) in frames {
+        out.extend_from_slice(&secs.to_le_bytes());
+        out.extend_from_slice(&micros.to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        out.extend_from_slice(frame);
+    }
+    std::fs::write(path, out).unwrap();
+}
+
+#[cfg(feature = "pcap")]
+#[tokio::test]
+async fn test_pcap_file_replay_keeps_timestamps() {
+    use prowl::source::build_probe_request;
+
+    let (config, db_path) = test_config("pcap-replay");
+    let pcap_path = temp_path("pcap-replay", "pcap");
+    let mac = [0x00, 0x03, 0x93, 0x00, 0x00, 0x01];
+    write_pcap(
+        &pcap_path,
+        &[
+            (1_700_000_000, 250_000, build_probe_request(mac, "HomeNet", Some(-50))),
+            (1_700_000_600, 17, build_probe_request(mac, "", Some(-52))),
+        ],
+    );
+
+    let db = Database::open(&config.capture.database).unwrap();
+    let running = Arc::new(AtomicBool::new(true));
+    CaptureEngine::new(config, db, IgnoreLists::new(), running)
+        .run_from_file(&pcap_path)
+        .await
+        .unwrap();
+
+    let db = Database::open(&db_path).unwrap();
+    let device = db.get_device_by_mac("00:03:93:00:00:01").unwrap().unwrap();
+    assert_eq!((device.first_seen, device.last_seen), (1_700_000_000, 1_700_000_600));
+    assert_eq!(db.count_probes().unwrap(), 2);
+    let micros: Vec<Option<u32>> = db
+        .get_probes_for_device(device.id)
+        .unwrap()
+        .iter()
+        .map(|p| p.timestamp_micros)
+        .collect();
+    assert_eq!(micros, vec![Some(17), Some(250_000)]);
+
+    let _ = std::fs::remove_file(&pcap_path);
+    let _ = std::fs::remove_file(&db_path);
+}